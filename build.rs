@@ -1,4 +1,8 @@
-use std::{path::Path, process::Command};
+use std::{
+    hash::{DefaultHasher, Hasher},
+    path::Path,
+    process::Command,
+};
 
 const WEB_CLIENT_DIR: &str = "./web_client";
 const STATIC_GAME_DIR: &str = "./static/game";
@@ -19,6 +23,9 @@ fn build_client() {
         //build_wasm.arg("--debug");
         build_wasm.arg("--target").arg("web");
         build_wasm.current_dir(web_client_dir);
+        if let Ok(features) = std::env::var("CLIENT_FEATURES") {
+            build_wasm.arg("--").arg("--features").arg(features);
+        }
         let status = build_wasm.status().unwrap();
 
         assert!(status.success(), "Failed to build web client");
@@ -30,8 +37,59 @@ fn build_client() {
     std::fs::create_dir_all(&server_asset_dir).unwrap();
     copy_dir(&web_client_dir.join("pkg"), &server_asset_dir);
     copy_dir(&web_client_dir.join("static"), &server_asset_dir);
+    fingerprint_client(&server_asset_dir);
 
     println!("cargo:rerun-if-changed={}", WEB_CLIENT_DIR);
+    println!("cargo:rerun-if-env-changed=CLIENT_FEATURES");
+}
+
+/// put a hash of their contents into the names of the client files so browsers can cache them
+/// forever, and point the files referencing them to the new names. the server serves hashed
+/// files as immutable and everything else (i.e. index.html) as no-cache.
+fn fingerprint_client(dir: &Path) {
+    // the js file loads the wasm file, so it has to be renamed first
+    let wasm_name = fingerprint(dir, "web_client_bg", "wasm", &[]);
+    let js_name = fingerprint(
+        dir,
+        "web_client",
+        "js",
+        &[("web_client_bg.wasm", &wasm_name)],
+    );
+    replace_in_file(
+        &dir.join("index.html"),
+        &[("./web_client.js", &format!("./{}", js_name))],
+    );
+}
+
+/// rename `<stem>.<extension>` to `<stem>.<hash>.<extension>` after applying the replacements
+fn fingerprint(dir: &Path, stem: &str, extension: &str, replacements: &[(&str, &str)]) -> String {
+    let path = dir.join(format!("{}.{}", stem, extension));
+    replace_in_file(&path, replacements);
+
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&std::fs::read(&path).unwrap());
+    let name = format!("{}.{:016x}.{}", stem, hasher.finish(), extension);
+
+    std::fs::rename(&path, dir.join(&name)).unwrap();
+    name
+}
+
+fn replace_in_file(path: &Path, replacements: &[(&str, &str)]) {
+    if replacements.is_empty() {
+        return;
+    }
+
+    let mut contents = std::fs::read_to_string(path).unwrap();
+    for (from, to) in replacements {
+        assert!(
+            contents.contains(from),
+            "{} doesn't reference {}",
+            path.display(),
+            from
+        );
+        contents = contents.replace(from, to);
+    }
+    std::fs::write(path, contents).unwrap();
 }
 
 fn copy_dir(src: &Path, dest: &Path) {
@@ -0,0 +1,120 @@
+//! extra art the server hands out at runtime, e.g. seasonal skins or event tiles, so it can be
+//! shipped without a new client build. a pack is a list of image files named like the built-in
+//! ones in `assets/`, loading it replaces those.
+
+use crate::{Assets, CiboError};
+use alloc::{string::String, vec::Vec};
+use monos_gfx::{image::SliceReader, Image};
+use serde::{Deserialize, Serialize};
+
+/// where a client can download a pack. the path changes whenever the pack does, so it can be
+/// cached forever
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetPackInfo {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetPack {
+    pub name: String,
+    /// file name and contents, `.ppm` for images and `.pbm` for masks
+    pub files: Vec<(String, Vec<u8>)>,
+}
+
+impl AssetPack {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CiboError> {
+        Ok(postcard::from_bytes(bytes)?)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
+        postcard::to_allocvec(self)
+    }
+
+    /// make sure every file replaces a known asset with an image of the same size, without
+    /// loading anything
+    pub fn check(&self) -> Result<(), CiboError> {
+        self.decode(&mut Assets::new()).map(|_| ())
+    }
+
+    /// the decoded images with the file they replace
+    fn decode(&self, assets: &mut Assets) -> Result<Vec<(&str, Image)>, CiboError> {
+        let problem = |file: &str, problem| CiboError::AssetPack {
+            file: file.into(),
+            problem,
+        };
+
+        let known = assets.images_mut();
+        let mut decoded = Vec::with_capacity(self.files.len());
+        for (file, bytes) in &self.files {
+            let image = if file.ends_with(".pbm") {
+                Image::from_pbm(&SliceReader::new(bytes))
+            } else {
+                Image::from_ppm(&SliceReader::new(bytes))
+            }
+            .map_err(|_| problem(file, "is not a readable image"))?;
+
+            let (_, target) = known
+                .iter()
+                .find(|(name, _)| *name == file.as_str())
+                .ok_or_else(|| problem(file, "doesn't replace a known asset"))?;
+            let (old, new) = (target.dimensions(), image.dimensions());
+            if old.width != new.width || old.height != new.height {
+                return Err(problem(
+                    file,
+                    "has a different size than the asset it replaces",
+                ));
+            }
+            decoded.push((file.as_str(), image));
+        }
+        Ok(decoded)
+    }
+}
+
+impl Assets {
+    /// put the images of a pack in place of the built-in ones, returns how many were replaced.
+    /// nothing is replaced if any of the files is broken
+    pub(crate) fn load_pack(&mut self, pack: &AssetPack) -> Result<usize, CiboError> {
+        let decoded = pack.decode(self)?;
+        let count = decoded.len();
+        let mut known = self.images_mut();
+        for (file, image) in decoded {
+            if let Some((_, target)) = known.iter_mut().find(|(name, _)| *name == file) {
+                **target = image;
+            }
+        }
+        self.cibo.sync_back_blink();
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn pack(file: &str, bytes: &[u8]) -> AssetPack {
+        AssetPack {
+            name: "test".into(),
+            files: vec![(file.into(), bytes.to_vec())],
+        }
+    }
+
+    #[test]
+    fn replaces_known_assets() {
+        let walk = include_bytes!("../../assets/cibo_front_walk1.ppm");
+        let mut assets = Assets::new();
+        let pack = pack("cibo_front_stand.ppm", walk);
+        let pack = AssetPack::from_bytes(&pack.to_bytes().unwrap()).unwrap();
+        assert_eq!(assets.load_pack(&pack).unwrap(), 1);
+    }
+
+    #[test]
+    fn rejects_unknown_and_mismatched_files() {
+        let hat = include_bytes!("../../assets/santa_hat.ppm");
+        assert!(pack("not_an_asset.ppm", hat).check().is_err());
+        assert!(pack("cibo_front_stand.ppm", hat).check().is_err());
+        assert!(pack("santa_hat.ppm", b"garbage").check().is_err());
+        assert!(pack("santa_hat.ppm", hat).check().is_ok());
+    }
+}
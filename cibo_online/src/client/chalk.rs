@@ -0,0 +1,60 @@
+use super::ClientMessage;
+use crate::world::{ChalkColor, ChalkStroke, CHALK_REACH, MAX_STROKE_POINTS};
+
+use alloc::vec::Vec;
+use monos_gfx::{input::Input, Position};
+
+/// chalk picked up with `/chalk`. lines are drawn onto the ground around the player while the mouse is held
+#[derive(Debug, Clone)]
+pub struct ChalkTool {
+    pub color: ChalkColor,
+    stroke: Vec<Position>,
+}
+
+impl ChalkTool {
+    pub fn new(color: ChalkColor) -> Self {
+        ChalkTool {
+            color,
+            stroke: Vec::new(),
+        }
+    }
+
+    /// follow the mouse while it is held and send the stroke once it is let go or full
+    pub(crate) fn update(
+        &mut self,
+        input: &Input,
+        camera: Position,
+        feet: Position,
+        send_msg: &mut dyn FnMut(ClientMessage),
+    ) {
+        let mouse = input.mouse.position + camera;
+        let in_reach =
+            (mouse.x - feet.x).abs() <= CHALK_REACH && (mouse.y - feet.y).abs() <= CHALK_REACH;
+        let drawing = input.mouse.left_button.pressed && in_reach;
+
+        if drawing {
+            if self.stroke.last() != Some(&mouse) {
+                self.stroke.push(mouse);
+            }
+            // points far apart are connected with a second step, so the stroke always stays under the limit
+            if self.stroke.len() < MAX_STROKE_POINTS / 2 {
+                return;
+            }
+        }
+
+        if let Some(stroke) = ChalkStroke::new(self.color, &self.stroke) {
+            send_msg(ClientMessage::DrawChalk(stroke));
+        }
+        // a full stroke is continued where it ended
+        let last = self.stroke.last().copied().filter(|_| drawing);
+        self.stroke.clear();
+        self.stroke.extend(last);
+    }
+
+    /// the stroke that is being drawn right now
+    pub(crate) fn preview(&self) -> Option<ChalkStroke> {
+        let mut stroke = ChalkStroke::new(self.color, &self.stroke)?;
+        stroke.expires_at = u64::MAX;
+        Some(stroke)
+    }
+}
@@ -0,0 +1,340 @@
+//! every key and chat command the player can use. input handling looks them up here and the help
+//! window lists them from here, so the two can't disagree.
+
+use alloc::{format, string::String, vec::Vec};
+use monos_gfx::input::{Key, RawKey};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Chat,
+    PlayerList,
+    Help,
+    StreamMode,
+    Editor,
+    Stats,
+    HudEditor,
+    MentionSound,
+    ColorMode,
+    EventMode,
+    ReducedMotion,
+    LabelLayout,
+    DebugOverlay,
+}
+
+pub struct KeyBinding {
+    pub action: Action,
+    pub keys: &'static [Key],
+    pub description: &'static str,
+}
+
+pub const KEYBINDINGS: &[KeyBinding] = &[
+    KeyBinding {
+        action: Action::MoveUp,
+        keys: &[Key::Unicode('w'), Key::RawKey(RawKey::ArrowUp)],
+        description: "walk up",
+    },
+    KeyBinding {
+        action: Action::MoveLeft,
+        keys: &[Key::Unicode('a'), Key::RawKey(RawKey::ArrowLeft)],
+        description: "walk left",
+    },
+    KeyBinding {
+        action: Action::MoveDown,
+        keys: &[Key::Unicode('s'), Key::RawKey(RawKey::ArrowDown)],
+        description: "walk down",
+    },
+    KeyBinding {
+        action: Action::MoveRight,
+        keys: &[Key::Unicode('d'), Key::RawKey(RawKey::ArrowRight)],
+        description: "walk right",
+    },
+    KeyBinding {
+        action: Action::Chat,
+        keys: &[Key::RawKey(RawKey::Return), Key::Unicode('t')],
+        description: "chat",
+    },
+    KeyBinding {
+        action: Action::PlayerList,
+        keys: &[Key::RawKey(RawKey::Tab)],
+        description: "hold to view the player list",
+    },
+    KeyBinding {
+        action: Action::Help,
+        keys: &[Key::Unicode('h'), Key::Unicode('?')],
+        description: "show this help",
+    },
+    KeyBinding {
+        action: Action::StreamMode,
+        keys: &[Key::RawKey(RawKey::F1)],
+        description: "stream mode",
+    },
+    KeyBinding {
+        action: Action::Editor,
+        keys: &[Key::RawKey(RawKey::F2)],
+        description: "world editor, if you are allowed to use it",
+    },
+    KeyBinding {
+        action: Action::Stats,
+        keys: &[Key::RawKey(RawKey::F3)],
+        description: "network stats",
+    },
+    KeyBinding {
+        action: Action::HudEditor,
+        keys: &[Key::RawKey(RawKey::F4)],
+        description: "arrange the hud",
+    },
+    KeyBinding {
+        action: Action::MentionSound,
+        keys: &[Key::RawKey(RawKey::F6)],
+        description: "mention sounds",
+    },
+    KeyBinding {
+        action: Action::ColorMode,
+        keys: &[Key::RawKey(RawKey::F7)],
+        description: "change colors",
+    },
+    KeyBinding {
+        action: Action::EventMode,
+        keys: &[Key::RawKey(RawKey::F8)],
+        description: "follow the camera during events",
+    },
+    KeyBinding {
+        action: Action::ReducedMotion,
+        keys: &[Key::RawKey(RawKey::F9)],
+        description: "reduce motion",
+    },
+    KeyBinding {
+        action: Action::LabelLayout,
+        keys: &[Key::RawKey(RawKey::F10)],
+        description: "change how crowded names are shown",
+    },
+    KeyBinding {
+        action: Action::DebugOverlay,
+        // ctrl+b, passed on as its ascii control character
+        keys: &[Key::Unicode('\u{2}')],
+        description: "show hitboxes and z order",
+    },
+];
+
+/// what pressing `key` does, if anything
+pub fn action(key: Key) -> Option<Action> {
+    KEYBINDINGS
+        .iter()
+        .find(|binding| binding.keys.contains(&key))
+        .map(|binding| binding.action)
+}
+
+pub fn keys(action: Action) -> &'static [Key] {
+    KEYBINDINGS
+        .iter()
+        .find(|binding| binding.action == action)
+        .map_or(&[], |binding| binding.keys)
+}
+
+/// the keys of an action as shown to the player, e.g. `enter / t`
+pub fn key_names(action: Action) -> String {
+    keys(action)
+        .iter()
+        .map(|key| key_name(*key))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+fn key_name(key: Key) -> String {
+    match key {
+        Key::Unicode(c) if c.is_ascii_control() => format!("ctrl+{}", (c as u8 + b'a' - 1) as char),
+        Key::Unicode(c) => format!("{}", c),
+        Key::RawKey(RawKey::Return) => String::from("enter"),
+        Key::RawKey(RawKey::Tab) => String::from("tab"),
+        Key::RawKey(RawKey::ArrowUp) => String::from("up"),
+        Key::RawKey(RawKey::ArrowDown) => String::from("down"),
+        Key::RawKey(RawKey::ArrowLeft) => String::from("left"),
+        Key::RawKey(RawKey::ArrowRight) => String::from("right"),
+        Key::RawKey(key) => format!("{:?}", key).to_lowercase(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Whisper,
+    Reply,
+    Filter,
+    Unfilter,
+    Block,
+    Unblock,
+    Bubbles,
+    Register,
+    TeleportRequest,
+    TeleportAccept,
+    SetHome,
+    Home,
+    Edit,
+    Stats,
+    Chalk,
+    Clock,
+    Mail,
+    Rename,
+    Transfer,
+    Staff,
+    SafeChat,
+}
+
+pub struct ChatCommand {
+    pub command: Command,
+    /// the first one is shown in the help
+    pub names: &'static [&'static str],
+    pub arguments: &'static str,
+    pub description: &'static str,
+}
+
+pub const COMMANDS: &[ChatCommand] = &[
+    ChatCommand {
+        command: Command::Whisper,
+        names: &["w", "whisper"],
+        arguments: "<name> <message>",
+        description: "private message",
+    },
+    ChatCommand {
+        command: Command::Reply,
+        names: &["reply"],
+        arguments: "",
+        description: "whisper to whoever mentioned you last",
+    },
+    ChatCommand {
+        command: Command::Filter,
+        names: &["filter"],
+        arguments: "<word>",
+        description: "hide messages with a word",
+    },
+    ChatCommand {
+        command: Command::Unfilter,
+        names: &["unfilter"],
+        arguments: "<word>",
+        description: "show them again",
+    },
+    ChatCommand {
+        command: Command::Block,
+        names: &["block"],
+        arguments: "<name>",
+        description: "hide a player",
+    },
+    ChatCommand {
+        command: Command::Unblock,
+        names: &["unblock"],
+        arguments: "<name>",
+        description: "show them again",
+    },
+    ChatCommand {
+        command: Command::Bubbles,
+        names: &["bubbles"],
+        arguments: "on|off",
+        description: "show or hide chat bubbles",
+    },
+    ChatCommand {
+        command: Command::Register,
+        names: &["register"],
+        arguments: "",
+        description: "make an account to play without a time limit, needs a linked discord account",
+    },
+    ChatCommand {
+        command: Command::TeleportRequest,
+        names: &["tpa"],
+        arguments: "<name>",
+        description: "ask to teleport to a player",
+    },
+    ChatCommand {
+        command: Command::TeleportAccept,
+        names: &["tpaccept"],
+        arguments: "",
+        description: "let them teleport to you",
+    },
+    ChatCommand {
+        command: Command::SetHome,
+        names: &["sethome"],
+        arguments: "",
+        description: "make this spot your home",
+    },
+    ChatCommand {
+        command: Command::Home,
+        names: &["home"],
+        arguments: "",
+        description: "go back home",
+    },
+    ChatCommand {
+        command: Command::Edit,
+        names: &["edit"],
+        arguments: "<message>",
+        description: "change your last message",
+    },
+    ChatCommand {
+        command: Command::Stats,
+        names: &["stats"],
+        arguments: "",
+        description: "see what you have been up to",
+    },
+    ChatCommand {
+        command: Command::Chalk,
+        names: &["chalk"],
+        arguments: "[color]",
+        description: "draw on the ground, again to put it away",
+    },
+    ChatCommand {
+        command: Command::Clock,
+        names: &["clock"],
+        arguments: "12|24",
+        description: "show the time as 12 or 24 hour clock",
+    },
+    ChatCommand {
+        command: Command::Mail,
+        names: &["mail"],
+        arguments: "<name> <message>",
+        description: "leave a letter at a mailbox",
+    },
+    ChatCommand {
+        command: Command::Rename,
+        names: &["rename"],
+        arguments: "<name>",
+        description: "change your name, registered players only",
+    },
+    ChatCommand {
+        command: Command::Transfer,
+        names: &["transfer"],
+        arguments: "",
+        description: "continue playing on another device",
+    },
+    ChatCommand {
+        command: Command::Staff,
+        names: &["staff"],
+        arguments: "decorate|mute|minigame <what>",
+        description: "event staff: put out decorations, mute players, run minigames",
+    },
+    ChatCommand {
+        command: Command::SafeChat,
+        names: &["safechat"],
+        arguments: "on|off",
+        description: "only show and send preset phrases",
+    },
+];
+
+/// split chat input like `/w name hi` into the command and everything after its name
+pub fn parse_command(chat: &str) -> Option<(Command, &str)> {
+    let rest = chat.strip_prefix('/')?;
+    let (name, arguments) = rest.split_once(' ').unwrap_or((rest, ""));
+    let command = COMMANDS
+        .iter()
+        .find(|command| command.names.contains(&name))?;
+    Some((command.command, arguments))
+}
+
+/// how a command is typed, e.g. `/w <name> <message>`
+pub fn usage(command: &ChatCommand) -> String {
+    if command.arguments.is_empty() {
+        format!("/{}", command.names[0])
+    } else {
+        format!("/{} {}", command.names[0], command.arguments)
+    }
+}
@@ -0,0 +1,50 @@
+use super::PlayStats;
+use crate::server::SpecialEvent;
+use serde::{Deserialize, Serialize};
+
+const SUNGLASSES_KICKS: u64 = 100;
+const FLOWER_CROWN_TILES: u64 = 10_000;
+const SANTA_HAT_ONLINE_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// things drawn over the sprite of a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CosmeticId {
+    SantaHat,
+    FlowerCrown,
+    Sunglasses,
+}
+
+impl CosmeticId {
+    /// also the order they are drawn in, so hats end up on top
+    pub const ALL: [CosmeticId; 3] = [
+        CosmeticId::Sunglasses,
+        CosmeticId::FlowerCrown,
+        CosmeticId::SantaHat,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CosmeticId::SantaHat => "santa hat",
+            CosmeticId::FlowerCrown => "flower crown",
+            CosmeticId::Sunglasses => "sunglasses",
+        }
+    }
+
+    /// everyone wears the cosmetic while this event is active
+    pub fn event(&self) -> SpecialEvent {
+        match self {
+            CosmeticId::SantaHat => SpecialEvent::Holidays,
+            CosmeticId::FlowerCrown => SpecialEvent::Spring,
+            CosmeticId::Sunglasses => SpecialEvent::BeachEpisode,
+        }
+    }
+
+    /// whether a player with these stats over all sessions keeps the cosmetic outside of its event
+    pub fn achieved(&self, stats: &PlayStats) -> bool {
+        match self {
+            CosmeticId::SantaHat => stats.time_online_ms >= SANTA_HAT_ONLINE_MS,
+            CosmeticId::FlowerCrown => stats.tiles_walked >= FLOWER_CROWN_TILES,
+            CosmeticId::Sunglasses => stats.balls_kicked >= SUNGLASSES_KICKS,
+        }
+    }
+}
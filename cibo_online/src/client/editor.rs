@@ -0,0 +1,168 @@
+use super::ClientMessage;
+use crate::{world::ObjectId, Object, PlaceableObject, WorldLocalState, WorldState};
+
+use alloc::{format, string::String};
+use monos_gfx::{
+    input::{Input, Key, KeyState, RawKey},
+    text::font,
+    ui::{Direction, UIFrame},
+    Framebuffer, Position, Rect,
+};
+
+const CAMERA_SPEED: i64 = 4;
+
+/// world editor for admins. replaces player movement with a free camera and lets objects be placed, moved and deleted.
+#[derive(Debug)]
+pub(crate) struct EditorState {
+    pub(crate) active: bool,
+    pub(crate) camera: Position,
+    camera_direction: Position,
+    selected: usize,
+    /// object that is currently being dragged and where it was grabbed
+    dragging: Option<(ObjectId, Position)>,
+    left_was_pressed: bool,
+    right_was_pressed: bool,
+}
+
+impl EditorState {
+    pub(crate) fn new() -> Self {
+        EditorState {
+            active: false,
+            camera: Position::zero(),
+            camera_direction: Position::zero(),
+            selected: 0,
+            dragging: None,
+            left_was_pressed: false,
+            right_was_pressed: false,
+        }
+    }
+
+    pub(crate) fn toggle(&mut self, camera: Position) {
+        self.active = !self.active;
+        self.camera = camera;
+        self.camera_direction = Position::zero();
+        self.dragging = None;
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        world: &mut WorldState,
+        local: &mut WorldLocalState,
+        input: &Input,
+        tick_amt: u64,
+        send_msg: &mut dyn FnMut(ClientMessage),
+    ) {
+        for event in &input.keyboard {
+            let down = event.state == KeyState::Down;
+            match event.key {
+                Key::RawKey(RawKey::ArrowUp) | Key::Unicode('w') => {
+                    self.camera_direction.y = if down { -1 } else { 0 }
+                }
+                Key::RawKey(RawKey::ArrowDown) | Key::Unicode('s') => {
+                    self.camera_direction.y = if down { 1 } else { 0 }
+                }
+                Key::RawKey(RawKey::ArrowLeft) | Key::Unicode('a') => {
+                    self.camera_direction.x = if down { -1 } else { 0 }
+                }
+                Key::RawKey(RawKey::ArrowRight) | Key::Unicode('d') => {
+                    self.camera_direction.x = if down { 1 } else { 0 }
+                }
+                // 0 is the tenth object, like on the keyboard
+                Key::Unicode(c @ '0'..='9') if down => {
+                    let index = (c as usize + 9 - '0' as usize) % 10;
+                    if index < PlaceableObject::ALL.len() {
+                        self.selected = index;
+                    }
+                }
+                // there are more objects than number keys
+                Key::RawKey(RawKey::Tab) if down => {
+                    self.selected = (self.selected + 1) % PlaceableObject::ALL.len();
+                }
+                _ => {}
+            }
+        }
+
+        let speed = tick_amt as i64 * CAMERA_SPEED;
+        self.camera.x += self.camera_direction.x * speed;
+        self.camera.y += self.camera_direction.y * speed;
+
+        let mouse = input.mouse.position + self.camera;
+        let left = input.mouse.left_button.pressed;
+        let right = input.mouse.right_button.pressed;
+
+        let hovered = world
+            .network_objects
+            .iter()
+            .find(|(_, object)| object.bounds().contains(mouse))
+            .map(|(id, object)| (*id, object.properties().position));
+
+        if left && !self.left_was_pressed {
+            match hovered {
+                Some((id, position)) => self.dragging = Some((id, mouse - position)),
+                None => send_msg(ClientMessage::EditorPlace(
+                    PlaceableObject::ALL[self.selected],
+                    mouse,
+                )),
+            }
+        }
+
+        if let Some((id, grab_offset)) = self.dragging {
+            let position = mouse - grab_offset;
+            if let Some(object) = world.network_objects.get_mut(&id) {
+                object.set_position(position);
+            }
+
+            if !left {
+                send_msg(ClientMessage::EditorMove(id, position));
+                self.dragging = None;
+            }
+        }
+
+        if right && !self.right_was_pressed && self.dragging.is_none() {
+            if let Some((id, _)) = hovered {
+                send_msg(ClientMessage::EditorDelete(id));
+            }
+        }
+
+        self.left_was_pressed = left;
+        self.right_was_pressed = right;
+
+        local.ghosts.clear();
+        if let Some((id, _)) = self.dragging {
+            local.ghosts.push(id);
+        }
+
+        if hovered.is_some() || self.dragging.is_some() {
+            local.placement_preview = None;
+        } else {
+            let kind = PlaceableObject::ALL[self.selected];
+            match &mut local.placement_preview {
+                Some((preview_kind, preview)) if *preview_kind == kind => {
+                    preview.set_position(mouse)
+                }
+                preview => *preview = Some((kind, kind.create(mouse))),
+            }
+        }
+    }
+
+    pub(crate) fn draw(&self, framebuffer: &mut Framebuffer, input: &mut Input) {
+        let rect = Rect::new(Position::new(5, 5), Position::new(200, 240));
+        let mut ui = UIFrame::new_stateless(Direction::TopToBottom);
+        ui.draw_frame(framebuffer, rect, input, |ui| {
+            ui.label::<font::Cozette>("editor");
+            for (i, object) in PlaceableObject::ALL.iter().enumerate() {
+                let marker = if i == self.selected { ">" } else { " " };
+                let key = if i < 10 {
+                    format!("{}", (i + 1) % 10)
+                } else {
+                    String::from(" ")
+                };
+                ui.label::<font::Glean>(&format!("{} {} {}", marker, key, object.name()));
+            }
+            ui.label::<font::Glean>("tab for the next object");
+            ui.label::<font::Glean>("click to place, drag to move");
+            ui.label::<font::Glean>("right click to delete");
+            ui.label::<font::Glean>("f2 to leave");
+        });
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{render::phase_offset, server::EffectKind};
+use micromath::F32Ext;
+use monos_gfx::{Color, Framebuffer, Position, Rect};
+
+const CONFETTI_PIECES: u32 = 48;
+const CONFETTI_COLORS: [Color; 5] = [
+    Color::new(240, 80, 90),
+    Color::new(250, 200, 60),
+    Color::new(80, 190, 240),
+    Color::new(120, 220, 110),
+    Color::new(220, 120, 230),
+];
+/// in pixels per ms²
+const CONFETTI_GRAVITY: f32 = 0.0006;
+
+const HEARTS: u32 = 24;
+const HEART_COLORS: [Color; 2] = [Color::new(240, 90, 130), Color::new(250, 150, 180)];
+/// time a heart takes to fall from the top of the shower to the bottom
+const HEART_FALL_MS: u64 = 2400;
+const HEART_SHOWER_WIDTH: u64 = 160;
+const HEART_SHOWER_HEIGHT: i64 = 128;
+const HEART_SHAPE: [&str; 4] = [".#.#.", "#####", ".###.", "..#.."];
+
+const SPOTLIGHT_RADIUS: i64 = 56;
+/// time the spotlight takes to close in on its target and to open up again
+const SPOTLIGHT_FADE_MS: u64 = 500;
+
+/// a visual effect the server started somewhere in the world
+#[derive(Debug, Clone)]
+pub(crate) struct Effect {
+    kind: EffectKind,
+    position: Position,
+    started_at: u64,
+    ends_at: u64,
+    /// makes effects at the same spot look different from each other
+    seed: u32,
+}
+
+impl Effect {
+    pub fn new(kind: EffectKind, position: Position, time_ms: u64, duration_ms: u64) -> Self {
+        Effect {
+            kind,
+            position,
+            started_at: time_ms,
+            ends_at: time_ms + duration_ms,
+            seed: time_ms as u32 ^ (position.x as u32).wrapping_mul(31) ^ position.y as u32,
+        }
+    }
+
+    pub fn is_done(&self, time_ms: u64) -> bool {
+        time_ms >= self.ends_at
+    }
+
+    pub fn render(&self, fb: &mut Framebuffer, camera: Position, time_ms: u64) {
+        let center = self.position - camera;
+        let elapsed = time_ms.saturating_sub(self.started_at);
+        match self.kind {
+            EffectKind::Confetti => self.draw_confetti(fb, center, elapsed),
+            EffectKind::Hearts => self.draw_hearts(fb, center, elapsed, time_ms),
+            EffectKind::Spotlight => self.draw_spotlight(fb, center, elapsed, time_ms),
+        }
+    }
+
+    fn draw_confetti(&self, fb: &mut Framebuffer, center: Position, elapsed: u64) {
+        let t = elapsed as f32;
+        for i in 0..CONFETTI_PIECES {
+            let seed = self.seed.wrapping_add(i * 3);
+            let velocity_x = (phase_offset(seed, 240) as f32 - 120.0) / 1000.0;
+            let velocity_y = -(phase_offset(seed.wrapping_add(1), 200) as f32 + 150.0) / 1000.0;
+            let x = center.x + (velocity_x * t) as i64;
+            let y = center.y + (velocity_y * t + CONFETTI_GRAVITY * t * t / 2.0) as i64;
+
+            let size = 1 + (i % 2) as i64;
+            fb.draw_rect(
+                Rect::new(Position::new(x, y), Position::new(x + size, y + size)),
+                CONFETTI_COLORS[i as usize % CONFETTI_COLORS.len()],
+            );
+        }
+    }
+
+    fn draw_hearts(&self, fb: &mut Framebuffer, center: Position, elapsed: u64, time_ms: u64) {
+        // hearts that are still falling when the effect ends just disappear, so stop spawning a bit earlier
+        let spawn_until = self.ends_at.saturating_sub(HEART_FALL_MS);
+        for i in 0..HEARTS {
+            let seed = self.seed.wrapping_add(i * 7);
+            let spawn_offset = phase_offset(seed, HEART_FALL_MS);
+            let Some(since_spawn) = elapsed.checked_sub(spawn_offset) else {
+                continue;
+            };
+            let fall = since_spawn % HEART_FALL_MS;
+            let spawned_at = time_ms - fall;
+            if since_spawn >= HEART_FALL_MS && spawned_at > spawn_until {
+                continue;
+            }
+
+            let x = center.x + phase_offset(seed.wrapping_add(1), HEART_SHOWER_WIDTH) as i64
+                - HEART_SHOWER_WIDTH as i64 / 2;
+            let sway = ((fall / 200 + i as u64) % 4) as i64 - 2;
+            let y = center.y - HEART_SHOWER_HEIGHT
+                + fall as i64 * HEART_SHOWER_HEIGHT / HEART_FALL_MS as i64;
+
+            let color = HEART_COLORS[i as usize % HEART_COLORS.len()];
+            for (row, line) in HEART_SHAPE.iter().enumerate() {
+                for (column, pixel) in line.chars().enumerate() {
+                    if pixel == '#' {
+                        let pos = Position::new(x + sway + column as i64, y + row as i64);
+                        fb.draw_rect(Rect::new(pos, pos + Position::new(1, 1)), color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// dims everything but a circle around the target with every other line blacked out
+    fn draw_spotlight(&self, fb: &mut Framebuffer, center: Position, elapsed: u64, time_ms: u64) {
+        let width = fb.dimensions().width as i64;
+        let height = fb.dimensions().height as i64;
+        let full_radius = width.max(height);
+
+        let fade = elapsed
+            .min(self.ends_at.saturating_sub(time_ms))
+            .min(SPOTLIGHT_FADE_MS);
+        let radius =
+            full_radius - (full_radius - SPOTLIGHT_RADIUS) * fade as i64 / SPOTLIGHT_FADE_MS as i64;
+        let dim = Color::new(0, 0, 0);
+
+        for y in (0..height).step_by(2) {
+            let dy = (y - center.y).abs();
+            if dy >= radius {
+                fb.draw_rect(
+                    Rect::new(Position::new(0, y), Position::new(width, y + 1)),
+                    dim,
+                );
+                continue;
+            }
+
+            let half_span = ((radius * radius - dy * dy) as f32).sqrt() as i64;
+            fb.draw_rect(
+                Rect::new(
+                    Position::new(0, y),
+                    Position::new(center.x - half_span, y + 1),
+                ),
+                dim,
+            );
+            fb.draw_rect(
+                Rect::new(
+                    Position::new(center.x + half_span, y),
+                    Position::new(width, y + 1),
+                ),
+                dim,
+            );
+        }
+    }
+}
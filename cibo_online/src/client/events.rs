@@ -0,0 +1,58 @@
+//! things that happened on the client that the hud panels might want to show. the game state only
+//! emits them, every panel picks out the events it cares about when they are dispatched.
+
+use super::panels::Panel;
+use crate::server::{ChatId, TickerKind};
+use alloc::{collections::VecDeque, string::String};
+use monos_gfx::Position;
+
+#[derive(Debug, Clone)]
+pub(crate) enum ClientEvent {
+    /// a line for the chat log that isn't a chat message, e.g. a system message
+    Log {
+        text: String,
+        highlight: bool,
+    },
+    /// a chat message or whisper, `prefix` is shown in front of it, e.g. the name of the sender
+    Chat {
+        id: ChatId,
+        prefix: String,
+        message: String,
+        highlight: bool,
+    },
+    ChatEdited {
+        id: ChatId,
+        message: String,
+    },
+    ChatDeleted(ChatId),
+    Ticker(String, TickerKind),
+    /// how many players are on a linked server
+    RemoteOnline {
+        server: String,
+        players: u32,
+    },
+    HomeChanged(Option<Position>),
+    /// the player list key went down or up
+    PlayerListHeld(bool),
+}
+
+/// events wait here until the next dispatch, so they can be emitted while the panels are borrowed
+#[derive(Debug, Default)]
+pub(crate) struct EventBus {
+    queue: VecDeque<ClientEvent>,
+}
+
+impl EventBus {
+    pub fn emit(&mut self, event: ClientEvent) {
+        self.queue.push_back(event);
+    }
+
+    /// hand every queued event to every panel, in the order they were emitted
+    pub fn dispatch(&mut self, panels: &mut [&mut dyn Panel], time_ms: u64) {
+        while let Some(event) = self.queue.pop_front() {
+            for panel in panels.iter_mut() {
+                panel.handle(&event, time_ms);
+            }
+        }
+    }
+}
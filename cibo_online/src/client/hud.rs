@@ -0,0 +1,251 @@
+use alloc::vec::Vec;
+use monos_gfx::{
+    input::Input,
+    text::font,
+    ui::{Lines, TextWrap},
+    Color, Dimension, Framebuffer, Position, Rect,
+};
+use serde::{Deserialize, Serialize};
+
+const MIN_SIZE: u32 = 20;
+/// size of the corner that resizes an element when dragged
+const RESIZE_HANDLE: i64 = 6;
+const OUTLINE_COLOR: Color = Color::new(255, 220, 80);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudElementKind {
+    ChatLog,
+    Coordinates,
+    Minimap,
+    Ticker,
+}
+
+impl HudElementKind {
+    pub const ALL: [HudElementKind; 4] = [
+        HudElementKind::ChatLog,
+        HudElementKind::Coordinates,
+        HudElementKind::Minimap,
+        HudElementKind::Ticker,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HudElementKind::ChatLog => "chat log",
+            HudElementKind::Coordinates => "coordinates",
+            HudElementKind::Minimap => "minimap",
+            HudElementKind::Ticker => "ticker",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HudElement {
+    pub x: i64,
+    pub y: i64,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl HudElement {
+    const fn new(x: i64, y: i64, width: u32, height: u32) -> Self {
+        HudElement {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(
+            Position::new(self.x, self.y),
+            Position::new(self.x + self.width as i64, self.y + self.height as i64),
+        )
+    }
+
+    /// keep the element on screen, e.g. after the layout was made for a bigger screen
+    fn clamp(&mut self, screen: Dimension) {
+        self.width = self.width.clamp(MIN_SIZE, screen.width.max(MIN_SIZE));
+        self.height = self.height.clamp(MIN_SIZE, screen.height.max(MIN_SIZE));
+        self.x = self
+            .x
+            .clamp(0, (screen.width as i64 - self.width as i64).max(0));
+        self.y = self
+            .y
+            .clamp(0, (screen.height as i64 - self.height as i64).max(0));
+    }
+}
+
+/// where the parts of the hud are drawn. persisted by the client so players can arrange it to their liking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HudLayout {
+    pub chat_log: HudElement,
+    pub coordinates: HudElement,
+    pub minimap: HudElement,
+    pub ticker: HudElement,
+}
+
+impl HudLayout {
+    pub fn new(screen: Dimension) -> Self {
+        let width = screen.width as i64;
+        let height = screen.height as i64;
+        HudLayout {
+            chat_log: HudElement::new(0, height - 100, 100, 100),
+            coordinates: HudElement::new(width - 100, 0, 100, 20),
+            minimap: HudElement::new(width - 85, height - 85, 80, 80),
+            ticker: HudElement::new(5, 5, 200, 60),
+        }
+    }
+
+    pub fn get(&self, kind: HudElementKind) -> &HudElement {
+        match kind {
+            HudElementKind::ChatLog => &self.chat_log,
+            HudElementKind::Coordinates => &self.coordinates,
+            HudElementKind::Minimap => &self.minimap,
+            HudElementKind::Ticker => &self.ticker,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: HudElementKind) -> &mut HudElement {
+        match kind {
+            HudElementKind::ChatLog => &mut self.chat_log,
+            HudElementKind::Coordinates => &mut self.coordinates,
+            HudElementKind::Minimap => &mut self.minimap,
+            HudElementKind::Ticker => &mut self.ticker,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
+        postcard::to_allocvec(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, postcard::Error> {
+        postcard::from_bytes(bytes)
+    }
+
+    pub(crate) fn clamp(&mut self, screen: Dimension) {
+        for kind in HudElementKind::ALL {
+            self.get_mut(kind).clamp(screen);
+        }
+    }
+
+    /// a copy that fits on the screen, the layout itself is kept for when the screen gets bigger again
+    pub(crate) fn clamped(&self, screen: Dimension) -> HudLayout {
+        let mut layout = self.clone();
+        layout.clamp(screen);
+        layout
+    }
+}
+
+/// layout mode that lets the hud elements be dragged around and resized
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HudEditor {
+    pub(crate) active: bool,
+    /// element that is being dragged, where it was grabbed and whether it is being resized
+    dragging: Option<(HudElementKind, Position, bool)>,
+    left_was_pressed: bool,
+}
+
+impl HudEditor {
+    pub(crate) fn toggle(&mut self) {
+        self.active = !self.active;
+        self.dragging = None;
+    }
+
+    /// returns whether the layout was changed
+    pub(crate) fn update(
+        &mut self,
+        layout: &mut HudLayout,
+        input: &Input,
+        screen: Dimension,
+    ) -> bool {
+        let mouse = input.mouse.position;
+        let left = input.mouse.left_button.pressed;
+
+        if left && !self.left_was_pressed {
+            // the last element is drawn on top, so it gets picked first
+            self.dragging = HudElementKind::ALL
+                .into_iter()
+                .rev()
+                .find(|kind| layout.get(*kind).rect().contains(mouse))
+                .map(|kind| {
+                    let rect = layout.get(kind).rect();
+                    let resize = mouse.x >= rect.max.x - RESIZE_HANDLE
+                        && mouse.y >= rect.max.y - RESIZE_HANDLE;
+                    let grab = if resize {
+                        rect.max - mouse
+                    } else {
+                        mouse - rect.min
+                    };
+                    (kind, grab, resize)
+                });
+        }
+        self.left_was_pressed = left;
+
+        let Some((kind, grab, resize)) = self.dragging else {
+            return false;
+        };
+        if !left {
+            self.dragging = None;
+            return true;
+        }
+
+        let element = layout.get_mut(kind);
+        if resize {
+            let corner = mouse + grab;
+            element.width = (corner.x - element.x).max(MIN_SIZE as i64) as u32;
+            element.height = (corner.y - element.y).max(MIN_SIZE as i64) as u32;
+        } else {
+            let position = mouse - grab;
+            element.x = position.x;
+            element.y = position.y;
+        }
+        element.clamp(screen);
+        false
+    }
+
+    pub(crate) fn draw(&self, framebuffer: &mut Framebuffer, layout: &HudLayout) {
+        for kind in HudElementKind::ALL {
+            let element = layout.get(kind);
+            let rect = element.rect();
+            draw_outline(framebuffer, rect, OUTLINE_COLOR);
+            framebuffer.draw_rect(
+                Rect::new(
+                    rect.max - Position::new(RESIZE_HANDLE, RESIZE_HANDLE),
+                    rect.max,
+                ),
+                OUTLINE_COLOR,
+            );
+            Lines::<font::Glean>::layout(
+                kind.name(),
+                TextWrap::Enabled { hyphenate: false },
+                Dimension::new(element.width, element.height),
+            )
+            .draw(framebuffer, rect.min + Position::new(2, 2), OUTLINE_COLOR);
+        }
+
+        let hint = "layout mode: drag to move, drag the corner to resize, f4 to leave";
+        Lines::<font::Glean>::layout(
+            hint,
+            TextWrap::Enabled { hyphenate: false },
+            Dimension::new(framebuffer.dimensions().width, 20),
+        )
+        .draw(
+            framebuffer,
+            Position::new(5, framebuffer.dimensions().height as i64 / 2),
+            OUTLINE_COLOR,
+        );
+    }
+}
+
+fn draw_outline(framebuffer: &mut Framebuffer, rect: Rect, color: Color) {
+    let edges = [
+        Rect::new(rect.min, Position::new(rect.max.x, rect.min.y + 1)),
+        Rect::new(Position::new(rect.min.x, rect.max.y - 1), rect.max),
+        Rect::new(rect.min, Position::new(rect.min.x + 1, rect.max.y)),
+        Rect::new(Position::new(rect.max.x - 1, rect.min.y), rect.max),
+    ];
+    for edge in edges {
+        framebuffer.draw_rect(edge, color);
+    }
+}
@@ -0,0 +1,113 @@
+//! spreads out the name labels and chat bubbles of players standing close together, so they stay readable.
+//! the sizes are estimates, the labels themselves are laid out by the ui.
+
+use super::{ClientId, LabelLayout};
+use alloc::vec::Vec;
+use monos_gfx::{Position, Rect};
+
+/// how far a label is moved up per step when staggering
+const STAGGER_STEP: i64 = 12;
+const MAX_STAGGER_STEPS: i64 = 3;
+const FADED_ALPHA: u8 = 64;
+const BUBBLE_HEIGHT: i64 = 18;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LabelPlacement {
+    pub(crate) offset: i64,
+    pub(crate) alpha: u8,
+}
+
+impl Default for LabelPlacement {
+    fn default() -> Self {
+        LabelPlacement {
+            offset: 0,
+            alpha: u8::MAX,
+        }
+    }
+}
+
+/// labels of more important players get placed first. compared field by field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct LabelPriority {
+    pub(crate) own: bool,
+    /// when the newest chat message arrived
+    pub(crate) last_chat: Option<u64>,
+    pub(crate) typing: bool,
+    /// negative distance to the own player, so closer players come first
+    pub(crate) closeness: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Label {
+    pub(crate) id: ClientId,
+    pub(crate) screen_position: Position,
+    /// chat bubbles shown above the player, including the typing indicator
+    pub(crate) bubbles: usize,
+    pub(crate) priority: LabelPriority,
+}
+
+impl Label {
+    /// rough area the name and the chat bubbles take up
+    fn rect(&self, offset: i64) -> Rect {
+        let position = self.screen_position + Position::new(0, offset);
+        Rect::new(
+            Position::new(
+                position.x - 30,
+                position.y + 7 - self.bubbles as i64 * BUBBLE_HEIGHT,
+            ),
+            Position::new(position.x + 62, position.y + 45),
+        )
+    }
+}
+
+/// memory used by `layout` that is kept between frames
+#[derive(Debug, Default)]
+pub(crate) struct LayoutBuffers {
+    /// filled by the caller before each layout
+    pub(crate) labels: Vec<Label>,
+    placed: Vec<Rect>,
+    /// where each label ended up, most important first
+    pub(crate) placements: Vec<(ClientId, LabelPlacement)>,
+}
+
+pub(crate) fn layout(buffers: &mut LayoutBuffers, mode: LabelLayout) {
+    let LayoutBuffers {
+        labels,
+        placed,
+        placements,
+    } = buffers;
+    labels.sort_unstable_by(|a, b| b.priority.cmp(&a.priority));
+
+    placed.clear();
+    placements.clear();
+    placements.extend(labels.iter().map(|label| {
+        let free = |offset: i64| {
+            let rect = label.rect(offset);
+            !placed.iter().any(|other| other.intersects(&rect))
+        };
+
+        let max_steps = match mode {
+            LabelLayout::Overlap => return (label.id, LabelPlacement::default()),
+            LabelLayout::Stagger => MAX_STAGGER_STEPS,
+            LabelLayout::Fade => 0,
+        };
+
+        let placement = match (0..=max_steps)
+            .map(|step| -step * STAGGER_STEP)
+            .find(|offset| free(*offset))
+        {
+            Some(offset) => {
+                placed.push(label.rect(offset));
+                LabelPlacement {
+                    offset,
+                    alpha: u8::MAX,
+                }
+            }
+            None => LabelPlacement {
+                offset: 0,
+                alpha: FADED_ALPHA,
+            },
+        };
+        (label.id, placement)
+    }));
+}
@@ -0,0 +1,246 @@
+//! where the time goes between a player pressing a key and seeing the result. every
+//! `TRACE_INTERVAL_MS` an action gets a trace id, the server stamps when it received and broadcast
+//! it and the client times the rest once its own action comes back in an `UpdateState`.
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+const TRACE_INTERVAL_MS: u64 = 1000;
+/// traces that didn't come back by then were most likely dropped
+const TRACE_TIMEOUT_MS: u64 = 5000;
+/// upper bounds of the histogram buckets in milliseconds, the last one catches everything else
+const BUCKETS: [u64; 8] = [5, 10, 25, 50, 100, 250, 500, u64::MAX];
+
+/// carried along with a traced `ClientAction`. the times are in server clock time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionTrace {
+    pub(crate) id: u32,
+    pub(crate) received: Option<u64>,
+    pub(crate) broadcast: Option<u64>,
+}
+
+impl ActionTrace {
+    pub(crate) fn new(id: u32) -> Self {
+        ActionTrace {
+            id,
+            received: None,
+            broadcast: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LatencyStage {
+    /// from sending the action until the server got it
+    Uplink,
+    /// waiting for the next tick on the server
+    Server,
+    /// from the server sending the update until it arrived
+    Downlink,
+    /// from the update arriving until the next frame showed it
+    Render,
+    /// everything together
+    Total,
+}
+
+impl LatencyStage {
+    pub const ALL: [LatencyStage; 5] = [
+        LatencyStage::Uplink,
+        LatencyStage::Server,
+        LatencyStage::Downlink,
+        LatencyStage::Render,
+        LatencyStage::Total,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LatencyStage::Uplink => "uplink",
+            LatencyStage::Server => "server",
+            LatencyStage::Downlink => "downlink",
+            LatencyStage::Render => "render",
+            LatencyStage::Total => "total",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    buckets: [u32; BUCKETS.len()],
+    count: u32,
+}
+
+impl Histogram {
+    fn record(&mut self, ms: u64) {
+        let bucket = BUCKETS.iter().position(|bound| ms <= *bound).unwrap_or(0);
+        self.buckets[bucket] += 1;
+        self.count += 1;
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// the upper bound of the bucket the percentile falls into, `None` without samples or when
+    /// it is in the last bucket that has no bound
+    pub fn percentile(&self, percentile: u32) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let target = (self.count as u64 * percentile as u64).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (count, bound) in self.buckets.iter().zip(BUCKETS) {
+            seen += *count as u64;
+            if seen >= target {
+                return Some(bound).filter(|bound| *bound != u64::MAX);
+            }
+        }
+        None
+    }
+
+    /// the bucket bounds and how many samples fell into each of them
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u32)> + '_ {
+        BUCKETS.into_iter().zip(self.buckets.iter().copied())
+    }
+}
+
+/// per-stage latency of the traced actions since joining
+#[derive(Debug, Clone, Default)]
+pub struct LatencyStats {
+    stages: [Histogram; LatencyStage::ALL.len()],
+}
+
+impl LatencyStats {
+    pub fn stage(&self, stage: LatencyStage) -> &Histogram {
+        &self.stages[stage as usize]
+    }
+
+    fn record(&mut self, stage: LatencyStage, ms: u64) {
+        self.stages[stage as usize].record(ms);
+    }
+}
+
+/// an action that came back and waits for the next frame
+#[derive(Debug)]
+struct Arrived {
+    sent: u64,
+    received: u64,
+    broadcast: u64,
+    arrived: u64,
+}
+
+/// the client side of the tracing, all times are in local time
+#[derive(Debug, Default)]
+pub(crate) struct LatencyTracer {
+    next_id: u32,
+    last_trace: Option<u64>,
+    /// trace ids that were sent and when
+    pending: Vec<(u32, u64)>,
+    arrived: Vec<Arrived>,
+    stats: LatencyStats,
+}
+
+impl LatencyTracer {
+    /// a trace for the action sent at `time_ms`, if it is time for the next one
+    pub fn start(&mut self, time_ms: u64) -> Option<ActionTrace> {
+        if self
+            .last_trace
+            .is_some_and(|last| time_ms < last + TRACE_INTERVAL_MS)
+        {
+            return None;
+        }
+        self.last_trace = Some(time_ms);
+        self.pending
+            .retain(|(_, sent)| time_ms < sent + TRACE_TIMEOUT_MS);
+
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.pending.push((id, time_ms));
+        Some(ActionTrace::new(id))
+    }
+
+    /// our own action came back from the server. `clock_offset` turns local into server time
+    pub fn arrived(&mut self, trace: &ActionTrace, time_ms: u64, clock_offset: i64) {
+        let Some(index) = self.pending.iter().position(|(id, _)| *id == trace.id) else {
+            return;
+        };
+        let (_, sent) = self.pending.swap_remove(index);
+        let (Some(received), Some(broadcast)) = (trace.received, trace.broadcast) else {
+            return;
+        };
+        let to_local = |server_time: u64| (server_time as i64 - clock_offset).max(0) as u64;
+        self.arrived.push(Arrived {
+            sent,
+            received: to_local(received),
+            broadcast: to_local(broadcast),
+            arrived: time_ms,
+        });
+    }
+
+    /// a frame was drawn at `time_ms`, everything that arrived before it is now visible
+    pub fn rendered(&mut self, time_ms: u64) {
+        for arrived in self.arrived.drain(..) {
+            // the clocks are only synced roughly, so the stages can't go below zero
+            self.stats.record(
+                LatencyStage::Uplink,
+                arrived.received.saturating_sub(arrived.sent),
+            );
+            self.stats.record(
+                LatencyStage::Server,
+                arrived.broadcast.saturating_sub(arrived.received),
+            );
+            self.stats.record(
+                LatencyStage::Downlink,
+                arrived.arrived.saturating_sub(arrived.broadcast),
+            );
+            self.stats.record(
+                LatencyStage::Render,
+                time_ms.saturating_sub(arrived.arrived),
+            );
+            self.stats
+                .record(LatencyStage::Total, time_ms.saturating_sub(arrived.sent));
+        }
+    }
+
+    pub fn stats(&self) -> &LatencyStats {
+        &self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traces_every_interval() {
+        let mut tracer = LatencyTracer::default();
+        assert!(tracer.start(0).is_some());
+        assert!(tracer.start(TRACE_INTERVAL_MS - 1).is_none());
+        assert!(tracer.start(TRACE_INTERVAL_MS).is_some());
+    }
+
+    #[test]
+    fn splits_the_round_trip_into_stages() {
+        let mut tracer = LatencyTracer::default();
+        let mut trace = tracer.start(1000).unwrap();
+        // the server clock is 500ms ahead
+        trace.received = Some(1520);
+        trace.broadcast = Some(1535);
+        tracer.arrived(&trace, 1060, 500);
+        tracer.rendered(1076);
+
+        let stats = tracer.stats();
+        assert_eq!(stats.stage(LatencyStage::Uplink).percentile(50), Some(25));
+        assert_eq!(stats.stage(LatencyStage::Server).percentile(50), Some(25));
+        assert_eq!(stats.stage(LatencyStage::Downlink).percentile(50), Some(25));
+        assert_eq!(stats.stage(LatencyStage::Render).percentile(50), Some(25));
+        assert_eq!(stats.stage(LatencyStage::Total).percentile(50), Some(100));
+    }
+
+    #[test]
+    fn ignores_unknown_traces() {
+        let mut tracer = LatencyTracer::default();
+        tracer.arrived(&ActionTrace::new(7), 100, 0);
+        tracer.rendered(100);
+        assert_eq!(tracer.stats().stage(LatencyStage::Total).count(), 0);
+    }
+}
@@ -2,19 +2,64 @@ mod render;
 pub use render::ClientLocal;
 pub(crate) use render::{OwnClient, OwnClientLocal};
 
+mod chalk;
+pub use chalk::ChalkTool;
+
+pub mod controls;
+
+mod cosmetics;
+pub use cosmetics::CosmeticId;
+
+mod editor;
+
+mod effects;
+
+mod events;
+
+pub(crate) mod labels;
+
+mod hud;
+pub use hud::{HudElement, HudElementKind, HudLayout};
+
+mod latency;
+pub use latency::{ActionTrace, Histogram, LatencyStage, LatencyStats};
+
+mod panels;
+
+mod play_stats;
+pub use play_stats::PlayStats;
+
+mod quick_chat;
+pub use quick_chat::{PhraseId, PHRASES};
+
+mod settings;
+pub use settings::{valid_account_key, LabelLayout, PlayerSettings, ACCOUNT_KEY_LENGTH};
+
 mod state;
 pub use state::ClientGameState;
 
-use crate::world::ObjectId;
+mod transfer;
+
+mod tutorial;
+pub use tutorial::TutorialStep;
+
+use crate::{
+    debug::WorldSnapshot, envelope, server::ChatId, world::ObjectId, ChalkStroke, Elevation,
+    EnvelopeError, MinigameKind, PlaceableObject,
+};
 
 use alloc::{string::String, vec::Vec};
 use core::sync::atomic::{AtomicU32, Ordering};
-use monos_gfx::Position;
+use monos_gfx::{Position, Rect};
 use serde::{Deserialize, Serialize};
 
 static CLIENT_ID: AtomicU32 = AtomicU32::new(0);
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd)]
+/// how many steps another client is moved along without a fresh action, about half a second of
+/// walking. anyone still walking sends a move long before that
+const MAX_PREDICTED_STEPS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct ClientId(u32);
 
 impl ClientId {
@@ -22,6 +67,10 @@ impl ClientId {
         ClientId(CLIENT_ID.fetch_add(1, Ordering::SeqCst))
     }
 
+    pub fn from_u32(id: u32) -> Self {
+        ClientId(id)
+    }
+
     pub fn as_u32(&self) -> u32 {
         self.0
     }
@@ -29,19 +78,145 @@ impl ClientId {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ClientMessage {
-    Connect { name: String },
+    Connect {
+        name: String,
+        /// token handed out through the admin panel, grants access to the world editor
+        admin_token: Option<String>,
+        /// key the player's settings are stored under, generated by the client
+        account: Option<String>,
+        /// settings saved on this device, used if the server has none stored for the account yet
+        settings: PlayerSettings,
+        /// the version of the server rules the player agreed to, `None` if there were none to agree to
+        rules_version: Option<u32>,
+        /// a code from `ServerMessage::TransferCode`, to take over the session it was made for
+        transfer: Option<String>,
+    },
     Action(ClientAction),
     Chat(String),
+    /// private message to the player with the given name
+    Whisper {
+        to: String,
+        message: String,
+    },
+    /// an encoded `ObjectMessage` for the object
     UpdateObject(ObjectId, Vec<u8>),
+    /// clock sync request containing the local client time
+    Ping(u64),
+
+    EditorPlace(PlaceableObject, Position),
+    EditorMove(ObjectId, Position),
+    EditorDelete(ObjectId),
+
+    /// the player changed their settings
+    UpdateSettings(PlayerSettings),
+
+    /// stop delivering chat and whispers from a client. kept for the players account, so it survives reconnects
+    Block(ClientId),
+    Unblock(ClientId),
+
+    /// turn the players account into a registered one, so guest time limits don't apply anymore
+    Register,
+
+    /// ask the player with the given name to be teleported next to them
+    TeleportRequest(String),
+    /// let the player that asked last teleport next to us
+    TeleportAccept,
+
+    /// remember where the player is standing as their home, stored with their account
+    SetHome,
+    /// teleport back to the home
+    Home,
+
+    /// change the text of our newest chat message, only possible for `CHAT_EDIT_WINDOW_MS` after sending it
+    EditChat {
+        message_id: ChatId,
+        new_text: String,
+    },
+
+    /// ask whether a name can be used before connecting with it, answered with `ServerMessage::NameCheck`
+    CheckName {
+        name: String,
+        account: Option<String>,
+    },
+
+    /// ask for the players statistics, answered with `ServerMessage::PlayStats`
+    RequestStats,
+
+    /// draw on the ground around the player
+    DrawChalk(ChalkStroke),
+
+    /// leave a letter for the player with the given name, only works while standing at a mailbox
+    SendMail {
+        to: String,
+        message: String,
+    },
+
+    /// confirms that a `ServerMessage::Critical` with this sequence number arrived
+    Ack(u32),
+
+    /// answer to a `ServerMessage::RequestSnapshot`
+    WorldSnapshot(WorldSnapshot),
+
+    /// change the name of a registered player while playing, answered with `ServerMessage::ClientRenamed`
+    Rename(String),
+
+    /// ask for a code to continue playing on another device, answered with `ServerMessage::TransferCode`
+    RequestTransfer,
+
+    /// put out one of `server::STAFF_DECORATIONS` where the player is standing, staff only
+    StaffDecorate(PlaceableObject),
+    /// mute the player with the given name on this shard for a while, staff only
+    StaffMute(String),
+    /// start a minigame on this shard or stop the running one with `None`, staff only
+    StaffMinigame(Option<MinigameKind>),
+
+    /// say one of the preset phrases, the only way to chat in safe chat
+    QuickChat(PhraseId),
 }
 
 impl ClientMessage {
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self, postcard::Error> {
-        postcard::from_bytes(bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        envelope::from_bytes(bytes)
     }
 
     pub fn to_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
-        postcard::to_allocvec(self)
+        envelope::to_bytes(self)
+    }
+
+    /// short name of the message type, mainly for statistics and logging.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClientMessage::Connect { .. } => "Connect",
+            ClientMessage::Action(_) => "Action",
+            ClientMessage::Chat(_) => "Chat",
+            ClientMessage::Whisper { .. } => "Whisper",
+            ClientMessage::UpdateObject(_, _) => "UpdateObject",
+            ClientMessage::Ping(_) => "Ping",
+            ClientMessage::EditorPlace(_, _) => "EditorPlace",
+            ClientMessage::EditorMove(_, _) => "EditorMove",
+            ClientMessage::EditorDelete(_) => "EditorDelete",
+            ClientMessage::UpdateSettings(_) => "UpdateSettings",
+            ClientMessage::Block(_) => "Block",
+            ClientMessage::Unblock(_) => "Unblock",
+            ClientMessage::Register => "Register",
+            ClientMessage::TeleportRequest(_) => "TeleportRequest",
+            ClientMessage::TeleportAccept => "TeleportAccept",
+            ClientMessage::SetHome => "SetHome",
+            ClientMessage::Home => "Home",
+            ClientMessage::EditChat { .. } => "EditChat",
+            ClientMessage::CheckName { .. } => "CheckName",
+            ClientMessage::RequestStats => "RequestStats",
+            ClientMessage::DrawChalk(_) => "DrawChalk",
+            ClientMessage::SendMail { .. } => "SendMail",
+            ClientMessage::Ack(_) => "Ack",
+            ClientMessage::WorldSnapshot(_) => "WorldSnapshot",
+            ClientMessage::Rename(_) => "Rename",
+            ClientMessage::RequestTransfer => "RequestTransfer",
+            ClientMessage::StaffDecorate(_) => "StaffDecorate",
+            ClientMessage::StaffMute(_) => "StaffMute",
+            ClientMessage::StaffMinigame(_) => "StaffMinigame",
+            ClientMessage::QuickChat(_) => "QuickChat",
+        }
     }
 }
 
@@ -53,6 +228,21 @@ pub struct Client {
     pub(crate) position: Position,
     pub(crate) movement: MoveDirection,
     pub(crate) look_direction: MoveDirection,
+    pub(crate) elevation: Elevation,
+    /// drawn over the sprite, in `CosmeticId::ALL` order
+    pub(crate) cosmetics: Vec<CosmeticId>,
+    /// linked a discord account, shown as a badge next to the name
+    pub(crate) verified: bool,
+    /// only sees and says preset phrases, shown as a badge so others know
+    pub(crate) safe_chat: bool,
+    #[serde(skip)]
+    pub(crate) glow: bool,
+    /// only set while rendering
+    #[serde(skip)]
+    pub(crate) swimming: bool,
+    /// steps moved by `predict_movement` since the last action
+    #[serde(skip)]
+    predicted_steps: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +255,11 @@ enum ClientActionMovement {
 pub struct ClientAction {
     movement: Option<ClientActionMovement>,
     typing: Option<bool>,
+    elevation: Option<Elevation>,
+    /// when the action happened, in server clock time
+    timestamp: Option<u64>,
+    /// set on the few actions whose latency is measured
+    trace: Option<ActionTrace>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -87,6 +282,46 @@ impl ClientAction {
         ClientAction {
             movement: None,
             typing: None,
+            elevation: None,
+            timestamp: None,
+            trace: None,
+        }
+    }
+
+    pub fn timestamp(&mut self, timestamp: u64) {
+        self.timestamp = Some(timestamp);
+    }
+
+    pub(crate) fn get_timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+
+    /// keep the timestamp inside of the window the server is willing to compensate for.
+    pub(crate) fn clamp_timestamp(&mut self, min: u64, max: u64) {
+        if let Some(timestamp) = &mut self.timestamp {
+            *timestamp = (*timestamp).clamp(min, max);
+        }
+    }
+
+    pub(crate) fn trace(&mut self, trace: ActionTrace) {
+        self.trace = Some(trace);
+    }
+
+    pub(crate) fn get_trace(&self) -> Option<&ActionTrace> {
+        self.trace.as_ref()
+    }
+
+    /// the server got the action at `time_ms`
+    pub(crate) fn stamp_received(&mut self, time_ms: u64) {
+        if let Some(trace) = &mut self.trace {
+            trace.received = Some(time_ms);
+        }
+    }
+
+    /// the server sent the action on to the clients at `time_ms`
+    pub(crate) fn stamp_broadcast(&mut self, time_ms: u64) {
+        if let Some(trace) = &mut self.trace {
+            trace.broadcast = Some(time_ms);
         }
     }
 
@@ -94,6 +329,20 @@ impl ClientAction {
         self.movement = Some(ClientActionMovement::Move(movement, direction));
     }
 
+    /// where the action moves the client, if it does
+    pub fn moved_to(&self) -> Option<Position> {
+        match self.movement {
+            Some(ClientActionMovement::Move(position, _)) => Some(position),
+            _ => None,
+        }
+    }
+
+    /// forget the move and level change, e.g. because the server refused them
+    pub(crate) fn drop_movement(&mut self) {
+        self.movement = None;
+        self.elevation = None;
+    }
+
     pub fn look(&mut self, direction: MoveDirection) {
         match self.movement {
             Some(ClientActionMovement::Move(_, ref mut d)) => {
@@ -107,8 +356,12 @@ impl ClientAction {
         self.typing = Some(typing);
     }
 
+    pub fn elevation(&mut self, elevation: Elevation) {
+        self.elevation = Some(elevation);
+    }
+
     pub fn any(&self) -> bool {
-        self.movement.is_some() || self.typing.is_some()
+        self.movement.is_some() || self.typing.is_some() || self.elevation.is_some()
     }
 
     pub(crate) fn combine(&mut self, action: &ClientAction) {
@@ -127,6 +380,18 @@ impl ClientAction {
         if action.typing.is_some() {
             self.typing = action.typing;
         }
+
+        if action.elevation.is_some() {
+            self.elevation = action.elevation;
+        }
+
+        if action.timestamp > self.timestamp {
+            self.timestamp = action.timestamp;
+        }
+
+        if action.trace.is_some() {
+            self.trace = action.trace;
+        }
     }
 }
 
@@ -139,9 +404,40 @@ impl Client {
             position,
             movement: MoveDirection::None,
             look_direction: MoveDirection::None,
+            elevation: Elevation::Ground,
+            cosmetics: Vec::new(),
+            verified: false,
+            safe_chat: false,
+            glow: false,
+            swimming: false,
+            predicted_steps: 0,
         }
     }
 
+    /// the hitbox of a client standing at `position` in world space.
+    pub(crate) fn hitbox_at(position: Position) -> Rect {
+        Rect::new(
+            Position::new(position.x + 2, position.y + 5),
+            Position::new(position.x + 30, position.y + 32),
+        )
+    }
+
+    /// roughly where the feet of the sprite are
+    pub(crate) fn feet(&self) -> Position {
+        Self::feet_at(self.position)
+    }
+
+    pub(crate) fn feet_at(position: Position) -> Position {
+        position + Position::new(16, 28)
+    }
+
+    /// whether the client is in the sea of the beach episode. bridges keep you dry
+    pub(crate) fn in_water(&self, beach_episode: bool) -> bool {
+        beach_episode
+            && self.elevation == Elevation::Ground
+            && crate::world::water::is_water(self.feet())
+    }
+
     #[inline]
     pub const fn id(&self) -> ClientId {
         self.id
@@ -152,6 +448,27 @@ impl Client {
         &self.name
     }
 
+    pub(crate) fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /// advance the position along the current movement direction.
+    ///
+    /// used to keep other clients moving between their updates. the next authoritative update overrides the predicted position.
+    /// gives up after `MAX_PREDICTED_STEPS`, so a client whose updates stop doesn't walk off forever.
+    pub(crate) fn predict_movement(&mut self, steps: u64) {
+        let steps = steps.min(MAX_PREDICTED_STEPS.saturating_sub(self.predicted_steps));
+        self.predicted_steps += steps;
+        let steps = steps as i64;
+        match self.movement {
+            MoveDirection::Up => self.position.y -= steps,
+            MoveDirection::Down => self.position.y += steps,
+            MoveDirection::Left => self.position.x -= steps,
+            MoveDirection::Right => self.position.x += steps,
+            MoveDirection::None => {}
+        }
+    }
+
     pub fn apply_action(&mut self, action: &ClientAction) {
         if let Some(movement) = &action.movement {
             match movement {
@@ -161,9 +478,13 @@ impl Client {
                     if *direction != MoveDirection::None {
                         self.look_direction = *direction;
                     }
+                    self.predicted_steps = 0;
                 }
+                // sent instead of a move when the client is blocked, so it is standing still
                 ClientActionMovement::Look(direction) => {
                     self.look_direction = *direction;
+                    self.movement = MoveDirection::None;
+                    self.predicted_steps = 0;
                 }
             }
         }
@@ -171,6 +492,10 @@ impl Client {
         if let Some(typing) = action.typing {
             self.typing = typing
         }
+
+        if let Some(elevation) = action.elevation {
+            self.elevation = elevation
+        }
     }
 }
 
@@ -0,0 +1,156 @@
+use super::{Panel, PanelContext};
+use crate::{client::events::ClientEvent, links, server::ChatId, widgets};
+use alloc::{collections::VecDeque, format, string::String};
+use monos_gfx::{
+    text::font,
+    ui::{Direction, UIFrame},
+    Color, Position, Rect,
+};
+
+const MAX_LINES: usize = 256;
+
+#[derive(Debug, Clone)]
+pub(crate) struct LogLine {
+    pub text: String,
+    highlight: bool,
+    /// the chat message this line belongs to, if any
+    chat_id: Option<ChatId>,
+    /// the first link in the message, the server already removed the ones that aren't allowed
+    link: Option<String>,
+    /// where the message starts in `text`, after the name of the sender
+    message_start: usize,
+}
+
+/// the first link in a chat message, as something that can be opened
+fn first_link(message: &str) -> Option<String> {
+    links::find_links(message)
+        .first()
+        .map(|link| links::url(&message[link.clone()]))
+}
+
+#[derive(Debug)]
+pub(crate) struct ChatLogPanel {
+    lines: VecDeque<LogLine>,
+    ui: UIFrame,
+    /// a link the player clicked that wasn't opened yet
+    pub clicked_link: Option<String>,
+    /// clicks only count when the button goes down
+    held: bool,
+}
+
+impl Default for ChatLogPanel {
+    fn default() -> Self {
+        ChatLogPanel {
+            lines: VecDeque::new(),
+            ui: UIFrame::new(Direction::BottomToTop),
+            clicked_link: None,
+            held: false,
+        }
+    }
+}
+
+impl ChatLogPanel {
+    pub fn lines(&self) -> impl DoubleEndedIterator<Item = &LogLine> + ExactSizeIterator {
+        self.lines.iter()
+    }
+
+    fn push(&mut self, line: LogLine) {
+        self.lines.push_back(line);
+        if self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+    }
+}
+
+impl Panel for ChatLogPanel {
+    fn handle(&mut self, event: &ClientEvent, _time_ms: u64) {
+        match event {
+            ClientEvent::Log { text, highlight } => self.push(LogLine {
+                text: text.clone(),
+                highlight: *highlight,
+                chat_id: None,
+                link: None,
+                message_start: 0,
+            }),
+            ClientEvent::Chat {
+                id,
+                prefix,
+                message,
+                highlight,
+            } => self.push(LogLine {
+                message_start: prefix.len(),
+                text: format!("{}{}", prefix, message),
+                highlight: *highlight,
+                chat_id: Some(*id),
+                // only in the message, so names aren't mistaken for links
+                link: first_link(message),
+            }),
+            ClientEvent::ChatEdited { id, message } => {
+                for line in self
+                    .lines
+                    .iter_mut()
+                    .filter(|line| line.chat_id == Some(*id))
+                {
+                    line.text.truncate(line.message_start);
+                    line.text.push_str(message);
+                    line.link = first_link(message);
+                }
+            }
+            ClientEvent::ChatDeleted(id) => self.lines.retain(|line| line.chat_id != Some(*id)),
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut PanelContext) {
+        if ctx.stream_mode {
+            return;
+        }
+
+        let rect = ctx.hud.chat_log.rect();
+        let hovered = rect.contains(ctx.input.mouse.position);
+        let scroll = if hovered { ctx.input.mouse.scroll } else { 0 };
+
+        let palette = ctx.palette;
+        let height = ctx.hud.chat_log.height;
+        let lines = &self.lines;
+        self.ui.draw_frame(ctx.fb, rect, ctx.input, |ui| {
+            ui.add(
+                widgets::ChatLog::new(
+                    lines.iter().map(|line| {
+                        let color = if line.highlight {
+                            palette.highlight
+                        } else if line.link.is_some() {
+                            palette.link
+                        } else {
+                            Color::new(255, 255, 255)
+                        };
+                        (line.text.as_str(), color)
+                    }),
+                    height,
+                )
+                .scroll(scroll),
+            );
+        });
+
+        // clicking the log opens the newest link in it
+        let newest_link = self.lines.iter().rev().find_map(|line| line.link.clone());
+        let pressed = ctx.input.mouse.left_button.pressed;
+        if let (true, Some(link)) = (hovered, newest_link) {
+            let hint_rect = Rect::new(
+                Position::new(rect.min.x, rect.min.y - 14),
+                Position::new(rect.max.x + 100, rect.min.y),
+            );
+            let mut hint_ui = UIFrame::new_stateless(Direction::TopToBottom);
+            hint_ui.draw_frame(ctx.fb, hint_rect, ctx.input, |ui| {
+                ui.label::<font::Glean>(&format!(
+                    "click to open {}",
+                    links::domain(&link).unwrap_or(&link)
+                ));
+            });
+            if pressed && !self.held {
+                self.clicked_link = Some(link);
+            }
+        }
+        self.held = pressed;
+    }
+}
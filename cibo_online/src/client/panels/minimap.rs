@@ -0,0 +1,79 @@
+use super::{Panel, PanelContext};
+use crate::{client::events::ClientEvent, Object};
+use monos_gfx::{Color, Framebuffer, Position, Rect};
+
+/// world pixels per minimap pixel
+const MINIMAP_SCALE: i64 = 16;
+const MINIMAP_BG: Color = Color::new(30, 30, 40);
+const MINIMAP_HOME: Color = Color::new(250, 200, 60);
+/// a little house, centered on the home
+const MINIMAP_HOME_ICON: [&str; 5] = ["..#..", ".###.", "#####", ".#.#.", ".#.#."];
+
+/// overview of the surroundings, centered on the player
+#[derive(Debug, Default)]
+pub(crate) struct MinimapPanel {
+    /// set with `/sethome`
+    home: Option<Position>,
+}
+
+impl Panel for MinimapPanel {
+    fn handle(&mut self, event: &ClientEvent, _time_ms: u64) {
+        if let ClientEvent::HomeChanged(home) = event {
+            self.home = *home;
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut PanelContext) {
+        if ctx.stream_mode {
+            return;
+        }
+
+        let rect = ctx.hud.minimap.rect();
+        let Some(center) = ctx.world.clients.first().map(|client| client.position) else {
+            return;
+        };
+        let framebuffer = &mut *ctx.fb;
+        framebuffer.draw_rect(rect, MINIMAP_BG);
+
+        let rect_center =
+            Position::new((rect.min.x + rect.max.x) / 2, (rect.min.y + rect.max.y) / 2);
+        let dot = |framebuffer: &mut Framebuffer, position: Position, color: Color| {
+            let offset = position - center;
+            let dot_position =
+                rect_center + Position::new(offset.x / MINIMAP_SCALE, offset.y / MINIMAP_SCALE);
+            let dot = Rect::new(dot_position, dot_position + Position::new(2, 2));
+            if rect.contains(dot.min) && rect.contains(dot.max - Position::new(1, 1)) {
+                framebuffer.draw_rect(dot, color);
+            }
+        };
+
+        for object in ctx.world.network_objects.values() {
+            dot(
+                framebuffer,
+                object.properties().position,
+                Color::new(120, 120, 120),
+            );
+        }
+        for client in ctx.world.clients.iter().skip(1) {
+            dot(framebuffer, client.position, Color::new(255, 255, 255));
+        }
+
+        if let Some(home) = self.home {
+            let offset = home - center;
+            let home_position = rect_center
+                + Position::new(offset.x / MINIMAP_SCALE, offset.y / MINIMAP_SCALE)
+                - Position::new(2, 2);
+            for (row, line) in MINIMAP_HOME_ICON.iter().enumerate() {
+                for (column, pixel) in line.chars().enumerate() {
+                    let pos = home_position + Position::new(column as i64, row as i64);
+                    if pixel == '#' && rect.contains(pos) {
+                        framebuffer
+                            .draw_rect(Rect::new(pos, pos + Position::new(1, 1)), MINIMAP_HOME);
+                    }
+                }
+            }
+        }
+
+        dot(framebuffer, center, ctx.palette.highlight);
+    }
+}
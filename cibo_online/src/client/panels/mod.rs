@@ -0,0 +1,57 @@
+//! the parts of the hud that only show what the game state tells them through `ClientEvent`s.
+//! adding a panel means adding it to `Panels`, the game state doesn't need to know about it.
+
+mod chat_log;
+pub(crate) use chat_log::ChatLogPanel;
+
+mod minimap;
+pub(crate) use minimap::MinimapPanel;
+
+mod player_list;
+pub(crate) use player_list::PlayerListPanel;
+
+mod toasts;
+pub(crate) use toasts::ToastPanel;
+
+use super::{events::ClientEvent, hud::HudLayout};
+use crate::{Palette, WorldState};
+use monos_gfx::{input::Input, Framebuffer};
+
+pub(crate) trait Panel: core::fmt::Debug {
+    /// called for every event, panels ignore the ones they don't care about
+    fn handle(&mut self, event: &ClientEvent, time_ms: u64);
+    fn draw(&mut self, ctx: &mut PanelContext);
+}
+
+/// everything a panel can look at while drawing
+pub(crate) struct PanelContext<'a> {
+    pub fb: &'a mut Framebuffer,
+    pub input: &'a mut Input,
+    /// the own client is always the first one
+    pub world: &'a WorldState,
+    pub hud: &'a HudLayout,
+    pub palette: &'static Palette,
+    pub time_ms: u64,
+    pub stream_mode: bool,
+    pub reduced_motion: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Panels {
+    pub chat_log: ChatLogPanel,
+    pub minimap: MinimapPanel,
+    pub player_list: PlayerListPanel,
+    pub toasts: ToastPanel,
+}
+
+impl Panels {
+    /// in drawing order, later panels end up on top
+    pub fn all(&mut self) -> [&mut dyn Panel; 4] {
+        [
+            &mut self.toasts,
+            &mut self.chat_log,
+            &mut self.minimap,
+            &mut self.player_list,
+        ]
+    }
+}
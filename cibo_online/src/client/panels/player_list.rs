@@ -0,0 +1,68 @@
+use super::{Panel, PanelContext};
+use crate::client::events::ClientEvent;
+use alloc::{format, string::String, vec::Vec};
+use monos_gfx::{
+    text::font,
+    ui::{Direction, MarginMode, UIFrame},
+    Position, Rect,
+};
+
+/// everyone who is online, shown while the player list key is held
+#[derive(Debug, Default)]
+pub(crate) struct PlayerListPanel {
+    ui: Option<UIFrame>,
+    /// players on linked servers
+    remote_online: Vec<(String, u32)>,
+}
+
+impl Panel for PlayerListPanel {
+    fn handle(&mut self, event: &ClientEvent, _time_ms: u64) {
+        match event {
+            ClientEvent::PlayerListHeld(held) => {
+                self.ui = held.then(|| UIFrame::new_stateless(Direction::TopToBottom));
+            }
+            ClientEvent::RemoteOnline { server, players } => {
+                self.remote_online.retain(|(name, _)| name != server);
+                if *players > 0 {
+                    self.remote_online.push((server.clone(), *players));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut PanelContext) {
+        if ctx.stream_mode {
+            return;
+        }
+        let Some(ui) = &mut self.ui else {
+            return;
+        };
+
+        let width = ctx.fb.dimensions().width as i64;
+        let height = ctx.fb.dimensions().height as i64;
+        let rect = Rect::new(
+            Position::new(width / 2 - 100, 10),
+            Position::new(width / 2 + 100, height - 10),
+        );
+        let clients = &ctx.world.clients;
+        let remote_online = &self.remote_online;
+        ui.draw_frame(ctx.fb, rect, ctx.input, |ui| {
+            ui.margin(MarginMode::Grow);
+            ui.label::<font::Cozette>(&format!("Players Online: {}", clients.len()));
+            for (server, players) in remote_online {
+                ui.label::<font::Glean>(&format!("+{} on {}", players, server));
+            }
+            ui.label::<font::Glean>("You");
+            for client in clients.iter().skip(1) {
+                let client_tile_position = client.position / 16;
+                ui.label::<font::Glean>(&format!(
+                    "{} | X{} / Y{}",
+                    client.name(),
+                    client_tile_position.x,
+                    client_tile_position.y
+                ));
+            }
+        });
+    }
+}
@@ -0,0 +1,93 @@
+use super::{Panel, PanelContext};
+use crate::{client::events::ClientEvent, server::TickerKind};
+use alloc::{collections::VecDeque, string::String};
+use monos_gfx::{
+    text::font,
+    ui::{Lines, TextWrap},
+    Color, Dimension, Position, Rect,
+};
+
+const TICKER_LINES: usize = 4;
+const TICKER_DURATION_MS: u64 = 6000;
+const TICKER_SLIDE_MS: u64 = 250;
+const TICKER_BG: Color = Color::new(30, 30, 40);
+
+#[derive(Debug, Clone)]
+struct TickerLine {
+    text: String,
+    kind: TickerKind,
+    shown_at: u64,
+}
+
+/// recent system events in the ticker, newest first
+#[derive(Debug, Default)]
+pub(crate) struct ToastPanel {
+    lines: VecDeque<TickerLine>,
+}
+
+impl Panel for ToastPanel {
+    fn handle(&mut self, event: &ClientEvent, time_ms: u64) {
+        if let ClientEvent::Ticker(text, kind) = event {
+            self.lines.push_front(TickerLine {
+                text: text.clone(),
+                kind: *kind,
+                shown_at: time_ms,
+            });
+            self.lines.truncate(TICKER_LINES);
+        }
+    }
+
+    /// new lines slide in from the left unless motion is reduced
+    fn draw(&mut self, ctx: &mut PanelContext) {
+        let time_ms = ctx.time_ms;
+        self.lines
+            .retain(|line| time_ms < line.shown_at + TICKER_DURATION_MS);
+
+        let rect = ctx.hud.ticker.rect();
+        let mut y = rect.min.y;
+        for line in self
+            .lines
+            .iter()
+            .filter(|line| !ctx.stream_mode || line.kind.show_in_stream_mode())
+        {
+            let text = Lines::<font::Glean>::layout(
+                &line.text,
+                TextWrap::Enabled { hyphenate: false },
+                Dimension::new((rect.max.x - rect.min.x) as u32, 10000),
+            );
+            let dimensions = text.dimensions();
+            if y + dimensions.height as i64 > rect.max.y {
+                break;
+            }
+
+            let age = if ctx.reduced_motion {
+                TICKER_SLIDE_MS
+            } else {
+                time_ms.saturating_sub(line.shown_at).min(TICKER_SLIDE_MS)
+            };
+            let hidden = (dimensions.width as u64 + 10) * (TICKER_SLIDE_MS - age) / TICKER_SLIDE_MS;
+            let x = rect.min.x - hidden as i64;
+
+            ctx.fb.draw_rect(
+                Rect::new(
+                    Position::new(x - 2, y - 1),
+                    Position::new(
+                        x + dimensions.width as i64 + 2,
+                        y + dimensions.height as i64 + 1,
+                    ),
+                ),
+                TICKER_BG,
+            );
+            let color = match line.kind {
+                TickerKind::Event => ctx.palette.event,
+                TickerKind::Achievement => ctx.palette.highlight,
+                TickerKind::Score => Color::new(255, 255, 255),
+                TickerKind::Error => ctx.palette.bad,
+                TickerKind::Tip => Color::new(190, 190, 190),
+            };
+            text.draw(ctx.fb, Position::new(x, y), color);
+
+            y += dimensions.height as i64 + 4;
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+/// what a player did in the game. added up over all sessions by the server if the player has an account
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayStats {
+    pub time_online_ms: u64,
+    pub tiles_walked: u64,
+    pub messages_sent: u64,
+    pub balls_kicked: u64,
+}
+
+impl PlayStats {
+    pub fn add(&mut self, other: &PlayStats) {
+        self.time_online_ms = self.time_online_ms.saturating_add(other.time_online_ms);
+        self.tiles_walked = self.tiles_walked.saturating_add(other.tiles_walked);
+        self.messages_sent = self.messages_sent.saturating_add(other.messages_sent);
+        self.balls_kicked = self.balls_kicked.saturating_add(other.balls_kicked);
+    }
+}
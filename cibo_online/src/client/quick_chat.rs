@@ -0,0 +1,66 @@
+//! preset phrases for players in safe chat. they only see what other players say if it is one of
+//! these, and can only answer with them, so nothing anyone types ever reaches them.
+
+use serde::{Deserialize, Serialize};
+
+/// everything safe chat players can say, numbered in the chat input. the ids are sent over the
+/// network, so new phrases go at the end
+pub const PHRASES: &[&str] = &[
+    "hi!",
+    "bye!",
+    "yes",
+    "no",
+    "thank you!",
+    "follow me!",
+    "wait for me!",
+    "nice!",
+    ":heart:",
+    ":cibo:",
+    "good game!",
+    "lol",
+];
+
+/// one of `PHRASES`, sent with `ClientMessage::QuickChat`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhraseId(u8);
+
+impl PhraseId {
+    /// `None` for ids a newer client might send
+    pub fn text(&self) -> Option<&'static str> {
+        PHRASES.get(self.0 as usize).copied()
+    }
+
+    /// the phrase a message is, ignoring case and spaces around it
+    pub fn find(message: &str) -> Option<Self> {
+        let message = message.trim();
+        PHRASES
+            .iter()
+            .position(|phrase| phrase.eq_ignore_ascii_case(message))
+            .map(|index| PhraseId(index as u8))
+    }
+
+    /// the phrase picked by its number in the chat input, counting from 1
+    pub fn from_number(number: usize) -> Option<Self> {
+        (1..=PHRASES.len())
+            .contains(&number)
+            .then(|| PhraseId(number as u8 - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_phrases() {
+        assert_eq!(PhraseId::find(" Hi! "), Some(PhraseId(0)));
+        assert_eq!(PhraseId::find(":cibo:"), Some(PhraseId(9)));
+        assert_eq!(PhraseId::find("hi! everyone"), None);
+        assert_eq!(
+            PhraseId::from_number(1).and_then(|id| id.text()),
+            Some("hi!")
+        );
+        assert_eq!(PhraseId::from_number(0), None);
+        assert_eq!(PhraseId::from_number(PHRASES.len() + 1), None);
+    }
+}
@@ -1,13 +1,67 @@
-use super::{Client, ClientMessage};
-use crate::{assets, widgets::ChatWidget, RenderContext, Renderable};
-use alloc::{collections::VecDeque, format, string::String};
+use super::{
+    controls::{parse_command, Command},
+    labels::LabelPlacement,
+    ChalkTool, Client, ClientMessage, MoveDirection, PhraseId, PHRASES,
+};
+use crate::{
+    assets,
+    render::{
+        phase_offset,
+        rich_text::{emoji_suggestions, partial_shortcode},
+        Animation,
+    },
+    widgets::{ChatWidget, RichLabel},
+    RenderContext, Renderable,
+};
+use crate::{server::ChatId, ChalkColor, ChalkStroke, MinigameKind, PlaceableObject};
+use alloc::{borrow::Cow, collections::VecDeque, format, string::String, vec::Vec};
 
 use monos_gfx::{
+    input::{Key, RawKey},
     text::{font, TextWrap},
     types::*,
-    ui::{widgets, Direction, MarginMode, UIFrame},
+    ui::{widgets, Direction, MarginMode, UIContext, UIFrame},
+    Color, Image,
 };
 
+/// white like the other labels
+const NAME_COLOR: Color = Color::new(255, 255, 255);
+/// how long a client has to stand still before it starts looking around
+const LOOK_AROUND_AFTER_MS: u64 = 8000;
+
+const BLINK: Animation<bool> = Animation::looping(&[
+    (false, 3800),
+    (true, 150),
+    (false, 2600),
+    (true, 120),
+    (false, 100),
+    (true, 120),
+]);
+
+const LOOK_AROUND: Animation<MoveDirection> = Animation::looping(&[
+    (MoveDirection::Down, 1500),
+    (MoveDirection::Left, 1200),
+    (MoveDirection::Down, 900),
+    (MoveDirection::Right, 1200),
+    (MoveDirection::Down, 3000),
+]);
+
+/// chat bubbles shown at once, the rest waits until there is room
+const MAX_BUBBLES: usize = 3;
+/// how long a bubble takes to shrink away
+const BUBBLE_DISMISS_MS: u64 = 250;
+
+/// how far a swimming client bobs up and down
+const BOB: Animation<i64> = Animation::looping(&[(0, 450), (1, 300), (2, 450), (1, 300)]);
+/// row of the sprite where the water starts while swimming
+const WATERLINE: i64 = 20;
+/// a little speech bubble over the head of players in safe chat, so others know they only see the
+/// preset phrases
+const SAFE_CHAT_BADGE: [&str; 5] = [".#####.", "##...##", "##...##", ".#####.", "..#...."];
+const SAFE_CHAT_COLOR: Color = Color::new(90, 200, 120);
+const WATER_COLOR: Color = Color::new(86, 170, 230);
+const FOAM_COLOR: Color = Color::new(240, 248, 255);
+
 // wrapper around client to make it render as the controlled player
 pub struct OwnClient<'a>(pub &'a Client);
 
@@ -15,10 +69,91 @@ pub struct OwnClient<'a>(pub &'a Client);
 pub struct ClientLocal {
     chat: VecDeque<ChatMessage>,
     ui: UIFrame,
+    pub flash_until: u64,
+    /// when the client stopped moving
+    idle_since: Option<u64>,
 }
 impl ClientLocal {
-    pub fn add_chat(&mut self, message: String, expiry: u64) {
-        self.chat.push_back(ChatMessage { message, expiry });
+    pub fn add_chat(&mut self, id: ChatId, message: String, received_at: u64, duration_ms: u64) {
+        self.chat.push_back(ChatMessage {
+            id,
+            message,
+            received_at,
+            duration_ms,
+            shown_at: None,
+        });
+    }
+
+    pub fn delete_chat(&mut self, id: ChatId) {
+        self.chat.retain(|chat| chat.id != id);
+    }
+
+    pub fn edit_chat(&mut self, id: ChatId, message: &str) {
+        if let Some(chat) = self.chat.iter_mut().find(|chat| chat.id == id) {
+            chat.message = String::from(message);
+        }
+    }
+
+    pub fn clear_chat(&mut self) {
+        self.chat.clear();
+    }
+
+    /// how many chat bubbles (and the waiting indicator) are shown and when the newest message arrived
+    pub(crate) fn visible_chat(&self) -> (usize, Option<u64>) {
+        let shown = self
+            .chat
+            .iter()
+            .filter(|chat| chat.shown_at.is_some())
+            .count();
+        let waiting = self.chat.len() > shown;
+        let newest = self.chat.back().map(|chat| chat.received_at);
+        (shown + waiting as usize, newest)
+    }
+
+    /// drop bubbles that are gone and show waiting ones if there is room
+    fn update_chat(&mut self, time_ms: u64) {
+        self.chat.retain(|chat| {
+            chat.dismiss_progress(time_ms)
+                .map_or(true, |progress| progress < 1.0)
+        });
+
+        let mut shown = self
+            .chat
+            .iter()
+            .filter(|chat| chat.shown_at.is_some())
+            .count();
+        for chat in self.chat.iter_mut().filter(|chat| chat.shown_at.is_none()) {
+            if shown >= MAX_BUBBLES {
+                break;
+            }
+            chat.shown_at = Some(time_ms);
+            shown += 1;
+        }
+    }
+
+    /// walk cycle while moving, otherwise blinking and looking around after a while.
+    /// also returns the direction the image is facing, so cosmetics can be placed on it
+    fn image(&mut self, client: &Client, ctx: &RenderContext) -> (&'static Image, MoveDirection) {
+        if client.movement != MoveDirection::None {
+            self.idle_since = None;
+            let image = assets().cibo.get_client_image(client, ctx.anim_frame());
+            return (image, client.movement);
+        }
+
+        let idle_ms = ctx.time_ms - *self.idle_since.get_or_insert(ctx.time_ms);
+        let blink_phase = phase_offset(client.id().as_u32(), BLINK.duration_ms());
+        let blink = BLINK.frame_at(ctx.time_ms + blink_phase);
+
+        let facing_front = matches!(
+            client.look_direction,
+            MoveDirection::Down | MoveDirection::None
+        );
+        let direction = if facing_front && idle_ms >= LOOK_AROUND_AFTER_MS {
+            LOOK_AROUND.frame_at(idle_ms - LOOK_AROUND_AFTER_MS)
+        } else {
+            client.look_direction
+        };
+        (assets().cibo.get_idle_image(direction, blink), direction)
     }
 }
 impl Default for ClientLocal {
@@ -26,6 +161,8 @@ impl Default for ClientLocal {
         Self {
             chat: VecDeque::new(),
             ui: UIFrame::new(Direction::BottomToTop),
+            flash_until: 0,
+            idle_since: None,
         }
     }
 }
@@ -34,54 +171,410 @@ impl Default for ClientLocal {
 pub struct OwnClientLocal {
     pub inner: ClientLocal,
     pub chat_input: Option<String>,
+    /// name of the last player that mentioned or whispered to us, used by `/reply`
+    pub last_mention: Option<String>,
+    pub pinged: bool,
+    pub slow_mode_ms: Option<u64>,
+    /// when the last chat message was sent, to show the slow mode countdown
+    pub last_chat_ms: Option<u64>,
+    /// our newest chat message, `/edit` changes it
+    pub last_sent_chat: Option<ChatId>,
+    /// changes to the chat settings entered as commands, applied after rendering
+    pub settings_edits: Vec<SettingsEdit>,
+    pub chalk: Option<ChalkTool>,
+    /// the chat input only takes preset phrases, mirrors the setting
+    pub safe_chat: bool,
+    /// how many ghosts `/ghosts` asked for, spawned on the next update
+    #[cfg(feature = "debug_ghosts")]
+    pub ghost_request: Option<usize>,
+}
+
+/// a change to the chat settings entered in the chat
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsEdit {
+    /// add (`true`) or remove a word from the chat filter with `/filter` and `/unfilter`
+    Filter(String, bool),
+    /// block (`true`) or unblock a player with `/block` and `/unblock`
+    Block(String, bool),
+    /// turn all chat bubbles off (`true`) or back on with `/bubbles off` and `/bubbles on`
+    HideBubbles(bool),
+    /// show the clock with 24 hours (`true`) or 12 with `/clock 24` and `/clock 12`
+    Clock24h(bool),
+    /// only show and send preset phrases (`true`) or chat freely with `/safechat on` and `/safechat off`
+    SafeChat(bool),
+}
+
+impl OwnClientLocal {
+    /// how long the player still has to wait before sending another message
+    fn slow_mode_remaining(&self, time_ms: u64) -> Option<u64> {
+        let ready_at = self.last_chat_ms? + self.slow_mode_ms?;
+        (ready_at > time_ms).then(|| ready_at - time_ms)
+    }
+
+    pub(crate) fn chalk_preview(&self) -> Option<ChalkStroke> {
+        self.chalk.as_ref()?.preview()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ChatMessage {
+    pub id: ChatId,
     pub message: String,
-    pub expiry: u64,
+    pub received_at: u64,
+    /// how long the bubble stays once it is shown
+    pub duration_ms: u64,
+    /// `None` while the bubble is waiting for room
+    pub shown_at: Option<u64>,
+}
+
+impl ChatMessage {
+    /// how far the bubble has shrunk away, `None` if it is still waiting or fully shown
+    fn dismiss_progress(&self, time_ms: u64) -> Option<f32> {
+        let dismissed_at = self.shown_at? + self.duration_ms;
+        (time_ms >= dismissed_at)
+            .then(|| (time_ms - dismissed_at) as f32 / BUBBLE_DISMISS_MS as f32)
+    }
+}
+
+/// chat bubbles of a client, newest at the bottom, and how many are still waiting on top
+fn draw_chat(ui: &mut UIContext, chat: &VecDeque<ChatMessage>, time_ms: u64) {
+    for chat in chat.iter().filter(|chat| chat.shown_at.is_some()).rev() {
+        let bubble = ChatWidget::new(&chat.message);
+        ui.add(match chat.dismiss_progress(time_ms) {
+            Some(progress) => bubble.closing(progress),
+            None => bubble,
+        });
+    }
+
+    let waiting = chat.iter().filter(|chat| chat.shown_at.is_none()).count();
+    if waiting > 0 {
+        ui.label::<font::Glean>(&format!("+{} more", waiting));
+    }
+}
+
+/// name label of a client, blinking while it is flashing. verified players get an `@` in front.
+/// only allocates while it blinks or for verified players
+fn name_label<'a>(client: &'a Client, flash_until: u64, ctx: &RenderContext) -> Cow<'a, str> {
+    let name = if client.verified {
+        Cow::Owned(format!("@{}", client.name()))
+    } else {
+        Cow::Borrowed(client.name())
+    };
+    if flash_until > ctx.time_ms && ctx.anim_frame() % 2 == 0 {
+        Cow::Owned(format!("> {} <", name))
+    } else {
+        name
+    }
+}
+
+/// names that aren't plain ascii are drawn with the fallback fonts, the ui labels only take one
+fn draw_name(ui: &mut UIContext, name: &str) {
+    if name.is_ascii() {
+        ui.label::<font::Glean>(name);
+    } else {
+        ui.add(RichLabel::new(name, NAME_COLOR));
+    }
+}
+
+/// turn chat input starting with `/w name` into a whisper
+fn parse_whisper(chat: &str) -> Option<ClientMessage> {
+    let (Command::Whisper, rest) = parse_command(chat)? else {
+        return None;
+    };
+    let (to, message) = rest.trim_start().split_once(' ')?;
+    let message = message.trim();
+    if message.is_empty() {
+        return None;
+    }
+
+    Some(ClientMessage::Whisper {
+        to: String::from(to),
+        message: String::from(message),
+    })
+}
+
+/// turn chat input starting with `/mail name` into a letter
+fn parse_mail(chat: &str) -> Option<ClientMessage> {
+    let (Command::Mail, rest) = parse_command(chat)? else {
+        return None;
+    };
+    let (to, message) = rest.trim_start().split_once(' ')?;
+    let message = message.trim();
+    if message.is_empty() {
+        return None;
+    }
+
+    Some(ClientMessage::SendMail {
+        to: String::from(to),
+        message: String::from(message),
+    })
+}
+
+/// turn chat input like `/rename name` into a name change, the server checks the name
+fn parse_rename(chat: &str) -> Option<ClientMessage> {
+    let (Command::Rename, name) = parse_command(chat.trim())? else {
+        return None;
+    };
+    let name = name.trim();
+    (!name.is_empty()).then(|| ClientMessage::Rename(String::from(name)))
+}
+
+/// `/staff decorate <object>`, `/staff mute <name>` and `/staff minigame <name|stop>`, the server
+/// checks if the player is actually staff
+fn parse_staff(chat: &str) -> Option<ClientMessage> {
+    let (Command::Staff, rest) = parse_command(chat.trim())? else {
+        return None;
+    };
+    let rest = rest.trim();
+    let (action, argument) = rest.split_once(' ').unwrap_or((rest, ""));
+    let argument = argument.trim();
+    let argument_lower = argument.to_lowercase();
+    match action {
+        "decorate" => PlaceableObject::ALL
+            .into_iter()
+            .find(|object| object.name() == argument_lower)
+            .map(ClientMessage::StaffDecorate),
+        "mute" => (!argument.is_empty()).then(|| ClientMessage::StaffMute(String::from(argument))),
+        "minigame" if argument_lower == "stop" => Some(ClientMessage::StaffMinigame(None)),
+        "minigame" => MinigameKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == argument_lower)
+            .map(|kind| ClientMessage::StaffMinigame(Some(kind))),
+        _ => None,
+    }
+}
+
+/// the new text of `/edit new text`
+fn parse_edit(chat: &str) -> Option<&str> {
+    let (Command::Edit, new_text) = parse_command(chat)? else {
+        return None;
+    };
+    let new_text = new_text.trim();
+    (!new_text.is_empty()).then_some(new_text)
+}
+
+/// `Some(color)` for `/chalk color`, `Some(None)` if no known color was given
+fn parse_chalk(chat: &str) -> Option<Option<ChalkColor>> {
+    let (Command::Chalk, color) = parse_command(chat.trim())? else {
+        return None;
+    };
+    Some(ChalkColor::from_name(&color.trim().to_lowercase()))
+}
+
+/// `/tpa name` asks to teleport to a player, `/tpaccept` lets the last one that asked us in.
+/// `/sethome` and `/home` save a spot and go back to it
+fn parse_teleport(chat: &str) -> Option<ClientMessage> {
+    let (command, argument) = parse_command(chat.trim())?;
+    match command {
+        Command::TeleportAccept => Some(ClientMessage::TeleportAccept),
+        Command::SetHome => Some(ClientMessage::SetHome),
+        Command::Home => Some(ClientMessage::Home),
+        Command::TeleportRequest => {
+            let name = argument.trim();
+            (!name.is_empty()).then(|| ClientMessage::TeleportRequest(String::from(name)))
+        }
+        _ => None,
+    }
+}
+
+/// the number of ghosts in `/ghosts 100`. not in the command list since it's only for testing
+#[cfg(feature = "debug_ghosts")]
+fn parse_ghosts(chat: &str) -> Option<usize> {
+    let count = chat.trim().strip_prefix("/ghosts ")?;
+    count.trim().parse().ok()
+}
+
+/// turn chat input like `/filter word`, `/block name` or `/bubbles off` into a change to the settings
+fn parse_settings_edit(chat: &str) -> Option<SettingsEdit> {
+    let (command, argument) = parse_command(chat)?;
+    let argument = argument.trim();
+    if argument.is_empty() {
+        return None;
+    }
+
+    let argument_lower = argument.to_lowercase();
+    match command {
+        Command::Filter => Some(SettingsEdit::Filter(argument_lower, true)),
+        Command::Unfilter => Some(SettingsEdit::Filter(argument_lower, false)),
+        Command::Block => Some(SettingsEdit::Block(argument_lower, true)),
+        Command::Unblock => Some(SettingsEdit::Block(argument_lower, false)),
+        Command::Bubbles => match argument_lower.as_str() {
+            "off" => Some(SettingsEdit::HideBubbles(true)),
+            "on" => Some(SettingsEdit::HideBubbles(false)),
+            _ => None,
+        },
+        Command::Clock => match argument_lower.as_str() {
+            "24" => Some(SettingsEdit::Clock24h(true)),
+            "12" => Some(SettingsEdit::Clock24h(false)),
+            _ => None,
+        },
+        Command::SafeChat => match argument_lower.as_str() {
+            "on" => Some(SettingsEdit::SafeChat(true)),
+            "off" => Some(SettingsEdit::SafeChat(false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// draw the sprite of a client and their cosmetics, half under water while swimming
+fn draw_cibo(
+    ctx: &mut RenderContext,
+    client: &Client,
+    (image, direction): (&Image, MoveDirection),
+    screen_position: Position,
+) {
+    if !client.swimming {
+        ctx.fb.draw_img(image, screen_position);
+        draw_cosmetics(ctx, client, direction, screen_position);
+        if client.safe_chat {
+            draw_safe_chat_badge(ctx, screen_position);
+        }
+        return;
+    }
+
+    let phase = phase_offset(client.id().as_u32(), BOB.duration_ms());
+    let position = screen_position + Position::new(0, BOB.frame_at(ctx.time_ms + phase));
+    ctx.fb.draw_img(image, position);
+    draw_cosmetics(ctx, client, direction, position);
+    if client.safe_chat {
+        draw_safe_chat_badge(ctx, position);
+    }
+
+    let waterline = position.y + WATERLINE;
+    ctx.fb.draw_rect(
+        Rect::new(
+            Position::new(position.x + 2, waterline),
+            Position::new(position.x + 30, screen_position.y + 32),
+        ),
+        WATER_COLOR,
+    );
+    ctx.fb.draw_rect(
+        Rect::new(
+            Position::new(position.x + 2, waterline),
+            Position::new(position.x + 30, waterline + 1),
+        ),
+        FOAM_COLOR,
+    );
+}
+
+fn draw_cosmetics(
+    ctx: &mut RenderContext,
+    client: &Client,
+    direction: MoveDirection,
+    position: Position,
+) {
+    for cosmetic in &client.cosmetics {
+        if let Some((image, offset)) = assets().cosmetics.get_overlay(*cosmetic, direction) {
+            ctx.fb.draw_img(image, position + offset);
+        }
+    }
+}
+
+/// area the name label and chat bubbles of a client at `screen_position` are laid out in
+fn label_frame_rect(screen_position: Position, placement: LabelPlacement) -> Rect {
+    let position = screen_position + Position::new(0, placement.offset);
+    Rect::new(
+        Position::new(position.x - 30, -i64::MAX),
+        Position::new(position.x + 30 + 32, position.y + 45),
+    )
+}
+
+/// draw labels see-through if they were faded to make room for others
+fn draw_label(
+    ctx: &mut RenderContext,
+    area: Rect,
+    placement: LabelPlacement,
+    draw: impl FnOnce(&mut RenderContext),
+) {
+    if placement.alpha == u8::MAX {
+        draw(ctx);
+    } else {
+        ctx.draw_ghost(area, placement.alpha, None, draw);
+    }
+}
+
+fn draw_safe_chat_badge(ctx: &mut RenderContext, screen_position: Position) {
+    // next to the top right corner of the sprite, out of the way of the cosmetics
+    let top_left = screen_position + Position::new(26, -4);
+    for (row, line) in SAFE_CHAT_BADGE.iter().enumerate() {
+        for (column, pixel) in line.chars().enumerate() {
+            if pixel == '#' {
+                let pos = top_left + Position::new(column as i64, row as i64);
+                ctx.fb
+                    .draw_rect(Rect::new(pos, pos + Position::new(1, 1)), SAFE_CHAT_COLOR);
+            }
+        }
+    }
+}
+
+/// draw a highlight under the feet of a client, e.g. to mark who is "it" in tag.
+fn draw_glow(ctx: &mut RenderContext, screen_position: Position) {
+    let center_x = screen_position.x + 16;
+    let top = screen_position.y + 26;
+    for (row, half_width) in [8, 12, 14, 14, 12, 8].iter().enumerate() {
+        let y = top + row as i64;
+        ctx.fb.draw_rect(
+            Rect::new(
+                Position::new(center_x - half_width, y),
+                Position::new(center_x + half_width, y + 1),
+            ),
+            ctx.palette.highlight,
+        );
+    }
 }
 
 impl Renderable for Client {
     type LocalState = ClientLocal;
     fn render(&mut self, state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
         let screen_position = self.position - camera;
+
+        if self.glow {
+            draw_glow(ctx, screen_position);
+        }
+
+        let image = state.image(self, ctx);
+        draw_cibo(ctx, self, image, screen_position);
+    }
+}
+
+impl Client {
+    /// draw the name label and chat bubbles. happens after all sprites, so they don't get covered
+    pub(crate) fn render_labels(
+        &self,
+        state: &mut ClientLocal,
+        camera: Position,
+        placement: LabelPlacement,
+        ctx: &mut RenderContext,
+    ) {
         let anim_frame = ctx.anim_frame();
+        let ui_rect = label_frame_rect(self.position - camera, placement);
+        state.update_chat(ctx.time_ms);
 
-        ctx.fb.draw_img(
-            assets().cibo.get_client_image(self, anim_frame),
-            screen_position,
-        );
+        let label = name_label(self, state.flash_until, ctx);
+        draw_label(ctx, ui_rect, placement, |ctx| {
+            state.ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
+                ui.margin(MarginMode::Grow);
+                draw_name(ui, &label);
 
-        let ui_rect = Rect::new(
-            Position::new(screen_position.x - 30, -i64::MAX),
-            Position::new(screen_position.x + 30 + 32, screen_position.y + 45),
-        );
+                ui.alloc_space(Dimension::new(0, 26));
 
-        state.ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
-            ui.margin(MarginMode::Grow);
-            ui.label::<font::Glean>(&self.name());
-
-            ui.alloc_space(Dimension::new(0, 26));
-
-            if self.typing {
-                let type_text = match anim_frame % 3 {
-                    0 => ".",
-                    1 => "..",
-                    2 => "...",
-                    _ => unreachable!(),
-                };
-                ui.add(ChatWidget::with_id(
-                    type_text,
-                    &format!("t_{}", self.id.as_u32()),
-                ));
-            }
+                if self.typing {
+                    let type_text = match anim_frame % 3 {
+                        0 => ".",
+                        1 => "..",
+                        2 => "...",
+                        _ => unreachable!(),
+                    };
+                    ui.add(ChatWidget::with_id(
+                        type_text,
+                        &format!("t_{}", self.id.as_u32()),
+                    ));
+                }
 
-            state.chat.retain(|chat| chat.expiry > ctx.time_ms);
-            for chat in state.chat.iter().rev().take(3) {
-                ui.add(ChatWidget::new(&chat.message));
-            }
-        })
+                draw_chat(ui, &state.chat, ctx.time_ms);
+            })
+        });
     }
 }
 
@@ -90,39 +583,178 @@ impl Renderable for OwnClient<'_> {
     fn render(&mut self, state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
         let screen_position = self.0.position - camera;
 
-        ctx.fb.draw_img(
-            assets().cibo.get_client_image(self.0, ctx.anim_frame()),
-            screen_position,
-        );
+        if self.0.glow {
+            draw_glow(ctx, screen_position);
+        }
 
-        let ui_rect = Rect::new(
-            Position::new(screen_position.x - 30, -i64::MAX),
-            Position::new(screen_position.x + 30 + 32, screen_position.y + 45),
-        );
+        let image = state.inner.image(self.0, ctx);
+        draw_cibo(ctx, self.0, image, screen_position);
+    }
+}
+
+impl OwnClient<'_> {
+    /// draw the name label, chat bubbles and chat input. happens after all sprites, so they don't get covered
+    pub(crate) fn render_labels(
+        &mut self,
+        state: &mut OwnClientLocal,
+        camera: Position,
+        placement: LabelPlacement,
+        ctx: &mut RenderContext,
+    ) {
+        let ui_rect = label_frame_rect(self.0.position - camera, placement);
+        state.inner.update_chat(ctx.time_ms);
+
+        // complete emoji shortcodes with tab
+        let mut suggestions = Vec::new();
+        if let Some(chat) = &mut state.chat_input {
+            if chat.ends_with(' ') && parse_command(chat) == Some((Command::Reply, "")) {
+                if let Some(name) = &state.last_mention {
+                    *chat = format!("/w {} ", name);
+                }
+            }
+
+            if let Some(partial) = partial_shortcode(chat) {
+                suggestions.extend(emoji_suggestions(partial));
+            }
+
+            if ctx.input.key_pressed(Key::RawKey(RawKey::Tab)) {
+                if let Some(name) = suggestions.first() {
+                    let partial_len = partial_shortcode(chat).map_or(0, |partial| partial.len());
+                    let completed_len = chat.len() - partial_len + name.len() + 2;
+                    if completed_len <= crate::MESSAGE_LIMIT {
+                        chat.truncate(chat.len() - partial_len);
+                        chat.push_str(name);
+                        chat.push_str(": ");
+                        suggestions.clear();
+                    }
+                }
+            }
+            ctx.input
+                .keyboard
+                .retain(|k| k.key != Key::RawKey(RawKey::Tab));
+        }
 
-        state.inner.ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
-            ui.margin(MarginMode::Grow);
-            ui.label::<font::Glean>(&self.0.name());
+        let label = name_label(self.0, state.inner.flash_until, ctx);
+        let slow_mode_remaining = state.slow_mode_remaining(ctx.time_ms);
+        let safe_chat = state.safe_chat;
+        draw_label(ctx, ui_rect, placement, |ctx| {
+            state.inner.ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
+                ui.margin(MarginMode::Grow);
+                draw_name(ui, &label);
 
-            ui.alloc_space(Dimension::new(0, 26));
+                ui.alloc_space(Dimension::new(0, 26));
 
-            if let Some(chat) = &mut state.chat_input {
-                let textbox = widgets::Textbox::<font::Glean>::new(chat)
-                    .wrap(TextWrap::Enabled { hyphenate: false })
-                    .char_limit(crate::MESSAGE_LIMIT);
-                if ui.add(textbox).submitted {
-                    if !chat.is_empty() {
-                        (ctx.send_msg)(ClientMessage::Chat(chat.clone()));
+                if let Some(chat) = &mut state.chat_input {
+                    let textbox = widgets::Textbox::<font::Glean>::new(chat)
+                        .wrap(TextWrap::Enabled { hyphenate: false })
+                        .char_limit(crate::MESSAGE_LIMIT);
+                    let submitted = ui.add(textbox).submitted;
+                    // sending the cleared input just closes it
+                    #[cfg(feature = "debug_ghosts")]
+                    if let Some(count) = parse_ghosts(chat).filter(|_| submitted) {
+                        state.ghost_request = Some(count);
+                        chat.clear();
                     }
+                    if let Some(edit) = parse_settings_edit(chat).filter(|_| submitted) {
+                        // only changes the players settings, so slow mode doesn't apply
+                        state.settings_edits.push(edit);
+                        state.chat_input = None;
+                    } else if submitted
+                        && parse_command(chat.trim()) == Some((Command::Register, ""))
+                    {
+                        (ctx.send_msg)(ClientMessage::Register);
+                        state.chat_input = None;
+                    } else if submitted && parse_command(chat.trim()) == Some((Command::Stats, ""))
+                    {
+                        (ctx.send_msg)(ClientMessage::RequestStats);
+                        state.chat_input = None;
+                    } else if submitted
+                        && parse_command(chat.trim()) == Some((Command::Transfer, ""))
+                    {
+                        (ctx.send_msg)(ClientMessage::RequestTransfer);
+                        state.chat_input = None;
+                    } else if let Some(color) = parse_chalk(chat).filter(|_| submitted) {
+                        state.chalk = match (color, &state.chalk) {
+                            (None, Some(_)) => None,
+                            (color, _) => Some(ChalkTool::new(color.unwrap_or(ChalkColor::White))),
+                        };
+                        state.chat_input = None;
+                    } else if let Some(new_text) =
+                        parse_edit(chat).filter(|_| submitted && !safe_chat)
+                    {
+                        // the server checks if the message can still be edited
+                        if let Some(message_id) = state.last_sent_chat {
+                            (ctx.send_msg)(ClientMessage::EditChat {
+                                message_id,
+                                new_text: String::from(new_text),
+                            });
+                        }
+                        state.chat_input = None;
+                    } else if let Some(teleport) = parse_teleport(chat).filter(|_| submitted) {
+                        // the server has its own cooldown for these
+                        (ctx.send_msg)(teleport);
+                        state.chat_input = None;
+                    } else if let Some(rename) = parse_rename(chat).filter(|_| submitted) {
+                        (ctx.send_msg)(rename);
+                        state.chat_input = None;
+                    } else if let Some(staff) = parse_staff(chat).filter(|_| submitted) {
+                        (ctx.send_msg)(staff);
+                        state.chat_input = None;
+                    } else if let Some(remaining) = slow_mode_remaining {
+                        // keep the message around until it can be sent
+                        ui.label::<font::Glean>(&format!(
+                            "slow mode: {}s",
+                            remaining.div_ceil(1000)
+                        ));
+                    } else if submitted && safe_chat {
+                        // nothing typed goes out, only the phrase it picks by number or matches
+                        let chat = chat.trim();
+                        let phrase = chat
+                            .parse()
+                            .ok()
+                            .and_then(PhraseId::from_number)
+                            .or_else(|| PhraseId::find(chat));
+                        if let Some(phrase) = phrase {
+                            (ctx.send_msg)(ClientMessage::QuickChat(phrase));
+                            state.last_chat_ms = Some(ctx.time_ms);
+                        }
 
-                    state.chat_input = None;
+                        state.chat_input = None;
+                    } else if submitted {
+                        let message =
+                            parse_whisper(chat)
+                                .or_else(|| parse_mail(chat))
+                                .or_else(|| {
+                                    (!chat.is_empty()).then(|| ClientMessage::Chat(chat.clone()))
+                                });
+                        if let Some(message) = message {
+                            (ctx.send_msg)(message);
+                            state.last_chat_ms = Some(ctx.time_ms);
+                        }
+
+                        state.chat_input = None;
+                    }
                 }
-            }
 
-            state.inner.chat.retain(|chat| chat.expiry > ctx.time_ms);
-            for chat in state.inner.chat.iter().rev().take(3) {
-                ui.add(ChatWidget::new(&chat.message));
-            }
-        })
+                if safe_chat && state.chat_input.is_some() {
+                    let phrases = PHRASES
+                        .iter()
+                        .enumerate()
+                        .map(|(index, phrase)| format!("{} {}", index + 1, phrase))
+                        .collect::<Vec<_>>();
+                    ui.add(RichLabel::new(&phrases.join("  "), NAME_COLOR));
+                }
+
+                if !suggestions.is_empty() {
+                    let suggestions = suggestions
+                        .iter()
+                        .map(|name| format!(":{}:", name))
+                        .collect::<Vec<_>>();
+                    ui.label::<font::Glean>(&format!("tab: {}", suggestions.join(" ")));
+                }
+
+                draw_chat(ui, &state.inner.chat, ctx.time_ms);
+            })
+        });
     }
 }
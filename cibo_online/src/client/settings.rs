@@ -0,0 +1,246 @@
+use super::PhraseId;
+use crate::ColorMode;
+use alloc::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+const CHAT_FILTER_LIMIT: usize = 32;
+const CHAT_FILTER_WORD_LIMIT: usize = 32;
+const BLOCKED_PLAYERS_LIMIT: usize = 64;
+/// length of the random key a client generates to identify its account
+pub const ACCOUNT_KEY_LENGTH: usize = 32;
+
+/// what to do with name labels and chat bubbles of players standing close together
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LabelLayout {
+    /// move overlapping labels out of the way, fade them if there is no room left
+    #[default]
+    Stagger,
+    /// fade overlapping labels of less important players
+    Fade,
+    /// draw every label where it belongs
+    Overlap,
+}
+
+impl LabelLayout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LabelLayout::Stagger => "stagger",
+            LabelLayout::Fade => "fade",
+            LabelLayout::Overlap => "overlap",
+        }
+    }
+
+    /// the layout after this one, for cycling through them with a single key
+    pub fn next(&self) -> Self {
+        match self {
+            LabelLayout::Stagger => LabelLayout::Fade,
+            LabelLayout::Fade => LabelLayout::Overlap,
+            LabelLayout::Overlap => LabelLayout::Stagger,
+        }
+    }
+}
+
+/// preferences that follow the player across devices. the server keeps them under the players account key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSettings {
+    pub color_mode: ColorMode,
+    pub ping_sound: bool,
+    /// cut to camera hints instead of panning and don't flash names on mentions
+    pub reduced_motion: bool,
+    /// chat messages from other players containing any of these words are hidden
+    pub chat_filter: Vec<String>,
+    pub label_layout: LabelLayout,
+    /// names of players whose messages are hidden, in lowercase
+    pub blocked_players: Vec<String>,
+    /// don't show any chat bubbles, messages only go to the chat log
+    pub hide_bubbles: bool,
+    /// show the time of day as 14:30 instead of 2:30 pm
+    pub clock_24h: bool,
+    /// only show and send preset phrases, for younger players
+    pub safe_chat: bool,
+}
+
+/// settings as they were stored before the label layout was added
+#[derive(Deserialize)]
+struct PlayerSettingsV1 {
+    color_mode: ColorMode,
+    ping_sound: bool,
+    reduced_motion: bool,
+    chat_filter: Vec<String>,
+}
+
+impl From<PlayerSettingsV1> for PlayerSettings {
+    fn from(settings: PlayerSettingsV1) -> Self {
+        PlayerSettings {
+            color_mode: settings.color_mode,
+            ping_sound: settings.ping_sound,
+            reduced_motion: settings.reduced_motion,
+            chat_filter: settings.chat_filter,
+            ..Default::default()
+        }
+    }
+}
+
+/// settings as they were stored before the blocklist was added
+#[derive(Deserialize)]
+struct PlayerSettingsV2 {
+    color_mode: ColorMode,
+    ping_sound: bool,
+    reduced_motion: bool,
+    chat_filter: Vec<String>,
+    label_layout: LabelLayout,
+}
+
+impl From<PlayerSettingsV2> for PlayerSettings {
+    fn from(settings: PlayerSettingsV2) -> Self {
+        PlayerSettings {
+            color_mode: settings.color_mode,
+            ping_sound: settings.ping_sound,
+            reduced_motion: settings.reduced_motion,
+            chat_filter: settings.chat_filter,
+            label_layout: settings.label_layout,
+            ..Default::default()
+        }
+    }
+}
+
+/// settings as they were stored before the clock format was added
+#[derive(Deserialize)]
+struct PlayerSettingsV3 {
+    color_mode: ColorMode,
+    ping_sound: bool,
+    reduced_motion: bool,
+    chat_filter: Vec<String>,
+    label_layout: LabelLayout,
+    blocked_players: Vec<String>,
+    hide_bubbles: bool,
+}
+
+impl From<PlayerSettingsV3> for PlayerSettings {
+    fn from(settings: PlayerSettingsV3) -> Self {
+        PlayerSettings {
+            color_mode: settings.color_mode,
+            ping_sound: settings.ping_sound,
+            reduced_motion: settings.reduced_motion,
+            chat_filter: settings.chat_filter,
+            label_layout: settings.label_layout,
+            blocked_players: settings.blocked_players,
+            hide_bubbles: settings.hide_bubbles,
+            ..Default::default()
+        }
+    }
+}
+
+/// settings as they were stored before safe chat was added
+#[derive(Deserialize)]
+struct PlayerSettingsV4 {
+    color_mode: ColorMode,
+    ping_sound: bool,
+    reduced_motion: bool,
+    chat_filter: Vec<String>,
+    label_layout: LabelLayout,
+    blocked_players: Vec<String>,
+    hide_bubbles: bool,
+    clock_24h: bool,
+}
+
+impl From<PlayerSettingsV4> for PlayerSettings {
+    fn from(settings: PlayerSettingsV4) -> Self {
+        PlayerSettings {
+            color_mode: settings.color_mode,
+            ping_sound: settings.ping_sound,
+            reduced_motion: settings.reduced_motion,
+            chat_filter: settings.chat_filter,
+            label_layout: settings.label_layout,
+            blocked_players: settings.blocked_players,
+            hide_bubbles: settings.hide_bubbles,
+            clock_24h: settings.clock_24h,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        PlayerSettings {
+            color_mode: ColorMode::default(),
+            ping_sound: true,
+            reduced_motion: false,
+            chat_filter: Vec::new(),
+            label_layout: LabelLayout::default(),
+            blocked_players: Vec::new(),
+            hide_bubbles: false,
+            clock_24h: true,
+            safe_chat: false,
+        }
+    }
+}
+
+impl PlayerSettings {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
+        postcard::to_allocvec(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, postcard::Error> {
+        postcard::from_bytes(bytes).or_else(|err| {
+            postcard::from_bytes::<PlayerSettingsV4>(bytes)
+                .map(PlayerSettings::from)
+                .or_else(|_| {
+                    postcard::from_bytes::<PlayerSettingsV3>(bytes).map(PlayerSettings::from)
+                })
+                .or_else(|_| {
+                    postcard::from_bytes::<PlayerSettingsV2>(bytes).map(PlayerSettings::from)
+                })
+                .or_else(|_| {
+                    postcard::from_bytes::<PlayerSettingsV1>(bytes).map(PlayerSettings::from)
+                })
+                .map_err(|_| err)
+        })
+    }
+
+    /// keep the filter list small, since the server stores it for every account
+    pub fn clamp(&mut self) {
+        for word in &mut self.chat_filter {
+            *word = word.trim().to_lowercase();
+            if let Some((end, _)) = word.char_indices().nth(CHAT_FILTER_WORD_LIMIT) {
+                word.truncate(end);
+            }
+        }
+        self.chat_filter.retain(|word| !word.is_empty());
+        self.chat_filter.truncate(CHAT_FILTER_LIMIT);
+
+        for name in &mut self.blocked_players {
+            *name = name.trim().to_lowercase();
+            if let Some((end, _)) = name.char_indices().nth(crate::NAME_LIMIT) {
+                name.truncate(end);
+            }
+        }
+        self.blocked_players.retain(|name| !name.is_empty());
+        self.blocked_players.truncate(BLOCKED_PLAYERS_LIMIT);
+    }
+
+    pub fn blocks(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.blocked_players.iter().any(|blocked| *blocked == name)
+    }
+
+    /// whether a message from another player is hidden, in safe chat everything but the preset
+    /// phrases is
+    pub fn filters(&self, message: &str) -> bool {
+        if self.safe_chat && PhraseId::find(message).is_none() {
+            return true;
+        }
+        if self.chat_filter.is_empty() {
+            return false;
+        }
+        let message = message.to_lowercase();
+        self.chat_filter
+            .iter()
+            .any(|word| message.contains(word.as_str()))
+    }
+}
+
+/// whether a key sent by a client looks like one the client would generate
+pub fn valid_account_key(key: &str) -> bool {
+    key.len() == ACCOUNT_KEY_LENGTH && key.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
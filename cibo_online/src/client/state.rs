@@ -1,22 +1,66 @@
 use crate::{
-    server::ServerMessage, CollisionInfo, CollisionTester, Object, RenderContext, Renderable,
-    WorldLocalState, WorldState,
+    debug::WorldSnapshot,
+    render::{push_int, DebugOverlay},
+    server::{ChatId, Letter, ServerMessage, TickerKind},
+    vec_from_f32,
+    world::{
+        objects::{format_countdown, MessageBoard, ScheduledEvent},
+        water, wildlife,
+    },
+    CiboError, CollisionInfo, CollisionTester, Critter, HideAndSeekPhase, Minigame, Object,
+    RenderContext, Renderable, WorldLocalState, WorldState,
 };
 
-use super::{Client, ClientAction, ClientId, ClientMessage, MoveDirection};
-use alloc::{boxed::Box, collections::VecDeque, format, string::String};
+use super::{
+    controls::{self, Action},
+    editor::EditorState,
+    effects::Effect,
+    events::{ClientEvent, EventBus},
+    hud::{HudEditor, HudLayout},
+    latency::LatencyTracer,
+    panels::{PanelContext, Panels},
+    render::SettingsEdit,
+    transfer::TransferPanel,
+    tutorial::{Tutorial, TutorialEvent, TutorialStep},
+    Client, ClientAction, ClientId, ClientMessage, LatencyStats, MoveDirection, PhraseId,
+    PlayStats, PlayerSettings,
+};
+use alloc::{boxed::Box, format, string::String, vec::Vec};
 #[allow(unused_imports)]
 use micromath::F32Ext;
 use monos_gfx::{
     input::{Input, Key, KeyState, RawKey},
-    text::{font, Origin, TextWrap},
-    ui::{widgets, Direction, MarginMode, UIFrame},
-    Edge, Framebuffer, Position, Rect,
+    text::font,
+    ui::{Direction, MarginMode, UIFrame},
+    Color, Edge, Framebuffer, Position, Rect,
 };
 use serde::{Deserialize, Serialize};
 
-const CAMERA_EDGE_X: i64 = 100;
-const CAMERA_EDGE_Y: i64 = 50;
+#[cfg(feature = "debug_ghosts")]
+mod ghosts;
+
+const SEEKER_VIEW_RADIUS: i64 = 56;
+
+const MENTION_FLASH_MS: u64 = 2000;
+
+/// how far away other players are still mentioned in the accessibility text, in world pixels
+const ACCESSIBILITY_NEARBY_RADIUS: i64 = 160;
+const ACCESSIBILITY_CHAT_LINES: usize = 3;
+
+/// how long the camera takes to pan to a camera hint and back
+const CAMERA_PAN_MS: u64 = 1500;
+
+/// width of each column of the help window
+const HELP_WIDTH: i64 = 230;
+const HELP_BG: Color = Color::new(30, 30, 40);
+const STATS_WIDTH: i64 = 100;
+const LETTERS_WIDTH: i64 = 160;
+
+/// height of each line of the clock below the coordinates
+const CLOCK_LINE_HEIGHT: i64 = 14;
+
+const CLOCK_SYNC_SAMPLES: usize = 5;
+const CLOCK_SYNC_INTERVAL: u64 = 500;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClientGameState {
@@ -41,10 +85,45 @@ pub struct ClientLocalState {
     time_ms: u64,
     last_tick: u64,
     last_message: u64,
+    /// actions of the frames since the last one was sent, combined into one
+    unsent_action: Option<ClientAction>,
+    last_action_sent: u64,
+    clock: ClockSync,
+    latency: LatencyTracer,
+    editor: Option<EditorState>,
+
+    settings: PlayerSettings,
+    /// changed by the player and not sent to the server yet
+    settings_changed: bool,
+    /// safe chat was turned on when the game was opened and can't be turned off in game
+    safe_chat_locked: bool,
+
+    /// when the time of a player without an account runs out, in local time
+    guest_until: Option<u64>,
+
+    /// the player that wants to teleport to us and until when they can be let in, in local time
+    teleport_request: Option<(ClientId, u64)>,
+    /// from the last `TimeSync`, `None` until the server sent one
+    world_clock: Option<WorldClock>,
+
+    tutorial: Tutorial,
 
     render: RenderState,
+    events: EventBus,
+    panels: Panels,
 
     world: WorldLocalState,
+
+    #[cfg(feature = "debug_ghosts")]
+    ghosts: ghosts::Ghosts,
+}
+
+#[derive(Debug)]
+struct WorldClock {
+    server_time: u64,
+    /// unix time in milliseconds at `server_time`
+    unix_ms: u64,
+    next_event: Option<ScheduledEvent>,
 }
 
 #[derive(Debug, Clone)]
@@ -52,12 +131,73 @@ struct RenderState {
     stream_mode: bool,
 
     camera: Position,
-
-    chat_log: VecDeque<String>,
-    chat_log_ui: UIFrame,
+    /// follow camera hints from the server, e.g. during live events
+    event_mode: bool,
+    camera_pan: Option<CameraPan>,
 
     coordinate_ui: UIFrame,
-    player_list_ui: Option<UIFrame>,
+    /// reused every frame instead of formatting a new string
+    coordinate_text: String,
+    clock_ui: UIFrame,
+
+    effects: Vec<Effect>,
+
+    /// created from the screen size on the first render unless a saved layout was set
+    hud: Option<HudLayout>,
+    hud_editor: HudEditor,
+    hud_changed: bool,
+
+    help_open: bool,
+    /// shown once the server answered `/stats`
+    play_stats: Option<PlayStats>,
+    /// letters that were waiting for the player, shown until closed
+    letters: Vec<Letter>,
+    /// shown after `/transfer` until closed
+    transfer: Option<TransferPanel>,
+
+    debug: DebugOverlay,
+}
+
+/// smooth camera move to a spot the server pointed out
+#[derive(Debug, Clone)]
+struct CameraPan {
+    target: Position,
+    started_at: u64,
+    /// when the camera starts moving back to the player
+    ends_at: u64,
+}
+
+impl CameraPan {
+    /// how far the camera has moved from the player to the target, from 0 to 1
+    fn weight(&self, time_ms: u64) -> f32 {
+        let pan_in = time_ms.saturating_sub(self.started_at) as f32 / CAMERA_PAN_MS as f32;
+        let pan_out =
+            (self.ends_at + CAMERA_PAN_MS).saturating_sub(time_ms) as f32 / CAMERA_PAN_MS as f32;
+        let t = pan_in.min(pan_out).min(1.0);
+        // smoothstep, so the camera eases in and out
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    fn is_done(&self, time_ms: u64) -> bool {
+        time_ms >= self.ends_at + CAMERA_PAN_MS
+    }
+
+    /// start moving back to the player right away, from wherever the camera currently is
+    fn cancel(&mut self, time_ms: u64) {
+        let shown = time_ms.saturating_sub(self.started_at).min(CAMERA_PAN_MS);
+        self.ends_at = self
+            .ends_at
+            .min((time_ms + shown).saturating_sub(CAMERA_PAN_MS));
+    }
+}
+
+/// estimate of the offset between the local clock and the server clock
+#[derive(Debug, Default)]
+struct ClockSync {
+    offset: i64,
+    best_rtt: Option<u64>,
+    samples: usize,
+    last_ping: Option<u64>,
 }
 
 impl Default for RenderState {
@@ -65,10 +205,20 @@ impl Default for RenderState {
         RenderState {
             stream_mode: false,
             camera: Position::new(0, 0),
-            chat_log: VecDeque::new(),
-            chat_log_ui: UIFrame::new(Direction::BottomToTop),
+            event_mode: false,
+            camera_pan: None,
             coordinate_ui: UIFrame::new_stateless(Direction::RightToLeft),
-            player_list_ui: None,
+            coordinate_text: String::new(),
+            clock_ui: UIFrame::new_stateless(Direction::TopToBottom),
+            effects: Vec::new(),
+            hud: None,
+            hud_editor: HudEditor::default(),
+            hud_changed: false,
+            help_open: false,
+            play_stats: None,
+            letters: Vec::new(),
+            transfer: None,
+            debug: DebugOverlay::default(),
         }
     }
 }
@@ -79,25 +229,365 @@ impl ClientLocalState {
             time_ms: 0,
             last_tick: 0,
             last_message: 0,
+            unsent_action: None,
+            last_action_sent: 0,
+            clock: ClockSync::default(),
+            latency: LatencyTracer::default(),
+            editor: None,
+
+            settings: PlayerSettings::default(),
+            settings_changed: false,
+            safe_chat_locked: false,
+
+            guest_until: None,
+
+            teleport_request: None,
+            world_clock: None,
+
+            tutorial: Tutorial::default(),
 
             render: Default::default(),
+            events: EventBus::default(),
+            panels: Panels::default(),
 
             world: WorldLocalState::new(own_id),
+
+            #[cfg(feature = "debug_ghosts")]
+            ghosts: Default::default(),
         }
     }
+
+    /// the current time in server clock time
+    fn server_time(&self) -> u64 {
+        (self.time_ms as i64 + self.clock.offset).max(0) as u64
+    }
+
+    /// queue an event for the panels, they see it on the next `dispatch_events`
+    fn emit(&mut self, event: ClientEvent) {
+        self.events.emit(event);
+    }
+
+    /// add a line that isn't a chat message to the chat log
+    fn log(&mut self, text: String, highlight: bool) {
+        self.emit(ClientEvent::Log { text, highlight });
+    }
+
+    fn dispatch_events(&mut self) {
+        self.events.dispatch(&mut self.panels.all(), self.time_ms);
+    }
 }
 
 impl ClientGameState {
-    pub(crate) fn new(client_id: ClientId, mut world: WorldState) -> Self {
-        let own_client = world.clients.iter().position(|c| c.id() == client_id);
-        world.clients.swap(0, own_client.unwrap());
+    /// whether someone mentioned or whispered to the player since the last call, e.g. to play a sound
+    pub fn take_ping(&mut self) -> bool {
+        core::mem::take(&mut self.local().world.own_local.borrow_mut().pinged)
+    }
 
-        ClientGameState {
+    /// critters that called out near the player since the last call and how loud, from 0 to 1
+    pub fn take_ambient_sounds(&mut self) -> Vec<(Critter, f32)> {
+        let position = self.client().position;
+        let Some(local) = self.local.as_mut() else {
+            return Vec::new();
+        };
+        let now = local.server_time();
+        let from = core::mem::replace(&mut local.world.ambience_heard_until, now);
+        if from == 0 || local.render.stream_mode {
+            return Vec::new();
+        }
+        wildlife::calls(
+            self.world.ambient_seed,
+            &self.world.special_events,
+            position,
+            from,
+            now,
+        )
+    }
+
+    /// plain text summary of what is on screen, e.g. for a screen reader
+    pub fn accessibility_text(&self) -> String {
+        let position = self.client().position;
+        let tile_position = position / 16;
+        let mut text = format!("you are at X{} / Y{}.", tile_position.x, tile_position.y);
+
+        let nearby: Vec<&str> = self
+            .world
+            .clients
+            .iter()
+            .skip(1)
+            .filter(|client| {
+                let offset = client.position - position;
+                offset.x.abs() <= ACCESSIBILITY_NEARBY_RADIUS
+                    && offset.y.abs() <= ACCESSIBILITY_NEARBY_RADIUS
+            })
+            .map(|client| client.name())
+            .collect();
+        if nearby.is_empty() {
+            text.push_str(" nobody is nearby.");
+        } else {
+            text.push_str(&format!(" nearby: {}.", nearby.join(", ")));
+        }
+
+        if let Some(local) = &self.local {
+            let chat_log = local.panels.chat_log.lines();
+            let skip = chat_log.len().saturating_sub(ACCESSIBILITY_CHAT_LINES);
+            for line in chat_log.skip(skip) {
+                text.push('\n');
+                text.push_str(&line.text);
+            }
+        }
+
+        text
+    }
+
+    pub fn settings(&self) -> PlayerSettings {
+        self.local
+            .as_ref()
+            .map_or_else(PlayerSettings::default, |local| local.settings.clone())
+    }
+
+    /// change the players settings and sync them to the server
+    pub fn set_settings(&mut self, settings: PlayerSettings) {
+        self.prepare_local();
+        let local = self.local_mut();
+        local.settings = settings;
+        local.settings_changed = true;
+        self.sync_safe_chat();
+    }
+
+    /// use settings saved on this device until the server sends the stored ones
+    pub fn restore_settings(&mut self, settings: PlayerSettings) {
+        self.prepare_local();
+        self.local_mut().settings = settings;
+        self.sync_safe_chat();
+    }
+
+    /// keep safe chat on for the rest of the session, e.g. when the game was opened for a child
+    pub fn lock_safe_chat(&mut self) {
+        self.prepare_local();
+        self.local_mut().safe_chat_locked = true;
+        self.sync_safe_chat();
+    }
+
+    /// turn safe chat back on if the settings changed while it is locked, and let the chat input
+    /// know whether it only takes preset phrases
+    fn sync_safe_chat(&mut self) {
+        let local = self.local_mut();
+        if local.safe_chat_locked && !local.settings.safe_chat {
+            local.settings.safe_chat = true;
+            local.settings_changed = true;
+        }
+        let turned_on = local.settings.safe_chat && !local.world.own_local.borrow().safe_chat;
+        if turned_on {
+            // bubbles from before safe chat was turned on could say anything
+            local.world.clear_chat(None);
+        }
+        local.world.own_local.borrow_mut().safe_chat = local.settings.safe_chat;
+    }
+
+    /// use a previously saved hud layout
+    pub fn set_hud_layout(&mut self, layout: HudLayout) {
+        self.prepare_local();
+        self.local_mut().render.hud = Some(layout);
+    }
+
+    /// the hud layout if the player changed it since the last call, e.g. to save it
+    pub fn take_hud_layout_changed(&mut self) -> Option<HudLayout> {
+        let render = &mut self.local.as_mut()?.render;
+        if !core::mem::take(&mut render.hud_changed) {
+            return None;
+        }
+        render.hud.clone()
+    }
+
+    /// a link the player clicked in the chat log since the last call, to open it in a new tab
+    pub fn take_clicked_link(&mut self) -> Option<String> {
+        self.local.as_mut()?.panels.chat_log.clicked_link.take()
+    }
+
+    /// how long our actions took to show up, split by stage. `None` before the game was rendered
+    pub fn latency(&self) -> Option<&LatencyStats> {
+        self.local.as_ref().map(|local| local.latency.stats())
+    }
+
+    /// show hitboxes, bounds and z order on top of the world, like ctrl+b does
+    pub fn set_debug_overlay(&mut self, enabled: bool) {
+        self.prepare_local();
+        self.local_mut().render.debug.enabled = enabled;
+    }
+
+    /// continue the tutorial from a previous session
+    pub fn set_tutorial_step(&mut self, step: TutorialStep) {
+        self.prepare_local();
+        self.local_mut().tutorial.restore(step);
+    }
+
+    /// the tutorial step if the player got further since the last call, e.g. to save it
+    pub fn take_tutorial_changed(&mut self) -> Option<TutorialStep> {
+        self.local.as_mut()?.tutorial.take_changed()
+    }
+
+    /// what the player did this frame that could move the tutorial along
+    fn tutorial_events(&self, moved: bool) -> Vec<TutorialEvent> {
+        let mut events = Vec::new();
+        if moved {
+            events.push(TutorialEvent::Moved);
+        }
+        if self.local().world.own_local.borrow().chat_input.is_some() {
+            events.push(TutorialEvent::OpenedChat);
+        }
+        let reading = self.world.network_objects.values().any(|object| {
+            object
+                .downcast_ref::<MessageBoard>()
+                .is_some_and(|board| board.is_open())
+        });
+        if reading {
+            events.push(TutorialEvent::ReadMessageBoard);
+        }
+        let feet = self.client().feet();
+        if feet.y >= water::beach_y(feet.x) {
+            events.push(TutorialEvent::ReachedBeach);
+        }
+        events
+    }
+
+    /// where the arrow of the current tutorial step points to, in world space
+    fn tutorial_target(&self) -> Option<Position> {
+        let position = self.client().position;
+        match self.local().tutorial.step() {
+            TutorialStep::MessageBoard => self
+                .world
+                .network_objects
+                .values()
+                .filter(|object| object.downcast_ref::<MessageBoard>().is_some())
+                .map(|object| object.properties().position)
+                .min_by_key(|target| {
+                    let offset = *target - position;
+                    offset.x.abs() + offset.y.abs()
+                }),
+            TutorialStep::Beach => Some(Position::new(position.x, water::beach_y(position.x))),
+            _ => None,
+        }
+    }
+
+    /// apply the chat settings commands like `/filter` or `/block` the player entered in the chat.
+    /// all of this stays on this client, the server only stores the resulting settings
+    fn apply_settings_edits(&mut self, send_msg: &mut dyn FnMut(ClientMessage)) {
+        let edits = core::mem::take(&mut self.local().world.own_local.borrow_mut().settings_edits);
+        if edits.is_empty() {
+            return;
+        }
+
+        let mut settings = self.settings();
+        for edit in edits {
+            let line = match edit {
+                SettingsEdit::Filter(word, add) => {
+                    settings.chat_filter.retain(|filtered| *filtered != word);
+                    if add {
+                        let line = format!("* hiding messages containing '{}'", word);
+                        settings.chat_filter.push(word);
+                        line
+                    } else {
+                        format!("* no longer hiding messages containing '{}'", word)
+                    }
+                }
+                SettingsEdit::Block(name, add) => {
+                    settings.blocked_players.retain(|blocked| *blocked != name);
+                    let ids = self
+                        .world
+                        .clients
+                        .iter()
+                        .filter(|client| client.id() != self.own_id)
+                        .filter(|client| client.name().to_lowercase() == name)
+                        .map(|client| client.id())
+                        .collect::<Vec<_>>();
+                    // the server also keeps the block, so it applies to whoever is using the name now
+                    // even after they rename or reconnect
+                    for id in &ids {
+                        send_msg(if add {
+                            ClientMessage::Block(*id)
+                        } else {
+                            ClientMessage::Unblock(*id)
+                        });
+                    }
+
+                    if add {
+                        self.local().world.clear_chat(Some(&ids));
+
+                        let line = format!("* hiding messages from {}", name);
+                        settings.blocked_players.push(name);
+                        line
+                    } else {
+                        format!("* no longer hiding messages from {}", name)
+                    }
+                }
+                SettingsEdit::HideBubbles(hide) => {
+                    settings.hide_bubbles = hide;
+                    if hide {
+                        self.local().world.clear_chat(None);
+                        String::from(
+                            "* chat bubbles are off, messages only show up in the chat log",
+                        )
+                    } else {
+                        String::from("* chat bubbles are back on")
+                    }
+                }
+                SettingsEdit::SafeChat(_) if self.local().safe_chat_locked => {
+                    String::from("* safe chat was turned on for this game and stays on")
+                }
+                SettingsEdit::SafeChat(safe_chat) => {
+                    settings.safe_chat = safe_chat;
+                    if safe_chat {
+                        String::from("* safe chat is on, only preset phrases are shown and sent")
+                    } else {
+                        String::from("* safe chat is off")
+                    }
+                }
+                SettingsEdit::Clock24h(clock_24h) => {
+                    settings.clock_24h = clock_24h;
+                    format!(
+                        "* showing the time as {} hour clock",
+                        if clock_24h { 24 } else { 12 }
+                    )
+                }
+            };
+            self.local_mut().log(line, false);
+        }
+        settings.clamp();
+        self.set_settings(settings);
+    }
+
+    /// whether the own client is a seeker that has to wait for the hiders
+    fn seeker_waiting(&self) -> bool {
+        match &self.world.minigame {
+            Some(Minigame::HideAndSeek(state)) => {
+                state.phase() == HideAndSeekPhase::HeadStart && state.is_seeker(self.own_id)
+            }
+            _ => false,
+        }
+    }
+
+    /// whether the world editor is open and controls the camera
+    fn editing(&self) -> bool {
+        self.local
+            .as_ref()
+            .and_then(|local| local.editor.as_ref())
+            .map_or(false, |editor| editor.active)
+    }
+
+    pub(crate) fn new(client_id: ClientId, mut world: WorldState) -> Result<Self, CiboError> {
+        let own_client = world
+            .clients
+            .iter()
+            .position(|c| c.id() == client_id)
+            .ok_or(CiboError::MissingOwnClient(client_id))?;
+        world.clients.swap(0, own_client);
+
+        Ok(ClientGameState {
             own_id: client_id,
             world,
 
             local: None,
-        }
+        })
     }
 
     #[inline(always)]
@@ -105,6 +595,11 @@ impl ClientGameState {
         self.world.clients.first().unwrap()
     }
 
+    /// the players and objects as this client sees them, see `ServerMessage::RequestSnapshot`
+    pub fn world_snapshot(&self) -> WorldSnapshot {
+        self.world.snapshot()
+    }
+
     #[inline(always)]
     fn client_mut(&mut self) -> &mut Client {
         self.world.clients.first_mut().unwrap()
@@ -125,44 +620,97 @@ impl ClientGameState {
         self.local.as_mut().unwrap()
     }
 
+    /// run the game for `delta_ms` and draw it. without `draw` the framebuffer is left alone,
+    /// e.g. while the game can't be seen anyway
     pub fn update(
         &mut self,
         delta_ms: u64,
         framebuffer: &mut Framebuffer,
         input: &mut Input,
         send_msg: &mut dyn FnMut(ClientMessage),
-    ) {
+        draw: bool,
+    ) -> Result<(), CiboError> {
         self.prepare_local();
 
         self.local_mut().time_ms += delta_ms;
 
+        // sync our clock with the server for the first few seconds
+        let local = self.local_mut();
+        if local.clock.samples < CLOCK_SYNC_SAMPLES
+            && local.clock.last_ping.map_or(true, |last_ping| {
+                local.time_ms - last_ping > CLOCK_SYNC_INTERVAL
+            })
+        {
+            local.clock.last_ping = Some(local.time_ms);
+            send_msg(ClientMessage::Ping(local.time_ms));
+        }
+
+        if core::mem::take(&mut local.settings_changed) {
+            send_msg(ClientMessage::UpdateSettings(local.settings.clone()));
+        }
+
         let mut client_action = ClientAction::new();
 
         let tick_amt = (self.local().time_ms - self.local().last_tick) / crate::SERVER_TICK_RATE;
         self.local_mut().last_tick += tick_amt * crate::SERVER_TICK_RATE;
 
+        // swimmers only move every other tick
+        let tick = self.local().last_tick / crate::SERVER_TICK_RATE;
+        let swim_ticks = tick / 2 - (tick - tick_amt) / 2;
+        let beach_episode = self.world.special_events.beach_episode;
+        let move_ticks = |client: &Client| {
+            if client.in_water(beach_episode) {
+                swim_ticks
+            } else {
+                tick_amt
+            }
+        };
+
+        #[cfg(feature = "debug_ghosts")]
+        self.update_ghosts();
+
+        // other clients keep moving until the server tells us otherwise
+        for client in self.world.clients.iter_mut().skip(1) {
+            client.predict_movement(move_ticks(client));
+        }
+
         let mut direction = match self.client().movement {
             MoveDirection::None => None,
             direction => Some(direction),
         };
 
-        if self.local().world.own_local.borrow().chat_input.is_none() {
+        let mut pressed_move = false;
+        if self.editing() {
+            // the keyboard is used by the editor
+        } else if self.local().world.own_local.borrow().chat_input.is_none() {
             for input in &input.keyboard {
-                let button_direction = match input.key {
-                    Key::RawKey(RawKey::ArrowUp) | Key::Unicode('w') => Some(MoveDirection::Up),
-                    Key::RawKey(RawKey::ArrowDown) | Key::Unicode('s') => Some(MoveDirection::Down),
-                    Key::RawKey(RawKey::ArrowLeft) | Key::Unicode('a') => Some(MoveDirection::Left),
-                    Key::RawKey(RawKey::ArrowRight) | Key::Unicode('d') => {
-                        Some(MoveDirection::Right)
-                    }
+                let button_direction = match controls::action(input.key) {
+                    Some(Action::MoveUp) => Some(MoveDirection::Up),
+                    Some(Action::MoveDown) => Some(MoveDirection::Down),
+                    Some(Action::MoveLeft) => Some(MoveDirection::Left),
+                    Some(Action::MoveRight) => Some(MoveDirection::Right),
 
-                    Key::RawKey(RawKey::Return) | Key::Unicode('t')
-                        if input.state == KeyState::Down =>
-                    {
+                    Some(Action::Chat) if input.state == KeyState::Down => {
                         self.local().world.own_local.borrow_mut().chat_input = Some(String::new());
                         client_action.typing(true);
                         Some(MoveDirection::None)
                     }
+                    // typed into the chat otherwise, so it is handled here and not with the other keys
+                    Some(Action::Help) if input.state == KeyState::Down => {
+                        let render = &mut self.local_mut().render;
+                        render.help_open = !render.help_open;
+                        None
+                    }
+                    _ if input.key == Key::RawKey(RawKey::Escape)
+                        && input.state == KeyState::Down =>
+                    {
+                        let render = &mut self.local_mut().render;
+                        render.help_open = false;
+                        render.play_stats = None;
+                        render.letters.clear();
+                        render.transfer = None;
+                        None
+                    }
                     _ => None,
                 };
 
@@ -170,6 +718,7 @@ impl ClientGameState {
                     match input.state {
                         KeyState::Down => {
                             direction = Some(button_direction);
+                            pressed_move |= button_direction != MoveDirection::None;
                         }
                         KeyState::Up => {
                             if direction == Some(button_direction) {
@@ -206,16 +755,33 @@ impl ClientGameState {
             }
         }
 
+        // moving hands the camera back to the player
+        if pressed_move {
+            let local = self.local_mut();
+            let time_ms = local.time_ms;
+            if let Some(pan) = &mut local.render.camera_pan {
+                pan.cancel(time_ms);
+            }
+        }
+
+        if self.seeker_waiting() || self.editing() {
+            direction = match self.client().movement {
+                MoveDirection::None => None,
+                _ => Some(MoveDirection::None),
+            };
+        }
+
+        let own_ticks = move_ticks(self.client());
         let own_velocity = match direction {
             Some(MoveDirection::None) => {
                 client_action.movement(self.client().position, MoveDirection::None);
                 (0.0, 0.0)
             }
             Some(direction) => match direction {
-                MoveDirection::Up => (0.0, -1.0 * tick_amt as f32),
-                MoveDirection::Down => (0.0, 1.0 * tick_amt as f32),
-                MoveDirection::Left => (-1.0 * tick_amt as f32, 0.0),
-                MoveDirection::Right => (1.0 * tick_amt as f32, 0.0),
+                MoveDirection::Up => (0.0, -1.0 * own_ticks as f32),
+                MoveDirection::Down => (0.0, 1.0 * own_ticks as f32),
+                MoveDirection::Left => (-1.0 * own_ticks as f32, 0.0),
+                MoveDirection::Right => (1.0 * own_ticks as f32, 0.0),
                 MoveDirection::None => unreachable!(),
             },
             None => (0.0, 0.0),
@@ -225,10 +791,18 @@ impl ClientGameState {
             self.client().position.y + own_velocity.1.ceil() as i64,
         );
 
-        let own_hitbox = Rect::new(
-            Position::new(new_position.x + 2, new_position.y + 5),
-            Position::new(new_position.x + 30, new_position.y + 32),
-        );
+        let own_hitbox = Client::hitbox_at(new_position);
+
+        // walking onto a ramp changes the level, anything that doesn't say otherwise is on the ground
+        let level = self.client().elevation;
+        let old_feet = self.client().feet();
+        let new_feet = Client::feet_at(new_position);
+        let new_level = self
+            .world
+            .network_objects
+            .values()
+            .find_map(|object| object.elevation_at(new_feet, level))
+            .unwrap_or_default();
 
         let mut camera = self.local().render.camera;
         let camera_rect = Rect::new(
@@ -241,14 +815,25 @@ impl ClientGameState {
 
         let client_pos = self.client().position;
 
-        let collision_info = CollisionInfo::new_player(own_hitbox.center(), own_velocity);
-        let mut cant_move = false;
+        let collision_info =
+            CollisionInfo::new_player(own_hitbox.center(), vec_from_f32(own_velocity));
+        // only kept for the debug overlay
+        let mut collisions = Vec::new();
+        let mut cant_move = self
+            .world
+            .network_objects
+            .values()
+            .any(|object| object.blocks_step(old_feet, new_feet, level));
         macro_rules! check_collision {
             ($object_iter: expr) => {{
                 for object in $object_iter {
-                    // collide with objects
-                    if let Some(object_hitbox) = object.hitbox() {
+                    // collide with objects on the same level
+                    if let Some(object_hitbox) = object
+                        .hitbox()
+                        .filter(|_| object.properties().elevation == level)
+                    {
                         if object_hitbox.intersects(&own_hitbox) {
+                            collisions.push(object_hitbox);
                             object.on_collision(collision_info);
                             if object.collision_info().is_static() {
                                 cant_move = true;
@@ -285,13 +870,16 @@ impl ClientGameState {
         if let Some(direction) = direction {
             if !cant_move {
                 client_action.movement(new_position, direction);
+                if new_level != level {
+                    client_action.elevation(new_level);
+                }
             } else {
                 client_action.look(direction);
             }
         }
 
         let forced_update =
-            self.local().time_ms - self.local().last_message > crate::SERVER_TICK_RATE * 15;
+            self.local().time_ms - self.local().last_message > self.world.tuning.forced_update_ms;
         let has_action = client_action.any();
         if has_action || forced_update {
             if forced_update && !has_action {
@@ -307,46 +895,102 @@ impl ClientGameState {
                 self.local_mut().last_message = self.local().time_ms;
             }
 
+            client_action.timestamp(self.local().server_time());
+            let time_ms = self.local().time_ms;
+            if let Some(trace) = self.local_mut().latency.start(time_ms) {
+                client_action.trace(trace);
+            }
             self.client_mut().apply_action(&client_action);
-            send_msg(ClientMessage::Action(client_action))
+            let local = self.local_mut();
+            match &mut local.unsent_action {
+                Some(unsent) => unsent.combine(&client_action),
+                None => local.unsent_action = Some(client_action),
+            }
+        }
+        // the server combines everything that arrives within a tick anyway, and sending less
+        // helps when it slowed down its ticks under load
+        let tick_ms = self.world.tuning.server_tick_ms;
+        let local = self.local_mut();
+        if local.time_ms >= local.last_action_sent + tick_ms {
+            if let Some(action) = local.unsent_action.take() {
+                local.last_action_sent = local.time_ms;
+                send_msg(ClientMessage::Action(action));
+            }
         }
 
+        let physics = self.world.physics;
+        let mut failed = None;
         for (id, network_object) in self.world.network_objects.iter_mut() {
-            if let Ok(Some(data)) = network_object.client_tick() {
-                send_msg(ClientMessage::UpdateObject(*id, data));
+            match network_object.client_tick() {
+                Ok(Some(data)) => send_msg(ClientMessage::UpdateObject(*id, data)),
+                Ok(None) => {}
+                Err(error) => {
+                    failed = Some(CiboError::Object {
+                        id: *id,
+                        error: Box::new(error),
+                    })
+                }
             }
 
             let mut collision_tester = |object: &mut dyn Object| {
+                if object.properties().elevation != level {
+                    return None;
+                }
                 let hitbox = object.hitbox()?;
 
                 if hitbox.intersects(&own_hitbox) {
+                    collisions.push(hitbox);
                     object.on_collision(collision_info);
                     Some(collision_info)
                 } else {
                     None
                 }
             };
-            network_object.tick(delta_ms, CollisionTester::new(&mut collision_tester));
-        }
-
-        self.render(framebuffer, input, send_msg);
-        // for object in self.local().world.objects.iter() {
-        //     if let Some(hitbox) = object.hitbox() {
-        //         let camera = self.local().render.camera;
-        //         let hitbox = hitbox.translate(Position::new(-camera.x, -camera.y));
-        //         framebuffer.draw_box(&hitbox, &monos_gfx::Color::new(255, 0, 0))
-        //     }
-        //
-        //     let bounds = object
-        //         .bounds()
-        //         .translate(Position::new(-camera.x, -camera.y));
-        //     framebuffer.draw_box(&bounds, &monos_gfx::Color::new(0, 255, 0))
-        // }
+            network_object.tick(
+                delta_ms,
+                &physics,
+                CollisionTester::new(&mut collision_tester),
+            );
+        }
+
+        let debug = &mut self.local_mut().render.debug;
+        debug.own_hitbox = Some(own_hitbox);
+        debug.collisions = collisions;
+        debug.blocked = cant_move;
+
+        let feet = self.client().feet();
+        if let Some(local) = self.local.as_mut() {
+            if let Some(editor) = local.editor.as_mut().filter(|editor| editor.active) {
+                editor.update(&mut self.world, &mut local.world, input, tick_amt, send_msg);
+            } else if let Some(chalk) = local.world.own_local.borrow_mut().chalk.as_mut() {
+                chalk.update(input, local.render.camera, feet, send_msg);
+            }
+        }
+
+        for event in self.tutorial_events(pressed_move) {
+            self.local_mut().tutorial.handle(event);
+        }
+
+        if draw {
+            self.render(framebuffer, input, send_msg);
+        }
+        self.apply_settings_edits(send_msg);
+        // also while nothing is drawn, so the queue doesn't grow
+        self.local_mut().dispatch_events();
 
         input.clear();
+        failed.map_or(Ok(()), Err)
     }
 
-    pub fn handle_message(&mut self, msg: ServerMessage) {
+    /// let the player know something went wrong, e.g. an error returned by `update` or `handle_message`
+    pub fn show_error(&mut self, error: &CiboError) {
+        self.local_mut().emit(ClientEvent::Ticker(
+            format!("error: {}", error),
+            TickerKind::Error,
+        ));
+    }
+
+    pub fn handle_message(&mut self, msg: ServerMessage) -> Result<(), CiboError> {
         match msg {
             ServerMessage::NewClient(client) => {
                 let client_id = client.id();
@@ -365,14 +1009,87 @@ impl ClientGameState {
                         .retain(|(id, _)| *id != client_id);
                 }
             }
+            // only sent before joining, so there is no game state yet
+            ServerMessage::ConnectRejected(_)
+            | ServerMessage::QueuePosition(_)
+            | ServerMessage::NameCheck { .. }
+            | ServerMessage::Refused(_)
+            | ServerMessage::FullStatePart { .. } => {}
+            ServerMessage::PlayStats(stats) => self.local_mut().render.play_stats = Some(stats),
+            ServerMessage::Mail(mut letters) => {
+                let local = self.local_mut();
+                // letters are written freely, so in safe chat only the preset phrases get through
+                letters.retain(|letter| {
+                    !local.settings.safe_chat || PhraseId::find(&letter.message).is_some()
+                });
+                if letters.is_empty() {
+                    return Ok(());
+                }
+                for letter in &letters {
+                    local.log(
+                        format!("* letter from {}: {}", letter.from, letter.message),
+                        true,
+                    );
+                }
+                local.world.own_local.borrow_mut().pinged = true;
+                local.render.letters = letters;
+            }
+            ServerMessage::Chalk(stroke) => {
+                self.world.chalk.expire(self.local().server_time());
+                self.world.chalk.add(stroke);
+            }
+            ServerMessage::ClearChalk { min, max } => {
+                self.world.chalk.clear_area(Rect::new(min, max));
+            }
+            ServerMessage::Cosmetics(client_id, cosmetics) => {
+                if let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == client_id) {
+                    client.cosmetics = cosmetics;
+                }
+            }
+            ServerMessage::Verified(client_id, verified) => {
+                if let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == client_id) {
+                    client.verified = verified;
+                }
+            }
+            ServerMessage::SafeChat(client_id, safe_chat) => {
+                if let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == client_id) {
+                    client.safe_chat = safe_chat;
+                }
+            }
+            ServerMessage::TransferCode { code, link } => {
+                let local = self.local_mut();
+                local.log(format!("* transfer code: {}", code), false);
+                local.render.transfer = Some(TransferPanel::new(code, link));
+            }
+            ServerMessage::ClientRenamed(client_id, name) => {
+                if let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == client_id) {
+                    client.set_name(name);
+                }
+            }
+            ServerMessage::TimeSync {
+                server_time,
+                unix_ms,
+                next_event,
+            } => {
+                self.local_mut().world_clock = Some(WorldClock {
+                    server_time,
+                    unix_ms,
+                    next_event,
+                });
+            }
             ServerMessage::FullState(_) => {
-                panic!(
-                    "unexpected FullState message. should be handled by the client implementation"
-                );
+                // full states are handled by the client implementation
+                return Err(CiboError::UnexpectedMessage("FullState"));
             }
             ServerMessage::UpdateState(updates) => {
                 for (id, action) in updates {
                     if id == self.client().id() {
+                        if let Some(trace) = action.get_trace() {
+                            let local = self.local_mut();
+                            local
+                                .latency
+                                .arrived(trace, local.time_ms, local.clock.offset);
+                        }
                         continue;
                     }
                     if let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == id) {
@@ -380,7 +1097,17 @@ impl ClientGameState {
                     }
                 }
             }
-            ServerMessage::Chat(client_id, message) => {
+            ServerMessage::Chat(chat_id, client_id, message) => {
+                let own_id = self.client().id();
+                let settings = &self.local().settings;
+                let filtered = client_id != own_id
+                    && (settings.filters(&message)
+                        || self
+                            .world
+                            .clients
+                            .iter()
+                            .find(|c| c.id() == client_id)
+                            .map_or(false, |c| settings.blocks(c.name())));
                 let client = self.world.clients.iter_mut().find(|c| c.id() == client_id);
 
                 let client_name;
@@ -395,37 +1122,331 @@ impl ClientGameState {
                     }
                 };
 
-                let log_line = format!("<{}> {}", client_name, message);
+                if filtered {
+                    return Ok(());
+                }
+
+                let log_prefix = format!("<{}> ", client_name);
+                let client_name = String::from(client_name);
+
+                // flash the name of everyone who was mentioned
+                let mentioned = self
+                    .world
+                    .clients
+                    .iter()
+                    .filter(|client| mentions(&message, client.name()))
+                    .map(|client| client.id())
+                    .collect::<Vec<_>>();
+                let mentions_self = client_id != own_id && mentioned.contains(&own_id);
+
+                let tuning = self.world.tuning;
+                let local = self.local_mut();
+                local.emit(ClientEvent::Chat {
+                    id: chat_id,
+                    prefix: log_prefix,
+                    message: message.clone(),
+                    highlight: mentions_self,
+                });
+                if client_id == own_id {
+                    local.world.own_local.borrow_mut().last_sent_chat = Some(chat_id);
+                }
+                if !local.settings.reduced_motion {
+                    for id in mentioned {
+                        local.world.flash_name(id, local.time_ms + MENTION_FLASH_MS);
+                    }
+                }
+                if mentions_self {
+                    let mut own_local = local.world.own_local.borrow_mut();
+                    own_local.last_mention = Some(client_name);
+                    own_local.pinged = true;
+                }
+
+                if !local.settings.hide_bubbles {
+                    let duration_ms = tuning.chat_duration_ms(&message);
+                    local
+                        .world
+                        .add_chat(client_id, chat_id, message, local.time_ms, duration_ms);
+                }
+            }
+            ServerMessage::Whisper {
+                id,
+                from,
+                to,
+                message,
+            } => {
+                let name_of = |id: ClientId| {
+                    self.world
+                        .clients
+                        .iter()
+                        .find(|client| client.id() == id)
+                        .map_or_else(|| String::from("Unknown"), |client| client.name().into())
+                };
+
+                let own_id = self.client().id();
+                let settings = &self.local().settings;
+                if from != own_id && (settings.filters(&message) || settings.blocks(&name_of(from)))
+                {
+                    return Ok(());
+                }
+
+                let log_prefix = if from == own_id {
+                    format!("-> {}: ", name_of(to))
+                } else {
+                    format!("<- {}: ", name_of(from))
+                };
+                let from_name = name_of(from);
+
                 let local = self.local_mut();
+                local.emit(ClientEvent::Chat {
+                    id,
+                    prefix: log_prefix,
+                    message,
+                    highlight: from != own_id,
+                });
+                if from != own_id {
+                    let mut own_local = local.world.own_local.borrow_mut();
+                    own_local.last_mention = Some(from_name);
+                    own_local.pinged = true;
+                }
+            }
 
-                let render_state = &mut local.render;
-                render_state.chat_log.push_back(log_line);
-                if render_state.chat_log.len() > 256 {
-                    render_state.chat_log.pop_front();
+            ServerMessage::SystemChat(message) => {
+                self.local_mut().log(format!("* {}", message), false);
+            }
+            ServerMessage::ShuttingDown => {
+                self.local_mut().log(
+                    "* the server is going down, you will be reconnected once it is back"
+                        .to_string(),
+                    false,
+                );
+            }
+            // acknowledged by the frontend, which knows how to reach the server
+            ServerMessage::Critical { message, .. } => return self.handle_message(*message),
+            // answered by the frontend as well
+            ServerMessage::RequestSnapshot => {}
+            // downloaded by the frontend, the game state only sees the assets once they are loaded
+            ServerMessage::AssetPacks(_) => {}
+            ServerMessage::RemoteChat {
+                server,
+                name,
+                message,
+            } => {
+                let settings = &self.local().settings;
+                if settings.filters(&message) || settings.blocks(&name) {
+                    return Ok(());
+                }
+                self.local_mut()
+                    .log(format!("[{}] <{}> {}", server, name, message), false);
+            }
+            ServerMessage::ShardChat {
+                id,
+                from: _,
+                name,
+                message,
+            } => {
+                let settings = &self.local().settings;
+                if settings.filters(&message) || settings.blocks(&name) {
+                    return Ok(());
                 }
 
-                local
+                let mentions_self = mentions(&message, self.client().name());
+                let local = self.local_mut();
+                local.emit(ClientEvent::Chat {
+                    id,
+                    prefix: format!("<{}> ", name),
+                    message,
+                    highlight: mentions_self,
+                });
+                if mentions_self {
+                    let mut own_local = local.world.own_local.borrow_mut();
+                    own_local.last_mention = Some(name);
+                    own_local.pinged = true;
+                }
+            }
+            ServerMessage::GuestTime(remaining_ms) => {
+                let local = self.local_mut();
+                local.guest_until = remaining_ms.map(|remaining_ms| local.time_ms + remaining_ms);
+            }
+            ServerMessage::TeleportRequest(requester) => {
+                let name = self
                     .world
-                    .add_chat(client_id, message, local.time_ms + 5000);
+                    .clients
+                    .iter()
+                    .find(|c| c.id() == requester)
+                    .map_or_else(|| String::from("someone"), |c| String::from(c.name()));
+                let local = self.local_mut();
+                local.teleport_request = Some((
+                    requester,
+                    local.time_ms + crate::server::TELEPORT_REQUEST_TIMEOUT_MS,
+                ));
+                local.log(
+                    format!("* {} wants to teleport to you, /tpaccept to let them", name),
+                    true,
+                );
+            }
+            ServerMessage::Teleport {
+                client,
+                position,
+                elevation,
+            } => {
+                if let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == client) {
+                    client.position = position;
+                    client.movement = MoveDirection::None;
+                    client.elevation = elevation;
+                }
+                let local = self.local_mut();
+                if local
+                    .teleport_request
+                    .is_some_and(|(requester, _)| requester == client)
+                {
+                    local.teleport_request = None;
+                }
+            }
+            ServerMessage::Home(home) => self.local_mut().emit(ClientEvent::HomeChanged(home)),
+            ServerMessage::Effect {
+                kind,
+                position,
+                duration,
+            } => {
+                let local = self.local_mut();
+                let effect = Effect::new(kind, position, local.time_ms, duration);
+                local.render.effects.push(effect);
+            }
+            ServerMessage::RemoteOnline { server, players } => {
+                self.local_mut()
+                    .emit(ClientEvent::RemoteOnline { server, players });
+            }
+            ServerMessage::CameraHint { target, duration } => {
+                let local = self.local_mut();
+                if local.render.event_mode {
+                    let time_ms = local.time_ms;
+                    local.render.camera_pan = Some(CameraPan {
+                        target,
+                        started_at: time_ms,
+                        ends_at: time_ms + duration,
+                    });
+                }
+            }
+            ServerMessage::Settings(settings) => {
+                self.local_mut().settings = settings;
+                self.sync_safe_chat();
+            }
+            ServerMessage::Ticker(text, kind) => {
+                self.local_mut().emit(ClientEvent::Ticker(text, kind));
+            }
+            ServerMessage::SlowMode(slow_mode_ms) => {
+                let local = self.local_mut();
+                local.world.own_local.borrow_mut().slow_mode_ms = slow_mode_ms;
+                let line = match slow_mode_ms {
+                    Some(slow_mode_ms) => format!(
+                        "* slow mode is on, one message every {}s",
+                        slow_mode_ms.div_ceil(1000)
+                    ),
+                    None => "* slow mode is off".into(),
+                };
+                local.log(line, false);
+            }
+            ServerMessage::ChatEdited {
+                message_id,
+                message,
+            } => {
+                let local = self.local_mut();
+                // the server only lets the sender edit, so our own edits are never filtered
+                let own = local.world.own_local.borrow().last_sent_chat == Some(message_id);
+                if !own && local.settings.filters(&message) {
+                    local.emit(ClientEvent::ChatDeleted(message_id));
+                    local.world.delete_chat(message_id);
+                    return Ok(());
+                }
+
+                let message = format!("{} (edited)", message);
+                local.world.edit_chat(message_id, &message);
+                local.emit(ClientEvent::ChatEdited {
+                    id: message_id,
+                    message,
+                });
+            }
+            ServerMessage::DeleteChat { message_id } => {
+                let local = self.local_mut();
+                local.emit(ClientEvent::ChatDeleted(message_id));
+                local.world.delete_chat(message_id);
             }
 
             ServerMessage::SpecialEvent { event, active } => {
                 self.world.set_special_event(event, active)
             }
+            ServerMessage::PhysicsParam { param, value } => self.world.physics.set(param, value),
+            ServerMessage::TuningChanged(tuning) => self.world.tuning = tuning,
+            ServerMessage::Minigame(state) => self.world.minigame = state,
 
             ServerMessage::NewObject(id, object) => {
-                self.world.network_objects.insert(id, object.serialize());
+                self.world.network_objects.insert(id, object.serialize()?);
+            }
+            ServerMessage::NewObjects(objects) => {
+                self.world.network_objects.reserve(objects.len());
+                for (id, object) in objects {
+                    self.world.network_objects.insert(id, object.serialize()?);
+                }
+            }
+            ServerMessage::MoveObject(id, position) => {
+                if let Some(object) = self.world.network_objects.get_mut(&id) {
+                    object.set_position(position);
+                }
             }
             ServerMessage::DeleteObject(id) => {
-                self.world.network_objects.remove(&id);
+                if let Some(object) = self.world.network_objects.remove(&id) {
+                    let local = self.local_mut();
+                    local.world.despawn(object, local.time_ms);
+                }
+            }
+            ServerMessage::DeleteObjects(ids) => {
+                for id in ids {
+                    if let Some(object) = self.world.network_objects.remove(&id) {
+                        let local = self.local_mut();
+                        local.world.despawn(object, local.time_ms);
+                    }
+                }
             }
             ServerMessage::UpdateObject(id, data) => {
                 let object = self.world.network_objects.get_mut(&id);
                 if let Some(object) = object {
-                    object.client_message(&data).unwrap();
+                    object
+                        .client_message(&data)
+                        .map_err(|error| CiboError::Object {
+                            id,
+                            error: Box::new(error),
+                        })?;
+                }
+            }
+
+            ServerMessage::EditorAccess => {
+                let local = self.local_mut();
+                local.editor = Some(EditorState::new());
+                local.log("* editor access granted, press f2 to open it".into(), false);
+            }
+            ServerMessage::StaffAccess => {
+                self.local_mut().log(
+                    "* you are event staff, see /staff in the help".into(),
+                    false,
+                );
+            }
+
+            ServerMessage::Pong {
+                client_time,
+                server_time,
+            } => {
+                let local = self.local_mut();
+                let rtt = local.time_ms.saturating_sub(client_time);
+                local.clock.samples += 1;
+
+                // the sample with the lowest round trip time gives the most accurate estimate
+                if local.clock.best_rtt.map_or(true, |best_rtt| rtt < best_rtt) {
+                    local.clock.best_rtt = Some(rtt);
+                    local.clock.offset = (server_time + rtt / 2) as i64 - local.time_ms as i64;
                 }
             }
         }
+        Ok(())
     }
 
     pub fn render(
@@ -434,42 +1455,121 @@ impl ClientGameState {
         input: &mut Input,
         send_msg: &mut dyn FnMut(ClientMessage),
     ) {
-        input.keyboard.iter().for_each(|input| match input.key {
-            Key::RawKey(RawKey::Tab) => {
-                self.local_mut().render.player_list_ui = if input.state == KeyState::Down {
-                    Some(UIFrame::new_stateless(Direction::TopToBottom))
-                } else {
-                    None
+        input
+            .keyboard
+            .iter()
+            .for_each(|input| match controls::action(input.key) {
+                Some(Action::PlayerList) => self
+                    .local_mut()
+                    .emit(ClientEvent::PlayerListHeld(input.state == KeyState::Down)),
+                Some(Action::StreamMode) if input.state == KeyState::Down => {
+                    self.local_mut().render.stream_mode = !self.local().render.stream_mode;
                 }
-            }
-            Key::RawKey(RawKey::F1) if input.state == KeyState::Down => {
-                self.local_mut().render.stream_mode = !self.local().render.stream_mode;
-            }
-            _ => {}
-        });
+                Some(Action::Editor) if input.state == KeyState::Down => {
+                    let local = self.local_mut();
+                    let camera = local.render.camera;
+                    if let Some(editor) = &mut local.editor {
+                        editor.toggle(camera);
+                        local.world.clear_previews();
+                    }
+                }
+                Some(Action::HudEditor) if input.state == KeyState::Down => {
+                    self.local_mut().render.hud_editor.toggle();
+                }
+                Some(Action::DebugOverlay) if input.state == KeyState::Down => {
+                    let local = self.local_mut();
+                    let debug = &mut local.render.debug;
+                    debug.enabled = !debug.enabled;
+                    let line = if debug.enabled {
+                        "* debug view on"
+                    } else {
+                        "* debug view off"
+                    };
+                    local.log(String::from(line), false);
+                }
+                Some(Action::EventMode) if input.state == KeyState::Down => {
+                    let local = self.local_mut();
+                    let render = &mut local.render;
+                    render.event_mode = !render.event_mode;
+                    if !render.event_mode {
+                        render.camera_pan = None;
+                    }
+                    let line = if render.event_mode {
+                        "* event mode on, the camera follows live events"
+                    } else {
+                        "* event mode off"
+                    };
+                    local.log(String::from(line), false);
+                }
+                _ => {}
+            });
+        self.local_mut().dispatch_events();
+        let time_ms = self.local().time_ms;
+        self.local_mut().latency.rendered(time_ms);
 
         // move camera to follow client
+        let camera_edge_x = self.world.tuning.camera_edge_x as i64;
+        let camera_edge_y = self.world.tuning.camera_edge_y as i64;
         let mut camera = self.local().render.camera;
         let mut client_screen_position = self.client().position - camera;
-        if client_screen_position.x < CAMERA_EDGE_X - 32 {
-            camera.x = self.client().position.x - CAMERA_EDGE_X + 32;
-            client_screen_position.x = CAMERA_EDGE_X - 32;
-        } else if client_screen_position.x > framebuffer.dimensions().width as i64 - CAMERA_EDGE_X {
+        if client_screen_position.x < camera_edge_x - 32 {
+            camera.x = self.client().position.x - camera_edge_x + 32;
+            client_screen_position.x = camera_edge_x - 32;
+        } else if client_screen_position.x > framebuffer.dimensions().width as i64 - camera_edge_x {
             camera.x =
-                self.client().position.x - framebuffer.dimensions().width as i64 + CAMERA_EDGE_X;
-            client_screen_position.x = framebuffer.dimensions().width as i64 - CAMERA_EDGE_X;
+                self.client().position.x - framebuffer.dimensions().width as i64 + camera_edge_x;
+            client_screen_position.x = framebuffer.dimensions().width as i64 - camera_edge_x;
         }
 
-        if client_screen_position.y < CAMERA_EDGE_Y - 32 {
-            camera.y = self.client().position.y - CAMERA_EDGE_Y + 32;
-            client_screen_position.y = CAMERA_EDGE_Y - 32;
-        } else if client_screen_position.y > framebuffer.dimensions().height as i64 - CAMERA_EDGE_Y
+        if client_screen_position.y < camera_edge_y - 32 {
+            camera.y = self.client().position.y - camera_edge_y + 32;
+            client_screen_position.y = camera_edge_y - 32;
+        } else if client_screen_position.y > framebuffer.dimensions().height as i64 - camera_edge_y
         {
             camera.y =
-                self.client().position.y - framebuffer.dimensions().height as i64 + CAMERA_EDGE_Y;
-            client_screen_position.y = framebuffer.dimensions().height as i64 - CAMERA_EDGE_Y;
+                self.client().position.y - framebuffer.dimensions().height as i64 + camera_edge_y;
+            client_screen_position.y = framebuffer.dimensions().height as i64 - camera_edge_y;
+        }
+        if let Some(editor) = self.local().editor.as_ref().filter(|editor| editor.active) {
+            camera = editor.camera;
+            client_screen_position = self.client().position - camera;
         }
         self.local_mut().render.camera = camera;
+        // where the player can walk without moving the camera
+        let deadzone = Rect::new(
+            Position::new(camera_edge_x - 32, camera_edge_y - 32),
+            Position::new(
+                framebuffer.dimensions().width as i64 - camera_edge_x + 32,
+                framebuffer.dimensions().height as i64 - camera_edge_y + 32,
+            ),
+        );
+
+        // pan over to whatever the server wants to show, without moving the camera that follows the player
+        {
+            let local = self.local_mut();
+            let time_ms = local.time_ms;
+            let reduced_motion = local.settings.reduced_motion;
+            if let Some(pan) = &local.render.camera_pan {
+                // with reduced motion the camera cuts over and back instead of panning
+                if pan.is_done(time_ms) || (reduced_motion && time_ms >= pan.ends_at) {
+                    local.render.camera_pan = None;
+                } else {
+                    let screen = framebuffer.dimensions();
+                    let target = pan.target
+                        - Position::new(screen.width as i64 / 2, screen.height as i64 / 2);
+                    let weight = if reduced_motion {
+                        1.0
+                    } else {
+                        pan.weight(time_ms)
+                    };
+                    camera = Position::new(
+                        camera.x + ((target.x - camera.x) as f32 * weight) as i64,
+                        camera.y + ((target.y - camera.y) as f32 * weight) as i64,
+                    );
+                    client_screen_position = self.client().position - camera;
+                }
+            }
+        }
 
         {
             let player_pos = self.client().position;
@@ -480,84 +1580,498 @@ impl ClientGameState {
             let mut ctx = RenderContext {
                 fb: framebuffer,
                 time_ms: local.time_ms,
+                server_time_ms: local.server_time(),
+                anim_frame_ms: self.world.tuning.anim_frame_ms,
                 stream_mode: local.render.stream_mode,
+                stream_safe: !local.render.stream_mode,
+                palette: local.settings.color_mode.palette(),
+                label_layout: local.settings.label_layout,
+                debug: local.render.debug.enabled,
                 player_pos,
+                own_id: self.own_id,
                 input,
                 send_msg,
             };
 
             self.world.render(&mut local.world, camera, &mut ctx);
+            local.render.debug.render(deadzone, camera, &mut ctx);
+
+            let time_ms = local.time_ms;
+            local
+                .render
+                .effects
+                .retain(|effect| !effect.is_done(time_ms));
+            for effect in &local.render.effects {
+                effect.render(framebuffer, camera, time_ms);
+            }
+        }
+
+        // seekers only see their close surroundings
+        if let Some(Minigame::HideAndSeek(state)) = &self.world.minigame {
+            if state.is_seeker(self.own_id) {
+                match state.phase() {
+                    HideAndSeekPhase::HeadStart => framebuffer.draw_rect(
+                        Rect::from_dimensions(framebuffer.dimensions()),
+                        Color::new(0, 0, 0),
+                    ),
+                    HideAndSeekPhase::Seeking => draw_fog(
+                        framebuffer,
+                        client_screen_position + Position::new(16, 16),
+                        SEEKER_VIEW_RADIUS,
+                    ),
+                    _ => {}
+                }
+            }
         }
 
-        // dont draw ui if in stream mode
+        // the screen size may have changed since the layout was made. it is only fitted to the
+        // screen for good while the player is arranging it
+        let screen = framebuffer.dimensions();
+        let hud = {
+            let render = &mut self.local_mut().render;
+            let hud = render.hud.get_or_insert_with(|| HudLayout::new(screen));
+            if render.hud_editor.active {
+                hud.clamp(screen);
+                if render.hud_editor.update(hud, input, screen) {
+                    render.hud_changed = true;
+                }
+            }
+            hud.clamped(screen)
+        };
+
+        // dont draw ui if in stream mode, the panels decide themselves what is still shown
         if self.local().render.stream_mode {
+            self.draw_panels(framebuffer, input, &hud);
             return;
         }
 
-        // draw chat log
-        let chat_log_rect = Rect::new(
-            Position::new(0, framebuffer.dimensions().height as i64 - 100),
-            Position::new(100, framebuffer.dimensions().height as i64),
-        );
+        // draw coordinate display, with a reminder next to it while the message board has news
+        let tile_position = self.client().position / 16;
+        let coordinate_rect = hud.coordinates.rect();
+        let board_unread = self.world.network_objects.values().any(|object| {
+            object
+                .downcast_ref::<MessageBoard>()
+                .is_some_and(|board| board.unread())
+        });
+        let render = &mut self.local_mut().render;
+        render.coordinate_text.clear();
+        render.coordinate_text.push('X');
+        push_int(&mut render.coordinate_text, tile_position.x);
+        render.coordinate_text.push_str(" / Y");
+        push_int(&mut render.coordinate_text, tile_position.y);
+        render
+            .coordinate_ui
+            .draw_frame(framebuffer, coordinate_rect, input, |ui| {
+                ui.label::<font::Glean>(&render.coordinate_text);
+                if board_unread {
+                    ui.label::<font::Glean>("! the message board was updated");
+                }
+            });
 
-        {
-            let local = self.local_mut();
+        // time of day and the next event, right below the coordinates
+        let local = self.local_mut();
+        if let Some(clock) = &local.world_clock {
+            let now = local.server_time();
+            let time = format_clock(
+                clock.unix_ms + now.saturating_sub(clock.server_time),
+                local.settings.clock_24h,
+            );
+            let event =
+                clock
+                    .next_event
+                    .as_ref()
+                    .map(|event| match event.starts_at.checked_sub(now) {
+                        Some(remaining_ms) if remaining_ms > 0 => {
+                            format!("{} in {}", event.name, format_countdown(remaining_ms))
+                        }
+                        _ => format!("{} is on!", event.name),
+                    });
+
+            let lines = if event.is_some() { 2 } else { 1 };
+            let clock_rect = Rect::new(
+                Position::new(coordinate_rect.min.x, coordinate_rect.max.y),
+                Position::new(
+                    coordinate_rect.max.x,
+                    coordinate_rect.max.y + CLOCK_LINE_HEIGHT * lines,
+                ),
+            );
             local
                 .render
-                .chat_log_ui
-                .draw_frame(framebuffer, chat_log_rect, input, |ui| {
-                    ui.add(
-                        widgets::ScrollableLabel::<font::Glean, _>::new_iter(
-                            local.render.chat_log.iter().map(|chat| chat.as_str()),
-                            Origin::Bottom,
-                        )
-                        .wrap(TextWrap::Enabled { hyphenate: false })
-                        .scroll_y(100),
-                    );
+                .clock_ui
+                .draw_frame(framebuffer, clock_rect, input, |ui| {
+                    ui.label::<font::Glean>(&time);
+                    if let Some(event) = &event {
+                        ui.label::<font::Glean>(event);
+                    }
                 });
         }
 
-        // draw coordinate display
-        let tile_position = self.client().position / 16;
-        let coordinate_rect = Rect::new(
-            Position::new(framebuffer.dimensions().width as i64 - 100, 0),
-            Position::new(framebuffer.dimensions().width as i64, 100),
-        );
-        self.local_mut().render.coordinate_ui.draw_frame(
+        if let Some(editor) = self.local().editor.as_ref().filter(|editor| editor.active) {
+            editor.draw(framebuffer, input);
+        }
+
+        // draw minigame status
+        if let Some(minigame) = &self.world.minigame {
+            let clients = &self.world.clients;
+            let name_of = |id| {
+                clients
+                    .iter()
+                    .find(|c| c.id() == id)
+                    .map_or("(left)", |c| c.name())
+            };
+            let seconds_left =
+                |phase_end: u64| phase_end.saturating_sub(self.local().server_time()) / 1000;
+
+            let minigame_rect = Rect::new(
+                Position::new(framebuffer.dimensions().width as i64 / 2 - 100, 5),
+                Position::new(framebuffer.dimensions().width as i64 / 2 + 100, 100),
+            );
+            let mut minigame_ui = UIFrame::new_stateless(Direction::TopToBottom);
+            minigame_ui.draw_frame(framebuffer, minigame_rect, input, |ui| {
+                ui.margin(MarginMode::Grow);
+                match minigame {
+                    Minigame::Tag(tag) => {
+                        match tag.it() {
+                            Some(it) if it == self.own_id => {
+                                ui.label::<font::Cozette>("you are it!");
+                            }
+                            Some(it) => {
+                                ui.label::<font::Cozette>(&format!("{} is it!", name_of(it)));
+                            }
+                            None => {
+                                ui.label::<font::Cozette>("tag! waiting for players...");
+                            }
+                        }
+                        for (id, score) in tag.scores().iter().take(3) {
+                            ui.label::<font::Glean>(&format!("{} - {}", name_of(*id), score));
+                        }
+                    }
+                    Minigame::HideAndSeek(state) => {
+                        let role = if state.is_seeker(self.own_id) {
+                            "seeker"
+                        } else {
+                            "hider"
+                        };
+                        match state.phase() {
+                            HideAndSeekPhase::Waiting => {
+                                ui.label::<font::Cozette>("hide and seek! waiting for players...");
+                            }
+                            HideAndSeekPhase::HeadStart => {
+                                ui.label::<font::Cozette>(&format!(
+                                    "{} - seeking starts in {}s",
+                                    role,
+                                    seconds_left(state.phase_end())
+                                ));
+                            }
+                            HideAndSeekPhase::Seeking => {
+                                ui.label::<font::Cozette>(&format!(
+                                    "{} - {}s left",
+                                    role,
+                                    seconds_left(state.phase_end())
+                                ));
+                            }
+                            HideAndSeekPhase::Over => {
+                                ui.label::<font::Cozette>(&format!(
+                                    "next round in {}s",
+                                    seconds_left(state.phase_end())
+                                ));
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
+        // draw guest countdown, or the prompt to register once it ran out
+        if let Some(guest_until) = self.local().guest_until {
+            let remaining_s = guest_until
+                .saturating_sub(self.local().time_ms)
+                .div_ceil(1000);
+            let width = framebuffer.dimensions().width as i64;
+            let height = framebuffer.dimensions().height as i64;
+            let mut guest_ui = UIFrame::new_stateless(Direction::TopToBottom);
+            if remaining_s > 0 {
+                let guest_rect =
+                    Rect::new(Position::new(width - 160, 5), Position::new(width - 5, 40));
+                guest_ui.draw_frame(framebuffer, guest_rect, input, |ui| {
+                    ui.label::<font::Glean>(&format!(
+                        "guest time left: {}:{:02}",
+                        remaining_s / 60,
+                        remaining_s % 60
+                    ));
+                    ui.label::<font::Glean>("/register to keep playing");
+                });
+            } else {
+                let prompt_rect = Rect::new(
+                    Position::new(width / 2 - 120, height / 2 - 40),
+                    Position::new(width / 2 + 120, height / 2 + 40),
+                );
+                guest_ui.draw_frame(framebuffer, prompt_rect, input, |ui| {
+                    ui.margin(MarginMode::Grow);
+                    ui.label::<font::Cozette>("your guest time for today is up!");
+                    ui.label::<font::Glean>("link your discord on the game's page and type");
+                    ui.label::<font::Glean>("/register to keep playing as long as you like :)");
+                });
+            }
+        }
+
+        // point new players at the things they should try out
+        let tutorial_target = self.tutorial_target().map(|target| target - camera);
+        self.local().tutorial.render(
             framebuffer,
-            coordinate_rect,
             input,
-            |ui| {
-                ui.label::<font::Glean>(&format!("X{} / Y{}", tile_position.x, tile_position.y));
-            },
+            client_screen_position + Position::new(16, 16),
+            tutorial_target,
         );
 
-        // draw player list
+        // draw the prompt for a pending teleport request
+        if let Some((requester, until)) = self.local().teleport_request {
+            let requester = self.world.clients.iter().find(|c| c.id() == requester);
+            match requester {
+                Some(requester) if until > self.local().time_ms => {
+                    let width = framebuffer.dimensions().width as i64;
+                    let prompt_rect = Rect::new(
+                        Position::new(width / 2 - 120, 5),
+                        Position::new(width / 2 + 120, 40),
+                    );
+                    let mut prompt_ui = UIFrame::new_stateless(Direction::TopToBottom);
+                    prompt_ui.draw_frame(framebuffer, prompt_rect, input, |ui| {
+                        ui.margin(MarginMode::Grow);
+                        ui.label::<font::Glean>(&format!(
+                            "{} wants to teleport to you",
+                            requester.name()
+                        ));
+                        ui.label::<font::Glean>("/tpaccept to let them");
+                    });
+                }
+                _ => self.local_mut().teleport_request = None,
+            }
+        }
+
+        self.draw_panels(framebuffer, input, &hud);
+
+        let local = self.local_mut();
+        if local.render.hud_editor.active {
+            local.render.hud_editor.draw(framebuffer, &hud);
+        }
+
+        if local.render.help_open {
+            draw_help(framebuffer, input, local);
+        } else if let Some(stats) = &local.render.play_stats {
+            draw_play_stats(framebuffer, input, stats);
+        } else if let Some(transfer) = &local.render.transfer {
+            transfer.draw(framebuffer, input);
+        } else if !local.render.letters.is_empty() {
+            draw_letters(framebuffer, input, &local.render.letters);
+        }
+    }
+
+    fn draw_panels(&mut self, framebuffer: &mut Framebuffer, input: &mut Input, hud: &HudLayout) {
         let local = self
             .local
             .get_or_insert_with(|| Box::new(ClientLocalState::new(self.own_id)));
-        if let Some(player_list) = &mut local.render.player_list_ui {
-            let player_list_rect = Rect::new(
-                Position::new(framebuffer.dimensions().width as i64 / 2 - 100, 10),
-                Position::new(
-                    framebuffer.dimensions().width as i64 / 2 + 100,
-                    framebuffer.dimensions().height as i64 - 10,
-                ),
-            );
-            player_list.draw_frame(framebuffer, player_list_rect, input, |ui| {
-                ui.margin(MarginMode::Grow);
-                ui.label::<font::Cozette>(&format!("Players Online: {}", self.world.clients.len()));
-                ui.label::<font::Glean>("You");
-                for client in self.world.clients.iter().skip(1) {
-                    let client_tile_position = client.position / 16;
-                    ui.label::<font::Glean>(&format!(
-                        "{} | X{} / Y{}",
-                        client.name(),
-                        client_tile_position.x,
-                        client_tile_position.y
-                    ));
+        let mut ctx = PanelContext {
+            fb: framebuffer,
+            input,
+            world: &self.world,
+            hud,
+            palette: local.settings.color_mode.palette(),
+            time_ms: local.time_ms,
+            stream_mode: local.render.stream_mode,
+            reduced_motion: local.settings.reduced_motion,
+        };
+        for panel in local.panels.all() {
+            panel.draw(&mut ctx);
+        }
+    }
+}
+
+fn draw_play_stats(framebuffer: &mut Framebuffer, input: &mut Input, stats: &PlayStats) {
+    let width = framebuffer.dimensions().width as i64;
+    let rect = Rect::new(
+        Position::new(width / 2 - STATS_WIDTH, 10),
+        Position::new(width / 2 + STATS_WIDTH, 110),
+    );
+    framebuffer.draw_rect(rect, HELP_BG);
+
+    let minutes = stats.time_online_ms / 60_000;
+    let mut stats_ui = UIFrame::new_stateless(Direction::TopToBottom);
+    stats_ui.draw_frame(
+        framebuffer,
+        Rect::new(
+            rect.min + Position::new(5, 5),
+            rect.max - Position::new(5, 5),
+        ),
+        input,
+        |ui| {
+            ui.label::<font::Cozette>("your stats");
+            ui.label::<font::Glean>(&format!("time online: {}h {}m", minutes / 60, minutes % 60));
+            ui.label::<font::Glean>(&format!("tiles walked: {}", stats.tiles_walked));
+            ui.label::<font::Glean>(&format!("messages sent: {}", stats.messages_sent));
+            ui.label::<font::Glean>(&format!("balls kicked: {}", stats.balls_kicked));
+            ui.label::<font::Glean>("esc to close");
+        },
+    );
+}
+
+fn draw_letters(framebuffer: &mut Framebuffer, input: &mut Input, letters: &[Letter]) {
+    let width = framebuffer.dimensions().width as i64;
+    let height = framebuffer.dimensions().height as i64;
+    let rect = Rect::new(
+        Position::new(width / 2 - LETTERS_WIDTH, 10),
+        Position::new(width / 2 + LETTERS_WIDTH, height - 10),
+    );
+    framebuffer.draw_rect(rect, HELP_BG);
+
+    let mut letters_ui = UIFrame::new_stateless(Direction::TopToBottom);
+    letters_ui.draw_frame(
+        framebuffer,
+        Rect::new(
+            rect.min + Position::new(5, 5),
+            rect.max - Position::new(5, 5),
+        ),
+        input,
+        |ui| {
+            ui.label::<font::Cozette>("you've got mail!");
+            for letter in letters {
+                ui.label::<font::Glean>(&format!("from {}:", letter.from));
+                ui.label::<font::Glean>(&letter.message);
+            }
+            ui.label::<font::Glean>("esc to close");
+        },
+    );
+}
+
+/// the time of day in utc, like `14:30` or `2:30 pm`
+fn format_clock(unix_ms: u64, clock_24h: bool) -> String {
+    let minute_of_day = unix_ms / 60_000 % (24 * 60);
+    let (hour, minute) = (minute_of_day / 60, minute_of_day % 60);
+    if clock_24h {
+        format!("{:02}:{:02} utc", hour, minute)
+    } else {
+        let suffix = if hour < 12 { "am" } else { "pm" };
+        let hour = if hour % 12 == 0 { 12 } else { hour % 12 };
+        format!("{}:{:02} {} utc", hour, minute, suffix)
+    }
+}
+
+/// every key and chat command, with the current state of the settings the keys toggle
+fn draw_help(framebuffer: &mut Framebuffer, input: &mut Input, local: &ClientLocalState) {
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    let status = |action: Action| match action {
+        Action::StreamMode => Some(on_off(local.render.stream_mode)),
+        Action::MentionSound => Some(on_off(local.settings.ping_sound)),
+        Action::ColorMode => Some(local.settings.color_mode.name()),
+        Action::EventMode => Some(on_off(local.render.event_mode)),
+        Action::ReducedMotion => Some(on_off(local.settings.reduced_motion)),
+        Action::LabelLayout => Some(local.settings.label_layout.name()),
+        Action::DebugOverlay => Some(on_off(local.render.debug.enabled)),
+        _ => None,
+    };
+
+    let width = framebuffer.dimensions().width as i64;
+    let height = framebuffer.dimensions().height as i64;
+    framebuffer.draw_rect(
+        Rect::new(
+            Position::new(width / 2 - HELP_WIDTH - 10, 10),
+            Position::new(width / 2 + HELP_WIDTH + 10, height - 10),
+        ),
+        HELP_BG,
+    );
+
+    let mut keys_ui = UIFrame::new_stateless(Direction::TopToBottom);
+    keys_ui.draw_frame(
+        framebuffer,
+        Rect::new(
+            Position::new(width / 2 - HELP_WIDTH, 15),
+            Position::new(width / 2 - 5, height - 15),
+        ),
+        input,
+        |ui| {
+            ui.label::<font::Cozette>("controls");
+            for binding in controls::KEYBINDINGS {
+                let mut line = format!(
+                    "{}: {}",
+                    controls::key_names(binding.action),
+                    binding.description
+                );
+                if let Some(status) = status(binding.action) {
+                    line.push_str(&format!(" ({})", status));
                 }
-            });
+                ui.label::<font::Glean>(&line);
+            }
+        },
+    );
+
+    let mut commands_ui = UIFrame::new_stateless(Direction::TopToBottom);
+    commands_ui.draw_frame(
+        framebuffer,
+        Rect::new(
+            Position::new(width / 2 + 5, 15),
+            Position::new(width / 2 + HELP_WIDTH, height - 15),
+        ),
+        input,
+        |ui| {
+            ui.label::<font::Cozette>("chat commands");
+            for command in controls::COMMANDS {
+                ui.label::<font::Glean>(&format!(
+                    "{}: {}",
+                    controls::usage(command),
+                    command.description
+                ));
+            }
+            ui.label::<font::Glean>(&format!(
+                "{} or esc to close",
+                controls::key_names(Action::Help)
+            ));
+        },
+    );
+}
+
+/// darken everything outside of a circle around `center`
+fn draw_fog(framebuffer: &mut Framebuffer, center: Position, radius: i64) {
+    let width = framebuffer.dimensions().width as i64;
+    let height = framebuffer.dimensions().height as i64;
+    let fog = Color::new(0, 0, 0);
+
+    for y in 0..height {
+        let dy = (y - center.y).abs();
+        if dy >= radius {
+            framebuffer.draw_rect(
+                Rect::new(Position::new(0, y), Position::new(width, y + 1)),
+                fog,
+            );
+            continue;
         }
+
+        let half_span = ((radius * radius - dy * dy) as f32).sqrt() as i64;
+        framebuffer.draw_rect(
+            Rect::new(
+                Position::new(0, y),
+                Position::new(center.x - half_span, y + 1),
+            ),
+            fog,
+        );
+        framebuffer.draw_rect(
+            Rect::new(
+                Position::new(center.x + half_span, y),
+                Position::new(width, y + 1),
+            ),
+            fog,
+        );
     }
 }
+
+/// whether the message contains an `@name` mention of the given name
+fn mentions(message: &str, name: &str) -> bool {
+    let message = message.to_lowercase();
+    let name = name.to_lowercase();
+    message.match_indices('@').any(|(i, _)| {
+        let rest = &message[i + 1..];
+        rest.starts_with(&name)
+            && !rest[name.len()..]
+                .chars()
+                .next()
+                .map_or(false, |c| c.is_alphanumeric())
+    })
+}
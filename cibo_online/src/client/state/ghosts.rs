@@ -0,0 +1,135 @@
+//! fake players that only exist on this client, to see how crowded spots render without running a
+//! load test. `/ghosts 200` spawns them around the player, `/ghosts 0` removes them again.
+//! only compiled with the `debug_ghosts` feature
+
+use super::ClientGameState;
+use crate::{
+    client::{Client, ClientId, MoveDirection},
+    server::ChatId,
+};
+use alloc::format;
+use monos_gfx::Position;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+const MAX_GHOSTS: usize = 2000;
+/// ghosts get the ids from here on up, real ids count up from 0 and never get this far
+const FIRST_GHOST_ID: u32 = u32::MAX - MAX_GHOSTS as u32;
+/// how far from the player ghosts spawn, in world pixels
+const SPAWN_RADIUS: i64 = 400;
+/// how long ghosts keep walking the same way
+const TURN_INTERVAL_MS: u64 = 1500;
+/// chance that a ghost says something whenever they turn
+const CHAT_CHANCE: f64 = 0.05;
+const CHAT_LINES: &[&str] = &[
+    "boo",
+    "hi!",
+    "is anyone here?",
+    "this is a much longer message to see how wrapped chat bubbles stack up in a crowd",
+    ":3",
+];
+
+#[derive(Debug)]
+pub(super) struct Ghosts {
+    count: usize,
+    next_turn: u64,
+    next_chat_id: u64,
+    rng: SmallRng,
+}
+
+impl Default for Ghosts {
+    fn default() -> Self {
+        Ghosts {
+            count: 0,
+            next_turn: 0,
+            next_chat_id: 0,
+            rng: SmallRng::seed_from_u64(0),
+        }
+    }
+}
+
+fn is_ghost(id: ClientId) -> bool {
+    id.0 >= FIRST_GHOST_ID
+}
+
+impl ClientGameState {
+    /// spawn the ghosts asked for with `/ghosts` and give the current ones a new direction now
+    /// and then. moving them is left to the usual movement prediction
+    pub(super) fn update_ghosts(&mut self) {
+        let request = self
+            .local()
+            .world
+            .own_local
+            .borrow_mut()
+            .ghost_request
+            .take();
+        if let Some(count) = request {
+            self.spawn_ghosts(count.min(MAX_GHOSTS));
+        }
+
+        let tuning = self.world.tuning;
+        let local = self.local.as_mut().unwrap();
+        let ghosts = &mut local.ghosts;
+        if ghosts.count == 0 || local.time_ms < ghosts.next_turn {
+            return;
+        }
+        ghosts.next_turn = local.time_ms + TURN_INTERVAL_MS;
+
+        for client in self.world.clients.iter_mut().filter(|c| is_ghost(c.id())) {
+            client.movement = match ghosts.rng.gen_range(0..5) {
+                0 => MoveDirection::Up,
+                1 => MoveDirection::Down,
+                2 => MoveDirection::Left,
+                3 => MoveDirection::Right,
+                _ => MoveDirection::None,
+            };
+            if client.movement != MoveDirection::None {
+                client.look_direction = client.movement;
+            }
+
+            if ghosts.rng.gen_bool(CHAT_CHANCE) {
+                let message = CHAT_LINES[ghosts.rng.gen_range(0..CHAT_LINES.len())];
+                // counting down so they never match a real chat id
+                let chat_id = ChatId::from_u64(u64::MAX - ghosts.next_chat_id);
+                ghosts.next_chat_id += 1;
+                local.world.add_chat(
+                    client.id(),
+                    chat_id,
+                    message.into(),
+                    local.time_ms,
+                    tuning.chat_duration_ms(message),
+                );
+            }
+        }
+    }
+
+    fn spawn_ghosts(&mut self, count: usize) {
+        let center = self.client().position;
+        self.world.clients.retain(|c| !is_ghost(c.id()));
+
+        let local = self.local.as_mut().unwrap();
+        local.world.clients.retain(|(id, _)| !is_ghost(*id));
+        let ghosts = &mut local.ghosts;
+        ghosts.count = count;
+        ghosts.next_turn = local.time_ms;
+
+        for i in 0..count {
+            let id = ClientId(FIRST_GHOST_ID + i as u32);
+            let offset = Position::new(
+                ghosts.rng.gen_range(-SPAWN_RADIUS..=SPAWN_RADIUS),
+                ghosts.rng.gen_range(-SPAWN_RADIUS..=SPAWN_RADIUS),
+            );
+            self.world
+                .clients
+                .push(Client::new(id, format!("ghost {}", i + 1), center + offset));
+            local.world.clients.push((id, Default::default()));
+        }
+
+        local.log(
+            match count {
+                0 => "* removed all ghosts".into(),
+                count => format!("* spawned {} ghosts", count),
+            },
+            false,
+        );
+    }
+}
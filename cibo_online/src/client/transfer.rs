@@ -0,0 +1,111 @@
+//! the panel shown after `/transfer`, with the code for continuing on another device and a qr code
+//! of the link that opens the game with it.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use monos_gfx::{
+    input::Input,
+    text::font,
+    ui::{Direction, UIFrame},
+    Color, Framebuffer, Position, Rect,
+};
+use qrcodegen_no_heap::{QrCode, QrCodeEcc, Version};
+
+const PANEL_WIDTH: i64 = 120;
+const PANEL_BG: Color = Color::new(30, 30, 40);
+/// room left for the text above the qr code
+const TEXT_HEIGHT: i64 = 90;
+/// the qr code is drawn dark on light, scanners don't like it the other way around
+const QR_DARK: Color = Color::new(0, 0, 0);
+const QR_LIGHT: Color = Color::new(255, 255, 255);
+/// in modules, scanners need some empty space around the code
+const QR_QUIET_ZONE: i64 = 2;
+/// fits links of more than 200 characters
+const QR_MAX_VERSION: Version = Version::new(10);
+
+#[derive(Debug)]
+pub(crate) struct TransferPanel {
+    code: String,
+    /// size and modules of the qr code, row by row. `None` if the server doesn't know its address
+    qr: Option<(usize, Vec<bool>)>,
+}
+
+impl TransferPanel {
+    pub fn new(code: String, link: Option<String>) -> Self {
+        let qr = link.and_then(|link| {
+            let mut temp = vec![0; QR_MAX_VERSION.buffer_len()];
+            let mut out = vec![0; QR_MAX_VERSION.buffer_len()];
+            let qr = QrCode::encode_text(
+                &link,
+                &mut temp,
+                &mut out,
+                QrCodeEcc::Low,
+                Version::MIN,
+                QR_MAX_VERSION,
+                None,
+                true,
+            )
+            .ok()?;
+            let size = qr.size();
+            let modules = (0..size)
+                .flat_map(|y| (0..size).map(move |x| (x, y)))
+                .map(|(x, y)| qr.get_module(x, y))
+                .collect();
+            Some((size as usize, modules))
+        });
+        TransferPanel { code, qr }
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, input: &mut Input) {
+        let width = framebuffer.dimensions().width as i64;
+        let height = framebuffer.dimensions().height as i64;
+        let rect = Rect::new(
+            Position::new(width / 2 - PANEL_WIDTH, 10),
+            Position::new(width / 2 + PANEL_WIDTH, height - 10),
+        );
+        framebuffer.draw_rect(rect, PANEL_BG);
+
+        let mut ui = UIFrame::new_stateless(Direction::TopToBottom);
+        ui.draw_frame(
+            framebuffer,
+            Rect::new(
+                rect.min + Position::new(5, 5),
+                rect.max - Position::new(5, 5),
+            ),
+            input,
+            |ui| {
+                ui.label::<font::Cozette>("continue on another device");
+                ui.label::<font::Glean>(&format!("your code: {}", self.code));
+                if self.qr.is_some() {
+                    ui.label::<font::Glean>("scan the code below or open the game there with");
+                } else {
+                    ui.label::<font::Glean>("open the game on the other device with");
+                }
+                ui.label::<font::Glean>(&format!("?transfer={}", self.code));
+                ui.label::<font::Glean>("it works once and runs out in a few minutes.");
+                ui.label::<font::Glean>("esc to close");
+            },
+        );
+
+        let Some((size, modules)) = &self.qr else {
+            return;
+        };
+        let qr_top = rect.min.y + TEXT_HEIGHT;
+        let space = (rect.max.y - qr_top - 5).min(rect.max.x - rect.min.x - 10);
+        let total = *size as i64 + QR_QUIET_ZONE * 2;
+        let scale = (space / total).max(1);
+        let origin = Position::new((rect.min.x + rect.max.x) / 2 - total * scale / 2, qr_top);
+        framebuffer.draw_rect(
+            Rect::new(origin, origin + Position::new(total * scale, total * scale)),
+            QR_LIGHT,
+        );
+        for (i, dark) in modules.iter().enumerate() {
+            if !dark {
+                continue;
+            }
+            let x = (i % size) as i64 + QR_QUIET_ZONE;
+            let y = (i / size) as i64 + QR_QUIET_ZONE;
+            let min = origin + Position::new(x * scale, y * scale);
+            framebuffer.draw_rect(Rect::new(min, min + Position::new(scale, scale)), QR_DARK);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! hints for players that join for the first time. the client keeps track of how far they got,
+//! so the tutorial only shows up once per device.
+
+use super::controls::{self, Action};
+use alloc::{format, string::String, vec::Vec};
+use micromath::F32Ext;
+use monos_gfx::{
+    input::Input,
+    text::font,
+    ui::{Direction, MarginMode, UIFrame},
+    Color, Framebuffer, Position, Rect,
+};
+use serde::{Deserialize, Serialize};
+
+const ARROW_COLOR: Color = Color::new(250, 200, 60);
+/// how far from the center of the player the tip of the arrow is
+const ARROW_DISTANCE: f32 = 40.0;
+const ARROW_LENGTH: f32 = 10.0;
+const ARROW_WIDTH: f32 = 6.0;
+/// targets closer than this don't need an arrow anymore
+const ARROW_HIDE_DISTANCE: i64 = 48;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TutorialStep {
+    #[default]
+    Move,
+    OpenChat,
+    MessageBoard,
+    Beach,
+    Done,
+}
+
+impl TutorialStep {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
+        postcard::to_allocvec(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, postcard::Error> {
+        postcard::from_bytes(bytes)
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            TutorialStep::Move => TutorialStep::OpenChat,
+            TutorialStep::OpenChat => TutorialStep::MessageBoard,
+            TutorialStep::MessageBoard => TutorialStep::Beach,
+            TutorialStep::Beach | TutorialStep::Done => TutorialStep::Done,
+        }
+    }
+
+    fn completed_by(&self) -> Option<TutorialEvent> {
+        match self {
+            TutorialStep::Move => Some(TutorialEvent::Moved),
+            TutorialStep::OpenChat => Some(TutorialEvent::OpenedChat),
+            TutorialStep::MessageBoard => Some(TutorialEvent::ReadMessageBoard),
+            TutorialStep::Beach => Some(TutorialEvent::ReachedBeach),
+            TutorialStep::Done => None,
+        }
+    }
+
+    fn hint(&self) -> String {
+        match self {
+            TutorialStep::Move => String::from("walk around with wasd or the arrow keys"),
+            TutorialStep::OpenChat => {
+                format!("press {} to chat", controls::key_names(Action::Chat))
+            }
+            TutorialStep::MessageBoard => {
+                String::from("walk up to the message board and press e to read it")
+            }
+            TutorialStep::Beach => String::from("the beach is down south, go have a look!"),
+            TutorialStep::Done => String::new(),
+        }
+    }
+}
+
+/// something the player did that could complete a step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TutorialEvent {
+    Moved,
+    OpenedChat,
+    ReadMessageBoard,
+    ReachedBeach,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tutorial {
+    step: TutorialStep,
+    /// the step changed and wasn't saved yet
+    changed: bool,
+}
+
+impl Tutorial {
+    pub fn step(&self) -> TutorialStep {
+        self.step
+    }
+
+    /// continue where the player left off last time
+    pub fn restore(&mut self, step: TutorialStep) {
+        self.step = step;
+        self.changed = false;
+    }
+
+    pub fn handle(&mut self, event: TutorialEvent) {
+        if self.step.completed_by() == Some(event) {
+            self.step = self.step.next();
+            self.changed = true;
+        }
+    }
+
+    pub fn take_changed(&mut self) -> Option<TutorialStep> {
+        core::mem::take(&mut self.changed).then_some(self.step)
+    }
+
+    /// draw the hint for the current step, with an arrow from the player towards `target` if there is one.
+    /// both are in screen space
+    pub fn render(
+        &self,
+        fb: &mut Framebuffer,
+        input: &mut Input,
+        player: Position,
+        target: Option<Position>,
+    ) {
+        if self.step == TutorialStep::Done {
+            return;
+        }
+
+        let width = fb.dimensions().width as i64;
+        let height = fb.dimensions().height as i64;
+        let hint_rect = Rect::new(
+            Position::new(width / 2 - 160, height - 45),
+            Position::new(width / 2 + 160, height - 10),
+        );
+        let mut ui = UIFrame::new_stateless(Direction::TopToBottom);
+        ui.draw_frame(fb, hint_rect, input, |ui| {
+            ui.margin(MarginMode::Grow);
+            ui.label::<font::Glean>(&self.step.hint());
+        });
+
+        if let Some(target) = target {
+            draw_arrow(fb, player, target);
+        }
+    }
+}
+
+/// a small triangle next to `from`, pointing at `to`
+fn draw_arrow(fb: &mut Framebuffer, from: Position, to: Position) {
+    let offset = to - from;
+    if offset.x.abs() < ARROW_HIDE_DISTANCE && offset.y.abs() < ARROW_HIDE_DISTANCE {
+        return;
+    }
+
+    let length = ((offset.x * offset.x + offset.y * offset.y) as f32).sqrt();
+    let (dx, dy) = (offset.x as f32 / length, offset.y as f32 / length);
+    let point = |along: f32, across: f32| {
+        (
+            from.x as f32 + dx * along - dy * across,
+            from.y as f32 + dy * along + dx * across,
+        )
+    };
+    let tip = point(ARROW_DISTANCE, 0.0);
+    let left = point(ARROW_DISTANCE - ARROW_LENGTH, ARROW_WIDTH);
+    let right = point(ARROW_DISTANCE - ARROW_LENGTH, -ARROW_WIDTH);
+
+    // which side of the edge from `a` to `b` the point is on
+    let side = |a: (f32, f32), b: (f32, f32), p: (f32, f32)| {
+        (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+    };
+
+    let min_x = tip.0.min(left.0).min(right.0).floor() as i64;
+    let max_x = tip.0.max(left.0).max(right.0).ceil() as i64;
+    let min_y = tip.1.min(left.1).min(right.1).floor() as i64;
+    let max_y = tip.1.max(left.1).max(right.1).ceil() as i64;
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let p = (x as f32 + 0.5, y as f32 + 0.5);
+            let sides = [
+                side(tip, left, p),
+                side(left, right, p),
+                side(right, tip, p),
+            ];
+            let inside = sides.iter().all(|s| *s >= 0.0) || sides.iter().all(|s| *s <= 0.0);
+            if inside {
+                let pos = Position::new(x, y);
+                fb.draw_rect(Rect::new(pos, pos + Position::new(1, 1)), ARROW_COLOR);
+            }
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! comparing the world a client sees with the one on the server, to track down desyncs. the server
+//! asks a client with `ServerMessage::RequestSnapshot`, the client answers with
+//! `ClientMessage::WorldSnapshot` and the server diffs it against a snapshot of its own.
+
+use crate::{ClientId, Elevation, Object, ObjectId, PlaceableObject, WorldState};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use monos_gfx::Position;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientSnapshot {
+    pub id: ClientId,
+    pub name: String,
+    pub position: Position,
+    pub elevation: Elevation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectSnapshot {
+    pub id: ObjectId,
+    /// `None` for objects that can't be placed with the editor, like the ones of minigames
+    pub kind: Option<PlaceableObject>,
+    pub position: Position,
+}
+
+/// the parts of the world that clients and the server should agree on, sorted by id
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub clients: Vec<ClientSnapshot>,
+    pub objects: Vec<ObjectSnapshot>,
+}
+
+impl WorldState {
+    pub(crate) fn snapshot(&self) -> WorldSnapshot {
+        let mut clients = self
+            .clients
+            .iter()
+            .map(|client| ClientSnapshot {
+                id: client.id(),
+                name: client.name().into(),
+                position: client.position,
+                elevation: client.elevation,
+            })
+            .collect::<Vec<_>>();
+        clients.sort_by_key(|client| client.id);
+
+        let mut objects = self
+            .network_objects
+            .iter()
+            .map(|(id, object)| ObjectSnapshot {
+                id: *id,
+                kind: PlaceableObject::ALL
+                    .into_iter()
+                    .find(|kind| kind.network_object_id() == object.id()),
+                position: object.properties().position,
+            })
+            .collect::<Vec<_>>();
+        objects.sort_by_key(|object| object.id);
+
+        WorldSnapshot { clients, objects }
+    }
+}
+
+/// something the client and the server don't agree on
+#[derive(Debug, Clone)]
+pub enum Difference {
+    /// the server has a player the client doesn't know about
+    MissingClient(ClientSnapshot),
+    /// the client still shows a player the server doesn't have
+    ExtraClient(ClientSnapshot),
+    ClientPosition {
+        server: ClientSnapshot,
+        client: Position,
+    },
+    ClientElevation {
+        server: ClientSnapshot,
+        client: Elevation,
+    },
+    MissingObject(ObjectSnapshot),
+    ExtraObject(ObjectSnapshot),
+    ObjectPosition {
+        server: ObjectSnapshot,
+        client: Position,
+    },
+    /// the same id belongs to different objects
+    ObjectKind {
+        server: ObjectSnapshot,
+        client: Option<PlaceableObject>,
+    },
+}
+
+fn distance(a: Position, b: Position) -> i64 {
+    (a.x - b.x).abs() + (a.y - b.y).abs()
+}
+
+fn kind_name(kind: Option<PlaceableObject>) -> &'static str {
+    kind.map_or("object", |kind| kind.name())
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::MissingClient(client) => write!(
+                f,
+                "player '{}' ({}) is missing on the client",
+                client.name,
+                client.id.as_u32()
+            ),
+            Difference::ExtraClient(client) => write!(
+                f,
+                "player '{}' ({}) is only on the client",
+                client.name,
+                client.id.as_u32()
+            ),
+            Difference::ClientPosition { server, client } => write!(
+                f,
+                "player '{}' is at {},{} on the server but at {},{} on the client",
+                server.name, server.position.x, server.position.y, client.x, client.y
+            ),
+            Difference::ClientElevation { server, client } => write!(
+                f,
+                "player '{}' is {:?} on the server but {:?} on the client",
+                server.name, server.elevation, client
+            ),
+            Difference::MissingObject(object) => write!(
+                f,
+                "{} {} is missing on the client",
+                kind_name(object.kind),
+                object.id.as_u32()
+            ),
+            Difference::ExtraObject(object) => write!(
+                f,
+                "{} {} is only on the client",
+                kind_name(object.kind),
+                object.id.as_u32()
+            ),
+            Difference::ObjectPosition { server, client } => write!(
+                f,
+                "{} {} is at {},{} on the server but at {},{} on the client",
+                kind_name(server.kind),
+                server.id.as_u32(),
+                server.position.x,
+                server.position.y,
+                client.x,
+                client.y
+            ),
+            Difference::ObjectKind { server, client } => write!(
+                f,
+                "object {} is a {} on the server but a {} on the client",
+                server.id.as_u32(),
+                kind_name(server.kind),
+                kind_name(*client)
+            ),
+        }
+    }
+}
+
+/// everything the two snapshots don't agree on. positions that are at most `tolerance` pixels
+/// apart are fine, the client is always a bit behind for things that move
+pub fn diff(server: &WorldSnapshot, client: &WorldSnapshot, tolerance: i64) -> Vec<Difference> {
+    let mut differences = Vec::new();
+
+    for server_client in &server.clients {
+        let Some(client_client) = client.clients.iter().find(|c| c.id == server_client.id) else {
+            differences.push(Difference::MissingClient(server_client.clone()));
+            continue;
+        };
+        if distance(server_client.position, client_client.position) > tolerance {
+            differences.push(Difference::ClientPosition {
+                server: server_client.clone(),
+                client: client_client.position,
+            });
+        }
+        if server_client.elevation != client_client.elevation {
+            differences.push(Difference::ClientElevation {
+                server: server_client.clone(),
+                client: client_client.elevation,
+            });
+        }
+    }
+    differences.extend(
+        client
+            .clients
+            .iter()
+            .filter(|c| !server.clients.iter().any(|s| s.id == c.id))
+            .cloned()
+            .map(Difference::ExtraClient),
+    );
+
+    for server_object in &server.objects {
+        let Some(client_object) = client.objects.iter().find(|o| o.id == server_object.id) else {
+            differences.push(Difference::MissingObject(server_object.clone()));
+            continue;
+        };
+        if server_object.kind != client_object.kind {
+            differences.push(Difference::ObjectKind {
+                server: server_object.clone(),
+                client: client_object.kind,
+            });
+        } else if distance(server_object.position, client_object.position) > tolerance {
+            differences.push(Difference::ObjectPosition {
+                server: server_object.clone(),
+                client: client_object.position,
+            });
+        }
+    }
+    differences.extend(
+        client
+            .objects
+            .iter()
+            .filter(|o| !server.objects.iter().any(|s| s.id == o.id))
+            .cloned()
+            .map(Difference::ExtraObject),
+    );
+
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    fn player(id: ClientId, x: i64) -> ClientSnapshot {
+        ClientSnapshot {
+            id,
+            name: "player".to_string(),
+            position: Position::new(x, 0),
+            elevation: Elevation::Ground,
+        }
+    }
+
+    fn object(id: u32, kind: PlaceableObject, x: i64) -> ObjectSnapshot {
+        ObjectSnapshot {
+            id: ObjectId::from_u32(id),
+            kind: Some(kind),
+            position: Position::new(x, 0),
+        }
+    }
+
+    #[test]
+    fn same_world_has_no_differences() {
+        let world = WorldSnapshot {
+            clients: vec![player(ClientId::new(), 0), player(ClientId::new(), 50)],
+            objects: vec![object(1, PlaceableObject::Easel, 10)],
+        };
+        assert!(diff(&world, &world.clone(), 0).is_empty());
+    }
+
+    #[test]
+    fn finds_missing_and_extra_entries() {
+        let (both, server_only, client_only) = (ClientId::new(), ClientId::new(), ClientId::new());
+        let server = WorldSnapshot {
+            clients: vec![player(both, 0), player(server_only, 0)],
+            objects: vec![object(1, PlaceableObject::Easel, 0)],
+        };
+        let client = WorldSnapshot {
+            clients: vec![player(both, 0), player(client_only, 0)],
+            objects: vec![object(2, PlaceableObject::Easel, 0)],
+        };
+        let differences = diff(&server, &client, 0);
+        assert_eq!(differences.len(), 4);
+        assert!(matches!(&differences[0], Difference::MissingClient(c) if c.id == server_only));
+        assert!(matches!(&differences[1], Difference::ExtraClient(c) if c.id == client_only));
+        assert!(matches!(&differences[2], Difference::MissingObject(o) if o.id.as_u32() == 1));
+        assert!(matches!(&differences[3], Difference::ExtraObject(o) if o.id.as_u32() == 2));
+    }
+
+    #[test]
+    fn positions_within_tolerance_match() {
+        let id = ClientId::new();
+        let server = WorldSnapshot {
+            clients: vec![player(id, 0)],
+            objects: vec![object(1, PlaceableObject::Easel, 0)],
+        };
+        let client = WorldSnapshot {
+            clients: vec![player(id, 4)],
+            objects: vec![object(1, PlaceableObject::Easel, 20)],
+        };
+        let differences = diff(&server, &client, 8);
+        assert_eq!(differences.len(), 1);
+        assert!(matches!(
+            &differences[0],
+            Difference::ObjectPosition { client, .. } if client.x == 20
+        ));
+    }
+
+    #[test]
+    fn different_kinds_are_not_compared_by_position() {
+        let server = WorldSnapshot {
+            objects: vec![object(1, PlaceableObject::Easel, 0)],
+            ..Default::default()
+        };
+        let client = WorldSnapshot {
+            objects: vec![object(1, PlaceableObject::BeachBall, 100)],
+            ..Default::default()
+        };
+        let differences = diff(&server, &client, 0);
+        assert_eq!(differences.len(), 1);
+        assert!(matches!(&differences[0], Difference::ObjectKind { .. }));
+    }
+}
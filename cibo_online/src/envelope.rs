@@ -0,0 +1,60 @@
+//! forward compatible framing for network messages.
+//!
+//! every message is sent as `[tag][length][payload]`, with the tag and length as varints.
+//! the tag is the index of the enum variant and the payload its postcard encoded fields.
+//! because the length is known up front, a receiver running an older version can skip
+//! messages it doesn't understand instead of misreading them.
+
+use alloc::vec::Vec;
+use serde::{de::DeserializeOwned, Serialize};
+
+#[derive(Debug)]
+pub enum EnvelopeError {
+    /// the framing itself is broken
+    Malformed,
+    /// the message couldn't be decoded, most likely because it was sent by a newer version.
+    /// it can safely be skipped.
+    Unknown { tag: u32, error: postcard::Error },
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    let mut value = 0u32;
+    for (i, byte) in bytes.iter().enumerate().take(5) {
+        value |= ((byte & 0x7f) as u32) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+pub(crate) fn to_bytes<T: Serialize>(msg: &T) -> Result<Vec<u8>, postcard::Error> {
+    let encoded = postcard::to_allocvec(msg)?;
+    // postcard starts every enum with the variant index
+    let (tag, payload) = read_varint(&encoded).ok_or(postcard::Error::SerializeBufferFull)?;
+
+    let mut bytes = Vec::with_capacity(payload.len() + 10);
+    write_varint(&mut bytes, tag);
+    write_varint(&mut bytes, payload.len() as u32);
+    bytes.extend_from_slice(payload);
+    Ok(bytes)
+}
+
+pub(crate) fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, EnvelopeError> {
+    let (tag, rest) = read_varint(bytes).ok_or(EnvelopeError::Malformed)?;
+    let (len, rest) = read_varint(rest).ok_or(EnvelopeError::Malformed)?;
+    let payload = rest.get(..len as usize).ok_or(EnvelopeError::Malformed)?;
+
+    let mut encoded = Vec::with_capacity(payload.len() + 5);
+    write_varint(&mut encoded, tag);
+    encoded.extend_from_slice(payload);
+    postcard::from_bytes(&encoded).map_err(|error| EnvelopeError::Unknown { tag, error })
+}
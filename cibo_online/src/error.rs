@@ -0,0 +1,59 @@
+//! the errors public entry points return instead of panicking, so the server can log them and
+//! clients can show them
+
+use crate::{ClientId, EnvelopeError, ObjectId};
+use alloc::{boxed::Box, string::String};
+use core::fmt;
+
+#[derive(Debug)]
+pub enum CiboError {
+    /// a network message couldn't be read
+    Envelope(EnvelopeError),
+    /// game state or object data couldn't be encoded or decoded
+    Serialization(postcard::Error),
+    /// the compressed game state is broken
+    Decompression,
+    /// the game state sent to a client doesn't contain the client itself
+    MissingOwnClient(ClientId),
+    /// an object couldn't make sense of a message meant for it
+    Object { id: ObjectId, error: Box<CiboError> },
+    /// a message arrived that its receiver doesn't take, e.g. a second full state
+    UnexpectedMessage(&'static str),
+    /// a file in an asset pack can't be used
+    AssetPack { file: String, problem: &'static str },
+}
+
+impl fmt::Display for CiboError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CiboError::Envelope(EnvelopeError::Malformed) => write!(f, "malformed message"),
+            CiboError::Envelope(EnvelopeError::Unknown { tag, error }) => {
+                write!(f, "unknown message with tag {}: {}", tag, error)
+            }
+            CiboError::Serialization(error) => write!(f, "failed to (de)serialize: {}", error),
+            CiboError::Decompression => write!(f, "failed to decompress the game state"),
+            CiboError::MissingOwnClient(id) => {
+                write!(f, "game state is missing own client {}", id.as_u32())
+            }
+            CiboError::Object { id, error } => {
+                write!(f, "object {:?} rejected a message: {}", id, error)
+            }
+            CiboError::UnexpectedMessage(kind) => write!(f, "unexpected {} message", kind),
+            CiboError::AssetPack { file, problem } => {
+                write!(f, "asset pack file {} {}", file, problem)
+            }
+        }
+    }
+}
+
+impl From<EnvelopeError> for CiboError {
+    fn from(error: EnvelopeError) -> Self {
+        CiboError::Envelope(error)
+    }
+}
+
+impl From<postcard::Error> for CiboError {
+    fn from(error: postcard::Error) -> Self {
+        CiboError::Serialization(error)
+    }
+}
@@ -2,32 +2,78 @@
 #![feature(trait_upcasting)]
 
 extern crate alloc;
+#[cfg(test)]
+extern crate std;
 
 mod world;
+pub use world::behavior;
 pub(crate) use world::{
-    get_network_object_id, BoxedNetworkObject, CollisionInfo, CollisionTester, NetworkObject,
-    NetworkObjectId, Object, ObjectId, ObjectProperties, WorldLocalState, WorldState,
+    get_network_object_id, BoxedNetworkObject, CollisionInfo, CollisionTester, InteractionCooldown,
+    NetworkObject, NetworkObjectId, Object, ObjectMessage, ObjectProperties, WorldLocalState,
+    WorldState, INTERACTION_COOLDOWN_MS,
+};
+pub use world::{
+    vec_from_f32, vec_to_f32, ChalkColor, ChalkStroke, Critter, Elevation, Fixed, GameTuning,
+    HideAndSeekPhase, HideAndSeekState, Minigame, MinigameKind, ObjectId, Painting, Physics,
+    PhysicsParam, PlaceableObject, Poll, Scalar, ScalarExt, ShopItem, Standings, TagState,
+    TuningParam, DEFAULT_BOARD_TEXT, POLL_OPTION_LIMIT, TROPHY_PLACES,
 };
 
 mod render;
 use render::{widgets, Assets, RectExt, RenderContext, Renderable, Sprite, ZOrder};
+pub use render::{ColorMode, Palette};
 
 pub mod client;
 pub use client::{Client, ClientAction, ClientId};
 
+pub mod asset_pack;
+
 pub mod server;
 
+pub mod debug;
+
+pub mod links;
+
+pub mod moderation;
+
+pub mod names;
+
+pub mod webhook;
+
+mod envelope;
+pub use envelope::EnvelopeError;
+
+mod error;
+pub use error::CiboError;
+
+pub use monos_gfx::Position;
+
+// safety: this assumes that the crate is only used in a single-threaded environment
+static mut ASSETS: Option<Assets> = None;
+
 fn assets() -> &'static Assets {
-    // safety: this assumes that the crate is only used in a single-threaded environment
-    static mut ASSETS: Option<Assets> = None;
     unsafe { ASSETS.get_or_insert_with(|| Assets::new()) }
 }
 
+/// replace built-in art with the images of an asset pack, returns how many were replaced
+pub fn load_asset_pack(bytes: &[u8]) -> Result<usize, CiboError> {
+    let pack = asset_pack::AssetPack::from_bytes(bytes)?;
+    // safety: see `assets`, nothing holds on to an asset between frames
+    unsafe { ASSETS.get_or_insert_with(|| Assets::new()) }.load_pack(&pack)
+}
+
 pub fn setup_network_objects() {
     world::objects::setup_network_objects();
 }
 
 pub const SERVER_TICK_RATE: u64 = 1000 / 60;
 pub const MESSAGE_LIMIT: usize = 100;
+/// cut a chat message to `MESSAGE_LIMIT` characters, like the chat input does. messages come
+/// from the network, so this has to cut between characters instead of at a byte index
+pub fn clip_message(message: &mut alloc::string::String) {
+    if let Some((end, _)) = message.char_indices().nth(MESSAGE_LIMIT) {
+        message.truncate(end);
+    }
+}
 pub const NAME_LIMIT: usize = 16;
 pub const BASE_ANIM_SPEED: usize = 250;
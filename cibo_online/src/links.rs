@@ -0,0 +1,73 @@
+//! finding links in chat messages. the server decides which ones are let through, the client
+//! highlights the ones that made it and opens them when they are clicked.
+
+use alloc::{format, string::String, vec::Vec};
+use core::ops::Range;
+
+/// punctuation that usually ends a sentence rather than a link
+const TRAILING: &[char] = &['.', ',', '!', '?', ':', ';', ')', '\'', '"'];
+
+/// byte ranges of everything in the text that looks like a link
+pub fn find_links(text: &str) -> Vec<Range<usize>> {
+    let mut links = Vec::new();
+    let mut start = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(core::iter::once((text.len(), ' ')))
+    {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(word_start)) => {
+                let word = text[word_start..i].trim_end_matches(TRAILING);
+                if domain(word).is_some() {
+                    links.push(word_start..word_start + word.len());
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    links
+}
+
+/// the domain of a link, e.g. `example.com` for `https://example.com/page`
+pub fn domain(link: &str) -> Option<&str> {
+    let rest = link
+        .strip_prefix("https://")
+        .or_else(|| link.strip_prefix("http://"))
+        .unwrap_or(link);
+    let domain = rest
+        .split(['/', '?', '#', ':'])
+        .next()
+        .filter(|domain| !domain.is_empty())?;
+
+    let mut labels = domain.split('.');
+    let tld = labels.next_back()?;
+    let valid_label = |label: &str| {
+        !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    // at least one dot, and a top level domain that could be real. keeps things like `e.g.` or `1.5` out
+    let is_domain = domain.contains('.')
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+        && labels.all(valid_label);
+    is_domain.then_some(domain)
+}
+
+/// whether the domain is one of the allowed ones or a subdomain of them
+pub fn domain_allowed(domain: &str, allowed: &[String]) -> bool {
+    let domain = domain.to_lowercase();
+    allowed.iter().any(|allowed| {
+        let allowed = allowed.to_lowercase();
+        domain == allowed || domain.ends_with(&format!(".{}", allowed))
+    })
+}
+
+/// something a browser can open, links without a scheme are assumed to be https
+pub fn url(link: &str) -> String {
+    if link.starts_with("https://") || link.starts_with("http://") {
+        String::from(link)
+    } else {
+        format!("https://{}", link)
+    }
+}
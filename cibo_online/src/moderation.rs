@@ -0,0 +1,321 @@
+//! matching banned words in chat. the server binary keeps the list of words and decides what
+//! happens to a message, this only finds them and guesses which language a message is in.
+
+use alloc::{format, string::String, vec::Vec};
+
+/// languages banned words can belong to, so whole lists can be turned on and off
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+    Portuguese,
+    Italian,
+    Dutch,
+    Polish,
+    Russian,
+    Arabic,
+    Japanese,
+    Korean,
+    Chinese,
+}
+
+impl Language {
+    pub const ALL: [Language; 13] = [
+        Language::English,
+        Language::German,
+        Language::French,
+        Language::Spanish,
+        Language::Portuguese,
+        Language::Italian,
+        Language::Dutch,
+        Language::Polish,
+        Language::Russian,
+        Language::Arabic,
+        Language::Japanese,
+        Language::Korean,
+        Language::Chinese,
+    ];
+
+    /// as stored in the database
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+            Language::French => "fr",
+            Language::Spanish => "es",
+            Language::Portuguese => "pt",
+            Language::Italian => "it",
+            Language::Dutch => "nl",
+            Language::Polish => "pl",
+            Language::Russian => "ru",
+            Language::Arabic => "ar",
+            Language::Japanese => "ja",
+            Language::Korean => "ko",
+            Language::Chinese => "zh",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "German",
+            Language::French => "French",
+            Language::Spanish => "Spanish",
+            Language::Portuguese => "Portuguese",
+            Language::Italian => "Italian",
+            Language::Dutch => "Dutch",
+            Language::Polish => "Polish",
+            Language::Russian => "Russian",
+            Language::Arabic => "Arabic",
+            Language::Japanese => "Japanese",
+            Language::Korean => "Korean",
+            Language::Chinese => "Chinese",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.code() == code)
+    }
+
+    /// short words that are common in the language and rare in the others
+    fn common_words(&self) -> &'static [&'static str] {
+        match self {
+            Language::English => &[
+                "the", "and", "you", "is", "are", "what", "this", "that", "with", "have", "i'm",
+                "it's", "my", "your",
+            ],
+            Language::German => &[
+                "und", "ich", "nicht", "das", "ist", "der", "ein", "eine", "du", "wie", "mit",
+                "auch", "bin", "bist",
+            ],
+            Language::French => &[
+                "le", "les", "et", "est", "je", "tu", "pas", "une", "des", "c'est", "pour", "avec",
+                "mais", "oui",
+            ],
+            Language::Spanish => &[
+                "el", "los", "las", "y", "por", "pero", "muy", "como", "estoy", "hola", "gracias",
+                "tengo", "eres", "soy",
+            ],
+            Language::Portuguese => &[
+                "não", "você", "eu", "um", "uma", "os", "obrigado", "muito", "tudo", "bem", "com",
+                "isso", "olá", "estou",
+            ],
+            Language::Italian => &[
+                "il", "che", "non", "sono", "della", "ciao", "grazie", "perché", "anche", "questo",
+                "io", "sei",
+            ],
+            Language::Dutch => &[
+                "het", "een", "niet", "ik", "jij", "wat", "zijn", "hoe", "dank", "goed", "ook",
+                "maar",
+            ],
+            Language::Polish => &[
+                "nie", "jest", "się", "tak", "jak", "mam", "czy", "ale", "dzięki", "cześć", "co",
+            ],
+            Language::Russian
+            | Language::Arabic
+            | Language::Japanese
+            | Language::Korean
+            | Language::Chinese => &[],
+        }
+    }
+
+    /// letters that hardly show up in the other languages
+    fn marks(&self) -> &'static [char] {
+        match self {
+            Language::German => &['ß', 'ä', 'ö', 'ü'],
+            Language::French => &['è', 'ê', 'ë', 'ù', 'œ'],
+            Language::Spanish => &['ñ', '¿', '¡'],
+            Language::Portuguese => &['ã', 'õ'],
+            Language::Polish => &['ą', 'ę', 'ł', 'ś', 'ź', 'ż', 'ń', 'ć'],
+            _ => &[],
+        }
+    }
+}
+
+impl TryFrom<String> for Language {
+    type Error = String;
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        Self::from_code(&code).ok_or_else(|| format!("unknown language '{}'", code))
+    }
+}
+
+/// the language that isn't written in latin letters a character belongs to
+fn script_of(c: char) -> Option<Language> {
+    match c {
+        '\u{0400}'..='\u{04ff}' => Some(Language::Russian),
+        '\u{0600}'..='\u{06ff}' => Some(Language::Arabic),
+        '\u{3040}'..='\u{30ff}' => Some(Language::Japanese),
+        '\u{1100}'..='\u{11ff}' | '\u{3130}'..='\u{318f}' | '\u{ac00}'..='\u{d7af}' => {
+            Some(Language::Korean)
+        }
+        '\u{4e00}'..='\u{9fff}' => Some(Language::Chinese),
+        _ => None,
+    }
+}
+
+/// guess the language of a message, `None` if it is too short or could be several. the script
+/// decides for languages that don't use latin letters, the others are told apart by common words
+/// and letters with accents
+pub fn detect_language(text: &str) -> Option<Language> {
+    let text = text.to_lowercase();
+
+    let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+    let mut scripts = Vec::<(Language, usize)>::new();
+    for language in text.chars().filter_map(script_of) {
+        match scripts.iter_mut().find(|(l, _)| *l == language) {
+            Some((_, count)) => *count += 1,
+            None => scripts.push((language, 1)),
+        }
+    }
+    let script_letters = scripts.iter().map(|(_, count)| count).sum::<usize>();
+    if script_letters * 2 > letters {
+        // japanese mixes kana with chinese characters, any kana makes it japanese
+        if scripts.iter().any(|(l, _)| *l == Language::Japanese) {
+            return Some(Language::Japanese);
+        }
+        return scripts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(language, _)| language);
+    }
+
+    let words = text
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    let mut best = None;
+    let mut best_score = 0;
+    let mut tied = false;
+    for language in Language::ALL {
+        let score = words
+            .iter()
+            .filter(|word| language.common_words().contains(word))
+            .count()
+            + text
+                .chars()
+                .filter(|c| language.marks().contains(c))
+                .count();
+        if score > best_score {
+            best = Some(language);
+            best_score = score;
+            tied = false;
+        } else if score == best_score && score > 0 {
+            tied = true;
+        }
+    }
+    best.filter(|_| !tied)
+}
+
+/// replace every character of the given words with `*`, keeping the rest of the text. matching
+/// ignores case, and words that overlap or touch are masked together
+pub fn mask_words<'a>(text: &str, words: impl IntoIterator<Item = &'a str>) -> String {
+    // lowercasing can turn one character into several, so every lowercased character remembers
+    // which character of the original text it came from
+    let mut lowered = Vec::new();
+    for (index, c) in text.chars().enumerate() {
+        lowered.extend(c.to_lowercase().map(|lower| (index, lower)));
+    }
+
+    let mut masked = alloc::vec![false; text.chars().count()];
+    for word in words {
+        let word = word
+            .chars()
+            .flat_map(char::to_lowercase)
+            .collect::<Vec<_>>();
+        if word.is_empty() || word.len() > lowered.len() {
+            continue;
+        }
+        for start in 0..=lowered.len() - word.len() {
+            let window = &lowered[start..start + word.len()];
+            if window.iter().map(|(_, c)| *c).eq(word.iter().copied()) {
+                let (first, _) = window[0];
+                let (last, _) = window[word.len() - 1];
+                masked[first..=last].fill(true);
+            }
+        }
+    }
+
+    text.chars()
+        .zip(masked)
+        .map(|(c, masked)| if masked { '*' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_only_the_word() {
+        assert_eq!(mask_words("you are a frog", ["frog"]), "you are a ****");
+        assert_eq!(mask_words("FROGS everywhere", ["frog"]), "****S everywhere");
+        assert_eq!(mask_words("nothing here", ["frog"]), "nothing here");
+    }
+
+    #[test]
+    fn overlapping_matches() {
+        // "abcd" and "cdef" share "cd"
+        assert_eq!(mask_words("xabcdefx", ["abcd", "cdef"]), "x******x");
+        // one word inside the other
+        assert_eq!(mask_words("toadstool", ["toadstool", "ads"]), "*********");
+        // repeats of the same word that overlap themselves
+        assert_eq!(mask_words("aaaa", ["aa"]), "****");
+        assert_eq!(mask_words("ababa", ["aba"]), "*****");
+    }
+
+    #[test]
+    fn keeps_characters_that_change_when_lowercased() {
+        assert_eq!(mask_words("İfrog frog", ["frog"]), "İ**** ****");
+        assert_eq!(mask_words("Ölfrog", ["öl"]), "**frog");
+    }
+
+    #[test]
+    fn detects_languages() {
+        assert_eq!(
+            detect_language("what are you doing with the ball"),
+            Some(Language::English)
+        );
+        assert_eq!(
+            detect_language("ich bin nicht müde"),
+            Some(Language::German)
+        );
+        assert_eq!(
+            detect_language("c'est pas mal, je suis là"),
+            Some(Language::French)
+        );
+        assert_eq!(
+            detect_language("hola, ¿cómo estás?"),
+            Some(Language::Spanish)
+        );
+        assert_eq!(detect_language("привет всем"), Some(Language::Russian));
+        assert_eq!(detect_language("こんにちは世界"), Some(Language::Japanese));
+        assert_eq!(detect_language("你好世界"), Some(Language::Chinese));
+    }
+
+    #[test]
+    fn unsure_without_hints() {
+        assert_eq!(detect_language("lol"), None);
+        assert_eq!(detect_language(""), None);
+        assert_eq!(detect_language(":cibo: :cibo:"), None);
+        // one english and one german word
+        assert_eq!(detect_language("the und"), None);
+    }
+
+    #[test]
+    fn language_codes_round_trip() {
+        for language in Language::ALL {
+            assert_eq!(Language::from_code(language.code()), Some(language));
+        }
+    }
+
+    #[test]
+    fn ignores_empty_words() {
+        assert_eq!(mask_words("hello", [""]), "hello");
+        assert_eq!(mask_words("", ["frog"]), "");
+    }
+}
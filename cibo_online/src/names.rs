@@ -0,0 +1,82 @@
+//! rules for player names. the join menu checks them while the player is typing, the server
+//! enforces them when the player connects.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+
+/// words for the names of players that didn't pick one. short enough that any two fit the limit
+const ADJECTIVES: &[&str] = &[
+    "Sleepy", "Happy", "Fuzzy", "Brave", "Quiet", "Lucky", "Sunny", "Tiny", "Cozy", "Jolly",
+    "Gentle", "Swift", "Mellow", "Bouncy", "Curious", "Calm", "Sparkly", "Breezy", "Humble",
+    "Plucky",
+];
+const ANIMALS: &[&str] = &[
+    "Otter", "Panda", "Fox", "Koala", "Penguin", "Owl", "Badger", "Rabbit", "Turtle", "Finch",
+    "Seal", "Lynx", "Gecko", "Moth", "Crab", "Heron", "Beaver", "Alpaca", "Puffin", "Newt",
+];
+/// how many different names `generated` can come up with
+pub const GENERATED_NAMES: usize = ADJECTIVES.len() * ANIMALS.len();
+
+/// characters that can't be seen or that change how the text around them is shown, which would
+/// let players pretend to be someone else
+pub fn banned_char(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{200b}'..='\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2060}'..='\u{2064}' | '\u{feff}'
+        )
+}
+
+/// what is wrong with a name, if anything
+pub fn problem(name: &str) -> Option<&'static str> {
+    if name.chars().count() > crate::NAME_LIMIT {
+        Some("that name is too long")
+    } else if name.chars().any(banned_char) {
+        Some("that name has characters that aren't allowed")
+    } else {
+        None
+    }
+}
+
+/// the name without banned characters, cut to the limit and trimmed. empty names stay empty
+pub fn clean(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| !banned_char(*c))
+        .take(crate::NAME_LIMIT)
+        .collect();
+    name.trim().to_string()
+}
+
+/// a friendly name like "Sleepy Otter" for players that didn't pick one. every index below
+/// `GENERATED_NAMES` gives a different one
+pub fn generated(index: usize) -> String {
+    let adjective = ADJECTIVES[index % ADJECTIVES.len()];
+    let animal = ANIMALS[index / ADJECTIVES.len() % ANIMALS.len()];
+    format!("{} {}", adjective, animal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn generated_names_are_valid() {
+        for index in 0..GENERATED_NAMES {
+            let name = generated(index);
+            assert_eq!(problem(&name), None, "{}", name);
+            assert_eq!(clean(&name), name);
+        }
+    }
+
+    #[test]
+    fn generated_names_are_unique() {
+        let mut names = (0..GENERATED_NAMES).map(generated).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), GENERATED_NAMES);
+    }
+}
@@ -0,0 +1,55 @@
+/// a sequence of frames that each last for a given amount of ms
+#[derive(Debug, Clone, Copy)]
+pub struct Animation<F: 'static> {
+    frames: &'static [(F, u64)],
+    looping: bool,
+}
+
+impl<F: Copy> Animation<F> {
+    pub const fn looping(frames: &'static [(F, u64)]) -> Self {
+        Animation {
+            frames,
+            looping: true,
+        }
+    }
+
+    /// plays once and then stays on the last frame
+    pub const fn once(frames: &'static [(F, u64)]) -> Self {
+        Animation {
+            frames,
+            looping: false,
+        }
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        self.frames.iter().map(|(_, duration)| duration).sum()
+    }
+
+    /// the frame shown `time_ms` after the animation started
+    pub fn frame_at(&self, time_ms: u64) -> F {
+        let duration = self.duration_ms();
+        let mut time_ms = match (self.looping, duration) {
+            (_, 0) => 0,
+            (true, _) => time_ms % duration,
+            (false, _) => time_ms.min(duration - 1),
+        };
+
+        for (frame, frame_duration) in self.frames {
+            if time_ms < *frame_duration {
+                return *frame;
+            }
+            time_ms -= frame_duration;
+        }
+        self.frames.last().expect("animation without frames").0
+    }
+}
+
+/// offset into a looping animation derived from `seed`, so e.g. a crowd of players doesn't blink in unison
+pub fn phase_offset(seed: u32, period_ms: u64) -> u64 {
+    if period_ms == 0 {
+        return 0;
+    }
+    let h = seed.wrapping_mul(2654435761);
+    let h = h ^ (h >> 16);
+    h as u64 % period_ms
+}
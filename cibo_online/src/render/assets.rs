@@ -1,8 +1,8 @@
-use crate::client::{Client, MoveDirection};
+use crate::client::{Client, CosmeticId, MoveDirection};
 use alloc::{vec, vec::Vec};
 #[allow(unused_imports)]
 use micromath::F32Ext;
-use monos_gfx::{image::SliceReader, Dimension, Image};
+use monos_gfx::{image::SliceReader, Dimension, Image, Position};
 
 macro_rules! include_ppm {
     ($file:expr) => {
@@ -25,10 +25,21 @@ macro_rules! include_pbm {
     () => {};
 }
 
+/// a weighted tile along with its file name, so asset packs can replace it
+macro_rules! tile {
+    ($weight:expr, $file:expr) => {
+        ($weight, $file, include_ppm!($file))
+    };
+}
+
 #[derive(Debug, Clone)]
 pub struct Assets {
     pub cibo: CiboAssets,
+    pub cosmetics: CosmeticAssets,
     pub tiles: [TileAssets; 2],
+    /// animation frames of the sea during the beach episode
+    pub water: [Image; 2],
+    pub water_shore: [Image; 2],
 
     pub message_board: Image,
     pub message_board_bg: Image,
@@ -42,11 +53,14 @@ pub struct Assets {
     pub spatula: Image,
 
     pub beach_ball: BeachBallAssets,
+
+    pub emoji_heart: Image,
+    pub emoji_cibo: Image,
 }
 
 #[derive(Debug, Clone)]
 pub struct TileAssets {
-    tiles: Vec<(usize, Image)>,
+    tiles: Vec<(usize, &'static str, Image)>,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +71,15 @@ pub struct CiboAssets {
     right: CiboImage,
 }
 
+#[derive(Debug, Clone)]
+pub struct CosmeticAssets {
+    santa_hat: Image,
+    flower_crown: Image,
+    sunglasses_front: Image,
+    sunglasses_left: Image,
+    sunglasses_right: Image,
+}
+
 #[derive(Debug, Clone)]
 pub struct BeachBallAssets {
     image_0: Image,
@@ -72,6 +95,7 @@ pub struct BeachBallAssets {
 #[derive(Debug, Clone)]
 struct CiboImage {
     stand: Image,
+    blink: Image,
     walk: [Image; 2],
 }
 
@@ -79,21 +103,30 @@ impl Assets {
     pub fn new() -> Self {
         Self {
             cibo: CiboAssets::new(),
+            cosmetics: CosmeticAssets::new(),
             tiles: [
                 TileAssets::new(vec![
-                    (12, include_ppm!("tile_plain.ppm")),
-                    (3, include_ppm!("tile_grass.ppm")),
-                    (1, include_ppm!("tile_flowers.ppm")),
-                    (1, include_ppm!("tile_rocks.ppm")),
+                    tile!(12, "tile_plain.ppm"),
+                    tile!(3, "tile_grass.ppm"),
+                    tile!(1, "tile_flowers.ppm"),
+                    tile!(1, "tile_rocks.ppm"),
                 ]),
                 TileAssets::new(vec![
-                    (80, include_ppm!("tile_sand.ppm")),
-                    (8, include_ppm!("tile_sand_rocky1.ppm")),
-                    (8, include_ppm!("tile_sand_rocky2.ppm")),
-                    (1, include_ppm!("tile_seashell.ppm")),
-                    (1, include_ppm!("tile_seastar.ppm")),
+                    tile!(80, "tile_sand.ppm"),
+                    tile!(8, "tile_sand_rocky1.ppm"),
+                    tile!(8, "tile_sand_rocky2.ppm"),
+                    tile!(1, "tile_seashell.ppm"),
+                    tile!(1, "tile_seastar.ppm"),
                 ]),
             ],
+            water: [
+                include_ppm!("tile_water1.ppm"),
+                include_ppm!("tile_water2.ppm"),
+            ],
+            water_shore: [
+                include_ppm!("tile_water_shore1.ppm"),
+                include_ppm!("tile_water_shore2.ppm"),
+            ],
 
             message_board: include_ppm!("msgboard.ppm"),
             message_board_bg: include_ppm!("msgboard_bg.ppm"),
@@ -107,19 +140,75 @@ impl Assets {
             spatula: include_ppm!("spatula.ppm"),
 
             beach_ball: BeachBallAssets::new(),
+
+            emoji_heart: include_ppm!("emoji_heart.ppm"),
+            emoji_cibo: include_ppm!("emoji_cibo.ppm"),
+        }
+    }
+
+    /// every image along with the name of its file in `assets/`, for asset packs to replace
+    pub(crate) fn images_mut(&mut self) -> Vec<(&'static str, &mut Image)> {
+        let Assets {
+            cibo,
+            cosmetics,
+            tiles,
+            water: [water1, water2],
+            water_shore: [water_shore1, water_shore2],
+            message_board,
+            message_board_bg,
+            easel,
+            palette,
+            palette_mask,
+            smudge_brush,
+            paint_tube,
+            paint_tube_mask,
+            spatula,
+            beach_ball,
+            emoji_heart,
+            emoji_cibo,
+        } = self;
+
+        let mut images = vec![
+            ("tile_water1.ppm", water1),
+            ("tile_water2.ppm", water2),
+            ("tile_water_shore1.ppm", water_shore1),
+            ("tile_water_shore2.ppm", water_shore2),
+            ("msgboard.ppm", message_board),
+            ("msgboard_bg.ppm", message_board_bg),
+            ("easel.ppm", easel),
+            ("palette.ppm", palette),
+            ("palette_mask.pbm", palette_mask),
+            ("smudge_brush.ppm", smudge_brush),
+            ("paint_tube.ppm", paint_tube),
+            ("paint_tube_mask.pbm", paint_tube_mask),
+            ("spatula.ppm", spatula),
+            ("emoji_heart.ppm", emoji_heart),
+            ("emoji_cibo.ppm", emoji_cibo),
+        ];
+        for tile_set in tiles {
+            images.extend(
+                tile_set
+                    .tiles
+                    .iter_mut()
+                    .map(|(_, name, image)| (*name, image)),
+            );
         }
+        cibo.images_mut(&mut images);
+        cosmetics.images_mut(&mut images);
+        beach_ball.images_mut(&mut images);
+        images
     }
 }
 
 impl TileAssets {
-    fn new(mut tiles: Vec<(usize, Image)>) -> Self {
+    fn new(mut tiles: Vec<(usize, &'static str, Image)>) -> Self {
         assert!(!tiles.is_empty());
 
         let mut weighted_tiles = Vec::with_capacity(tiles.len());
         let mut threshold = 0;
-        for (weight, tile) in tiles.drain(..) {
+        for (weight, name, tile) in tiles.drain(..) {
             threshold += weight;
-            weighted_tiles.push((threshold, tile));
+            weighted_tiles.push((threshold, name, tile));
         }
 
         Self {
@@ -134,7 +223,7 @@ impl TileAssets {
         let h = h ^ (h >> 16);
         let h = h as usize % self.tiles.last().unwrap().0;
 
-        for (threshold, tile) in self.tiles.iter() {
+        for (threshold, _, tile) in self.tiles.iter() {
             if h < *threshold {
                 return tile;
             }
@@ -148,8 +237,16 @@ impl CiboAssets {
     fn new() -> Self {
         macro_rules! include_cibo {
             ($name:expr) => {
+                include_cibo!($name, concat!($name, "_blink.ppm"))
+            };
+            // no eyes to close from behind
+            ($name:expr, no_blink) => {
+                include_cibo!($name, concat!($name, "_stand.ppm"))
+            };
+            ($name:expr, $blink:expr) => {
                 CiboImage {
                     stand: include_ppm!(concat!($name, "_stand.ppm")),
+                    blink: include_ppm!($blink),
                     walk: [
                         include_ppm!(concat!($name, "_walk1.ppm")),
                         include_ppm!(concat!($name, "_walk2.ppm")),
@@ -160,12 +257,51 @@ impl CiboAssets {
 
         Self {
             front: include_cibo!("cibo_front"),
-            back: include_cibo!("cibo_back"),
+            back: include_cibo!("cibo_back", no_blink),
             left: include_cibo!("cibo_left"),
             right: include_cibo!("cibo_right"),
         }
     }
 
+    fn images_mut<'a>(&'a mut self, images: &mut Vec<(&'static str, &'a mut Image)>) {
+        let CiboAssets {
+            front,
+            back,
+            left,
+            right,
+        } = self;
+        front.images_mut(
+            "cibo_front_stand.ppm",
+            Some("cibo_front_blink.ppm"),
+            ["cibo_front_walk1.ppm", "cibo_front_walk2.ppm"],
+            images,
+        );
+        // there is no blinking from behind, see `sync_back_blink`
+        back.images_mut(
+            "cibo_back_stand.ppm",
+            None,
+            ["cibo_back_walk1.ppm", "cibo_back_walk2.ppm"],
+            images,
+        );
+        left.images_mut(
+            "cibo_left_stand.ppm",
+            Some("cibo_left_blink.ppm"),
+            ["cibo_left_walk1.ppm", "cibo_left_walk2.ppm"],
+            images,
+        );
+        right.images_mut(
+            "cibo_right_stand.ppm",
+            Some("cibo_right_blink.ppm"),
+            ["cibo_right_walk1.ppm", "cibo_right_walk2.ppm"],
+            images,
+        );
+    }
+
+    /// the back has no eyes to close, so its blink frame is just the standing one
+    pub(crate) fn sync_back_blink(&mut self) {
+        self.back.blink = self.back.stand.clone();
+    }
+
     fn get_image(&self, direction: MoveDirection) -> &CiboImage {
         match direction {
             MoveDirection::Up => &self.back,
@@ -184,6 +320,101 @@ impl CiboAssets {
             &self.get_image(client.look_direction).stand
         }
     }
+
+    /// image of something walking in `direction` that isn't a client, e.g. an actor on a stage
+    pub fn get_walk_image(&self, direction: MoveDirection, walk_frame: usize) -> &Image {
+        &self.get_image(direction).walk[walk_frame % 2]
+    }
+
+    /// image of a standing client looking in `direction`
+    pub fn get_idle_image(&self, direction: MoveDirection, blink: bool) -> &Image {
+        let image = self.get_image(direction);
+        if blink {
+            &image.blink
+        } else {
+            &image.stand
+        }
+    }
+}
+
+impl CiboImage {
+    fn images_mut<'a>(
+        &'a mut self,
+        stand: &'static str,
+        blink: Option<&'static str>,
+        walk: [&'static str; 2],
+        images: &mut Vec<(&'static str, &'a mut Image)>,
+    ) {
+        let [walk1, walk2] = &mut self.walk;
+        images.push((stand, &mut self.stand));
+        if let Some(blink) = blink {
+            images.push((blink, &mut self.blink));
+        }
+        images.push((walk[0], walk1));
+        images.push((walk[1], walk2));
+    }
+}
+
+impl CosmeticAssets {
+    fn images_mut<'a>(&'a mut self, images: &mut Vec<(&'static str, &'a mut Image)>) {
+        images.push(("santa_hat.ppm", &mut self.santa_hat));
+        images.push(("flower_crown.ppm", &mut self.flower_crown));
+        images.push(("sunglasses_front.ppm", &mut self.sunglasses_front));
+        images.push(("sunglasses_left.ppm", &mut self.sunglasses_left));
+        images.push(("sunglasses_right.ppm", &mut self.sunglasses_right));
+    }
+
+    fn new() -> Self {
+        Self {
+            santa_hat: include_ppm!("santa_hat.ppm"),
+            flower_crown: include_ppm!("flower_crown.ppm"),
+            sunglasses_front: include_ppm!("sunglasses_front.ppm"),
+            sunglasses_left: include_ppm!("sunglasses_left.ppm"),
+            sunglasses_right: include_ppm!("sunglasses_right.ppm"),
+        }
+    }
+
+    /// overlay for a cibo sprite looking in `direction` and where it goes, relative to the top left of the sprite.
+    /// the right sprite is the left one mirrored, so the offsets are too
+    pub fn get_overlay(
+        &self,
+        cosmetic: CosmeticId,
+        direction: MoveDirection,
+    ) -> Option<(&Image, Position)> {
+        let (image, offset) = match (cosmetic, direction) {
+            (CosmeticId::SantaHat, MoveDirection::Up) => (&self.santa_hat, Position::new(9, -5)),
+            (CosmeticId::SantaHat, MoveDirection::Left | MoveDirection::Right) => {
+                (&self.santa_hat, Position::new(1, -2))
+            }
+            (CosmeticId::SantaHat, _) => (&self.santa_hat, Position::new(9, -3)),
+
+            (CosmeticId::FlowerCrown, MoveDirection::Up) => {
+                (&self.flower_crown, Position::new(8, 1))
+            }
+            (CosmeticId::FlowerCrown, MoveDirection::Left | MoveDirection::Right) => {
+                (&self.flower_crown, Position::new(0, 5))
+            }
+            (CosmeticId::FlowerCrown, _) => (&self.flower_crown, Position::new(8, 3)),
+
+            // nothing to see from behind
+            (CosmeticId::Sunglasses, MoveDirection::Up) => return None,
+            (CosmeticId::Sunglasses, MoveDirection::Left) => {
+                (&self.sunglasses_left, Position::new(3, 10))
+            }
+            (CosmeticId::Sunglasses, MoveDirection::Right) => {
+                (&self.sunglasses_right, Position::new(3, 10))
+            }
+            (CosmeticId::Sunglasses, _) => (&self.sunglasses_front, Position::new(9, 14)),
+        };
+
+        let offset = match direction {
+            MoveDirection::Right => {
+                Position::new(32 - offset.x - image.dimensions().width as i64, offset.y)
+            }
+            _ => offset,
+        };
+        Some((image, offset))
+    }
 }
 
 impl BeachBallAssets {
@@ -200,6 +431,17 @@ impl BeachBallAssets {
         }
     }
 
+    fn images_mut<'a>(&'a mut self, images: &mut Vec<(&'static str, &'a mut Image)>) {
+        images.push(("beach_ball_0.ppm", &mut self.image_0));
+        images.push(("beach_ball_45.ppm", &mut self.image_45));
+        images.push(("beach_ball_90.ppm", &mut self.image_90));
+        images.push(("beach_ball_135.ppm", &mut self.image_135));
+        images.push(("beach_ball_180.ppm", &mut self.image_180));
+        images.push(("beach_ball_225.ppm", &mut self.image_225));
+        images.push(("beach_ball_270.ppm", &mut self.image_270));
+        images.push(("beach_ball_315.ppm", &mut self.image_315));
+    }
+
     pub fn dimensions(&self) -> Dimension {
         self.image_0.dimensions()
     }
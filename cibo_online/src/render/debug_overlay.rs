@@ -0,0 +1,96 @@
+//! shows how the game sees the world: hitboxes, bounds and z order of every sprite, the area the
+//! player can walk in before the camera follows and what they ran into. toggled with ctrl+b or the
+//! `debug` parameter. sprites get it just by being drawn, objects that want to show more can use
+//! `RenderContext::debug_box`.
+
+use super::{push_int, RenderContext, Sprite};
+use crate::client::Client;
+use alloc::{string::String, vec::Vec};
+use monos_gfx::{
+    text::font,
+    ui::{Direction, UIFrame},
+    Color, Position, Rect,
+};
+
+const HITBOX_COLOR: Color = Color::new(255, 0, 0);
+const BOUNDS_COLOR: Color = Color::new(0, 255, 0);
+const DEADZONE_COLOR: Color = Color::new(0, 160, 255);
+const COLLISION_COLOR: Color = Color::new(255, 220, 0);
+const BLOCKED_COLOR: Color = Color::new(255, 0, 255);
+
+#[derive(Debug, Default)]
+pub(crate) struct DebugOverlay {
+    pub enabled: bool,
+    /// where the player tried to move to in the last update
+    pub own_hitbox: Option<Rect>,
+    /// hitboxes the player ran into in the last update
+    pub collisions: Vec<Rect>,
+    /// whether one of them stopped the player
+    pub blocked: bool,
+}
+
+impl DebugOverlay {
+    /// the collision results and the camera deadzone, which is in screen coordinates
+    pub fn render(&self, deadzone: Rect, camera: Position, ctx: &mut RenderContext) {
+        if !ctx.debug {
+            return;
+        }
+
+        ctx.fb.draw_box(&deadzone, &DEADZONE_COLOR);
+        for hitbox in &self.collisions {
+            ctx.debug_box(*hitbox, camera, COLLISION_COLOR);
+        }
+        if let Some(hitbox) = self.own_hitbox {
+            let color = if self.blocked {
+                BLOCKED_COLOR
+            } else {
+                COLLISION_COLOR
+            };
+            ctx.debug_box(hitbox, camera, color);
+        }
+    }
+}
+
+/// outline the hitbox and bounds of a sprite that was just drawn and write its z order next to it
+pub(crate) fn draw_sprite(sprite: &Sprite, camera: Position, ctx: &mut RenderContext) {
+    let position = sprite.position();
+    let (hitbox, bounds) = match sprite {
+        Sprite::Object(object) => (object.hitbox(), object.bounds()),
+        Sprite::Client(..) | Sprite::OwnClient(..) => (
+            Some(Client::hitbox_at(position)),
+            Rect::new(
+                position,
+                position + Position::from_dimensions(sprite.dimensions()),
+            ),
+        ),
+        Sprite::Static { .. } => (
+            None,
+            Rect::new(
+                position,
+                position + Position::from_dimensions(sprite.dimensions()),
+            ),
+        ),
+    };
+
+    ctx.debug_box(bounds, camera, BOUNDS_COLOR);
+    if let Some(hitbox) = hitbox {
+        ctx.debug_box(hitbox, camera, HITBOX_COLOR);
+    }
+
+    let (z, raised) = sprite.z_order().within_level();
+    let mut text = String::from("z");
+    push_int(&mut text, z);
+    if raised {
+        text.push('^');
+    }
+    let min = bounds.min - camera;
+    let mut ui = UIFrame::new_stateless(Direction::TopToBottom);
+    ui.draw_frame(
+        ctx.fb,
+        Rect::new(min, min + Position::new(64, 16)),
+        ctx.input,
+        |ui| {
+            ui.label::<font::Glean>(&text);
+        },
+    );
+}
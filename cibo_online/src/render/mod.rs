@@ -1,27 +1,153 @@
 mod renderable;
-pub use renderable::{Renderable, Sprite, ZOrder};
+pub use renderable::{Renderable, Sprite, SpriteBuffer, ZOrder};
+
+mod animation;
+pub use animation::{phase_offset, Animation};
 
 mod assets;
 pub use assets::Assets;
 
+mod debug_overlay;
+pub(crate) use debug_overlay::{draw_sprite as draw_sprite_debug, DebugOverlay};
+
+mod palette;
+pub use palette::{ColorMode, Palette};
+
+pub mod rich_text;
 pub mod widgets;
 
-use crate::client::ClientMessage;
-use monos_gfx::{Framebuffer, Input, Position, Rect};
+use crate::{
+    client::{ClientMessage, LabelLayout},
+    ClientId,
+};
+use alloc::{string::String, vec::Vec};
+use monos_gfx::{Color, Framebuffer, Input, Position, Rect};
+
+/// alpha of see-through previews
+pub const GHOST_ALPHA: u8 = 128;
+/// prompts of objects that can't be used again yet
+pub(crate) const COOLDOWN_PROMPT_COLOR: Color = Color::new(120, 120, 120);
 
 pub struct RenderContext<'a, 'f> {
     pub fb: &'a mut Framebuffer<'f>,
     pub player_pos: Position,
+    pub own_id: ClientId,
     pub input: &'a mut Input,
     pub time_ms: u64,
+    /// the server clock, for things that have to line up between all players
+    pub server_time_ms: u64,
+    /// how long a frame of the player animations lasts, from the [`GameTuning`](crate::GameTuning)
+    pub anim_frame_ms: u64,
     pub stream_mode: bool,
+    /// whether overlays and popups can be drawn. off in stream mode while drawing objects that aren't stream safe
+    pub stream_safe: bool,
+    pub palette: &'static Palette,
+    pub label_layout: LabelLayout,
+    /// whether the debug overlay is on, see [`debug_box`](Self::debug_box)
+    pub debug: bool,
     pub send_msg: &'a mut dyn FnMut(ClientMessage),
 }
 
 impl<'a> RenderContext<'a, '_> {
     #[inline(always)]
     pub fn anim_frame(&self) -> usize {
-        self.time_ms as usize / crate::BASE_ANIM_SPEED
+        (self.time_ms / self.anim_frame_ms) as usize
+    }
+
+    /// outline `rect`, in world coordinates, while the debug overlay is on. every sprite already
+    /// shows its hitbox and bounds, this is for anything else an object wants to show
+    pub fn debug_box(&mut self, rect: Rect, camera: Position, color: Color) {
+        if self.debug {
+            let rect = rect.translate(Position::new(-camera.x, -camera.y));
+            self.fb.draw_box(&rect, &color);
+        }
+    }
+
+    /// run `draw` and blend everything it changed inside `area` with what was there before.
+    /// the drawn colors are multiplied with `tint` first, if there is one
+    pub fn draw_ghost(
+        &mut self,
+        area: Rect,
+        alpha: u8,
+        tint: Option<Color>,
+        draw: impl FnOnce(&mut Self),
+    ) {
+        let screen = self.fb.dimensions();
+        let min = Position::new(area.min.x.max(0), area.min.y.max(0));
+        let max = Position::new(
+            area.max.x.min(screen.width as i64),
+            area.max.y.min(screen.height as i64),
+        );
+        if min.x >= max.x || min.y >= max.y || alpha == 0 {
+            return;
+        }
+
+        let mut below = Vec::with_capacity(((max.x - min.x) * (max.y - min.y)) as usize);
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                below.push(self.fb.get_pixel_alpha(Position::new(x, y)).0);
+            }
+        }
+
+        draw(self);
+
+        let mut below = below.into_iter();
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                let position = Position::new(x, y);
+                let below = below.next().unwrap();
+                let above = self.fb.get_pixel_alpha(position).0;
+                if above == below {
+                    continue;
+                }
+
+                let above = match tint {
+                    Some(tint) => multiply(above, tint),
+                    None => above,
+                };
+                self.fb.draw_pixel(position, blend(below, above, alpha));
+            }
+        }
+    }
+}
+
+fn multiply(color: Color, tint: Color) -> Color {
+    let channel = |a: u8, b: u8| (a as u16 * b as u16 / 255) as u8;
+    Color::new(
+        channel(color.r, tint.r),
+        channel(color.g, tint.g),
+        channel(color.b, tint.b),
+    )
+}
+
+pub(crate) fn blend(below: Color, above: Color, alpha: u8) -> Color {
+    let channel =
+        |a: u8, b: u8| ((a as u16 * (255 - alpha as u16) + b as u16 * alpha as u16) / 255) as u8;
+    Color::new(
+        channel(below.r, above.r),
+        channel(below.g, above.g),
+        channel(below.b, above.b),
+    )
+}
+
+/// append a number to `text` without `format!`, so text that changes every frame can reuse its string
+pub fn push_int(text: &mut String, value: i64) {
+    if value < 0 {
+        text.push('-');
+    }
+    let mut digits = [0u8; 20];
+    let mut len = 0;
+    let mut rest = value.unsigned_abs();
+    loop {
+        digits[len] = b'0' + (rest % 10) as u8;
+        len += 1;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+    for digit in digits[..len].iter().rev() {
+        text.push(*digit as char);
     }
 }
 
@@ -0,0 +1,95 @@
+use monos_gfx::Color;
+use serde::{Deserialize, Serialize};
+
+/// colors that carry meaning, so they can be swapped out for players with color vision deficiencies
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    /// mentions, achievements and the player that is "it"
+    pub highlight: Color,
+    /// server events in the ticker
+    pub event: Color,
+    /// chat lines with a link in them
+    pub link: Color,
+    /// background in stream mode, keyed out by streaming software
+    pub chroma_key: Color,
+
+    pub good: Color,
+    pub warning: Color,
+    pub bad: Color,
+}
+
+const NORMAL: Palette = Palette {
+    highlight: Color::new(255, 220, 80),
+    event: Color::new(120, 220, 255),
+    link: Color::new(150, 160, 255),
+    chroma_key: Color::new(0, 255, 0),
+    good: Color::new(0, 255, 0),
+    warning: Color::new(255, 255, 0),
+    bad: Color::new(255, 0, 0),
+};
+
+// based on the okabe-ito palette, which stays distinguishable without red or green cones
+const DEUTERANOPIA: Palette = Palette {
+    highlight: Color::new(230, 159, 0),
+    event: Color::new(86, 180, 233),
+    link: Color::new(204, 121, 167),
+    chroma_key: Color::new(0, 0, 255),
+    good: Color::new(86, 180, 233),
+    warning: Color::new(240, 228, 66),
+    bad: Color::new(213, 94, 0),
+};
+
+// protanopes see reds darker, so the highlight and warning colors lean towards yellow
+const PROTANOPIA: Palette = Palette {
+    highlight: Color::new(240, 228, 66),
+    event: Color::new(86, 180, 233),
+    link: Color::new(204, 121, 167),
+    chroma_key: Color::new(0, 0, 255),
+    good: Color::new(0, 114, 178),
+    warning: Color::new(240, 228, 66),
+    bad: Color::new(230, 159, 0),
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMode {
+    #[default]
+    Normal,
+    Deuteranopia,
+    Protanopia,
+}
+
+impl ColorMode {
+    pub fn palette(&self) -> &'static Palette {
+        match self {
+            ColorMode::Normal => &NORMAL,
+            ColorMode::Deuteranopia => &DEUTERANOPIA,
+            ColorMode::Protanopia => &PROTANOPIA,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorMode::Normal => "normal",
+            ColorMode::Deuteranopia => "deuteranopia",
+            ColorMode::Protanopia => "protanopia",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(ColorMode::Normal),
+            "deuteranopia" => Some(ColorMode::Deuteranopia),
+            "protanopia" => Some(ColorMode::Protanopia),
+            _ => None,
+        }
+    }
+
+    /// the mode after this one, for cycling through them with a single key
+    pub fn next(&self) -> Self {
+        match self {
+            ColorMode::Normal => ColorMode::Deuteranopia,
+            ColorMode::Deuteranopia => ColorMode::Protanopia,
+            ColorMode::Protanopia => ColorMode::Normal,
+        }
+    }
+}
@@ -1,10 +1,10 @@
 use super::RenderContext;
 use crate::client::{Client, ClientLocal, OwnClient, OwnClientLocal};
-use crate::Object;
+use crate::{Elevation, Object};
 
-use alloc::rc::Rc;
+use alloc::{rc::Rc, vec::Vec};
 use core::cell::RefCell;
-use monos_gfx::{Dimension, Image, Position};
+use monos_gfx::{Color, Dimension, Image, Position, Rect};
 use serde::{Deserialize, Serialize};
 
 pub trait Renderable {
@@ -13,6 +13,9 @@ pub trait Renderable {
     fn render(&mut self, state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext);
 }
 
+/// range of z positions each level gets. far bigger than the world, so levels never overlap
+const LEVEL_HEIGHT: i64 = 1 << 40;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct ZOrder(i64);
 
@@ -22,6 +25,32 @@ impl ZOrder {
         ZOrder(z)
     }
 
+    /// create a z position for game elements on the given level. everything raised is on top of the ground
+    pub const fn elevated(z: i64, elevation: Elevation) -> Self {
+        ZOrder(z + Self::level_offset(elevation))
+    }
+
+    /// below everything else on the given level, e.g. for the floor of a bridge
+    pub const fn level_floor(elevation: Elevation) -> Self {
+        ZOrder(Self::level_offset(elevation) - LEVEL_HEIGHT / 2)
+    }
+
+    const fn level_offset(elevation: Elevation) -> i64 {
+        match elevation {
+            Elevation::Ground => 0,
+            Elevation::Raised => LEVEL_HEIGHT,
+        }
+    }
+
+    /// the z position within its level and whether that level is raised, for the debug overlay
+    pub(crate) const fn within_level(self) -> (i64, bool) {
+        if self.0 >= LEVEL_HEIGHT / 2 && self.0 < i64::MAX - u8::MAX as i64 {
+            (self.0 - LEVEL_HEIGHT, true)
+        } else {
+            (self.0, false)
+        }
+    }
+
     /// create a z position for UI elements. this is on on top of everything but ui elements with a higher z position
     pub const fn new_ui(z: u8) -> Self {
         let z = u8::MAX - z;
@@ -44,9 +73,21 @@ impl<'a> Sprite<'a> {
     #[inline(always)]
     pub fn z_order(&self) -> ZOrder {
         match self {
-            Self::Object(object) => object.properties().override_z.unwrap_or(ZOrder::new(
-                object.properties().position.y + object.properties().dimensions.height as i64,
-            )),
+            Self::Object(object) => {
+                let properties = object.properties();
+                properties.override_z.unwrap_or(ZOrder::elevated(
+                    properties.position.y + properties.dimensions.height as i64,
+                    properties.elevation,
+                ))
+            }
+            Self::Client(client, _) => ZOrder::elevated(
+                client.position.y + self.dimensions().height as i64,
+                client.elevation,
+            ),
+            Self::OwnClient(client, _) => ZOrder::elevated(
+                client.0.position.y + self.dimensions().height as i64,
+                client.0.elevation,
+            ),
             _ => ZOrder::new(self.position().y + self.dimensions().height as i64),
         }
     }
@@ -70,14 +111,74 @@ impl<'a> Sprite<'a> {
         }
     }
 
+    /// draw the sprite see-through, e.g. for previews or things that are fading out.
+    /// it can't be interacted with while drawn like this
+    pub fn render_ghost(
+        &mut self,
+        camera: Position,
+        ctx: &mut RenderContext,
+        alpha: u8,
+        tint: Option<Color>,
+    ) {
+        let min = self.position() - camera;
+        let area = Rect::new(min, min + Position::from_dimensions(self.dimensions()));
+
+        let player_pos = core::mem::replace(
+            &mut ctx.player_pos,
+            Position::new(i64::MIN / 2, i64::MIN / 2),
+        );
+        ctx.draw_ghost(area, alpha, tint, |ctx| self.render(camera, ctx));
+        ctx.player_pos = player_pos;
+    }
+
     pub fn render(&mut self, camera: Position, ctx: &mut RenderContext) {
         match self {
             Self::Client(client, local) => client.render(&mut local.borrow_mut(), camera, ctx),
             Self::OwnClient(client, local) => client.render(&mut local.borrow_mut(), camera, ctx),
-            Self::Object(object) => object.render(&mut (), camera, ctx),
+            Self::Object(object) => {
+                let stream_safe = core::mem::replace(
+                    &mut ctx.stream_safe,
+                    !ctx.stream_mode || object.properties().stream_safe,
+                );
+                object.render(&mut (), camera, ctx);
+                ctx.stream_safe = stream_safe;
+            }
             Self::Static { position, image } => {
                 ctx.fb.draw_img(image, *position - camera);
             }
         }
     }
 }
+
+/// keeps the memory of a sprite list between frames. sprites borrow the world, so the list itself
+/// can't be kept, only its allocation.
+#[derive(Default)]
+pub struct SpriteBuffer(Vec<(Sprite<'static>, u8)>);
+
+impl core::fmt::Debug for SpriteBuffer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SpriteBuffer")
+            .field("capacity", &self.0.capacity())
+            .finish()
+    }
+}
+
+impl SpriteBuffer {
+    /// an empty list that uses the kept memory. hand it back with `put_back` once drawing is done
+    pub fn take<'a>(&mut self) -> Vec<(Sprite<'a>, u8)> {
+        // the buffer is always empty, so the map never runs. collecting a mapped `IntoIter`
+        // into an element of the same size reuses its allocation
+        core::mem::take(&mut self.0)
+            .into_iter()
+            .map(|_| -> (Sprite<'a>, u8) { unreachable!() })
+            .collect()
+    }
+
+    pub fn put_back(&mut self, mut sprites: Vec<(Sprite<'_>, u8)>) {
+        sprites.clear();
+        self.0 = sprites
+            .into_iter()
+            .map(|_| -> (Sprite<'static>, u8) { unreachable!() })
+            .collect();
+    }
+}
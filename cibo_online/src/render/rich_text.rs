@@ -0,0 +1,318 @@
+use crate::assets;
+use alloc::vec::Vec;
+use monos_gfx::{
+    text::font,
+    ui::{Lines, TextWrap},
+    Color, Dimension, Framebuffer, Image, Position,
+};
+
+/// shortcodes that can be used in chat, without the surrounding colons
+pub const EMOJIS: [&str; 2] = ["heart", "cibo"];
+/// put at the end of text that was cut off
+const ELLIPSIS: &str = "...";
+
+fn emoji_image(name: &str) -> Option<&'static Image> {
+    match name {
+        "heart" => Some(&assets().emoji_heart),
+        "cibo" => Some(&assets().emoji_cibo),
+        _ => None,
+    }
+}
+
+/// the shortcode that is currently being typed at the end of the text, without the leading colon
+pub fn partial_shortcode(text: &str) -> Option<&str> {
+    let word = text.rsplit(' ').next()?;
+    let colons = word.matches(':').count();
+    // with an even amount of colons the last one closes a shortcode
+    if colons % 2 == 0 {
+        return None;
+    }
+
+    let partial = &word[word.rfind(':')? + 1..];
+    (!partial.is_empty()).then_some(partial)
+}
+
+/// emojis whose shortcode starts with the given partial shortcode
+pub fn emoji_suggestions(partial: &str) -> impl Iterator<Item = &'static str> + '_ {
+    EMOJIS
+        .iter()
+        .copied()
+        .filter(move |name| name.starts_with(partial))
+}
+
+/// the fonts text is drawn with, picked for every character. glean only has ascii, cozette
+/// covers most other alphabets and symbols. characters neither has still show up as boxes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextFont {
+    Glean,
+    Cozette,
+}
+
+impl TextFont {
+    fn for_char(c: char) -> Self {
+        if c.is_ascii() {
+            TextFont::Glean
+        } else {
+            TextFont::Cozette
+        }
+    }
+
+    fn dimensions(self, text: &str) -> Dimension {
+        let wrap = TextWrap::Enabled { hyphenate: false };
+        let max = Dimension::new(10000, 10000);
+        match self {
+            TextFont::Glean => Lines::<font::Glean>::layout(text, wrap, max).dimensions(),
+            TextFont::Cozette => Lines::<font::Cozette>::layout(text, wrap, max).dimensions(),
+        }
+    }
+
+    fn draw(self, fb: &mut Framebuffer, text: &str, position: Position, color: Color) {
+        let wrap = TextWrap::Enabled { hyphenate: false };
+        let max = Dimension::new(10000, 10000);
+        match self {
+            TextFont::Glean => {
+                Lines::<font::Glean>::layout(text, wrap, max).draw(fb, position, color);
+            }
+            TextFont::Cozette => {
+                Lines::<font::Cozette>::layout(text, wrap, max).draw(fb, position, color);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Token<'a> {
+    Text(&'a str, TextFont),
+    Emoji(&'static Image),
+}
+
+impl Token<'_> {
+    fn width(&self) -> u32 {
+        match self {
+            Token::Text(text, font) => font.dimensions(text).width,
+            Token::Emoji(image) => image.dimensions().width,
+        }
+    }
+}
+
+fn text_dimensions(text: &str) -> Dimension {
+    TextFont::Glean.dimensions(text)
+}
+
+/// split text into runs that are drawn with the same font
+fn push_text<'a>(tokens: &mut Vec<Token<'a>>, text: &'a str) {
+    let mut rest = text;
+    while let Some(first) = rest.chars().next() {
+        let font = TextFont::for_char(first);
+        let end = rest
+            .char_indices()
+            .find(|(_, c)| TextFont::for_char(*c) != font)
+            .map_or(rest.len(), |(i, _)| i);
+        tokens.push(Token::Text(&rest[..end], font));
+        rest = &rest[end..];
+    }
+}
+
+/// split a single word into text and emojis
+fn tokenize_word(word: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut text_start = 0;
+    let mut search_start = 0;
+    while let Some(start) = word[search_start..].find(':').map(|i| i + search_start) {
+        let after = &word[start + 1..];
+        let emoji = after
+            .find(':')
+            .and_then(|end| Some((end, emoji_image(&after[..end])?)));
+
+        match emoji {
+            Some((end, image)) => {
+                if start > text_start {
+                    push_text(&mut tokens, &word[text_start..start]);
+                }
+                tokens.push(Token::Emoji(image));
+                text_start = start + end + 2;
+                search_start = text_start;
+            }
+            None => search_start = start + 1,
+        }
+    }
+
+    if text_start < word.len() {
+        push_text(&mut tokens, &word[text_start..]);
+    }
+    tokens
+}
+
+/// split text that doesn't fit into a single line into pieces that do
+fn split_text(text: &str, font: TextFont, max_width: u32) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while font.dimensions(rest).width > max_width {
+        let split = rest
+            .char_indices()
+            .skip(1)
+            .map(|(i, _)| i)
+            .take_while(|i| font.dimensions(&rest[..*i]).width <= max_width)
+            .last()
+            .unwrap_or_else(|| rest.chars().next().map_or(rest.len(), |c| c.len_utf8()));
+        pieces.push(&rest[..split]);
+        rest = &rest[split..];
+    }
+    pieces.push(rest);
+    pieces
+}
+
+/// word wrapped text that can contain emojis
+#[derive(Debug, Clone)]
+pub struct RichLines<'a> {
+    tokens: Vec<(Position, Token<'a>)>,
+    line_height: u32,
+    dimensions: Dimension,
+}
+
+impl<'a> RichLines<'a> {
+    pub fn layout(text: &'a str, max_width: u32) -> Self {
+        let mut line_height = EMOJIS
+            .iter()
+            .filter_map(|name| emoji_image(name))
+            .map(|image| image.dimensions().height)
+            .fold(text_dimensions("A").height, u32::max);
+        // only make room for the bigger font if it is needed, most text is plain ascii
+        if !text.is_ascii() {
+            line_height = line_height.max(TextFont::Cozette.dimensions("A").height);
+        }
+        let space_width = text_dimensions("a a")
+            .width
+            .saturating_sub(text_dimensions("aa").width);
+
+        let mut tokens = Vec::new();
+        let mut x = 0;
+        let mut y = 0;
+        let mut width = 0;
+        for word in text.split(' ') {
+            let mut parts = tokenize_word(word);
+            let word_width: u32 = parts.iter().map(|token| token.width()).sum();
+
+            if x > 0 && x + space_width + word_width > max_width {
+                x = 0;
+                y += line_height;
+            } else if x > 0 {
+                x += space_width;
+            }
+
+            // words that are too long on their own have to be broken up
+            if word_width > max_width {
+                parts = parts
+                    .into_iter()
+                    .flat_map(|token| match token {
+                        Token::Text(text, font) => split_text(text, font, max_width)
+                            .into_iter()
+                            .map(|piece| Token::Text(piece, font))
+                            .collect(),
+                        token => alloc::vec![token],
+                    })
+                    .collect();
+            }
+
+            for part in parts {
+                let part_width = part.width();
+                if x > 0 && x + part_width > max_width {
+                    x = 0;
+                    y += line_height;
+                }
+
+                tokens.push((Position::new(x as i64, y as i64), part));
+                x += part_width;
+                width = width.max(x);
+            }
+        }
+
+        RichLines {
+            tokens,
+            line_height,
+            dimensions: Dimension::new(width, y + line_height),
+        }
+    }
+
+    pub fn dimensions(&self) -> Dimension {
+        self.dimensions
+    }
+
+    /// cut the text off after `max_lines` lines, the last one ends in an ellipsis
+    pub fn truncate(&mut self, max_lines: u32, max_width: u32) {
+        let max_lines = max_lines.max(1);
+        if self.dimensions.height <= max_lines * self.line_height {
+            return;
+        }
+
+        let last_y = ((max_lines - 1) * self.line_height) as i64;
+        self.tokens.retain(|(position, _)| position.y <= last_y);
+
+        // make room for the ellipsis at the end of the last line
+        let ellipsis_width = text_dimensions(ELLIPSIS).width;
+        let mut end = 0;
+        while let Some((position, token)) = self.tokens.last_mut() {
+            if position.y != last_y {
+                break;
+            }
+            end = position.x as u32 + token.width();
+            if end + ellipsis_width <= max_width {
+                break;
+            }
+
+            match token {
+                Token::Text(text, _) if text.chars().count() > 1 => {
+                    let full = *text;
+                    let cut = full.char_indices().last().map_or(0, |(i, _)| i);
+                    *text = &full[..cut];
+                }
+                _ => {
+                    self.tokens.pop();
+                    end = 0;
+                }
+            }
+        }
+        self.tokens.push((
+            Position::new(end as i64, last_y),
+            Token::Text(ELLIPSIS, TextFont::Glean),
+        ));
+
+        let width = self
+            .tokens
+            .iter()
+            .map(|(position, token)| position.x as u32 + token.width())
+            .max()
+            .unwrap_or(0);
+        self.dimensions = Dimension::new(width, max_lines * self.line_height);
+    }
+
+    pub fn draw(&self, fb: &mut Framebuffer, position: Position, color: Color) {
+        self.draw_rows(fb, position, color, i64::MIN..i64::MAX);
+    }
+
+    /// draw only the lines that are completely within `rows`, in screen coordinates. lets a text
+    /// that is too big to be shown at once be scrolled through
+    pub fn draw_rows(
+        &self,
+        fb: &mut Framebuffer,
+        position: Position,
+        color: Color,
+        rows: core::ops::Range<i64>,
+    ) {
+        for (offset, token) in &self.tokens {
+            let token_position = position + *offset;
+            if token_position.y < rows.start
+                || token_position.y + self.line_height as i64 > rows.end
+            {
+                continue;
+            }
+            match token {
+                Token::Text(text, font) => font.draw(fb, text, token_position, color),
+                Token::Emoji(image) => {
+                    let padding = (self.line_height - image.dimensions().height) as i64 / 2;
+                    fb.draw_img(image, token_position + Position::new(0, padding));
+                }
+            }
+        }
+    }
+}
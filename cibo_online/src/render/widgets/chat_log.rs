@@ -0,0 +1,90 @@
+use crate::render::rich_text::RichLines;
+use alloc::vec::Vec;
+use monos_gfx::{
+    ui::{Deserialize, Serialize, UIContext, UIElement, UIResult},
+    Color, Dimension, Position,
+};
+
+const SCROLL_SPEED: i64 = 8;
+
+/// scrollable list of chat messages and their color, newest at the bottom
+#[derive(Debug, Clone)]
+pub struct ChatLog<'a, I> {
+    lines: I,
+    height: u32,
+    scroll: i64,
+    _marker: core::marker::PhantomData<&'a str>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct ChatLogState {
+    scroll: i64,
+}
+
+impl<'a, I> ChatLog<'a, I>
+where
+    I: DoubleEndedIterator<Item = (&'a str, Color)>,
+{
+    pub fn new(lines: I, height: u32) -> Self {
+        Self {
+            lines,
+            height,
+            scroll: 0,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// how far the mouse wheel was moved since the last frame
+    pub fn scroll(mut self, scroll: i64) -> Self {
+        self.scroll = scroll;
+        self
+    }
+}
+
+impl<'a, I> UIElement for ChatLog<'a, I>
+where
+    I: DoubleEndedIterator<Item = (&'a str, Color)>,
+{
+    fn draw(self, context: &mut UIContext) -> UIResult {
+        let id = context.next_id_from_string("chat_log");
+        let mut state: ChatLogState = context.state_get(id).unwrap_or_default();
+
+        let width = context.placer.max_width();
+        let result = context.alloc_space(Dimension::new(width, self.height));
+
+        let lines = self
+            .lines
+            .rev()
+            .map(|(line, color)| (RichLines::layout(line, width), color))
+            .collect::<Vec<_>>();
+        let total_height = lines
+            .iter()
+            .map(|(line, _)| line.dimensions().height as i64)
+            .sum::<i64>();
+
+        let max_scroll = (total_height - self.height as i64).max(0);
+        state.scroll = (state.scroll - self.scroll * SCROLL_SPEED).clamp(0, max_scroll);
+
+        // only draw the rows that fit completely, messages taller than the log are scrolled through
+        let rows = result.rect.min.y..result.rect.max.y;
+        let mut y = result.rect.max.y + state.scroll;
+        for (line, color) in &lines {
+            if y <= result.rect.min.y {
+                break;
+            }
+            y -= line.dimensions().height as i64;
+            if y < result.rect.max.y {
+                line.draw_rows(
+                    context.fb,
+                    Position::new(result.rect.min.x, y),
+                    *color,
+                    rows.clone(),
+                );
+            }
+        }
+
+        context.state_insert(id, state);
+
+        result
+    }
+}
@@ -1,13 +1,20 @@
+use crate::render::rich_text::RichLines;
 use monos_gfx::{
-    text::font,
-    ui::{Deserialize, Lines, Serialize, TextWrap, UIContext, UIElement, UIResult},
+    ui::{Deserialize, Serialize, UIContext, UIElement, UIResult},
     Color, Dimension, Position, Rect,
 };
 
+/// bubbles don't get wider than this, even with room to spare
+const MAX_TEXT_WIDTH: u32 = 88;
+/// longer messages are cut off, the chat log still has all of it
+const MAX_LINES: u32 = 5;
+
 #[derive(Debug, Clone)]
 pub struct ChatWidget<'a> {
     text: &'a str,
     custom_id: Option<&'a str>,
+    /// how far the bubble has shrunk away, from 0 to 1
+    closing: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -21,6 +28,7 @@ impl<'a> ChatWidget<'a> {
         Self {
             text,
             custom_id: None,
+            closing: None,
         }
     }
 
@@ -28,8 +36,15 @@ impl<'a> ChatWidget<'a> {
         Self {
             text,
             custom_id: Some(id),
+            closing: None,
         }
     }
+
+    /// shrink the bubble away, `progress` goes from 0 to 1
+    pub fn closing(mut self, progress: f32) -> Self {
+        self.closing = Some(progress.clamp(0.0, 1.0));
+        self
+    }
 }
 
 impl UIElement for ChatWidget<'_> {
@@ -42,25 +57,31 @@ impl UIElement for ChatWidget<'_> {
 
         let mut state: ChatWidgetState = context.state_get(id).unwrap_or_default();
 
-        let line_max_dimensions = Dimension::new(
-            context.placer.max_width() - 2,
-            context.fb.dimensions().height,
-        );
-        let lines = Lines::<font::Glean>::layout(
-            self.text,
-            TextWrap::Enabled { hyphenate: false },
-            line_max_dimensions,
-        );
+        let text_width = (context.placer.max_width() - 2).min(MAX_TEXT_WIDTH);
+        let mut lines = RichLines::layout(self.text, text_width);
+        lines.truncate(MAX_LINES, text_width);
         let line_dimensions = lines.dimensions();
 
         let dimensions = Dimension::new(line_dimensions.width + 2, line_dimensions.height + 4);
+        // the space shrinks along with the bubble, so the ones above slide down
+        let dimensions = match self.closing {
+            Some(progress) => Dimension::new(
+                (dimensions.width as f32 * (1.0 - progress)) as u32,
+                (dimensions.height as f32 * (1.0 - progress)) as u32,
+            ),
+            None => dimensions,
+        };
 
         let mut result = context.alloc_space(dimensions);
+        if dimensions.width < 4 || dimensions.height < 6 {
+            // too small to draw a bubble into
+            return result;
+        }
         result.rect.max.y -= 2;
 
         let center_x = result.rect.center().x;
 
-        let drawn_rect = if state.open {
+        let drawn_rect = if state.open || self.closing.is_some() {
             result.rect
         } else {
             state.size += 3;
@@ -135,7 +156,7 @@ impl UIElement for ChatWidget<'_> {
         );
         context.fb.draw_rect(right_line, Color::new(0, 0, 0));
 
-        if state.open {
+        if state.open && self.closing.is_none() {
             let lines_rect = Rect::centered_in(result.rect, line_dimensions);
             lines.draw(context.fb, lines_rect.min, Color::new(0, 0, 0));
         }
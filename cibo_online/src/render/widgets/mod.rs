@@ -1,2 +1,8 @@
 pub mod chat_widget;
 pub use chat_widget::ChatWidget;
+
+pub mod chat_log;
+pub use chat_log::ChatLog;
+
+pub mod rich_label;
+pub use rich_label::RichLabel;
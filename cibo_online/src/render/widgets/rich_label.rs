@@ -0,0 +1,27 @@
+use crate::render::rich_text::RichLines;
+use monos_gfx::{
+    ui::{UIContext, UIElement, UIResult},
+    Color,
+};
+
+/// a single label that can fall back to other fonts, for text that isn't plain ascii
+#[derive(Debug, Clone)]
+pub struct RichLabel<'a> {
+    text: &'a str,
+    color: Color,
+}
+
+impl<'a> RichLabel<'a> {
+    pub fn new(text: &'a str, color: Color) -> Self {
+        Self { text, color }
+    }
+}
+
+impl UIElement for RichLabel<'_> {
+    fn draw(self, context: &mut UIContext) -> UIResult {
+        let lines = RichLines::layout(self.text, context.placer.max_width());
+        let result = context.alloc_space(lines.dimensions());
+        lines.draw(context.fb, result.rect.min, self.color);
+        result
+    }
+}
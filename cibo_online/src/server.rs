@@ -1,23 +1,249 @@
+mod chalk;
+mod clock;
+mod cosmetics;
+mod cutscene;
+pub use cutscene::{Cutscene, CUTSCENE_STEP_LIMIT};
+mod minigame;
+
+mod anticheat;
+pub use anticheat::{Flag, Suspicion, SPEED_SLACK};
+
+mod inspect;
+pub use inspect::ObjectInfo;
+
+mod mail;
+pub use mail::Letter;
+
+mod protection;
+pub use protection::ProtectedRegion;
+
+mod shop;
+pub use shop::SEASHELLS_PER_POINT;
+
+mod snapshot;
+pub use snapshot::TickSnapshot;
+
+mod staff;
+pub use staff::STAFF_DECORATIONS;
+
+mod transfer;
+
 use crate::{
-    client::{ClientGameState, ClientMessage},
-    BoxedNetworkObject, Client, ClientAction, ClientId, CollisionInfo, CollisionTester,
-    NetworkObjectId, Object, ObjectId, WorldState,
+    asset_pack::AssetPackInfo,
+    client::{
+        ClientGameState, ClientMessage, CosmeticId, MoveDirection, PlayStats, PlayerSettings,
+    },
+    envelope, vec_to_f32,
+    world::{
+        objects::{
+            BeachBall, CountdownBoard, Easel, GalleryWall, MessageBoard, PollBoard, ScheduledEvent,
+            Shop, ShopItem, TrophyCase, DEFAULT_BOARD_TEXT, GALLERY_LIMIT,
+        },
+        ChalkStroke,
+    },
+    BoxedNetworkObject, CiboError, Client, ClientAction, ClientId, CollisionInfo, CollisionTester,
+    Elevation, EnvelopeError, GameTuning, Minigame, MinigameKind, Object, ObjectId, ObjectMessage,
+    Painting, Physics, PhysicsParam, PlaceableObject, Poll, Standings, TuningParam, WorldState,
 };
 
 use alloc::{
     boxed::Box,
+    collections::VecDeque,
+    format,
     string::{String, ToString},
     vec::Vec,
 };
-use monos_gfx::Rect;
+use core::{
+    cell::RefCell,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use hashbrown::HashMap;
+use monos_gfx::{Position, Rect};
 use rand::SeedableRng;
+use rustc_hash::FxBuildHasher;
 use serde::{Deserialize, Serialize};
 
+/// the compressed full state is sent in parts of this size, so big worlds don't end up in one huge frame
+const FULL_STATE_PART_SIZE: usize = 16 * 1024;
+
+/// how far back in time the server is willing to accept action timestamps
+pub const MAX_LAG_COMPENSATION_MS: u64 = 250;
+
+/// objects further away from a client than this get state updates at the reduced rate
+const INTEREST_RADIUS: i64 = 320;
+/// objects slower than this get state updates at the reduced rate
+const FAST_OBJECT_SPEED: f32 = 1.0;
+/// time between state updates for far away or slow objects, 5Hz
+const REDUCED_UPDATE_INTERVAL_MS: u64 = 200;
+
+/// objects spawned by admins or events are scattered across this area
+const SPAWN_AREA_X: core::ops::Range<i64> = -2000..2000;
+const SPAWN_AREA_Y: core::ops::Range<i64> = -1000..1000;
+const BEACH_EPISODE_BALLS: usize = 500;
+
+/// new players show up around here if there are no spawn points in the world
+const DEFAULT_PLAYER_SPAWN_X: core::ops::Range<i64> = -48..48;
+const DEFAULT_PLAYER_SPAWN_Y: core::ops::Range<i64> = -48..48;
+/// how often to look for a free spot before giving up and stacking players
+const SPAWN_ATTEMPTS: usize = 16;
+/// players are drawn this big, their position is the top left corner
+const PLAYER_SIZE: i64 = 32;
+
+/// how long a teleport request can be accepted for
+pub const TELEPORT_REQUEST_TIMEOUT_MS: u64 = 60_000;
+/// minimum time between two teleport requests of the same client
+const TELEPORT_COOLDOWN_MS: u64 = 30_000;
+/// minimum time between two trips home of the same client
+const HOME_COOLDOWN_MS: u64 = 60_000;
+/// how long a chat message can be edited after it was sent
+pub const CHAT_EDIT_WINDOW_MS: u64 = 30_000;
+/// pushes of the same player closer together than this are one kick, a ball gets pushed every tick it is touched
+const KICK_COOLDOWN_MS: u64 = 500;
+/// moves further than this in one tick are teleports and don't count as walking
+const MAX_STEP: i64 = 32;
+const TILE_SIZE: u64 = 16;
+
+/// the next event from the schedule, shown on countdown boards
+#[derive(Debug, Clone)]
+pub struct NextEvent {
+    pub id: u64,
+    pub name: String,
+    /// milliseconds until the event starts, negative if it already started
+    pub starts_in_ms: i64,
+    pub rsvps: u32,
+}
+
+/// how many objects of a kind there are and how many have come and gone
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObjectStats {
+    pub count: usize,
+    pub cap: usize,
+    pub spawned: u64,
+    pub despawned: u64,
+}
+
+/// the latest state update of an object that a client hasn't received yet
+struct ThrottledUpdate {
+    last_sent: Option<u64>,
+    pending: Option<Vec<u8>>,
+}
+
 pub struct ServerGameState<T> {
+    time_ms: u64,
     world: WorldState,
     notify_client: Box<dyn Fn(&T, ServerMessage) + Send + Sync>,
     client_mapping: Vec<(ClientId, T)>,
     queued_moves: Vec<(ClientId, ClientAction)>,
+    position_history: Vec<(ClientId, VecDeque<(u64, Position)>)>,
+    minigame_cooldown_until: u64,
+    editors: Vec<ClientId>,
+    /// clients with an event staff account, see `grant_staff`
+    staff: Vec<ClientId>,
+    /// decorations put out by staff, they count towards `STAFF_DECORATION_LIMIT`
+    staff_decorations: Vec<ObjectId>,
+    /// clients muted by staff and until when
+    staff_mutes: Vec<(ClientId, u64)>,
+    /// until when messages from a client to an object are ignored, see `interaction_cooldown_ms`
+    interaction_cooldowns: Vec<(ClientId, ObjectId, u64)>,
+    placed_objects: Vec<(ObjectId, PlacementId, PlaceableObject)>,
+    /// editor changes since the last call to `take_placed_changes`
+    placed_changes: Vec<PlacedChange>,
+    gallery: Vec<Painting>,
+    gallery_changed: bool,
+    next_chat_id: u64,
+    /// minimum time between two chat messages of the same client
+    slow_mode_ms: Option<u64>,
+    last_chat: Vec<(ClientId, u64)>,
+    /// the newest chat message of each client and when it was sent, so it can be edited for a bit
+    last_chat_message: Vec<(ClientId, ChatId, u64)>,
+    /// edited messages that weren't taken by `take_chat_edits` yet
+    chat_edits: Vec<ChatId>,
+    throttled_updates: HashMap<(ClientId, ObjectId), ThrottledUpdate, FxBuildHasher>,
+    /// objects that were spawned instead of placed, they don't get persisted
+    spawned_objects: Vec<(ObjectId, PlaceableObject)>,
+    object_caps: Vec<(PlaceableObject, usize)>,
+    /// total spawned and despawned objects per kind
+    spawn_counts: Vec<(PlaceableObject, u64, u64)>,
+    rng: rand::rngs::SmallRng,
+    /// (blocker, blocked) pairs. chat and whispers from the blocked client aren't delivered to the blocker
+    blocks: Vec<(ClientId, ClientId)>,
+    /// players without an account and when their time runs out
+    guests: Vec<Guest>,
+    next_event: Option<ScheduledEvent>,
+    event_rsvps: u32,
+    /// rsvps from countdown boards that still have to be stored, with the event they are for
+    rsvps: Vec<(ClientId, u64)>,
+    poll: Option<Poll>,
+    poll_votes: Vec<u32>,
+    /// votes from poll boards that still have to be stored, as (client, poll id, option)
+    votes: Vec<(ClientId, u64, u32)>,
+    standings: Vec<Standings>,
+    /// points scored in minigames that still have to be added to the leaderboards, by player name
+    minigame_points: Vec<(MinigameKind, String)>,
+    /// seashells earned that still have to be added to the accounts, as (client, amount)
+    seashells_earned: Vec<(ClientId, u64)>,
+    /// items bought at shops that still have to be checked and stored, as (client, item)
+    purchases: Vec<(ClientId, ShopItem)>,
+    wallets: Vec<shop::Wallet>,
+    spawn_spread: SpawnSpread,
+    /// area set by admins that is used instead of the spawn points, e.g. during events.
+    /// as center and how far players are spread around it
+    spawn_area: Option<(Position, u32)>,
+    /// the spawn point the next player gets when spreading round robin
+    next_spawn: usize,
+    teleport_requests: Vec<TeleportRequest>,
+    /// when each client last sent a teleport request
+    last_teleport_request: Vec<(ClientId, u64)>,
+    homes: Vec<(ClientId, Position)>,
+    /// when each client last went home
+    last_home: Vec<(ClientId, u64)>,
+    /// homes that were set since the last call to `take_home_changes` and still have to be stored
+    home_changes: Vec<(ClientId, Position)>,
+    board_text: String,
+    board_revision: u32,
+    /// message board revisions that were read and still have to be stored, as (client, revision)
+    board_reads: Vec<(ClientId, u32)>,
+    sessions: Vec<(ClientId, Session)>,
+    next_achievement_check: u64,
+    /// strokes drawn since the server binary last took them
+    new_chalk: Vec<ChalkStroke>,
+    next_chalk_expiry: u64,
+    protected_regions: Vec<ProtectedRegion>,
+    /// unix time minus `time_ms`, once the server binary told us
+    unix_offset: Option<i64>,
+    last_time_sync: u64,
+    /// letters posted since the server binary last took them, as (sender, recipient name, message)
+    outgoing_letters: Vec<(ClientId, String, String)>,
+    suspicion: Vec<(ClientId, anticheat::ClientSuspicion)>,
+    /// flags raised since the server binary last took them
+    flags: Vec<Flag>,
+    cutscene: Option<cutscene::RunningCutscene>,
+    /// messages held back while `tick_snapshot` runs, `None` otherwise
+    deferred: RefCell<Option<Vec<(ServerMessage, NotifyTarget)>>>,
+}
+
+/// what a player did since joining, taken by the server binary when they leave
+#[derive(Debug, Default)]
+struct Session {
+    /// stats of all earlier sessions of the account, only used for unlocking cosmetics
+    earlier: PlayStats,
+    joined_at: u64,
+    walked: u64,
+    messages_sent: u64,
+    balls_kicked: u64,
+    last_kick: Option<u64>,
+}
+
+struct Guest {
+    client: ClientId,
+    expires_at: u64,
+    expired: bool,
+}
+
+struct TeleportRequest {
+    requester: ClientId,
+    target: ClientId,
+    expires_at: u64,
 }
 
 impl<T> core::fmt::Debug for ServerGameState<T> {
@@ -33,19 +259,91 @@ impl<T> core::fmt::Debug for ServerGameState<T> {
 enum NotifyTarget {
     All,
     AllExcept(ClientId),
+    /// everyone that didn't block the given client
+    AllNotBlocking(ClientId),
     Only(ClientId),
 }
 
+impl NotifyTarget {
+    /// whether a client gets the message, `blocks` are (blocker, blocked) pairs
+    fn includes(&self, id: ClientId, blocks: &[(ClientId, ClientId)]) -> bool {
+        match *self {
+            NotifyTarget::All => true,
+            NotifyTarget::AllExcept(except_id) => id != except_id,
+            NotifyTarget::AllNotBlocking(sender) => !blocks.contains(&(id, sender)),
+            NotifyTarget::Only(target_id) => id == target_id,
+        }
+    }
+}
+
 impl<T> ServerGameState<T> {
     pub fn new<F>(notify_client: F) -> Self
     where
         F: Fn(&T, ServerMessage) + Send + Sync + 'static,
     {
         ServerGameState {
+            time_ms: 0,
             world: WorldState::new(),
             notify_client: Box::new(notify_client),
             client_mapping: Vec::new(),
             queued_moves: Vec::new(),
+            position_history: Vec::new(),
+            minigame_cooldown_until: 0,
+            editors: Vec::new(),
+            staff: Vec::new(),
+            staff_decorations: Vec::new(),
+            staff_mutes: Vec::new(),
+            interaction_cooldowns: Vec::new(),
+            placed_objects: Vec::new(),
+            placed_changes: Vec::new(),
+            gallery: Vec::new(),
+            gallery_changed: false,
+            next_chat_id: 0,
+            slow_mode_ms: None,
+            last_chat: Vec::new(),
+            last_chat_message: Vec::new(),
+            chat_edits: Vec::new(),
+            throttled_updates: HashMap::with_hasher(FxBuildHasher::default()),
+            spawned_objects: Vec::new(),
+            object_caps: Vec::new(),
+            spawn_counts: Vec::new(),
+            rng: rand::rngs::SmallRng::seed_from_u64(0),
+            blocks: Vec::new(),
+            guests: Vec::new(),
+            next_event: None,
+            event_rsvps: 0,
+            rsvps: Vec::new(),
+            poll: None,
+            poll_votes: Vec::new(),
+            votes: Vec::new(),
+            standings: Vec::new(),
+            minigame_points: Vec::new(),
+            seashells_earned: Vec::new(),
+            purchases: Vec::new(),
+            wallets: Vec::new(),
+            spawn_spread: SpawnSpread::RoundRobin,
+            spawn_area: None,
+            next_spawn: 0,
+            teleport_requests: Vec::new(),
+            last_teleport_request: Vec::new(),
+            homes: Vec::new(),
+            last_home: Vec::new(),
+            home_changes: Vec::new(),
+            board_text: String::from(DEFAULT_BOARD_TEXT),
+            board_revision: 0,
+            board_reads: Vec::new(),
+            sessions: Vec::new(),
+            next_achievement_check: 0,
+            new_chalk: Vec::new(),
+            next_chalk_expiry: 0,
+            protected_regions: Vec::new(),
+            unix_offset: None,
+            last_time_sync: 0,
+            outgoing_letters: Vec::new(),
+            suspicion: Vec::new(),
+            flags: Vec::new(),
+            cutscene: None,
+            deferred: RefCell::new(None),
         }
     }
 
@@ -56,6 +354,28 @@ impl<T> ServerGameState<T> {
     pub fn remove_client(&mut self, client_id: ClientId) {
         self.client_mapping.retain(|(id, _)| *id != client_id);
         self.world.clients.retain(|c| c.id() != client_id);
+        self.position_history.retain(|(id, _)| *id != client_id);
+        self.editors.retain(|id| *id != client_id);
+        self.staff.retain(|id| *id != client_id);
+        self.staff_mutes.retain(|(id, _)| *id != client_id);
+        self.interaction_cooldowns
+            .retain(|(id, _, _)| *id != client_id);
+        self.last_chat.retain(|(id, _)| *id != client_id);
+        self.last_chat_message.retain(|(id, _, _)| *id != client_id);
+        self.blocks
+            .retain(|(blocker, blocked)| *blocker != client_id && *blocked != client_id);
+        self.guests.retain(|guest| guest.client != client_id);
+        self.throttled_updates
+            .retain(|(client, _), _| *client != client_id);
+        self.teleport_requests
+            .retain(|request| request.requester != client_id && request.target != client_id);
+        self.last_teleport_request
+            .retain(|(id, _)| *id != client_id);
+        self.homes.retain(|(id, _)| *id != client_id);
+        self.last_home.retain(|(id, _)| *id != client_id);
+        self.suspicion.retain(|(id, _)| *id != client_id);
+        self.wallets.retain(|wallet| wallet.client != client_id);
+        self.minigame_client_left(client_id);
 
         self.notify_clients(
             ServerMessage::ClientLeft(client_id),
@@ -64,12 +384,18 @@ impl<T> ServerGameState<T> {
     }
 
     pub fn tick(&mut self, delta_ms: u64) {
+        self.time_ms += delta_ms;
+        self.expire_guests();
+        self.send_time_sync_if_due();
+        self.step_cutscene();
+
         let mut messages = Vec::new();
 
         struct CollectedHitbox {
             id: ObjectId,
             hitbox: Rect,
             info: CollisionInfo,
+            elevation: Elevation,
         }
         let hitboxes = self
             .world
@@ -80,21 +406,24 @@ impl<T> ServerGameState<T> {
                     id: *id,
                     hitbox: object.hitbox()?,
                     info: object.collision_info(),
+                    elevation: object.properties().elevation,
                 })
             })
             .collect::<Vec<_>>();
 
         let mut collisions = Vec::new();
 
+        let physics = self.world.physics;
         for (id, object) in self.world.network_objects.iter_mut() {
             let mut collision_tester = |object: &mut dyn Object| {
                 let hitbox = object.hitbox()?;
                 let collision_info = object.collision_info();
+                let elevation = object.properties().elevation;
 
                 hitboxes
                     .iter()
                     .filter_map(|other| {
-                        if *id == other.id {
+                        if *id == other.id || elevation != other.elevation {
                             return None;
                         }
 
@@ -108,7 +437,11 @@ impl<T> ServerGameState<T> {
                     })
                     .next()
             };
-            object.tick(delta_ms, CollisionTester::new(&mut collision_tester));
+            object.tick(
+                delta_ms,
+                &physics,
+                CollisionTester::new(&mut collision_tester),
+            );
         }
 
         for (id, info) in collisions {
@@ -119,61 +452,273 @@ impl<T> ServerGameState<T> {
 
         for (id, object) in self.world.network_objects.iter_mut() {
             if let Ok(Some(data)) = object.server_tick() {
-                messages.push((*id, data))
+                messages.push((*id, data, object.throttle_tick_updates()))
             }
         }
 
-        for (id, msg) in messages {
-            self.notify_clients(ServerMessage::UpdateObject(id, msg), NotifyTarget::All);
+        for (id, msg, throttle) in messages {
+            if throttle {
+                self.queue_throttled_update(id, msg);
+            } else {
+                self.notify_clients(ServerMessage::UpdateObject(id, msg), NotifyTarget::All);
+            }
         }
+        self.flush_throttled_updates();
 
-        if self.queued_moves.is_empty() {
-            return;
+        // keep clients without a fresh action moving in their last direction, swimmers only move
+        // every other tick like on the client
+        let tick = self.time_ms / crate::SERVER_TICK_RATE;
+        let last_tick = (self.time_ms - delta_ms) / crate::SERVER_TICK_RATE;
+        let (ticks, swim_ticks) = (tick - last_tick, tick / 2 - last_tick / 2);
+        let beach_episode = self.world.special_events.beach_episode;
+        for client in self.world.clients.iter_mut() {
+            if !self.queued_moves.iter().any(|(id, _)| *id == client.id()) {
+                client.predict_movement(if client.in_water(beach_episode) {
+                    swim_ticks
+                } else {
+                    ticks
+                });
+            }
         }
 
-        self.notify_clients(
-            ServerMessage::UpdateState(self.queued_moves.clone()),
-            NotifyTarget::All,
-        );
+        let action_times = self
+            .queued_moves
+            .iter()
+            .filter_map(|(id, action)| Some((*id, action.get_timestamp()?)))
+            .collect::<Vec<_>>();
+
+        if !self.queued_moves.is_empty() {
+            self.check_moves();
+
+            // apply actions in the order they actually happened, not the order they arrived in
+            self.queued_moves
+                .sort_by_key(|(_, action)| action.get_timestamp().unwrap_or(u64::MAX));
+
+            for (_, action) in self.queued_moves.iter_mut() {
+                action.stamp_broadcast(self.time_ms);
+            }
+            self.notify_clients(
+                ServerMessage::UpdateState(self.queued_moves.clone()),
+                NotifyTarget::All,
+            );
+
+            for (id, action) in self.queued_moves.drain(..) {
+                if let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == id) {
+                    client.apply_action(&action);
+                }
+            }
+        }
+
+        self.record_positions();
+        self.tick_minigame(&action_times);
+        self.check_achievements();
+        self.expire_chalk();
+    }
+
+    fn record_positions(&mut self) {
+        let min_time = self.time_ms.saturating_sub(MAX_LAG_COMPENSATION_MS);
+        for client in &self.world.clients {
+            let index = match self
+                .position_history
+                .iter()
+                .position(|(id, _)| *id == client.id())
+            {
+                Some(index) => index,
+                None => {
+                    self.position_history.push((client.id(), VecDeque::new()));
+                    self.position_history.len() - 1
+                }
+            };
+
+            let history = &mut self.position_history[index].1;
+            if let Some((_, last)) = history.back() {
+                let step = (client.position.x - last.x).abs() + (client.position.y - last.y).abs();
+                if let (true, Some((_, session))) = (
+                    step <= MAX_STEP,
+                    self.sessions.iter_mut().find(|(id, _)| *id == client.id()),
+                ) {
+                    session.walked += step as u64;
+                }
+            }
+            history.push_back((self.time_ms, client.position));
+            while history.front().map_or(false, |(time, _)| *time < min_time) {
+                history.pop_front();
+            }
+        }
+    }
+
+    fn queue_throttled_update(&mut self, object: ObjectId, msg: Vec<u8>) {
+        for (client, _) in &self.client_mapping {
+            self.throttled_updates
+                .entry((*client, object))
+                .or_insert(ThrottledUpdate {
+                    last_sent: None,
+                    pending: None,
+                })
+                .pending = Some(msg.clone());
+        }
+    }
+
+    /// whether the client should receive every state update of the object
+    fn wants_full_rate(&self, client_id: ClientId, object_id: ObjectId) -> bool {
+        let (Some(client), Some(object)) = (
+            self.world.clients.iter().find(|c| c.id() == client_id),
+            self.world.network_objects.get(&object_id),
+        ) else {
+            return false;
+        };
+
+        let properties = object.properties();
+        let center = properties.position + properties.dimensions.center();
+        let distance = center - client.position;
+        let (vx, vy) = vec_to_f32(object.collision_info().velocity());
+
+        distance.x * distance.x + distance.y * distance.y <= INTEREST_RADIUS * INTEREST_RADIUS
+            && vx * vx + vy * vy >= FAST_OBJECT_SPEED * FAST_OBJECT_SPEED
+    }
+
+    /// send pending state updates to the clients that are due for one. updates are only held back,
+    /// never dropped, so that clients always end up with the final state
+    fn flush_throttled_updates(&mut self) {
+        let mut due = Vec::new();
+        for (&(client, object), update) in self.throttled_updates.iter() {
+            if update.pending.is_none() {
+                continue;
+            }
+            let elapsed = update
+                .last_sent
+                .map_or(u64::MAX, |last_sent| self.time_ms - last_sent);
+            if elapsed >= REDUCED_UPDATE_INTERVAL_MS || self.wants_full_rate(client, object) {
+                due.push((client, object));
+            }
+        }
 
-        let mut clients = self.world.clients.iter_mut();
-        for queued in self.queued_moves.drain(..) {
-            if let Some(client) = clients.find(|c| c.id() == queued.0) {
-                client.apply_action(&queued.1);
+        for (client, object) in due {
+            let Some(update) = self.throttled_updates.get_mut(&(client, object)) else {
+                continue;
+            };
+            update.last_sent = Some(self.time_ms);
+            if let Some(msg) = update.pending.take() {
+                self.notify_clients(
+                    ServerMessage::UpdateObject(object, msg),
+                    NotifyTarget::Only(client),
+                );
             }
         }
     }
 
-    pub fn update(&mut self, client_id: ClientId, client_msg: ClientMessage) {
+    /// where a client was at the given server time, as far as the recorded history goes back.
+    fn position_at(&self, client_id: ClientId, time: u64) -> Option<Position> {
+        let (_, history) = self
+            .position_history
+            .iter()
+            .find(|(id, _)| *id == client_id)?;
+
+        history
+            .iter()
+            .rev()
+            .find(|(recorded, _)| *recorded <= time)
+            .or(history.front())
+            .map(|(_, position)| *position)
+    }
+
+    fn random_client(&self) -> Option<ClientId> {
+        use rand::Rng;
+
+        if self.world.clients.is_empty() {
+            return None;
+        }
+
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(self.time_ms);
+        Some(self.world.clients[rng.gen_range(0..self.world.clients.len())].id())
+    }
+
+    /// where everyone is standing right now
+    pub fn client_positions(&self) -> Vec<Position> {
+        self.world
+            .clients
+            .iter()
+            .map(|client| client.position)
+            .collect()
+    }
+
+    pub fn client_name(&self, id: ClientId) -> String {
+        self.world
+            .clients
+            .iter()
+            .find(|c| c.id() == id)
+            .map_or_else(|| "(left)".to_string(), |c| c.name().to_string())
+    }
+
+    /// handle a message from a client. returns the id of the chat message or whisper it sent, if any
+    pub fn update(
+        &mut self,
+        client_id: ClientId,
+        client_msg: ClientMessage,
+    ) -> Result<Option<ChatId>, CiboError> {
+        // guests that ran out of time can only wait for the host to register them
+        if self.guest_expired(client_id)
+            && !matches!(
+                client_msg,
+                ClientMessage::Ping(_)
+                    | ClientMessage::UpdateSettings(_)
+                    | ClientMessage::Register
+                    | ClientMessage::Block(_)
+                    | ClientMessage::Unblock(_)
+            )
+        {
+            return Ok(None);
+        }
+
         match client_msg {
-            ClientMessage::Connect { mut name } => {
-                name.truncate(crate::NAME_LIMIT);
-                let mut name = name.trim().to_string();
+            ClientMessage::Connect { name, settings, .. } => {
+                let mut name = crate::names::clean(&name);
                 if name.is_empty() {
                     name = "Anon".to_string();
                 }
 
                 if self.world.clients.iter().any(|c| c.id() == client_id) {
-                    return;
+                    return Ok(None);
                 }
 
-                let client = Client::new(client_id, name, Default::default());
+                let position = self.spawn_position();
+                let mut client = Client::new(client_id, name, position);
+                client.cosmetics = self.cosmetics_for(&PlayStats::default(), &[]);
+                client.safe_chat = settings.safe_chat;
                 self.world.clients.push(client.clone());
+                self.sessions.push((
+                    client_id,
+                    Session {
+                        joined_at: self.time_ms,
+                        ..Default::default()
+                    },
+                ));
 
-                self.notify_clients(
-                    ServerMessage::FullState(SerializedClientGameState::new(
-                        client_id,
-                        &self.world,
-                    )),
-                    NotifyTarget::Only(client_id),
-                );
+                for part in SerializedClientGameState::new(client_id, &self.world)?.into_parts()? {
+                    self.notify_clients(part, NotifyTarget::Only(client_id));
+                }
 
                 self.notify_clients(
                     ServerMessage::NewClient(client),
                     NotifyTarget::AllExcept(client_id),
                 );
+
+                if self.slow_mode_ms.is_some() {
+                    self.notify_clients(
+                        ServerMessage::SlowMode(self.slow_mode_ms),
+                        NotifyTarget::Only(client_id),
+                    );
+                }
+                self.send_time_sync_to(client_id);
             }
-            ClientMessage::Action(action) => {
+            ClientMessage::Action(mut action) => {
+                action.clamp_timestamp(
+                    self.time_ms.saturating_sub(MAX_LAG_COMPENSATION_MS),
+                    self.time_ms,
+                );
+                action.stamp_received(self.time_ms);
+                self.count_action(client_id);
+
                 if let Some((_, existing_action)) = self
                     .queued_moves
                     .iter_mut()
@@ -184,158 +729,2078 @@ impl<T> ServerGameState<T> {
                     self.queued_moves.push((client_id, action));
                 }
             }
+            ClientMessage::Chat(..)
+            | ClientMessage::Whisper { .. }
+            | ClientMessage::EditChat { .. }
+                if self.staff_muted(client_id) =>
+            {
+                self.system_chat_to(client_id, "you are muted by staff".to_string());
+            }
+            ClientMessage::Chat(..) | ClientMessage::Whisper { .. }
+                if !self.chat_allowed(client_id) =>
+            {
+                self.notify_clients(
+                    ServerMessage::SystemChat("slow mode is on, please wait a bit".to_string()),
+                    NotifyTarget::Only(client_id),
+                );
+            }
             ClientMessage::Chat(mut message) => {
-                message.truncate(crate::MESSAGE_LIMIT);
-                self.notify_clients(ServerMessage::Chat(client_id, message), NotifyTarget::All)
+                crate::clip_message(&mut message);
+                let id = self.take_chat_id();
+                self.last_chat_message
+                    .retain(|(sender, _, _)| *sender != client_id);
+                self.last_chat_message.push((client_id, id, self.time_ms));
+                if let Some(session) = self.session_mut(client_id) {
+                    session.messages_sent += 1;
+                }
+                self.notify_clients(
+                    ServerMessage::Chat(id, client_id, message),
+                    NotifyTarget::AllNotBlocking(client_id),
+                );
+                return Ok(Some(id));
+            }
+            ClientMessage::EditChat {
+                message_id,
+                mut new_text,
+            } => {
+                crate::clip_message(&mut new_text);
+                let now = self.time_ms;
+                let editable = self.last_chat_message.iter().any(|(id, chat_id, sent_at)| {
+                    *id == client_id
+                        && *chat_id == message_id
+                        && now < sent_at + CHAT_EDIT_WINDOW_MS
+                });
+                if !editable {
+                    self.system_chat_to(
+                        client_id,
+                        format!(
+                            "only your newest message can be edited, for {} seconds",
+                            CHAT_EDIT_WINDOW_MS / 1000
+                        ),
+                    );
+                    return Ok(None);
+                }
+                if new_text.is_empty() {
+                    return Ok(None);
+                }
+
+                self.chat_edits.push(message_id);
+                self.notify_clients(
+                    ServerMessage::ChatEdited {
+                        message_id,
+                        message: new_text,
+                    },
+                    NotifyTarget::AllNotBlocking(client_id),
+                )
+            }
+            ClientMessage::Whisper { to, mut message } => {
+                crate::clip_message(&mut message);
+                let to_id = match self
+                    .world
+                    .clients
+                    .iter()
+                    .find(|client| client.name().eq_ignore_ascii_case(&to))
+                {
+                    Some(client) => client.id(),
+                    None => {
+                        self.notify_clients(
+                            ServerMessage::SystemChat(format!("nobody named {} is online", to)),
+                            NotifyTarget::Only(client_id),
+                        );
+                        return Ok(None);
+                    }
+                };
+
+                let id = self.take_chat_id();
+                let whisper = ServerMessage::Whisper {
+                    id,
+                    from: client_id,
+                    to: to_id,
+                    message,
+                };
+                // the sender still sees the whisper, so blocking can't be detected
+                if !self.blocks(to_id, client_id) {
+                    self.notify_clients(whisper.clone(), NotifyTarget::Only(to_id));
+                }
+                if to_id != client_id {
+                    self.notify_clients(whisper, NotifyTarget::Only(client_id));
+                }
+                if let Some(session) = self.session_mut(client_id) {
+                    session.messages_sent += 1;
+                }
+                return Ok(Some(id));
             }
             ClientMessage::UpdateObject(id, data) => {
+                let now = self.time_ms;
+                let cooling_down =
+                    self.interaction_cooldowns
+                        .iter()
+                        .any(|(client, object, until)| {
+                            *client == client_id && *object == id && now < *until
+                        });
+                if cooling_down {
+                    return Ok(None);
+                }
+
+                let sender_position = self
+                    .world
+                    .clients
+                    .iter()
+                    .find(|c| c.id() == client_id)
+                    .map(|c| c.position);
                 let object = match self.world.network_objects.get_mut(&id) {
                     Some(object) => object,
-                    None => return,
+                    None => return Ok(None),
                 };
 
-                match object.server_message(&data) {
+                if let (Some(easel), Some(position)) =
+                    (object.downcast_mut::<Easel>(), sender_position)
+                {
+                    easel.set_sender(client_id, position);
+                }
+                if let Some(ball) = object.downcast_mut::<BeachBall>() {
+                    ball.set_sender(client_id);
+                }
+                let cooldown_ms = object.interaction_cooldown_ms();
+                let result = object.server_message(&data);
+                let kicked = object.downcast_mut::<BeachBall>().is_some();
+                let painting = object
+                    .downcast_mut::<Easel>()
+                    .and_then(|easel| easel.take_painting());
+                if let Some(event_id) = object
+                    .downcast_mut::<CountdownBoard>()
+                    .and_then(|board| board.take_rsvp())
+                {
+                    self.rsvps.push((client_id, event_id));
+                }
+                if let Some((poll, option)) = object
+                    .downcast_mut::<PollBoard>()
+                    .and_then(|board| board.take_vote())
+                {
+                    self.votes.push((client_id, poll, option));
+                }
+                if let Some(item) = object
+                    .downcast_mut::<Shop>()
+                    .and_then(|shop| shop.take_purchase())
+                {
+                    self.purchases.push((client_id, item));
+                }
+                if let Some(revision) = object
+                    .downcast_mut::<MessageBoard>()
+                    .and_then(|board| board.take_read())
+                {
+                    self.board_reads.push((client_id, revision));
+                }
+                if cooldown_ms > 0 {
+                    self.interaction_cooldowns
+                        .retain(|(_, _, until)| now < *until);
+                    self.interaction_cooldowns
+                        .push((client_id, id, now + cooldown_ms));
+                }
+                if let (true, Some(session)) = (kicked, self.session_mut(client_id)) {
+                    if session
+                        .last_kick
+                        .map_or(true, |last| now >= last + KICK_COOLDOWN_MS)
+                    {
+                        session.balls_kicked += 1;
+                    }
+                    session.last_kick = Some(now);
+                }
+
+                let result = match result {
                     Ok(Some(msg)) => {
-                        self.notify_clients(ServerMessage::UpdateObject(id, msg), NotifyTarget::All)
+                        // held back tick updates are older than this one now
+                        for ((_, object), update) in self.throttled_updates.iter_mut() {
+                            if *object == id {
+                                update.pending = None;
+                            }
+                        }
+                        self.notify_clients(
+                            ServerMessage::UpdateObject(id, msg),
+                            NotifyTarget::All,
+                        );
+                        Ok(())
                     }
-                    Ok(None) => {}
-                    Err(_) => {}
+                    Ok(None) => Ok(()),
+                    Err(error) => Err(CiboError::Object {
+                        id,
+                        error: Box::new(error),
+                    }),
+                };
+
+                if let Some(mut painting) = painting {
+                    painting.artist = self.client_name(client_id);
+                    self.hang_painting(painting);
                 }
+                result?;
             }
-        }
-    }
+            ClientMessage::Ping(client_time) => self.notify_clients(
+                ServerMessage::Pong {
+                    client_time,
+                    server_time: self.time_ms,
+                },
+                NotifyTarget::Only(client_id),
+            ),
 
-    pub fn get_special_event(&self, event: SpecialEvent) -> bool {
-        self.world.get_special_event(event)
-    }
-    pub fn set_special_event(&mut self, event: SpecialEvent, active: bool) {
-        use crate::world::objects::*;
+            ClientMessage::EditorPlace(..)
+            | ClientMessage::EditorMove(..)
+            | ClientMessage::EditorDelete(..)
+                if !self.editors.contains(&client_id) => {}
+            ClientMessage::EditorPlace(object, position) => {
+                if self.object_count(object) >= self.object_cap(object) {
+                    self.notify_clients(
+                        ServerMessage::SystemChat(format!(
+                            "there are already too many of {}s",
+                            object.name()
+                        )),
+                        NotifyTarget::Only(client_id),
+                    );
+                    return Ok(None);
+                }
+                if self.refuse_protected(client_id, position) {
+                    return Ok(None);
+                }
+                let placement = PlacementId::new();
+                self.place_object(placement, object, position)?;
+                self.placed_changes
+                    .push(PlacedChange::Placed(placement, object, position));
+            }
+            ClientMessage::EditorMove(id, position) => {
+                let protected = self.refuse_protected(client_id, position);
+                let object = match self.world.network_objects.get_mut(&id) {
+                    Some(object) => object,
+                    None => return Ok(None),
+                };
 
-        match event {
-            SpecialEvent::BeachEpisode => {
-                let beach_ball_id: NetworkObjectId =
-                    crate::get_network_object_id::<BeachBall>().unwrap();
+                if protected {
+                    // the editor already moved it, put it back
+                    let position = object.properties().position;
+                    self.notify_clients(
+                        ServerMessage::MoveObject(id, position),
+                        NotifyTarget::Only(client_id),
+                    );
+                    return Ok(None);
+                }
+                object.set_position(position);
+                self.notify_clients(ServerMessage::MoveObject(id, position), NotifyTarget::All);
+                if let Some((_, placement, _)) = self
+                    .placed_objects
+                    .iter()
+                    .find(|(placed_id, _, _)| *placed_id == id)
+                {
+                    self.placed_changes
+                        .push(PlacedChange::Moved(*placement, position));
+                }
+            }
+            ClientMessage::EditorDelete(id) => {
+                if let Some(index) = self
+                    .placed_objects
+                    .iter()
+                    .position(|(placed_id, _, _)| *placed_id == id)
+                {
+                    let (_, placement, _) = self.placed_objects.remove(index);
+                    self.placed_changes.push(PlacedChange::Removed(placement));
+                }
+                self.remove_network_object(id);
+            }
 
-                if active {
-                    use rand::Rng;
-                    let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
-                    for _ in 0..500 {
-                        self.add_network_object(BeachBall::new(monos_gfx::Position::new(
-                            rng.gen_range(-2000..2000),
-                            rng.gen_range(-1000..1000),
-                        )));
-                    }
-                } else {
-                    let removed_ids = self
-                        .world
-                        .network_objects
-                        .iter()
-                        .filter_map(|(id, object)| {
-                            if object.id() == beach_ball_id {
-                                Some(*id)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>();
+            ClientMessage::StaffDecorate(..)
+            | ClientMessage::StaffMute(..)
+            | ClientMessage::StaffMinigame(..)
+                if !self.is_staff(client_id) =>
+            {
+                self.system_chat_to(client_id, "only event staff can do that".to_string());
+            }
+            ClientMessage::StaffDecorate(object) => self.staff_decorate(client_id, object)?,
+            ClientMessage::StaffMute(name) => self.staff_mute(client_id, &name),
+            ClientMessage::StaffMinigame(kind) => self.staff_minigame(client_id, kind),
 
-                    for id in removed_ids {
-                        self.remove_network_object(id);
-                    }
+            // stored by the server binary, only safe chat shows up in the game
+            ClientMessage::UpdateSettings(settings) => {
+                self.set_safe_chat(client_id, settings.safe_chat)
+            }
+            // goes through the same checks as typed messages, so slow mode and mutes still apply
+            ClientMessage::QuickChat(phrase) => {
+                if let Some(text) = phrase.text() {
+                    return self.update(client_id, ClientMessage::Chat(text.to_string()));
                 }
             }
-        }
 
-        self.world.set_special_event(event, active);
-        self.notify_clients(
-            ServerMessage::SpecialEvent { event, active },
-            NotifyTarget::All,
-        );
-    }
+            // handled by the server binary, which knows about accounts
+            ClientMessage::Register
+            | ClientMessage::CheckName { .. }
+            | ClientMessage::RequestStats
+            | ClientMessage::Ack(_)
+            | ClientMessage::WorldSnapshot(_)
+            | ClientMessage::Rename(_)
+            | ClientMessage::RequestTransfer => {}
 
-    fn add_network_object(&mut self, object: BoxedNetworkObject) -> ObjectId {
-        let id = ObjectId::new();
-        self.notify_clients(
-            ServerMessage::NewObject(id, SerializedNetworkObject::new(&object)),
-            NotifyTarget::All,
-        );
-        self.world.network_objects.insert(id, object);
-        id
-    }
+            ClientMessage::DrawChalk(stroke) => self.draw_chalk(client_id, stroke),
 
-    fn remove_network_object(&mut self, id: ObjectId) {
-        self.world.network_objects.remove(&id);
-        self.notify_clients(ServerMessage::DeleteObject(id), NotifyTarget::All);
-    }
+            ClientMessage::Block(blocked) => self.block(client_id, blocked),
+            ClientMessage::Unblock(blocked) => self.unblock(client_id, blocked),
 
-    fn notify_clients(&self, msg: ServerMessage, target: NotifyTarget) {
-        for (id, data) in &self.client_mapping {
-            match target {
-                NotifyTarget::All => (self.notify_client)(data, msg.clone()),
-                NotifyTarget::AllExcept(except_id) if *id != except_id => {
-                    (self.notify_client)(data, msg.clone())
-                }
-                NotifyTarget::Only(target_id) if *id == target_id => {
-                    (self.notify_client)(data, msg);
-                    break;
-                }
-                _ => {}
+            // teleporting would make tag and hide and seek pointless
+            ClientMessage::TeleportRequest(_)
+            | ClientMessage::TeleportAccept
+            | ClientMessage::Home
+                if self.world.minigame.is_some() =>
+            {
+                self.system_chat_to(
+                    client_id,
+                    "teleports are off while a minigame is running".to_string(),
+                );
             }
+            ClientMessage::TeleportRequest(to) => self.request_teleport(client_id, &to),
+            ClientMessage::TeleportAccept => self.accept_teleport(client_id),
+
+            ClientMessage::SetHome => self.set_home_here(client_id),
+            ClientMessage::Home => self.go_home(client_id),
+
+            ClientMessage::SendMail { to, message } => self.post_letter(client_id, to, message),
         }
+        Ok(None)
     }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum ServerMessage {
-    FullState(SerializedClientGameState),
 
-    NewClient(Client),
-    ClientLeft(ClientId),
-    UpdateState(Vec<(ClientId, ClientAction)>),
-    Chat(ClientId, String),
+    fn request_teleport(&mut self, requester: ClientId, to: &str) {
+        let now = self.time_ms;
+        if let Some((_, last)) = self
+            .last_teleport_request
+            .iter()
+            .find(|(id, _)| *id == requester)
+        {
+            if now < *last + TELEPORT_COOLDOWN_MS {
+                self.system_chat_to(
+                    requester,
+                    format!(
+                        "please wait {}s before asking again",
+                        (*last + TELEPORT_COOLDOWN_MS - now).div_ceil(1000)
+                    ),
+                );
+                return;
+            }
+        }
 
-    SpecialEvent { event: SpecialEvent, active: bool },
+        let target = match self
+            .world
+            .clients
+            .iter()
+            .find(|client| client.name().eq_ignore_ascii_case(to))
+        {
+            Some(client) if client.id() == requester => {
+                self.system_chat_to(requester, "you are already there :)".to_string());
+                return;
+            }
+            Some(client) => client.id(),
+            None => {
+                self.system_chat_to(requester, format!("nobody named {} is online", to));
+                return;
+            }
+        };
 
-    NewObject(ObjectId, SerializedNetworkObject),
-    UpdateObject(ObjectId, Vec<u8>),
-    DeleteObject(ObjectId),
-}
+        self.last_teleport_request
+            .retain(|(id, _)| *id != requester);
+        self.last_teleport_request.push((requester, now));
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SerializedClientGameState(ClientId, Vec<u8>);
-impl SerializedClientGameState {
-    fn new(client_id: ClientId, world: &WorldState) -> Self {
-        Self(client_id, postcard::to_allocvec(world).unwrap())
+        // like whispers, the requester can't tell whether they were blocked
+        self.teleport_requests
+            .retain(|request| request.requester != requester && request.target != target);
+        if !self.blocks(target, requester) {
+            self.teleport_requests.push(TeleportRequest {
+                requester,
+                target,
+                expires_at: now + TELEPORT_REQUEST_TIMEOUT_MS,
+            });
+            self.notify_clients(
+                ServerMessage::TeleportRequest(requester),
+                NotifyTarget::Only(target),
+            );
+        }
+        self.system_chat_to(
+            requester,
+            format!(
+                "asked {} if you can teleport to them",
+                self.client_name(target)
+            ),
+        );
     }
 
-    pub fn serialize(self) -> ClientGameState {
-        ClientGameState::new(self.0, postcard::from_bytes(&self.1).unwrap())
-    }
-}
+    /// move the client that last asked `target` next to them
+    fn accept_teleport(&mut self, target: ClientId) {
+        let now = self.time_ms;
+        self.teleport_requests
+            .retain(|request| request.expires_at > now);
+        let Some(index) = self
+            .teleport_requests
+            .iter()
+            .position(|request| request.target == target)
+        else {
+            self.system_chat_to(target, "nobody asked to teleport to you".to_string());
+            return;
+        };
+        let requester = self.teleport_requests.swap_remove(index).requester;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SerializedNetworkObject(Vec<u8>);
-impl SerializedNetworkObject {
-    fn new(object: &BoxedNetworkObject) -> Self {
-        Self(postcard::to_allocvec(object).unwrap())
-    }
+        let Some(target_client) = self.world.clients.iter().find(|c| c.id() == target) else {
+            return;
+        };
+        let (center, elevation) = (target_client.position, target_client.elevation);
 
-    pub fn serialize(self) -> BoxedNetworkObject {
-        postcard::from_bytes(&self.0).unwrap()
+        // right next to the target if there is room, otherwise on top of them
+        let position = [
+            Position::new(PLAYER_SIZE, 0),
+            Position::new(-PLAYER_SIZE, 0),
+            Position::new(0, PLAYER_SIZE),
+            Position::new(0, -PLAYER_SIZE),
+        ]
+        .into_iter()
+        .map(|offset| center + offset)
+        .find(|position| elevation != Elevation::Ground || self.spawn_free(*position))
+        .unwrap_or(center);
+
+        self.teleport(requester, position, elevation);
+        self.system_chat_to(
+            requester,
+            format!("teleported to {}", self.client_name(target)),
+        );
     }
-}
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub enum SpecialEvent {
-    BeachEpisode,
-}
+    /// move a client somewhere else right away and tell everyone about it
+    fn teleport(&mut self, client_id: ClientId, position: Position, elevation: Elevation) {
+        let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == client_id) else {
+            return;
+        };
+        client.position = position;
+        client.movement = MoveDirection::None;
+        client.elevation = elevation;
+        // movement sent before the teleport would put the client back
+        self.queued_moves.retain(|(id, _)| *id != client_id);
+        self.grant_move_grace(client_id);
 
-impl ServerMessage {
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self, postcard::Error> {
-        postcard::from_bytes(bytes)
+        self.notify_clients(
+            ServerMessage::Teleport {
+                client: client_id,
+                position,
+                elevation,
+            },
+            NotifyTarget::All,
+        );
     }
 
-    pub fn to_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
-        postcard::to_allocvec(self)
+    /// the home stored for a client's account, sent to the client so it can show it on the minimap
+    pub fn set_home(&mut self, client_id: ClientId, home: Option<Position>) {
+        self.homes.retain(|(id, _)| *id != client_id);
+        if let Some(home) = home {
+            self.homes.push((client_id, home));
+        }
+        self.notify_clients(ServerMessage::Home(home), NotifyTarget::Only(client_id));
+    }
+
+    fn set_home_here(&mut self, client_id: ClientId) {
+        let Some(client) = self.world.clients.iter().find(|c| c.id() == client_id) else {
+            return;
+        };
+        let position = client.position;
+        if client.elevation != Elevation::Ground {
+            self.system_chat_to(client_id, "homes can only be on the ground".to_string());
+            return;
+        }
+        if self.blocked_by_object(position) {
+            self.system_chat_to(client_id, "there is no room for a home here".to_string());
+            return;
+        }
+
+        self.set_home(client_id, Some(position));
+        self.home_changes.retain(|(id, _)| *id != client_id);
+        self.home_changes.push((client_id, position));
+        self.system_chat_to(
+            client_id,
+            format!("home set at X{} / Y{}", position.x / 16, position.y / 16),
+        );
+    }
+
+    fn go_home(&mut self, client_id: ClientId) {
+        let Some(home) = self
+            .homes
+            .iter()
+            .find(|(id, _)| *id == client_id)
+            .map(|(_, home)| *home)
+        else {
+            self.system_chat_to(client_id, "set a home with /sethome first".to_string());
+            return;
+        };
+
+        let now = self.time_ms;
+        if let Some((_, last)) = self.last_home.iter().find(|(id, _)| *id == client_id) {
+            if now < *last + HOME_COOLDOWN_MS {
+                self.system_chat_to(
+                    client_id,
+                    format!(
+                        "please wait {}s before going home again",
+                        (*last + HOME_COOLDOWN_MS - now).div_ceil(1000)
+                    ),
+                );
+                return;
+            }
+        }
+        // the world could have changed since the home was set
+        if self.blocked_by_object(home) {
+            self.system_chat_to(
+                client_id,
+                "something is in the way at your home".to_string(),
+            );
+            return;
+        }
+
+        self.last_home.retain(|(id, _)| *id != client_id);
+        self.last_home.push((client_id, now));
+        self.teleport(client_id, home, Elevation::Ground);
+    }
+
+    fn session_mut(&mut self, client_id: ClientId) -> Option<&mut Session> {
+        self.sessions
+            .iter_mut()
+            .find(|(id, _)| *id == client_id)
+            .map(|(_, session)| session)
+    }
+
+    /// what the client did since joining
+    pub fn play_stats(&self, client_id: ClientId) -> PlayStats {
+        self.sessions
+            .iter()
+            .find(|(id, _)| *id == client_id)
+            .map_or_else(PlayStats::default, |(_, session)| PlayStats {
+                time_online_ms: self.time_ms - session.joined_at,
+                tiles_walked: session.walked / TILE_SIZE,
+                messages_sent: session.messages_sent,
+                balls_kicked: session.balls_kicked,
+            })
+    }
+
+    /// the stats of a client that left, so they can be added to their account.
+    /// kept after `remove_client`, since that is also called when a client gets banned
+    pub fn take_play_stats(&mut self, client_id: ClientId) -> Option<PlayStats> {
+        let stats = self.play_stats(client_id);
+        let index = self.sessions.iter().position(|(id, _)| *id == client_id)?;
+        self.sessions.remove(index);
+        Some(stats)
+    }
+
+    pub fn send_play_stats(&self, client_id: ClientId, stats: PlayStats) {
+        self.notify_clients(
+            ServerMessage::PlayStats(stats),
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    /// homes that were set since the last call, as (client, position).
+    pub fn take_home_changes(&mut self) -> Vec<(ClientId, Position)> {
+        core::mem::take(&mut self.home_changes)
+    }
+
+    /// stop delivering chat and whispers from `blocked` to `blocker`
+    pub fn block(&mut self, blocker: ClientId, blocked: ClientId) {
+        if blocker != blocked && !self.blocks(blocker, blocked) {
+            self.blocks.push((blocker, blocked));
+        }
+    }
+
+    pub fn unblock(&mut self, blocker: ClientId, blocked: ClientId) {
+        self.blocks.retain(|block| *block != (blocker, blocked));
+    }
+
+    fn blocks(&self, blocker: ClientId, sender: ClientId) -> bool {
+        self.blocks.contains(&(blocker, sender))
+    }
+
+    /// only allow one message every `slow_mode_ms` per client. `None` disables slow mode.
+    pub fn set_slow_mode(&mut self, slow_mode_ms: Option<u64>) {
+        self.slow_mode_ms = slow_mode_ms;
+        self.notify_clients(ServerMessage::SlowMode(slow_mode_ms), NotifyTarget::All);
+    }
+
+    pub fn slow_mode(&self) -> Option<u64> {
+        self.slow_mode_ms
+    }
+
+    /// check the slow mode cooldown of a client and restart it if the client is allowed to chat
+    fn chat_allowed(&mut self, client_id: ClientId) -> bool {
+        let slow_mode_ms = match self.slow_mode_ms {
+            Some(slow_mode_ms) => slow_mode_ms,
+            None => return true,
+        };
+
+        let now = self.time_ms;
+        match self.last_chat.iter_mut().find(|(id, _)| *id == client_id) {
+            Some((_, last)) if now < *last + slow_mode_ms => false,
+            Some((_, last)) => {
+                *last = now;
+                true
+            }
+            None => {
+                self.last_chat.push((client_id, now));
+                true
+            }
+        }
+    }
+
+    /// limit how long a client can play for, `None` once it has an account.
+    /// the client is told how much time it has left
+    pub fn set_guest_time(&mut self, client_id: ClientId, remaining_ms: Option<u64>) {
+        self.guests.retain(|guest| guest.client != client_id);
+        if let Some(remaining_ms) = remaining_ms {
+            self.guests.push(Guest {
+                client: client_id,
+                expires_at: self.time_ms + remaining_ms,
+                expired: false,
+            });
+        }
+
+        self.notify_clients(
+            ServerMessage::GuestTime(remaining_ms),
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    fn guest_expired(&self, client_id: ClientId) -> bool {
+        self.guests
+            .iter()
+            .any(|guest| guest.client == client_id && guest.expired)
+    }
+
+    /// stop guests whose time ran out where they are
+    fn expire_guests(&mut self) {
+        let time_ms = self.time_ms;
+        let expired = self
+            .guests
+            .iter_mut()
+            .filter(|guest| !guest.expired && guest.expires_at <= time_ms)
+            .map(|guest| {
+                guest.expired = true;
+                guest.client
+            })
+            .collect::<Vec<_>>();
+
+        for client_id in expired {
+            let Some(client) = self.world.clients.iter().find(|c| c.id() == client_id) else {
+                continue;
+            };
+            let mut action = ClientAction::new();
+            action.movement(client.position, MoveDirection::None);
+            action.timestamp(time_ms);
+
+            self.queued_moves.retain(|(id, _)| *id != client_id);
+            self.queued_moves.push((client_id, action));
+        }
+    }
+
+    /// start numbering chat messages at `first`, so game states running side by side don't hand out the same ids.
+    pub fn set_first_chat_id(&mut self, first: u64) {
+        self.next_chat_id = first;
+    }
+
+    /// decides where critters live on the clients. game states side by side should use the same one,
+    /// so players don't see them change when moving between them
+    pub fn set_ambient_seed(&mut self, seed: u64) {
+        self.world.ambient_seed = seed;
+    }
+
+    fn take_chat_id(&mut self) -> ChatId {
+        let id = ChatId(self.next_chat_id);
+        self.next_chat_id += 1;
+        id
+    }
+
+    /// chat messages that were edited since the last call
+    pub fn take_chat_edits(&mut self) -> Vec<ChatId> {
+        core::mem::take(&mut self.chat_edits)
+    }
+
+    /// remove a chat message from all chat logs and bubbles, e.g. because it slipped through the filter.
+    pub fn delete_chat(&mut self, message_id: ChatId) {
+        self.notify_clients(ServerMessage::DeleteChat { message_id }, NotifyTarget::All);
+    }
+
+    /// show a verified badge next to the name of the client, for players that linked their discord account
+    pub fn set_verified(&mut self, client_id: ClientId, verified: bool) {
+        let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == client_id) else {
+            return;
+        };
+        if client.verified == verified {
+            return;
+        }
+        client.verified = verified;
+        self.notify_clients(
+            ServerMessage::Verified(client_id, verified),
+            NotifyTarget::All,
+        );
+    }
+
+    /// show a safe chat badge over the head of the client, so others know it only sees the
+    /// preset phrases
+    pub fn set_safe_chat(&mut self, client_id: ClientId, safe_chat: bool) {
+        let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == client_id) else {
+            return;
+        };
+        if client.safe_chat == safe_chat {
+            return;
+        }
+        client.safe_chat = safe_chat;
+        self.notify_clients(
+            ServerMessage::SafeChat(client_id, safe_chat),
+            NotifyTarget::All,
+        );
+    }
+
+    /// change the name of a client for everyone. the host is responsible for checking the name
+    pub fn rename_client(&mut self, client_id: ClientId, name: String) {
+        let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == client_id) else {
+            return;
+        };
+        if client.name() == name {
+            return;
+        }
+        let old_name = client.name().to_string();
+        client.set_name(name.clone());
+        self.notify_clients(
+            ServerMessage::ClientRenamed(client_id, name.clone()),
+            NotifyTarget::All,
+        );
+        // so nobody gets confused about who they are talking to
+        self.system_chat(format!("{} is now called {}", old_name, name));
+    }
+
+    /// allow a client to use the world editor. the host is responsible for checking if it is allowed to.
+    pub fn grant_editor(&mut self, client_id: ClientId) {
+        if !self.editors.contains(&client_id) {
+            self.editors.push(client_id);
+            self.notify_clients(ServerMessage::EditorAccess, NotifyTarget::Only(client_id));
+        }
+    }
+
+    /// place an object like the editor does. game states running side by side should get the same
+    /// `placement` for the same object, so changes to it can be copied between them
+    pub fn place_object(
+        &mut self,
+        placement: PlacementId,
+        object: PlaceableObject,
+        position: Position,
+    ) -> Result<ObjectId, CiboError> {
+        let id = self.create_object(object, position)?;
+        self.placed_objects.push((id, placement, object));
+        Ok(id)
+    }
+
+    fn create_object(
+        &mut self,
+        object: PlaceableObject,
+        position: Position,
+    ) -> Result<ObjectId, CiboError> {
+        let network_object = self.prepare_object(object, position);
+        let id = self.add_network_object(network_object)?;
+        if object == PlaceableObject::Shop {
+            self.send_wallets(id);
+        }
+        Ok(id)
+    }
+
+    /// a pooled or new object with everything it shows filled in, not added to the world yet
+    fn prepare_object(
+        &mut self,
+        object: PlaceableObject,
+        position: Position,
+    ) -> BoxedNetworkObject {
+        let mut network_object = self
+            .world
+            .object_pool
+            .take(object.network_object_id(), position)
+            .unwrap_or_else(|| object.create(position));
+        if let Some(wall) = network_object.downcast_mut::<GalleryWall>() {
+            wall.set_paintings(self.gallery.clone());
+        }
+        if let Some(board) = network_object.downcast_mut::<CountdownBoard>() {
+            board.set_event(self.next_event.clone(), self.event_rsvps);
+        }
+        if let Some(board) = network_object.downcast_mut::<PollBoard>() {
+            board.set_poll(self.poll.clone(), self.poll_votes.clone());
+        }
+        if let Some(case) = network_object.downcast_mut::<TrophyCase>() {
+            case.set_standings(self.standings.clone());
+        }
+        if let Some(board) = network_object.downcast_mut::<MessageBoard>() {
+            board.set_text(self.board_text.clone(), self.board_revision);
+        }
+        network_object
+    }
+
+    fn object_count(&self, kind: PlaceableObject) -> usize {
+        let network_id = kind.network_object_id();
+        self.world
+            .network_objects
+            .values()
+            .filter(|object| object.id() == network_id)
+            .count()
+    }
+
+    pub fn object_cap(&self, kind: PlaceableObject) -> usize {
+        self.object_caps
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map_or(kind.default_cap(), |(_, cap)| *cap)
+    }
+
+    /// limit how many objects of a kind can be spawned or placed. existing objects are kept
+    pub fn set_object_cap(&mut self, kind: PlaceableObject, cap: usize) {
+        self.object_caps.retain(|(k, _)| *k != kind);
+        self.object_caps.push((kind, cap));
+    }
+
+    pub fn object_stats(&self, kind: PlaceableObject) -> ObjectStats {
+        let (spawned, despawned) = self
+            .spawn_counts
+            .iter()
+            .find(|(k, _, _)| *k == kind)
+            .map_or((0, 0), |(_, spawned, despawned)| (*spawned, *despawned));
+
+        ObjectStats {
+            count: self.object_count(kind),
+            cap: self.object_cap(kind),
+            spawned,
+            despawned,
+        }
+    }
+
+    fn spawn_count_entry(&mut self, kind: PlaceableObject) -> &mut (PlaceableObject, u64, u64) {
+        let index = match self.spawn_counts.iter().position(|(k, _, _)| *k == kind) {
+            Some(index) => index,
+            None => {
+                self.spawn_counts.push((kind, 0, 0));
+                self.spawn_counts.len() - 1
+            }
+        };
+        &mut self.spawn_counts[index]
+    }
+
+    /// scatter up to `count` temporary objects across the world, as far as the cap allows.
+    /// returns how many were actually spawned
+    pub fn spawn_objects(
+        &mut self,
+        kind: PlaceableObject,
+        count: usize,
+    ) -> Result<usize, CiboError> {
+        use rand::Rng;
+
+        let count = count.min(
+            self.object_cap(kind)
+                .saturating_sub(self.object_count(kind)),
+        );
+        let mut new_objects = Vec::with_capacity(count);
+        let mut failed = None;
+        for _ in 0..count {
+            let mut position = Position::new(
+                self.rng.gen_range(SPAWN_AREA_X),
+                self.rng.gen_range(SPAWN_AREA_Y),
+            );
+            for _ in 0..SPAWN_ATTEMPTS {
+                if self.protected_region(position).is_none() {
+                    break;
+                }
+                position = Position::new(
+                    self.rng.gen_range(SPAWN_AREA_X),
+                    self.rng.gen_range(SPAWN_AREA_Y),
+                );
+            }
+            let object = self.prepare_object(kind, position);
+            match self.insert_network_object(object) {
+                Ok((id, serialized)) => {
+                    new_objects.push((id, serialized));
+                    self.spawned_objects.push((id, kind));
+                }
+                Err(error) => {
+                    failed = Some(error);
+                    break;
+                }
+            }
+        }
+        // the ones that made it in still have to reach the clients
+        let spawned = new_objects.len();
+        // a single message instead of hundreds
+        if !new_objects.is_empty() {
+            self.notify_clients(ServerMessage::NewObjects(new_objects), NotifyTarget::All);
+        }
+
+        self.spawn_count_entry(kind).1 += spawned as u64;
+        match failed {
+            Some(error) => Err(error),
+            None => Ok(spawned),
+        }
+    }
+
+    /// remove all spawned objects of a kind. placed objects stay where they are.
+    /// returns how many were removed
+    pub fn clear_objects(&mut self, kind: PlaceableObject) -> usize {
+        let removed_ids = self
+            .spawned_objects
+            .iter()
+            .filter(|(_, k)| *k == kind)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        for id in &removed_ids {
+            self.take_network_object(*id);
+        }
+        let removed = removed_ids.len();
+        if removed > 0 {
+            self.notify_clients(ServerMessage::DeleteObjects(removed_ids), NotifyTarget::All);
+        }
+        removed
+    }
+
+    /// objects placed, moved or removed with the editor since the last call
+    pub fn take_placed_changes(&mut self) -> Vec<PlacedChange> {
+        core::mem::take(&mut self.placed_changes)
+    }
+
+    /// apply an editor change made in another game state
+    pub fn apply_placed_change(&mut self, change: PlacedChange) -> Result<(), CiboError> {
+        let find = |placed: &[(ObjectId, PlacementId, PlaceableObject)], placement| {
+            placed.iter().position(|(_, other, _)| *other == placement)
+        };
+        match change {
+            PlacedChange::Placed(placement, object, position) => {
+                self.place_object(placement, object, position)?;
+            }
+            PlacedChange::Moved(placement, position) => {
+                let Some(index) = find(&self.placed_objects, placement) else {
+                    return Ok(());
+                };
+                let id = self.placed_objects[index].0;
+                if let Some(object) = self.world.network_objects.get_mut(&id) {
+                    object.set_position(position);
+                    self.notify_clients(ServerMessage::MoveObject(id, position), NotifyTarget::All);
+                }
+            }
+            PlacedChange::Removed(placement) => {
+                let Some(index) = find(&self.placed_objects, placement) else {
+                    return Ok(());
+                };
+                let (id, _, _) = self.placed_objects.remove(index);
+                self.remove_network_object(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// all objects placed using the editor together with their current position, e.g. to persist them.
+    pub fn placed_objects(&self) -> Vec<(PlaceableObject, Position)> {
+        self.placed_objects
+            .iter()
+            .filter_map(|(id, _, object)| {
+                let position = self.world.network_objects.get(id)?.properties().position;
+                Some((*object, position))
+            })
+            .collect()
+    }
+
+    pub fn spawn_spread(&self) -> SpawnSpread {
+        self.spawn_spread
+    }
+
+    pub fn set_spawn_spread(&mut self, spread: SpawnSpread) {
+        self.spawn_spread = spread;
+    }
+
+    pub fn spawn_area(&self) -> Option<(Position, u32)> {
+        self.spawn_area
+    }
+
+    /// make new players show up around a spot instead of at the spawn points. `None` goes back to them
+    pub fn set_spawn_area(&mut self, area: Option<(Position, u32)>) {
+        self.spawn_area = area;
+    }
+
+    /// where a new player should show up. picks a spawn area and a spot in it that isn't taken
+    /// by an object or another player
+    fn spawn_position(&mut self) -> Position {
+        use rand::Rng;
+
+        let areas = match self.spawn_area {
+            Some((center, spread)) => {
+                let spread = Position::new(spread as i64, spread as i64);
+                alloc::vec![Rect::new(center - spread, center + spread)]
+            }
+            None => self
+                .placed_objects
+                .iter()
+                .filter(|(_, _, kind)| *kind == PlaceableObject::SpawnPoint)
+                .filter_map(|(id, _, _)| Some(self.world.network_objects.get(id)?.bounds()))
+                .collect(),
+        };
+        let area = if areas.is_empty() {
+            Rect::new(
+                Position::new(DEFAULT_PLAYER_SPAWN_X.start, DEFAULT_PLAYER_SPAWN_Y.start),
+                Position::new(DEFAULT_PLAYER_SPAWN_X.end, DEFAULT_PLAYER_SPAWN_Y.end),
+            )
+        } else {
+            match self.spawn_spread {
+                SpawnSpread::RoundRobin => {
+                    self.next_spawn = self.next_spawn.wrapping_add(1);
+                    areas[self.next_spawn % areas.len()]
+                }
+                SpawnSpread::Random => areas[self.rng.gen_range(0..areas.len())],
+            }
+        };
+
+        // keep the whole player inside the area if it is big enough
+        let max = Position::new(
+            (area.max.x - PLAYER_SIZE).max(area.min.x),
+            (area.max.y - PLAYER_SIZE).max(area.min.y),
+        );
+        let mut candidate = area.min;
+        for _ in 0..SPAWN_ATTEMPTS {
+            candidate = Position::new(
+                self.rng.gen_range(area.min.x..=max.x),
+                self.rng.gen_range(area.min.y..=max.y),
+            );
+            if self.spawn_free(candidate) {
+                break;
+            }
+        }
+        candidate
+    }
+
+    fn spawn_free(&self, position: Position) -> bool {
+        let hitbox = Client::hitbox_at(position);
+        let blocked_by_client = self.world.clients.iter().any(|client| {
+            client.elevation == Elevation::Ground
+                && Client::hitbox_at(client.position).intersects(&hitbox)
+        });
+        !self.blocked_by_object(position) && !blocked_by_client
+    }
+
+    /// whether a player standing on the ground at `position` would be stuck in an object
+    fn blocked_by_object(&self, position: Position) -> bool {
+        let hitbox = Client::hitbox_at(position);
+        self.world.network_objects.values().any(|object| {
+            object.properties().elevation == Elevation::Ground
+                && object
+                    .hitbox()
+                    .is_some_and(|object_hitbox| object_hitbox.intersects(&hitbox))
+        })
+    }
+
+    /// add a painting to the gallery and show it on every gallery wall.
+    pub fn hang_painting(&mut self, painting: Painting) {
+        if !painting.is_valid() {
+            return;
+        }
+
+        self.gallery.push(painting.clone());
+        if self.gallery.len() > GALLERY_LIMIT {
+            self.gallery.remove(0);
+        }
+        self.gallery_changed = true;
+
+        let data = match ObjectMessage::encode::<GalleryWall>(&painting) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        let wall_ids = self
+            .world
+            .network_objects
+            .iter_mut()
+            .filter_map(|(id, object)| {
+                object.downcast_mut::<GalleryWall>()?.hang(painting.clone());
+                Some(*id)
+            })
+            .collect::<Vec<_>>();
+        for id in wall_ids {
+            self.notify_clients(
+                ServerMessage::UpdateObject(id, data.clone()),
+                NotifyTarget::All,
+            );
+        }
+    }
+
+    pub fn gallery(&self) -> &[Painting] {
+        &self.gallery
+    }
+
+    /// whether paintings were hung since the last call, e.g. to know when to persist them.
+    pub fn take_gallery_changed(&mut self) -> bool {
+        core::mem::take(&mut self.gallery_changed)
+    }
+
+    /// show an event on every countdown board. nothing is sent if it didn't change
+    pub fn set_next_event(&mut self, event: Option<NextEvent>) {
+        let rsvps = event.as_ref().map_or(0, |event| event.rsvps);
+        let event = event.map(|event| ScheduledEvent {
+            id: event.id,
+            name: event.name,
+            starts_at: (self.time_ms as i64 + event.starts_in_ms).max(0) as u64,
+        });
+
+        // the start time is recalculated every time, so allow it to drift by a bit
+        let unchanged = match (&self.next_event, &event) {
+            (None, None) => true,
+            (Some(current), Some(new)) => {
+                current.id == new.id
+                    && current.name == new.name
+                    && current.starts_at.abs_diff(new.starts_at) < 1000
+            }
+            _ => false,
+        };
+        if unchanged && rsvps == self.event_rsvps {
+            return;
+        }
+        if !unchanged {
+            self.next_event = event;
+            self.send_time_sync();
+        }
+        self.event_rsvps = rsvps;
+
+        let data = match CountdownBoard::event_message(self.next_event.clone(), rsvps) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        let board_ids = self
+            .world
+            .network_objects
+            .iter_mut()
+            .filter_map(|(id, object)| {
+                object
+                    .downcast_mut::<CountdownBoard>()?
+                    .set_event(self.next_event.clone(), rsvps);
+                Some(*id)
+            })
+            .collect::<Vec<_>>();
+        for id in board_ids {
+            self.notify_clients(
+                ServerMessage::UpdateObject(id, data.clone()),
+                NotifyTarget::All,
+            );
+        }
+    }
+
+    /// rsvps that came in since the last call, as (client, event id).
+    pub fn take_rsvps(&mut self) -> Vec<(ClientId, u64)> {
+        core::mem::take(&mut self.rsvps)
+    }
+
+    /// show a poll and its votes per option on every poll board. nothing is sent if it didn't change
+    pub fn set_poll(&mut self, poll: Option<Poll>, votes: Vec<u32>) {
+        if poll == self.poll && votes == self.poll_votes {
+            return;
+        }
+        self.poll = poll;
+        self.poll_votes = votes;
+
+        let data = match PollBoard::poll_message(self.poll.clone(), self.poll_votes.clone()) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        let board_ids = self
+            .world
+            .network_objects
+            .iter_mut()
+            .filter_map(|(id, object)| {
+                object
+                    .downcast_mut::<PollBoard>()?
+                    .set_poll(self.poll.clone(), self.poll_votes.clone());
+                Some(*id)
+            })
+            .collect::<Vec<_>>();
+        for id in board_ids {
+            self.notify_clients(
+                ServerMessage::UpdateObject(id, data.clone()),
+                NotifyTarget::All,
+            );
+        }
+    }
+
+    /// votes that came in since the last call, as (client, poll id, option).
+    pub fn take_votes(&mut self) -> Vec<(ClientId, u64, u32)> {
+        core::mem::take(&mut self.votes)
+    }
+
+    /// change what every message board says. players that read an older revision get a badge until they read it again
+    pub fn set_board_text(&mut self, text: String, revision: u32) {
+        if text == self.board_text && revision == self.board_revision {
+            return;
+        }
+        self.board_text = text;
+        self.board_revision = revision;
+
+        let data = match MessageBoard::text_message(self.board_text.clone(), self.board_revision) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        let board_ids = self
+            .world
+            .network_objects
+            .iter_mut()
+            .filter_map(|(id, object)| {
+                object
+                    .downcast_mut::<MessageBoard>()?
+                    .set_text(self.board_text.clone(), self.board_revision);
+                Some(*id)
+            })
+            .collect::<Vec<_>>();
+        for id in board_ids {
+            self.notify_clients(
+                ServerMessage::UpdateObject(id, data.clone()),
+                NotifyTarget::All,
+            );
+        }
+    }
+
+    /// tell a client which message board revision they read last time, e.g. right after they joined
+    pub fn set_board_seen(&self, client_id: ClientId, revision: u32) {
+        let data = match MessageBoard::seen_message(revision) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        for (id, object) in self.world.network_objects.iter() {
+            if object.downcast_ref::<MessageBoard>().is_some() {
+                self.notify_clients(
+                    ServerMessage::UpdateObject(*id, data.clone()),
+                    NotifyTarget::Only(client_id),
+                );
+            }
+        }
+    }
+
+    /// message board reads that came in since the last call, as (client, revision).
+    pub fn take_board_reads(&mut self) -> Vec<(ClientId, u32)> {
+        core::mem::take(&mut self.board_reads)
+    }
+
+    /// show the top players of the leaderboards in every trophy case. nothing is sent if they didn't change
+    pub fn set_standings(&mut self, standings: Vec<Standings>) {
+        if standings == self.standings {
+            return;
+        }
+        self.standings = standings;
+
+        let data = match TrophyCase::standings_message(&self.standings) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        let case_ids = self
+            .world
+            .network_objects
+            .iter_mut()
+            .filter_map(|(id, object)| {
+                object
+                    .downcast_mut::<TrophyCase>()?
+                    .set_standings(self.standings.clone());
+                Some(*id)
+            })
+            .collect::<Vec<_>>();
+        for id in case_ids {
+            self.notify_clients(
+                ServerMessage::UpdateObject(id, data.clone()),
+                NotifyTarget::All,
+            );
+        }
+    }
+
+    /// minigame points scored since the last call, one entry per point.
+    pub fn take_minigame_points(&mut self) -> Vec<(MinigameKind, String)> {
+        core::mem::take(&mut self.minigame_points)
+    }
+
+    /// current server clock time in milliseconds.
+    pub fn time_ms(&self) -> u64 {
+        self.time_ms
+    }
+
+    pub fn get_special_event(&self, event: SpecialEvent) -> bool {
+        self.world.get_special_event(event)
+    }
+    /// the event is switched even if its objects couldn't all be spawned
+    pub fn set_special_event(
+        &mut self,
+        event: SpecialEvent,
+        active: bool,
+    ) -> Result<(), CiboError> {
+        let mut spawned = Ok(());
+        match event {
+            SpecialEvent::BeachEpisode => {
+                if active {
+                    spawned = self
+                        .spawn_objects(PlaceableObject::BeachBall, BEACH_EPISODE_BALLS)
+                        .map(|_| ());
+                } else {
+                    self.clear_objects(PlaceableObject::BeachBall);
+                }
+            }
+            SpecialEvent::Holidays | SpecialEvent::Spring => {}
+        }
+
+        self.world.set_special_event(event, active);
+        self.notify_clients(
+            ServerMessage::SpecialEvent { event, active },
+            NotifyTarget::All,
+        );
+        self.refresh_all_cosmetics();
+
+        let message = if active {
+            format!("{} started!", event.name())
+        } else {
+            format!("{} is over", event.name())
+        };
+        self.ticker(message, TickerKind::Event);
+        spawned
+    }
+
+    pub fn physics(&self) -> Physics {
+        self.world.physics
+    }
+
+    /// change how objects move, e.g. less friction for icy ground. clients get the new value for their prediction
+    pub fn set_physics_param(&mut self, param: PhysicsParam, value: f32) {
+        self.world.physics.set(param, value);
+        let value = self.world.physics.get(param);
+        self.notify_clients(
+            ServerMessage::PhysicsParam { param, value },
+            NotifyTarget::All,
+        );
+    }
+
+    pub fn tuning(&self) -> GameTuning {
+        self.world.tuning
+    }
+
+    /// change one of the client side constants. clients pick it up right away
+    pub fn set_tuning_param(&mut self, param: TuningParam, value: u64) {
+        self.world.tuning.set(param, value);
+        self.notify_clients(
+            ServerMessage::TuningChanged(self.world.tuning),
+            NotifyTarget::All,
+        );
+    }
+
+    /// how much time the server binary simulates per tick from now on. clients pace their actions to it
+    pub fn set_tick_ms(&mut self, tick_ms: u64) {
+        if self.world.tuning.server_tick_ms == tick_ms {
+            return;
+        }
+        self.world.tuning.server_tick_ms = tick_ms;
+        self.notify_clients(
+            ServerMessage::TuningChanged(self.world.tuning),
+            NotifyTarget::All,
+        );
+    }
+
+    fn add_network_object(&mut self, object: BoxedNetworkObject) -> Result<ObjectId, CiboError> {
+        let (id, serialized) = self.insert_network_object(object)?;
+        self.notify_clients(ServerMessage::NewObject(id, serialized), NotifyTarget::All);
+        Ok(id)
+    }
+
+    /// add an object without telling the clients, so they can be told about many objects at once
+    fn insert_network_object(
+        &mut self,
+        object: BoxedNetworkObject,
+    ) -> Result<(ObjectId, SerializedNetworkObject), CiboError> {
+        let serialized = SerializedNetworkObject::new(&object)?;
+        let id = self.world.object_pool.next_id(self.time_ms);
+        self.world.network_objects.insert(id, object);
+        Ok((id, serialized))
+    }
+
+    fn remove_network_object(&mut self, id: ObjectId) {
+        self.take_network_object(id);
+        self.notify_clients(ServerMessage::DeleteObject(id), NotifyTarget::All);
+    }
+
+    /// remove an object without telling the clients and hand it back to the pool
+    fn take_network_object(&mut self, id: ObjectId) {
+        let Some(object) = self.world.network_objects.remove(&id) else {
+            return;
+        };
+        self.world.object_pool.release(id, object, self.time_ms);
+        if let Some(index) = self.spawned_objects.iter().position(|(i, _)| *i == id) {
+            let (_, kind) = self.spawned_objects.swap_remove(index);
+            self.spawn_count_entry(kind).2 += 1;
+        }
+        self.throttled_updates
+            .retain(|(_, object), _| *object != id);
+    }
+
+    pub fn system_chat(&self, message: String) {
+        self.notify_clients(ServerMessage::SystemChat(message), NotifyTarget::All);
+    }
+
+    /// a system message only one client gets to see
+    pub fn system_chat_to(&self, client_id: ClientId, message: String) {
+        self.notify_clients(
+            ServerMessage::SystemChat(message),
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    /// point the cameras of clients in event mode at a spot in the world, e.g. during a live show
+    pub fn camera_hint(&self, target: Position, duration_ms: u64) {
+        self.notify_clients(
+            ServerMessage::CameraHint {
+                target,
+                duration: duration_ms,
+            },
+            NotifyTarget::All,
+        );
+    }
+
+    /// show a visual effect to everyone
+    pub fn effect(&self, kind: EffectKind, position: Position, duration_ms: u64) {
+        self.notify_clients(
+            ServerMessage::Effect {
+                kind,
+                position,
+                duration: duration_ms,
+            },
+            NotifyTarget::All,
+        );
+    }
+
+    /// tell a client which asset packs to load, right after connecting
+    pub fn advertise_asset_packs(&self, client_id: ClientId, packs: Vec<AssetPackInfo>) {
+        if packs.is_empty() {
+            return;
+        }
+        self.notify_clients(
+            ServerMessage::AssetPacks(packs),
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    /// hand a client the settings stored for its account, sent right after the full state on connect
+    pub fn send_settings(&self, client_id: ClientId, settings: PlayerSettings) {
+        self.notify_clients(
+            ServerMessage::Settings(settings),
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    /// show a chat message sent on another shard. it keeps its id, so deleting it works everywhere
+    pub fn relay_chat(&self, id: ChatId, from: ClientId, name: &str, message: &str) {
+        self.notify_clients(
+            ServerMessage::ShardChat {
+                id,
+                from,
+                name: name.into(),
+                message: message.into(),
+            },
+            NotifyTarget::AllNotBlocking(from),
+        );
+    }
+
+    pub fn remote_chat(&self, server: &str, name: &str, message: &str) {
+        self.notify_clients(
+            ServerMessage::RemoteChat {
+                server: server.into(),
+                name: name.into(),
+                message: message.into(),
+            },
+            NotifyTarget::All,
+        );
+    }
+
+    pub fn remote_online(&self, server: &str, players: u32) {
+        self.notify_clients(
+            ServerMessage::RemoteOnline {
+                server: server.into(),
+                players,
+            },
+            NotifyTarget::All,
+        );
+    }
+
+    pub fn reject_connect(&self, client_id: ClientId, reason: String) {
+        self.notify_clients(
+            ServerMessage::ConnectRejected(reason),
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    /// tell the client why it is about to be disconnected
+    pub fn refuse(&self, client_id: ClientId, refusal: Refusal) {
+        self.notify_clients(
+            ServerMessage::Refused(refusal),
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    /// warn everyone that the server goes down right after this
+    pub fn shutting_down(&self) {
+        self.notify_clients(ServerMessage::ShuttingDown, NotifyTarget::All);
+    }
+
+    pub fn name_checked(&self, client_id: ClientId, name: String, problem: Option<String>) {
+        self.notify_clients(
+            ServerMessage::NameCheck { name, problem },
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    /// whether a player with this name is playing, ignoring case
+    pub fn name_online(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.world
+            .clients
+            .iter()
+            .any(|client| client.name().to_lowercase() == name)
+    }
+
+    /// a hint for everyone, shown in the ticker
+    pub fn show_tip(&self, tip: String) {
+        self.ticker(tip, TickerKind::Tip);
+    }
+
+    fn ticker(&self, message: String, kind: TickerKind) {
+        self.notify_clients(ServerMessage::Ticker(message, kind), NotifyTarget::All);
+    }
+
+    fn notify_clients(&self, msg: ServerMessage, target: NotifyTarget) {
+        if let Some(deferred) = self.deferred.borrow_mut().as_mut() {
+            deferred.push((msg, target));
+            return;
+        }
+
+        if let NotifyTarget::Only(target_id) = target {
+            if let Some((_, data)) = self.client_mapping.iter().find(|(id, _)| *id == target_id) {
+                (self.notify_client)(data, msg);
+            }
+            return;
+        }
+        for (id, data) in &self.client_mapping {
+            if target.includes(*id, &self.blocks) {
+                (self.notify_client)(data, msg.clone());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerMessage {
+    FullState(SerializedClientGameState),
+
+    NewClient(Client),
+    ClientLeft(ClientId),
+    UpdateState(Vec<(ClientId, ClientAction)>),
+    Chat(ChatId, ClientId, String),
+    /// private message, sent to both the sender and the recipient
+    Whisper {
+        id: ChatId,
+        from: ClientId,
+        to: ClientId,
+        message: String,
+    },
+    /// announcement from the server itself
+    SystemChat(String),
+    DeleteChat {
+        message_id: ChatId,
+    },
+    /// minimum time in milliseconds between two chat messages, `None` if slow mode is off
+    SlowMode(Option<u64>),
+
+    SpecialEvent {
+        event: SpecialEvent,
+        active: bool,
+    },
+    Minigame(Option<Minigame>),
+
+    NewObject(ObjectId, SerializedNetworkObject),
+    /// an encoded `ObjectMessage` for the object
+    UpdateObject(ObjectId, Vec<u8>),
+    MoveObject(ObjectId, Position),
+    DeleteObject(ObjectId),
+
+    /// the client is allowed to use the world editor
+    EditorAccess,
+
+    /// response to a clock sync `ClientMessage::Ping`
+    Pong {
+        client_time: u64,
+        server_time: u64,
+    },
+
+    /// a short notice for the event ticker, separate from the chat
+    Ticker(String, TickerKind),
+
+    /// clients in event mode pan their camera over to `target` for `duration` ms
+    CameraHint {
+        target: Position,
+        duration: u64,
+    },
+
+    /// the players settings merged with the ones stored for their account
+    Settings(PlayerSettings),
+
+    /// the `ClientMessage::Connect` was refused, the client can try again with another name
+    ConnectRejected(String),
+
+    /// position in the join queue while the server is full, `0` once there is room to connect
+    QueuePosition(u32),
+
+    /// chat message from a player on a linked server
+    RemoteChat {
+        server: String,
+        name: String,
+        message: String,
+    },
+    /// players online on a linked server, `0` once the link is gone
+    RemoteOnline {
+        server: String,
+        players: u32,
+    },
+
+    /// how many ms the player can still play for without an account, `None` if there is no limit
+    GuestTime(Option<u64>),
+
+    /// show a visual effect at a spot in the world for `duration` ms
+    Effect {
+        kind: EffectKind,
+        position: Position,
+        duration: u64,
+    },
+
+    /// a player wants to be teleported next to the receiver, who can accept with `ClientMessage::TeleportAccept`
+    TeleportRequest(ClientId),
+    /// the server moved a client, e.g. after a teleport request was accepted
+    Teleport {
+        client: ClientId,
+        position: Position,
+        elevation: Elevation,
+    },
+    /// the home of the player, `None` if they didn't set one
+    Home(Option<Position>),
+    ChatEdited {
+        message_id: ChatId,
+        message: String,
+    },
+    PhysicsParam {
+        param: PhysicsParam,
+        value: f32,
+    },
+    /// answer to a `ClientMessage::CheckName`, `problem` is `None` if the name is fine to use
+    NameCheck {
+        name: String,
+        problem: Option<String>,
+    },
+    /// the server won't let the client play, sent right before the connection is closed
+    Refused(Refusal),
+    /// a piece of the compressed `FullState`, sent in order. put back together with [`FullStateParts`]
+    FullStatePart {
+        index: u32,
+        total: u32,
+        bytes: Vec<u8>,
+    },
+    /// answer to a `ClientMessage::RequestStats`
+    PlayStats(PlayStats),
+    /// the client now wears these cosmetics
+    Cosmetics(ClientId, Vec<CosmeticId>),
+    Chalk(ChalkStroke),
+    /// all chalk touching the area was wiped by a moderator
+    ClearChalk {
+        min: Position,
+        max: Position,
+    },
+    /// the client side constants were changed by an admin
+    TuningChanged(GameTuning),
+    /// many objects spawned at once
+    NewObjects(Vec<(ObjectId, SerializedNetworkObject)>),
+    /// many objects removed at once
+    DeleteObjects(Vec<ObjectId>),
+    /// the client linked or unlinked a verified account
+    Verified(ClientId, bool),
+    /// the time of day and the next scheduled event, sent every minute and when the event changes
+    TimeSync {
+        server_time: u64,
+        /// unix time in milliseconds at `server_time`
+        unix_ms: u64,
+        next_event: Option<ScheduledEvent>,
+    },
+    /// letters that were waiting for the player, sent when they join
+    Mail(Vec<Letter>),
+    /// a message with `Delivery::Critical`, the client answers with `ClientMessage::Ack(seq)`.
+    /// sequence numbers count up per connection
+    Critical {
+        seq: u32,
+        message: Box<ServerMessage>,
+    },
+    /// the server is about to go down, e.g. for a scheduled restart. the client reconnects on its own
+    ShuttingDown,
+    /// a developer wants to compare the world of the client with the server's, answered with
+    /// `ClientMessage::WorldSnapshot`
+    RequestSnapshot,
+    /// art the client should download and load on top of the built-in assets, sent on connect
+    AssetPacks(Vec<AssetPackInfo>),
+    /// the client is called something else from now on
+    ClientRenamed(ClientId, String),
+    /// answer to `ClientMessage::RequestTransfer`. another device connecting with the code takes
+    /// over the session, `link` opens the game with it if the server knows its address
+    TransferCode {
+        code: String,
+        link: Option<String>,
+    },
+    /// the client has an event staff account and can use the `ClientMessage::Staff*` messages
+    StaffAccess,
+    /// the client turned safe chat on or off
+    SafeChat(ClientId, bool),
+    /// chat message from a player on another shard of this server, who the client doesn't know
+    ShardChat {
+        id: ChatId,
+        from: ClientId,
+        name: String,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Refusal {
+    /// the ip is banned, bans can be appealed at `/appeal`
+    Banned,
+    /// someone is already playing from the same ip
+    AlreadyConnected,
+    /// a moderator disconnected the player, they can join again right away
+    Kicked,
+    /// the player continued on another device with a transfer code
+    Transferred,
+}
+
+static PLACEMENT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// an object placed with the editor. unlike its `ObjectId` it is the same in every game state
+/// running side by side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlacementId(u64);
+
+impl PlacementId {
+    pub fn new() -> Self {
+        PlacementId(PLACEMENT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// a change made with the editor, see `take_placed_changes`
+#[derive(Debug, Clone)]
+pub enum PlacedChange {
+    Placed(PlacementId, PlaceableObject, Position),
+    Moved(PlacementId, Position),
+    Removed(PlacementId),
+}
+
+/// id of a chat message or whisper, so it can be deleted later on
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct ChatId(u64);
+
+impl ChatId {
+    pub fn from_u64(id: u64) -> Self {
+        ChatId(id)
+    }
+
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerializedClientGameState(ClientId, Vec<u8>);
+impl SerializedClientGameState {
+    fn new(client_id: ClientId, world: &WorldState) -> Result<Self, CiboError> {
+        Ok(Self(client_id, postcard::to_allocvec(world)?))
+    }
+
+    pub fn serialize(self) -> Result<ClientGameState, CiboError> {
+        ClientGameState::new(self.0, postcard::from_bytes(&self.1)?)
+    }
+
+    /// compress the state and split it into `FullStatePart` messages
+    fn into_parts(self) -> Result<Vec<ServerMessage>, CiboError> {
+        let compressed = miniz_oxide::deflate::compress_to_vec(&postcard::to_allocvec(&self)?, 6);
+        let total = compressed.len().div_ceil(FULL_STATE_PART_SIZE).max(1) as u32;
+        Ok((0..total)
+            .map(|index| {
+                let start = index as usize * FULL_STATE_PART_SIZE;
+                let end = (start + FULL_STATE_PART_SIZE).min(compressed.len());
+                ServerMessage::FullStatePart {
+                    index,
+                    total,
+                    bytes: compressed[start..end].to_vec(),
+                }
+            })
+            .collect())
+    }
+}
+
+/// puts the `FullStatePart`s back together on the client
+#[derive(Debug, Default)]
+pub struct FullStateParts {
+    received: u32,
+    bytes: Vec<u8>,
+}
+
+impl FullStateParts {
+    /// the state once the last part arrived. parts that don't follow the previous one are dropped
+    pub fn add(
+        &mut self,
+        index: u32,
+        total: u32,
+        bytes: &[u8],
+    ) -> Result<Option<SerializedClientGameState>, CiboError> {
+        if index == 0 {
+            self.received = 0;
+            self.bytes.clear();
+        }
+        if index != self.received {
+            return Ok(None);
+        }
+        self.bytes.extend_from_slice(bytes);
+        self.received += 1;
+        if self.received < total {
+            return Ok(None);
+        }
+
+        self.received = 0;
+        let compressed = core::mem::take(&mut self.bytes);
+        let decompressed = miniz_oxide::inflate::decompress_to_vec(&compressed)
+            .map_err(|_| CiboError::Decompression)?;
+        Ok(Some(postcard::from_bytes(&decompressed)?))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerializedNetworkObject(Vec<u8>);
+impl SerializedNetworkObject {
+    fn new(object: &BoxedNetworkObject) -> Result<Self, CiboError> {
+        Ok(Self(postcard::to_allocvec(object)?))
+    }
+
+    pub fn serialize(self) -> Result<BoxedNetworkObject, CiboError> {
+        Ok(postcard::from_bytes(&self.0)?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpecialEvent {
+    BeachEpisode,
+    Holidays,
+    Spring,
+}
+
+impl SpecialEvent {
+    pub const ALL: [SpecialEvent; 3] = [
+        SpecialEvent::BeachEpisode,
+        SpecialEvent::Holidays,
+        SpecialEvent::Spring,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpecialEvent::BeachEpisode => "beach episode",
+            SpecialEvent::Holidays => "holidays",
+            SpecialEvent::Spring => "spring",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TickerKind {
+    Achievement,
+    Score,
+    Event,
+    /// something went wrong on the client, never sent by the server
+    Error,
+    /// hints picked from the tips admins wrote
+    Tip,
+}
+
+/// visual effects the server can show at any spot in the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EffectKind {
+    /// a burst of confetti flying out from the spot
+    Confetti,
+    /// hearts raining down around the spot
+    Hearts,
+    /// dims everything but the spot
+    Spotlight,
+}
+
+impl EffectKind {
+    pub const ALL: [EffectKind; 3] = [
+        EffectKind::Confetti,
+        EffectKind::Hearts,
+        EffectKind::Spotlight,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EffectKind::Confetti => "confetti",
+            EffectKind::Hearts => "hearts",
+            EffectKind::Spotlight => "spotlight",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// how new players are spread over the spawn points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnSpread {
+    /// one spawn point after the other
+    RoundRobin,
+    /// a random spawn point for every player
+    Random,
+}
+
+impl SpawnSpread {
+    pub const ALL: [SpawnSpread; 2] = [SpawnSpread::RoundRobin, SpawnSpread::Random];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpawnSpread::RoundRobin => "round robin",
+            SpawnSpread::Random => "random",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|spread| spread.name() == name)
+    }
+}
+
+impl TickerKind {
+    /// whether the ticker shows this kind of notice while stream mode hides the rest of the ui
+    pub fn show_in_stream_mode(&self) -> bool {
+        match self {
+            TickerKind::Event => true,
+            TickerKind::Achievement | TickerKind::Score | TickerKind::Error | TickerKind::Tip => {
+                false
+            }
+        }
+    }
+}
+
+/// how a message needs to be delivered to the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// the message has to arrive, in order (chat, object lifecycle, ...).
+    Reliable,
+    /// the message only carries the latest state and may be merged with or superseded by newer ones.
+    Unreliable,
+    /// like `Reliable`, but the client has to confirm it arrived. it is sent again until it does,
+    /// and the client is disconnected if it never does (kick reasons, letters, ...).
+    Critical,
+}
+
+impl ServerMessage {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        envelope::from_bytes(bytes)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
+        envelope::to_bytes(self)
+    }
+
+    /// short name of the message type, mainly for statistics and logging.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ServerMessage::FullState(_) => "FullState",
+            ServerMessage::NewClient(_) => "NewClient",
+            ServerMessage::ClientLeft(_) => "ClientLeft",
+            ServerMessage::UpdateState(_) => "UpdateState",
+            ServerMessage::Chat(_, _, _) => "Chat",
+            ServerMessage::Whisper { .. } => "Whisper",
+            ServerMessage::SystemChat(_) => "SystemChat",
+            ServerMessage::DeleteChat { .. } => "DeleteChat",
+            ServerMessage::SlowMode(_) => "SlowMode",
+            ServerMessage::SpecialEvent { .. } => "SpecialEvent",
+            ServerMessage::Minigame(_) => "Minigame",
+            ServerMessage::NewObject(_, _) => "NewObject",
+            ServerMessage::UpdateObject(_, _) => "UpdateObject",
+            ServerMessage::MoveObject(_, _) => "MoveObject",
+            ServerMessage::DeleteObject(_) => "DeleteObject",
+            ServerMessage::EditorAccess => "EditorAccess",
+            ServerMessage::Ticker(_, _) => "Ticker",
+            ServerMessage::Pong { .. } => "Pong",
+            ServerMessage::CameraHint { .. } => "CameraHint",
+            ServerMessage::Settings(_) => "Settings",
+            ServerMessage::ConnectRejected(_) => "ConnectRejected",
+            ServerMessage::QueuePosition(_) => "QueuePosition",
+            ServerMessage::RemoteChat { .. } => "RemoteChat",
+            ServerMessage::RemoteOnline { .. } => "RemoteOnline",
+            ServerMessage::GuestTime(_) => "GuestTime",
+            ServerMessage::Effect { .. } => "Effect",
+            ServerMessage::TeleportRequest(_) => "TeleportRequest",
+            ServerMessage::Teleport { .. } => "Teleport",
+            ServerMessage::Home(_) => "Home",
+            ServerMessage::ChatEdited { .. } => "ChatEdited",
+            ServerMessage::PhysicsParam { .. } => "PhysicsParam",
+            ServerMessage::NameCheck { .. } => "NameCheck",
+            ServerMessage::Refused(_) => "Refused",
+            ServerMessage::FullStatePart { .. } => "FullStatePart",
+            ServerMessage::PlayStats(_) => "PlayStats",
+            ServerMessage::Cosmetics(_, _) => "Cosmetics",
+            ServerMessage::Chalk(_) => "Chalk",
+            ServerMessage::ClearChalk { .. } => "ClearChalk",
+            ServerMessage::TuningChanged(_) => "TuningChanged",
+            ServerMessage::NewObjects(_) => "NewObjects",
+            ServerMessage::DeleteObjects(_) => "DeleteObjects",
+            ServerMessage::Verified(_, _) => "Verified",
+            ServerMessage::TimeSync { .. } => "TimeSync",
+            ServerMessage::Mail(_) => "Mail",
+            ServerMessage::Critical { .. } => "Critical",
+            ServerMessage::ShuttingDown => "ShuttingDown",
+            ServerMessage::RequestSnapshot => "RequestSnapshot",
+            ServerMessage::AssetPacks(_) => "AssetPacks",
+            ServerMessage::ClientRenamed(_, _) => "ClientRenamed",
+            ServerMessage::TransferCode { .. } => "TransferCode",
+            ServerMessage::StaffAccess => "StaffAccess",
+            ServerMessage::SafeChat(_, _) => "SafeChat",
+            ServerMessage::ShardChat { .. } => "ShardChat",
+        }
+    }
+
+    pub fn delivery(&self) -> Delivery {
+        match self {
+            ServerMessage::UpdateState(_) => Delivery::Unreliable,
+            ServerMessage::Refused(_)
+            | ServerMessage::Mail(_)
+            | ServerMessage::ShuttingDown
+            | ServerMessage::Ticker(_, TickerKind::Achievement) => Delivery::Critical,
+            _ => Delivery::Reliable,
+        }
+    }
+
+    /// whether `coalesce` would merge `newer` into this message
+    pub fn coalesces_with(&self, newer: &ServerMessage) -> bool {
+        matches!(
+            (self, newer),
+            (ServerMessage::UpdateState(_), ServerMessage::UpdateState(_))
+        )
+    }
+
+    /// try to merge a newer message into this one so only a single message has to be sent.
+    ///
+    /// returns the newer message back if the two can't be merged.
+    pub fn coalesce(&mut self, newer: ServerMessage) -> Option<ServerMessage> {
+        match (self, newer) {
+            (ServerMessage::UpdateState(updates), ServerMessage::UpdateState(newer_updates)) => {
+                for (id, action) in newer_updates {
+                    if let Some((_, existing)) = updates.iter_mut().find(|(u_id, _)| *u_id == id) {
+                        existing.combine(&action);
+                    } else {
+                        updates.push((id, action));
+                    }
+                }
+                None
+            }
+            (_, newer) => Some(newer),
+        }
     }
 }
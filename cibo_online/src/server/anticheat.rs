@@ -0,0 +1,181 @@
+//! heuristics for spotting modified clients. every suspicious thing a client does adds to its
+//! score, which slowly goes down again. the server binary logs the flags for the admin panel,
+//! nothing is done to the client automatically apart from refusing moves that are way too far.
+
+use super::{NotifyTarget, ServerGameState, ServerMessage, MAX_LAG_COMPENSATION_MS, MAX_STEP};
+use crate::{ClientId, SERVER_TICK_RATE};
+use alloc::vec::Vec;
+
+/// how far a move can be off from where the server thinks the client is. a client can be up to
+/// `MAX_LAG_COMPENSATION_MS` behind and change direction in between, so that is allowed twice
+pub const SPEED_SLACK: i64 = MAX_STEP + 2 * (MAX_LAG_COMPENSATION_MS / SERVER_TICK_RATE) as i64;
+/// moves further than this are refused and the client is put back
+const TELEPORT_DISTANCE: i64 = 256;
+/// after being teleported by the server, moves sent before the client knew about it can still arrive
+const TELEPORT_GRACE_MS: u64 = 1000;
+/// clients send an action every frame while walking, this leaves room for fast screens
+const MAX_ACTIONS_PER_SECOND: u32 = 250;
+/// the score goes down by one point this often
+const SCORE_DECAY_MS: u64 = 1000;
+/// the same kind of suspicion is only reported this often per client, the score still goes up
+const FLAG_COOLDOWN_MS: u64 = 10_000;
+
+/// something a client did that the normal client can't do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suspicion {
+    /// moved faster than walking allows
+    Speeding,
+    /// sent more actions than there are frames
+    ActionFlood,
+    /// jumped so far that the move was refused
+    Teleport,
+}
+
+impl Suspicion {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Suspicion::Speeding => "speeding",
+            Suspicion::ActionFlood => "action flood",
+            Suspicion::Teleport => "teleport",
+        }
+    }
+
+    fn weight(&self) -> u32 {
+        match self {
+            Suspicion::Speeding => 5,
+            Suspicion::ActionFlood => 10,
+            Suspicion::Teleport => 20,
+        }
+    }
+}
+
+/// a suspicious thing a client did and its score after it
+#[derive(Debug, Clone)]
+pub struct Flag {
+    pub client: ClientId,
+    pub suspicion: Suspicion,
+    pub score: u32,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct ClientSuspicion {
+    score: u32,
+    last_decay: u64,
+    actions: u32,
+    actions_since: u64,
+    grace_until: u64,
+    last_flagged: Vec<(Suspicion, u64)>,
+}
+
+impl<T> ServerGameState<T> {
+    fn suspicion_mut(&mut self, client_id: ClientId) -> &mut ClientSuspicion {
+        let index = match self.suspicion.iter().position(|(id, _)| *id == client_id) {
+            Some(index) => index,
+            None => {
+                self.suspicion.push((client_id, ClientSuspicion::default()));
+                self.suspicion.len() - 1
+            }
+        };
+        &mut self.suspicion[index].1
+    }
+
+    fn flag(&mut self, client_id: ClientId, suspicion: Suspicion) {
+        let now = self.time_ms;
+        let state = self.suspicion_mut(client_id);
+        let decayed = (now - state.last_decay) / SCORE_DECAY_MS;
+        state.score = state.score.saturating_sub(decayed as u32);
+        state.last_decay += decayed * SCORE_DECAY_MS;
+        state.score += suspicion.weight();
+        let score = state.score;
+
+        let reported = state
+            .last_flagged
+            .iter()
+            .any(|(kind, at)| *kind == suspicion && now < at + FLAG_COOLDOWN_MS);
+        if reported {
+            return;
+        }
+        state.last_flagged.retain(|(kind, _)| *kind != suspicion);
+        state.last_flagged.push((suspicion, now));
+
+        self.flags.push(Flag {
+            client: client_id,
+            suspicion,
+            score,
+        });
+    }
+
+    /// count an action of a client, too many in a second are flagged
+    pub(super) fn count_action(&mut self, client_id: ClientId) {
+        let now = self.time_ms;
+        let state = self.suspicion_mut(client_id);
+        if now >= state.actions_since + 1000 {
+            state.actions = 0;
+            state.actions_since = now;
+        }
+        state.actions += 1;
+        if state.actions == MAX_ACTIONS_PER_SECOND + 1 {
+            self.flag(client_id, Suspicion::ActionFlood);
+        }
+    }
+
+    /// don't judge the moves of a client the server just put somewhere else
+    pub(super) fn grant_move_grace(&mut self, client_id: ClientId) {
+        let until = self.time_ms + TELEPORT_GRACE_MS;
+        self.suspicion_mut(client_id).grace_until = until;
+    }
+
+    /// compare the queued moves with where the server thinks the clients are. moves that are too
+    /// far are flagged, teleports are taken out of the action before anyone else sees them
+    pub(super) fn check_moves(&mut self) {
+        let now = self.time_ms;
+        let mut incidents = Vec::new();
+        for (id, action) in self.queued_moves.iter_mut() {
+            let Some(target) = action.moved_to() else {
+                continue;
+            };
+            let Some(client) = self.world.clients.iter().find(|c| c.id() == *id) else {
+                continue;
+            };
+            let in_grace = self
+                .suspicion
+                .iter()
+                .any(|(client_id, state)| client_id == id && now < state.grace_until);
+            if in_grace {
+                continue;
+            }
+
+            let distance =
+                (target.x - client.position.x).abs() + (target.y - client.position.y).abs();
+            if distance > TELEPORT_DISTANCE {
+                action.drop_movement();
+                incidents.push((*id, Some((client.position, client.elevation))));
+            } else if distance > SPEED_SLACK {
+                incidents.push((*id, None));
+            }
+        }
+
+        for (id, refused) in incidents {
+            match refused {
+                Some((position, elevation)) => {
+                    // the client already moved there on its end, put it back
+                    self.notify_clients(
+                        ServerMessage::Teleport {
+                            client: id,
+                            position,
+                            elevation,
+                        },
+                        NotifyTarget::Only(id),
+                    );
+                    self.flag(id, Suspicion::Teleport);
+                }
+                None => self.flag(id, Suspicion::Speeding),
+            }
+        }
+    }
+
+    /// suspicious things clients did since the last call, for the server binary to log
+    pub fn take_flags(&mut self) -> Vec<Flag> {
+        core::mem::take(&mut self.flags)
+    }
+}
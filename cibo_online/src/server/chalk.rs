@@ -0,0 +1,91 @@
+use super::{NotifyTarget, ServerGameState, ServerMessage};
+use crate::{
+    world::{ChalkStroke, CHALK_LIFETIME_MS, CHALK_REACH, MAX_STROKE_POINTS},
+    ClientId,
+};
+use alloc::vec::Vec;
+use monos_gfx::{Position, Rect};
+
+/// players can walk while drawing, so strokes are allowed to end up a bit further away than the reach
+const CHALK_REACH_LEEWAY: i64 = 64;
+/// time between removing strokes that faded away
+const CHALK_EXPIRE_INTERVAL_MS: u64 = 60_000;
+
+impl<T> ServerGameState<T> {
+    pub(super) fn draw_chalk(&mut self, client_id: ClientId, mut stroke: ChalkStroke) {
+        let Some(client) = self.world.clients.iter().find(|c| c.id() == client_id) else {
+            return;
+        };
+        if stroke.point_count() > MAX_STROKE_POINTS {
+            return;
+        }
+        let feet = client.feet();
+        let reach = CHALK_REACH + CHALK_REACH_LEEWAY;
+        if stroke
+            .points()
+            .any(|point| (point.x - feet.x).abs() > reach || (point.y - feet.y).abs() > reach)
+        {
+            return;
+        }
+        if let Some(point) = stroke
+            .points()
+            .find(|point| self.protected_region(*point).is_some())
+        {
+            self.refuse_protected(client_id, point);
+            return;
+        }
+
+        stroke.expires_at = self.time_ms + CHALK_LIFETIME_MS;
+        self.new_chalk.push(stroke.clone());
+        self.add_chalk(stroke);
+    }
+
+    /// a stroke drawn in another game state
+    pub fn add_chalk(&mut self, stroke: ChalkStroke) {
+        self.world.chalk.add(stroke.clone());
+        self.notify_clients(ServerMessage::Chalk(stroke), NotifyTarget::All);
+    }
+
+    /// strokes drawn since the last call, so the server binary can store them and pass them on
+    pub fn take_new_chalk(&mut self) -> Vec<ChalkStroke> {
+        core::mem::take(&mut self.new_chalk)
+    }
+
+    /// wipe all chalk within `radius` pixels around `center`, returns the chunks that changed
+    pub fn clear_chalk(&mut self, center: Position, radius: i64) -> Vec<(i64, i64)> {
+        let min = center - Position::new(radius, radius);
+        let max = center + Position::new(radius, radius);
+        let changed = self.world.chalk.clear_area(Rect::new(min, max));
+        self.notify_clients(ServerMessage::ClearChalk { min, max }, NotifyTarget::All);
+        changed
+    }
+
+    pub fn chalk_stroke_count(&self) -> usize {
+        self.world.chalk.stroke_count()
+    }
+
+    /// the compressed strokes of a chunk, to be passed to `load_chalk_chunk` after a restart
+    pub fn save_chalk_chunk(&self, chunk: (i64, i64)) -> Vec<u8> {
+        self.world.chalk.save_chunk(chunk, self.time_ms)
+    }
+
+    /// put back a chunk that was saved `elapsed_ms` ago, returns how many strokes are still around
+    pub fn load_chalk_chunk(
+        &mut self,
+        chunk: (i64, i64),
+        data: &[u8],
+        elapsed_ms: u64,
+    ) -> Option<usize> {
+        self.world
+            .chalk
+            .load_chunk(chunk, data, elapsed_ms, self.time_ms)
+    }
+
+    pub(super) fn expire_chalk(&mut self) {
+        if self.time_ms < self.next_chalk_expiry {
+            return;
+        }
+        self.next_chalk_expiry = self.time_ms + CHALK_EXPIRE_INTERVAL_MS;
+        self.world.chalk.expire(self.time_ms);
+    }
+}
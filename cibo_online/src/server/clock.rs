@@ -0,0 +1,43 @@
+use super::{NotifyTarget, ServerGameState, ServerMessage};
+use crate::ClientId;
+
+/// time between two `TimeSync` messages, clients count on from the last one in between
+const TIME_SYNC_INTERVAL_MS: u64 = 60_000;
+
+impl<T> ServerGameState<T> {
+    /// tell the game state the current unix time in milliseconds, it has no wall clock of its own.
+    /// clients only get the time of day once this was called
+    pub fn set_unix_time(&mut self, unix_ms: u64) {
+        self.unix_offset = Some(unix_ms as i64 - self.time_ms as i64);
+    }
+
+    fn time_sync_message(&self) -> Option<ServerMessage> {
+        let unix_offset = self.unix_offset?;
+        Some(ServerMessage::TimeSync {
+            server_time: self.time_ms,
+            unix_ms: (self.time_ms as i64 + unix_offset).max(0) as u64,
+            next_event: self.next_event.clone(),
+        })
+    }
+
+    /// send the time to a client that just joined
+    pub(super) fn send_time_sync_to(&mut self, client_id: ClientId) {
+        if let Some(message) = self.time_sync_message() {
+            self.notify_clients(message, NotifyTarget::Only(client_id));
+        }
+    }
+
+    /// send the time to everyone, e.g. because the next event changed
+    pub(super) fn send_time_sync(&mut self) {
+        if let Some(message) = self.time_sync_message() {
+            self.last_time_sync = self.time_ms;
+            self.notify_clients(message, NotifyTarget::All);
+        }
+    }
+
+    pub(super) fn send_time_sync_if_due(&mut self) {
+        if self.time_ms - self.last_time_sync >= TIME_SYNC_INTERVAL_MS {
+            self.send_time_sync();
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use super::{NotifyTarget, ServerGameState, ServerMessage, TickerKind};
+use crate::{
+    client::{CosmeticId, PlayStats},
+    ClientId,
+};
+use alloc::{format, string::ToString, vec::Vec};
+
+/// time between checks whether someone reached the stats for a cosmetic
+const ACHIEVEMENT_CHECK_INTERVAL_MS: u64 = 5000;
+
+impl<T> ServerGameState<T> {
+    /// the stats of earlier sessions of the client, loaded from their account when they join
+    pub fn set_earlier_play_stats(&mut self, client_id: ClientId, stats: PlayStats) {
+        if let Some(session) = self.session_mut(client_id) {
+            session.earlier = stats;
+        }
+        self.refresh_cosmetics(client_id, false);
+    }
+
+    /// cosmetics of active events, the ones unlocked with `stats` and the bought ones
+    pub(super) fn cosmetics_for(
+        &self,
+        stats: &PlayStats,
+        purchased: &[CosmeticId],
+    ) -> Vec<CosmeticId> {
+        CosmeticId::ALL
+            .into_iter()
+            .filter(|cosmetic| {
+                self.world.get_special_event(cosmetic.event())
+                    || cosmetic.achieved(stats)
+                    || purchased.contains(cosmetic)
+            })
+            .collect()
+    }
+
+    pub(super) fn check_achievements(&mut self) {
+        if self.time_ms < self.next_achievement_check {
+            return;
+        }
+        self.next_achievement_check = self.time_ms + ACHIEVEMENT_CHECK_INTERVAL_MS;
+
+        let clients = self.sessions.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        for client_id in clients {
+            self.refresh_cosmetics(client_id, true);
+        }
+    }
+
+    pub(super) fn refresh_all_cosmetics(&mut self) {
+        let clients = self.sessions.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        for client_id in clients {
+            self.refresh_cosmetics(client_id, false);
+        }
+    }
+
+    /// hand out or take away cosmetics and tell everyone if they changed.
+    /// `announce` posts cosmetics that were just unlocked to the ticker
+    pub(super) fn refresh_cosmetics(&mut self, client_id: ClientId, announce: bool) {
+        let Some((_, session)) = self.sessions.iter().find(|(id, _)| *id == client_id) else {
+            return;
+        };
+        let mut stats = session.earlier;
+        stats.add(&self.play_stats(client_id));
+        let cosmetics = self.cosmetics_for(&stats, &self.purchased_cosmetics(client_id));
+
+        let Some(client) = self.world.clients.iter_mut().find(|c| c.id() == client_id) else {
+            return;
+        };
+        if client.cosmetics == cosmetics {
+            return;
+        }
+
+        let unlocked = cosmetics
+            .iter()
+            .filter(|cosmetic| !client.cosmetics.contains(*cosmetic) && cosmetic.achieved(&stats))
+            .copied()
+            .collect::<Vec<_>>();
+        client.cosmetics = cosmetics.clone();
+        let name = client.name().to_string();
+
+        self.notify_clients(
+            ServerMessage::Cosmetics(client_id, cosmetics),
+            NotifyTarget::All,
+        );
+        if announce {
+            for cosmetic in unlocked {
+                self.ticker(
+                    format!("{} unlocked the {}!", name, cosmetic.name()),
+                    TickerKind::Achievement,
+                );
+            }
+        }
+    }
+}
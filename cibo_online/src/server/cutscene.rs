@@ -0,0 +1,393 @@
+//! scripted shows on a stage. admins write a script in the admin panel, one step per line, and the
+//! server steps through it on every shard, so everyone watches the same show at the same time.
+//! positions in the script are tiles counted from the top left corner of the stage.
+//!
+//! ```text
+//! # comments start with a hash
+//! place <actor> <x> <y>
+//! move <actor> <x> <y> <seconds>
+//! say <actor> <line>
+//! leave <actor>
+//! camera <x> <y> <seconds>
+//! effect <confetti|hearts|spotlight> <x> <y> <seconds>
+//! wait <seconds>
+//! ```
+
+use super::{EffectKind, NotifyTarget, ServerGameState, ServerMessage};
+use crate::{
+    names,
+    world::objects::{Actor, Stage},
+    ObjectId,
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use monos_gfx::Position;
+
+/// steps a script can have at most
+pub const CUTSCENE_STEP_LIMIT: usize = 500;
+/// longest a single step can take
+const MAX_STEP_MS: u64 = 10 * 60 * 1000;
+/// how long a line stays above an actor, plus a bit per character
+const LINE_MS: u64 = 3000;
+const LINE_MS_PER_CHAR: u64 = 60;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Place {
+        actor: String,
+        at: Position,
+    },
+    Move {
+        actor: String,
+        to: Position,
+        duration_ms: u64,
+    },
+    Say {
+        actor: String,
+        line: String,
+    },
+    Leave {
+        actor: String,
+    },
+    Camera {
+        at: Position,
+        duration_ms: u64,
+    },
+    Effect {
+        kind: EffectKind,
+        at: Position,
+        duration_ms: u64,
+    },
+    Wait(u64),
+}
+
+/// a parsed script, ready to be played on a stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cutscene {
+    steps: Vec<Step>,
+}
+
+impl Cutscene {
+    /// the error says which line is wrong and why
+    pub fn parse(script: &str) -> Result<Self, String> {
+        let mut steps = Vec::new();
+        for (number, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let step = parse_step(line).map_err(|err| format!("line {}: {}", number + 1, err))?;
+            steps.push(step);
+        }
+
+        if steps.is_empty() {
+            return Err("the script has no steps".to_string());
+        }
+        if steps.len() > CUTSCENE_STEP_LIMIT {
+            return Err(format!(
+                "the script has more than {} steps",
+                CUTSCENE_STEP_LIMIT
+            ));
+        }
+        Ok(Cutscene { steps })
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// how long the whole show takes, in ms
+    pub fn duration_ms(&self) -> u64 {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                Step::Wait(ms) => *ms,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+fn parse_step(line: &str) -> Result<Step, String> {
+    let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+    let mut args = rest.split_whitespace();
+    let mut next = |what: &str| args.next().ok_or_else(|| format!("missing {}", what));
+
+    let step = match command {
+        "place" => Step::Place {
+            actor: actor(next("actor")?)?,
+            at: tile(next("x")?, next("y")?)?,
+        },
+        "move" => Step::Move {
+            actor: actor(next("actor")?)?,
+            to: tile(next("x")?, next("y")?)?,
+            duration_ms: seconds(next("seconds")?)?,
+        },
+        "say" => {
+            let (name, line) = rest.split_once(' ').ok_or("missing line")?;
+            Step::Say {
+                actor: actor(name)?,
+                line: line.trim().to_string(),
+            }
+        }
+        "leave" => Step::Leave {
+            actor: actor(next("actor")?)?,
+        },
+        "camera" => Step::Camera {
+            at: tile(next("x")?, next("y")?)?,
+            duration_ms: seconds(next("seconds")?)?,
+        },
+        "effect" => {
+            let name = next("effect")?;
+            Step::Effect {
+                kind: EffectKind::from_name(name)
+                    .ok_or_else(|| format!("there is no {} effect", name))?,
+                at: tile(next("x")?, next("y")?)?,
+                duration_ms: seconds(next("seconds")?)?,
+            }
+        }
+        "wait" => Step::Wait(seconds(next("seconds")?)?),
+        other => return Err(format!("unknown step '{}'", other)),
+    };
+    Ok(step)
+}
+
+/// actor names follow the rules for player names, but are a single word
+fn actor(name: &str) -> Result<String, String> {
+    match names::problem(name) {
+        Some(problem) => Err(format!("{} ('{}')", problem, name)),
+        None => Ok(name.to_string()),
+    }
+}
+
+/// the top left corner of a tile, relative to the stage
+fn tile(x: &str, y: &str) -> Result<Position, String> {
+    let parse = |value: &str| {
+        value
+            .parse::<i64>()
+            .map_err(|_| format!("'{}' is not a tile coordinate", value))
+    };
+    Ok(Position::new(parse(x)? * 16, parse(y)? * 16))
+}
+
+fn seconds(value: &str) -> Result<u64, String> {
+    match value.parse::<f32>() {
+        Ok(seconds) if seconds >= 0.0 && seconds * 1000.0 <= MAX_STEP_MS as f32 => {
+            Ok((seconds * 1000.0) as u64)
+        }
+        _ => Err(format!("'{}' is not a number of seconds", value)),
+    }
+}
+
+/// a cutscene that is being played and where it is at
+#[derive(Debug)]
+pub(super) struct RunningCutscene {
+    stage: ObjectId,
+    cutscene: Cutscene,
+    next_step: usize,
+    next_at: u64,
+}
+
+impl<T> ServerGameState<T> {
+    /// start playing a cutscene on the stage at `stage_position`, replacing the one that is running.
+    /// returns false if there is no stage there
+    pub fn play_cutscene(&mut self, stage_position: Position, cutscene: Cutscene) -> bool {
+        let Some(stage) = self
+            .world
+            .network_objects
+            .iter()
+            .find(|(_, object)| {
+                object.downcast_ref::<Stage>().is_some()
+                    && object.properties().position == stage_position
+            })
+            .map(|(id, _)| *id)
+        else {
+            return false;
+        };
+
+        self.stop_cutscene();
+        self.cutscene = Some(RunningCutscene {
+            stage,
+            cutscene,
+            next_step: 0,
+            next_at: self.time_ms,
+        });
+        true
+    }
+
+    /// stop the running cutscene and clear the stages, actors stay on them after the end otherwise
+    pub fn stop_cutscene(&mut self) {
+        self.cutscene = None;
+        let stages = self
+            .world
+            .network_objects
+            .iter_mut()
+            .filter_map(|(id, object)| {
+                let stage = object.downcast_mut::<Stage>()?;
+                (!stage.actors_mut().is_empty()).then_some(*id)
+            })
+            .collect::<Vec<_>>();
+        for stage in stages {
+            self.update_stage(stage, |actors| actors.clear());
+        }
+    }
+
+    /// play every step that is due, called every tick
+    pub(super) fn step_cutscene(&mut self) {
+        while let Some(running) = &mut self.cutscene {
+            if running.next_at > self.time_ms {
+                return;
+            }
+            let Some(step) = running.cutscene.steps.get(running.next_step).cloned() else {
+                // the actors stay on stage until the next cutscene or until it is stopped
+                self.cutscene = None;
+                return;
+            };
+            running.next_step += 1;
+            let stage = running.stage;
+
+            let Some(stage_position) = self
+                .world
+                .network_objects
+                .get(&stage)
+                .map(|object| object.properties().position)
+            else {
+                // the stage was deleted during the show
+                self.cutscene = None;
+                return;
+            };
+            self.play_step(stage, stage_position, step);
+        }
+    }
+
+    fn play_step(&mut self, stage: ObjectId, stage_position: Position, step: Step) {
+        let now = self.time_ms;
+        // the middle of the tile, like the camera hints from the admin panel
+        let in_world = |at: Position| stage_position + at + Position::new(8, 8);
+
+        match step {
+            Step::Place { actor, at } => self.update_stage(stage, |actors| {
+                actors.retain(|other| other.name != actor);
+                actors.push(Actor {
+                    name: actor,
+                    from: at,
+                    to: at,
+                    start_ms: now,
+                    end_ms: now,
+                    line: None,
+                });
+            }),
+            Step::Move {
+                actor,
+                to,
+                duration_ms,
+            } => self.update_stage(stage, |actors| {
+                if let Some(actor) = actors.iter_mut().find(|other| other.name == actor) {
+                    actor.from = actor.to;
+                    actor.to = to;
+                    actor.start_ms = now;
+                    actor.end_ms = now + duration_ms;
+                }
+            }),
+            Step::Say { actor, line } => {
+                self.system_chat(format!("{}: {}", actor, line));
+                let until = now + LINE_MS + line.len() as u64 * LINE_MS_PER_CHAR;
+                self.update_stage(stage, |actors| {
+                    if let Some(actor) = actors.iter_mut().find(|other| other.name == actor) {
+                        actor.line = Some((line, until));
+                    }
+                });
+            }
+            Step::Leave { actor } => {
+                self.update_stage(stage, |actors| actors.retain(|other| other.name != actor))
+            }
+            Step::Camera { at, duration_ms } => self.camera_hint(in_world(at), duration_ms),
+            Step::Effect {
+                kind,
+                at,
+                duration_ms,
+            } => self.effect(kind, in_world(at), duration_ms),
+            Step::Wait(ms) => {
+                if let Some(running) = &mut self.cutscene {
+                    running.next_at = now + ms;
+                }
+            }
+        }
+    }
+
+    /// change the actors of a stage and send them to everyone
+    fn update_stage(&mut self, stage: ObjectId, change: impl FnOnce(&mut Vec<Actor>)) {
+        let Some(object) = self
+            .world
+            .network_objects
+            .get_mut(&stage)
+            .and_then(|object| object.downcast_mut::<Stage>())
+        else {
+            return;
+        };
+        change(object.actors_mut());
+        if let Ok(data) = object.actors_message() {
+            self.notify_clients(ServerMessage::UpdateObject(stage, data), NotifyTarget::All);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_step() {
+        let cutscene = Cutscene::parse(
+            "# intro
+            place Bob 2 3
+            move Bob 5 3 1.5
+
+            say Bob hello everyone!
+            camera 4 2 3
+            effect confetti 4 2 2
+            wait 2
+            leave Bob",
+        )
+        .unwrap();
+
+        assert_eq!(cutscene.steps(), 7);
+        assert_eq!(cutscene.duration_ms(), 2000);
+        assert_eq!(
+            cutscene.steps[1],
+            Step::Move {
+                actor: "Bob".to_string(),
+                to: Position::new(80, 48),
+                duration_ms: 1500,
+            }
+        );
+        assert_eq!(
+            cutscene.steps[2],
+            Step::Say {
+                actor: "Bob".to_string(),
+                line: "hello everyone!".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(
+            Cutscene::parse("place Bob 1 1\ndance Bob"),
+            Err("line 2: unknown step 'dance'".to_string())
+        );
+        assert_eq!(
+            Cutscene::parse("wait -1"),
+            Err("line 1: '-1' is not a number of seconds".to_string())
+        );
+        assert_eq!(
+            Cutscene::parse("effect fireworks 1 1 1"),
+            Err("line 1: there is no fireworks effect".to_string())
+        );
+        assert!(Cutscene::parse("# nothing\n\n").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! lets the admin panel look at single network objects and edit their fields
+
+use super::{NotifyTarget, SerializedNetworkObject, ServerGameState, ServerMessage};
+use crate::{debug::WorldSnapshot, BoxedNetworkObject, ClientId, ObjectId, PlaceableObject};
+use alloc::vec::Vec;
+use monos_gfx::Position;
+use serde::{de, Deserializer, Serialize, Serializer};
+
+/// a network object as the admin panel lists it
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectInfo {
+    pub id: ObjectId,
+    pub kind: PlaceableObject,
+    pub position: Position,
+}
+
+impl<T> ServerGameState<T> {
+    /// the players and objects, to compare with what a client sees
+    pub fn world_snapshot(&self) -> WorldSnapshot {
+        self.world.snapshot()
+    }
+
+    /// ask a client for its own snapshot
+    pub fn request_snapshot(&self, client_id: ClientId) {
+        self.notify_clients(
+            ServerMessage::RequestSnapshot,
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    /// objects of the given kind and/or with the given id, ordered by id
+    pub fn find_objects(
+        &self,
+        kind: Option<PlaceableObject>,
+        id: Option<ObjectId>,
+    ) -> Vec<ObjectInfo> {
+        let mut found = self
+            .world
+            .network_objects
+            .iter()
+            .filter(|(object_id, _)| id.map_or(true, |id| id == **object_id))
+            .filter_map(|(object_id, object)| {
+                let object_kind = PlaceableObject::ALL
+                    .into_iter()
+                    .find(|kind| kind.network_object_id() == object.id())?;
+                Some(ObjectInfo {
+                    id: *object_id,
+                    kind: object_kind,
+                    position: object.properties().position,
+                })
+            })
+            .filter(|info| kind.map_or(true, |kind| kind == info.kind))
+            .collect::<Vec<_>>();
+        found.sort_by_key(|info| info.id);
+        found
+    }
+
+    /// write the fields of an object with `serializer`, `None` if it isn't in this game state
+    pub fn serialize_object<S: Serializer>(
+        &self,
+        id: ObjectId,
+        serializer: S,
+    ) -> Option<Result<S::Ok, S::Error>> {
+        let object = self.world.network_objects.get(&id)?;
+        Some((**object).serialize(serializer))
+    }
+
+    /// check that `deserializer` holds valid fields for the object without changing anything
+    pub fn check_object_patch<'de, D: Deserializer<'de>>(
+        &self,
+        id: ObjectId,
+        deserializer: D,
+    ) -> Option<Result<(), D::Error>> {
+        let object = self.world.network_objects.get(&id)?;
+        Some(BoxedNetworkObject::deserialize_fields(object.id(), deserializer).map(|_| ()))
+    }
+
+    /// replace the fields of an object with the ones in `deserializer`, which have to make up an
+    /// object of the same type. fields that aren't serialized start over, like they do on clients.
+    /// `None` if the object isn't in this game state
+    pub fn patch_object<'de, D: Deserializer<'de>>(
+        &mut self,
+        id: ObjectId,
+        deserializer: D,
+    ) -> Option<Result<(), D::Error>> {
+        let object = self.world.network_objects.get(&id)?;
+        let patched = match BoxedNetworkObject::deserialize_fields(object.id(), deserializer) {
+            Ok(patched) => patched,
+            Err(err) => return Some(Err(err)),
+        };
+
+        // clients replace objects that they already know
+        let serialized = match SerializedNetworkObject::new(&patched) {
+            Ok(serialized) => serialized,
+            Err(err) => return Some(Err(de::Error::custom(err))),
+        };
+        self.world.network_objects.insert(id, patched);
+        self.notify_clients(ServerMessage::NewObject(id, serialized), NotifyTarget::All);
+        Some(Ok(()))
+    }
+}
@@ -0,0 +1,56 @@
+use super::{NotifyTarget, ServerGameState, ServerMessage};
+use crate::{world::objects::Mailbox, ClientId};
+use alloc::{string::String, string::ToString, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+/// a message left at a mailbox, delivered when the recipient joins the next time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Letter {
+    pub from: String,
+    pub message: String,
+}
+
+impl<T> ServerGameState<T> {
+    /// queue a letter for the server binary to store, if the sender is standing at a mailbox
+    pub(super) fn post_letter(&mut self, client_id: ClientId, to: String, mut message: String) {
+        let Some(client) = self.world.clients.iter().find(|c| c.id() == client_id) else {
+            return;
+        };
+        let position = client.position;
+        let at_mailbox = self.world.network_objects.values().any(|object| {
+            object
+                .downcast_ref::<Mailbox>()
+                .is_some_and(|mailbox| mailbox.in_reach(position))
+        });
+        if !at_mailbox {
+            self.system_chat_to(
+                client_id,
+                "letters can only be sent from a mailbox".to_string(),
+            );
+            return;
+        }
+
+        let to = to.trim();
+        crate::clip_message(&mut message);
+        let message = message.trim();
+        if to.is_empty() || to.len() > crate::NAME_LIMIT || message.is_empty() {
+            return;
+        }
+        self.outgoing_letters
+            .push((client_id, to.to_string(), message.to_string()));
+    }
+
+    /// letters that were posted since the last call, as (sender, recipient name, message).
+    /// the server binary stores them for the account of the recipient
+    pub fn take_letters(&mut self) -> Vec<(ClientId, String, String)> {
+        core::mem::take(&mut self.outgoing_letters)
+    }
+
+    /// hand the letters that were waiting for a client to them
+    pub fn deliver_letters(&self, client_id: ClientId, letters: Vec<Letter>) {
+        if letters.is_empty() {
+            return;
+        }
+        self.notify_clients(ServerMessage::Mail(letters), NotifyTarget::Only(client_id));
+    }
+}
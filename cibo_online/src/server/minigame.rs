@@ -0,0 +1,306 @@
+use super::{NotifyTarget, ServerGameState, ServerMessage, TickerKind, SEASHELLS_PER_POINT};
+use crate::{
+    world::{HideAndSeekPhase, Minigame, MinigameKind},
+    Client, ClientId,
+};
+use alloc::{format, string::String, vec::Vec};
+
+/// time after a tag during which nobody can be tagged
+const TAG_COOLDOWN_MS: u64 = 2000;
+
+const HIDE_AND_SEEK_MIN_PLAYERS: usize = 2;
+const HIDE_AND_SEEK_HEAD_START_MS: u64 = 30_000;
+const HIDE_AND_SEEK_ROUND_MS: u64 = 180_000;
+const HIDE_AND_SEEK_BREAK_MS: u64 = 10_000;
+
+impl<T> ServerGameState<T> {
+    pub fn get_minigame(&self) -> Option<MinigameKind> {
+        self.world.minigame.as_ref().map(|minigame| minigame.kind())
+    }
+
+    /// start the given minigame, replacing the one that is currently running. `None` stops all minigames.
+    pub fn set_minigame(&mut self, kind: Option<MinigameKind>) {
+        if kind == self.get_minigame() {
+            return;
+        }
+
+        let previous = self.get_minigame();
+        self.world.minigame = kind.map(Minigame::new);
+        self.minigame_cooldown_until = 0;
+        self.sync_minigame();
+
+        match (kind, previous) {
+            (Some(kind), _) => self.ticker(format!("{} started!", kind.name()), TickerKind::Event),
+            (None, Some(previous)) => {
+                self.ticker(format!("{} is over", previous.name()), TickerKind::Event)
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// names and scores of the running minigame, highest first.
+    pub fn minigame_scores(&self) -> Vec<(String, u32)> {
+        match &self.world.minigame {
+            Some(Minigame::Tag(tag)) => tag
+                .scores()
+                .into_iter()
+                .map(|(id, score)| (self.client_name(id), score))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn award_point(&mut self, game: MinigameKind, id: ClientId) {
+        let name = self.client_name(id);
+        self.minigame_points.push((game, name));
+        self.award_seashells(id, SEASHELLS_PER_POINT);
+    }
+
+    pub(super) fn minigame_client_left(&mut self, client_id: ClientId) {
+        let changed = match &mut self.world.minigame {
+            Some(Minigame::Tag(tag)) if tag.is_it(client_id) => {
+                // a new player will be picked on the next tick
+                tag.it = None;
+                true
+            }
+            Some(Minigame::HideAndSeek(state)) if state.is_seeker(client_id) => {
+                state.seekers.retain(|id| *id != client_id);
+                true
+            }
+            _ => false,
+        };
+
+        if changed {
+            self.sync_minigame();
+        }
+    }
+
+    pub(super) fn tick_minigame(&mut self, action_times: &[(ClientId, u64)]) {
+        match self.world.minigame {
+            Some(Minigame::Tag(_)) => self.tick_tag(action_times),
+            Some(Minigame::HideAndSeek(_)) => self.tick_hide_and_seek(action_times),
+            None => {}
+        }
+    }
+
+    fn sync_minigame(&self) {
+        self.notify_clients(
+            ServerMessage::Minigame(self.world.minigame.clone()),
+            NotifyTarget::All,
+        );
+    }
+
+    /// find a client that `hunter` is touching.
+    ///
+    /// the others are checked at the position they had when `hunter` last moved to compensate for latency.
+    fn find_caught(
+        &self,
+        hunter: ClientId,
+        action_times: &[(ClientId, u64)],
+        catchable: impl Fn(ClientId) -> bool,
+    ) -> Option<ClientId> {
+        let hunter_position = self
+            .world
+            .clients
+            .iter()
+            .find(|c| c.id() == hunter)?
+            .position;
+        let hunter_hitbox = Client::hitbox_at(hunter_position);
+
+        let check_time = action_times
+            .iter()
+            .find(|(id, _)| *id == hunter)
+            .map_or(self.time_ms, |(_, time)| *time);
+
+        self.world
+            .clients
+            .iter()
+            .filter(|c| c.id() != hunter && catchable(c.id()))
+            .find(|c| {
+                let position = self.position_at(c.id(), check_time).unwrap_or(c.position);
+                Client::hitbox_at(position).intersects(&hunter_hitbox)
+            })
+            .map(|c| c.id())
+    }
+
+    fn tick_tag(&mut self, action_times: &[(ClientId, u64)]) {
+        let it = match &self.world.minigame {
+            Some(Minigame::Tag(tag)) => tag.it(),
+            _ => return,
+        };
+
+        let it = match it {
+            Some(it) => it,
+            None => {
+                // nobody is it yet, pick someone as soon as there are players
+                if let Some(id) = self.random_client() {
+                    self.set_it(id, None);
+                }
+                return;
+            }
+        };
+
+        if self.time_ms < self.minigame_cooldown_until {
+            return;
+        }
+
+        if let Some(tagged) = self.find_caught(it, action_times, |_| true) {
+            self.set_it(tagged, Some(it));
+        }
+    }
+
+    fn set_it(&mut self, id: ClientId, tagged_by: Option<ClientId>) {
+        let tag = match &mut self.world.minigame {
+            Some(Minigame::Tag(tag)) => tag,
+            _ => return,
+        };
+
+        tag.it = Some(id);
+        if let Some(tagged_by) = tagged_by {
+            tag.add_score(tagged_by);
+        }
+
+        self.minigame_cooldown_until = self.time_ms + TAG_COOLDOWN_MS;
+        self.sync_minigame();
+
+        if let Some(tagged_by) = tagged_by {
+            self.award_point(MinigameKind::Tag, tagged_by);
+            self.ticker(
+                format!(
+                    "{} tagged {}",
+                    self.client_name(tagged_by),
+                    self.client_name(id)
+                ),
+                TickerKind::Score,
+            );
+        }
+    }
+
+    fn tick_hide_and_seek(&mut self, action_times: &[(ClientId, u64)]) {
+        let state = match &self.world.minigame {
+            Some(Minigame::HideAndSeek(state)) => state.clone(),
+            _ => return,
+        };
+
+        match state.phase {
+            HideAndSeekPhase::Waiting => {
+                if self.world.clients.len() >= HIDE_AND_SEEK_MIN_PLAYERS {
+                    self.start_hide_and_seek_round();
+                }
+            }
+            HideAndSeekPhase::HeadStart => {
+                if self.time_ms >= state.phase_end {
+                    self.set_hide_and_seek_phase(HideAndSeekPhase::Seeking, HIDE_AND_SEEK_ROUND_MS);
+                    self.system_chat("ready or not, here they come!".into());
+                }
+            }
+            HideAndSeekPhase::Seeking => {
+                let hiders_left = self.world.clients.iter().any(|c| !state.is_seeker(c.id()));
+                if state.seekers.is_empty() || !hiders_left || self.time_ms >= state.phase_end {
+                    self.end_hide_and_seek_round();
+                    return;
+                }
+
+                let mut found: Vec<(ClientId, ClientId)> = Vec::new();
+                for seeker in state.seekers.iter() {
+                    let hider = self.find_caught(*seeker, action_times, |id| {
+                        !state.is_seeker(id) && !found.iter().any(|(hider, _)| *hider == id)
+                    });
+                    if let Some(hider) = hider {
+                        found.push((hider, *seeker));
+                    }
+                }
+
+                if found.is_empty() {
+                    return;
+                }
+
+                for (hider, seeker) in found.iter() {
+                    self.award_point(MinigameKind::HideAndSeek, *seeker);
+                    self.ticker(
+                        format!(
+                            "{} was found by {}!",
+                            self.client_name(*hider),
+                            self.client_name(*seeker)
+                        ),
+                        TickerKind::Score,
+                    );
+                }
+
+                // found hiders join the seekers
+                if let Some(Minigame::HideAndSeek(state)) = &mut self.world.minigame {
+                    state.seekers.extend(found.iter().map(|(hider, _)| *hider));
+                }
+                self.sync_minigame();
+            }
+            HideAndSeekPhase::Over => {
+                if self.time_ms >= state.phase_end {
+                    if self.world.clients.len() >= HIDE_AND_SEEK_MIN_PLAYERS {
+                        self.start_hide_and_seek_round();
+                    } else {
+                        self.set_hide_and_seek_phase(HideAndSeekPhase::Waiting, 0);
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_hide_and_seek_phase(&mut self, phase: HideAndSeekPhase, duration_ms: u64) {
+        if let Some(Minigame::HideAndSeek(state)) = &mut self.world.minigame {
+            state.phase = phase;
+            state.phase_end = self.time_ms + duration_ms;
+        }
+        self.sync_minigame();
+    }
+
+    fn start_hide_and_seek_round(&mut self) {
+        let seeker = match self.random_client() {
+            Some(seeker) => seeker,
+            None => return,
+        };
+
+        if let Some(Minigame::HideAndSeek(state)) = &mut self.world.minigame {
+            state.seekers.clear();
+            state.seekers.push(seeker);
+        }
+
+        self.system_chat(format!(
+            "hide and seek! {} is seeking, you have {} seconds to hide!",
+            self.client_name(seeker),
+            HIDE_AND_SEEK_HEAD_START_MS / 1000
+        ));
+        self.set_hide_and_seek_phase(HideAndSeekPhase::HeadStart, HIDE_AND_SEEK_HEAD_START_MS);
+    }
+
+    fn end_hide_and_seek_round(&mut self) {
+        let survivors = match &self.world.minigame {
+            Some(Minigame::HideAndSeek(state)) => self
+                .world
+                .clients
+                .iter()
+                .filter(|c| !state.is_seeker(c.id()))
+                .map(|c| (c.id(), String::from(c.name())))
+                .collect::<Vec<_>>(),
+            _ => return,
+        };
+        // staying hidden for the whole round is worth a point too
+        for (id, _) in &survivors {
+            self.award_point(MinigameKind::HideAndSeek, *id);
+        }
+        let survivors = survivors
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>();
+
+        if survivors.is_empty() {
+            self.ticker("round over! everyone was found.".into(), TickerKind::Score);
+        } else {
+            self.ticker(
+                format!("round over! {} stayed hidden.", survivors.join(", ")),
+                TickerKind::Score,
+            );
+        }
+
+        self.set_hide_and_seek_phase(HideAndSeekPhase::Over, HIDE_AND_SEEK_BREAK_MS);
+    }
+}
@@ -0,0 +1,44 @@
+use super::ServerGameState;
+use crate::ClientId;
+use alloc::{format, string::String, vec::Vec};
+use monos_gfx::Position;
+
+/// area where objects can't be placed, chalk can't be drawn and nothing gets spawned, e.g. around the spawn
+#[derive(Debug, Clone)]
+pub struct ProtectedRegion {
+    pub name: String,
+    /// corners in world space, both included
+    pub min: Position,
+    pub max: Position,
+}
+
+impl ProtectedRegion {
+    pub fn contains(&self, position: Position) -> bool {
+        position.x >= self.min.x
+            && position.x <= self.max.x
+            && position.y >= self.min.y
+            && position.y <= self.max.y
+    }
+}
+
+impl<T> ServerGameState<T> {
+    pub fn set_protected_regions(&mut self, regions: Vec<ProtectedRegion>) {
+        self.protected_regions = regions;
+    }
+
+    /// the region `position` is protected by, if any
+    pub fn protected_region(&self, position: Position) -> Option<&ProtectedRegion> {
+        self.protected_regions
+            .iter()
+            .find(|region| region.contains(position))
+    }
+
+    /// tell the client why nothing happened if `position` is protected
+    pub(super) fn refuse_protected(&self, client_id: ClientId, position: Position) -> bool {
+        let Some(region) = self.protected_region(position) else {
+            return false;
+        };
+        self.system_chat_to(client_id, format!("{} is protected", region.name));
+        true
+    }
+}
@@ -0,0 +1,98 @@
+use super::{NotifyTarget, ServerGameState, ServerMessage};
+use crate::{
+    client::CosmeticId,
+    world::objects::{Shop, ShopItem},
+    ClientId, ObjectId,
+};
+use alloc::vec::Vec;
+
+/// seashells for every point scored in a minigame
+pub const SEASHELLS_PER_POINT: u64 = 10;
+
+/// the seashells and bought items of a client, as the server binary last stored them
+#[derive(Debug, Clone)]
+pub(super) struct Wallet {
+    pub client: ClientId,
+    pub seashells: u64,
+    pub owned: Vec<ShopItem>,
+}
+
+impl<T> ServerGameState<T> {
+    /// what the client can spend and already bought, loaded from their account. shows up on every
+    /// shop for that client and hands out the bought cosmetics
+    pub fn set_wallet(&mut self, client_id: ClientId, seashells: u64, owned: Vec<ShopItem>) {
+        if !self.world.clients.iter().any(|c| c.id() == client_id) {
+            return;
+        }
+        self.wallets.retain(|wallet| wallet.client != client_id);
+        self.wallets.push(Wallet {
+            client: client_id,
+            seashells,
+            owned,
+        });
+
+        let shop_ids = self
+            .world
+            .network_objects
+            .iter_mut()
+            .filter_map(|(id, object)| object.downcast_mut::<Shop>().map(|_| *id))
+            .collect::<Vec<_>>();
+        for id in shop_ids {
+            self.send_wallet(client_id, id);
+        }
+        self.refresh_cosmetics(client_id, false);
+    }
+
+    /// tell every client with a wallet about it on a shop that was just added
+    pub(super) fn send_wallets(&mut self, shop: ObjectId) {
+        let clients = self
+            .wallets
+            .iter()
+            .map(|wallet| wallet.client)
+            .collect::<Vec<_>>();
+        for client_id in clients {
+            self.send_wallet(client_id, shop);
+        }
+    }
+
+    fn send_wallet(&mut self, client_id: ClientId, shop: ObjectId) {
+        let Some(wallet) = self.wallets.iter().find(|w| w.client == client_id) else {
+            return;
+        };
+        let Ok(data) = Shop::wallet_message(wallet.seashells, wallet.owned.clone()) else {
+            return;
+        };
+        self.notify_clients(
+            ServerMessage::UpdateObject(shop, data),
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    /// cosmetics the client bought, they wear them no matter the event or their stats
+    pub(super) fn purchased_cosmetics(&self, client_id: ClientId) -> Vec<CosmeticId> {
+        self.wallets
+            .iter()
+            .filter(|wallet| wallet.client == client_id)
+            .flat_map(|wallet| &wallet.owned)
+            .map(|item| match item {
+                ShopItem::Cosmetic(cosmetic) => *cosmetic,
+            })
+            .collect()
+    }
+
+    pub(super) fn award_seashells(&mut self, client_id: ClientId, amount: u64) {
+        self.seashells_earned.push((client_id, amount));
+    }
+
+    /// items clients want to buy since the last call, as (client, item). the server binary checks
+    /// and stores them, then answers with `set_wallet`
+    pub fn take_purchases(&mut self) -> Vec<(ClientId, ShopItem)> {
+        core::mem::take(&mut self.purchases)
+    }
+
+    /// seashells earned since the last call that still have to be added to the accounts, as
+    /// (client, amount)
+    pub fn take_seashells(&mut self) -> Vec<(ClientId, u64)> {
+        core::mem::take(&mut self.seashells_earned)
+    }
+}
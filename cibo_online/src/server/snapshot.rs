@@ -0,0 +1,54 @@
+use super::{NotifyTarget, ServerGameState, ServerMessage};
+use crate::ClientId;
+use alloc::vec::Vec;
+
+/// the messages a tick sent and who they go to. it doesn't borrow the game state, so the server
+/// binary can serialize and send them after unlocking it
+pub struct TickSnapshot<T> {
+    recipients: Vec<(ClientId, T)>,
+    blocks: Vec<(ClientId, ClientId)>,
+    messages: Vec<(ServerMessage, NotifyTarget)>,
+}
+
+impl<T: Clone> ServerGameState<T> {
+    /// like `tick`, but the messages for the clients are handed back instead of being sent.
+    /// the snapshot has to be delivered before the next one is taken, otherwise clients get
+    /// updates out of order
+    pub fn tick_snapshot(&mut self, delta_ms: u64) -> TickSnapshot<T> {
+        *self.deferred.get_mut() = Some(Vec::new());
+        self.tick(delta_ms);
+        let messages = self.deferred.get_mut().take().unwrap_or_default();
+
+        if messages.is_empty() {
+            return TickSnapshot {
+                recipients: Vec::new(),
+                blocks: Vec::new(),
+                messages,
+            };
+        }
+        TickSnapshot {
+            recipients: self.client_mapping.clone(),
+            blocks: self.blocks.clone(),
+            messages,
+        }
+    }
+}
+
+impl<T> TickSnapshot<T> {
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// every message with the clients it goes to, in the order they were sent
+    pub fn deliveries(&self) -> impl Iterator<Item = (&ServerMessage, Vec<&T>)> + '_ {
+        self.messages.iter().map(|(message, target)| {
+            let recipients = self
+                .recipients
+                .iter()
+                .filter(|(id, _)| target.includes(*id, &self.blocks))
+                .map(|(_, data)| data)
+                .collect();
+            (message, recipients)
+        })
+    }
+}
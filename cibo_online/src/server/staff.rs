@@ -0,0 +1,119 @@
+//! event staff: accounts an admin marked as staff get a few in-game powers without the admin panel.
+//! they can put out decorations, mute players on their shard and start or stop minigames. the
+//! server binary decides who is staff and calls `grant_staff` when they join.
+
+use super::{NotifyTarget, ServerGameState, ServerMessage};
+use crate::{CiboError, ClientId, MinigameKind, PlaceableObject};
+use alloc::{format, string::ToString};
+
+/// what staff can put out, nothing that needs an admin to set it up
+pub const STAFF_DECORATIONS: &[PlaceableObject] = &[PlaceableObject::BeachBall];
+/// decorations from staff that can be out at once on a shard
+const STAFF_DECORATION_LIMIT: usize = 10;
+/// how long a mute by staff lasts, admins can mute for longer through the panel
+const STAFF_MUTE_MS: u64 = 10 * 60 * 1000;
+
+impl<T> ServerGameState<T> {
+    /// does nothing for clients that aren't in this game state, so it can be called on every shard
+    pub fn grant_staff(&mut self, client_id: ClientId) {
+        let joined = self.world.clients.iter().any(|c| c.id() == client_id);
+        if joined && !self.staff.contains(&client_id) {
+            self.staff.push(client_id);
+            self.notify_clients(ServerMessage::StaffAccess, NotifyTarget::Only(client_id));
+        }
+    }
+
+    pub fn revoke_staff(&mut self, client_id: ClientId) {
+        if self.staff.contains(&client_id) {
+            self.staff.retain(|id| *id != client_id);
+            self.system_chat_to(client_id, "you are not event staff anymore".to_string());
+        }
+    }
+
+    pub(super) fn is_staff(&self, client_id: ClientId) -> bool {
+        self.staff.contains(&client_id)
+    }
+
+    /// whether staff muted the client, chat from them isn't sent to anyone until it runs out
+    pub(super) fn staff_muted(&self, client_id: ClientId) -> bool {
+        self.staff_mutes
+            .iter()
+            .any(|(id, until)| *id == client_id && self.time_ms < *until)
+    }
+
+    /// put a decoration where the staff member is standing. they are temporary like spawned objects
+    pub(super) fn staff_decorate(
+        &mut self,
+        client_id: ClientId,
+        object: PlaceableObject,
+    ) -> Result<(), CiboError> {
+        if !STAFF_DECORATIONS.contains(&object) {
+            self.system_chat_to(client_id, format!("staff can't put out {}s", object.name()));
+            return Ok(());
+        }
+
+        let network_objects = &self.world.network_objects;
+        self.staff_decorations
+            .retain(|id| network_objects.contains_key(id));
+        if self.staff_decorations.len() >= STAFF_DECORATION_LIMIT
+            || self.object_count(object) >= self.object_cap(object)
+        {
+            self.system_chat_to(
+                client_id,
+                "there are already too many decorations out".to_string(),
+            );
+            return Ok(());
+        }
+
+        let Some((position, _)) = self.client_spot(client_id) else {
+            return Ok(());
+        };
+        if self.refuse_protected(client_id, position) {
+            return Ok(());
+        }
+        let id = self.create_object(object, position)?;
+        self.spawned_objects.push((id, object));
+        self.spawn_count_entry(object).1 += 1;
+        self.staff_decorations.push(id);
+        Ok(())
+    }
+
+    /// mute the player with the given name on this shard, until `STAFF_MUTE_MS` is over or they leave
+    pub(super) fn staff_mute(&mut self, client_id: ClientId, name: &str) {
+        let Some(target) = self
+            .world
+            .clients
+            .iter()
+            .find(|client| client.name().eq_ignore_ascii_case(name.trim()))
+            .map(|client| client.id())
+        else {
+            self.system_chat_to(client_id, format!("nobody named {} is online", name.trim()));
+            return;
+        };
+        if self.is_staff(target) {
+            self.system_chat_to(client_id, "staff can't mute each other".to_string());
+            return;
+        }
+
+        let until = self.time_ms + STAFF_MUTE_MS;
+        self.staff_mutes.retain(|(id, _)| *id != target);
+        self.staff_mutes.push((target, until));
+        self.system_chat_to(
+            target,
+            format!(
+                "you were muted by staff for {} minutes",
+                STAFF_MUTE_MS / 60_000
+            ),
+        );
+        self.system_chat_to(client_id, format!("{} is muted", self.client_name(target)));
+    }
+
+    /// start or stop a minigame on this shard. the ones started through the admin panel run on all of them
+    pub(super) fn staff_minigame(&mut self, client_id: ClientId, kind: Option<MinigameKind>) {
+        if kind.is_none() && self.get_minigame().is_none() {
+            self.system_chat_to(client_id, "no minigame is running".to_string());
+            return;
+        }
+        self.set_minigame(kind);
+    }
+}
@@ -0,0 +1,29 @@
+use super::{NotifyTarget, ServerGameState, ServerMessage};
+use crate::{ClientId, Elevation};
+use alloc::string::String;
+use monos_gfx::Position;
+
+impl<T> ServerGameState<T> {
+    /// hand a client the code it asked for with `ClientMessage::RequestTransfer`. `link` opens the
+    /// game with the code already filled in, if the server knows its own address
+    pub fn transfer_code(&self, client_id: ClientId, code: String, link: Option<String>) {
+        self.notify_clients(
+            ServerMessage::TransferCode { code, link },
+            NotifyTarget::Only(client_id),
+        );
+    }
+
+    /// where a client is standing, to put them back there after moving to another device
+    pub fn client_spot(&self, client_id: ClientId) -> Option<(Position, Elevation)> {
+        self.world
+            .clients
+            .iter()
+            .find(|c| c.id() == client_id)
+            .map(|client| (client.position, client.elevation))
+    }
+
+    /// move a client that just joined to where their session left off on the other device
+    pub fn resume_at(&mut self, client_id: ClientId, position: Position, elevation: Elevation) {
+        self.teleport(client_id, position, elevation);
+    }
+}
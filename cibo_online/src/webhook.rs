@@ -0,0 +1,83 @@
+//! signatures of the webhooks the server sends to overlays and chat bridges.
+//!
+//! if the server has a webhook secret, every request carries the unix time (in seconds) it was
+//! sent at in [`TIMESTAMP_HEADER`] and `sha256=` followed by the hex encoded HMAC-SHA256 of
+//! `{timestamp}.{body}` in [`SIGNATURE_HEADER`]. tools that know the secret can check both with
+//! [`verify`], and should drop requests with old timestamps so recorded ones can't be replayed.
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub const TIMESTAMP_HEADER: &str = "x-cibo-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-cibo-signature";
+
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac(secret: &[u8], timestamp: u64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("hmac takes keys of any length");
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body);
+    mac
+}
+
+/// the value of the signature header for a request sent at `timestamp`
+pub fn sign(secret: &[u8], timestamp: u64, body: &[u8]) -> String {
+    let tag = mac(secret, timestamp, body).finalize().into_bytes();
+    let mut signature = String::from(SIGNATURE_PREFIX);
+    for byte in tag {
+        let _ = write!(signature, "{:02x}", byte);
+    }
+    signature
+}
+
+/// whether `signature` is a valid signature header for the timestamp and body of a request
+pub fn verify(secret: &[u8], timestamp: u64, body: &[u8], signature: &str) -> bool {
+    let Some(tag) = signature
+        .strip_prefix(SIGNATURE_PREFIX)
+        .and_then(decode_hex)
+    else {
+        return false;
+    };
+    // compares in constant time, so the signature can't be guessed byte by byte
+    mac(secret, timestamp, body).verify_slice(&tag).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_roundtrip() {
+        let signature = sign(b"secret", 1700000000, b"{\"kind\":\"chat\"}");
+        assert!(verify(
+            b"secret",
+            1700000000,
+            b"{\"kind\":\"chat\"}",
+            &signature
+        ));
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let signature = sign(b"secret", 1700000000, b"hello");
+        assert!(!verify(b"secret", 1700000000, b"hellO", &signature));
+        assert!(!verify(b"secret", 1700000001, b"hello", &signature));
+        assert!(!verify(b"other", 1700000000, b"hello", &signature));
+        assert!(!verify(b"secret", 1700000000, b"hello", "sha256=zz"));
+        assert!(!verify(b"secret", 1700000000, b"hello", &signature[7..]));
+    }
+}
@@ -1,23 +1,52 @@
+pub mod behavior;
+mod chalk;
+mod footprints;
+mod minigame;
 mod network_object;
 mod object;
 pub(crate) mod objects;
+mod physics;
+mod pool;
+mod scalar;
+mod tuning;
+pub(crate) mod water;
+pub(crate) mod wildlife;
+pub use chalk::{ChalkColor, ChalkStroke, CHALK_LIFETIME_MS, CHALK_REACH, MAX_STROKE_POINTS};
+pub use minigame::{HideAndSeekPhase, HideAndSeekState, Minigame, MinigameKind, TagState};
+pub use objects::{
+    Painting, PlaceableObject, Poll, ShopItem, Standings, DEFAULT_BOARD_TEXT, POLL_OPTION_LIMIT,
+    TROPHY_PLACES,
+};
+pub use physics::{Physics, PhysicsParam};
+pub use scalar::{vec_from_f32, vec_to_f32, Fixed, Scalar, ScalarExt};
+pub use tuning::{GameTuning, TuningParam};
+pub use wildlife::Critter;
 
+use chalk::{draw_stroke, ChalkLayer};
+use footprints::Footprints;
 pub(crate) use network_object::{
-    get_network_object_id, BoxedNetworkObject, NetworkObject, NetworkObjectId,
+    get_network_object_id, BoxedNetworkObject, InteractionCooldown, NetworkObject, NetworkObjectId,
+    ObjectMessage, INTERACTION_COOLDOWN_MS,
 };
+pub use object::Elevation;
 pub(crate) use object::{CollisionInfo, CollisionTester, Object, ObjectProperties};
+pub(crate) use pool::ObjectPool;
 
 use crate::{
     assets,
-    client::{ClientLocal, OwnClient, OwnClientLocal},
-    server::SpecialEvent,
-    Client, ClientId, RenderContext, Renderable, Sprite,
+    client::{
+        labels::{self, Label, LabelPriority, LayoutBuffers},
+        ClientLocal, OwnClient, OwnClientLocal,
+    },
+    render::{draw_sprite_debug, SpriteBuffer, GHOST_ALPHA},
+    server::{ChatId, SpecialEvent},
+    Client, ClientId, RenderContext, Renderable, Sprite, ZOrder,
 };
 
 use alloc::{boxed::Box, rc::Rc, string::String, vec, vec::Vec};
 use core::cell::RefCell;
 use hashbrown::HashMap;
-use monos_gfx::{Color, Position, Rect};
+use monos_gfx::{Position, Rect};
 use rustc_hash::FxBuildHasher;
 use serde::{Deserialize, Serialize};
 
@@ -25,7 +54,16 @@ use serde::{Deserialize, Serialize};
 pub(crate) struct WorldState {
     pub(crate) clients: Vec<Client>,
     pub(crate) special_events: SpecialEventState,
+    pub(crate) physics: Physics,
+    pub(crate) tuning: GameTuning,
     pub(crate) network_objects: HashMap<ObjectId, BoxedNetworkObject, FxBuildHasher>,
+    pub(crate) minigame: Option<Minigame>,
+    pub(crate) chalk: ChalkLayer,
+    /// where the critters live, see `wildlife`
+    pub(crate) ambient_seed: u64,
+    /// only filled on the server
+    #[serde(skip)]
+    pub(crate) object_pool: ObjectPool,
 }
 
 impl WorldState {
@@ -35,13 +73,21 @@ impl WorldState {
         WorldState {
             clients: Vec::new(),
             special_events: SpecialEventState::default(),
+            physics: Physics::default(),
+            tuning: GameTuning::default(),
             network_objects: HashMap::with_hasher(FxBuildHasher::default()),
+            minigame: None,
+            chalk: ChalkLayer::default(),
+            ambient_seed: 0,
+            object_pool: ObjectPool::default(),
         }
     }
 
     pub(crate) fn get_special_event(&self, event: SpecialEvent) -> bool {
         match event {
             SpecialEvent::BeachEpisode => self.special_events.beach_episode,
+            SpecialEvent::Holidays => self.special_events.holidays,
+            SpecialEvent::Spring => self.special_events.spring,
         }
     }
     pub fn set_special_event(&mut self, event: SpecialEvent, active: bool) {
@@ -49,6 +95,8 @@ impl WorldState {
             SpecialEvent::BeachEpisode => {
                 self.special_events.beach_episode = active;
             }
+            SpecialEvent::Holidays => self.special_events.holidays = active,
+            SpecialEvent::Spring => self.special_events.spring = active,
         }
     }
 }
@@ -56,6 +104,8 @@ impl WorldState {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub(crate) struct SpecialEventState {
     pub(crate) beach_episode: bool,
+    pub(crate) holidays: bool,
+    pub(crate) spring: bool,
 }
 
 #[derive(Debug)]
@@ -64,8 +114,23 @@ pub(crate) struct WorldLocalState {
     pub(crate) own_local: Rc<RefCell<OwnClientLocal>>,
     pub(crate) clients: Vec<(ClientId, Rc<RefCell<ClientLocal>>)>,
     pub(crate) objects: Vec<Box<dyn Object>>,
+    footprints: Footprints,
+    /// server time up to which critter calls were already played
+    pub(crate) ambience_heard_until: u64,
+    /// objects that are drawn see-through, e.g. while they are dragged around in the editor
+    pub(crate) ghosts: Vec<ObjectId>,
+    /// what the editor would place at the mouse
+    pub(crate) placement_preview: Option<(PlaceableObject, BoxedNetworkObject)>,
+    /// deleted objects that are still fading out and when they were deleted
+    despawning: Vec<(BoxedNetworkObject, u64)>,
+    /// kept between frames so drawing doesn't allocate every time
+    sprite_buffer: SpriteBuffer,
+    label_buffers: LayoutBuffers,
 }
 
+/// how long deleted objects take to fade out
+const DESPAWN_FADE_MS: u64 = 400;
+
 impl WorldLocalState {
     pub fn new(own_id: ClientId) -> Self {
         //use objects::*;
@@ -84,12 +149,57 @@ impl WorldLocalState {
             own_local: Rc::new(RefCell::new(OwnClientLocal::default())),
             clients: Vec::new(),
             objects,
+            footprints: Footprints::default(),
+            ambience_heard_until: 0,
+            ghosts: Vec::new(),
+            placement_preview: None,
+            despawning: Vec::new(),
+            sprite_buffer: SpriteBuffer::default(),
+            label_buffers: LayoutBuffers::default(),
         }
     }
 
-    pub fn add_chat(&self, id: ClientId, message: String, expiry: u64) {
+    pub fn clear_previews(&mut self) {
+        self.ghosts.clear();
+        self.placement_preview = None;
+    }
+
+    /// keep drawing a deleted object for a bit while it fades out
+    pub fn despawn(&mut self, object: BoxedNetworkObject, time_ms: u64) {
+        self.despawning.push((object, time_ms));
+    }
+
+    /// make the name label of a client flash, e.g. because they were mentioned
+    pub fn flash_name(&mut self, id: ClientId, until: u64) {
         if id == self.own_id {
-            self.own_local.borrow_mut().inner.add_chat(message, expiry);
+            self.own_local.borrow_mut().inner.flash_until = until;
+        } else if let Some((_, local)) = self.clients.iter().find(|(client_id, _)| *client_id == id)
+        {
+            local.borrow_mut().flash_until = until;
+        }
+    }
+
+    fn client_local(&self, id: ClientId) -> Option<&Rc<RefCell<ClientLocal>>> {
+        self.clients
+            .iter()
+            .find(|(client_id, _)| *client_id == id)
+            .map(|(_, local)| local)
+    }
+
+    /// show a chat bubble above a client. longer messages stay up longer, see [`GameTuning::chat_duration_ms`]
+    pub fn add_chat(
+        &self,
+        id: ClientId,
+        chat_id: ChatId,
+        message: String,
+        time_ms: u64,
+        duration_ms: u64,
+    ) {
+        if id == self.own_id {
+            self.own_local
+                .borrow_mut()
+                .inner
+                .add_chat(chat_id, message, time_ms, duration_ms);
         } else {
             if let Some(local) =
                 self.clients.iter().find_map(
@@ -102,7 +212,39 @@ impl WorldLocalState {
                     },
                 )
             {
-                local.borrow_mut().add_chat(message, expiry);
+                local
+                    .borrow_mut()
+                    .add_chat(chat_id, message, time_ms, duration_ms);
+            }
+        }
+    }
+
+    pub fn delete_chat(&self, chat_id: ChatId) {
+        self.own_local.borrow_mut().inner.delete_chat(chat_id);
+        for (_, local) in &self.clients {
+            local.borrow_mut().delete_chat(chat_id);
+        }
+    }
+
+    pub fn edit_chat(&self, chat_id: ChatId, message: &str) {
+        self.own_local
+            .borrow_mut()
+            .inner
+            .edit_chat(chat_id, message);
+        for (_, local) in &self.clients {
+            local.borrow_mut().edit_chat(chat_id, message);
+        }
+    }
+
+    /// remove the chat bubbles of the given clients, or of everyone if `None`
+    pub fn clear_chat(&self, ids: Option<&[ClientId]>) {
+        let clears = |id: ClientId| ids.map_or(true, |ids| ids.contains(&id));
+        if clears(self.own_id) {
+            self.own_local.borrow_mut().inner.clear_chat();
+        }
+        for (id, local) in &self.clients {
+            if clears(*id) {
+                local.borrow_mut().clear_chat();
             }
         }
     }
@@ -118,14 +260,23 @@ impl Renderable for WorldState {
         if ctx.stream_mode {
             ctx.fb.draw_rect(
                 Rect::from_dimensions(ctx.fb.dimensions()),
-                Color::new(0, 255, 0),
+                ctx.palette.chroma_key,
             );
         } else {
             for x in start_tile.x - 1..start_tile.x + fb_tile_size.width as i64 + 2 {
                 for y in start_tile.y - 1..start_tile.y + fb_tile_size.height as i64 + 2 {
                     let position = Position::new(x * 16, y * 16) - camera;
                     let tile = if self.special_events.beach_episode {
-                        assets().tiles[1].from_coords(x, y)
+                        if water::is_water_tile(x, y) {
+                            let frame = (ctx.anim_frame() / 4) % 2;
+                            if y == water::shore_tile(x) {
+                                &assets().water_shore[frame]
+                            } else {
+                                &assets().water[frame]
+                            }
+                        } else {
+                            assets().tiles[1].from_coords(x, y)
+                        }
                     } else {
                         assets().tiles[0].from_coords(x, y)
                     };
@@ -134,39 +285,213 @@ impl Renderable for WorldState {
             }
         }
 
-        let mut sprites: Vec<Sprite> =
-            Vec::with_capacity(self.clients.len() + 1 + state.objects.len());
-        sprites.extend(self.clients.iter_mut().map(|client| {
-            if client.id() == state.own_id {
-                Sprite::OwnClient(OwnClient(client), state.own_local.clone())
-            } else {
-                Sprite::Client(
-                    client,
-                    state
-                        .clients
-                        .iter()
-                        .find(|(id, _)| client.id() == *id)
-                        .map(|(_, local)| local.clone())
-                        .unwrap_or_else(|| {
-                            let local = Rc::new(RefCell::new(ClientLocal::default()));
-                            state.clients.push((client.id(), local.clone()));
-                            state.clients.last().unwrap().1.clone()
-                        }),
-                )
+        // the footprints are kept up to date in stream mode too, so they don't all appear at once when leaving it
+        if self.special_events.beach_episode {
+            state.footprints.update(&self.clients, ctx.time_ms);
+            if !ctx.stream_mode {
+                let min_tile = start_tile - Position::new(1, 1);
+                let max_tile = start_tile
+                    + Position::new(
+                        fb_tile_size.width as i64 + 2,
+                        fb_tile_size.height as i64 + 2,
+                    );
+                state.footprints.render(min_tile, max_tile, camera, ctx);
             }
-        }));
-        state.objects.iter_mut().for_each(|object| {
-            sprites.push(object.as_sprite());
-        });
-        self.network_objects.iter_mut().for_each(|(_, object)| {
-            sprites.push(object.as_sprite());
+        } else {
+            state.footprints.clear();
+        }
+
+        if !ctx.stream_mode {
+            self.chalk.render(camera, ctx);
+            if let Some(stroke) = state.own_local.borrow().chalk_preview() {
+                draw_stroke(&stroke, camera, ctx);
+            }
+            wildlife::render(self.ambient_seed, &self.special_events, camera, ctx, false);
+        }
+
+        state
+            .despawning
+            .retain(|(_, deleted_at)| ctx.time_ms < deleted_at + DESPAWN_FADE_MS);
+
+        // placing something on top of another object is allowed, but probably not what was intended
+        let preview_tint = state.placement_preview.as_ref().and_then(|(_, preview)| {
+            let bounds = preview.bounds();
+            self.network_objects
+                .values()
+                .any(|object| object.bounds().intersects(&bounds))
+                .then_some(ctx.palette.bad)
         });
 
-        // TODO: filter out sprites that are not in the visible area
-        sprites.sort_unstable_by(|a, b| a.z_order().cmp(&b.z_order()));
+        let ui_z = ZOrder::new_ui(0);
+        {
+            let mut sprites = state.sprite_buffer.take();
+            let minigame = &self.minigame;
+            let beach_episode = self.special_events.beach_episode;
+            sprites.extend(self.clients.iter_mut().map(|client| {
+                client.glow = minigame
+                    .as_ref()
+                    .map_or(false, |minigame| minigame.highlighted(client.id()));
+                client.swimming = client.in_water(beach_episode);
+                let sprite = if client.id() == state.own_id {
+                    Sprite::OwnClient(OwnClient(client), state.own_local.clone())
+                } else {
+                    Sprite::Client(
+                        client,
+                        state
+                            .clients
+                            .iter()
+                            .find(|(id, _)| client.id() == *id)
+                            .map(|(_, local)| local.clone())
+                            .unwrap_or_else(|| {
+                                let local = Rc::new(RefCell::new(ClientLocal::default()));
+                                state.clients.push((client.id(), local.clone()));
+                                state.clients.last().unwrap().1.clone()
+                            }),
+                    )
+                };
+                (sprite, u8::MAX)
+            }));
+            object_sprites(
+                &mut sprites,
+                &mut self.network_objects,
+                &mut state.objects,
+                &mut state.despawning,
+                &state.ghosts,
+                ctx.time_ms,
+            );
+            sprites.retain(|(sprite, _)| sprite.z_order() < ui_z);
 
-        for sprite in sprites.iter_mut() {
+            // TODO: filter out sprites that are not in the visible area
+            render_sprites(&mut sprites, camera, ctx);
+            if ctx.debug {
+                for (sprite, _) in &sprites {
+                    draw_sprite_debug(sprite, camera, ctx);
+                }
+            }
+            state.sprite_buffer.put_back(sprites);
+        }
+
+        if !ctx.stream_mode {
+            wildlife::render(self.ambient_seed, &self.special_events, camera, ctx, true);
+        }
+
+        // names and chat bubbles go on top of all sprites, spread out so crowds stay readable
+        let own_position = self
+            .clients
+            .iter()
+            .find(|c| c.id() == state.own_id)
+            .map(|c| c.position);
+        let mut label_buffers = core::mem::take(&mut state.label_buffers);
+        label_buffers.labels.clear();
+        label_buffers
+            .labels
+            .extend(self.clients.iter().filter_map(|client| {
+                let own = client.id() == state.own_id;
+                let (bubbles, last_chat) = if own {
+                    let local = state.own_local.borrow();
+                    let (bubbles, last_chat) = local.inner.visible_chat();
+                    (bubbles + local.chat_input.is_some() as usize, last_chat)
+                } else {
+                    let (bubbles, last_chat) =
+                        state.client_local(client.id())?.borrow().visible_chat();
+                    (bubbles + client.typing as usize, last_chat)
+                };
+                let distance = own_position.map_or(0, |own_position| {
+                    (client.position.x - own_position.x).abs()
+                        + (client.position.y - own_position.y).abs()
+                });
+
+                Some(Label {
+                    id: client.id(),
+                    screen_position: client.position - camera,
+                    bubbles,
+                    priority: LabelPriority {
+                        own,
+                        last_chat,
+                        typing: client.typing,
+                        closeness: -distance,
+                    },
+                })
+            }));
+
+        // the most important labels are drawn last, so they end up on top
+        labels::layout(&mut label_buffers, ctx.label_layout);
+        for (id, placement) in label_buffers.placements.iter().rev().copied() {
+            let Some(client) = self.clients.iter().find(|client| client.id() == id) else {
+                continue;
+            };
+            if id == state.own_id {
+                OwnClient(client).render_labels(
+                    &mut state.own_local.borrow_mut(),
+                    camera,
+                    placement,
+                    ctx,
+                );
+            } else if let Some(local) = state.client_local(id) {
+                client.render_labels(&mut local.borrow_mut(), camera, placement, ctx);
+            }
+        }
+        state.label_buffers = label_buffers;
+
+        let mut ui_sprites = state.sprite_buffer.take();
+        object_sprites(
+            &mut ui_sprites,
+            &mut self.network_objects,
+            &mut state.objects,
+            &mut state.despawning,
+            &state.ghosts,
+            ctx.time_ms,
+        );
+        ui_sprites.retain(|(sprite, _)| sprite.z_order() >= ui_z);
+        render_sprites(&mut ui_sprites, camera, ctx);
+        state.sprite_buffer.put_back(ui_sprites);
+
+        // the preview goes on top of everything, so it stays visible
+        if let Some((_, preview)) = &mut state.placement_preview {
+            preview
+                .as_sprite()
+                .render_ghost(camera, ctx, GHOST_ALPHA, preview_tint);
+        }
+    }
+}
+
+/// add all objects as sprites, paired with their alpha. fully opaque ones are drawn normally
+fn object_sprites<'a>(
+    sprites: &mut Vec<(Sprite<'a>, u8)>,
+    network_objects: &'a mut HashMap<ObjectId, BoxedNetworkObject, FxBuildHasher>,
+    objects: &'a mut [Box<dyn Object>],
+    despawning: &'a mut [(BoxedNetworkObject, u64)],
+    ghosts: &[ObjectId],
+    time_ms: u64,
+) {
+    sprites.extend(
+        objects
+            .iter_mut()
+            .map(|object| (object.as_sprite(), u8::MAX)),
+    );
+    sprites.extend(network_objects.iter_mut().map(|(id, object)| {
+        let alpha = if ghosts.contains(id) {
+            GHOST_ALPHA
+        } else {
+            u8::MAX
+        };
+        (object.as_sprite(), alpha)
+    }));
+    sprites.extend(despawning.iter_mut().map(|(object, deleted_at)| {
+        let remaining = (*deleted_at + DESPAWN_FADE_MS).saturating_sub(time_ms);
+        let alpha = (remaining * u8::MAX as u64 / DESPAWN_FADE_MS) as u8;
+        (object.as_sprite(), alpha)
+    }));
+}
+
+fn render_sprites(sprites: &mut [(Sprite, u8)], camera: Position, ctx: &mut RenderContext) {
+    sprites.sort_unstable_by(|(a, _), (b, _)| a.z_order().cmp(&b.z_order()));
+
+    for (sprite, alpha) in sprites.iter_mut() {
+        if *alpha == u8::MAX {
             sprite.render(camera, ctx);
+        } else {
+            sprite.render_ghost(camera, ctx, *alpha, None);
         }
     }
 }
@@ -181,6 +506,10 @@ impl ObjectId {
         ObjectId(OBJECT_ID.fetch_add(1, Ordering::SeqCst))
     }
 
+    pub fn from_u32(id: u32) -> Self {
+        ObjectId(id)
+    }
+
     pub fn as_u32(&self) -> u32 {
         self.0
     }
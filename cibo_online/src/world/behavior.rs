@@ -0,0 +1,267 @@
+//! small state machine for objects that go through timed phases, e.g. npcs, spawners or minigame props.
+//! everything is serializable, so it can be embedded in a `NetworkObject` and sent along with it.
+
+use serde::{Deserialize, Serialize};
+
+/// what to do when a state runs out of time
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transition<S> {
+    /// keep the state without a time limit
+    Stay,
+    /// switch to a state without a time limit
+    To(S),
+    /// switch to a state that lasts for the given amount of ms
+    ToFor(S, u64),
+}
+
+pub trait BehaviorState: Clone + PartialEq {
+    /// called when the current state runs out of time
+    fn timed_out(&self) -> Transition<Self>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Behavior<S> {
+    state: S,
+    /// time spent in the current state
+    elapsed_ms: u64,
+    duration_ms: Option<u64>,
+}
+
+impl<S: BehaviorState> Behavior<S> {
+    pub fn new(state: S) -> Self {
+        Behavior {
+            state,
+            elapsed_ms: 0,
+            duration_ms: None,
+        }
+    }
+
+    pub fn new_for(state: S, duration_ms: u64) -> Self {
+        Behavior {
+            state,
+            elapsed_ms: 0,
+            duration_ms: Some(duration_ms),
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn is(&self, state: &S) -> bool {
+        &self.state == state
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        self.elapsed_ms
+    }
+
+    /// time until the current state runs out, if it has a time limit
+    pub fn remaining_ms(&self) -> Option<u64> {
+        self.duration_ms
+            .map(|duration| duration.saturating_sub(self.elapsed_ms))
+    }
+
+    /// how far along the current state is, from 0 to 1. states without a time limit are always at 0
+    pub fn progress(&self) -> f32 {
+        match self.duration_ms {
+            Some(0) => 1.0,
+            Some(duration) => (self.elapsed_ms as f32 / duration as f32).min(1.0),
+            None => 0.0,
+        }
+    }
+
+    pub fn set(&mut self, state: S) {
+        self.apply(Transition::To(state));
+    }
+
+    pub fn set_for(&mut self, state: S, duration_ms: u64) {
+        self.apply(Transition::ToFor(state, duration_ms));
+    }
+
+    fn apply(&mut self, transition: Transition<S>) {
+        match transition {
+            Transition::Stay => self.duration_ms = None,
+            Transition::To(state) => {
+                self.state = state;
+                self.elapsed_ms = 0;
+                self.duration_ms = None;
+            }
+            Transition::ToFor(state, duration_ms) => {
+                self.state = state;
+                self.elapsed_ms = 0;
+                self.duration_ms = Some(duration_ms);
+            }
+        }
+    }
+
+    /// advance the timer and follow transitions of states that ran out.
+    /// returns whether the state changed, e.g. to know when to send an update
+    pub fn tick(&mut self, delta_ms: u64) -> bool {
+        let start = self.state.clone();
+        self.elapsed_ms += delta_ms;
+
+        // a long tick can skip through multiple short states
+        while let Some(duration) = self.duration_ms {
+            if self.elapsed_ms < duration {
+                break;
+            }
+            let overflow = self.elapsed_ms - duration;
+            self.apply(self.state.timed_out());
+            self.elapsed_ms = overflow;
+            if duration == 0 && self.duration_ms == Some(0) {
+                // zero length states that lead into each other would loop forever
+                break;
+            }
+        }
+
+        self.state != start
+    }
+}
+
+/// repeating timer, e.g. for spawning something every few seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timer {
+    interval_ms: u64,
+    elapsed_ms: u64,
+}
+
+impl Timer {
+    pub fn new(interval_ms: u64) -> Self {
+        Timer {
+            interval_ms,
+            elapsed_ms: 0,
+        }
+    }
+
+    pub fn interval_ms(&self) -> u64 {
+        self.interval_ms
+    }
+
+    pub fn set_interval(&mut self, interval_ms: u64) {
+        self.interval_ms = interval_ms;
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed_ms = 0;
+    }
+
+    /// advance the timer and return how often it fired
+    pub fn tick(&mut self, delta_ms: u64) -> u32 {
+        if self.interval_ms == 0 {
+            return 0;
+        }
+
+        self.elapsed_ms += delta_ms;
+        let fired = self.elapsed_ms / self.interval_ms;
+        self.elapsed_ms %= self.interval_ms;
+        fired as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestState {
+        Idle,
+        Walk,
+        Rest,
+        Flip,
+        Flop,
+        Flash,
+    }
+
+    impl BehaviorState for TestState {
+        fn timed_out(&self) -> Transition<Self> {
+            match self {
+                TestState::Idle => Transition::Stay,
+                TestState::Walk => Transition::ToFor(TestState::Rest, 50),
+                TestState::Rest => Transition::To(TestState::Idle),
+                TestState::Flip => Transition::ToFor(TestState::Flop, 0),
+                TestState::Flop => Transition::ToFor(TestState::Flip, 0),
+                TestState::Flash => Transition::ToFor(TestState::Walk, 100),
+            }
+        }
+    }
+
+    #[test]
+    fn runs_out_after_duration() {
+        let mut behavior = Behavior::new_for(TestState::Walk, 100);
+        assert!(!behavior.tick(99));
+        assert!(behavior.is(&TestState::Walk));
+        assert_eq!(behavior.remaining_ms(), Some(1));
+
+        assert!(behavior.tick(1));
+        assert!(behavior.is(&TestState::Rest));
+        assert_eq!(behavior.elapsed_ms(), 0);
+        assert_eq!(behavior.remaining_ms(), Some(50));
+    }
+
+    #[test]
+    fn long_tick_skips_states() {
+        let mut behavior = Behavior::new_for(TestState::Walk, 100);
+        assert!(behavior.tick(200));
+        assert!(behavior.is(&TestState::Idle));
+        assert_eq!(behavior.elapsed_ms(), 50);
+        assert_eq!(behavior.remaining_ms(), None);
+    }
+
+    #[test]
+    fn stay_drops_the_time_limit() {
+        let mut behavior = Behavior::new_for(TestState::Idle, 10);
+        assert!(!behavior.tick(20));
+        assert!(behavior.is(&TestState::Idle));
+        assert_eq!(behavior.remaining_ms(), None);
+        assert_eq!(behavior.progress(), 0.0);
+    }
+
+    #[test]
+    fn zero_length_states() {
+        let mut behavior = Behavior::new_for(TestState::Flash, 0);
+        assert_eq!(behavior.progress(), 1.0);
+        assert!(behavior.tick(0));
+        assert!(behavior.is(&TestState::Walk));
+        assert_eq!(behavior.remaining_ms(), Some(100));
+
+        // leading into each other, they stop after one step instead of looping forever
+        let mut behavior = Behavior::new_for(TestState::Flip, 0);
+        assert!(behavior.tick(5));
+        assert!(behavior.is(&TestState::Flop));
+        assert!(behavior.tick(0));
+        assert!(behavior.is(&TestState::Flip));
+    }
+
+    #[test]
+    fn set_resets_the_timer() {
+        let mut behavior = Behavior::new(TestState::Idle);
+        behavior.tick(500);
+        behavior.set_for(TestState::Walk, 100);
+        assert_eq!(behavior.elapsed_ms(), 0);
+        assert_eq!(behavior.progress(), 0.0);
+        behavior.set(TestState::Rest);
+        assert!(!behavior.tick(1000));
+        assert!(behavior.is(&TestState::Rest));
+    }
+
+    #[test]
+    fn timer_fires_and_keeps_the_rest() {
+        let mut timer = Timer::new(100);
+        assert_eq!(timer.tick(99), 0);
+        assert_eq!(timer.tick(1), 1);
+        assert_eq!(timer.tick(250), 2);
+        assert_eq!(timer.tick(50), 1);
+
+        timer.reset();
+        assert_eq!(timer.tick(99), 0);
+    }
+
+    #[test]
+    fn zero_interval_timer_never_fires() {
+        let mut timer = Timer::new(0);
+        assert_eq!(timer.tick(1000), 0);
+        timer.set_interval(10);
+        assert_eq!(timer.tick(25), 2);
+    }
+}
@@ -0,0 +1,254 @@
+//! chalk drawings that players leave on the ground. the server keeps them for a day, then they fade away.
+//! strokes are grouped into chunks, which are stored compressed by the server binary.
+
+use super::objects::line_points;
+use crate::{render::blend, RenderContext};
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+use monos_gfx::{Color, Position, Rect};
+use rustc_hash::FxBuildHasher;
+use serde::{Deserialize, Serialize};
+
+pub const CHALK_LIFETIME_MS: u64 = 24 * 60 * 60 * 1000;
+/// strokes start fading this long before they are gone
+const FADE_MS: u64 = 6 * 60 * 60 * 1000;
+/// how far from their feet players can draw
+pub const CHALK_REACH: i64 = 96;
+pub const MAX_STROKE_POINTS: usize = 256;
+/// the oldest strokes of a chunk are wiped when it gets too full
+const MAX_STROKES_PER_CHUNK: usize = 400;
+/// strokes are grouped into square chunks this many pixels wide
+const CHUNK_SIZE: i64 = 256;
+const CHALK_ALPHA: u8 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChalkColor {
+    White,
+    Pink,
+    Yellow,
+    Blue,
+    Green,
+}
+
+impl ChalkColor {
+    pub const ALL: [ChalkColor; 5] = [
+        ChalkColor::White,
+        ChalkColor::Pink,
+        ChalkColor::Yellow,
+        ChalkColor::Blue,
+        ChalkColor::Green,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChalkColor::White => "white",
+            ChalkColor::Pink => "pink",
+            ChalkColor::Yellow => "yellow",
+            ChalkColor::Blue => "blue",
+            ChalkColor::Green => "green",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|color| color.name() == name)
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            ChalkColor::White => Color::new(245, 245, 240),
+            ChalkColor::Pink => Color::new(247, 150, 190),
+            ChalkColor::Yellow => Color::new(250, 225, 110),
+            ChalkColor::Blue => Color::new(140, 195, 250),
+            ChalkColor::Green => Color::new(150, 225, 140),
+        }
+    }
+}
+
+/// a line drawn in one go. stored as the first point and the steps to the following ones,
+/// which postcard packs into a couple of bytes each
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChalkStroke {
+    pub color: ChalkColor,
+    /// server clock time the stroke is gone at, set by the server
+    pub(crate) expires_at: u64,
+    start: Position,
+    steps: Vec<(i8, i8)>,
+}
+
+impl ChalkStroke {
+    /// `None` if there are no points. points further apart than a step can cover are connected with extra steps
+    pub fn new(color: ChalkColor, points: &[Position]) -> Option<Self> {
+        let (&start, rest) = points.split_first()?;
+        let mut steps = Vec::with_capacity(rest.len());
+        let mut last = start;
+        for &point in rest {
+            while last != point {
+                let step = (
+                    (point.x - last.x).clamp(i8::MIN as i64, i8::MAX as i64),
+                    (point.y - last.y).clamp(i8::MIN as i64, i8::MAX as i64),
+                );
+                steps.push((step.0 as i8, step.1 as i8));
+                last = last + Position::new(step.0, step.1);
+            }
+        }
+
+        Some(ChalkStroke {
+            color,
+            expires_at: 0,
+            start,
+            steps,
+        })
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Position> + '_ {
+        let mut position = self.start;
+        core::iter::once(self.start).chain(self.steps.iter().map(move |(x, y)| {
+            position = position + Position::new(*x as i64, *y as i64);
+            position
+        }))
+    }
+
+    pub fn point_count(&self) -> usize {
+        self.steps.len() + 1
+    }
+
+    /// the chunk the stroke is stored in
+    pub fn chunk(&self) -> (i64, i64) {
+        chunk_of(self.start)
+    }
+}
+
+fn chunk_of(position: Position) -> (i64, i64) {
+    (
+        position.x.div_euclid(CHUNK_SIZE),
+        position.y.div_euclid(CHUNK_SIZE),
+    )
+}
+
+/// all chalk in the world, grouped by the chunk the strokes start in
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ChalkLayer {
+    chunks: HashMap<(i64, i64), Vec<ChalkStroke>, FxBuildHasher>,
+}
+
+impl ChalkLayer {
+    pub fn add(&mut self, stroke: ChalkStroke) {
+        let strokes = self.chunks.entry(stroke.chunk()).or_default();
+        strokes.push(stroke);
+        if strokes.len() > MAX_STROKES_PER_CHUNK {
+            strokes.remove(0);
+        }
+    }
+
+    /// drop strokes that are gone by `time_ms`
+    pub fn expire(&mut self, time_ms: u64) {
+        self.chunks.retain(|_, strokes| {
+            strokes.retain(|stroke| stroke.expires_at > time_ms);
+            !strokes.is_empty()
+        });
+    }
+
+    /// wipe every stroke that touches `area`, returns the chunks that changed
+    pub fn clear_area(&mut self, area: Rect) -> Vec<(i64, i64)> {
+        let mut changed = Vec::new();
+        for (chunk, strokes) in self.chunks.iter_mut() {
+            let before = strokes.len();
+            strokes.retain(|stroke| !stroke.points().any(|point| area.contains(point)));
+            if strokes.len() != before {
+                changed.push(*chunk);
+            }
+        }
+        self.chunks.retain(|_, strokes| !strokes.is_empty());
+        changed
+    }
+
+    pub fn stroke_count(&self) -> usize {
+        self.chunks.values().map(|strokes| strokes.len()).sum()
+    }
+
+    /// the strokes of a chunk, compressed and with their expiry relative to `time_ms` so they survive restarts
+    pub fn save_chunk(&self, chunk: (i64, i64), time_ms: u64) -> Vec<u8> {
+        let strokes = self.chunks.get(&chunk).map_or_else(Vec::new, |strokes| {
+            strokes
+                .iter()
+                .map(|stroke| ChalkStroke {
+                    expires_at: stroke.expires_at.saturating_sub(time_ms),
+                    ..stroke.clone()
+                })
+                .collect()
+        });
+        miniz_oxide::deflate::compress_to_vec(&postcard::to_allocvec(&strokes).unwrap(), 6)
+    }
+
+    /// put back a chunk from `save_chunk`, that was stored `elapsed_ms` ago. `None` if the data is broken
+    pub fn load_chunk(
+        &mut self,
+        chunk: (i64, i64),
+        data: &[u8],
+        elapsed_ms: u64,
+        time_ms: u64,
+    ) -> Option<usize> {
+        let data = miniz_oxide::inflate::decompress_to_vec(data).ok()?;
+        let strokes: Vec<ChalkStroke> = postcard::from_bytes(&data).ok()?;
+        let strokes = strokes
+            .into_iter()
+            .filter(|stroke| stroke.expires_at > elapsed_ms && stroke.chunk() == chunk)
+            .map(|stroke| ChalkStroke {
+                expires_at: time_ms + stroke.expires_at - elapsed_ms,
+                ..stroke
+            })
+            .collect::<Vec<_>>();
+
+        let count = strokes.len();
+        if !strokes.is_empty() {
+            self.chunks.insert(chunk, strokes);
+        }
+        Some(count)
+    }
+
+    /// draw the strokes on screen. has to happen after the floor and before any sprites
+    pub fn render(&self, camera: Position, ctx: &mut RenderContext) {
+        let screen = ctx.fb.dimensions();
+        // strokes can reach a bit outside of the chunk they start in
+        let min = chunk_of(camera);
+        let max = chunk_of(camera + Position::new(screen.width as i64, screen.height as i64));
+        for x in min.0 - 1..=max.0 {
+            for y in min.1 - 1..=max.1 {
+                let Some(strokes) = self.chunks.get(&(x, y)) else {
+                    continue;
+                };
+                for stroke in strokes {
+                    draw_stroke(stroke, camera, ctx);
+                }
+            }
+        }
+    }
+}
+
+/// draw a stroke that is still being drawn or already on the ground, fading out towards the end of its life
+pub(crate) fn draw_stroke(stroke: &ChalkStroke, camera: Position, ctx: &mut RenderContext) {
+    let remaining = stroke.expires_at.saturating_sub(ctx.server_time_ms);
+    if remaining == 0 {
+        return;
+    }
+    let alpha = (CHALK_ALPHA as u64 * remaining.min(FADE_MS) / FADE_MS) as u8;
+    let color = stroke.color.color();
+
+    let screen = Rect::from_dimensions(ctx.fb.dimensions());
+    let mut last = None;
+    for point in stroke.points() {
+        // the first pixel of a line is the last one of the previous line, it would be blended twice
+        let (from, skip) = match last {
+            Some(last) => (last, 1),
+            None => (point, 0),
+        };
+        last = Some(point);
+        for pixel in line_points(from - camera, point - camera).skip(skip) {
+            if !screen.contains(pixel) {
+                continue;
+            }
+            let below = ctx.fb.get_pixel_alpha(pixel).0;
+            ctx.fb.draw_pixel(pixel, blend(below, color, alpha));
+        }
+    }
+}
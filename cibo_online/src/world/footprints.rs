@@ -0,0 +1,147 @@
+//! footprints that players leave behind in the sand during the beach episode.
+//! purely cosmetic, so every client keeps track of them on its own.
+
+use super::water;
+use crate::{
+    client::{Client, MoveDirection},
+    ClientId, RenderContext,
+};
+use alloc::collections::VecDeque;
+use hashbrown::HashMap;
+use monos_gfx::{Color, Position, Rect};
+use rustc_hash::FxBuildHasher;
+
+const LIFETIME_MS: u64 = 8000;
+/// distance a client walks between two steps
+const STEP_DISTANCE: i64 = 7;
+/// how far the left and right foot are apart
+const FOOT_OFFSET: i64 = 3;
+const MAX_PER_TILE: usize = 12;
+
+/// fresh prints are darker, then they fade back towards the color of the sand
+const COLORS: [Color; 3] = [
+    Color::new(214, 182, 128),
+    Color::new(228, 197, 143),
+    Color::new(241, 211, 156),
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Footprint {
+    position: Position,
+    placed_at: u64,
+    /// walking up or down, the prints are rotated accordingly
+    vertical: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LastStep {
+    position: Position,
+    left_foot: bool,
+}
+
+/// decaying decal buffer of footprints, grouped by the tile they are on
+#[derive(Debug, Default)]
+pub(crate) struct Footprints {
+    tiles: HashMap<(i64, i64), VecDeque<Footprint>, FxBuildHasher>,
+    last_steps: HashMap<ClientId, LastStep, FxBuildHasher>,
+}
+
+impl Footprints {
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.last_steps.clear();
+    }
+
+    /// leave new prints behind moving clients and remove the ones that faded away
+    pub fn update(&mut self, clients: &[Client], time_ms: u64) {
+        self.tiles.retain(|_, prints| {
+            while prints
+                .front()
+                .map_or(false, |print| time_ms >= print.placed_at + LIFETIME_MS)
+            {
+                prints.pop_front();
+            }
+            !prints.is_empty()
+        });
+        self.last_steps
+            .retain(|id, _| clients.iter().any(|client| client.id() == *id));
+
+        for client in clients {
+            let feet = client.feet();
+            let last_step = self.last_steps.entry(client.id()).or_insert(LastStep {
+                position: feet,
+                left_foot: false,
+            });
+            if client.movement == MoveDirection::None {
+                continue;
+            }
+
+            let distance = feet - last_step.position;
+            if distance.x.abs().max(distance.y.abs()) < STEP_DISTANCE {
+                continue;
+            }
+            last_step.position = feet;
+            last_step.left_foot = !last_step.left_foot;
+
+            let vertical = matches!(client.movement, MoveDirection::Up | MoveDirection::Down);
+            let side = if last_step.left_foot {
+                -FOOT_OFFSET
+            } else {
+                FOOT_OFFSET
+            };
+            let position = if vertical {
+                feet + Position::new(side, 0)
+            } else {
+                feet + Position::new(0, side)
+            };
+
+            // the waves would wash them away right away
+            if water::is_water(position) {
+                continue;
+            }
+
+            let prints = self
+                .tiles
+                .entry((position.x.div_euclid(16), position.y.div_euclid(16)))
+                .or_default();
+            prints.push_back(Footprint {
+                position,
+                placed_at: time_ms,
+                vertical,
+            });
+            if prints.len() > MAX_PER_TILE {
+                prints.pop_front();
+            }
+        }
+    }
+
+    /// draw the prints on the visible tiles. has to happen after the floor and before any sprites
+    pub fn render(
+        &self,
+        min_tile: Position,
+        max_tile: Position,
+        camera: Position,
+        ctx: &mut RenderContext,
+    ) {
+        for x in min_tile.x..max_tile.x {
+            for y in min_tile.y..max_tile.y {
+                let Some(prints) = self.tiles.get(&(x, y)) else {
+                    continue;
+                };
+
+                for print in prints {
+                    let age = ctx.time_ms.saturating_sub(print.placed_at);
+                    let fade = (age * COLORS.len() as u64 / LIFETIME_MS) as usize;
+                    let color = COLORS[fade.min(COLORS.len() - 1)];
+                    let size = if print.vertical {
+                        Position::new(2, 3)
+                    } else {
+                        Position::new(3, 2)
+                    };
+                    let min = print.position - camera;
+                    ctx.fb.draw_rect(Rect::new(min, min + size), color);
+                }
+            }
+        }
+    }
+}
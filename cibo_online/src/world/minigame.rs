@@ -0,0 +1,130 @@
+use crate::ClientId;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// the kinds of minigames the server can run. only one minigame can run at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MinigameKind {
+    Tag,
+    HideAndSeek,
+}
+
+impl MinigameKind {
+    pub const ALL: [MinigameKind; 2] = [MinigameKind::Tag, MinigameKind::HideAndSeek];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MinigameKind::Tag => "tag",
+            MinigameKind::HideAndSeek => "hide and seek",
+        }
+    }
+}
+
+/// state of the running minigame that is shared with all clients.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Minigame {
+    Tag(TagState),
+    HideAndSeek(HideAndSeekState),
+}
+
+impl Minigame {
+    pub fn new(kind: MinigameKind) -> Self {
+        match kind {
+            MinigameKind::Tag => Minigame::Tag(TagState::default()),
+            MinigameKind::HideAndSeek => Minigame::HideAndSeek(HideAndSeekState::default()),
+        }
+    }
+
+    pub fn kind(&self) -> MinigameKind {
+        match self {
+            Minigame::Tag(_) => MinigameKind::Tag,
+            Minigame::HideAndSeek(_) => MinigameKind::HideAndSeek,
+        }
+    }
+
+    /// whether the client should be highlighted for everyone
+    pub fn highlighted(&self, id: ClientId) -> bool {
+        match self {
+            Minigame::Tag(tag) => tag.is_it(id),
+            Minigame::HideAndSeek(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagState {
+    pub(crate) it: Option<ClientId>,
+    pub(crate) scores: Vec<(ClientId, u32)>,
+}
+
+impl TagState {
+    #[inline]
+    pub fn it(&self) -> Option<ClientId> {
+        self.it
+    }
+
+    #[inline]
+    pub fn is_it(&self, id: ClientId) -> bool {
+        self.it == Some(id)
+    }
+
+    /// number of tags per client, highest first.
+    pub fn scores(&self) -> Vec<(ClientId, u32)> {
+        let mut scores = self.scores.clone();
+        scores.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+        scores
+    }
+
+    pub(crate) fn add_score(&mut self, id: ClientId) {
+        match self.scores.iter_mut().find(|(score_id, _)| *score_id == id) {
+            Some((_, score)) => *score += 1,
+            None => self.scores.push((id, 1)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HideAndSeekPhase {
+    /// waiting for enough players to start a round
+    Waiting,
+    /// hiders run off while seekers can't see or move
+    HeadStart,
+    Seeking,
+    /// the round is over, a new one starts after a short break
+    Over,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HideAndSeekState {
+    pub(crate) phase: HideAndSeekPhase,
+    /// server time at which the current phase ends
+    pub(crate) phase_end: u64,
+    pub(crate) seekers: Vec<ClientId>,
+}
+
+impl Default for HideAndSeekState {
+    fn default() -> Self {
+        HideAndSeekState {
+            phase: HideAndSeekPhase::Waiting,
+            phase_end: 0,
+            seekers: Vec::new(),
+        }
+    }
+}
+
+impl HideAndSeekState {
+    #[inline]
+    pub fn phase(&self) -> HideAndSeekPhase {
+        self.phase
+    }
+
+    #[inline]
+    pub fn phase_end(&self) -> u64 {
+        self.phase_end
+    }
+
+    #[inline]
+    pub fn is_seeker(&self, id: ClientId) -> bool {
+        self.seekers.contains(&id)
+    }
+}
@@ -1,27 +1,37 @@
-use crate::{render::Sprite, Object};
+use crate::{render::Sprite, CiboError, Object};
 use alloc::{boxed::Box, vec::Vec};
 use core::{
-    any::TypeId,
+    any::{Any, TypeId},
     ops::Deref,
     sync::atomic::{AtomicU64, Ordering},
 };
 use hashbrown::HashMap;
+use monos_gfx::Position;
 use rustc_hash::FxBuildHasher;
 use serde::{
-    de::{DeserializeSeed, Visitor},
+    de::{DeserializeOwned, Visitor},
     ser::SerializeSeq,
     Deserialize, Serialize,
 };
 
 type DeserializeFn =
     fn(&mut dyn erased_serde::Deserializer) -> erased_serde::Result<Box<dyn NetworkObject>>;
+type MigrateFn = fn(u8, &[u8]) -> Option<Vec<u8>>;
+
+/// how to read back a type of network object
+#[derive(Clone, Copy)]
+struct NetworkObjectType {
+    deserialize_fn: DeserializeFn,
+    version: u8,
+    migrate_fn: MigrateFn,
+}
 
 static NETWORK_OBJ_ID: AtomicU64 = AtomicU64::new(0);
 // safety: this assumes that the crate is only used in a single-threaded environment
 static mut TYPEID_TO_NETWORK_OBJECT_ID: Option<HashMap<TypeId, NetworkObjectId, FxBuildHasher>> =
     None;
-static mut NETWORK_OBJ_ID_TO_DESERIALIZE_FN: Option<
-    HashMap<NetworkObjectId, DeserializeFn, FxBuildHasher>,
+static mut NETWORK_OBJ_ID_TO_TYPE: Option<
+    HashMap<NetworkObjectId, NetworkObjectType, FxBuildHasher>,
 > = None;
 pub fn register_network_object<T: NetworkObject + 'static>(deserialize_fn: DeserializeFn) {
     // safety: this assumes that the crate is only used in a single-threaded environment
@@ -30,8 +40,15 @@ pub fn register_network_object<T: NetworkObject + 'static>(deserialize_fn: Deser
     map.insert(TypeId::of::<T>(), id);
 
     // safety: this assumes that the crate is only used in a single-threaded environment
-    let map = unsafe { NETWORK_OBJ_ID_TO_DESERIALIZE_FN.get_or_insert_with(|| HashMap::default()) };
-    map.insert(id, deserialize_fn);
+    let map = unsafe { NETWORK_OBJ_ID_TO_TYPE.get_or_insert_with(|| HashMap::default()) };
+    map.insert(
+        id,
+        NetworkObjectType {
+            deserialize_fn,
+            version: T::version(),
+            migrate_fn: T::migrate,
+        },
+    );
 }
 
 pub fn get_network_object_id<T: NetworkObject + 'static>() -> Option<NetworkObjectId> {
@@ -48,42 +65,149 @@ impl NetworkObjectId {
     }
 }
 
+/// a message for a network object, tagged with the type of object it is meant for
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ObjectMessage<T> {
+    object: NetworkObjectId,
+    payload: T,
+}
+
+impl<T: Serialize> ObjectMessage<T> {
+    /// encode a message for objects of type `O`
+    pub fn encode<O: NetworkObject>(payload: T) -> Result<Vec<u8>, postcard::Error> {
+        let object = get_network_object_id::<O>().expect("network object not registered");
+        postcard::to_allocvec(&ObjectMessage { object, payload })
+    }
+}
+
+impl<T: DeserializeOwned> ObjectMessage<T> {
+    /// decode a message meant for objects of type `O`.
+    /// messages for a different object type or payload are rejected, they come straight from the
+    /// network so they can't be trusted to be right
+    pub fn decode<O: NetworkObject>(data: &[u8]) -> Result<T, postcard::Error> {
+        let (message, rest): (ObjectMessage<T>, _) = postcard::take_from_bytes(data)?;
+        if Some(message.object) != get_network_object_id::<O>() || !rest.is_empty() {
+            return Err(postcard::Error::DeserializeBadEncoding);
+        }
+        Ok(message.payload)
+    }
+}
+
+/// time between two interactions of a client with an object, for objects that don't need a
+/// different one
+pub const INTERACTION_COOLDOWN_MS: u64 = 2000;
+
+/// the client side of the interaction cooldown, so the prompt can be greyed out while the server
+/// would ignore the player anyway
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct InteractionCooldown {
+    last_ms: Option<u64>,
+}
+
+impl InteractionCooldown {
+    /// whether the server takes another interaction at `now_ms`
+    pub fn ready(&self, now_ms: u64, cooldown_ms: u64) -> bool {
+        self.last_ms
+            .map_or(true, |last| now_ms >= last + cooldown_ms)
+    }
+
+    pub fn start(&mut self, now_ms: u64) {
+        self.last_ms = Some(now_ms);
+    }
+}
+
 /// serializable object that can optionally also send/receive custom messages
 pub trait NetworkObject
 where
-    Self: Object + Send + Sync + erased_serde::Serialize + 'static,
+    Self: Object + Any + Send + Sync + erased_serde::Serialize + 'static,
 {
     /// what the server should do when it receives a message from the client. the default implementation does nothing
     ///
     /// `data` is the message data
     /// if returns `Some`, the server will send the data to all clients
     #[allow(unused_variables)]
-    fn server_message(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, postcard::Error> {
+    fn server_message(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, CiboError> {
         Ok(None)
     }
 
-    /// what the client should do when it receives a message from the server. the default implementation panics
+    /// how long the server ignores messages from a client to this object after taking one, so
+    /// holding down a key can't flood everyone with updates. the default is no cooldown, for
+    /// objects that stream their state like the easel or the beach ball
+    fn interaction_cooldown_ms(&self) -> u64 {
+        0
+    }
+
+    /// what the client should do when it receives a message from the server. the default implementation
+    /// rejects it as unexpected
     ///
     /// `data` is the message data
     #[allow(unused_variables)]
-    fn client_message(&mut self, data: &[u8]) -> Result<(), postcard::Error> {
-        panic!("{:?} received unexpected client message", self);
+    fn client_message(&mut self, data: &[u8]) -> Result<(), CiboError> {
+        Err(CiboError::UnexpectedMessage(core::any::type_name::<Self>()))
     }
 
     /// what the server should do every tick. the default implementation does nothing
     ///
     /// if returns `Some`, the server will send the data to all clients
     #[allow(unused_variables)]
-    fn server_tick(&mut self) -> Result<Option<Vec<u8>>, postcard::Error> {
+    fn server_tick(&mut self) -> Result<Option<Vec<u8>>, CiboError> {
         Ok(None)
     }
 
+    /// whether messages from `server_tick` only contain the latest state of the object, so that
+    /// clients far away from it can get them at a reduced rate. the default is false
+    fn throttle_tick_updates(&self) -> bool {
+        false
+    }
+
     /// what the client should do every tick. the default implementation does nothing
     ///
     /// if returns `Some`, the client will send the data to the server
-    fn client_tick(&mut self) -> Result<Option<Vec<u8>>, postcard::Error> {
+    fn client_tick(&mut self) -> Result<Option<Vec<u8>>, CiboError> {
         Ok(None)
     }
+
+    /// put the object back into the state it was created in, at `position`, so the object pool can
+    /// hand it out again. returns false if it can't be reused, which is the default
+    #[allow(unused_variables)]
+    fn reset(&mut self, position: Position) -> bool {
+        false
+    }
+
+    /// encode a message for this kind of object, to be returned from the message and tick functions
+    fn send_typed<T: Serialize>(&self, payload: &T) -> Result<Vec<u8>, postcard::Error>
+    where
+        Self: Sized,
+    {
+        ObjectMessage::encode::<Self>(payload)
+    }
+
+    /// decode a message that was sent with `send_typed`
+    fn recv_typed<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, postcard::Error>
+    where
+        Self: Sized,
+    {
+        ObjectMessage::decode::<Self>(data)
+    }
+
+    /// version of the serialized fields. has to be bumped whenever they change, so objects written
+    /// by an older build go through `migrate` instead of failing to deserialize
+    fn version() -> u8
+    where
+        Self: Sized,
+    {
+        0
+    }
+
+    /// turn an object serialized at `old_version` into the current format.
+    /// the default implementation can't upgrade anything, so the object fails to deserialize
+    #[allow(unused_variables)]
+    fn migrate(old_version: u8, bytes: &[u8]) -> Option<Vec<u8>>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 erased_serde::serialize_trait_object!(NetworkObject);
 
@@ -116,6 +240,32 @@ impl BoxedNetworkObject {
     pub fn as_object(&mut self) -> &mut dyn Object {
         &mut *self.object
     }
+
+    /// access the concrete object, if it is of type `T`
+    pub fn downcast_mut<T: NetworkObject>(&mut self) -> Option<&mut T> {
+        (&mut *self.object as &mut dyn Any).downcast_mut()
+    }
+
+    pub fn downcast_ref<T: NetworkObject>(&self) -> Option<&T> {
+        (&*self.object as &dyn Any).downcast_ref()
+    }
+
+    /// read just the fields of an object of type `id` with any deserializer, e.g. ones edited by hand
+    pub fn deserialize_fields<'de, D>(
+        id: NetworkObjectId,
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let object_type =
+            object_type(id).ok_or_else(|| serde::de::Error::custom("unknown network object id"))?;
+        let object = (object_type.deserialize_fn)(&mut <dyn erased_serde::Deserializer>::erase(
+            deserializer,
+        ))
+        .map_err(serde::de::Error::custom)?;
+        Ok(BoxedNetworkObject { id, object })
+    }
 }
 
 impl Deref for BoxedNetworkObject {
@@ -137,9 +287,16 @@ impl Serialize for BoxedNetworkObject {
     where
         S: serde::Serializer,
     {
-        let mut seq = serializer.serialize_seq(Some(2))?;
+        // the fields are stored as their own bytes, so they can be migrated before deserializing them
+        let version = object_type(self.id)
+            .ok_or_else(|| serde::ser::Error::custom("unknown network object id"))?
+            .version;
+        let bytes = postcard::to_allocvec(&self.object).map_err(serde::ser::Error::custom)?;
+
+        let mut seq = serializer.serialize_seq(Some(3))?;
         seq.serialize_element(&self.id)?;
-        seq.serialize_element(&self.object)?;
+        seq.serialize_element(&version)?;
+        seq.serialize_element(&bytes)?;
         seq.end()
     }
 }
@@ -153,19 +310,10 @@ impl<'de> Deserialize<'de> for BoxedNetworkObject {
     }
 }
 
-struct DeserializeFnApplicator {
-    deserialize_fn: DeserializeFn,
-}
-impl<'de> DeserializeSeed<'de> for DeserializeFnApplicator {
-    type Value = Box<dyn NetworkObject>;
-
-    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
-        (self.deserialize_fn)(&mut erased).map_err(serde::de::Error::custom)
-    }
+fn object_type(id: NetworkObjectId) -> Option<NetworkObjectType> {
+    // safety: this assumes that the crate is only used in a single-threaded environment
+    let map = unsafe { NETWORK_OBJ_ID_TO_TYPE.get_or_insert_with(|| HashMap::default()) };
+    map.get(&id).copied()
 }
 
 struct BoxedNetworkObjectVisitor;
@@ -183,19 +331,30 @@ impl<'de> Visitor<'de> for BoxedNetworkObjectVisitor {
         let id = seq
             .next_element::<NetworkObjectId>()?
             .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
-        let deserialize_fn = {
-            // safety: this assumes that the crate is only used in a single-threaded environment
-            let map = unsafe {
-                NETWORK_OBJ_ID_TO_DESERIALIZE_FN.get_or_insert_with(|| HashMap::default())
-            };
-            map.get(&id)
-                .copied()
-                .ok_or_else(|| serde::de::Error::custom("unknown network object id"))?
-        };
-        let object = seq
-            .next_element_seed(DeserializeFnApplicator { deserialize_fn })
-            .map_err(serde::de::Error::custom)?
+        let object_type =
+            object_type(id).ok_or_else(|| serde::de::Error::custom("unknown network object id"))?;
+        let version = seq
+            .next_element::<u8>()?
             .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+        let bytes = seq
+            .next_element::<Vec<u8>>()?
+            .ok_or_else(|| serde::de::Error::invalid_length(2, &self))?;
+
+        let bytes = if version == object_type.version {
+            bytes
+        } else {
+            (object_type.migrate_fn)(version, &bytes).ok_or_else(|| {
+                serde::de::Error::custom(format_args!(
+                    "network object can't be upgraded from version {} to {}",
+                    version, object_type.version
+                ))
+            })?
+        };
+        let mut deserializer = postcard::Deserializer::from_bytes(&bytes);
+        let object = (object_type.deserialize_fn)(&mut <dyn erased_serde::Deserializer>::erase(
+            &mut deserializer,
+        ))
+        .map_err(serde::de::Error::custom)?;
 
         Ok(BoxedNetworkObject { id, object })
     }
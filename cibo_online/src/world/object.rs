@@ -1,10 +1,22 @@
+use super::{
+    scalar::{Scalar, ScalarExt},
+    Physics,
+};
 use crate::{RectExt, Renderable, Sprite, ZOrder};
 
-#[allow(unused_imports)]
-use micromath::F32Ext;
 use monos_gfx::{Dimension, Position, Rect};
 use serde::{Deserialize, Serialize};
 
+/// the level something is on. things only collide with things on the same level,
+/// and everything raised is drawn above everything on the ground
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Elevation {
+    #[default]
+    Ground,
+    /// e.g. on top of a bridge
+    Raised,
+}
+
 /// properties of an object that need to be known in advance.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObjectProperties {
@@ -15,6 +27,10 @@ pub struct ObjectProperties {
     pub rel_bounds: Rect,
     pub interactable: bool,
     pub override_z: Option<ZOrder>,
+    pub elevation: Elevation,
+    /// whether the overlays and popups of this object can be shown in stream mode.
+    /// objects showing things players made or popups covering the world should leave this off
+    pub stream_safe: bool,
 }
 
 /// objects that can be converted into a sprite and has a hitbox.
@@ -23,7 +39,7 @@ where
     Self: Renderable<LocalState = ()> + core::fmt::Debug,
 {
     #[allow(unused_variables)]
-    fn tick(&mut self, delta_ms: u64, collision_tester: CollisionTester) {}
+    fn tick(&mut self, delta_ms: u64, physics: &Physics, collision_tester: CollisionTester) {}
 
     fn as_sprite(&mut self) -> Sprite;
 
@@ -65,6 +81,19 @@ where
     }
 
     fn set_position(&mut self, position: Position);
+
+    /// the level a client whose feet are at `feet` ends up on, for objects that can be walked onto.
+    /// `None` if the object doesn't change it
+    #[allow(unused_variables)]
+    fn elevation_at(&self, feet: Position, current: Elevation) -> Option<Elevation> {
+        None
+    }
+
+    /// whether a client on `elevation` can't step from `from` to `to`, e.g. off the side of a bridge
+    #[allow(unused_variables)]
+    fn blocks_step(&self, from: Position, to: Position, elevation: Elevation) -> bool {
+        false
+    }
 }
 
 pub struct CollisionTester<'a> {
@@ -86,12 +115,12 @@ impl CollisionTester<'_> {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct CollisionInfo {
     center: Position,
-    velocity: Option<(f32, f32)>,
+    velocity: Option<(Scalar, Scalar)>,
     is_player: bool,
 }
 
 impl CollisionInfo {
-    pub fn new_dynamic(center: Position, velocity: (f32, f32)) -> Self {
+    pub fn new_dynamic(center: Position, velocity: (Scalar, Scalar)) -> Self {
         CollisionInfo {
             center,
             velocity: Some(velocity),
@@ -107,7 +136,7 @@ impl CollisionInfo {
         }
     }
 
-    pub(crate) fn new_player(center: Position, velocity: (f32, f32)) -> Self {
+    pub(crate) fn new_player(center: Position, velocity: (Scalar, Scalar)) -> Self {
         CollisionInfo {
             center,
             velocity: Some(velocity),
@@ -123,57 +152,183 @@ impl CollisionInfo {
         self.velocity.is_none()
     }
 
-    pub fn velocity(&self) -> (f32, f32) {
-        self.velocity.unwrap_or((0.0, 0.0))
+    pub fn velocity(&self) -> (Scalar, Scalar) {
+        self.velocity.unwrap_or((Scalar::ZERO, Scalar::ZERO))
     }
 
-    /// apply the collision self with other and return the new velocity.
-    pub fn apply(self, other: CollisionInfo) -> (f32, f32) {
+    /// direction from self towards other and the distance between them.
+    fn normal(self, other: CollisionInfo) -> ((Scalar, Scalar), Scalar) {
         let normal = (
-            other.center.x as f32 - self.center.x as f32,
-            other.center.y as f32 - self.center.y as f32,
+            Scalar::from_int(other.center.x - self.center.x),
+            Scalar::from_int(other.center.y - self.center.y),
         );
         let normal_len = (normal.0 * normal.0 + normal.1 * normal.1).sqrt();
-        let normal = (normal.0 / normal_len, normal.1 / normal_len);
+        if normal_len > Scalar::EPSILON {
+            return ((normal.0 / normal_len, normal.1 / normal_len), normal_len);
+        }
+
+        // both centers are in the same spot, assume we ran into other head on
+        let velocity = self.velocity();
+        let speed = (velocity.0 * velocity.0 + velocity.1 * velocity.1).sqrt();
+        if speed > Scalar::EPSILON {
+            ((velocity.0 / speed, velocity.1 / speed), Scalar::ZERO)
+        } else {
+            ((Scalar::ZERO, Scalar::ONE), Scalar::ZERO)
+        }
+    }
 
-        self.apply_raw(other, normal, normal_len)
+    /// apply the collision self with other and return the new velocity.
+    pub fn apply(self, other: CollisionInfo) -> (Scalar, Scalar) {
+        self.apply_limited(other, Scalar::from_f32(Physics::default().max_velocity))
     }
 
-    pub fn apply_with_force(self, other: CollisionInfo, force: f32) -> (f32, f32) {
-        let normal = (
-            other.center.x as f32 - self.center.x as f32,
-            other.center.y as f32 - self.center.y as f32,
-        );
-        let normal_len = (normal.0 * normal.0 + normal.1 * normal.1).sqrt();
-        let normal = (normal.0 / normal_len, normal.1 / normal_len);
+    /// like `apply`, but clamped to `limit` instead of the default max velocity
+    pub fn apply_limited(self, other: CollisionInfo, limit: Scalar) -> (Scalar, Scalar) {
+        let (normal, normal_len) = self.normal(other);
+        self.apply_raw(other, normal, normal_len, limit)
+    }
+
+    pub fn apply_with_force(self, other: CollisionInfo, force: Scalar) -> (Scalar, Scalar) {
+        self.apply_with_force_limited(
+            other,
+            force,
+            Scalar::from_f32(Physics::default().max_velocity),
+        )
+    }
 
-        let mut velocity = self.apply_raw(other, normal, normal_len);
+    /// the force is added after clamping, so the result can be up to `limit + force`
+    pub fn apply_with_force_limited(
+        self,
+        other: CollisionInfo,
+        force: Scalar,
+        limit: Scalar,
+    ) -> (Scalar, Scalar) {
+        let (normal, normal_len) = self.normal(other);
+
+        let mut velocity = self.apply_raw(other, normal, normal_len, limit);
         velocity.0 += -normal.0 * force;
         velocity.1 += -normal.1 * force;
 
         velocity
     }
 
-    fn apply_raw(self, other: CollisionInfo, normal: (f32, f32), normal_len: f32) -> (f32, f32) {
+    fn apply_raw(
+        self,
+        other: CollisionInfo,
+        normal: (Scalar, Scalar),
+        normal_len: Scalar,
+        limit: Scalar,
+    ) -> (Scalar, Scalar) {
         if self.is_static() {
-            return (0.0, 0.0);
+            return (Scalar::ZERO, Scalar::ZERO);
         }
 
         let mut velocity = self.velocity.unwrap();
 
         if other.is_static() {
             let dot = velocity.0 * normal.0 + velocity.1 * normal.1;
-            velocity.0 -= 2.0 * dot * normal.0;
-            velocity.1 -= 2.0 * dot * normal.1;
+            let two = Scalar::from_int(2);
+            velocity.0 -= two * dot * normal.0;
+            velocity.1 -= two * dot * normal.1;
         } else {
             // TODO: this is not correct but the engine gets unstable otherwise. this only works for beach ball sized objects :P
-            velocity.0 -= normal.0 * (16.0 / normal_len.max(0.1)) * 0.5;
-            velocity.1 -= normal.1 * (16.0 / normal_len.max(0.1)) * 0.5;
+            let push = Scalar::from_int(8) / normal_len.max(Scalar::from_f32(0.1));
+            velocity.0 -= normal.0 * push;
+            velocity.1 -= normal.1 * push;
         }
 
-        velocity.0 = velocity.0.clamp(-5.0, 5.0);
-        velocity.1 = velocity.1.clamp(-5.0, 5.0);
+        velocity.0 = velocity.0.clamp(-limit, limit);
+        velocity.1 = velocity.1.clamp(-limit, limit);
 
         velocity
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::scalar::{vec_from_f32, vec_to_f32};
+    use proptest::prelude::*;
+
+    fn position() -> impl Strategy<Value = Position> {
+        (-1000i64..1000, -1000i64..1000).prop_map(|(x, y)| Position::new(x, y))
+    }
+
+    fn velocity() -> impl Strategy<Value = (Scalar, Scalar)> {
+        (-10.0f32..10.0, -10.0f32..10.0).prop_map(vec_from_f32)
+    }
+
+    fn collision() -> impl Strategy<Value = CollisionInfo> {
+        prop_oneof![
+            position().prop_map(CollisionInfo::new_static),
+            (position(), velocity()).prop_map(|(p, v)| CollisionInfo::new_dynamic(p, v)),
+            (position(), velocity()).prop_map(|(p, v)| CollisionInfo::new_player(p, v)),
+        ]
+    }
+
+    fn assert_bounded(velocity: (Scalar, Scalar), limit: f32) -> Result<(), TestCaseError> {
+        let velocity = vec_to_f32(velocity);
+        prop_assert!(
+            velocity.0.is_finite() && velocity.1.is_finite(),
+            "{:?}",
+            velocity
+        );
+        prop_assert!(
+            velocity.0.abs() <= limit && velocity.1.abs() <= limit,
+            "{:?}",
+            velocity
+        );
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn apply_is_finite_and_clamped(a in collision(), b in collision()) {
+            assert_bounded(a.apply(b), 5.0)?;
+        }
+
+        #[test]
+        fn apply_with_force_is_finite_and_bounded(
+            a in collision(),
+            b in collision(),
+            force in 0.0f32..5.0,
+        ) {
+            let velocity = a.apply_with_force(b, Scalar::from_f32(force));
+            if a.is_static() {
+                prop_assert_eq!(vec_to_f32(velocity), (0.0, 0.0));
+            }
+            assert_bounded(velocity, 5.0 + force)?;
+        }
+
+        #[test]
+        fn same_center_does_not_produce_nan(
+            center in position(),
+            a in velocity(),
+            b in velocity(),
+            force in 0.0f32..5.0,
+        ) {
+            let own = CollisionInfo::new_dynamic(center, a);
+            for other in [
+                CollisionInfo::new_static(center),
+                CollisionInfo::new_dynamic(center, b),
+                CollisionInfo::new_player(center, b),
+            ] {
+                assert_bounded(own.apply(other), 5.0)?;
+                assert_bounded(own.apply_with_force(other, Scalar::from_f32(force)), 5.0 + force)?;
+            }
+        }
+
+        #[test]
+        fn static_bounce_keeps_speed(
+            a in position(),
+            b in position(),
+            velocity in (-5.0f32..5.0, -5.0f32..5.0),
+        ) {
+            // reflecting off a static object only changes the direction
+            let own = CollisionInfo::new_dynamic(a, vec_from_f32(velocity));
+            let bounced = vec_to_f32(own.apply(CollisionInfo::new_static(b)));
+            let speed = |v: (f32, f32)| (v.0 * v.0 + v.1 * v.1).sqrt();
+            prop_assert!(speed(bounced) <= speed(velocity) + 0.01);
+        }
+    }
+}
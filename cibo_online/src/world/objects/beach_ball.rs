@@ -2,24 +2,67 @@ use alloc::vec::Vec;
 use core::ops::Not;
 
 use crate::{
-    assets, BoxedNetworkObject, CollisionInfo, CollisionTester, NetworkObject, Object,
-    ObjectProperties, RenderContext, Renderable, Sprite,
+    assets, render::Animation, world::water, BoxedNetworkObject, CiboError, ClientId,
+    CollisionInfo, CollisionTester, Elevation, NetworkObject, Object, ObjectProperties, Physics,
+    RenderContext, Renderable, Scalar, ScalarExt, Sprite,
 };
-use micromath::F32Ext;
 use monos_gfx::{Color, Dimension, Position, Rect};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BeachBall {
     properties: ObjectProperties,
-    position_f: (f32, f32),
-    velocity: (f32, f32),
+    position_f: (Scalar, Scalar),
+    velocity: (Scalar, Scalar),
     #[serde(skip)]
     angle: f32,
     #[serde(skip)]
     queued_collision: Option<CollisionInfo>,
+
+    /// the last push the server applied for each of the recent pushers, oldest first
+    #[serde(skip)]
+    last_pushes: Vec<(ClientId, u32)>,
+    /// who sent the message the server is handling
+    #[serde(skip)]
+    sender: Option<ClientId>,
+    #[serde(skip)]
+    prediction: Prediction,
+    /// server ticks spent floating in the sea
+    #[serde(skip)]
+    drift_ticks: u32,
+    /// the physics of the world, as of the last tick
+    #[serde(skip)]
+    physics: Physics,
+}
+
+/// client side prediction for pushes by the local player
+#[derive(Debug, Default)]
+struct Prediction {
+    own_id: Option<ClientId>,
+    next_push: u32,
+    /// the push that has been applied locally but not yet confirmed by the server
+    pending: Option<u32>,
+    pending_ms: u64,
+    /// difference between the predicted and the authoritative position, smoothed out over time
+    error: (f32, f32),
 }
 
+/// give up on a push that the server hasn't confirmed after this long
+const PREDICTION_TIMEOUT_MS: u64 = 1000;
+/// how many pushers get their last push confirmed in each state update
+const CONFIRMED_PUSHERS: usize = 8;
+
+/// how far the current moves a floating ball along the shore every tick
+const CURRENT: f32 = 0.15;
+/// the current changes direction after this many ticks
+const CURRENT_PERIOD_TICKS: u32 = 60 * 20;
+/// how often the server sends the position of a drifting ball, in ticks
+const DRIFT_UPDATE_TICKS: u32 = 30;
+/// the water slows balls down more than the sand does
+const WATER_DRAG: f32 = 0.1;
+
+const BOB: Animation<i64> = Animation::looping(&[(0, 500), (1, 500)]);
+
 impl BeachBall {
     pub fn new(position: Position) -> BoxedNetworkObject {
         let dimensions = assets().beach_ball.dimensions();
@@ -37,20 +80,42 @@ impl BeachBall {
                 rel_bounds: bounds,
                 interactable: false,
                 override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: true,
             },
             angle: 0.0,
-            velocity: (0.0, 0.0),
-            position_f: (position.x as f32, position.y as f32),
+            velocity: (Scalar::ZERO, Scalar::ZERO),
+            position_f: (Scalar::from_int(position.x), Scalar::from_int(position.y)),
             queued_collision: None,
+            last_pushes: Vec::new(),
+            sender: None,
+            prediction: Prediction::default(),
+            drift_ticks: 0,
+            physics: Physics::default(),
         })
     }
 
+    /// set by the server before handing it a message from `client`
+    pub(crate) fn set_sender(&mut self, client: ClientId) {
+        self.sender = Some(client);
+    }
+
+    fn floating(&self) -> bool {
+        water::is_water(self.properties.position + self.properties.dimensions.center())
+    }
+
     fn apply_collision(&mut self, collision: CollisionInfo) {
-        self.velocity = if collision.is_player() && collision.velocity() != (0.0, 0.0) {
-            self.collision_info().apply_with_force(collision, 1.2)
-        } else {
-            self.collision_info().apply(collision)
-        };
+        let limit = Scalar::from_f32(self.physics.max_velocity);
+        self.velocity =
+            if collision.is_player() && collision.velocity() != (Scalar::ZERO, Scalar::ZERO) {
+                self.collision_info().apply_with_force_limited(
+                    collision,
+                    Scalar::from_f32(self.physics.bounce_force),
+                    limit,
+                )
+            } else {
+                self.collision_info().apply_limited(collision, limit)
+            };
 
         /*
         self.position_f.0 += self.velocity.0;
@@ -68,10 +133,20 @@ impl BeachBall {
 impl Renderable for BeachBall {
     type LocalState = ();
     fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
-        let screen_pos = self.properties.position - camera;
+        self.prediction.own_id = Some(ctx.own_id);
+
+        let error = self.prediction.error;
+        let mut screen_pos = self.properties.position - camera
+            + Position::new(
+                micromath::F32Ext::round(error.0) as i64,
+                micromath::F32Ext::round(error.1) as i64,
+            );
+        if self.floating() {
+            screen_pos.y += BOB.frame_at(ctx.time_ms);
+        }
 
         self.properties.position =
-            Position::new(self.position_f.0 as i64, self.position_f.1 as i64);
+            Position::new(self.position_f.0.to_int(), self.position_f.1.to_int());
 
         ctx.fb
             .draw_img(&assets().beach_ball.get_image(self.angle), screen_pos);
@@ -98,20 +173,29 @@ impl Object for BeachBall {
     }
 
     fn set_position(&mut self, position: Position) {
-        self.position_f = (position.x as f32, position.y as f32);
+        self.position_f = (Scalar::from_int(position.x), Scalar::from_int(position.y));
     }
 
-    fn tick(&mut self, delta_ms: u64, mut collision_tester: CollisionTester) {
-        let passed_ticks = delta_ms as f32 / crate::SERVER_TICK_RATE as f32;
-        let blend = 1.0 - 0.05f32.powf(passed_ticks);
+    fn tick(&mut self, delta_ms: u64, physics: &Physics, mut collision_tester: CollisionTester) {
+        self.physics = *physics;
+
+        let passed_ticks =
+            Scalar::from_int(delta_ms as i64) / Scalar::from_int(crate::SERVER_TICK_RATE as i64);
+        let drag = if self.floating() {
+            WATER_DRAG
+        } else {
+            physics.friction
+        };
+        let blend = Scalar::ONE - Scalar::from_f32(drag).powf(passed_ticks);
         self.velocity.0 *= blend;
         self.velocity.1 *= blend;
 
-        if self.velocity.0.abs() < 0.1 {
-            self.velocity.0 = 0.0;
+        let rest = Scalar::from_f32(0.1);
+        if self.velocity.0.abs() < rest {
+            self.velocity.0 = Scalar::ZERO;
         }
-        if self.velocity.1.abs() < 0.1 {
-            self.velocity.1 = 0.0;
+        if self.velocity.1.abs() < rest {
+            self.velocity.1 = Scalar::ZERO;
         }
 
         collision_tester.test(self);
@@ -119,65 +203,255 @@ impl Object for BeachBall {
         self.position_f.0 += self.velocity.0 * passed_ticks;
         self.position_f.1 += self.velocity.1 * passed_ticks;
 
-        self.angle += (self.velocity.0.abs() + self.velocity.1.abs() * 0.5)
-            * self.velocity.0.signum()
+        // the angle is only drawn, so it doesn't need to be deterministic
+        let velocity = (self.velocity.0.to_f32(), self.velocity.1.to_f32());
+        self.angle += (micromath::F32Ext::abs(velocity.0)
+            + micromath::F32Ext::abs(velocity.1) * 0.5)
+            * micromath::F32Ext::signum(velocity.0)
             * 7.5
-            * passed_ticks;
+            * passed_ticks.to_f32();
 
-        self.properties.position.x = self.position_f.0 as i64;
-        self.properties.position.y = self.position_f.1 as i64;
+        self.properties.position.x = self.position_f.0.to_int();
+        self.properties.position.y = self.position_f.1.to_int();
+
+        let decay = Scalar::from_f32(0.8).powf(passed_ticks).to_f32();
+        self.prediction.error.0 *= decay;
+        self.prediction.error.1 *= decay;
+        if self.prediction.pending.is_some() {
+            self.prediction.pending_ms += delta_ms;
+        }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BeachBallStateMessage {
-    position: (f32, f32),
-    velocity: (f32, f32),
+    position: (Scalar, Scalar),
+    velocity: (Scalar, Scalar),
+    /// the last applied push of each recent pusher
+    last_pushes: Vec<(ClientId, u32)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BeachBallPush {
+    /// numbers the pushes of the sender, so it knows when the server has applied one
+    push: Option<u32>,
+    collision: CollisionInfo,
 }
 
 impl NetworkObject for BeachBall {
-    fn server_message(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, postcard::Error> {
-        let collision: CollisionInfo = postcard::from_bytes(data)?;
-        self.apply_collision(collision);
+    fn reset(&mut self, position: Position) -> bool {
+        self.properties.position = position;
+        self.properties.elevation = Elevation::Ground;
+        self.position_f = (Scalar::from_int(position.x), Scalar::from_int(position.y));
+        self.velocity = (Scalar::ZERO, Scalar::ZERO);
+        self.angle = 0.0;
+        self.queued_collision = None;
+        self.last_pushes.clear();
+        self.sender = None;
+        self.prediction = Prediction::default();
+        self.drift_ticks = 0;
+        true
+    }
+
+    fn server_message(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, CiboError> {
+        let sender = self.sender.take();
+        let push: BeachBallPush = self.recv_typed(data)?;
+        self.apply_collision(push.collision);
+        if let (Some(sender), Some(push)) = (sender, push.push) {
+            self.last_pushes.retain(|(pusher, _)| *pusher != sender);
+            if self.last_pushes.len() >= CONFIRMED_PUSHERS {
+                self.last_pushes.remove(0);
+            }
+            self.last_pushes.push((sender, push));
+        }
 
-        Ok(Some(postcard::to_allocvec(&BeachBallStateMessage {
+        Ok(Some(self.send_typed(&BeachBallStateMessage {
             position: self.position_f,
             velocity: self.velocity,
+            last_pushes: self.last_pushes.clone(),
         })?))
     }
 
-    fn client_message(&mut self, data: &[u8]) -> Result<(), postcard::Error> {
-        let state: BeachBallStateMessage = postcard::from_bytes(data)?;
+    fn client_message(&mut self, data: &[u8]) -> Result<(), CiboError> {
+        let state: BeachBallStateMessage = self.recv_typed(data)?;
+
+        let prediction = &mut self.prediction;
+        if let (Some(pending), Some(own_id)) = (prediction.pending, prediction.own_id) {
+            let confirmed = state.last_pushes.contains(&(own_id, pending));
+            // states sent before the server saw our push would undo the prediction
+            if !confirmed && prediction.pending_ms < PREDICTION_TIMEOUT_MS {
+                return Ok(());
+            }
+            prediction.pending = None;
+        }
+
+        // don't snap to the authoritative position, blend towards it instead
+        prediction.error.0 += (self.position_f.0 - state.position.0).to_f32();
+        prediction.error.1 += (self.position_f.1 - state.position.1).to_f32();
+
         self.position_f = state.position;
         self.velocity = state.velocity;
 
         Ok(())
     }
 
-    fn server_tick(&mut self) -> Result<Option<Vec<u8>>, postcard::Error> {
+    fn server_tick(&mut self) -> Result<Option<Vec<u8>>, CiboError> {
         let mut result = None;
 
         if let Some(collision) = self.queued_collision.take() {
             self.apply_collision(collision);
         }
 
-        if self.velocity.0.abs() > 0.1 || self.velocity.1.abs() > 0.1 {
-            result = Some(postcard::to_allocvec(&BeachBallStateMessage {
+        // floating balls slowly drift along the shore
+        let mut drift_update = false;
+        if self.floating() {
+            self.drift_ticks = self.drift_ticks.wrapping_add(1);
+            let current = if (self.drift_ticks / CURRENT_PERIOD_TICKS) % 2 == 0 {
+                CURRENT
+            } else {
+                -CURRENT
+            };
+            self.position_f.0 += Scalar::from_f32(current);
+            drift_update = self.drift_ticks % DRIFT_UPDATE_TICKS == 0;
+        }
+
+        let rest = Scalar::from_f32(0.1);
+        if self.velocity.0.abs() > rest || self.velocity.1.abs() > rest || drift_update {
+            result = Some(self.send_typed(&BeachBallStateMessage {
                 position: self.position_f,
                 velocity: self.velocity,
+                last_pushes: self.last_pushes.clone(),
             })?);
         }
 
         Ok(result)
     }
 
-    fn client_tick(&mut self) -> Result<Option<Vec<u8>>, postcard::Error> {
+    fn throttle_tick_updates(&self) -> bool {
+        true
+    }
+
+    fn client_tick(&mut self) -> Result<Option<Vec<u8>>, CiboError> {
         let mut result = None;
 
         if let Some(collision) = self.queued_collision.take() {
-            result = Some(postcard::to_allocvec(&collision)?);
+            // predict the push locally instead of waiting for the server to respond
+            self.apply_collision(collision);
+
+            let push = self.prediction.own_id.map(|_| {
+                let push = self.prediction.next_push;
+                self.prediction.next_push = push.wrapping_add(1);
+                self.prediction.pending = Some(push);
+                self.prediction.pending_ms = 0;
+                push
+            });
+            result = Some(self.send_typed(&BeachBallPush { push, collision })?);
         }
 
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{vec_from_f32, vec_to_f32};
+    use proptest::prelude::*;
+
+    // built by hand since `BeachBall::new` needs the assets.
+    // collisions are applied directly, sending messages needs the global object registry
+    fn ball(position: (f32, f32), velocity: (f32, f32)) -> BeachBall {
+        let dimensions = Dimension::new(16, 16);
+        BeachBall {
+            properties: ObjectProperties {
+                position: Position::new(position.0 as i64, position.1 as i64),
+                dimensions,
+                rel_hitbox: Some(Rect::from_dimensions(dimensions)),
+                rel_bounds: Rect::from_dimensions(dimensions),
+                interactable: false,
+                override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: true,
+            },
+            position_f: vec_from_f32(position),
+            velocity: vec_from_f32(velocity),
+            angle: 0.0,
+            queued_collision: None,
+            last_pushes: Vec::new(),
+            sender: None,
+            prediction: Prediction::default(),
+            drift_ticks: 0,
+            physics: Physics::default(),
+        }
+    }
+
+    fn collision() -> impl Strategy<Value = CollisionInfo> {
+        let position = (-100i64..100, -100i64..100).prop_map(|(x, y)| Position::new(x, y));
+        let velocity = (-5.0f32..5.0, -5.0f32..5.0);
+        prop_oneof![
+            position.clone().prop_map(CollisionInfo::new_static),
+            (position.clone(), velocity.clone())
+                .prop_map(|(p, v)| CollisionInfo::new_dynamic(p, vec_from_f32(v))),
+            (position, velocity).prop_map(|(p, v)| CollisionInfo::new_player(p, vec_from_f32(v))),
+        ]
+    }
+
+    fn is_finite(ball: &BeachBall) -> bool {
+        let (velocity, position) = (vec_to_f32(ball.velocity), vec_to_f32(ball.position_f));
+        velocity.0.is_finite()
+            && velocity.1.is_finite()
+            && position.0.is_finite()
+            && position.1.is_finite()
+            && ball.angle.is_finite()
+    }
+
+    proptest! {
+        #[test]
+        fn tick_never_speeds_up(
+            position in (-100.0f32..100.0, -100.0f32..100.0),
+            velocity in (-5.0f32..5.0, -5.0f32..5.0),
+            deltas in prop::collection::vec(0u64..200, 1..50),
+        ) {
+            let mut ball = ball(position, velocity);
+            for delta_ms in deltas {
+                let before = vec_to_f32(ball.velocity);
+                ball.tick(delta_ms, &Physics::default(), CollisionTester::new(&mut |_| None));
+                prop_assert!(is_finite(&ball));
+                let after = vec_to_f32(ball.velocity);
+                prop_assert!(after.0.abs() <= before.0.abs());
+                prop_assert!(after.1.abs() <= before.1.abs());
+            }
+        }
+
+        #[test]
+        fn collisions_stay_bounded(
+            velocity in (-5.0f32..5.0, -5.0f32..5.0),
+            collisions in prop::collection::vec((collision(), 0u64..200), 1..50),
+        ) {
+            let mut ball = ball((0.0, 0.0), velocity);
+            for (collision, delta_ms) in collisions {
+                ball.apply_collision(collision);
+                ball.tick(delta_ms, &Physics::default(), CollisionTester::new(&mut |_| None));
+
+                prop_assert!(is_finite(&ball));
+                // pushes by players add a bit of force on top of the clamped velocity
+                let velocity = vec_to_f32(ball.velocity);
+                prop_assert!(velocity.0.abs() <= 6.2 && velocity.1.abs() <= 6.2);
+            }
+        }
+
+        #[test]
+        fn collision_on_the_center_is_finite(velocity in (-5.0f32..5.0, -5.0f32..5.0)) {
+            let mut ball = ball((0.0, 0.0), velocity);
+            let center = ball.properties.position + ball.properties.dimensions.center();
+            for collision in [
+                CollisionInfo::new_static(center),
+                CollisionInfo::new_dynamic(center, vec_from_f32(velocity)),
+                CollisionInfo::new_player(center, vec_from_f32((1.0, 0.0))),
+            ] {
+                ball.apply_collision(collision);
+                prop_assert!(is_finite(&ball));
+            }
+        }
+    }
+}
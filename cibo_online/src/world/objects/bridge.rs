@@ -0,0 +1,124 @@
+use crate::{
+    BoxedNetworkObject, Elevation, NetworkObject, Object, ObjectProperties, RenderContext,
+    Renderable, Sprite, ZOrder,
+};
+use monos_gfx::{Color, Dimension, Position, Rect};
+use serde::{Deserialize, Serialize};
+
+const PLANK_COLOR: Color = Color::new(168, 118, 72);
+const GAP_COLOR: Color = Color::new(120, 80, 45);
+const RAIL_COLOR: Color = Color::new(98, 62, 34);
+const RAMP_COLOR: Color = Color::new(150, 104, 62);
+
+const WIDTH: u32 = 96;
+const HEIGHT: u32 = 40;
+const PLANK_WIDTH: i64 = 8;
+const RAIL_HEIGHT: i64 = 4;
+/// the ends of the bridge that take players up onto it
+const RAMP_WIDTH: i64 = 16;
+
+/// a wooden bridge going from left to right. players can walk up the ramps at either end and cross
+/// it on top, or walk underneath it through the middle.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bridge {
+    properties: ObjectProperties,
+}
+
+impl Bridge {
+    pub fn new(position: Position) -> BoxedNetworkObject {
+        let dimensions = Dimension::new(WIDTH, HEIGHT);
+        let bounds = Rect::new(Position::zero(), Position::from_dimensions(dimensions));
+
+        BoxedNetworkObject::new(Bridge {
+            properties: ObjectProperties {
+                position,
+                dimensions,
+                rel_hitbox: None,
+                rel_bounds: bounds,
+                interactable: false,
+                // players under the bridge are hidden by it, players on top are drawn above it
+                override_z: Some(ZOrder::level_floor(Elevation::Raised)),
+                elevation: Elevation::Raised,
+                stream_safe: true,
+            },
+        })
+    }
+
+    fn on_ramp(&self, position: Position) -> bool {
+        let x = position.x - self.properties.position.x;
+        x < RAMP_WIDTH || x >= WIDTH as i64 - RAMP_WIDTH
+    }
+}
+
+impl Renderable for Bridge {
+    type LocalState = ();
+    fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
+        let screen_pos = self.properties.position - camera;
+        let deck = Rect::new(
+            screen_pos,
+            screen_pos + Position::from_dimensions(self.properties.dimensions),
+        );
+        ctx.fb.draw_rect(deck, GAP_COLOR);
+
+        for x in (0..WIDTH as i64).step_by(PLANK_WIDTH as usize) {
+            let color = if x < RAMP_WIDTH || x >= WIDTH as i64 - RAMP_WIDTH {
+                RAMP_COLOR
+            } else {
+                PLANK_COLOR
+            };
+            ctx.fb.draw_rect(
+                Rect::new(
+                    screen_pos + Position::new(x, RAIL_HEIGHT),
+                    screen_pos + Position::new(x + PLANK_WIDTH - 1, HEIGHT as i64 - RAIL_HEIGHT),
+                ),
+                color,
+            );
+        }
+
+        for y in [0, HEIGHT as i64 - RAIL_HEIGHT] {
+            ctx.fb.draw_rect(
+                Rect::new(
+                    screen_pos + Position::new(RAMP_WIDTH, y),
+                    screen_pos + Position::new(WIDTH as i64 - RAMP_WIDTH, y + RAIL_HEIGHT),
+                ),
+                RAIL_COLOR,
+            );
+        }
+    }
+}
+
+impl Object for Bridge {
+    fn as_sprite(&mut self) -> Sprite {
+        Sprite::Object(self)
+    }
+
+    fn properties(&self) -> &ObjectProperties {
+        &self.properties
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.properties.position = position;
+    }
+
+    fn elevation_at(&self, feet: Position, current: Elevation) -> Option<Elevation> {
+        if !self.bounds().contains(feet) {
+            None
+        } else if self.on_ramp(feet) {
+            Some(Elevation::Raised)
+        } else {
+            // on top stays on top, underneath stays underneath
+            Some(current)
+        }
+    }
+
+    fn blocks_step(&self, from: Position, to: Position, elevation: Elevation) -> bool {
+        // the railings keep players on top from falling off the sides
+        let bounds = self.bounds();
+        elevation == Elevation::Raised
+            && bounds.contains(from)
+            && !bounds.contains(to)
+            && !self.on_ramp(from)
+    }
+}
+
+impl NetworkObject for Bridge {}
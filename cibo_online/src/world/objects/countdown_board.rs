@@ -0,0 +1,286 @@
+use crate::{
+    render::{phase_offset, COOLDOWN_PROMPT_COLOR},
+    widgets::RichLabel,
+    BoxedNetworkObject, CiboError, Elevation, InteractionCooldown, NetworkObject, Object,
+    ObjectProperties, RectExt, RenderContext, Renderable, Sprite, INTERACTION_COOLDOWN_MS,
+};
+use alloc::{format, string::String, vec::Vec};
+use monos_gfx::{
+    font,
+    input::Key,
+    ui::{Direction, MarginMode, UIFrame},
+    Color, Dimension, Position, Rect,
+};
+use serde::{Deserialize, Serialize};
+
+const FRAME_COLOR: Color = Color::new(120, 80, 45);
+const BOARD_COLOR: Color = Color::new(46, 72, 58);
+const FRAME_WIDTH: i64 = 4;
+const LEG_HEIGHT: i64 = 12;
+
+const WIDTH: u32 = 112;
+const HEIGHT: u32 = 64;
+
+/// how long the confetti keeps falling once the countdown is over
+const CONFETTI_MS: u64 = 6000;
+const CONFETTI_PIECES: u32 = 32;
+/// how far above the board the confetti starts falling
+const CONFETTI_HEIGHT: i64 = 48;
+const CONFETTI_COLORS: [Color; 5] = [
+    Color::new(240, 80, 90),
+    Color::new(250, 200, 60),
+    Color::new(80, 190, 240),
+    Color::new(120, 220, 110),
+    Color::new(220, 120, 230),
+];
+
+/// an event from the schedule. `starts_at` is in server time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledEvent {
+    pub id: u64,
+    pub name: String,
+    pub starts_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum BoardUpdate {
+    /// a player is going to the event with this id
+    Rsvp(u64),
+    /// the server changed the upcoming event or its rsvp count
+    Event {
+        event: Option<ScheduledEvent>,
+        rsvps: u32,
+    },
+}
+
+/// counts down to the next scheduled event. players can press e to say they are going.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CountdownBoard {
+    properties: ObjectProperties,
+    event: Option<ScheduledEvent>,
+    rsvps: u32,
+    /// the event this player said they are going to
+    #[serde(skip)]
+    rsvped: Option<u64>,
+    /// rsvp that still has to be sent to the server, or picked up by it on the server side
+    #[serde(skip)]
+    pending_rsvp: Option<u64>,
+    #[serde(skip)]
+    cooldown: InteractionCooldown,
+}
+
+impl CountdownBoard {
+    pub fn new(position: Position) -> BoxedNetworkObject {
+        let dimensions = Dimension::new(WIDTH, HEIGHT);
+        let hitbox = Rect::new(
+            Position::new(0, dimensions.height as i64 - 8),
+            Position::from_dimensions(dimensions),
+        );
+        // leave room for the confetti above the board
+        let bounds = Rect::new(
+            Position::new(0, -CONFETTI_HEIGHT),
+            Position::from_dimensions(dimensions),
+        );
+
+        BoxedNetworkObject::new(CountdownBoard {
+            properties: ObjectProperties {
+                position,
+                dimensions,
+                rel_hitbox: Some(hitbox),
+                rel_bounds: bounds,
+                interactable: true,
+                override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: true,
+            },
+            event: None,
+            rsvps: 0,
+            rsvped: None,
+            pending_rsvp: None,
+            cooldown: InteractionCooldown::default(),
+        })
+    }
+
+    pub(crate) fn set_event(&mut self, event: Option<ScheduledEvent>, rsvps: u32) {
+        self.event = event;
+        self.rsvps = rsvps;
+    }
+
+    pub(crate) fn event_message(
+        event: Option<ScheduledEvent>,
+        rsvps: u32,
+    ) -> Result<Vec<u8>, postcard::Error> {
+        crate::ObjectMessage::encode::<Self>(BoardUpdate::Event { event, rsvps })
+    }
+
+    /// take the event id of the last rsvp a client sent. the server has to remember who sent it.
+    pub(crate) fn take_rsvp(&mut self) -> Option<u64> {
+        self.pending_rsvp.take()
+    }
+
+    fn draw_confetti(&self, board_pos: Position, elapsed_ms: u64, ctx: &mut RenderContext) {
+        let fall_height = CONFETTI_HEIGHT + HEIGHT as i64;
+        for i in 0..CONFETTI_PIECES {
+            let seed = self.event.as_ref().map_or(0, |event| event.id as u32) ^ i;
+            let x = phase_offset(seed, WIDTH as u64) as i64;
+            let delay = phase_offset(seed.wrapping_add(7), 1500);
+            let speed = 20 + phase_offset(seed.wrapping_add(13), 30) as i64;
+            let fallen = elapsed_ms.saturating_sub(delay) as i64 * speed / 1000;
+            if elapsed_ms < delay || fallen > fall_height {
+                continue;
+            }
+
+            // a little sway while falling
+            let sway = ((fallen / 6 + i as i64) % 4 - 2).abs();
+            let piece_pos = board_pos + Position::new(x + sway, fallen - CONFETTI_HEIGHT);
+            ctx.fb.draw_rect(
+                Rect::new(piece_pos, piece_pos + Position::new(2, 2)),
+                CONFETTI_COLORS[i as usize % CONFETTI_COLORS.len()],
+            );
+        }
+    }
+}
+
+pub(crate) fn format_countdown(remaining_ms: u64) -> String {
+    let seconds = remaining_ms.div_ceil(1000);
+    let (days, hours, minutes, seconds) = (
+        seconds / 86400,
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+    );
+    if days > 0 {
+        format!("{}d {:02}:{:02}:{:02}", days, hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    }
+}
+
+impl Renderable for CountdownBoard {
+    type LocalState = ();
+    fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
+        let screen_pos = self.properties.position - camera;
+        let board_bottom = HEIGHT as i64 - LEG_HEIGHT;
+
+        for x in [FRAME_WIDTH * 2, WIDTH as i64 - FRAME_WIDTH * 3] {
+            ctx.fb.draw_rect(
+                Rect::new(
+                    screen_pos + Position::new(x, board_bottom),
+                    screen_pos + Position::new(x + FRAME_WIDTH, HEIGHT as i64),
+                ),
+                FRAME_COLOR,
+            );
+        }
+        let board_rect = Rect::new(
+            screen_pos,
+            screen_pos + Position::new(WIDTH as i64, board_bottom),
+        );
+        ctx.fb.draw_rect(board_rect, FRAME_COLOR);
+        let face_rect = Rect::new(
+            board_rect.min + Position::new(FRAME_WIDTH, FRAME_WIDTH),
+            board_rect.max - Position::new(FRAME_WIDTH, FRAME_WIDTH),
+        );
+        ctx.fb.draw_rect(face_rect, BOARD_COLOR);
+
+        let now = ctx.server_time_ms;
+        let mut ui = UIFrame::new_stateless(Direction::TopToBottom);
+        match &self.event {
+            Some(event) => {
+                let countdown = match event.starts_at.checked_sub(now) {
+                    Some(remaining_ms) if remaining_ms > 0 => format_countdown(remaining_ms),
+                    _ => String::from("happening now!"),
+                };
+                ui.draw_frame(ctx.fb, face_rect, ctx.input, |ui| {
+                    ui.margin(MarginMode::Grow);
+                    ui.label::<font::Glean>(&event.name);
+                    ui.label::<font::Glean>(&countdown);
+                    ui.label::<font::Glean>(&format!("{} going", self.rsvps));
+                });
+            }
+            None => ui.draw_frame(ctx.fb, face_rect, ctx.input, |ui| {
+                ui.margin(MarginMode::Grow);
+                ui.label::<font::Glean>("no events planned");
+            }),
+        }
+
+        if let Some(event) = &self.event {
+            let elapsed_ms = now.saturating_sub(event.starts_at);
+            if now >= event.starts_at && elapsed_ms < CONFETTI_MS {
+                self.draw_confetti(screen_pos, elapsed_ms, ctx);
+            }
+        }
+
+        let Some(event_id) = self.event.as_ref().map(|event| event.id) else {
+            return;
+        };
+        if ctx.stream_safe && self.hitbox().unwrap().interactable(ctx.player_pos) {
+            let going = self.rsvped == Some(event_id);
+            let ready = self.cooldown.ready(ctx.time_ms, INTERACTION_COOLDOWN_MS);
+            if !going && ready && ctx.input.key_pressed(Key::Unicode('e')) {
+                self.rsvped = Some(event_id);
+                self.pending_rsvp = Some(event_id);
+                self.cooldown.start(ctx.time_ms);
+            }
+
+            let mut ui = UIFrame::new_stateless(Direction::BottomToTop);
+            let ui_rect = Rect::new(
+                Position::new(screen_pos.x - 100, i64::MIN),
+                Position::new(screen_pos.x + WIDTH as i64 + 100, screen_pos.y),
+            );
+            ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
+                ui.margin(MarginMode::Grow);
+                if self.rsvped == Some(event_id) {
+                    ui.label::<font::Glean>("you're going!");
+                } else if ready {
+                    ui.label::<font::Glean>("press e to rsvp");
+                } else {
+                    ui.add(RichLabel::new("press e to rsvp", COOLDOWN_PROMPT_COLOR));
+                }
+            });
+        }
+    }
+}
+
+impl Object for CountdownBoard {
+    fn as_sprite(&mut self) -> Sprite {
+        Sprite::Object(self)
+    }
+
+    fn properties(&self) -> &ObjectProperties {
+        &self.properties
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.properties.position = position;
+    }
+}
+
+impl NetworkObject for CountdownBoard {
+    fn server_message(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, CiboError> {
+        // the count is only updated once the server has stored the rsvp
+        if let BoardUpdate::Rsvp(event_id) = self.recv_typed(data)? {
+            if self.event.as_ref().map(|event| event.id) == Some(event_id) {
+                self.pending_rsvp = Some(event_id);
+            }
+        }
+        Ok(None)
+    }
+
+    fn interaction_cooldown_ms(&self) -> u64 {
+        INTERACTION_COOLDOWN_MS
+    }
+
+    fn client_message(&mut self, data: &[u8]) -> Result<(), CiboError> {
+        if let BoardUpdate::Event { event, rsvps } = self.recv_typed(data)? {
+            self.set_event(event, rsvps);
+        }
+        Ok(())
+    }
+
+    fn client_tick(&mut self) -> Result<Option<Vec<u8>>, CiboError> {
+        match self.pending_rsvp.take() {
+            Some(event_id) => Ok(Some(self.send_typed(&BoardUpdate::Rsvp(event_id))?)),
+            None => Ok(None),
+        }
+    }
+}
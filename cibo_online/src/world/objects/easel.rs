@@ -1,23 +1,41 @@
 use core::ops::Add;
 
-use crate::{assets, Object, ObjectProperties, RectExt, RenderContext, Renderable, Sprite, ZOrder};
-use alloc::{boxed::Box, vec, vec::Vec};
+use super::Painting;
+use crate::{
+    assets, BoxedNetworkObject, CiboError, ClientId, Elevation, NetworkObject, Object,
+    ObjectProperties, RectExt, RenderContext, Renderable, Sprite, ZOrder,
+};
+use alloc::{collections::VecDeque, string::String, vec, vec::Vec};
 use monos_gfx::{
     font,
-    input::Key,
+    input::{Key, KeyState},
     ui::{Direction, MarginMode, UIContext, UIElement, UIFrame, UIResult},
     Color, Dimension, Framebuffer, FramebufferFormat, Position, Rect,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Easel {
     properties: ObjectProperties,
+    shared: SharedCanvas,
+    #[serde(skip)]
     canvas: Option<Canvas>,
+    #[serde(skip)]
     opened: bool,
+    /// painting that was taken off the easel and still needs to be hung in the gallery
+    #[serde(skip)]
+    finished: Option<Painting>,
+    /// who sent the message the server is handling and where they stand, the painter in it
+    /// can't be trusted
+    #[serde(skip)]
+    sender: Option<(ClientId, Position)>,
+    /// everyone who painted on the canvas since it was last taken down, only they can hang it
+    #[serde(skip)]
+    painters: Vec<ClientId>,
 }
 
 impl Easel {
-    pub fn new(position: Position) -> Box<dyn Object> {
+    pub fn new(position: Position) -> BoxedNetworkObject {
         let dimensions = assets().easel.dimensions();
 
         let hitbox = Rect::new(
@@ -26,7 +44,7 @@ impl Easel {
         );
         let bounds = Rect::new(Position::zero(), Position::from_dimensions(dimensions));
 
-        Box::new(Easel {
+        BoxedNetworkObject::new(Easel {
             properties: ObjectProperties {
                 position,
                 dimensions,
@@ -34,11 +52,27 @@ impl Easel {
                 rel_bounds: bounds,
                 interactable: true,
                 override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: false,
             },
+            shared: SharedCanvas::new(CanvasSize::small()),
             opened: true,
             canvas: None,
+            finished: None,
+            sender: None,
+            painters: Vec::new(),
         })
     }
+
+    /// take the last finished painting. the artist is left empty and has to be filled in by the server.
+    pub(crate) fn take_painting(&mut self) -> Option<Painting> {
+        self.finished.take()
+    }
+
+    /// set by the server before handing it a message from `client` standing at `position`
+    pub(crate) fn set_sender(&mut self, client: ClientId, position: Position) {
+        self.sender = Some((client, position));
+    }
 }
 
 impl Renderable for Easel {
@@ -48,7 +82,7 @@ impl Renderable for Easel {
 
         ctx.fb.draw_img(&assets().easel, screen_pos);
 
-        if self.hitbox().unwrap().interactable(ctx.player_pos) {
+        if ctx.stream_safe && self.hitbox().unwrap().interactable(ctx.player_pos) {
             if ctx.input.key_pressed(Key::Unicode('e')) {
                 self.opened = !self.opened;
                 if self.opened {
@@ -66,8 +100,17 @@ impl Renderable for Easel {
                     screen_pos.y,
                 ),
             );
+            // the server only lets people who painted on it hang it
+            let can_hang = self.opened && !self.shared.blank && self.shared.painted_here;
+            if can_hang && ctx.input.key_pressed(Key::Unicode('h')) {
+                self.shared.push(CanvasOp::Finish);
+            }
+
             ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
                 ui.margin(MarginMode::Grow);
+                if can_hang {
+                    ui.label::<font::Glean>("press h to hang in the gallery");
+                }
                 ui.label::<font::Glean>("press e");
             });
         } else if self.opened {
@@ -75,10 +118,14 @@ impl Renderable for Easel {
             self.properties.override_z = None;
         }
 
+        self.shared.painter = Some(ctx.own_id);
         if self.opened {
             let canvas = self.canvas.get_or_insert_with(|| Canvas::new());
 
-            canvas.render(&mut (), camera, ctx);
+            canvas.render(&mut self.shared, camera, ctx);
+        } else if self.shared.cursor.is_some() {
+            self.shared.cursor = None;
+            self.shared.cursor_changed = true;
         }
     }
 }
@@ -97,10 +144,99 @@ impl Object for Easel {
     }
 }
 
+impl NetworkObject for Easel {
+    fn server_message(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, CiboError> {
+        let mut update: EaselUpdate = self.recv_typed(data)?;
+        let Some((sender, position)) = self.sender.take() else {
+            return Ok(None);
+        };
+        update.painter = sender;
+
+        // only someone who painted on it and is still standing at the easel can hang the painting
+        let in_reach = self
+            .hitbox()
+            .is_some_and(|hitbox| hitbox.interactable(position));
+        let mut painted = self.painters.contains(&sender);
+        update.ops.retain(|op| match op {
+            CanvasOp::Paint { .. } | CanvasOp::Erase { .. } | CanvasOp::Patch { .. } => {
+                painted = true;
+                true
+            }
+            CanvasOp::Finish => in_reach && painted,
+            CanvasOp::Resize(_) => true,
+        });
+        if painted && !self.painters.contains(&sender) {
+            self.painters.push(sender);
+        }
+
+        for op in &update.ops {
+            if matches!(op, CanvasOp::Finish) {
+                if !self.shared.blank {
+                    self.finished = Some(self.shared.painting());
+                }
+                self.painters.clear();
+            }
+            self.shared.apply(op);
+        }
+
+        // everyone else replays the strokes and shows the cursor
+        Ok(Some(self.send_typed(&update)?))
+    }
+
+    fn client_message(&mut self, data: &[u8]) -> Result<(), CiboError> {
+        let update: EaselUpdate = self.recv_typed(data)?;
+        if Some(update.painter) == self.shared.painter {
+            // our own strokes were already applied locally
+            return Ok(());
+        }
+
+        for op in &update.ops {
+            self.shared.apply(op);
+        }
+
+        self.shared.cursors.retain(|(id, _)| *id != update.painter);
+        if let Some(cursor) = update.cursor {
+            self.shared.cursors.push((update.painter, cursor));
+        }
+
+        Ok(())
+    }
+
+    fn client_tick(&mut self) -> Result<Option<Vec<u8>>, CiboError> {
+        let painter = match self.shared.painter {
+            Some(painter) => painter,
+            None => return Ok(None),
+        };
+        if self.shared.outgoing.is_empty() && !self.shared.cursor_changed {
+            return Ok(None);
+        }
+
+        self.shared.cursor_changed = false;
+        let update = EaselUpdate {
+            painter,
+            ops: core::mem::take(&mut self.shared.outgoing),
+            cursor: self.shared.cursor,
+        };
+        Ok(Some(self.send_typed(&update)?))
+    }
+}
+
 const CANVAS_FG: Color = Color::new(184, 128, 75);
-const PALETTE_TOOLS: [PaletteTool; 7] = [
+const CANVAS_BG: Color = Color::new(255, 255, 255);
+const ERASER_COLOR: Color = Color::new(240, 150, 170);
+const LAYER_BORDER: Color = Color::new(60, 40, 25);
+const DROPPER_COLOR: Color = Color::new(200, 220, 230);
+const CURSOR_COLORS: [Color; 4] = [
+    Color::new(230, 60, 60),
+    Color::new(60, 120, 230),
+    Color::new(60, 180, 80),
+    Color::new(200, 80, 200),
+];
+const PALETTE_TOOLS: [PaletteTool; 9] = [
     PaletteTool::Brush,
     PaletteTool::Smudge,
+    PaletteTool::Eraser,
+    PaletteTool::Dropper,
     PaletteTool::PaintTube(Color::new(255, 0, 0)),
     PaletteTool::PaintTube(Color::new(0, 0, 255)),
     PaletteTool::PaintTube(Color::new(255, 255, 0)),
@@ -108,17 +244,316 @@ const PALETTE_TOOLS: [PaletteTool; 7] = [
     PaletteTool::PaintTube(Color::new(0, 0, 0)),
 ];
 
+const SWATCH_COUNT: usize = 4;
+const MAX_BRUSH_SIZE: u32 = 4;
+
+/// maximum number of strokes that can be undone
+const UNDO_LIMIT: usize = 32;
+
+// ctrl+z and ctrl+y arrive as their ascii control characters
+const UNDO_KEY: Key = Key::Unicode('\u{1a}');
+const REDO_KEY: Key = Key::Unicode('\u{19}');
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Layer {
+    Background,
+    Foreground,
+}
+
+/// bytes of a layer that were changed by a single stroke as (index, before, after)
 #[derive(Debug)]
-struct Canvas {
+struct CanvasEdit {
+    layer: Layer,
+    changes: Vec<(u32, u8, u8)>,
+}
+
+/// a single change to the canvas, small enough to be sent for every stroke segment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum CanvasOp {
+    Paint {
+        layer: Layer,
+        from: Position,
+        to: Position,
+        color: [u8; 3],
+        alpha: u8,
+        size: u32,
+    },
+    Erase {
+        layer: Layer,
+        from: Position,
+        to: Position,
+        size: u32,
+    },
+    /// raw bytes written to a layer, used for undo/redo
+    Patch {
+        layer: Layer,
+        changes: Vec<(u32, u8)>,
+    },
+    /// start over with a different size. only possible while nothing has been painted yet
+    Resize(CanvasSize),
+    /// take the painting off the easel to hang it in the gallery
+    Finish,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EaselUpdate {
+    painter: ClientId,
+    ops: Vec<CanvasOp>,
+    cursor: Option<Position>,
+}
+
+/// canvas contents everyone painting on the same easel works on. the server keeps the merged result.
+#[derive(Debug, Serialize, Deserialize)]
+struct SharedCanvas {
     properties: CanvasSize,
-    canvas_data: Vec<u8>,
+    blank: bool,
+    /// increased every time the canvas is cleared, so painters know their undo history is stale
+    generation: u32,
+    /// opaque rgb layer
+    background_data: Vec<u8>,
+    /// rgba layer that is drawn on top of the background
+    foreground_data: Vec<u8>,
+
+    #[serde(skip)]
+    painter: Option<ClientId>,
+    #[serde(skip)]
+    outgoing: Vec<CanvasOp>,
+    #[serde(skip)]
+    cursor: Option<Position>,
+    #[serde(skip)]
+    cursor_changed: bool,
+    /// where the other painters currently point on the canvas
+    #[serde(skip)]
+    cursors: Vec<(ClientId, Position)>,
+    /// we painted on the canvas since it was last taken down
+    #[serde(skip)]
+    painted_here: bool,
+}
+
+impl SharedCanvas {
+    fn new(properties: CanvasSize) -> Self {
+        let mut canvas = Self {
+            properties,
+            blank: true,
+            generation: 0,
+            background_data: Vec::new(),
+            foreground_data: Vec::new(),
+
+            painter: None,
+            outgoing: Vec::new(),
+            cursor: None,
+            cursor_changed: false,
+            cursors: Vec::new(),
+            painted_here: false,
+        };
+        canvas.reset(properties);
+        canvas
+    }
+
+    fn reset(&mut self, properties: CanvasSize) {
+        let pixels = properties.size.width as usize * properties.size.height as usize;
+        let mut background_data = vec![0; pixels * 3];
+        let mut background_fb = Framebuffer::new(
+            background_data.as_mut_slice(),
+            properties.size,
+            Layer::Background.format(properties.size),
+        );
+        background_fb.draw_rect(Rect::from_dimensions(background_fb.dimensions()), CANVAS_BG);
+
+        self.properties = properties;
+        self.background_data = background_data;
+        self.foreground_data = vec![0; pixels * 4];
+        self.blank = true;
+        self.painted_here = false;
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// blend the foreground on top of the background into an rgb buffer
+    fn composite(&mut self, out: &mut Vec<u8>) {
+        let size = self.properties.size;
+        out.clear();
+        out.extend_from_slice(&self.background_data);
+        let mut composite_fb =
+            Framebuffer::new(out.as_mut_slice(), size, Layer::Background.format(size));
+        let foreground_fb = Framebuffer::new(
+            self.foreground_data.as_mut_slice(),
+            size,
+            Layer::Foreground.format(size),
+        );
+        for y in 0..size.height as i64 {
+            for x in 0..size.width as i64 {
+                let pos = Position::new(x, y);
+                let (color, alpha) = foreground_fb.get_pixel_alpha(pos);
+                if alpha > 0 {
+                    composite_fb.draw_pixel_alpha(pos, color, alpha);
+                }
+            }
+        }
+    }
+
+    fn painting(&mut self) -> Painting {
+        let mut data = Vec::new();
+        self.composite(&mut data);
+        Painting {
+            artist: String::new(),
+            width: self.properties.size.width,
+            height: self.properties.size.height,
+            data,
+        }
+    }
+
+    /// whether a stroke position is close enough to the canvas to be worth drawing
+    fn in_reach(&self, pos: Position) -> bool {
+        let size = self.properties.size;
+        (-(size.width as i64)..size.width as i64 * 2).contains(&pos.x)
+            && (-(size.height as i64)..size.height as i64 * 2).contains(&pos.y)
+    }
+
+    /// the closest point on the canvas, so a stroke never has to be walked further than across it
+    fn clamp_to_canvas(&self, pos: Position) -> Position {
+        let size = self.properties.size;
+        Position::new(
+            pos.x.clamp(0, size.width as i64 - 1),
+            pos.y.clamp(0, size.height as i64 - 1),
+        )
+    }
+
+    fn layer_data(&mut self, layer: Layer) -> &mut Vec<u8> {
+        match layer {
+            Layer::Background => &mut self.background_data,
+            Layer::Foreground => &mut self.foreground_data,
+        }
+    }
+
+    /// apply a change locally and queue it to be sent to the other painters
+    fn push(&mut self, op: CanvasOp) {
+        self.apply(&op);
+        if matches!(
+            op,
+            CanvasOp::Paint { .. } | CanvasOp::Erase { .. } | CanvasOp::Patch { .. }
+        ) {
+            self.painted_here = true;
+        }
+        self.outgoing.push(op);
+    }
+
+    fn apply(&mut self, op: &CanvasOp) {
+        let size = self.properties.size;
+        match op {
+            CanvasOp::Paint {
+                layer,
+                from,
+                to,
+                color,
+                alpha,
+                size: brush_size,
+            } => {
+                if !self.in_reach(*from) || !self.in_reach(*to) {
+                    return;
+                }
+                self.blank = false;
+                let (from, to) = (self.clamp_to_canvas(*from), self.clamp_to_canvas(*to));
+
+                // ops come from other clients, so keep them within what the ui allows
+                let brush_size = (*brush_size).min(MAX_BRUSH_SIZE);
+                let color = Color::new(color[0], color[1], color[2]);
+                let mut layer_fb =
+                    Framebuffer::new(self.layer_data(*layer), size, layer.format(size));
+                if brush_size > 1 {
+                    for pos in line_points(from, to) {
+                        layer_fb.draw_disc_alpha(&pos, (brush_size - 1) as _, color, *alpha);
+                    }
+                } else {
+                    if from != to {
+                        layer_fb.draw_line_alpha(from, to, color, *alpha);
+                    }
+                    layer_fb.draw_pixel_alpha(to, color, *alpha);
+                }
+            }
+            CanvasOp::Erase {
+                layer,
+                from,
+                to,
+                size: brush_size,
+            } => {
+                if !self.in_reach(*from) || !self.in_reach(*to) {
+                    return;
+                }
+                self.blank = false;
+                let (from, to) = (self.clamp_to_canvas(*from), self.clamp_to_canvas(*to));
+
+                let radius = (*brush_size).min(MAX_BRUSH_SIZE) as i64 - 1;
+                for pos in line_points(from, to) {
+                    for y in -radius..=radius {
+                        for x in -radius..=radius {
+                            if x * x + y * y <= radius * radius {
+                                self.erase_pixel(*layer, pos + Position::new(x, y));
+                            }
+                        }
+                    }
+                }
+            }
+            CanvasOp::Patch { layer, changes } => {
+                self.blank = false;
+                let data = self.layer_data(*layer);
+                for (i, value) in changes {
+                    if let Some(byte) = data.get_mut(*i as usize) {
+                        *byte = *value;
+                    }
+                }
+            }
+            CanvasOp::Resize(properties) => {
+                if self.blank && properties.is_valid() {
+                    self.reset(*properties);
+                }
+            }
+            CanvasOp::Finish => self.reset(self.properties),
+        }
+    }
+
+    fn erase_pixel(&mut self, layer: Layer, pos: Position) {
+        let size = self.properties.size;
+        if pos.x < 0 || pos.y < 0 || pos.x >= size.width as i64 || pos.y >= size.height as i64 {
+            return;
+        }
+
+        let index = pos.y as usize * size.width as usize + pos.x as usize;
+        match layer {
+            Layer::Background => self.background_data[index * 3..index * 3 + 3].copy_from_slice(&[
+                CANVAS_BG.r,
+                CANVAS_BG.g,
+                CANVAS_BG.b,
+            ]),
+            Layer::Foreground => self.foreground_data[index * 4 + 3] = 0,
+        }
+    }
+
+    fn set_cursor(&mut self, cursor: Option<Position>) {
+        if self.cursor != cursor {
+            self.cursor = cursor;
+            self.cursor_changed = true;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Canvas {
+    composite_data: Vec<u8>,
+    generation: u32,
+    layer: Layer,
     last_canvas_pos: Option<Position>,
 
+    stroke_snapshot: Option<(Layer, Vec<u8>)>,
+    undo_stack: VecDeque<CanvasEdit>,
+    redo_stack: Vec<CanvasEdit>,
+
     palette_data: Vec<u8>,
     last_palette_pos: Option<Position>,
 
     primary_color: (Color, u8),
     secondary_color: (Color, u8),
+    swatches: [Option<(Color, u8)>; SWATCH_COUNT],
+    brush_size: u32,
 
     ui: UIFrame,
     tool: usize,
@@ -127,7 +562,7 @@ struct Canvas {
     prev_tool_offset: i64,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 struct CanvasSize {
     size: Dimension,
     scale: u32,
@@ -138,6 +573,16 @@ impl CanvasSize {
         self.size * self.scale
     }
 
+    fn is_valid(&self) -> bool {
+        [Self::small(), Self::medium()]
+            .iter()
+            .any(|size| size.size.width == self.size.width && size.size.height == self.size.height)
+    }
+
+    fn is_small(&self) -> bool {
+        self.size.width == Self::small().size.width
+    }
+
     fn small() -> Self {
         Self {
             size: Dimension::new(32, 32),
@@ -158,12 +603,12 @@ enum PaletteTool {
     Brush,
     PaintTube(Color),
     Smudge,
+    Eraser,
+    Dropper,
 }
 
 impl Canvas {
     fn new() -> Self {
-        let properties = CanvasSize::small();
-
         let mut palette_data = vec![
             0;
             assets().palette.dimensions().height as usize
@@ -184,35 +629,23 @@ impl Canvas {
         );
         palette_fb.draw_rect(Rect::from_dimensions(palette_fb.dimensions()), CANVAS_FG);
 
-        let mut canvas_data =
-            vec![0; properties.size.width as usize * properties.size.height as usize * 3];
-        let mut canvas_fb = Framebuffer::new(
-            canvas_data.as_mut_slice(),
-            properties.size,
-            FramebufferFormat {
-                r_position: 0,
-                g_position: 1,
-                b_position: 2,
-                a_position: None,
-                bytes_per_pixel: 3,
-                stride: properties.size.width as u64,
-            },
-        );
-        canvas_fb.draw_rect(
-            Rect::from_dimensions(canvas_fb.dimensions()),
-            Color::new(255, 255, 255),
-        );
-
         Self {
-            canvas_data,
+            composite_data: Vec::new(),
+            generation: 0,
+            layer: Layer::Background,
             last_canvas_pos: None,
 
+            stroke_snapshot: None,
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+
             palette_data,
             last_palette_pos: None,
 
-            properties,
             primary_color: (Color::new(255, 0, 0), 255),
             secondary_color: (Color::new(255, 255, 255), 255),
+            swatches: [None; SWATCH_COUNT],
+            brush_size: 1,
 
             ui: UIFrame::new_stateless(Direction::RightToLeft),
             tool: 0,
@@ -222,61 +655,180 @@ impl Canvas {
         }
     }
 
-    fn handle_canvas(&mut self, ctx: &mut RenderContext) {
-        let mut canvas_fb = Framebuffer::new(
-            self.canvas_data.as_mut_slice(),
-            self.properties.size,
-            FramebufferFormat {
-                r_position: 0,
-                g_position: 1,
-                b_position: 2,
-                a_position: None,
-                bytes_per_pixel: 3,
-                stride: self.properties.size.width as u64,
-            },
-        );
+    fn handle_canvas(&mut self, shared: &mut SharedCanvas, ctx: &mut RenderContext) {
+        // the canvas was cleared or resized, old strokes can't be undone anymore
+        if self.generation != shared.generation {
+            self.generation = shared.generation;
+            self.last_canvas_pos = None;
+            self.stroke_snapshot = None;
+            self.undo_stack.clear();
+            self.redo_stack.clear();
+        }
+
+        for event in &ctx.input.keyboard {
+            if event.state != KeyState::Down {
+                continue;
+            }
+            if event.key == UNDO_KEY {
+                self.undo(shared);
+            } else if event.key == REDO_KEY {
+                self.redo(shared);
+            }
+        }
 
+        let properties = shared.properties;
         let canvas_fb_pos = Position::new(
-            ctx.fb.dimensions().width as i64 / 2 - self.properties.scaled().width as i64 / 2,
-            ctx.fb.dimensions().height as i64 / 2 - self.properties.scaled().height as i64 / 2,
+            ctx.fb.dimensions().width as i64 / 2 - properties.scaled().width as i64 / 2,
+            ctx.fb.dimensions().height as i64 / 2 - properties.scaled().height as i64 / 2,
         );
-        let scaled_mouse =
-            (ctx.input.mouse.position - canvas_fb_pos) / self.properties.scale as i64;
+        let scaled_mouse = (ctx.input.mouse.position - canvas_fb_pos) / properties.scale as i64;
 
         let canvas_fb_rect = Rect::new(
             Position::zero(),
-            Position::from_dimensions(self.properties.size) * self.properties.scale as i64,
+            Position::from_dimensions(properties.size) * properties.scale as i64,
         )
         .translate(canvas_fb_pos);
 
-        if canvas_fb_rect.contains(ctx.input.mouse.position) {
-            if ctx.input.mouse.left_button.pressed || ctx.input.mouse.right_button.pressed {
+        let hovered = canvas_fb_rect.contains(ctx.input.mouse.position);
+        shared.set_cursor(hovered.then_some(scaled_mouse));
+
+        let pressed = ctx.input.mouse.left_button.pressed || ctx.input.mouse.right_button.pressed;
+        let tool = PALETTE_TOOLS[self.tool];
+        if pressed && hovered && tool == PaletteTool::Dropper {
+            if let Some(color) = self.composite_pixel(properties.size, scaled_mouse) {
+                match ctx.input.mouse.left_button.pressed {
+                    true => self.primary_color = color,
+                    false => self.secondary_color = color,
+                }
+            }
+        } else if pressed && hovered {
+            if self.stroke_snapshot.is_none() {
+                let layer = self.layer;
+                self.stroke_snapshot = Some((layer, shared.layer_data(layer).clone()));
+            }
+
+            let last_cursor_pos = self.last_canvas_pos.unwrap_or(scaled_mouse);
+            if tool == PaletteTool::Eraser {
+                shared.push(CanvasOp::Erase {
+                    layer: self.layer,
+                    from: last_cursor_pos,
+                    to: scaled_mouse,
+                    size: self.brush_size,
+                });
+            } else {
                 let used_color = if ctx.input.mouse.left_button.pressed {
                     self.primary_color
                 } else {
                     self.secondary_color
                 };
 
-                if let Some(last_cursor_pos) = self.last_canvas_pos {
-                    canvas_fb.draw_line_alpha(
-                        last_cursor_pos,
-                        scaled_mouse,
-                        used_color.0,
-                        used_color.1,
-                    );
-                }
-
-                canvas_fb.draw_pixel_alpha(scaled_mouse, used_color.0, used_color.1);
-
-                self.last_canvas_pos = Some(scaled_mouse);
-            } else {
-                self.last_canvas_pos = None;
+                shared.push(CanvasOp::Paint {
+                    layer: self.layer,
+                    from: last_cursor_pos,
+                    to: scaled_mouse,
+                    color: [used_color.0.r, used_color.0.g, used_color.0.b],
+                    alpha: used_color.1,
+                    size: self.brush_size,
+                });
             }
+
+            self.last_canvas_pos = Some(scaled_mouse);
+        } else if !pressed {
+            self.last_canvas_pos = None;
+            self.finish_stroke(shared);
         }
 
+        shared.composite(&mut self.composite_data);
+        let size = properties.size;
+        let composite_fb = Framebuffer::new(
+            self.composite_data.as_mut_slice(),
+            size,
+            Layer::Background.format(size),
+        );
+
         //ctx.fb.draw_rect(canvas_fb_rect, Color::new(255, 255, 255));
         ctx.fb
-            .draw_fb_scaled(&canvas_fb, &canvas_fb_pos, self.properties.scale);
+            .draw_fb_scaled(&composite_fb, &canvas_fb_pos, properties.scale);
+
+        // show where the other painters are pointing
+        for (painter, cursor) in &shared.cursors {
+            let center = canvas_fb_pos
+                + *cursor * properties.scale as i64
+                + Position::new(properties.scale as i64 / 2, properties.scale as i64 / 2);
+            let color = CURSOR_COLORS[painter.as_u32() as usize % CURSOR_COLORS.len()];
+            ctx.fb.draw_rect(
+                Rect::new(center - Position::new(4, 0), center + Position::new(5, 1)),
+                color,
+            );
+            ctx.fb.draw_rect(
+                Rect::new(center - Position::new(0, 4), center + Position::new(1, 5)),
+                color,
+            );
+        }
+    }
+
+    /// color of the canvas with all layers applied
+    fn composite_pixel(&self, size: Dimension, pos: Position) -> Option<(Color, u8)> {
+        if pos.x < 0 || pos.y < 0 || pos.x >= size.width as i64 || pos.y >= size.height as i64 {
+            return None;
+        }
+
+        let index = (pos.y as usize * size.width as usize + pos.x as usize) * 3;
+        let rgb = self.composite_data.get(index..index + 3)?;
+        Some((Color::new(rgb[0], rgb[1], rgb[2]), 255))
+    }
+
+    /// store the changes of the current stroke so it can be undone
+    fn finish_stroke(&mut self, shared: &mut SharedCanvas) {
+        let (layer, before) = match self.stroke_snapshot.take() {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+
+        let changes = before
+            .iter()
+            .zip(shared.layer_data(layer).iter())
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(i, (before, after))| (i as u32, *before, *after))
+            .collect::<Vec<_>>();
+        if changes.is_empty() {
+            return;
+        }
+
+        self.undo_stack.push_back(CanvasEdit { layer, changes });
+        if self.undo_stack.len() > UNDO_LIMIT {
+            self.undo_stack.pop_front();
+        }
+        self.redo_stack.clear();
+    }
+
+    fn undo(&mut self, shared: &mut SharedCanvas) {
+        if let Some(edit) = self.undo_stack.pop_back() {
+            shared.push(CanvasOp::Patch {
+                layer: edit.layer,
+                changes: edit
+                    .changes
+                    .iter()
+                    .map(|(i, before, _)| (*i, *before))
+                    .collect(),
+            });
+            self.redo_stack.push(edit);
+        }
+    }
+
+    fn redo(&mut self, shared: &mut SharedCanvas) {
+        if let Some(edit) = self.redo_stack.pop() {
+            shared.push(CanvasOp::Patch {
+                layer: edit.layer,
+                changes: edit
+                    .changes
+                    .iter()
+                    .map(|(i, _, after)| (*i, *after))
+                    .collect(),
+            });
+            self.undo_stack.push_back(edit);
+        }
     }
 
     fn handle_palette(&mut self, ctx: &mut RenderContext) {
@@ -428,6 +980,8 @@ impl Canvas {
             PaletteTool::Smudge => {
                 Self::smudge_brush(&mut palette_fb, scaled_mouse, prev_scaled_mouse, None)
             }
+            // the eraser and dropper only work on the canvas
+            PaletteTool::Eraser | PaletteTool::Dropper => (),
         }
     }
 
@@ -509,14 +1063,14 @@ impl Canvas {
 }
 
 impl Renderable for Canvas {
-    type LocalState = ();
+    type LocalState = SharedCanvas;
     fn render(
         &mut self,
-        _state: &mut Self::LocalState,
+        shared: &mut Self::LocalState,
         _camera: Position,
         ctx: &mut RenderContext,
     ) {
-        self.handle_canvas(ctx);
+        self.handle_canvas(shared, ctx);
         self.handle_palette(ctx);
 
         self.ui.draw_frame(
@@ -563,6 +1117,40 @@ impl Renderable for Canvas {
                     }
                 }
 
+                for layer in [Layer::Foreground, Layer::Background] {
+                    if ui.add(LayerWidget::new(layer, layer == self.layer)).clicked {
+                        self.layer = layer;
+                    }
+                }
+
+                if ui.add(BrushSizeWidget::new(self.brush_size)).clicked {
+                    self.brush_size = self.brush_size % MAX_BRUSH_SIZE + 1;
+                }
+
+                // the size can only be changed on a fresh canvas
+                if shared.blank {
+                    let small = shared.properties.is_small();
+                    if ui.add(CanvasSizeWidget::new(small)).clicked {
+                        shared.push(CanvasOp::Resize(if small {
+                            CanvasSize::medium()
+                        } else {
+                            CanvasSize::small()
+                        }));
+                    }
+                }
+
+                // empty swatches store the current color, with the dropper selected filled ones are overwritten
+                for i in (0..SWATCH_COUNT).rev() {
+                    if ui.add(SwatchWidget::new(self.swatches[i])).clicked {
+                        match self.swatches[i] {
+                            Some(color) if PALETTE_TOOLS[self.tool] != PaletteTool::Dropper => {
+                                self.primary_color = color
+                            }
+                            _ => self.swatches[i] = Some(self.primary_color),
+                        }
+                    }
+                }
+
                 self.tool_offset = self.tool_offset.add(1).min(6);
                 self.prev_tool_offset = self.prev_tool_offset.add(-1).max(0);
             },
@@ -588,6 +1176,40 @@ impl UIElement for PaletteToolWidget {
             PaletteTool::Brush => &assets().paint_tube,
             PaletteTool::PaintTube(_) => &assets().paint_tube,
             PaletteTool::Smudge => &assets().spatula,
+            PaletteTool::Dropper => {
+                let dimensions = assets().paint_tube.dimensions();
+                let result = context.alloc_space(dimensions);
+                let pos = result.rect.min + Position::new(0, -self.offset);
+                let center = dimensions.width as i64 / 2;
+                context.fb.draw_rect(
+                    Rect::new(
+                        pos + Position::new(center - 1, 4),
+                        pos + Position::new(center + 1, dimensions.height as i64 - 6),
+                    ),
+                    DROPPER_COLOR,
+                );
+                context.fb.draw_rect(
+                    Rect::new(
+                        pos + Position::new(center - 3, dimensions.height as i64 - 6),
+                        pos + Position::new(center + 3, dimensions.height as i64),
+                    ),
+                    LAYER_BORDER,
+                );
+                return result;
+            }
+            PaletteTool::Eraser => {
+                let dimensions = assets().paint_tube.dimensions();
+                let result = context.alloc_space(dimensions);
+                let pos = result.rect.min + Position::new(0, -self.offset);
+                context.fb.draw_rect(
+                    Rect::new(
+                        pos + Position::new(4, dimensions.height as i64 / 2),
+                        pos + Position::new(dimensions.width as i64 - 4, dimensions.height as i64),
+                    ),
+                    ERASER_COLOR,
+                );
+                return result;
+            }
         };
 
         let result = context.alloc_space(img.dimensions());
@@ -603,8 +1225,177 @@ impl UIElement for PaletteToolWidget {
                 context.fb.draw_img(&fill, pos);
             }
             PaletteTool::Smudge => (),
+            PaletteTool::Eraser => (),
+            PaletteTool::Dropper => (),
         }
 
         result
     }
 }
+
+impl Layer {
+    fn format(&self, size: Dimension) -> FramebufferFormat {
+        let (a_position, bytes_per_pixel) = match self {
+            Layer::Background => (None, 3),
+            Layer::Foreground => (Some(3), 4),
+        };
+
+        FramebufferFormat {
+            r_position: 0,
+            g_position: 1,
+            b_position: 2,
+            a_position,
+            bytes_per_pixel,
+            stride: size.width as u64,
+        }
+    }
+}
+
+/// toolbar button to select which layer is painted on
+#[derive(Debug)]
+struct LayerWidget {
+    layer: Layer,
+    active: bool,
+}
+
+impl LayerWidget {
+    fn new(layer: Layer, active: bool) -> LayerWidget {
+        Self { layer, active }
+    }
+}
+
+impl UIElement for LayerWidget {
+    fn draw(self, context: &mut UIContext) -> UIResult {
+        let result = context.alloc_space(Dimension::new(18, 22));
+        let mut pos = result.rect.min + Position::new(0, 6);
+        if self.active {
+            pos.y -= 6;
+        }
+
+        let mut draw_square = |offset: Position, fill: Color| {
+            let min = pos + offset;
+            context
+                .fb
+                .draw_rect(Rect::new(min, min + Position::new(12, 12)), LAYER_BORDER);
+            context.fb.draw_rect(
+                Rect::new(min + Position::new(1, 1), min + Position::new(11, 11)),
+                fill,
+            );
+        };
+
+        match self.layer {
+            Layer::Background => draw_square(Position::new(0, 4), CANVAS_BG),
+            Layer::Foreground => {
+                draw_square(Position::new(0, 4), CANVAS_FG);
+                draw_square(Position::new(4, 0), CANVAS_BG);
+            }
+        }
+
+        result
+    }
+}
+
+/// toolbar button that cycles through the brush sizes
+#[derive(Debug)]
+struct BrushSizeWidget {
+    size: u32,
+}
+
+impl BrushSizeWidget {
+    fn new(size: u32) -> BrushSizeWidget {
+        Self { size }
+    }
+}
+
+impl UIElement for BrushSizeWidget {
+    fn draw(self, context: &mut UIContext) -> UIResult {
+        let result = context.alloc_space(Dimension::new(18, 22));
+        let center = result.rect.min + Position::new(8, 14);
+        let radius = self.size as i64;
+        context.fb.draw_rect(
+            Rect::new(
+                center - Position::new(radius, radius),
+                center + Position::new(radius, radius),
+            ),
+            LAYER_BORDER,
+        );
+
+        result
+    }
+}
+
+/// toolbar button that switches between the canvas sizes
+#[derive(Debug)]
+struct CanvasSizeWidget {
+    small: bool,
+}
+
+impl CanvasSizeWidget {
+    fn new(small: bool) -> CanvasSizeWidget {
+        Self { small }
+    }
+}
+
+impl UIElement for CanvasSizeWidget {
+    fn draw(self, context: &mut UIContext) -> UIResult {
+        let result = context.alloc_space(Dimension::new(18, 22));
+        let size = if self.small { 8 } else { 14 };
+        let min = result.rect.max - Position::new(size + 2, size + 2);
+        context.fb.draw_rect(
+            Rect::new(min, min + Position::new(size, size)),
+            LAYER_BORDER,
+        );
+        context.fb.draw_rect(
+            Rect::new(
+                min + Position::new(1, 1),
+                min + Position::new(size - 1, size - 1),
+            ),
+            CANVAS_BG,
+        );
+
+        result
+    }
+}
+
+/// toolbar button holding a saved color
+#[derive(Debug)]
+struct SwatchWidget {
+    color: Option<(Color, u8)>,
+}
+
+impl SwatchWidget {
+    fn new(color: Option<(Color, u8)>) -> SwatchWidget {
+        Self { color }
+    }
+}
+
+impl UIElement for SwatchWidget {
+    fn draw(self, context: &mut UIContext) -> UIResult {
+        let result = context.alloc_space(Dimension::new(14, 22));
+        let min = result.rect.min + Position::new(0, 10);
+        context
+            .fb
+            .draw_rect(Rect::new(min, min + Position::new(12, 12)), LAYER_BORDER);
+        context.fb.draw_rect(
+            Rect::new(min + Position::new(1, 1), min + Position::new(11, 11)),
+            self.color.map_or(CANVAS_FG, |(color, _)| color),
+        );
+
+        result
+    }
+}
+
+/// all points on the line between two positions. if they are too far apart to even tell the
+/// distance, only the end point
+pub(crate) fn line_points(from: Position, to: Position) -> impl Iterator<Item = Position> {
+    let (from, dx, dy) = match (to.x.checked_sub(from.x), to.y.checked_sub(from.y)) {
+        (Some(dx), Some(dy)) => (from, dx, dy),
+        _ => (to, 0, 0),
+    };
+    let steps = dx.unsigned_abs().max(dy.unsigned_abs()).max(1);
+    // wide enough that the products can't overflow, and the results lie between the two positions
+    let along = move |start: i64, delta: i64, i: u64| {
+        start + (delta as i128 * i as i128 / steps as i128) as i64
+    };
+    (0..=steps).map(move |i| Position::new(along(from.x, dx, i), along(from.y, dy, i)))
+}
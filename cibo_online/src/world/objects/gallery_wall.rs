@@ -0,0 +1,188 @@
+use crate::{
+    BoxedNetworkObject, CiboError, Elevation, NetworkObject, Object, ObjectProperties,
+    RenderContext, Renderable, Sprite,
+};
+use alloc::{format, string::String, vec::Vec};
+use monos_gfx::{
+    font,
+    input::{Key, RawKey},
+    ui::{Direction, MarginMode, UIFrame},
+    Color, Dimension, Framebuffer, FramebufferFormat, Position, Rect,
+};
+use serde::{Deserialize, Serialize};
+
+const FRAME_COLOR: Color = Color::new(120, 80, 45);
+const WALL_COLOR: Color = Color::new(230, 215, 190);
+const FRAME_WIDTH: i64 = 8;
+/// size paintings are scaled up to fit into
+const PICTURE_SIZE: u32 = 64;
+
+/// maximum number of paintings that are kept. when a new one is hung, the oldest is taken down
+pub const GALLERY_LIMIT: usize = 32;
+
+/// a finished painting from an easel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Painting {
+    pub artist: String,
+    pub width: u32,
+    pub height: u32,
+    /// rgb pixel data, row by row
+    pub data: Vec<u8>,
+}
+
+impl Painting {
+    /// whether the pixel data matches the dimensions
+    pub fn is_valid(&self) -> bool {
+        self.width > 0
+            && self.width <= PICTURE_SIZE
+            && self.height > 0
+            && self.height <= PICTURE_SIZE
+            && self.data.len() == self.width as usize * self.height as usize * 3
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GalleryWall {
+    properties: ObjectProperties,
+    paintings: Vec<Painting>,
+    #[serde(skip)]
+    page: usize,
+}
+
+impl GalleryWall {
+    pub fn new(position: Position) -> BoxedNetworkObject {
+        let dimensions = Dimension::new(
+            PICTURE_SIZE + FRAME_WIDTH as u32 * 2,
+            PICTURE_SIZE + FRAME_WIDTH as u32 * 2,
+        );
+        let hitbox = Rect::new(
+            Position::new(0, dimensions.height as i64 - 8),
+            Position::from_dimensions(dimensions),
+        );
+        let bounds = Rect::new(Position::zero(), Position::from_dimensions(dimensions));
+
+        BoxedNetworkObject::new(GalleryWall {
+            properties: ObjectProperties {
+                position,
+                dimensions,
+                rel_hitbox: Some(hitbox),
+                rel_bounds: bounds,
+                interactable: true,
+                override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: false,
+            },
+            paintings: Vec::new(),
+            page: 0,
+        })
+    }
+
+    pub(crate) fn set_paintings(&mut self, paintings: Vec<Painting>) {
+        self.paintings = paintings;
+    }
+
+    pub(crate) fn hang(&mut self, painting: Painting) {
+        self.paintings.push(painting);
+        if self.paintings.len() > GALLERY_LIMIT {
+            self.paintings.remove(0);
+        }
+    }
+}
+
+impl Renderable for GalleryWall {
+    type LocalState = ();
+    fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
+        let screen_pos = self.properties.position - camera;
+        let frame_rect = Rect::new(
+            screen_pos,
+            screen_pos + Position::from_dimensions(self.properties.dimensions),
+        );
+        ctx.fb.draw_rect(frame_rect, FRAME_COLOR);
+
+        let picture_pos = screen_pos + Position::new(FRAME_WIDTH, FRAME_WIDTH);
+        ctx.fb.draw_rect(
+            Rect::new(
+                picture_pos,
+                picture_pos + Position::new(PICTURE_SIZE as i64, PICTURE_SIZE as i64),
+            ),
+            WALL_COLOR,
+        );
+
+        // paintings are made by players, so they are left out in stream mode
+        if self.paintings.is_empty() || !ctx.stream_safe {
+            return;
+        }
+        self.page = self.page.min(self.paintings.len() - 1);
+
+        let painting = &mut self.paintings[self.page];
+        if painting.is_valid() {
+            let size = Dimension::new(painting.width, painting.height);
+            let scale = (PICTURE_SIZE / painting.width).min(PICTURE_SIZE / painting.height);
+            let painting_fb = Framebuffer::new(
+                painting.data.as_mut_slice(),
+                size,
+                FramebufferFormat {
+                    r_position: 0,
+                    g_position: 1,
+                    b_position: 2,
+                    a_position: None,
+                    bytes_per_pixel: 3,
+                    stride: size.width as u64,
+                },
+            );
+            ctx.fb.draw_fb_scaled(&painting_fb, &picture_pos, scale);
+        }
+
+        if ctx.stream_safe && self.hitbox().unwrap().interactable(ctx.player_pos) {
+            if ctx.input.key_pressed(Key::RawKey(RawKey::ArrowLeft)) {
+                self.page = self.page.checked_sub(1).unwrap_or(self.paintings.len() - 1);
+            }
+            if ctx.input.key_pressed(Key::RawKey(RawKey::ArrowRight)) {
+                self.page = (self.page + 1) % self.paintings.len();
+            }
+
+            let painting = &self.paintings[self.page];
+            let mut ui = UIFrame::new_stateless(Direction::BottomToTop);
+            let ui_rect = Rect::new(
+                Position::new(screen_pos.x - 100, i64::MIN),
+                Position::new(
+                    screen_pos.x + self.properties.dimensions.width as i64 + 100,
+                    screen_pos.y,
+                ),
+            );
+            ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
+                ui.margin(MarginMode::Grow);
+                ui.label::<font::Glean>(&format!(
+                    "< {} ({}/{}) >",
+                    painting.artist,
+                    self.page + 1,
+                    self.paintings.len()
+                ));
+            });
+        }
+    }
+}
+
+impl Object for GalleryWall {
+    fn as_sprite(&mut self) -> Sprite {
+        Sprite::Object(self)
+    }
+
+    fn properties(&self) -> &ObjectProperties {
+        &self.properties
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.properties.position = position;
+    }
+}
+
+impl NetworkObject for GalleryWall {
+    fn client_message(&mut self, data: &[u8]) -> Result<(), CiboError> {
+        let painting: Painting = self.recv_typed(data)?;
+        self.hang(painting);
+        // show the new painting right away
+        self.page = self.paintings.len() - 1;
+        Ok(())
+    }
+}
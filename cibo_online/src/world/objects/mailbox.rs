@@ -0,0 +1,132 @@
+use crate::{
+    BoxedNetworkObject, Elevation, NetworkObject, Object, ObjectProperties, RectExt, RenderContext,
+    Renderable, Sprite,
+};
+use monos_gfx::{
+    font,
+    ui::{Direction, MarginMode, UIFrame},
+    Color, Dimension, Position, Rect,
+};
+use serde::{Deserialize, Serialize};
+
+const POST_COLOR: Color = Color::new(120, 80, 45);
+const BOX_COLOR: Color = Color::new(200, 60, 60);
+const LID_COLOR: Color = Color::new(160, 40, 45);
+const SLOT_COLOR: Color = Color::new(40, 30, 30);
+const FLAG_COLOR: Color = Color::new(250, 200, 60);
+
+const WIDTH: u32 = 32;
+const HEIGHT: u32 = 40;
+const BOX_HEIGHT: i64 = 16;
+const POST_WIDTH: i64 = 4;
+
+/// players standing next to it can leave letters for others with `/mail`, they get them the next
+/// time they join
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Mailbox {
+    properties: ObjectProperties,
+}
+
+impl Mailbox {
+    pub fn new(position: Position) -> BoxedNetworkObject {
+        let dimensions = Dimension::new(WIDTH, HEIGHT);
+        let hitbox = Rect::new(
+            Position::new(0, HEIGHT as i64 - 6),
+            Position::from_dimensions(dimensions),
+        );
+        let bounds = Rect::new(Position::zero(), Position::from_dimensions(dimensions));
+
+        BoxedNetworkObject::new(Mailbox {
+            properties: ObjectProperties {
+                position,
+                dimensions,
+                rel_hitbox: Some(hitbox),
+                rel_bounds: bounds,
+                interactable: true,
+                override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: true,
+            },
+        })
+    }
+
+    /// whether a player at `position` is close enough to put a letter in
+    pub(crate) fn in_reach(&self, position: Position) -> bool {
+        self.hitbox()
+            .is_some_and(|hitbox| hitbox.interactable(position))
+    }
+}
+
+impl Renderable for Mailbox {
+    type LocalState = ();
+    fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
+        let screen_pos = self.properties.position - camera;
+        let center = WIDTH as i64 / 2;
+
+        ctx.fb.draw_rect(
+            Rect::new(
+                screen_pos + Position::new(center - POST_WIDTH / 2, BOX_HEIGHT),
+                screen_pos + Position::new(center + POST_WIDTH / 2, HEIGHT as i64),
+            ),
+            POST_COLOR,
+        );
+        ctx.fb.draw_rect(
+            Rect::new(
+                screen_pos + Position::new(4, 2),
+                screen_pos + Position::new(WIDTH as i64 - 6, BOX_HEIGHT),
+            ),
+            BOX_COLOR,
+        );
+        ctx.fb.draw_rect(
+            Rect::new(
+                screen_pos + Position::new(4, 0),
+                screen_pos + Position::new(WIDTH as i64 - 6, 3),
+            ),
+            LID_COLOR,
+        );
+        ctx.fb.draw_rect(
+            Rect::new(
+                screen_pos + Position::new(8, 6),
+                screen_pos + Position::new(WIDTH as i64 - 10, 8),
+            ),
+            SLOT_COLOR,
+        );
+        // the little flag on the side
+        ctx.fb.draw_rect(
+            Rect::new(
+                screen_pos + Position::new(WIDTH as i64 - 6, 2),
+                screen_pos + Position::new(WIDTH as i64 - 4, 12),
+            ),
+            FLAG_COLOR,
+        );
+
+        if ctx.stream_safe && self.in_reach(ctx.player_pos) {
+            let mut ui = UIFrame::new_stateless(Direction::BottomToTop);
+            let ui_rect = Rect::new(
+                Position::new(screen_pos.x - 100, i64::MIN),
+                Position::new(screen_pos.x + WIDTH as i64 + 100, screen_pos.y),
+            );
+            ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
+                ui.margin(MarginMode::Grow);
+                ui.label::<font::Glean>("/mail <name> <message>");
+                ui.label::<font::Glean>("leave a letter for someone");
+            });
+        }
+    }
+}
+
+impl Object for Mailbox {
+    fn as_sprite(&mut self) -> Sprite {
+        Sprite::Object(self)
+    }
+
+    fn properties(&self) -> &ObjectProperties {
+        &self.properties
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.properties.position = position;
+    }
+}
+
+impl NetworkObject for Mailbox {}
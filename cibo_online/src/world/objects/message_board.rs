@@ -1,5 +1,8 @@
-use crate::{assets, Object, ObjectProperties, RectExt, RenderContext, Renderable, Sprite, ZOrder};
-use alloc::boxed::Box;
+use crate::{
+    assets, BoxedNetworkObject, CiboError, Elevation, NetworkObject, Object, ObjectProperties,
+    RectExt, RenderContext, Renderable, Sprite, ZOrder,
+};
+use alloc::{string::String, vec::Vec};
 use monos_gfx::{
     font::{self, Font},
     input::Key,
@@ -7,16 +10,45 @@ use monos_gfx::{
     ui::{widgets, Direction, MarginMode, UIFrame},
     Color, Position, Rect,
 };
+use serde::{Deserialize, Serialize};
+
+/// what the board says until something else is set in the admin panel
+pub const DEFAULT_BOARD_TEXT: &str = include_str!("message_board_text.txt");
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
+enum BoardUpdate {
+    /// the server changed the text
+    Text { text: String, revision: u32 },
+    /// the newest revision this player read before, sent by the server when they join
+    Seen(u32),
+    /// a player read the board at this revision
+    Read(u32),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MessageBoard {
     properties: ObjectProperties,
+    text: String,
+    /// goes up every time the text is changed, `0` is the default text
+    revision: u32,
+    #[serde(skip, default = "message_board_ui")]
     ui: UIFrame,
+    #[serde(skip)]
     opened: bool,
+    /// the newest revision this player has read
+    #[serde(skip)]
+    seen: u32,
+    /// revision that was read and still has to be sent to the server, or picked up by it on the server side
+    #[serde(skip)]
+    pending_read: Option<u32>,
+}
+
+fn message_board_ui() -> UIFrame {
+    UIFrame::new(Direction::TopToBottom)
 }
 
 impl MessageBoard {
-    pub fn new(position: Position) -> Box<dyn Object> {
+    pub fn new(position: Position) -> BoxedNetworkObject {
         let dimensions = assets().message_board.dimensions();
 
         let hitbox = Rect::new(
@@ -29,7 +61,7 @@ impl MessageBoard {
             Position::from_dimensions(dimensions),
         );
 
-        Box::new(MessageBoard {
+        BoxedNetworkObject::new(MessageBoard {
             properties: ObjectProperties {
                 position,
                 dimensions,
@@ -37,20 +69,56 @@ impl MessageBoard {
                 rel_bounds: bounds,
                 interactable: true,
                 override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: false,
             },
-            ui: UIFrame::new(Direction::TopToBottom),
+            text: String::from(DEFAULT_BOARD_TEXT),
+            revision: 0,
+            ui: message_board_ui(),
             opened: false,
+            seen: 0,
+            pending_read: None,
         })
     }
 }
 
+impl MessageBoard {
+    /// whether the player is reading the board right now
+    pub(crate) fn is_open(&self) -> bool {
+        self.opened
+    }
+
+    /// whether the text changed since the player last read it
+    pub(crate) fn unread(&self) -> bool {
+        self.revision > self.seen
+    }
+
+    pub(crate) fn set_text(&mut self, text: String, revision: u32) {
+        self.text = text;
+        self.revision = revision;
+    }
+
+    pub(crate) fn text_message(text: String, revision: u32) -> Result<Vec<u8>, postcard::Error> {
+        crate::ObjectMessage::encode::<Self>(BoardUpdate::Text { text, revision })
+    }
+
+    pub(crate) fn seen_message(revision: u32) -> Result<Vec<u8>, postcard::Error> {
+        crate::ObjectMessage::encode::<Self>(BoardUpdate::Seen(revision))
+    }
+
+    /// take the revision a client read last. the server has to remember who sent it.
+    pub(crate) fn take_read(&mut self) -> Option<u32> {
+        self.pending_read.take()
+    }
+}
+
 impl Renderable for MessageBoard {
     type LocalState = ();
     fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
         let screen_pos = self.properties.position - camera;
         ctx.fb.draw_img(&assets().message_board, screen_pos);
 
-        if self.hitbox().unwrap().interactable(ctx.player_pos) {
+        if ctx.stream_safe && self.hitbox().unwrap().interactable(ctx.player_pos) {
             if ctx.input.key_pressed(Key::Unicode('e')) {
                 self.opened = !self.opened;
                 if self.opened {
@@ -58,6 +126,10 @@ impl Renderable for MessageBoard {
                 } else {
                     self.properties.override_z = None;
                 }
+                if self.opened && self.unread() {
+                    self.seen = self.revision;
+                    self.pending_read = Some(self.revision);
+                }
             }
 
             let mut ui = UIFrame::new_stateless(Direction::BottomToTop);
@@ -72,9 +144,26 @@ impl Renderable for MessageBoard {
                 ui.margin(MarginMode::Grow);
                 ui.label::<font::Glean>("press e");
             });
-        } else if self.opened {
-            self.opened = false;
-            self.properties.override_z = None;
+        } else {
+            if self.opened {
+                self.opened = false;
+                self.properties.override_z = None;
+            }
+
+            if ctx.stream_safe && self.unread() {
+                let mut ui = UIFrame::new_stateless(Direction::BottomToTop);
+                let ui_rect = Rect::new(
+                    Position::new(screen_pos.x, i64::MIN),
+                    Position::new(
+                        screen_pos.x + self.properties.dimensions.width as i64,
+                        screen_pos.y,
+                    ),
+                );
+                ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
+                    ui.margin(MarginMode::Grow);
+                    ui.label::<font::Glean>("!");
+                });
+            }
         }
 
         if self.opened {
@@ -106,13 +195,10 @@ impl Renderable for MessageBoard {
             self.ui.draw_frame(ctx.fb, text_rect, ctx.input, |ui| {
                 ui.margin(MarginMode::Grow);
                 ui.add(
-                    widgets::ScrollableLabel::<font::Haeberli, _>::new(
-                        include_str!("message_board_text.txt"),
-                        Origin::Top,
-                    )
-                    .wrap(TextWrap::Enabled { hyphenate: false })
-                    .scroll_y(text_rect.dimensions().height)
-                    .text_color(Color::new(224, 238, 255)),
+                    widgets::ScrollableLabel::<font::Haeberli, _>::new(&self.text, Origin::Top)
+                        .wrap(TextWrap::Enabled { hyphenate: false })
+                        .scroll_y(text_rect.dimensions().height)
+                        .text_color(Color::new(224, 238, 255)),
                 );
             })
         }
@@ -132,3 +218,28 @@ impl Object for MessageBoard {
         self.properties.position = position;
     }
 }
+
+impl NetworkObject for MessageBoard {
+    fn server_message(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, CiboError> {
+        if let BoardUpdate::Read(revision) = self.recv_typed(data)? {
+            self.pending_read = Some(revision.min(self.revision));
+        }
+        Ok(None)
+    }
+
+    fn client_message(&mut self, data: &[u8]) -> Result<(), CiboError> {
+        match self.recv_typed(data)? {
+            BoardUpdate::Text { text, revision } => self.set_text(text, revision),
+            BoardUpdate::Seen(revision) => self.seen = self.seen.max(revision),
+            BoardUpdate::Read(_) => {}
+        }
+        Ok(())
+    }
+
+    fn client_tick(&mut self) -> Result<Option<Vec<u8>>, CiboError> {
+        match self.pending_read.take() {
+            Some(revision) => Ok(Some(self.send_typed(&BoardUpdate::Read(revision))?)),
+            None => Ok(None),
+        }
+    }
+}
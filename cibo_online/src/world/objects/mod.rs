@@ -1,17 +1,160 @@
 #![allow(unused_imports)]
 
 mod message_board;
-pub use message_board::MessageBoard;
+pub use message_board::{MessageBoard, DEFAULT_BOARD_TEXT};
 
 mod easel;
+pub(crate) use easel::line_points;
 pub use easel::Easel;
 
 pub mod beach_ball;
 pub use beach_ball::BeachBall;
 
+mod gallery_wall;
+pub use gallery_wall::{GalleryWall, Painting, GALLERY_LIMIT};
+
+mod bridge;
+pub use bridge::Bridge;
+
+mod countdown_board;
+pub(crate) use countdown_board::format_countdown;
+pub use countdown_board::{CountdownBoard, ScheduledEvent};
+
+mod poll_board;
+pub use poll_board::{Poll, PollBoard, POLL_OPTION_LIMIT};
+
+mod trophy_case;
+pub use trophy_case::{Standings, TrophyCase, TROPHY_PLACES};
+
+mod spawn_point;
+pub use spawn_point::SpawnPoint;
+
+mod mailbox;
+pub use mailbox::Mailbox;
+
+mod shop;
+pub use shop::{Shop, ShopItem};
+
+mod stage;
+pub(crate) use stage::Actor;
+pub use stage::Stage;
+
+use crate::{get_network_object_id, BoxedNetworkObject, NetworkObjectId};
+use monos_gfx::Position;
+use serde::{Deserialize, Serialize};
+
+/// objects that can be placed into the world using the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlaceableObject {
+    MessageBoard,
+    Easel,
+    BeachBall,
+    GalleryWall,
+    Bridge,
+    CountdownBoard,
+    PollBoard,
+    TrophyCase,
+    SpawnPoint,
+    Mailbox,
+    Shop,
+    Stage,
+}
+
+impl PlaceableObject {
+    pub const ALL: [PlaceableObject; 12] = [
+        PlaceableObject::MessageBoard,
+        PlaceableObject::Easel,
+        PlaceableObject::BeachBall,
+        PlaceableObject::GalleryWall,
+        PlaceableObject::Bridge,
+        PlaceableObject::CountdownBoard,
+        PlaceableObject::PollBoard,
+        PlaceableObject::TrophyCase,
+        PlaceableObject::SpawnPoint,
+        PlaceableObject::Mailbox,
+        PlaceableObject::Shop,
+        PlaceableObject::Stage,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlaceableObject::MessageBoard => "message board",
+            PlaceableObject::Easel => "easel",
+            PlaceableObject::BeachBall => "beach ball",
+            PlaceableObject::GalleryWall => "gallery wall",
+            PlaceableObject::Bridge => "bridge",
+            PlaceableObject::CountdownBoard => "countdown board",
+            PlaceableObject::PollBoard => "poll board",
+            PlaceableObject::TrophyCase => "trophy case",
+            PlaceableObject::SpawnPoint => "spawn point",
+            PlaceableObject::Mailbox => "mailbox",
+            PlaceableObject::Shop => "shop",
+            PlaceableObject::Stage => "stage",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|object| object.name() == name)
+    }
+
+    /// how many objects of this kind can be in the world unless configured otherwise
+    pub const fn default_cap(&self) -> usize {
+        match self {
+            PlaceableObject::BeachBall => 500,
+            _ => 50,
+        }
+    }
+
+    pub(crate) fn network_object_id(&self) -> NetworkObjectId {
+        match self {
+            PlaceableObject::MessageBoard => get_network_object_id::<MessageBoard>(),
+            PlaceableObject::Easel => get_network_object_id::<Easel>(),
+            PlaceableObject::BeachBall => get_network_object_id::<BeachBall>(),
+            PlaceableObject::GalleryWall => get_network_object_id::<GalleryWall>(),
+            PlaceableObject::Bridge => get_network_object_id::<Bridge>(),
+            PlaceableObject::CountdownBoard => get_network_object_id::<CountdownBoard>(),
+            PlaceableObject::PollBoard => get_network_object_id::<PollBoard>(),
+            PlaceableObject::TrophyCase => get_network_object_id::<TrophyCase>(),
+            PlaceableObject::SpawnPoint => get_network_object_id::<SpawnPoint>(),
+            PlaceableObject::Mailbox => get_network_object_id::<Mailbox>(),
+            PlaceableObject::Shop => get_network_object_id::<Shop>(),
+            PlaceableObject::Stage => get_network_object_id::<Stage>(),
+        }
+        .expect("network object not registered")
+    }
+
+    pub(crate) fn create(&self, position: Position) -> BoxedNetworkObject {
+        match self {
+            PlaceableObject::MessageBoard => MessageBoard::new(position),
+            PlaceableObject::Easel => Easel::new(position),
+            PlaceableObject::BeachBall => BeachBall::new(position),
+            PlaceableObject::GalleryWall => GalleryWall::new(position),
+            PlaceableObject::Bridge => Bridge::new(position),
+            PlaceableObject::CountdownBoard => CountdownBoard::new(position),
+            PlaceableObject::PollBoard => PollBoard::new(position),
+            PlaceableObject::TrophyCase => TrophyCase::new(position),
+            PlaceableObject::SpawnPoint => SpawnPoint::new(position),
+            PlaceableObject::Mailbox => Mailbox::new(position),
+            PlaceableObject::Shop => Shop::new(position),
+            PlaceableObject::Stage => Stage::new(position),
+        }
+    }
+}
+
 pub fn setup_network_objects() {
     use super::network_object::register_objects;
     register_objects! {
-        BeachBall
+        BeachBall,
+        MessageBoard,
+        Easel,
+        GalleryWall,
+        Bridge,
+        CountdownBoard,
+        PollBoard,
+        TrophyCase,
+        SpawnPoint,
+        Mailbox,
+        Shop,
+        Stage,
     }
 }
@@ -0,0 +1,284 @@
+use crate::{
+    render::COOLDOWN_PROMPT_COLOR, widgets::RichLabel, BoxedNetworkObject, CiboError, Elevation,
+    InteractionCooldown, NetworkObject, Object, ObjectProperties, RectExt, RenderContext,
+    Renderable, Sprite, INTERACTION_COOLDOWN_MS,
+};
+use alloc::{format, string::String, vec::Vec};
+use monos_gfx::{
+    font,
+    input::Key,
+    ui::{Direction, MarginMode, UIFrame},
+    Color, Dimension, Position, Rect,
+};
+use serde::{Deserialize, Serialize};
+
+const FRAME_COLOR: Color = Color::new(120, 80, 45);
+const BOARD_COLOR: Color = Color::new(236, 226, 204);
+const BAR_BG_COLOR: Color = Color::new(206, 192, 166);
+const BAR_COLOR: Color = Color::new(120, 170, 220);
+const VOTED_BAR_COLOR: Color = Color::new(90, 190, 120);
+const FRAME_WIDTH: i64 = 4;
+const LEG_HEIGHT: i64 = 12;
+/// space for the question above the bars
+const QUESTION_HEIGHT: i64 = 20;
+const ROW_HEIGHT: i64 = 12;
+
+/// more options don't fit onto the board
+pub const POLL_OPTION_LIMIT: usize = 5;
+
+const WIDTH: u32 = 144;
+const HEIGHT: u32 =
+    (FRAME_WIDTH * 2 + QUESTION_HEIGHT + ROW_HEIGHT * POLL_OPTION_LIMIT as i64 + LEG_HEIGHT) as u32;
+
+/// a question players can vote on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Poll {
+    pub id: u64,
+    pub question: String,
+    pub options: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum PollUpdate {
+    /// a player votes for an option of the poll with this id
+    Vote { poll: u64, option: u32 },
+    /// the server changed the running poll or its results
+    Poll { poll: Option<Poll>, votes: Vec<u32> },
+}
+
+/// shows the running poll and its results. players press the number of an option to vote.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PollBoard {
+    properties: ObjectProperties,
+    poll: Option<Poll>,
+    /// votes per option
+    votes: Vec<u32>,
+    /// the poll and option this player voted for
+    #[serde(skip)]
+    voted: Option<(u64, u32)>,
+    /// vote that still has to be sent to the server, or picked up by it on the server side
+    #[serde(skip)]
+    pending_vote: Option<(u64, u32)>,
+    #[serde(skip)]
+    cooldown: InteractionCooldown,
+}
+
+impl PollBoard {
+    pub fn new(position: Position) -> BoxedNetworkObject {
+        let dimensions = Dimension::new(WIDTH, HEIGHT);
+        let hitbox = Rect::new(
+            Position::new(0, dimensions.height as i64 - 8),
+            Position::from_dimensions(dimensions),
+        );
+        let bounds = Rect::new(Position::zero(), Position::from_dimensions(dimensions));
+
+        BoxedNetworkObject::new(PollBoard {
+            properties: ObjectProperties {
+                position,
+                dimensions,
+                rel_hitbox: Some(hitbox),
+                rel_bounds: bounds,
+                interactable: true,
+                override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: true,
+            },
+            poll: None,
+            votes: Vec::new(),
+            voted: None,
+            pending_vote: None,
+            cooldown: InteractionCooldown::default(),
+        })
+    }
+
+    pub(crate) fn set_poll(&mut self, poll: Option<Poll>, votes: Vec<u32>) {
+        self.poll = poll;
+        self.votes = votes;
+    }
+
+    pub(crate) fn poll_message(
+        poll: Option<Poll>,
+        votes: Vec<u32>,
+    ) -> Result<Vec<u8>, postcard::Error> {
+        crate::ObjectMessage::encode::<Self>(PollUpdate::Poll { poll, votes })
+    }
+
+    /// take the last vote a client sent as (poll id, option). the server has to remember who sent it.
+    pub(crate) fn take_vote(&mut self) -> Option<(u64, u32)> {
+        self.pending_vote.take()
+    }
+}
+
+impl Renderable for PollBoard {
+    type LocalState = ();
+    fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
+        let screen_pos = self.properties.position - camera;
+        let board_bottom = HEIGHT as i64 - LEG_HEIGHT;
+
+        for x in [FRAME_WIDTH * 2, WIDTH as i64 - FRAME_WIDTH * 3] {
+            ctx.fb.draw_rect(
+                Rect::new(
+                    screen_pos + Position::new(x, board_bottom),
+                    screen_pos + Position::new(x + FRAME_WIDTH, HEIGHT as i64),
+                ),
+                FRAME_COLOR,
+            );
+        }
+        let board_rect = Rect::new(
+            screen_pos,
+            screen_pos + Position::new(WIDTH as i64, board_bottom),
+        );
+        ctx.fb.draw_rect(board_rect, FRAME_COLOR);
+        let face_rect = Rect::new(
+            board_rect.min + Position::new(FRAME_WIDTH, FRAME_WIDTH),
+            board_rect.max - Position::new(FRAME_WIDTH, FRAME_WIDTH),
+        );
+        ctx.fb.draw_rect(face_rect, BOARD_COLOR);
+
+        let Some(poll) = &self.poll else {
+            let mut ui = UIFrame::new_stateless(Direction::TopToBottom);
+            ui.draw_frame(ctx.fb, face_rect, ctx.input, |ui| {
+                ui.margin(MarginMode::Grow);
+                ui.label::<font::Glean>("no poll running");
+            });
+            return;
+        };
+
+        let mut ui = UIFrame::new_stateless(Direction::TopToBottom);
+        ui.draw_frame(
+            ctx.fb,
+            Rect::new(
+                face_rect.min,
+                Position::new(face_rect.max.x, face_rect.min.y + QUESTION_HEIGHT),
+            ),
+            ctx.input,
+            |ui| {
+                ui.margin(MarginMode::Grow);
+                ui.label::<font::Glean>(&poll.question);
+            },
+        );
+
+        let total = self.votes.iter().sum::<u32>().max(1);
+        let voted_option = self
+            .voted
+            .filter(|(poll_id, _)| *poll_id == poll.id)
+            .map(|(_, option)| option);
+        for (i, option) in poll.options.iter().enumerate() {
+            let votes = self.votes.get(i).copied().unwrap_or(0);
+            let row_min = Position::new(
+                face_rect.min.x + 2,
+                face_rect.min.y + QUESTION_HEIGHT + i as i64 * ROW_HEIGHT,
+            );
+            let row_rect = Rect::new(
+                row_min,
+                Position::new(face_rect.max.x - 2, row_min.y + ROW_HEIGHT - 2),
+            );
+            ctx.fb.draw_rect(row_rect, BAR_BG_COLOR);
+
+            let bar_width = row_rect.dimensions().width as i64 * votes as i64 / total as i64;
+            let bar_color = if voted_option == Some(i as u32) {
+                VOTED_BAR_COLOR
+            } else {
+                BAR_COLOR
+            };
+            ctx.fb.draw_rect(
+                Rect::new(
+                    row_min,
+                    Position::new(row_min.x + bar_width, row_rect.max.y),
+                ),
+                bar_color,
+            );
+
+            let mut ui = UIFrame::new_stateless(Direction::LeftToRight);
+            ui.draw_frame(ctx.fb, row_rect, ctx.input, |ui| {
+                ui.label::<font::Glean>(&format!("{} {} ({})", i + 1, option, votes));
+            });
+        }
+
+        if ctx.stream_safe && self.hitbox().unwrap().interactable(ctx.player_pos) {
+            let poll_id = poll.id;
+            let ready = self.cooldown.ready(ctx.time_ms, INTERACTION_COOLDOWN_MS);
+            if voted_option.is_none() && ready {
+                let option_count = poll.options.len() as u32;
+                let chosen = (0..option_count).find(|option| {
+                    let key = char::from_digit(option + 1, 10).unwrap_or('0');
+                    ctx.input.key_pressed(Key::Unicode(key))
+                });
+                if let Some(option) = chosen {
+                    self.voted = Some((poll_id, option));
+                    self.pending_vote = Some((poll_id, option));
+                    self.cooldown.start(ctx.time_ms);
+                }
+            }
+
+            let mut ui = UIFrame::new_stateless(Direction::BottomToTop);
+            let ui_rect = Rect::new(
+                Position::new(screen_pos.x - 100, i64::MIN),
+                Position::new(screen_pos.x + WIDTH as i64 + 100, screen_pos.y),
+            );
+            ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
+                ui.margin(MarginMode::Grow);
+                if self
+                    .voted
+                    .is_some_and(|(voted_poll, _)| voted_poll == poll_id)
+                {
+                    ui.label::<font::Glean>("thanks for voting!");
+                } else if ready {
+                    ui.label::<font::Glean>("press a number to vote");
+                } else {
+                    ui.add(RichLabel::new(
+                        "press a number to vote",
+                        COOLDOWN_PROMPT_COLOR,
+                    ));
+                }
+            });
+        }
+    }
+}
+
+impl Object for PollBoard {
+    fn as_sprite(&mut self) -> Sprite {
+        Sprite::Object(self)
+    }
+
+    fn properties(&self) -> &ObjectProperties {
+        &self.properties
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.properties.position = position;
+    }
+}
+
+impl NetworkObject for PollBoard {
+    fn interaction_cooldown_ms(&self) -> u64 {
+        INTERACTION_COOLDOWN_MS
+    }
+
+    fn server_message(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, CiboError> {
+        // results are only updated once the server has stored the vote
+        if let PollUpdate::Vote { poll, option } = self.recv_typed(data)? {
+            let valid = self.poll.as_ref().is_some_and(|current| {
+                current.id == poll && (option as usize) < current.options.len()
+            });
+            if valid {
+                self.pending_vote = Some((poll, option));
+            }
+        }
+        Ok(None)
+    }
+
+    fn client_message(&mut self, data: &[u8]) -> Result<(), CiboError> {
+        if let PollUpdate::Poll { poll, votes } = self.recv_typed(data)? {
+            self.set_poll(poll, votes);
+        }
+        Ok(())
+    }
+
+    fn client_tick(&mut self) -> Result<Option<Vec<u8>>, CiboError> {
+        match self.pending_vote.take() {
+            Some((poll, option)) => Ok(Some(self.send_typed(&PollUpdate::Vote { poll, option })?)),
+            None => Ok(None),
+        }
+    }
+}
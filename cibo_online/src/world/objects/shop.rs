@@ -0,0 +1,273 @@
+use crate::{
+    client::CosmeticId, render::COOLDOWN_PROMPT_COLOR, widgets::RichLabel, BoxedNetworkObject,
+    CiboError, Elevation, InteractionCooldown, NetworkObject, Object, ObjectProperties, RectExt,
+    RenderContext, Renderable, Sprite, INTERACTION_COOLDOWN_MS,
+};
+use alloc::{format, vec::Vec};
+use monos_gfx::{
+    font,
+    input::Key,
+    ui::{Direction, MarginMode, UIFrame},
+    Color, Dimension, Position, Rect,
+};
+use serde::{Deserialize, Serialize};
+
+const COUNTER_COLOR: Color = Color::new(150, 100, 60);
+const COUNTER_TOP_COLOR: Color = Color::new(190, 140, 90);
+const POLE_COLOR: Color = Color::new(120, 80, 45);
+const AWNING_COLORS: [Color; 2] = [Color::new(230, 90, 90), Color::new(250, 244, 230)];
+const AWNING_HEIGHT: i64 = 12;
+const STRIPE_WIDTH: i64 = 8;
+const POLE_WIDTH: i64 = 4;
+const COUNTER_HEIGHT: i64 = 20;
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 56;
+
+/// what can be bought with seashells at a shop
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShopItem {
+    /// worn outside of its event, on top of the unlocked ones
+    Cosmetic(CosmeticId),
+}
+
+impl ShopItem {
+    pub const ALL: [ShopItem; 3] = [
+        ShopItem::Cosmetic(CosmeticId::Sunglasses),
+        ShopItem::Cosmetic(CosmeticId::FlowerCrown),
+        ShopItem::Cosmetic(CosmeticId::SantaHat),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShopItem::Cosmetic(cosmetic) => cosmetic.name(),
+        }
+    }
+
+    /// in seashells
+    pub fn price(&self) -> u64 {
+        match self {
+            ShopItem::Cosmetic(CosmeticId::Sunglasses) => 150,
+            ShopItem::Cosmetic(CosmeticId::FlowerCrown) => 250,
+            ShopItem::Cosmetic(CosmeticId::SantaHat) => 400,
+        }
+    }
+
+    /// how purchases are stored, doesn't change when items are renamed
+    pub fn key(&self) -> &'static str {
+        match self {
+            ShopItem::Cosmetic(CosmeticId::Sunglasses) => "cosmetic:sunglasses",
+            ShopItem::Cosmetic(CosmeticId::FlowerCrown) => "cosmetic:flower_crown",
+            ShopItem::Cosmetic(CosmeticId::SantaHat) => "cosmetic:santa_hat",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|item| item.key() == key)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum ShopUpdate {
+    /// a player wants to buy an item
+    Buy(ShopItem),
+    /// the seashells and items of the player the message is sent to
+    Wallet {
+        seashells: u64,
+        owned: Vec<ShopItem>,
+    },
+}
+
+/// a stall where players spend the seashells they earned. players press the number of an item to
+/// buy it, the server checks and stores the purchase.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Shop {
+    properties: ObjectProperties,
+    /// only known once the server sent it, guests never get one
+    #[serde(skip)]
+    wallet: Option<(u64, Vec<ShopItem>)>,
+    /// waiting for the server to answer, nothing else can be bought until then
+    #[serde(skip)]
+    buying: Option<ShopItem>,
+    /// purchase that still has to be sent to the server, or picked up by it on the server side
+    #[serde(skip)]
+    pending_purchase: Option<ShopItem>,
+    #[serde(skip)]
+    cooldown: InteractionCooldown,
+}
+
+impl Shop {
+    pub fn new(position: Position) -> BoxedNetworkObject {
+        let dimensions = Dimension::new(WIDTH, HEIGHT);
+        let hitbox = Rect::new(
+            Position::new(0, dimensions.height as i64 - COUNTER_HEIGHT / 2),
+            Position::from_dimensions(dimensions),
+        );
+        let bounds = Rect::new(Position::zero(), Position::from_dimensions(dimensions));
+
+        BoxedNetworkObject::new(Shop {
+            properties: ObjectProperties {
+                position,
+                dimensions,
+                rel_hitbox: Some(hitbox),
+                rel_bounds: bounds,
+                interactable: true,
+                override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: true,
+            },
+            wallet: None,
+            buying: None,
+            pending_purchase: None,
+            cooldown: InteractionCooldown::default(),
+        })
+    }
+
+    pub(crate) fn wallet_message(
+        seashells: u64,
+        owned: Vec<ShopItem>,
+    ) -> Result<Vec<u8>, postcard::Error> {
+        crate::ObjectMessage::encode::<Self>(ShopUpdate::Wallet { seashells, owned })
+    }
+
+    /// take the last item a client wants to buy. the server has to remember who sent it.
+    pub(crate) fn take_purchase(&mut self) -> Option<ShopItem> {
+        self.pending_purchase.take()
+    }
+}
+
+impl Renderable for Shop {
+    type LocalState = ();
+    fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
+        let screen_pos = self.properties.position - camera;
+        let counter_top = HEIGHT as i64 - COUNTER_HEIGHT;
+
+        for x in [0, WIDTH as i64 - POLE_WIDTH] {
+            ctx.fb.draw_rect(
+                Rect::new(
+                    screen_pos + Position::new(x, AWNING_HEIGHT),
+                    screen_pos + Position::new(x + POLE_WIDTH, counter_top),
+                ),
+                POLE_COLOR,
+            );
+        }
+        for (i, x) in (0..WIDTH as i64).step_by(STRIPE_WIDTH as usize).enumerate() {
+            ctx.fb.draw_rect(
+                Rect::new(
+                    screen_pos + Position::new(x, 0),
+                    screen_pos + Position::new((x + STRIPE_WIDTH).min(WIDTH as i64), AWNING_HEIGHT),
+                ),
+                AWNING_COLORS[i % AWNING_COLORS.len()],
+            );
+        }
+        ctx.fb.draw_rect(
+            Rect::new(
+                screen_pos + Position::new(0, counter_top),
+                screen_pos + Position::new(WIDTH as i64, HEIGHT as i64),
+            ),
+            COUNTER_COLOR,
+        );
+        ctx.fb.draw_rect(
+            Rect::new(
+                screen_pos + Position::new(0, counter_top),
+                screen_pos + Position::new(WIDTH as i64, counter_top + 3),
+            ),
+            COUNTER_TOP_COLOR,
+        );
+
+        if !ctx.stream_safe || !self.hitbox().unwrap().interactable(ctx.player_pos) {
+            return;
+        }
+
+        let ready = self.cooldown.ready(ctx.time_ms, INTERACTION_COOLDOWN_MS);
+        if let (Some((seashells, owned)), None, true) = (&self.wallet, self.buying, ready) {
+            let chosen = ShopItem::ALL.into_iter().enumerate().find(|(i, _)| {
+                let key = char::from_digit(*i as u32 + 1, 10).unwrap_or('0');
+                ctx.input.key_pressed(Key::Unicode(key))
+            });
+            if let Some((_, item)) = chosen {
+                // the server checks again, this only saves asking it when it can't work anyway
+                if !owned.contains(&item) && *seashells >= item.price() {
+                    self.buying = Some(item);
+                    self.pending_purchase = Some(item);
+                    self.cooldown.start(ctx.time_ms);
+                }
+            }
+        }
+
+        let mut ui = UIFrame::new_stateless(Direction::BottomToTop);
+        let ui_rect = Rect::new(
+            Position::new(screen_pos.x - 100, i64::MIN),
+            Position::new(screen_pos.x + WIDTH as i64 + 100, screen_pos.y),
+        );
+        ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
+            ui.margin(MarginMode::Grow);
+            let Some((seashells, owned)) = &self.wallet else {
+                ui.label::<font::Glean>("log in to shop here");
+                return;
+            };
+            if let Some(item) = self.buying {
+                ui.label::<font::Glean>(&format!("buying the {}...", item.name()));
+            } else if ready {
+                ui.label::<font::Glean>("press a number to buy");
+            } else {
+                ui.add(RichLabel::new(
+                    "press a number to buy",
+                    COOLDOWN_PROMPT_COLOR,
+                ));
+            }
+            for (i, item) in ShopItem::ALL.iter().enumerate().rev() {
+                let line = if owned.contains(item) {
+                    format!("{} {} (owned)", i + 1, item.name())
+                } else {
+                    format!("{} {} - {} shells", i + 1, item.name(), item.price())
+                };
+                ui.label::<font::Glean>(&line);
+            }
+            ui.label::<font::Glean>(&format!("you have {} seashells", seashells));
+        });
+    }
+}
+
+impl Object for Shop {
+    fn as_sprite(&mut self) -> Sprite {
+        Sprite::Object(self)
+    }
+
+    fn properties(&self) -> &ObjectProperties {
+        &self.properties
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.properties.position = position;
+    }
+}
+
+impl NetworkObject for Shop {
+    fn server_message(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, CiboError> {
+        // the wallet is only sent back once the server binary has stored the purchase
+        if let ShopUpdate::Buy(item) = self.recv_typed(data)? {
+            self.pending_purchase = Some(item);
+        }
+        Ok(None)
+    }
+
+    fn interaction_cooldown_ms(&self) -> u64 {
+        INTERACTION_COOLDOWN_MS
+    }
+
+    fn client_message(&mut self, data: &[u8]) -> Result<(), CiboError> {
+        if let ShopUpdate::Wallet { seashells, owned } = self.recv_typed(data)? {
+            self.wallet = Some((seashells, owned));
+            self.buying = None;
+        }
+        Ok(())
+    }
+
+    fn client_tick(&mut self) -> Result<Option<Vec<u8>>, CiboError> {
+        match self.pending_purchase.take() {
+            Some(item) => Ok(Some(self.send_typed(&ShopUpdate::Buy(item))?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{
+    BoxedNetworkObject, Elevation, NetworkObject, Object, ObjectProperties, RenderContext,
+    Renderable, Sprite, ZOrder,
+};
+use micromath::F32Ext;
+use monos_gfx::{Color, Dimension, Position, Rect};
+use serde::{Deserialize, Serialize};
+
+const PAD_COLOR: Color = Color::new(176, 170, 158);
+const EDGE_COLOR: Color = Color::new(128, 122, 112);
+const EDGE_WIDTH: i64 = 2;
+
+/// players are spread across the whole pad, so it is big enough for a few of them
+const WIDTH: u32 = 96;
+const HEIGHT: u32 = 64;
+
+/// a stone pad on the ground where new players show up. with more than one, players are spread
+/// across all of them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpawnPoint {
+    properties: ObjectProperties,
+}
+
+impl SpawnPoint {
+    pub fn new(position: Position) -> BoxedNetworkObject {
+        let dimensions = Dimension::new(WIDTH, HEIGHT);
+        let bounds = Rect::new(Position::zero(), Position::from_dimensions(dimensions));
+
+        BoxedNetworkObject::new(SpawnPoint {
+            properties: ObjectProperties {
+                position,
+                dimensions,
+                rel_hitbox: None,
+                rel_bounds: bounds,
+                interactable: false,
+                override_z: Some(ZOrder::level_floor(Elevation::Ground)),
+                elevation: Elevation::Ground,
+                stream_safe: true,
+            },
+        })
+    }
+}
+
+impl Renderable for SpawnPoint {
+    type LocalState = ();
+    fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
+        let center =
+            self.properties.position - camera + Position::new(WIDTH as i64 / 2, HEIGHT as i64 / 2);
+        let (radius_x, radius_y) = (WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0);
+
+        // an ellipse, drawn row by row with a darker rim
+        for y in 0..HEIGHT as i64 {
+            let dy = (y as f32 + 0.5 - radius_y) / radius_y;
+            let half_width = (radius_x * (1.0 - dy * dy).sqrt()) as i64;
+            if half_width <= 0 {
+                continue;
+            }
+
+            let row_y = center.y - HEIGHT as i64 / 2 + y;
+            let rim = y < EDGE_WIDTH || y >= HEIGHT as i64 - EDGE_WIDTH;
+            ctx.fb.draw_rect(
+                Rect::new(
+                    Position::new(center.x - half_width, row_y),
+                    Position::new(center.x + half_width, row_y + 1),
+                ),
+                if rim { EDGE_COLOR } else { PAD_COLOR },
+            );
+            if !rim {
+                for x in [center.x - half_width, center.x + half_width - EDGE_WIDTH] {
+                    ctx.fb.draw_rect(
+                        Rect::new(
+                            Position::new(x, row_y),
+                            Position::new(x + EDGE_WIDTH, row_y + 1),
+                        ),
+                        EDGE_COLOR,
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Object for SpawnPoint {
+    fn as_sprite(&mut self) -> Sprite {
+        Sprite::Object(self)
+    }
+
+    fn properties(&self) -> &ObjectProperties {
+        &self.properties
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.properties.position = position;
+    }
+}
+
+impl NetworkObject for SpawnPoint {}
@@ -0,0 +1,242 @@
+use crate::{
+    assets, client::MoveDirection, widgets::RichLabel, BoxedNetworkObject, CiboError, Elevation,
+    NetworkObject, Object, ObjectProperties, RenderContext, Renderable, Sprite,
+};
+use alloc::{string::String, vec::Vec};
+use monos_gfx::{
+    font,
+    ui::{Direction, MarginMode, UIFrame},
+    Color, Dimension, Position, Rect,
+};
+use serde::{Deserialize, Serialize};
+
+const CURTAIN_COLORS: [Color; 2] = [Color::new(150, 30, 45), Color::new(120, 20, 35)];
+const BACKDROP_COLOR: Color = Color::new(40, 30, 45);
+const FLOOR_COLOR: Color = Color::new(168, 118, 72);
+const FLOOR_GAP_COLOR: Color = Color::new(140, 96, 58);
+const EDGE_COLOR: Color = Color::new(98, 62, 34);
+const LINE_COLOR: Color = Color::new(250, 244, 230);
+
+const WIDTH: u32 = 192;
+const HEIGHT: u32 = 112;
+const CURTAIN_WIDTH: i64 = 16;
+const FOLD_WIDTH: i64 = 4;
+/// the part at the back that actors stand in front of
+const BACKDROP_HEIGHT: i64 = 48;
+const EDGE_HEIGHT: i64 = 8;
+const PLANK_HEIGHT: i64 = 8;
+
+/// someone on the stage during a cutscene. positions are relative to the stage, like in the script
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Actor {
+    pub name: String,
+    /// walking from `from` to `to` between the two server times, standing at `to` afterwards
+    pub from: Position,
+    pub to: Position,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// what they are saying and until when, in server time
+    pub line: Option<(String, u64)>,
+}
+
+impl Actor {
+    fn position(&self, now: u64) -> Position {
+        if now >= self.end_ms || self.end_ms <= self.start_ms {
+            return self.to;
+        }
+        let done = now.saturating_sub(self.start_ms) as i64;
+        let length = (self.end_ms - self.start_ms) as i64;
+        self.from
+            + Position::new(
+                (self.to.x - self.from.x) * done / length,
+                (self.to.y - self.from.y) * done / length,
+            )
+    }
+
+    fn walking(&self, now: u64) -> Option<MoveDirection> {
+        if now < self.start_ms || now >= self.end_ms || self.from == self.to {
+            return None;
+        }
+        let distance = self.to - self.from;
+        Some(if distance.x.abs() >= distance.y.abs() {
+            if distance.x < 0 {
+                MoveDirection::Left
+            } else {
+                MoveDirection::Right
+            }
+        } else if distance.y < 0 {
+            MoveDirection::Up
+        } else {
+            MoveDirection::Down
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum StageUpdate {
+    /// everyone on the stage, sent whenever the cutscene changes something
+    Actors(Vec<Actor>),
+}
+
+/// a stage with curtains for shows. admins play cutscenes on it from the admin panel, the actors
+/// in them walk around on it and talk.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Stage {
+    properties: ObjectProperties,
+    actors: Vec<Actor>,
+}
+
+impl Stage {
+    pub fn new(position: Position) -> BoxedNetworkObject {
+        let dimensions = Dimension::new(WIDTH, HEIGHT);
+        // players stand in front of it, not on it
+        let hitbox = Rect::new(
+            Position::new(0, BACKDROP_HEIGHT),
+            Position::from_dimensions(dimensions),
+        );
+        let bounds = Rect::new(Position::zero(), Position::from_dimensions(dimensions));
+
+        BoxedNetworkObject::new(Stage {
+            properties: ObjectProperties {
+                position,
+                dimensions,
+                rel_hitbox: Some(hitbox),
+                rel_bounds: bounds,
+                interactable: false,
+                override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: true,
+            },
+            actors: Vec::new(),
+        })
+    }
+
+    pub(crate) fn actors_mut(&mut self) -> &mut Vec<Actor> {
+        &mut self.actors
+    }
+
+    pub(crate) fn actors_message(&self) -> Result<Vec<u8>, postcard::Error> {
+        self.send_typed(&StageUpdate::Actors(self.actors.clone()))
+    }
+
+    fn draw_actor(actor: &Actor, screen_pos: Position, ctx: &mut RenderContext) {
+        let now = ctx.server_time_ms;
+        let image = match actor.walking(now) {
+            Some(direction) => assets().cibo.get_walk_image(direction, ctx.anim_frame()),
+            None => assets().cibo.get_idle_image(MoveDirection::Down, false),
+        };
+        ctx.fb.draw_img(image, screen_pos + actor.position(now));
+    }
+
+    /// the name above an actor and what they are saying
+    fn draw_actor_label(actor: &Actor, screen_pos: Position, ctx: &mut RenderContext) {
+        let now = ctx.server_time_ms;
+        let position = screen_pos + actor.position(now);
+        let line = actor
+            .line
+            .as_ref()
+            .filter(|(_, until)| now < *until)
+            .map(|(line, _)| line);
+        let mut ui = UIFrame::new_stateless(Direction::BottomToTop);
+        let ui_rect = Rect::new(
+            Position::new(position.x - 40, i64::MIN),
+            Position::new(position.x + 32 + 40, position.y),
+        );
+        ui.draw_frame(ctx.fb, ui_rect, ctx.input, |ui| {
+            ui.margin(MarginMode::Grow);
+            ui.label::<font::Glean>(&actor.name);
+            if let Some(line) = line {
+                ui.add(RichLabel::new(line, LINE_COLOR));
+            }
+        });
+    }
+}
+
+impl Renderable for Stage {
+    type LocalState = ();
+    fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
+        let screen_pos = self.properties.position - camera;
+        let floor_bottom = HEIGHT as i64 - EDGE_HEIGHT;
+
+        ctx.fb.draw_rect(
+            Rect::new(
+                screen_pos,
+                screen_pos + Position::new(WIDTH as i64, BACKDROP_HEIGHT),
+            ),
+            BACKDROP_COLOR,
+        );
+        ctx.fb.draw_rect(
+            Rect::new(
+                screen_pos + Position::new(0, BACKDROP_HEIGHT),
+                screen_pos + Position::new(WIDTH as i64, floor_bottom),
+            ),
+            FLOOR_GAP_COLOR,
+        );
+        for y in (BACKDROP_HEIGHT..floor_bottom).step_by(PLANK_HEIGHT as usize) {
+            ctx.fb.draw_rect(
+                Rect::new(
+                    screen_pos + Position::new(0, y),
+                    screen_pos
+                        + Position::new(WIDTH as i64, (y + PLANK_HEIGHT - 1).min(floor_bottom)),
+                ),
+                FLOOR_COLOR,
+            );
+        }
+        ctx.fb.draw_rect(
+            Rect::new(
+                screen_pos + Position::new(0, floor_bottom),
+                screen_pos + Position::new(WIDTH as i64, HEIGHT as i64),
+            ),
+            EDGE_COLOR,
+        );
+
+        // the actors stand between the backdrop and the curtains
+        let mut actors = self.actors.iter().collect::<Vec<_>>();
+        actors.sort_by_key(|actor| actor.position(ctx.server_time_ms).y);
+        for actor in &actors {
+            Self::draw_actor(actor, screen_pos, ctx);
+        }
+
+        for x in [0, WIDTH as i64 - CURTAIN_WIDTH] {
+            for (i, fold) in (x..x + CURTAIN_WIDTH)
+                .step_by(FOLD_WIDTH as usize)
+                .enumerate()
+            {
+                ctx.fb.draw_rect(
+                    Rect::new(
+                        screen_pos + Position::new(fold, 0),
+                        screen_pos + Position::new(fold + FOLD_WIDTH, floor_bottom),
+                    ),
+                    CURTAIN_COLORS[i % CURTAIN_COLORS.len()],
+                );
+            }
+        }
+
+        // names and lines go on top of the curtains, so they can always be read
+        for actor in &actors {
+            Self::draw_actor_label(actor, screen_pos, ctx);
+        }
+    }
+}
+
+impl Object for Stage {
+    fn as_sprite(&mut self) -> Sprite {
+        Sprite::Object(self)
+    }
+
+    fn properties(&self) -> &ObjectProperties {
+        &self.properties
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.properties.position = position;
+    }
+}
+
+impl NetworkObject for Stage {
+    fn client_message(&mut self, data: &[u8]) -> Result<(), CiboError> {
+        let StageUpdate::Actors(actors) = self.recv_typed(data)?;
+        self.actors = actors;
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    BoxedNetworkObject, CiboError, Elevation, MinigameKind, NetworkObject, Object,
+    ObjectProperties, RenderContext, Renderable, Sprite,
+};
+use alloc::{format, string::String, vec::Vec};
+use monos_gfx::{
+    font,
+    ui::{Direction, MarginMode, UIFrame},
+    Color, Dimension, Position, Rect,
+};
+use serde::{Deserialize, Serialize};
+
+const FRAME_COLOR: Color = Color::new(98, 62, 34);
+const GLASS_COLOR: Color = Color::new(200, 225, 235);
+const SHELF_COLOR: Color = Color::new(150, 104, 62);
+const MEDAL_COLORS: [Color; 3] = [
+    Color::new(235, 190, 60),
+    Color::new(190, 195, 205),
+    Color::new(190, 120, 70),
+];
+const FRAME_WIDTH: i64 = 4;
+const TITLE_HEIGHT: i64 = 12;
+const ROW_HEIGHT: i64 = 10;
+const MEDAL_SIZE: i64 = 5;
+
+/// how many places of every leaderboard are shown
+pub const TROPHY_PLACES: usize = 3;
+
+const COLUMN_WIDTH: i64 = 88;
+const HEIGHT: u32 = (FRAME_WIDTH * 2 + TITLE_HEIGHT + ROW_HEIGHT * TROPHY_PLACES as i64 + 4) as u32;
+
+/// the best players of a minigame, highest score first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standings {
+    pub game: MinigameKind,
+    pub top: Vec<(String, u32)>,
+}
+
+/// shows the top players of every minigame leaderboard.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrophyCase {
+    properties: ObjectProperties,
+    standings: Vec<Standings>,
+}
+
+impl TrophyCase {
+    pub fn new(position: Position) -> BoxedNetworkObject {
+        let dimensions = Dimension::new(
+            (COLUMN_WIDTH * MinigameKind::ALL.len() as i64 + FRAME_WIDTH * 2) as u32,
+            HEIGHT,
+        );
+        let hitbox = Rect::new(
+            Position::new(0, dimensions.height as i64 - 8),
+            Position::from_dimensions(dimensions),
+        );
+        let bounds = Rect::new(Position::zero(), Position::from_dimensions(dimensions));
+
+        BoxedNetworkObject::new(TrophyCase {
+            properties: ObjectProperties {
+                position,
+                dimensions,
+                rel_hitbox: Some(hitbox),
+                rel_bounds: bounds,
+                interactable: false,
+                override_z: None,
+                elevation: Elevation::Ground,
+                stream_safe: true,
+            },
+            standings: Vec::new(),
+        })
+    }
+
+    pub(crate) fn set_standings(&mut self, standings: Vec<Standings>) {
+        self.standings = standings;
+    }
+
+    pub(crate) fn standings_message(standings: &[Standings]) -> Result<Vec<u8>, postcard::Error> {
+        crate::ObjectMessage::encode::<Self>(standings)
+    }
+}
+
+impl Renderable for TrophyCase {
+    type LocalState = ();
+    fn render(&mut self, _state: &mut Self::LocalState, camera: Position, ctx: &mut RenderContext) {
+        let screen_pos = self.properties.position - camera;
+        let case_rect = Rect::new(
+            screen_pos,
+            screen_pos + Position::from_dimensions(self.properties.dimensions),
+        );
+        ctx.fb.draw_rect(case_rect, FRAME_COLOR);
+        ctx.fb.draw_rect(
+            Rect::new(
+                case_rect.min + Position::new(FRAME_WIDTH, FRAME_WIDTH),
+                case_rect.max - Position::new(FRAME_WIDTH, FRAME_WIDTH),
+            ),
+            GLASS_COLOR,
+        );
+
+        for (column, game) in MinigameKind::ALL.iter().enumerate() {
+            let column_min =
+                screen_pos + Position::new(FRAME_WIDTH + column as i64 * COLUMN_WIDTH, FRAME_WIDTH);
+            let top = self
+                .standings
+                .iter()
+                .find(|standings| standings.game == *game)
+                .map_or(&[][..], |standings| standings.top.as_slice());
+
+            let mut ui = UIFrame::new_stateless(Direction::TopToBottom);
+            ui.draw_frame(
+                ctx.fb,
+                Rect::new(
+                    column_min,
+                    column_min + Position::new(COLUMN_WIDTH, TITLE_HEIGHT),
+                ),
+                ctx.input,
+                |ui| {
+                    ui.margin(MarginMode::Grow);
+                    ui.label::<font::Glean>(game.name());
+                },
+            );
+
+            for place in 0..TROPHY_PLACES {
+                let row_min =
+                    column_min + Position::new(2, TITLE_HEIGHT + place as i64 * ROW_HEIGHT);
+                let shelf_y = row_min.y + ROW_HEIGHT - 1;
+                ctx.fb.draw_rect(
+                    Rect::new(
+                        Position::new(row_min.x, shelf_y),
+                        Position::new(row_min.x + COLUMN_WIDTH - 4, shelf_y + 1),
+                    ),
+                    SHELF_COLOR,
+                );
+
+                let Some((name, score)) = top.get(place) else {
+                    continue;
+                };
+                let medal_min = row_min + Position::new(0, ROW_HEIGHT - 1 - MEDAL_SIZE);
+                ctx.fb.draw_rect(
+                    Rect::new(medal_min, medal_min + Position::new(MEDAL_SIZE, MEDAL_SIZE)),
+                    MEDAL_COLORS[place],
+                );
+
+                let mut ui = UIFrame::new_stateless(Direction::LeftToRight);
+                ui.draw_frame(
+                    ctx.fb,
+                    Rect::new(
+                        row_min + Position::new(MEDAL_SIZE + 2, 0),
+                        row_min + Position::new(COLUMN_WIDTH - 4, ROW_HEIGHT),
+                    ),
+                    ctx.input,
+                    |ui| {
+                        ui.label::<font::Glean>(&format!("{} ({})", name, score));
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl Object for TrophyCase {
+    fn as_sprite(&mut self) -> Sprite {
+        Sprite::Object(self)
+    }
+
+    fn properties(&self) -> &ObjectProperties {
+        &self.properties
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.properties.position = position;
+    }
+}
+
+impl NetworkObject for TrophyCase {
+    fn client_message(&mut self, data: &[u8]) -> Result<(), CiboError> {
+        let standings: Vec<Standings> = self.recv_typed(data)?;
+        self.set_standings(standings);
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+//! tunables for how objects move. admins can change them during events, e.g. less friction for
+//! icy ground in winter. they are part of the world state, so clients predict with the same values
+//! as the server.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Physics {
+    /// how much speed balls on the ground lose every tick, lower is more slippery
+    pub friction: f32,
+    /// the fastest objects can move after a collision, per axis
+    pub max_velocity: f32,
+    /// how hard players kick balls on top of the bounce itself
+    pub bounce_force: f32,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Physics {
+            friction: 0.05,
+            max_velocity: 5.0,
+            bounce_force: 1.2,
+        }
+    }
+}
+
+impl Physics {
+    pub fn get(&self, param: PhysicsParam) -> f32 {
+        match param {
+            PhysicsParam::Friction => self.friction,
+            PhysicsParam::MaxVelocity => self.max_velocity,
+            PhysicsParam::BounceForce => self.bounce_force,
+        }
+    }
+
+    /// values outside of the range of the parameter are clamped
+    pub fn set(&mut self, param: PhysicsParam, value: f32) {
+        let (min, max) = param.range();
+        let value = if value.is_nan() {
+            param.default_value()
+        } else {
+            value.clamp(min, max)
+        };
+        match param {
+            PhysicsParam::Friction => self.friction = value,
+            PhysicsParam::MaxVelocity => self.max_velocity = value,
+            PhysicsParam::BounceForce => self.bounce_force = value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PhysicsParam {
+    Friction,
+    MaxVelocity,
+    BounceForce,
+}
+
+impl PhysicsParam {
+    pub const ALL: [PhysicsParam; 3] = [
+        PhysicsParam::Friction,
+        PhysicsParam::MaxVelocity,
+        PhysicsParam::BounceForce,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PhysicsParam::Friction => "friction",
+            PhysicsParam::MaxVelocity => "max-velocity",
+            PhysicsParam::BounceForce => "bounce-force",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|param| param.name() == name)
+    }
+
+    /// the lowest and highest value that keeps the simulation stable
+    pub fn range(&self) -> (f32, f32) {
+        match self {
+            PhysicsParam::Friction => (0.0, 0.5),
+            PhysicsParam::MaxVelocity => (1.0, 10.0),
+            PhysicsParam::BounceForce => (0.0, 5.0),
+        }
+    }
+
+    pub fn default_value(&self) -> f32 {
+        Physics::default().get(*self)
+    }
+}
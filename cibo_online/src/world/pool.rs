@@ -0,0 +1,59 @@
+//! reuses the objects and ids of despawned network objects, so mass spawn events don't churn through
+//! allocations and ids. only used by the server.
+
+use super::{BoxedNetworkObject, NetworkObjectId, ObjectId};
+use alloc::{collections::VecDeque, vec::Vec};
+use hashbrown::HashMap;
+use monos_gfx::Position;
+use rustc_hash::FxBuildHasher;
+
+/// ids are only handed out again after this long, so late messages for the old object are dropped
+/// instead of reaching the new one
+const ID_REUSE_DELAY_MS: u64 = 10_000;
+/// objects kept around per type, the rest is dropped
+const MAX_POOLED: usize = 256;
+
+#[derive(Debug, Default)]
+pub(crate) struct ObjectPool {
+    /// ids of removed objects and the time they were removed at, oldest first
+    free_ids: VecDeque<(ObjectId, u64)>,
+    free_objects: HashMap<NetworkObjectId, Vec<BoxedNetworkObject>, FxBuildHasher>,
+}
+
+impl ObjectPool {
+    /// a recycled id if one has been free for long enough, a new one otherwise
+    pub fn next_id(&mut self, time_ms: u64) -> ObjectId {
+        match self.free_ids.front() {
+            Some((_, freed_at)) if time_ms >= freed_at + ID_REUSE_DELAY_MS => {
+                self.free_ids.pop_front().unwrap().0
+            }
+            _ => ObjectId::new(),
+        }
+    }
+
+    /// a pooled object of the given type, already reset to `position`
+    pub fn take(
+        &mut self,
+        kind: NetworkObjectId,
+        position: Position,
+    ) -> Option<BoxedNetworkObject> {
+        let mut object = self.free_objects.get_mut(&kind)?.pop()?;
+        // the object agreed to be reset when it was released
+        object.reset(position);
+        Some(object)
+    }
+
+    /// hand back the id and object of a removed object. objects that can't be reset are dropped
+    pub fn release(&mut self, id: ObjectId, mut object: BoxedNetworkObject, time_ms: u64) {
+        self.free_ids.push_back((id, time_ms));
+
+        let position = object.properties().position;
+        if !object.reset(position) {
+            return;
+        }
+        let objects = self.free_objects.entry(object.id()).or_default();
+        if objects.len() < MAX_POOLED {
+            objects.push(object);
+        }
+    }
+}
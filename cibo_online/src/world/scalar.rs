@@ -0,0 +1,298 @@
+//! number type used for object physics.
+//! floats can round differently between the wasm client and the server, so with the `fixed_physics` feature
+//! they get swapped out for fixed point numbers that simulate exactly the same everywhere.
+
+use core::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(feature = "fixed_physics"))]
+pub type Scalar = f32;
+#[cfg(feature = "fixed_physics")]
+pub type Scalar = Fixed;
+
+pub fn vec_from_f32(v: (f32, f32)) -> (Scalar, Scalar) {
+    (Scalar::from_f32(v.0), Scalar::from_f32(v.1))
+}
+
+pub fn vec_to_f32(v: (Scalar, Scalar)) -> (f32, f32) {
+    (v.0.to_f32(), v.1.to_f32())
+}
+
+pub trait ScalarExt:
+    Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    /// smallest difference that still counts as one
+    const EPSILON: Self;
+
+    fn from_f32(value: f32) -> Self;
+    fn to_f32(self) -> f32;
+    fn from_int(value: i64) -> Self;
+    /// rounds towards zero, like `as i64` does for floats
+    fn to_int(self) -> i64;
+
+    fn sqrt(self) -> Self;
+    fn powf(self, exp: Self) -> Self;
+    fn abs(self) -> Self;
+    fn signum(self) -> Self;
+}
+
+impl ScalarExt for f32 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+    const EPSILON: Self = f32::EPSILON;
+
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+    fn to_f32(self) -> f32 {
+        self
+    }
+    fn from_int(value: i64) -> Self {
+        value as f32
+    }
+    fn to_int(self) -> i64 {
+        self as i64
+    }
+
+    fn sqrt(self) -> Self {
+        micromath::F32Ext::sqrt(self)
+    }
+    fn powf(self, exp: Self) -> Self {
+        micromath::F32Ext::powf(self, exp)
+    }
+    fn abs(self) -> Self {
+        micromath::F32Ext::abs(self)
+    }
+    fn signum(self) -> Self {
+        micromath::F32Ext::signum(self)
+    }
+}
+
+const FRACTION_BITS: u32 = 16;
+const ONE_RAW: i64 = 1 << FRACTION_BITS;
+
+/// 2^(2^-i) for i in 1..=16
+const EXP2_FRACTIONS: [i64; 16] = [
+    92682, 77936, 71468, 68438, 66971, 66250, 65892, 65714, 65625, 65580, 65558, 65547, 65542,
+    65539, 65537, 65537,
+];
+
+/// fixed point number with 16 fractional bits. only uses integer math, so results are the same on every platform
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    pub const fn from_raw(raw: i64) -> Self {
+        Fixed(raw)
+    }
+
+    pub const fn raw(self) -> i64 {
+        self.0
+    }
+
+    fn log2(self) -> Fixed {
+        if self.0 <= 0 {
+            return Fixed(i64::MIN);
+        }
+
+        // integer part from the highest set bit, then normalize into [1, 2)
+        let exponent = 63 - self.0.leading_zeros() as i64 - FRACTION_BITS as i64;
+        let mut y = if exponent >= 0 {
+            self.0 >> exponent
+        } else {
+            self.0 << -exponent
+        };
+
+        // each squaring of y reveals one more fractional bit of the result
+        let mut result = exponent << FRACTION_BITS;
+        for bit in (0..FRACTION_BITS).rev() {
+            y = (y * y) >> FRACTION_BITS;
+            if y >= 2 * ONE_RAW {
+                y >>= 1;
+                result += 1 << bit;
+            }
+        }
+        Fixed(result)
+    }
+
+    fn exp2(self) -> Fixed {
+        let integer = self.0 >> FRACTION_BITS;
+        let fraction = self.0 & (ONE_RAW - 1);
+
+        let mut result = ONE_RAW;
+        for (i, factor) in EXP2_FRACTIONS.iter().enumerate() {
+            if fraction & (1 << (FRACTION_BITS as usize - 1 - i)) != 0 {
+                result = (result * factor) >> FRACTION_BITS;
+            }
+        }
+
+        match integer {
+            i if i >= 0 && i < 46 => Fixed(result << i),
+            i if i >= 0 => Fixed(i64::MAX),
+            i if i > -63 => Fixed(result >> -i),
+            _ => Fixed(0),
+        }
+    }
+}
+
+impl ScalarExt for Fixed {
+    const ZERO: Self = Fixed(0);
+    const ONE: Self = Fixed(ONE_RAW);
+    const EPSILON: Self = Fixed(1);
+
+    fn from_f32(value: f32) -> Self {
+        Fixed((value * ONE_RAW as f32) as i64)
+    }
+    fn to_f32(self) -> f32 {
+        self.0 as f32 / ONE_RAW as f32
+    }
+    fn from_int(value: i64) -> Self {
+        Fixed(value << FRACTION_BITS)
+    }
+    fn to_int(self) -> i64 {
+        self.0 / ONE_RAW
+    }
+
+    fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Fixed(0);
+        }
+
+        // newtons method on the raw value, scaled up so the result keeps its fractional bits
+        let n = (self.0 as u128) << FRACTION_BITS;
+        let mut x = 1u128 << ((128 - n.leading_zeros()) / 2 + 1);
+        loop {
+            let y = (x + n / x) / 2;
+            if y >= x {
+                return Fixed(x as i64);
+            }
+            x = y;
+        }
+    }
+
+    fn powf(self, exp: Self) -> Self {
+        if self.0 <= 0 {
+            return Fixed(0);
+        }
+        (exp * self.log2()).exp2()
+    }
+
+    fn abs(self) -> Self {
+        Fixed(self.0.abs())
+    }
+
+    fn signum(self) -> Self {
+        Fixed(self.0.signum() * ONE_RAW)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, rhs: Fixed) -> Fixed {
+        let result = (self.0 as i128 * rhs.0 as i128) >> FRACTION_BITS;
+        Fixed(result.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Fixed;
+    fn div(self, rhs: Fixed) -> Fixed {
+        if rhs.0 == 0 {
+            return if self.0 < 0 {
+                Fixed(i64::MIN)
+            } else {
+                Fixed(i64::MAX)
+            };
+        }
+        let result = ((self.0 as i128) << FRACTION_BITS) / rhs.0 as i128;
+        Fixed(result.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed {
+        Fixed(self.0.saturating_neg())
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Fixed) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, rhs: Fixed) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, rhs: Fixed) {
+        *self = *self * rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn close(a: Fixed, b: f32, tolerance: f32) -> bool {
+        let a = a.to_f32();
+        (a - b).abs() <= tolerance * b.abs().max(1.0)
+    }
+
+    proptest! {
+        #[test]
+        fn arithmetic_matches_floats(a in -1000.0f32..1000.0, b in -1000.0f32..1000.0) {
+            let (fa, fb) = (Fixed::from_f32(a), Fixed::from_f32(b));
+            prop_assert!(close(fa + fb, a + b, 0.001));
+            prop_assert!(close(fa - fb, a - b, 0.001));
+            prop_assert!(close(fa * fb, a * b, 0.001));
+            if b.abs() > 0.1 {
+                prop_assert!(close(fa / fb, a / b, 0.01));
+            }
+        }
+
+        #[test]
+        fn sqrt_matches_floats(a in 0.0f32..100000.0) {
+            prop_assert!(close(Fixed::from_f32(a).sqrt(), a.sqrt(), 0.001));
+        }
+
+        #[test]
+        fn powf_matches_floats(base in 0.01f32..1.0, exp in 0.0f32..20.0) {
+            // the decay factors used by the physics are all in this range
+            let result = Fixed::from_f32(base).powf(Fixed::from_f32(exp));
+            prop_assert!((result.to_f32() - base.powf(exp)).abs() < 0.01);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! client side constants that admins can tweak without shipping a new client. they are part of the
+//! world state, so clients get them with the full state and whenever they change.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameTuning {
+    /// how close to the left and right of the screen players get before the camera follows
+    pub camera_edge_x: u64,
+    /// how close to the top and bottom of the screen players get before the camera follows
+    pub camera_edge_y: u64,
+    /// chat bubbles stay up for a base time plus a bit for every character, roughly reading speed
+    pub chat_base_ms: u64,
+    pub chat_ms_per_char: u64,
+    pub chat_max_ms: u64,
+    /// how often the own position is sent while nothing happens, so the server knows we're still there
+    pub forced_update_ms: u64,
+    /// how long a frame of the player animations lasts
+    pub anim_frame_ms: u64,
+    /// how much time a server tick simulates. raised by the server itself when it can't keep up,
+    /// so it isn't a `TuningParam`
+    pub server_tick_ms: u64,
+}
+
+impl Default for GameTuning {
+    fn default() -> Self {
+        GameTuning {
+            camera_edge_x: 100,
+            camera_edge_y: 50,
+            chat_base_ms: 3500,
+            chat_ms_per_char: 60,
+            chat_max_ms: 12000,
+            forced_update_ms: crate::SERVER_TICK_RATE * 15,
+            anim_frame_ms: crate::BASE_ANIM_SPEED as u64,
+            server_tick_ms: crate::SERVER_TICK_RATE,
+        }
+    }
+}
+
+impl GameTuning {
+    pub fn get(&self, param: TuningParam) -> u64 {
+        match param {
+            TuningParam::CameraEdgeX => self.camera_edge_x,
+            TuningParam::CameraEdgeY => self.camera_edge_y,
+            TuningParam::ChatBaseMs => self.chat_base_ms,
+            TuningParam::ChatMsPerChar => self.chat_ms_per_char,
+            TuningParam::ChatMaxMs => self.chat_max_ms,
+            TuningParam::ForcedUpdateMs => self.forced_update_ms,
+            TuningParam::AnimFrameMs => self.anim_frame_ms,
+        }
+    }
+
+    /// values outside of the range of the parameter are clamped
+    pub fn set(&mut self, param: TuningParam, value: u64) {
+        let (min, max) = param.range();
+        let value = value.clamp(min, max);
+        match param {
+            TuningParam::CameraEdgeX => self.camera_edge_x = value,
+            TuningParam::CameraEdgeY => self.camera_edge_y = value,
+            TuningParam::ChatBaseMs => self.chat_base_ms = value,
+            TuningParam::ChatMsPerChar => self.chat_ms_per_char = value,
+            TuningParam::ChatMaxMs => self.chat_max_ms = value,
+            TuningParam::ForcedUpdateMs => self.forced_update_ms = value,
+            TuningParam::AnimFrameMs => self.anim_frame_ms = value,
+        }
+    }
+
+    /// how long the chat bubble of `message` stays up
+    pub fn chat_duration_ms(&self, message: &str) -> u64 {
+        let chars = message.chars().count() as u64;
+        (self.chat_base_ms + chars * self.chat_ms_per_char).min(self.chat_max_ms)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TuningParam {
+    CameraEdgeX,
+    CameraEdgeY,
+    ChatBaseMs,
+    ChatMsPerChar,
+    ChatMaxMs,
+    ForcedUpdateMs,
+    AnimFrameMs,
+}
+
+impl TuningParam {
+    pub const ALL: [TuningParam; 7] = [
+        TuningParam::CameraEdgeX,
+        TuningParam::CameraEdgeY,
+        TuningParam::ChatBaseMs,
+        TuningParam::ChatMsPerChar,
+        TuningParam::ChatMaxMs,
+        TuningParam::ForcedUpdateMs,
+        TuningParam::AnimFrameMs,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TuningParam::CameraEdgeX => "camera-edge-x",
+            TuningParam::CameraEdgeY => "camera-edge-y",
+            TuningParam::ChatBaseMs => "chat-base-ms",
+            TuningParam::ChatMsPerChar => "chat-ms-per-char",
+            TuningParam::ChatMaxMs => "chat-max-ms",
+            TuningParam::ForcedUpdateMs => "forced-update-ms",
+            TuningParam::AnimFrameMs => "anim-frame-ms",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|param| param.name() == name)
+    }
+
+    /// the lowest and highest value that still plays fine
+    pub fn range(&self) -> (u64, u64) {
+        match self {
+            TuningParam::CameraEdgeX => (40, 200),
+            TuningParam::CameraEdgeY => (40, 120),
+            TuningParam::ChatBaseMs => (1000, 10_000),
+            TuningParam::ChatMsPerChar => (0, 200),
+            TuningParam::ChatMaxMs => (2000, 30_000),
+            TuningParam::ForcedUpdateMs => (100, 2000),
+            TuningParam::AnimFrameMs => (50, 1000),
+        }
+    }
+
+    pub fn default_value(&self) -> u64 {
+        GameTuning::default().get(*self)
+    }
+}
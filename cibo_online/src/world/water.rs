@@ -0,0 +1,30 @@
+//! the sea along the southern edge of the map during the beach episode.
+//! only uses integer math, so the server and all clients agree on where the water is.
+
+use monos_gfx::Position;
+
+/// tile row where the sea starts
+const SHORE_TILE_Y: i64 = 56;
+/// width of one wave of the shore line, in tiles
+const SHORE_WAVE_TILES: i64 = 24;
+/// rows of sand in front of the sea
+const BEACH_TILES: i64 = 6;
+
+/// first tile row of the sea in the given tile column
+pub(crate) fn shore_tile(x_tile: i64) -> i64 {
+    let phase = x_tile.rem_euclid(SHORE_WAVE_TILES);
+    SHORE_TILE_Y + (phase - SHORE_WAVE_TILES / 2).abs() / 4
+}
+
+pub(crate) fn is_water_tile(x_tile: i64, y_tile: i64) -> bool {
+    y_tile >= shore_tile(x_tile)
+}
+
+pub(crate) fn is_water(position: Position) -> bool {
+    is_water_tile(position.x.div_euclid(16), position.y.div_euclid(16))
+}
+
+/// where the sand starts in the given column, in world pixels. also there when the beach episode isn't running
+pub(crate) fn beach_y(x: i64) -> i64 {
+    (shore_tile(x.div_euclid(16)) - BEACH_TILES) * 16
+}
@@ -0,0 +1,274 @@
+//! seagulls, butterflies and crickets living around the world. purely cosmetic and never sent over
+//! the network: which critters live in a chunk only depends on the seed the server sends along with
+//! the world, and where they are only on the server clock, so every client sees the same ones.
+
+use super::{water, SpecialEventState};
+use crate::RenderContext;
+use alloc::vec::Vec;
+use core::f32::consts::TAU;
+use micromath::F32Ext;
+use monos_gfx::{Color, Position, Rect};
+
+/// width and height of a chunk, in pixels
+const CHUNK_SIZE: i64 = 256;
+const MAX_PER_CHUNK: u64 = 3;
+/// how far away a critter can still be heard, in pixels
+const HEARING_DISTANCE: i64 = 160;
+/// how long a cricket sits still before it hops somewhere else
+const CRICKET_HOP_MS: u64 = 2500;
+const CRICKET_HOP_LENGTH_MS: u64 = 150;
+/// sounds further back than this are dropped, e.g. after the tab was in the background
+const MAX_SOUND_DELAY_MS: u64 = 1000;
+
+const SEAGULL_FRAMES: [[&str; 3]; 2] = [["#...#", ".#.#.", "..#.."], [".....", "##.##", "..#.."]];
+const SEAGULL_COLOR: Color = Color::new(236, 236, 240);
+const BUTTERFLY_FRAMES: [[&str; 2]; 2] = [["#.#", "#.#"], [".#.", ".#."]];
+const BUTTERFLY_COLORS: [Color; 4] = [
+    Color::new(250, 210, 70),
+    Color::new(240, 130, 60),
+    Color::new(160, 200, 250),
+    Color::new(245, 245, 245),
+];
+const CRICKET_COLOR: Color = Color::new(70, 100, 45);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Critter {
+    Seagull,
+    Butterfly,
+    Cricket,
+}
+
+impl Critter {
+    /// whether it makes any sound the platform can play
+    pub fn is_audible(&self) -> bool {
+        !matches!(self, Critter::Butterfly)
+    }
+
+    /// drawn above players instead of below them
+    fn flies_above(&self) -> bool {
+        matches!(self, Critter::Seagull)
+    }
+
+    /// how far it strays from home, in pixels
+    fn range(&self) -> i64 {
+        match self {
+            Critter::Seagull => 96,
+            Critter::Butterfly => 24,
+            Critter::Cricket => 6,
+        }
+    }
+
+    /// time for one loop around home
+    fn period_ms(&self) -> u64 {
+        match self {
+            Critter::Seagull => 20_000,
+            Critter::Butterfly => 7000,
+            Critter::Cricket => CRICKET_HOP_MS,
+        }
+    }
+
+    /// how often it calls out, if it is audible
+    fn call_period_ms(&self) -> u64 {
+        match self {
+            Critter::Seagull => 9000,
+            Critter::Butterfly => 0,
+            Critter::Cricket => 3000,
+        }
+    }
+}
+
+fn hash(seed: u64, chunk: (i64, i64), index: u64) -> u64 {
+    let h = seed
+        ^ (chunk.0 as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (chunk.1 as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+        ^ index.wrapping_mul(0x1656_67b1_9e37_79f9);
+    let h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Spawn {
+    critter: Critter,
+    home: Position,
+    hash: u64,
+}
+
+impl Spawn {
+    /// keeps critters of the same kind from moving in lockstep
+    fn offset_ms(&self, period_ms: u64) -> u64 {
+        (self.hash >> 40) % period_ms.max(1)
+    }
+
+    fn position(&self, time_ms: u64) -> Position {
+        let range = self.critter.range();
+        let period = self.critter.period_ms();
+        let time_ms = time_ms + self.offset_ms(period);
+        let angle = (time_ms % period) as f32 / period as f32 * TAU;
+
+        let (x, y) = match self.critter {
+            Critter::Seagull => (angle.cos() * range as f32, angle.sin() * range as f32 * 0.5),
+            Critter::Butterfly => (
+                angle.sin() * range as f32,
+                (angle * 2.0).sin() * range as f32 * 0.5 + (angle * 24.0).sin() * 2.0,
+            ),
+            // sits still and hops somewhere else now and then
+            Critter::Cricket => {
+                let hop = time_ms / CRICKET_HOP_MS;
+                let spot = hash(self.hash, (hop as i64, 0), 0);
+                let width = range as u64 * 2 + 1;
+                let lift = if time_ms % CRICKET_HOP_MS < CRICKET_HOP_LENGTH_MS {
+                    2.0
+                } else {
+                    0.0
+                };
+                (
+                    (spot % width) as f32 - range as f32,
+                    ((spot >> 8) % width) as f32 - range as f32 - lift,
+                )
+            }
+        };
+        self.home + Position::new(x as i64, y as i64)
+    }
+
+    fn render(&self, camera: Position, ctx: &mut RenderContext) {
+        let position = self.position(ctx.server_time_ms) - camera;
+        match self.critter {
+            Critter::Seagull => {
+                let frame = ((ctx.server_time_ms + self.offset_ms(600)) / 300 % 2) as usize;
+                draw_shape(ctx, &SEAGULL_FRAMES[frame], position, SEAGULL_COLOR);
+            }
+            Critter::Butterfly => {
+                let frame = ((ctx.server_time_ms + self.offset_ms(240)) / 120 % 2) as usize;
+                let color = BUTTERFLY_COLORS[(self.hash % BUTTERFLY_COLORS.len() as u64) as usize];
+                draw_shape(ctx, &BUTTERFLY_FRAMES[frame], position, color);
+            }
+            Critter::Cricket => {
+                ctx.fb.draw_rect(
+                    Rect::new(position, position + Position::new(2, 1)),
+                    CRICKET_COLOR,
+                );
+            }
+        }
+    }
+}
+
+fn draw_shape(ctx: &mut RenderContext, shape: &[&str], position: Position, color: Color) {
+    for (row, line) in shape.iter().enumerate() {
+        for (column, pixel) in line.chars().enumerate() {
+            if pixel == '#' {
+                let pos = position + Position::new(column as i64, row as i64);
+                ctx.fb
+                    .draw_rect(Rect::new(pos, pos + Position::new(1, 1)), color);
+            }
+        }
+    }
+}
+
+/// the critters living in a chunk, the same on every client with the same seed
+fn spawns(
+    seed: u64,
+    chunk: (i64, i64),
+    events: &SpecialEventState,
+) -> impl Iterator<Item = Spawn> + '_ {
+    let count = hash(seed, chunk, 0) % (MAX_PER_CHUNK + 1);
+    (1..=count).filter_map(move |index| {
+        let h = hash(seed, chunk, index);
+        let home = Position::new(
+            chunk.0 * CHUNK_SIZE + (h % CHUNK_SIZE as u64) as i64,
+            chunk.1 * CHUNK_SIZE + ((h >> 16) % CHUNK_SIZE as u64) as i64,
+        );
+        let roll = (h >> 32) % 10;
+        let butterflies = if events.spring { 7 } else { 4 };
+        let critter = if events.beach_episode && roll < 4 {
+            Critter::Seagull
+        } else if roll < butterflies {
+            Critter::Butterfly
+        } else {
+            Critter::Cricket
+        };
+
+        if critter != Critter::Seagull {
+            // too cold for bugs, and they don't go out on the water
+            if events.holidays || (events.beach_episode && water::is_water(home)) {
+                return None;
+            }
+        }
+        Some(Spawn {
+            critter,
+            home,
+            hash: h,
+        })
+    })
+}
+
+/// every critter that could be within `margin` of the area between `min` and `max`
+fn spawns_around(
+    seed: u64,
+    events: &SpecialEventState,
+    min: Position,
+    max: Position,
+    margin: i64,
+) -> impl Iterator<Item = Spawn> + '_ {
+    let min_chunk = (
+        (min.x - margin).div_euclid(CHUNK_SIZE),
+        (min.y - margin).div_euclid(CHUNK_SIZE),
+    );
+    let max_chunk = (
+        (max.x + margin).div_euclid(CHUNK_SIZE),
+        (max.y + margin).div_euclid(CHUNK_SIZE),
+    );
+    (min_chunk.0..=max_chunk.0)
+        .flat_map(move |x| (min_chunk.1..=max_chunk.1).map(move |y| (x, y)))
+        .flat_map(move |chunk| spawns(seed, chunk, events))
+}
+
+/// draw the critters on screen, either the ones on the ground or the ones flying above everything
+pub(crate) fn render(
+    seed: u64,
+    events: &SpecialEventState,
+    camera: Position,
+    ctx: &mut RenderContext,
+    flying: bool,
+) {
+    let screen = ctx.fb.dimensions();
+    let max = camera + Position::new(screen.width as i64, screen.height as i64);
+    let margin = Critter::Seagull.range() + 8;
+    for spawn in spawns_around(seed, events, camera, max, margin) {
+        if spawn.critter.flies_above() == flying {
+            spawn.render(camera, ctx);
+        }
+    }
+}
+
+/// the critters that called out near `listener` between two server times, with how loud they are
+/// from 0 to 1
+pub(crate) fn calls(
+    seed: u64,
+    events: &SpecialEventState,
+    listener: Position,
+    from_ms: u64,
+    to_ms: u64,
+) -> Vec<(Critter, f32)> {
+    let from_ms = from_ms.max(to_ms.saturating_sub(MAX_SOUND_DELAY_MS));
+    let margin = HEARING_DISTANCE + Critter::Seagull.range();
+    spawns_around(seed, events, listener, listener, margin)
+        .filter(|spawn| spawn.critter.is_audible())
+        .filter_map(|spawn| {
+            let period = spawn.critter.call_period_ms();
+            let offset = spawn.offset_ms(period);
+            if (from_ms + offset) / period == (to_ms + offset) / period {
+                return None;
+            }
+
+            let distance = spawn.position(to_ms) - listener;
+            let distance = distance.x.abs().max(distance.y.abs());
+            (distance < HEARING_DISTANCE).then(|| {
+                (
+                    spawn.critter,
+                    1.0 - distance as f32 / HEARING_DISTANCE as f32,
+                )
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,428 @@
+use crate::{
+    menu::Menu,
+    platform::{
+        account_key, load_bytes, load_settings, save_bytes, save_settings, send, HUD_LAYOUT_KEY,
+        TUTORIAL_KEY,
+    },
+    recording::Recording,
+    Platform,
+};
+use alloc::{format, string::String, vec::Vec};
+use cibo_online::{
+    client::{
+        controls::{self, Action},
+        ClientGameState, ClientMessage, HudLayout, LatencyStats, PlayerSettings, TutorialStep,
+    },
+    server::{FullStateParts, Refusal, SerializedClientGameState, ServerMessage},
+    EnvelopeError,
+};
+use monos_gfx::{input::Input, Framebuffer};
+
+/// whether any of the keys bound to the action was pressed
+pub fn action_pressed(input: &mut Input, action: Action) -> bool {
+    controls::keys(action)
+        .iter()
+        .any(|key| input.key_pressed(*key))
+}
+
+/// the rules players agree to before joining. the version is on the first line, the text after it
+const RULES_PATH: &str = "/rules";
+/// the wait before reconnecting doubles with every failed attempt, up to this
+const MAX_RECONNECT_DELAY_MS: u64 = 30_000;
+
+/// where the client is on its way into the game
+pub enum ConnectionState {
+    /// waiting for the connection to the server to open
+    Connecting,
+    /// the connection was lost, trying again once the time runs out
+    Reconnecting {
+        in_ms: u64,
+    },
+    /// the server won't let the player in, there is no point in reconnecting
+    Refused(Refusal),
+    /// waiting for the player to enter a name
+    Menu,
+    /// asked the server to join, waiting for the game state
+    Joining,
+    /// the server is full, the position in the join queue
+    Queued(u32),
+    /// receiving the game state, `received` out of `total` parts arrived
+    Loading {
+        received: u32,
+        total: u32,
+    },
+    Playing(ClientGameState),
+}
+
+pub struct Frontend {
+    state: ConnectionState,
+    pub input: Input,
+    settings: PlayerSettings,
+    menu: Menu,
+    /// connection attempts since the last one that went through
+    reconnect_attempts: u32,
+    full_state: FullStateParts,
+    /// the game can't be seen, so it keeps running without drawing anything
+    low_power: bool,
+    /// paths of the asset packs that were fetched, so reconnecting doesn't load them again
+    asset_packs: Vec<String>,
+    /// `Some` while the session is recorded, see the `recording` module
+    recording: Option<Recording>,
+    /// code from the `transfer` parameter, only sent with the first join since it works once
+    transfer: Option<String>,
+    /// opened with the `safe` parameter, safe chat stays on no matter the settings
+    safe_chat_locked: bool,
+}
+
+impl Frontend {
+    pub fn new(platform: &mut dyn Platform) -> Self {
+        platform.fetch(RULES_PATH);
+        platform.connect();
+        let recording = platform
+            .param("record")
+            .is_some()
+            .then(|| Recording::new(platform));
+        let safe_chat_locked = platform.param("safe").is_some();
+        let mut settings = load_settings(platform);
+        settings.safe_chat |= safe_chat_locked;
+        Frontend {
+            state: ConnectionState::Connecting,
+            input: Input::default(),
+            settings,
+            menu: Menu::new(platform),
+            reconnect_attempts: 0,
+            full_state: FullStateParts::default(),
+            low_power: false,
+            asset_packs: Vec::new(),
+            recording,
+            transfer: platform.param("transfer"),
+            safe_chat_locked,
+        }
+    }
+
+    /// the connection opened by `Platform::connect` is ready
+    pub fn connected(&mut self) {
+        if let Some(recording) = &mut self.recording {
+            recording.connected();
+        }
+        self.reconnect_attempts = 0;
+        self.state = ConnectionState::Menu;
+    }
+
+    /// the connection to the server was closed or couldn't be opened
+    pub fn disconnected(&mut self) {
+        if let Some(recording) = &mut self.recording {
+            recording.disconnected();
+        }
+        if matches!(self.state, ConnectionState::Refused(_)) {
+            return;
+        }
+        let delay = 1000u64
+            .saturating_mul(1 << self.reconnect_attempts.min(16))
+            .min(MAX_RECONNECT_DELAY_MS);
+        self.reconnect_attempts += 1;
+        self.state = ConnectionState::Reconnecting { in_ms: delay };
+    }
+
+    /// stop drawing while the game is hidden, `update` then only keeps the game state going
+    pub fn set_low_power(&mut self, low_power: bool) {
+        if let Some(recording) = &mut self.recording {
+            recording.low_power(low_power);
+        }
+        self.low_power = low_power;
+    }
+
+    pub fn low_power(&self) -> bool {
+        self.low_power
+    }
+
+    pub fn state(&self) -> &ConnectionState {
+        &self.state
+    }
+
+    pub fn handle_server_bytes(&mut self, bytes: &[u8], platform: &mut dyn Platform) {
+        if let Some(recording) = &mut self.recording {
+            recording.server_bytes(bytes);
+        }
+        let server_message = ServerMessage::from_bytes(bytes);
+        if let Ok(message) = &server_message {
+            platform.message_received(message.kind(), bytes.len());
+        }
+        match server_message {
+            Ok(message) => self.handle_server_message(message, platform),
+            Err(EnvelopeError::Unknown { tag, .. }) => {
+                // sent by a newer server, a newer client will pick it up
+                platform.message_received("Unknown", bytes.len());
+                platform.log(&format!("skipped unknown server message with tag {}", tag));
+            }
+            Err(e) => platform.log(&format!("Error deserializing server message: {:#?}", e)),
+        }
+    }
+
+    fn handle_server_message(&mut self, message: ServerMessage, platform: &mut dyn Platform) {
+        // the game state updates the label, the menu has to remember the name for the next join
+        if let (
+            ServerMessage::ClientRenamed(client_id, name),
+            ConnectionState::Playing(game_state),
+        ) = (&message, &self.state)
+        {
+            if game_state.client().id() == *client_id {
+                self.menu.renamed(name.clone(), platform);
+            }
+        }
+
+        match message {
+            ServerMessage::Critical { seq, message } => {
+                send(platform, &ClientMessage::Ack(seq));
+                self.handle_server_message(*message, platform);
+            }
+            ServerMessage::FullState(new_state) => self.start_game(new_state, platform),
+            ServerMessage::FullStatePart {
+                index,
+                total,
+                bytes,
+            } => match self.full_state.add(index, total, &bytes) {
+                Ok(Some(new_state)) => self.start_game(new_state, platform),
+                Ok(None) if index + 1 >= total => {
+                    platform.log("failed to put the game state back together");
+                }
+                Err(err) => platform.log(&format!("failed to load the game state: {}", err)),
+                Ok(None) => {
+                    self.state = ConnectionState::Loading {
+                        received: index + 1,
+                        total,
+                    }
+                }
+            },
+            ServerMessage::ConnectRejected(reason) => {
+                platform.alert(&reason);
+                self.state = ConnectionState::Menu;
+            }
+            ServerMessage::QueuePosition(position) => {
+                self.state = match position {
+                    // the name input was hidden while queued
+                    0 => ConnectionState::Menu,
+                    position => ConnectionState::Queued(position),
+                }
+            }
+            ServerMessage::NameCheck { name, problem } => self.menu.name_checked(name, problem),
+            ServerMessage::Refused(refusal) => self.state = ConnectionState::Refused(refusal),
+            ServerMessage::AssetPacks(packs) => {
+                for pack in packs {
+                    if !self.asset_packs.contains(&pack.path) {
+                        platform.fetch(&pack.path);
+                        self.asset_packs.push(pack.path);
+                    }
+                }
+            }
+            ServerMessage::RequestSnapshot => {
+                if let ConnectionState::Playing(game_state) = &self.state {
+                    let snapshot = game_state.world_snapshot();
+                    send(platform, &ClientMessage::WorldSnapshot(snapshot));
+                }
+            }
+            message => {
+                if let ConnectionState::Playing(game_state) = &mut self.state {
+                    if let Err(err) = game_state.handle_message(message) {
+                        platform.log(&format!("failed to handle server message: {}", err));
+                        game_state.show_error(&err);
+                    }
+                }
+            }
+        }
+    }
+
+    fn start_game(&mut self, new_state: SerializedClientGameState, platform: &mut dyn Platform) {
+        cibo_online::setup_network_objects();
+        let mut new_state = match new_state.serialize() {
+            Ok(new_state) => new_state,
+            Err(err) => {
+                platform.log(&format!("failed to load the game state: {}", err));
+                return;
+            }
+        };
+        if let Some(layout) = load_bytes(platform, HUD_LAYOUT_KEY)
+            .and_then(|bytes| HudLayout::from_bytes(&bytes).ok())
+        {
+            new_state.set_hud_layout(layout);
+        }
+        if let Some(step) = load_bytes(platform, TUTORIAL_KEY)
+            .and_then(|bytes| TutorialStep::from_bytes(&bytes).ok())
+        {
+            new_state.set_tutorial_step(step);
+        }
+        new_state.restore_settings(load_settings(platform));
+        if self.safe_chat_locked {
+            new_state.lock_safe_chat();
+        }
+        if platform.param("debug").is_some() {
+            new_state.set_debug_overlay(true);
+        }
+        self.state = ConnectionState::Playing(new_state);
+    }
+
+    /// the platform loaded something asked for with `Platform::fetch`
+    pub fn fetched(&mut self, path: &str, body: &[u8], platform: &mut dyn Platform) {
+        if let Some(recording) = &mut self.recording {
+            recording.fetched(path, body);
+        }
+        if self.asset_packs.iter().any(|pack| pack == path) {
+            match cibo_online::load_asset_pack(body) {
+                Ok(count) => platform.log(&format!("loaded {} assets from {}", count, path)),
+                Err(err) => platform.log(&format!("failed to load {}: {}", path, err)),
+            }
+            return;
+        }
+
+        if path == RULES_PATH {
+            let Ok(body) = core::str::from_utf8(body) else {
+                platform.log("the rules are not valid text");
+                return;
+            };
+            let rules = body
+                .split_once('\n')
+                .and_then(|(version, text)| Some((version.trim().parse().ok()?, text.trim())))
+                .filter(|(_, text)| !text.is_empty());
+            self.menu
+                .set_rules(rules.map(|(version, text)| (version, text.into())));
+        }
+    }
+
+    /// change the settings, save them and sync them to the server once connected
+    pub fn change_settings(
+        &mut self,
+        platform: &mut dyn Platform,
+        change: impl FnOnce(&mut PlayerSettings),
+    ) {
+        if let ConnectionState::Playing(game_state) = &self.state {
+            // may have changed in game, e.g. through the chat filter commands
+            self.settings = game_state.settings();
+        }
+        change(&mut self.settings);
+        save_settings(platform, &self.settings);
+        if let ConnectionState::Playing(game_state) = &mut self.state {
+            game_state.set_settings(self.settings.clone());
+        }
+    }
+
+    pub fn settings(&self) -> &PlayerSettings {
+        &self.settings
+    }
+
+    pub fn update(&mut self, delta_ms: u64, fb: &mut Framebuffer, platform: &mut dyn Platform) {
+        if let Some(recording) = &mut self.recording {
+            recording.update(delta_ms, &self.input, fb);
+        }
+        if action_pressed(&mut self.input, Action::MentionSound) {
+            self.change_settings(platform, |settings| {
+                settings.ping_sound = !settings.ping_sound
+            });
+        }
+        if action_pressed(&mut self.input, Action::ColorMode) {
+            self.change_settings(platform, |settings| {
+                settings.color_mode = settings.color_mode.next()
+            });
+        }
+        if action_pressed(&mut self.input, Action::ReducedMotion) {
+            self.change_settings(platform, |settings| {
+                settings.reduced_motion = !settings.reduced_motion
+            });
+        }
+        if action_pressed(&mut self.input, Action::LabelLayout) {
+            self.change_settings(platform, |settings| {
+                settings.label_layout = settings.label_layout.next()
+            });
+        }
+
+        if let ConnectionState::Reconnecting { in_ms } = &mut self.state {
+            *in_ms = in_ms.saturating_sub(delta_ms);
+            if *in_ms == 0 {
+                self.state = ConnectionState::Connecting;
+                platform.connect();
+            }
+        }
+
+        let ConnectionState::Playing(game_state) = &mut self.state else {
+            if self.low_power {
+                return;
+            }
+            // no game state was received yet, let the player enter their name
+            if let Some(name) = self
+                .menu
+                .draw(fb, &mut self.input, &self.state, delta_ms, platform)
+            {
+                let client_msg = ClientMessage::Connect {
+                    name,
+                    admin_token: platform.param("admin"),
+                    account: Some(account_key(platform)),
+                    settings: self.settings.clone(),
+                    rules_version: self.menu.agreed_rules(),
+                    transfer: self.transfer.take(),
+                };
+                send(platform, &client_msg);
+                self.state = ConnectionState::Joining;
+            }
+            return;
+        };
+
+        // we are connected to the server and have received a game state.
+        // let the game state handle the rest
+        let result = game_state.update(
+            delta_ms,
+            fb,
+            &mut self.input,
+            &mut |client_msg| send(platform, &client_msg),
+            !self.low_power,
+        );
+        if let Err(err) = result {
+            platform.log(&format!("failed to update the game: {}", err));
+            game_state.show_error(&err);
+        }
+        // sent by the server or changed in game
+        let settings = game_state.settings();
+        if settings != self.settings {
+            save_settings(platform, &settings);
+            self.settings = settings;
+        }
+        if game_state.take_ping() && self.settings.ping_sound {
+            platform.play_ping();
+        }
+        for (critter, volume) in game_state.take_ambient_sounds() {
+            platform.play_ambient(critter, volume);
+        }
+        if let Some(layout) = game_state.take_hud_layout_changed() {
+            if let Ok(bytes) = layout.to_bytes() {
+                save_bytes(platform, HUD_LAYOUT_KEY, &bytes);
+            }
+        }
+        if let Some(step) = game_state.take_tutorial_changed() {
+            if let Ok(bytes) = step.to_bytes() {
+                save_bytes(platform, TUTORIAL_KEY, &bytes);
+            }
+        }
+        if let Some(link) = game_state.take_clicked_link() {
+            platform.open_link(&link);
+        }
+    }
+
+    /// textual summary of the game for screen readers, empty until the game state was received
+    pub fn accessibility_text(&self) -> String {
+        match &self.state {
+            ConnectionState::Playing(game_state) => game_state.accessibility_text(),
+            _ => String::new(),
+        }
+    }
+
+    /// the session so far, `None` unless it is recorded
+    pub fn recording(&self) -> Option<&Recording> {
+        self.recording.as_ref()
+    }
+
+    /// how long our actions take to show up, see `ClientGameState::latency`
+    pub fn latency(&self) -> Option<&LatencyStats> {
+        match &self.state {
+            ConnectionState::Playing(game_state) => game_state.latency(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! everything around the game that doesn't depend on where it runs: the connection to the server,
+//! the join menu and keeping settings between sessions. the web and desktop clients only provide a
+//! [`Platform`] and feed in input.
+
+#![no_std]
+
+extern crate alloc;
+
+mod platform;
+pub use platform::Platform;
+
+mod menu;
+
+mod frontend;
+pub use frontend::{action_pressed, ConnectionState, Frontend};
+
+pub mod recording;
+
+#[cfg(feature = "net_sim")]
+pub mod net_sim;
@@ -0,0 +1,363 @@
+use crate::{
+    platform::{account_key, send},
+    ConnectionState, Platform,
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use cibo_online::{
+    client::{
+        controls::{self, Action},
+        ClientMessage,
+    },
+    names,
+    server::Refusal,
+};
+use monos_gfx::{
+    image::SliceReader,
+    input::Input,
+    text::{font, Origin},
+    ui::{
+        self,
+        widgets::{self, Textbox},
+        Lines, TextWrap, UIContext, UIElement, UIFrame, UIResult,
+    },
+    Color, Dimension, Framebuffer, Image, Position, Rect,
+};
+
+/// the name the player joined with last time, filled in again
+const NAME_KEY: &str = "name";
+/// the server is only asked about a name once the player stopped typing for this long
+const CHECK_DELAY_MS: u64 = 500;
+/// the rules scroll if they are longer than this
+const RULES_HEIGHT: u32 = 60;
+
+const RANDOM_ADJECTIVES: &[&str] = &[
+    "sleepy", "tiny", "crunchy", "fluffy", "spicy", "sweet", "sunny", "shy", "bouncy", "cozy",
+    "grumpy", "happy",
+];
+const RANDOM_FOODS: &[&str] = &[
+    "onigiri", "mochi", "dango", "taiyaki", "ramen", "udon", "tofu", "melonpan", "pudding",
+    "dumpling", "takoyaki", "sushi",
+];
+
+/// shown until the server sent the game state, lets the player enter their name
+pub(crate) struct Menu {
+    ui_frame: UIFrame,
+    name_input: String,
+    game_logo: Image,
+    /// the name as it was last frame, to notice when the player types
+    edited_name: String,
+    since_edit_ms: u64,
+    /// the last name the server was asked about, and its answer once it arrived
+    checked_name: Option<String>,
+    name_problem: Option<String>,
+    /// the rules of the server and their version, once they were fetched
+    rules: Option<(u32, String)>,
+    agreed: bool,
+    /// the player tried to join without agreeing
+    show_agree_hint: bool,
+}
+
+impl Menu {
+    pub fn new(platform: &dyn Platform) -> Self {
+        let name_input = platform
+            .load(NAME_KEY)
+            .map(|name| names::clean(&name))
+            .unwrap_or_default();
+
+        Menu {
+            ui_frame: UIFrame::new(ui::Direction::TopToBottom),
+            edited_name: name_input.clone(),
+            name_input,
+            game_logo: Image::from_ppm(&SliceReader::new(include_bytes!("../../assets/logo.ppm")))
+                .expect("Failed to load logo"),
+            since_edit_ms: 0,
+            checked_name: None,
+            name_problem: None,
+            rules: None,
+            agreed: false,
+            show_agree_hint: false,
+        }
+    }
+
+    pub fn set_rules(&mut self, rules: Option<(u32, String)>) {
+        self.rules = rules;
+        self.agreed = false;
+    }
+
+    /// the version of the rules the player agreed to
+    pub fn agreed_rules(&self) -> Option<u32> {
+        self.rules
+            .as_ref()
+            .filter(|_| self.agreed)
+            .map(|(version, _)| *version)
+    }
+
+    /// the server answered a `ClientMessage::CheckName`
+    pub fn name_checked(&mut self, name: String, problem: Option<String>) {
+        if self.checked_name.as_ref() == Some(&name) {
+            self.name_problem = problem;
+        }
+    }
+
+    /// the server changed our name while playing, join with the new one next time
+    pub fn renamed(&mut self, name: String, platform: &mut dyn Platform) {
+        platform.save(NAME_KEY, &name);
+        self.name_input = name;
+    }
+
+    /// ask the server about the name once the player stopped typing
+    fn check_name(&mut self, delta_ms: u64, platform: &mut dyn Platform) {
+        if self.name_input != self.edited_name {
+            self.edited_name = self.name_input.clone();
+            self.since_edit_ms = 0;
+            return;
+        }
+        self.since_edit_ms += delta_ms;
+
+        let name = self.name_input.trim();
+        if self.since_edit_ms < CHECK_DELAY_MS
+            || name.is_empty()
+            || self.checked_name.as_deref() == Some(name)
+        {
+            return;
+        }
+        self.checked_name = Some(name.to_string());
+        self.name_problem = None;
+        let client_msg = ClientMessage::CheckName {
+            name: name.to_string(),
+            account: Some(account_key(platform)),
+        };
+        send(platform, &client_msg);
+    }
+
+    /// what is wrong with the name that is typed in right now, if anything
+    fn problem(&self) -> Option<&str> {
+        let name = self.name_input.trim();
+        if let Some(problem) = names::problem(name) {
+            return Some(problem);
+        }
+        if self.checked_name.as_deref() == Some(name) {
+            return self.name_problem.as_deref();
+        }
+        None
+    }
+
+    /// the name the player wants to join with, once they submitted it
+    pub fn draw(
+        &mut self,
+        fb: &mut Framebuffer,
+        input: &mut Input,
+        state: &ConnectionState,
+        delta_ms: u64,
+        platform: &mut dyn Platform,
+    ) -> Option<String> {
+        if matches!(state, ConnectionState::Menu) {
+            self.check_name(delta_ms, platform);
+        }
+
+        let fb_rect = Rect::from_dimensions(fb.dimensions());
+
+        fb.draw_rect(fb_rect, Color::new(100, 100, 100));
+
+        let logo_pos = Position::new(
+            (fb.dimensions().width as i64 - self.game_logo.dimensions().width as i64) / 2,
+            20,
+        );
+        fb.draw_img(&self.game_logo, logo_pos);
+
+        let mut rect = fb_rect.clone();
+        rect.min.y = self.game_logo.dimensions().height as i64 + 50;
+
+        let problem = self.problem().map(|problem| problem.to_string());
+        let mut submitted = false;
+        let mut random_name = false;
+        let mut toggle_agreed = false;
+        self.ui_frame.draw_frame(fb, rect, input, |ui| {
+            ui.margin(ui::MarginMode::Grow);
+
+            match state {
+                // the server only lets queued players connect once there is room
+                ConnectionState::Queued(position) => {
+                    ui.label::<font::Cozette>("the server is full right now!");
+                    ui.label::<font::Cozette>(&format!("you are number {} in the queue", position));
+                    ui.label::<font::Glean>("you will be let in as soon as a spot frees up");
+                }
+                ConnectionState::Joining => {
+                    ui.label::<font::Cozette>("joining...");
+                }
+                ConnectionState::Loading { received, total } => {
+                    ui.label::<font::Cozette>("loading the world...");
+                    ui.add(ProgressBar::new(*received as f32 / *total as f32));
+                }
+                ConnectionState::Connecting => {
+                    ui.label::<font::Cozette>("connecting...");
+                }
+                ConnectionState::Reconnecting { in_ms } => {
+                    ui.label::<font::Cozette>("lost the connection to the server");
+                    ui.label::<font::Glean>(&format!("trying again in {}s", in_ms.div_ceil(1000)));
+                }
+                ConnectionState::Refused(Refusal::Banned) => {
+                    ui.label::<font::Cozette>("you are banned from this server");
+                    ui.label::<font::Glean>(
+                        "if you think this is a mistake, you can appeal at /appeal",
+                    );
+                }
+                ConnectionState::Refused(Refusal::AlreadyConnected) => {
+                    ui.label::<font::Cozette>("someone is already playing from your ip");
+                    ui.label::<font::Glean>("only one connection per ip is allowed");
+                }
+                ConnectionState::Refused(Refusal::Kicked) => {
+                    ui.label::<font::Cozette>("you were kicked by a moderator");
+                    ui.label::<font::Glean>("reload the page to join again");
+                }
+                ConnectionState::Refused(Refusal::Transferred) => {
+                    ui.label::<font::Cozette>("you continued on another device");
+                    ui.label::<font::Glean>("reload the page to play here again");
+                }
+                _ => {
+                    ui.label::<font::Cozette>("please enter a nickname!");
+                    ui.label::<font::Glean>("(or leave empty to join anonymously)");
+
+                    let name_input = Textbox::<font::Cozette>::new(&mut self.name_input)
+                        .char_limit(cibo_online::NAME_LIMIT);
+                    submitted = ui.add(name_input).submitted;
+
+                    if let Some(problem) = &problem {
+                        ui.label::<font::Glean>(problem);
+                    }
+                    random_name = ui.add(TextButton::new("random name")).clicked;
+
+                    if let Some((_, rules)) = &self.rules {
+                        ui.label::<font::Cozette>("the rules:");
+                        ui.add(
+                            widgets::ScrollableLabel::<font::Glean, _>::new(rules, Origin::Top)
+                                .wrap(TextWrap::Enabled { hyphenate: false })
+                                .scroll_y(RULES_HEIGHT),
+                        );
+                        let checkbox = if self.agreed {
+                            "[x] i agree to the rules"
+                        } else {
+                            "[ ] i agree to the rules"
+                        };
+                        toggle_agreed = ui.add(TextButton::new(checkbox)).clicked;
+                        if self.show_agree_hint && !self.agreed {
+                            ui.label::<font::Glean>("please agree to the rules to join");
+                        }
+                    }
+                }
+            }
+        });
+        if random_name {
+            self.name_input = generate_name(platform);
+        }
+        if toggle_agreed {
+            self.agreed = !self.agreed;
+        }
+
+        let mut credits_frame = UIFrame::new_stateless(ui::Direction::BottomToTop);
+        credits_frame.draw_frame(fb, fb_rect, input, |ui| {
+            ui.margin(ui::MarginMode::Grow);
+
+            ui.label::<font::Glean>("made with ♡ by sakanaa");
+        });
+
+        let mut controls_frame = UIFrame::new_stateless(ui::Direction::BottomToTop);
+        controls_frame.draw_frame(fb, fb_rect, input, |ui| {
+            ui.label::<font::Glean>(&format!(
+                "press {} once you joined to see all controls and chat commands",
+                controls::key_names(Action::Help)
+            ));
+        });
+
+        input.clear();
+
+        if !submitted {
+            return None;
+        }
+        if self.rules.is_some() && !self.agreed {
+            self.show_agree_hint = true;
+            return None;
+        }
+        let name = names::clean(&self.name_input);
+        platform.save(NAME_KEY, &name);
+        Some(name)
+    }
+}
+
+/// something like `sleepy_mochi`, always within the name limit
+fn generate_name(platform: &mut dyn Platform) -> String {
+    let mut pick = |words: &[&'static str]| {
+        let index = (platform.random() * words.len() as f64) as usize;
+        words[index.min(words.len() - 1)]
+    };
+    let adjective = pick(RANDOM_ADJECTIVES);
+    let food = pick(RANDOM_FOODS);
+    format!("{}_{}", adjective, food)
+}
+
+/// a bar filled up to `progress`, between 0 and 1
+struct ProgressBar {
+    progress: f32,
+}
+
+impl ProgressBar {
+    fn new(progress: f32) -> Self {
+        ProgressBar {
+            progress: progress.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl UIElement for ProgressBar {
+    fn draw(self, context: &mut UIContext) -> UIResult {
+        let width = context.placer.max_width().min(150);
+        let result = context.alloc_space(Dimension::new(width, 8));
+
+        context.fb.draw_rect(result.rect, Color::new(70, 70, 70));
+        let filled_width = (width as f32 * self.progress) as i64;
+        context.fb.draw_rect(
+            Rect::new(
+                result.rect.min,
+                Position::new(result.rect.min.x + filled_width, result.rect.max.y),
+            ),
+            Color::new(255, 255, 255),
+        );
+        result
+    }
+}
+
+struct TextButton<'a> {
+    text: &'a str,
+}
+
+impl<'a> TextButton<'a> {
+    fn new(text: &'a str) -> Self {
+        TextButton { text }
+    }
+}
+
+impl UIElement for TextButton<'_> {
+    fn draw(self, context: &mut UIContext) -> UIResult {
+        let lines = Lines::<font::Glean>::layout(
+            self.text,
+            TextWrap::Enabled { hyphenate: false },
+            Dimension::new(context.placer.max_width().saturating_sub(4), 20),
+        );
+        let text_dimensions = lines.dimensions();
+        let result = context.alloc_space(Dimension::new(
+            text_dimensions.width + 4,
+            text_dimensions.height + 4,
+        ));
+
+        context.fb.draw_rect(result.rect, Color::new(70, 70, 70));
+        lines.draw(
+            context.fb,
+            result.rect.min + Position::new(2, 2),
+            Color::new(255, 255, 255),
+        );
+        result
+    }
+}
@@ -0,0 +1,101 @@
+//! artificial latency, jitter and packet loss for testing how the client copes with bad connections.
+//! configured through the platform parameters, e.g. `?latency=150&jitter=50&drop=0.05` in the page url.
+//! times are in ms and passed in by the caller, since only it knows where to get them from.
+
+use alloc::{collections::VecDeque, string::String, vec::Vec};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NetSimConfig {
+    /// delay added to every message in each direction, in ms
+    pub latency: f64,
+    /// maximum random deviation from the latency, in ms
+    pub jitter: f64,
+    /// chance for a message to get lost, from 0 to 1
+    pub drop: f64,
+}
+
+impl NetSimConfig {
+    /// read from the parameters the game was started with, see [`crate::Platform::param`]
+    pub fn from_params(param: impl Fn(&str) -> Option<String>) -> Self {
+        let param = |name: &str| {
+            param(name)
+                .and_then(|value| value.parse::<f64>().ok())
+                .unwrap_or(0.0)
+        };
+
+        NetSimConfig {
+            latency: param("latency").max(0.0),
+            jitter: param("jitter").max(0.0),
+            drop: param("drop").clamp(0.0, 1.0),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Link {
+    queue: VecDeque<(f64, Vec<u8>)>,
+    last_delivery: f64,
+}
+
+impl Link {
+    fn push(&mut self, config: &NetSimConfig, rng: &mut SmallRng, now: f64, bytes: Vec<u8>) {
+        if rng.gen::<f64>() < config.drop {
+            return;
+        }
+
+        let jitter = (rng.gen::<f64>() * 2.0 - 1.0) * config.jitter;
+        // websockets never reorder messages, so jitter can only hold back the ones behind it
+        let deliver_at = (now + (config.latency + jitter).max(0.0)).max(self.last_delivery);
+        self.last_delivery = deliver_at;
+        self.queue.push_back((deliver_at, bytes));
+    }
+
+    fn take_due(&mut self, now: f64) -> Vec<Vec<u8>> {
+        let mut due = Vec::new();
+        while let Some((deliver_at, _)) = self.queue.front() {
+            if *deliver_at > now {
+                break;
+            }
+            due.push(self.queue.pop_front().unwrap().1);
+        }
+        due
+    }
+}
+
+#[derive(Debug)]
+pub struct NetSim {
+    config: NetSimConfig,
+    rng: SmallRng,
+    incoming: Link,
+    outgoing: Link,
+}
+
+impl NetSim {
+    pub fn new(config: NetSimConfig, seed: u64) -> Self {
+        NetSim {
+            config,
+            rng: SmallRng::seed_from_u64(seed),
+            incoming: Link::default(),
+            outgoing: Link::default(),
+        }
+    }
+
+    pub fn send(&mut self, now: f64, bytes: Vec<u8>) {
+        self.outgoing.push(&self.config, &mut self.rng, now, bytes);
+    }
+
+    pub fn receive(&mut self, now: f64, bytes: Vec<u8>) {
+        self.incoming.push(&self.config, &mut self.rng, now, bytes);
+    }
+
+    /// messages that should now be sent to the server
+    pub fn take_outgoing(&mut self, now: f64) -> Vec<Vec<u8>> {
+        self.outgoing.take_due(now)
+    }
+
+    /// messages that should now be handled as if they just arrived from the server
+    pub fn take_incoming(&mut self, now: f64) -> Vec<Vec<u8>> {
+        self.incoming.take_due(now)
+    }
+}
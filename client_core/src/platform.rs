@@ -0,0 +1,119 @@
+use alloc::{format, string::String, vec::Vec};
+use cibo_online::{
+    client::{valid_account_key, ClientMessage, PlayerSettings, ACCOUNT_KEY_LENGTH},
+    ColorMode, Critter,
+};
+
+/// what the game needs from the platform it runs on
+pub trait Platform {
+    /// open a new connection to the server, replacing the old one if there is any. the platform
+    /// reports back through [`Frontend::connected`](crate::Frontend::connected) and
+    /// [`Frontend::disconnected`](crate::Frontend::disconnected)
+    fn connect(&mut self);
+    /// send a message to the server
+    fn send(&mut self, bytes: &[u8]);
+
+    /// something saved with `save`, possibly in an earlier session
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&mut self, key: &str, value: &str);
+
+    /// a parameter the game was started with, e.g. `?admin=<token>` in the page url
+    fn param(&self, name: &str) -> Option<String>;
+
+    /// a random number in `0.0..1.0`
+    fn random(&mut self) -> f64;
+    /// fill `bytes` from a cryptographically secure source, for secrets like the account key
+    fn secure_random(&mut self, bytes: &mut [u8]);
+
+    fn log(&mut self, message: &str);
+    /// show a message the player has to acknowledge, e.g. why they couldn't join
+    fn alert(&mut self, message: &str);
+
+    /// short beep for when the player gets mentioned
+    fn play_ping(&mut self) {}
+    /// a critter near the player called out, `volume` goes from 0 to 1 depending on how close it is
+    #[allow(unused_variables)]
+    fn play_ambient(&mut self, critter: Critter, volume: f32) {}
+
+    #[allow(unused_variables)]
+    fn open_link(&mut self, url: &str) {}
+
+    /// load something from the server over http, e.g. `/rules` or an asset pack. the body is
+    /// handed back through [`Frontend::fetched`](crate::Frontend::fetched) once it arrived
+    #[allow(unused_variables)]
+    fn fetch(&mut self, path: &str) {}
+
+    /// called for every message, e.g. to keep network stats
+    #[allow(unused_variables)]
+    fn message_sent(&mut self, kind: &'static str, bytes: usize) {}
+    #[allow(unused_variables)]
+    fn message_received(&mut self, kind: &'static str, bytes: usize) {}
+}
+
+pub(crate) const HUD_LAYOUT_KEY: &str = "hud_layout";
+pub(crate) const TUTORIAL_KEY: &str = "tutorial";
+pub(crate) const SETTINGS_KEY: &str = "settings";
+/// only the color mode used to be saved, under its own key
+const COLOR_MODE_KEY: &str = "color_mode";
+const ACCOUNT_KEY: &str = "account";
+
+/// binary data saved by the platform, stored hex encoded
+pub(crate) fn load_bytes(platform: &dyn Platform, key: &str) -> Option<Vec<u8>> {
+    let hex = platform.load(key)?;
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub(crate) fn save_bytes(platform: &mut dyn Platform, key: &str, bytes: &[u8]) {
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    platform.save(key, &hex);
+}
+
+/// the settings from the last session on this device. the server replaces them with the ones stored for the account
+pub(crate) fn load_settings(platform: &dyn Platform) -> PlayerSettings {
+    if let Some(settings) =
+        load_bytes(platform, SETTINGS_KEY).and_then(|bytes| PlayerSettings::from_bytes(&bytes).ok())
+    {
+        return settings;
+    }
+
+    let color_mode = platform
+        .load(COLOR_MODE_KEY)
+        .and_then(|name| ColorMode::from_name(&name))
+        .unwrap_or_default();
+    PlayerSettings {
+        color_mode,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn save_settings(platform: &mut dyn Platform, settings: &PlayerSettings) {
+    if let Ok(bytes) = settings.to_bytes() {
+        save_bytes(platform, SETTINGS_KEY, &bytes);
+    }
+}
+
+/// random key the players settings are stored under on the server.
+/// passing it as the `account` parameter on another device carries the settings over
+pub(crate) fn account_key(platform: &mut dyn Platform) -> String {
+    let key = platform
+        .param(ACCOUNT_KEY)
+        .filter(|key| valid_account_key(key))
+        .or_else(|| platform.load(ACCOUNT_KEY))
+        .unwrap_or_else(|| {
+            // the key is all it takes to play as someone, so it can't come from `random`
+            let mut bytes = [0; ACCOUNT_KEY_LENGTH / 2];
+            platform.secure_random(&mut bytes);
+            bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+        });
+    platform.save(ACCOUNT_KEY, &key);
+    key
+}
+
+pub(crate) fn send(platform: &mut dyn Platform, message: &ClientMessage) {
+    let bytes = message.to_bytes().unwrap();
+    platform.message_sent(message.kind(), bytes.len());
+    platform.send(&bytes);
+}
@@ -0,0 +1,323 @@
+//! everything that went into the frontend during a session: input, messages from the server and
+//! fetched files, so a bug report can come with a recording that plays back the same way. started
+//! with the `record` parameter, e.g. `?record=1` in the page url.
+//!
+//! [`replay`] runs a recording through a new frontend without a server or a window, e.g. from a
+//! test that checks the glitch is gone.
+
+use crate::{
+    platform::{HUD_LAYOUT_KEY, SETTINGS_KEY, TUTORIAL_KEY},
+    Frontend, Platform,
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use monos_gfx::{
+    input::{Input, Key, KeyEvent, KeyState, RawKey},
+    Framebuffer, Position,
+};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// recordings stop growing past this, the start of a session is needed to replay the rest of it
+const MAX_RECORDING_BYTES: usize = 16 * 1024 * 1024;
+/// saved values the frontend reads when the game starts. the account key is left out on purpose
+const RECORDED_KEYS: [&str; 3] = [SETTINGS_KEY, HUD_LAYOUT_KEY, TUTORIAL_KEY];
+
+/// the keys the clients send, the game doesn't know any others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum RecordedKey {
+    Unicode(char),
+    Return,
+    Tab,
+    Backspace,
+    Escape,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    /// F1 to F10, there is no F5 binding
+    F(u8),
+}
+
+impl RecordedKey {
+    fn from_key(key: Key) -> Option<Self> {
+        Some(match key {
+            Key::Unicode(c) => RecordedKey::Unicode(c),
+            Key::RawKey(RawKey::Return) => RecordedKey::Return,
+            Key::RawKey(RawKey::Tab) => RecordedKey::Tab,
+            Key::RawKey(RawKey::Backspace) => RecordedKey::Backspace,
+            Key::RawKey(RawKey::Escape) => RecordedKey::Escape,
+            Key::RawKey(RawKey::ArrowUp) => RecordedKey::ArrowUp,
+            Key::RawKey(RawKey::ArrowDown) => RecordedKey::ArrowDown,
+            Key::RawKey(RawKey::ArrowLeft) => RecordedKey::ArrowLeft,
+            Key::RawKey(RawKey::ArrowRight) => RecordedKey::ArrowRight,
+            Key::RawKey(RawKey::F1) => RecordedKey::F(1),
+            Key::RawKey(RawKey::F2) => RecordedKey::F(2),
+            Key::RawKey(RawKey::F3) => RecordedKey::F(3),
+            Key::RawKey(RawKey::F4) => RecordedKey::F(4),
+            Key::RawKey(RawKey::F6) => RecordedKey::F(6),
+            Key::RawKey(RawKey::F7) => RecordedKey::F(7),
+            Key::RawKey(RawKey::F8) => RecordedKey::F(8),
+            Key::RawKey(RawKey::F9) => RecordedKey::F(9),
+            Key::RawKey(RawKey::F10) => RecordedKey::F(10),
+            _ => return None,
+        })
+    }
+
+    fn to_key(self) -> Option<Key> {
+        Some(match self {
+            RecordedKey::Unicode(c) => Key::Unicode(c),
+            RecordedKey::Return => Key::RawKey(RawKey::Return),
+            RecordedKey::Tab => Key::RawKey(RawKey::Tab),
+            RecordedKey::Backspace => Key::RawKey(RawKey::Backspace),
+            RecordedKey::Escape => Key::RawKey(RawKey::Escape),
+            RecordedKey::ArrowUp => Key::RawKey(RawKey::ArrowUp),
+            RecordedKey::ArrowDown => Key::RawKey(RawKey::ArrowDown),
+            RecordedKey::ArrowLeft => Key::RawKey(RawKey::ArrowLeft),
+            RecordedKey::ArrowRight => Key::RawKey(RawKey::ArrowRight),
+            RecordedKey::F(1) => Key::RawKey(RawKey::F1),
+            RecordedKey::F(2) => Key::RawKey(RawKey::F2),
+            RecordedKey::F(3) => Key::RawKey(RawKey::F3),
+            RecordedKey::F(4) => Key::RawKey(RawKey::F4),
+            RecordedKey::F(6) => Key::RawKey(RawKey::F6),
+            RecordedKey::F(7) => Key::RawKey(RawKey::F7),
+            RecordedKey::F(8) => Key::RawKey(RawKey::F8),
+            RecordedKey::F(9) => Key::RawKey(RawKey::F9),
+            RecordedKey::F(10) => Key::RawKey(RawKey::F10),
+            RecordedKey::F(_) => return None,
+        })
+    }
+}
+
+/// the input as the frontend saw it at the start of a frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedInput {
+    /// the key and whether it went down
+    keys: Vec<(RecordedKey, bool)>,
+    mouse: (i64, i64),
+    scroll: i64,
+    left: bool,
+    right: bool,
+}
+
+impl RecordedInput {
+    fn capture(input: &Input) -> Self {
+        RecordedInput {
+            keys: input
+                .keyboard
+                .iter()
+                .filter_map(|event| {
+                    let key = RecordedKey::from_key(event.key)?;
+                    Some((key, event.state == KeyState::Down))
+                })
+                .collect(),
+            mouse: (input.mouse.position.x, input.mouse.position.y),
+            scroll: input.mouse.scroll,
+            left: input.mouse.left_button.pressed,
+            right: input.mouse.right_button.pressed,
+        }
+    }
+
+    fn apply(&self, input: &mut Input) {
+        for (key, down) in &self.keys {
+            if let Some(key) = key.to_key() {
+                input.keyboard.push_back(KeyEvent {
+                    key,
+                    state: if *down { KeyState::Down } else { KeyState::Up },
+                });
+            }
+        }
+        input.mouse.position = Position::new(self.mouse.0, self.mouse.1);
+        input.mouse.scroll = self.scroll;
+        input.mouse.left_button.update(self.left);
+        input.mouse.right_button.update(self.right);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum RecordedEvent {
+    Connected,
+    Disconnected,
+    ServerBytes(Vec<u8>),
+    Fetched { path: String, body: Vec<u8> },
+    LowPower(bool),
+    Update { delta_ms: u64, input: RecordedInput },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recording {
+    /// what the platform had saved when the recording started
+    saved: Vec<(String, String)>,
+    /// the size of the framebuffer of the first frame, replays should use the same
+    pub screen: Option<(u32, u32)>,
+    events: Vec<RecordedEvent>,
+    bytes: usize,
+    /// the recording hit `MAX_RECORDING_BYTES`, what happened after it is missing
+    pub truncated: bool,
+}
+
+impl Recording {
+    pub(crate) fn new(platform: &dyn Platform) -> Self {
+        Recording {
+            saved: RECORDED_KEYS
+                .iter()
+                .filter_map(|key| Some((key.to_string(), platform.load(key)?)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, postcard::Error> {
+        postcard::from_bytes(bytes)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
+        postcard::to_allocvec(self)
+    }
+
+    /// how many events were recorded
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    fn push(&mut self, event: RecordedEvent, bytes: usize) {
+        if self.truncated {
+            return;
+        }
+        if self.bytes + bytes > MAX_RECORDING_BYTES {
+            self.truncated = true;
+            return;
+        }
+        self.bytes += bytes;
+        self.events.push(event);
+    }
+
+    pub(crate) fn connected(&mut self) {
+        self.push(RecordedEvent::Connected, 1);
+    }
+
+    pub(crate) fn disconnected(&mut self) {
+        self.push(RecordedEvent::Disconnected, 1);
+    }
+
+    pub(crate) fn server_bytes(&mut self, bytes: &[u8]) {
+        self.push(RecordedEvent::ServerBytes(bytes.to_vec()), bytes.len());
+    }
+
+    pub(crate) fn fetched(&mut self, path: &str, body: &[u8]) {
+        let event = RecordedEvent::Fetched {
+            path: path.to_string(),
+            body: body.to_vec(),
+        };
+        self.push(event, path.len() + body.len());
+    }
+
+    pub(crate) fn low_power(&mut self, low_power: bool) {
+        self.push(RecordedEvent::LowPower(low_power), 1);
+    }
+
+    pub(crate) fn update(&mut self, delta_ms: u64, input: &Input, fb: &Framebuffer) {
+        let dimensions = fb.dimensions();
+        self.screen
+            .get_or_insert((dimensions.width, dimensions.height));
+        let input = RecordedInput::capture(input);
+        let bytes = 32 + input.keys.len() * 8;
+        self.push(RecordedEvent::Update { delta_ms, input }, bytes);
+    }
+}
+
+/// a platform for replays: nothing leaves it, saved values come from the recording and random
+/// numbers from a fixed seed
+#[derive(Debug)]
+pub struct ReplayPlatform {
+    saved: Vec<(String, String)>,
+    rng: SmallRng,
+    /// everything the frontend sent to the server
+    pub sent: Vec<Vec<u8>>,
+    /// everything the frontend logged or alerted, to look for errors
+    pub log: Vec<String>,
+}
+
+impl ReplayPlatform {
+    pub fn new(recording: &Recording) -> Self {
+        ReplayPlatform {
+            saved: recording.saved.clone(),
+            rng: SmallRng::seed_from_u64(0),
+            sent: Vec::new(),
+            log: Vec::new(),
+        }
+    }
+}
+
+impl Platform for ReplayPlatform {
+    fn connect(&mut self) {}
+
+    fn send(&mut self, bytes: &[u8]) {
+        self.sent.push(bytes.to_vec());
+    }
+
+    fn load(&self, key: &str) -> Option<String> {
+        self.saved
+            .iter()
+            .find(|(saved, _)| saved == key)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        self.saved.retain(|(saved, _)| saved != key);
+        self.saved.push((key.to_string(), value.to_string()));
+    }
+
+    fn param(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    fn random(&mut self) -> f64 {
+        self.rng.gen()
+    }
+
+    // replays only need the same key every time
+    fn secure_random(&mut self, bytes: &mut [u8]) {
+        self.rng.fill(bytes);
+    }
+
+    fn log(&mut self, message: &str) {
+        self.log.push(message.to_string());
+    }
+
+    fn alert(&mut self, message: &str) {
+        self.log.push(message.to_string());
+    }
+}
+
+/// run a recording through a new frontend, frame by frame. `frame` is called after every frame
+/// with the frontend and the framebuffer it was drawn to, e.g. to check the state of the game
+pub fn replay(
+    recording: &Recording,
+    platform: &mut ReplayPlatform,
+    fb: &mut Framebuffer,
+    mut frame: impl FnMut(&Frontend, &Framebuffer),
+) -> Frontend {
+    let mut frontend = Frontend::new(platform);
+    for event in &recording.events {
+        match event {
+            RecordedEvent::Connected => frontend.connected(),
+            RecordedEvent::Disconnected => frontend.disconnected(),
+            RecordedEvent::ServerBytes(bytes) => frontend.handle_server_bytes(bytes, platform),
+            RecordedEvent::Fetched { path, body } => frontend.fetched(path, body, platform),
+            RecordedEvent::LowPower(low_power) => frontend.set_low_power(*low_power),
+            RecordedEvent::Update { delta_ms, input } => {
+                input.apply(&mut frontend.input);
+                frontend.update(*delta_ms, fb, platform);
+                frame(&frontend, fb);
+            }
+        }
+    }
+    frontend
+}
@@ -0,0 +1,289 @@
+//! native client for playing outside of the browser. draws into a software framebuffer just like
+//! the web client does, everything around the game itself lives in `client_core`.
+
+mod net;
+mod platform;
+
+use std::{num::NonZeroU32, rc::Rc, time::Instant};
+
+use client_core::{Frontend, Platform};
+use monos_gfx::{
+    input::{Key, KeyEvent, KeyState, RawKey},
+    Dimension, Framebuffer, FramebufferFormat, Position,
+};
+use net::NetEvent;
+use platform::DesktopPlatform;
+use winit::{
+    dpi::LogicalSize,
+    event::{ElementState, Event, MouseButton, MouseScrollDelta, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    keyboard::{Key as WinitKey, NamedKey},
+    window::WindowBuilder,
+};
+
+const DEFAULT_SERVER: &str = "ws://localhost:8080/ws";
+
+/// the game is drawn at a low resolution and scaled up by whole pixels, same as in the browser
+fn scale_for(width: u32, height: u32) -> u32 {
+    let scale_x = width as f32 / 480.0;
+    let scale_y = height as f32 / 240.0;
+    (scale_x.max(scale_y).floor() as u32).max(1)
+}
+
+fn winit_key_to_key(key: &WinitKey, ctrl: bool) -> Option<Key> {
+    match key {
+        // ctrl+z, ctrl+y and ctrl+b are passed on as their ascii control characters
+        WinitKey::Character(c) if ctrl && c.eq_ignore_ascii_case("z") => {
+            Some(Key::Unicode('\u{1a}'))
+        }
+        WinitKey::Character(c) if ctrl && c.eq_ignore_ascii_case("y") => {
+            Some(Key::Unicode('\u{19}'))
+        }
+        WinitKey::Character(c) if ctrl && c.eq_ignore_ascii_case("b") => {
+            Some(Key::Unicode('\u{2}'))
+        }
+        WinitKey::Character(c) => {
+            let mut chars = c.chars();
+            match (chars.next(), chars.next()) {
+                (Some(char), None) => Some(Key::Unicode(char)),
+                _ => None,
+            }
+        }
+        WinitKey::Named(NamedKey::Space) => Some(Key::Unicode(' ')),
+        WinitKey::Named(NamedKey::ArrowUp) => Some(Key::RawKey(RawKey::ArrowUp)),
+        WinitKey::Named(NamedKey::ArrowDown) => Some(Key::RawKey(RawKey::ArrowDown)),
+        WinitKey::Named(NamedKey::ArrowLeft) => Some(Key::RawKey(RawKey::ArrowLeft)),
+        WinitKey::Named(NamedKey::ArrowRight) => Some(Key::RawKey(RawKey::ArrowRight)),
+        WinitKey::Named(NamedKey::Backspace) => Some(Key::RawKey(RawKey::Backspace)),
+        WinitKey::Named(NamedKey::Escape) => Some(Key::RawKey(RawKey::Escape)),
+        WinitKey::Named(NamedKey::Enter) => Some(Key::RawKey(RawKey::Return)),
+        WinitKey::Named(NamedKey::Tab) => Some(Key::RawKey(RawKey::Tab)),
+        WinitKey::Named(NamedKey::F1) => Some(Key::RawKey(RawKey::F1)),
+        WinitKey::Named(NamedKey::F2) => Some(Key::RawKey(RawKey::F2)),
+        WinitKey::Named(NamedKey::F3) => Some(Key::RawKey(RawKey::F3)),
+        WinitKey::Named(NamedKey::F4) => Some(Key::RawKey(RawKey::F4)),
+        WinitKey::Named(NamedKey::F6) => Some(Key::RawKey(RawKey::F6)),
+        WinitKey::Named(NamedKey::F7) => Some(Key::RawKey(RawKey::F7)),
+        WinitKey::Named(NamedKey::F8) => Some(Key::RawKey(RawKey::F8)),
+        WinitKey::Named(NamedKey::F9) => Some(Key::RawKey(RawKey::F9)),
+        WinitKey::Named(NamedKey::F10) => Some(Key::RawKey(RawKey::F10)),
+        _ => None,
+    }
+}
+
+/// the framebuffer the game draws into, sized to the window divided by the scale
+struct GameBuffer {
+    data: Vec<u8>,
+    dimensions: Dimension,
+    scale: u32,
+}
+
+impl GameBuffer {
+    fn new(window_width: u32, window_height: u32) -> Self {
+        let scale = scale_for(window_width, window_height);
+        let dimensions = Dimension::new(
+            (window_width / scale).max(1),
+            (window_height / scale).max(1),
+        );
+        GameBuffer {
+            data: vec![0; (dimensions.width * dimensions.height * 4) as usize],
+            dimensions,
+            scale,
+        }
+    }
+
+    fn framebuffer(&mut self) -> Framebuffer {
+        let format = FramebufferFormat {
+            bytes_per_pixel: 4,
+            stride: self.dimensions.width as u64,
+            r_position: 0,
+            g_position: 1,
+            b_position: 2,
+            a_position: Some(3),
+        };
+        Framebuffer::new(&mut self.data, self.dimensions, format)
+    }
+
+    /// scale the frame up into the window surface, which expects `0RGB` pixels
+    fn present(&self, target: &mut [u32], window_width: u32, window_height: u32) {
+        let width = self.dimensions.width as usize;
+        let height = self.dimensions.height as usize;
+        let scale = self.scale as usize;
+        for (y, row) in target
+            .chunks_exact_mut(window_width as usize)
+            .take(window_height as usize)
+            .enumerate()
+        {
+            let src_y = y / scale;
+            if src_y >= height {
+                row.fill(0);
+                continue;
+            }
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let src_x = x / scale;
+                *pixel = if src_x < width {
+                    let i = (src_y * width + src_x) * 4;
+                    let [r, g, b] = [self.data[i], self.data[i + 1], self.data[i + 2]];
+                    (r as u32) << 16 | (g as u32) << 8 | b as u32
+                } else {
+                    0
+                };
+            }
+        }
+    }
+}
+
+/// write the session to the file given with `--record`, `recording.cibo` if there was none
+fn save_recording(frontend: &Frontend, platform: &DesktopPlatform) {
+    let Some(recording) = frontend.recording() else {
+        return;
+    };
+    let path = platform
+        .param("record")
+        .filter(|path| !path.is_empty())
+        .unwrap_or_else(|| String::from("recording.cibo"));
+    let result = recording
+        .to_bytes()
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| std::fs::write(&path, bytes).map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => println!("saved the recording to {}", path),
+        Err(e) => eprintln!("could not save the recording to {}: {}", path, e),
+    }
+}
+
+fn main() {
+    let args = platform::parse_args(std::env::args().skip(1));
+    let server = args
+        .iter()
+        .find(|(name, _)| name == "server")
+        .map(|(_, value)| value.clone())
+        .unwrap_or_else(|| String::from(DEFAULT_SERVER));
+
+    let (incoming_tx, incoming) = std::sync::mpsc::channel();
+    let mut platform = DesktopPlatform::new(server, incoming_tx, args);
+    let mut frontend = Frontend::new(&mut platform);
+
+    let event_loop = EventLoop::new().expect("failed to create the event loop");
+    let window = Rc::new(
+        WindowBuilder::new()
+            .with_title("Cibo Online!")
+            .with_inner_size(LogicalSize::new(960.0, 480.0))
+            .build(&event_loop)
+            .expect("failed to open a window"),
+    );
+    let context = softbuffer::Context::new(window.clone()).expect("failed to create the surface");
+    let mut surface =
+        softbuffer::Surface::new(&context, window.clone()).expect("failed to create the surface");
+
+    let size = window.inner_size();
+    let mut game_buffer = GameBuffer::new(size.width, size.height);
+    let mut ctrl = false;
+    let mut last_frame = Instant::now();
+
+    event_loop
+        .run(move |event, elwt| {
+            elwt.set_control_flow(ControlFlow::Poll);
+
+            let Event::WindowEvent { event, .. } = event else {
+                if let Event::AboutToWait = event {
+                    window.request_redraw();
+                }
+                return;
+            };
+
+            match event {
+                WindowEvent::CloseRequested => {
+                    save_recording(&frontend, &platform);
+                    elwt.exit();
+                }
+                WindowEvent::Resized(size) => {
+                    game_buffer = GameBuffer::new(size.width, size.height);
+                }
+                WindowEvent::ModifiersChanged(modifiers) => {
+                    ctrl = modifiers.state().control_key();
+                }
+                WindowEvent::KeyboardInput { event, .. } => {
+                    if let Some(key) = winit_key_to_key(&event.logical_key, ctrl) {
+                        let state = match event.state {
+                            ElementState::Pressed => KeyState::Down,
+                            ElementState::Released => KeyState::Up,
+                        };
+                        frontend.input.keyboard.push_back(KeyEvent { key, state });
+                    }
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    let scale = game_buffer.scale as f64;
+                    frontend.input.mouse.position =
+                        Position::new((position.x / scale) as i64, (position.y / scale) as i64);
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    // same direction and rough step size as the browser
+                    let lines = match delta {
+                        MouseScrollDelta::LineDelta(_, y) => -y as i64,
+                        MouseScrollDelta::PixelDelta(position) => -(position.y / 50.0) as i64,
+                    };
+                    frontend.input.mouse.scroll += lines;
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    let down = state == ElementState::Pressed;
+                    match button {
+                        MouseButton::Left => frontend.input.mouse.left_button.update(down),
+                        MouseButton::Right => frontend.input.mouse.right_button.update(down),
+                        _ => {}
+                    }
+                }
+                WindowEvent::RedrawRequested => {
+                    loop {
+                        match incoming.try_recv() {
+                            Ok(NetEvent::Message(bytes)) => {
+                                frontend.handle_server_bytes(&bytes, &mut platform)
+                            }
+                            Ok(NetEvent::Fetched { path, body }) => {
+                                frontend.fetched(&path, &body, &mut platform)
+                            }
+                            Ok(NetEvent::Opened) => frontend.connected(),
+                            Ok(NetEvent::Closed(reason)) => {
+                                eprintln!("{}", reason);
+                                frontend.disconnected();
+                            }
+                            Err(_) => break,
+                        }
+                    }
+
+                    let now = Instant::now();
+                    let delta_ms = now.duration_since(last_frame).as_millis() as u64;
+                    last_frame = now;
+
+                    let mut fb = game_buffer.framebuffer();
+                    fb.clear();
+                    fb.clear_alpha();
+                    frontend.update(delta_ms, &mut fb, &mut platform);
+
+                    let size = window.inner_size();
+                    let (Some(width), Some(height)) =
+                        (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+                    else {
+                        return;
+                    };
+                    if let Err(e) = surface.resize(width, height) {
+                        eprintln!("failed to resize the surface: {}", e);
+                        return;
+                    }
+                    let mut buffer = match surface.buffer_mut() {
+                        Ok(buffer) => buffer,
+                        Err(e) => {
+                            eprintln!("failed to get the surface buffer: {}", e);
+                            return;
+                        }
+                    };
+                    game_buffer.present(&mut buffer, size.width, size.height);
+                    if let Err(e) = buffer.present() {
+                        eprintln!("failed to present the frame: {}", e);
+                    }
+                }
+                _ => {}
+            }
+        })
+        .expect("event loop failed");
+}
@@ -0,0 +1,102 @@
+use futures_util::{SinkExt, StreamExt};
+use std::{io::Read, sync::mpsc};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio_tungstenite::tungstenite::Message;
+
+pub enum NetEvent {
+    Opened,
+    Message(Vec<u8>),
+    Closed(String),
+    /// response to a `Platform::fetch`
+    Fetched {
+        path: String,
+        body: Vec<u8>,
+    },
+}
+
+/// connect to the server on a separate thread. the game itself stays on the main thread,
+/// messages are passed back and forth through channels
+pub fn connect(url: String, incoming_tx: mpsc::Sender<NetEvent>) -> UnboundedSender<Vec<u8>> {
+    let (outgoing_tx, mut outgoing_rx) = unbounded_channel::<Vec<u8>>();
+
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the network runtime");
+
+        runtime.block_on(async move {
+            let ws = match tokio_tungstenite::connect_async(&url).await {
+                Ok((ws, _)) => ws,
+                Err(e) => {
+                    let _ = incoming_tx.send(NetEvent::Closed(format!(
+                        "connection to {} failed: {}",
+                        url, e
+                    )));
+                    return;
+                }
+            };
+            if incoming_tx.send(NetEvent::Opened).is_err() {
+                return;
+            }
+            let (mut write, mut read) = ws.split();
+
+            let reason = loop {
+                tokio::select! {
+                    bytes = outgoing_rx.recv() => match bytes {
+                        Some(bytes) => {
+                            if let Err(e) = write.send(Message::Binary(bytes)).await {
+                                break format!("connection lost: {}", e);
+                            }
+                        }
+                        // the game was closed or replaced the connection
+                        None => return,
+                    },
+                    message = read.next() => match message {
+                        Some(Ok(Message::Binary(bytes))) => {
+                            if incoming_tx.send(NetEvent::Message(bytes)).is_err() {
+                                return;
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | None => break String::from("the server closed the connection"),
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => break format!("connection lost: {}", e),
+                    },
+                }
+            };
+            let _ = incoming_tx.send(NetEvent::Closed(reason));
+        });
+    });
+
+    outgoing_tx
+}
+
+/// the http address of the server behind the websocket url, e.g. `https://example.com` for `wss://example.com/ws`
+pub fn http_base(ws_url: &str) -> String {
+    let url = if let Some(rest) = ws_url.strip_prefix("wss://") {
+        format!("https://{}", rest)
+    } else if let Some(rest) = ws_url.strip_prefix("ws://") {
+        format!("http://{}", rest)
+    } else {
+        ws_url.to_string()
+    };
+    url.strip_suffix("/ws").unwrap_or(&url).to_string()
+}
+
+/// load a file from the server in the background
+pub fn fetch(base: &str, path: &str, incoming_tx: mpsc::Sender<NetEvent>) {
+    let url = format!("{}{}", base, path);
+    let path = path.to_string();
+    std::thread::spawn(move || {
+        match ureq::get(&url).call().map(|response| {
+            let mut body = Vec::new();
+            response.into_reader().read_to_end(&mut body).map(|_| body)
+        }) {
+            Ok(Ok(body)) => {
+                let _ = incoming_tx.send(NetEvent::Fetched { path, body });
+            }
+            Ok(Err(e)) => eprintln!("failed to read {}: {}", url, e),
+            Err(e) => eprintln!("failed to fetch {}: {}", url, e),
+        }
+    });
+}
@@ -0,0 +1,158 @@
+use std::{io::Write, path::PathBuf, sync::mpsc};
+
+use crate::net::{self, NetEvent};
+use client_core::Platform;
+use tokio::sync::mpsc::UnboundedSender;
+
+pub struct DesktopPlatform {
+    /// the websocket url of the server
+    server: String,
+    /// `None` until the frontend asked for the first connection
+    outgoing: Option<UnboundedSender<Vec<u8>>>,
+    /// fetched files are passed back to the main loop along with the messages from the server
+    incoming: mpsc::Sender<NetEvent>,
+    /// e.g. `https://example.com`, for fetching things that aren't sent over the websocket
+    http_base: String,
+    /// `--name value` pairs from the command line
+    args: Vec<(String, String)>,
+    data_dir: Option<PathBuf>,
+}
+
+impl DesktopPlatform {
+    pub fn new(
+        server: String,
+        incoming: mpsc::Sender<NetEvent>,
+        args: Vec<(String, String)>,
+    ) -> Self {
+        let data_dir = data_dir();
+        if let Some(dir) = &data_dir {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                eprintln!("could not create {}: {}", dir.display(), e);
+            }
+        }
+
+        DesktopPlatform {
+            http_base: net::http_base(&server),
+            server,
+            outgoing: None,
+            incoming,
+            args,
+            data_dir,
+        }
+    }
+}
+
+/// where settings are kept between sessions, e.g. `~/.local/share/cibo_online`
+fn data_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join("Library")
+                .join("Application Support")
+        })
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".local").join("share"))
+            })
+    };
+    base.map(|base| base.join("cibo_online"))
+}
+
+/// parse `--name value` pairs, anything else is ignored
+pub fn parse_args(args: impl Iterator<Item = String>) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        let Some(name) = arg.strip_prefix("--") else {
+            continue;
+        };
+        let value = match args.peek() {
+            Some(value) if !value.starts_with("--") => args.next().unwrap(),
+            _ => String::new(),
+        };
+        parsed.push((name.to_string(), value));
+    }
+    parsed
+}
+
+impl Platform for DesktopPlatform {
+    fn connect(&mut self) {
+        // dropping the old sender shuts down its connection without reporting it as closed
+        self.outgoing = Some(net::connect(self.server.clone(), self.incoming.clone()));
+    }
+
+    fn send(&mut self, bytes: &[u8]) {
+        // only fails once the connection is gone, which the main loop notices on its own
+        if let Some(outgoing) = &self.outgoing {
+            let _ = outgoing.send(bytes.to_vec());
+        }
+    }
+
+    fn load(&self, key: &str) -> Option<String> {
+        let path = self.data_dir.as_ref()?.join(key);
+        std::fs::read_to_string(path).ok()
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        let Some(dir) = &self.data_dir else {
+            return;
+        };
+        if let Err(e) = std::fs::write(dir.join(key), value) {
+            eprintln!("could not save {}: {}", key, e);
+        }
+    }
+
+    fn param(&self, name: &str) -> Option<String> {
+        self.args
+            .iter()
+            .find(|(arg, _)| arg == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn random(&mut self) -> f64 {
+        rand::random()
+    }
+
+    fn secure_random(&mut self, bytes: &mut [u8]) {
+        use rand::RngCore;
+        rand::rngs::OsRng.fill_bytes(bytes);
+    }
+
+    fn log(&mut self, message: &str) {
+        eprintln!("{}", message);
+    }
+
+    fn alert(&mut self, message: &str) {
+        eprintln!("{}", message);
+    }
+
+    fn play_ping(&mut self) {
+        // the terminal bell, if the client was started from one
+        print!("\x07");
+        let _ = std::io::stdout().flush();
+    }
+
+    fn fetch(&mut self, path: &str) {
+        net::fetch(&self.http_base, path, self.incoming.clone());
+    }
+
+    fn open_link(&mut self, url: &str) {
+        let result = if cfg!(windows) {
+            std::process::Command::new("cmd")
+                .args(["/c", "start", "", url])
+                .spawn()
+        } else if cfg!(target_os = "macos") {
+            std::process::Command::new("open").arg(url).spawn()
+        } else {
+            std::process::Command::new("xdg-open").arg(url).spawn()
+        };
+        if let Err(e) = result {
+            eprintln!("could not open {}: {}", url, e);
+        }
+    }
+}
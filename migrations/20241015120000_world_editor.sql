@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS editor_tokens (
+    id         INTEGER   PRIMARY KEY,
+    token      TEXT      UNIQUE       NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS world_objects (
+    id     INTEGER PRIMARY KEY,
+    object TEXT    NOT NULL,
+    x      INTEGER NOT NULL,
+    y      INTEGER NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS paintings (
+    id     INTEGER PRIMARY KEY,
+    artist TEXT    NOT NULL,
+    width  INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    data   BLOB    NOT NULL
+);
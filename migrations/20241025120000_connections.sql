@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS connections (
+    id        INTEGER PRIMARY KEY,
+    ip        TEXT    NOT NULL,
+    name      TEXT    NOT NULL,
+    client_id INTEGER NOT NULL,
+    joined_at INTEGER NOT NULL,
+    left_at   INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS connections_ip ON connections (ip);
+CREATE INDEX IF NOT EXISTS connections_name ON connections (name COLLATE NOCASE);
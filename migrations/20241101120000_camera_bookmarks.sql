@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS camera_bookmarks (
+    id   INTEGER PRIMARY KEY,
+    name TEXT    UNIQUE NOT NULL,
+    x    INTEGER NOT NULL,
+    y    INTEGER NOT NULL
+);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS player_settings (
+    account    TEXT    PRIMARY KEY,
+    settings   BLOB    NOT NULL,
+    updated_at INTEGER NOT NULL
+);
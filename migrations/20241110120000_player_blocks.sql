@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS player_blocks (
+    account    TEXT    NOT NULL,
+    blocked    TEXT    NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (account, blocked)
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS protected_names (
+    name       TEXT      PRIMARY KEY,
+    account    TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO protected_names (name) VALUES ('admin'), ('sakanaa');
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS registered_accounts (
+    account       TEXT    PRIMARY KEY,
+    registered_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS guest_time (
+    ip        TEXT    NOT NULL,
+    day       TEXT    NOT NULL,
+    played_ms INTEGER NOT NULL,
+    PRIMARY KEY (ip, day)
+);
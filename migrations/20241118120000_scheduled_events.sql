@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS scheduled_events (
+    id        INTEGER PRIMARY KEY AUTOINCREMENT,
+    name      TEXT    NOT NULL,
+    starts_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS event_rsvps (
+    event_id INTEGER NOT NULL REFERENCES scheduled_events (id) ON DELETE CASCADE,
+    voter    TEXT    NOT NULL,
+    PRIMARY KEY (event_id, voter)
+);
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS polls (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    question   TEXT    NOT NULL,
+    -- one option per line
+    options    TEXT    NOT NULL,
+    created_at INTEGER NOT NULL,
+    closed_at  INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS poll_votes (
+    poll_id INTEGER NOT NULL REFERENCES polls (id),
+    voter   TEXT    NOT NULL,
+    option  INTEGER NOT NULL,
+    PRIMARY KEY (poll_id, voter)
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS leaderboard (
+    game  TEXT    NOT NULL,
+    name  TEXT    NOT NULL,
+    score INTEGER NOT NULL,
+    PRIMARY KEY (game, name)
+);
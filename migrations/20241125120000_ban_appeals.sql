@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS ban_appeals (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    ip         TEXT    NOT NULL,
+    message    TEXT    NOT NULL,
+    contact    TEXT    NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS ban_appeals_ip ON ban_appeals (ip);
@@ -0,0 +1,2 @@
+ALTER TABLE users ADD COLUMN theme TEXT NOT NULL DEFAULT 'light';
+ALTER TABLE users ADD COLUMN compact BOOLEAN NOT NULL DEFAULT FALSE;
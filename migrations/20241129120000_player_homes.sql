@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS player_homes (
+    account    TEXT    PRIMARY KEY NOT NULL,
+    x          INTEGER NOT NULL,
+    y          INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
@@ -0,0 +1,7 @@
+ALTER TABLE banned_words ADD COLUMN category TEXT NOT NULL DEFAULT 'other';
+
+-- what happens to messages with words of a category. categories without a row are censored
+CREATE TABLE IF NOT EXISTS word_categories (
+    category TEXT PRIMARY KEY,
+    severity TEXT NOT NULL
+);
@@ -0,0 +1,12 @@
+-- every change to the message board is a new revision, the newest one is shown
+CREATE TABLE IF NOT EXISTS message_board (
+    revision   INTEGER PRIMARY KEY AUTOINCREMENT,
+    text       TEXT    NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+-- the newest revision each account has read
+CREATE TABLE IF NOT EXISTS board_reads (
+    account  TEXT    PRIMARY KEY NOT NULL,
+    revision INTEGER NOT NULL
+);
@@ -0,0 +1,16 @@
+-- every change to the rules is a new version, players agree to the newest one before joining
+CREATE TABLE IF NOT EXISTS rules (
+    version    INTEGER PRIMARY KEY AUTOINCREMENT,
+    text       TEXT    NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+-- who agreed to which version of the rules, kept as evidence for moderation
+CREATE TABLE IF NOT EXISTS rules_agreements (
+    id        INTEGER PRIMARY KEY AUTOINCREMENT,
+    ip        TEXT    NOT NULL,
+    account   TEXT,
+    name      TEXT    NOT NULL,
+    version   INTEGER NOT NULL,
+    agreed_at INTEGER NOT NULL
+);
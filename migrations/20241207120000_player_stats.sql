@@ -0,0 +1,10 @@
+-- added up over all sessions of an account, updated whenever the player leaves
+CREATE TABLE IF NOT EXISTS player_stats (
+    account        TEXT    PRIMARY KEY NOT NULL,
+    name           TEXT    NOT NULL,
+    time_online_ms INTEGER NOT NULL,
+    tiles_walked   INTEGER NOT NULL,
+    messages_sent  INTEGER NOT NULL,
+    balls_kicked   INTEGER NOT NULL,
+    updated_at     INTEGER NOT NULL
+);
@@ -0,0 +1,8 @@
+-- chalk drawings, compressed per chunk of the world. strokes know how long they have left since `saved_at`
+CREATE TABLE IF NOT EXISTS chalk_chunks (
+    chunk_x  INTEGER NOT NULL,
+    chunk_y  INTEGER NOT NULL,
+    data     BLOB    NOT NULL,
+    saved_at INTEGER NOT NULL,
+    PRIMARY KEY (chunk_x, chunk_y)
+);
@@ -0,0 +1,8 @@
+-- areas where nothing can be placed, drawn or spawned. corners are in tiles, both included
+CREATE TABLE IF NOT EXISTS protected_regions (
+    name  TEXT    PRIMARY KEY NOT NULL,
+    min_x INTEGER NOT NULL,
+    min_y INTEGER NOT NULL,
+    max_x INTEGER NOT NULL,
+    max_y INTEGER NOT NULL
+);
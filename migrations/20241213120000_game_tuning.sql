@@ -0,0 +1,5 @@
+-- client side constants changed in the admin panel. params without a row use the default
+CREATE TABLE IF NOT EXISTS game_tuning (
+    param TEXT    PRIMARY KEY,
+    value INTEGER NOT NULL
+);
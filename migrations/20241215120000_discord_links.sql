@@ -0,0 +1,8 @@
+-- discord accounts linked through the oauth login on the landing page. a discord account can only be
+-- linked to one game account at a time
+CREATE TABLE IF NOT EXISTS discord_links (
+    account      TEXT    PRIMARY KEY,
+    discord_id   TEXT    NOT NULL UNIQUE,
+    discord_name TEXT    NOT NULL,
+    linked_at    INTEGER NOT NULL
+);
@@ -0,0 +1,8 @@
+-- hints shown in the ticker every few minutes. tips with an event are only picked while it is active
+CREATE TABLE IF NOT EXISTS tips (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    text       TEXT    NOT NULL,
+    weight     INTEGER NOT NULL DEFAULT 1,
+    event      TEXT,
+    created_at INTEGER NOT NULL
+);
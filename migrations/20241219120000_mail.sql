@@ -0,0 +1,11 @@
+-- letters left at mailboxes, deleted once they were delivered or expired
+CREATE TABLE IF NOT EXISTS mail (
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    recipient      TEXT    NOT NULL,
+    sender_account TEXT    NOT NULL,
+    sender_name    TEXT    NOT NULL,
+    message        TEXT    NOT NULL,
+    sent_at        INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS mail_recipient ON mail (recipient);
@@ -0,0 +1,11 @@
+-- suspicious things clients did, raised by the anti-cheat heuristics
+CREATE TABLE IF NOT EXISTS flags (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    ip         TEXT,
+    name       TEXT    NOT NULL,
+    reason     TEXT    NOT NULL,
+    score      INTEGER NOT NULL,
+    flagged_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS flags_flagged_at ON flags (flagged_at);
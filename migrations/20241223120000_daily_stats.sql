@@ -0,0 +1,10 @@
+-- one row per UTC day, counted up while the server runs and finished after midnight
+CREATE TABLE IF NOT EXISTS daily_stats (
+    day            TEXT    PRIMARY KEY NOT NULL,
+    unique_players INTEGER NOT NULL DEFAULT 0,
+    peak_players   INTEGER NOT NULL DEFAULT 0,
+    messages       INTEGER NOT NULL DEFAULT 0,
+    filter_hits    INTEGER NOT NULL DEFAULT 0,
+    new_bans       INTEGER NOT NULL DEFAULT 0,
+    finished       BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1,25 @@
+-- the seashells an account has to spend at shops
+CREATE TABLE IF NOT EXISTS seashells (
+    account TEXT    PRIMARY KEY NOT NULL,
+    balance INTEGER NOT NULL DEFAULT 0
+);
+
+-- items bought at shops, every item can only be bought once
+CREATE TABLE IF NOT EXISTS shop_purchases (
+    account      TEXT    NOT NULL,
+    item         TEXT    NOT NULL,
+    purchased_at INTEGER NOT NULL,
+    PRIMARY KEY (account, item)
+);
+
+-- every change to a balance with the balance after it, for audits
+CREATE TABLE IF NOT EXISTS seashell_transactions (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    account    TEXT    NOT NULL,
+    amount     INTEGER NOT NULL,
+    reason     TEXT    NOT NULL,
+    balance    INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS seashell_transactions_account ON seashell_transactions (account);
@@ -0,0 +1,11 @@
+-- every name change of a registered account while playing, kept for moderation
+CREATE TABLE IF NOT EXISTS name_history (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    account    TEXT    NOT NULL,
+    old_name   TEXT    NOT NULL,
+    new_name   TEXT    NOT NULL,
+    ip         TEXT    NOT NULL,
+    changed_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS name_history_account ON name_history (account);
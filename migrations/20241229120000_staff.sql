@@ -0,0 +1,2 @@
+-- event staff get a few in-game powers, like muting players on their shard, without the admin panel
+ALTER TABLE registered_accounts ADD COLUMN staff BOOLEAN NOT NULL DEFAULT FALSE;
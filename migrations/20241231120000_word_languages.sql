@@ -0,0 +1,8 @@
+ALTER TABLE banned_words ADD COLUMN language TEXT NOT NULL DEFAULT 'en';
+
+-- how the banned words of a language are used. languages without a row are enabled and not strict
+CREATE TABLE IF NOT EXISTS word_languages (
+    language TEXT    PRIMARY KEY,
+    enabled  BOOLEAN NOT NULL,
+    strict   BOOLEAN NOT NULL
+);
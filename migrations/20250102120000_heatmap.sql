@@ -0,0 +1,8 @@
+-- how many players were counted in each cell of the world, added up per hour. sampled once a minute
+CREATE TABLE IF NOT EXISTS heatmap (
+    hour    INTEGER NOT NULL,
+    x       INTEGER NOT NULL,
+    y       INTEGER NOT NULL,
+    samples INTEGER NOT NULL,
+    PRIMARY KEY (hour, x, y)
+);
@@ -0,0 +1,21 @@
+-- how flagged players moved for a while after the flag, so it can be checked before banning them
+CREATE TABLE IF NOT EXISTS movement_recordings (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    ip         TEXT,
+    name       TEXT    NOT NULL,
+    -- the flag that started the recording
+    reason     TEXT    NOT NULL,
+    started_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS movement_samples (
+    recording_id INTEGER NOT NULL REFERENCES movement_recordings (id),
+    -- since the recording started
+    at_ms        INTEGER NOT NULL,
+    -- the action message exactly as the client sent it, NULL for positions of the server
+    action       BLOB,
+    x            INTEGER,
+    y            INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS movement_samples_recording ON movement_samples (recording_id, at_ms);
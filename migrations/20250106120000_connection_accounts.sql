@@ -0,0 +1,5 @@
+-- the account a connection was made with, so exporting or deleting an account finds exactly its
+-- connections. older connections stay without one
+ALTER TABLE connections ADD COLUMN account TEXT;
+
+CREATE INDEX IF NOT EXISTS connections_account ON connections (account);
@@ -0,0 +1,5 @@
+-- where a vote came from, so a player can't vote again with an account and as a guest or with
+-- several accounts. older votes stay without one
+ALTER TABLE poll_votes ADD COLUMN ip TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS poll_votes_ip ON poll_votes (poll_id, ip);
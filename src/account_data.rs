@@ -0,0 +1,500 @@
+//! everything the server keeps about a registered account, so players can download it or have it
+//! removed. players prove who they are with their account key like the discord login does, sent in
+//! the body so it doesn't end up in logs. admins have to enter the whole key in the admin panel.
+//!
+//! deleting removes the rows that belong to the account and anonymizes the ones that are kept for
+//! moderation or make up other players' history, like rules agreements and poll votes.
+
+use crate::{admin_panel, appeal::request_ip, db::db, game_server};
+use axum::{
+    extract::connect_info::ConnectInfo,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Form, Json, Router,
+};
+use cibo_online::client::{valid_account_key, PlayerSettings};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
+
+/// what anonymized rows are left with instead of the ip and name
+const DELETED: &str = "deleted";
+
+/// keys that aren't registered an ip can try before it has to wait, so keys can't be guessed here
+const FAILED_ATTEMPT_LIMIT: u32 = 10;
+const FAILED_ATTEMPT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// unknown keys sent per ip and when the first of them was
+static FAILED_ATTEMPTS: LazyLock<Mutex<HashMap<IpAddr, (u32, Instant)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Serialize)]
+pub struct AccountExport {
+    registered_at: i64,
+    /// every name the account joined with
+    names: Vec<String>,
+    settings: Option<PlayerSettings>,
+    /// only the number, the blocked accounts are other players' keys
+    blocked_players: usize,
+    home: Option<(i64, i64)>,
+    stats: Option<Stats>,
+    /// the newest message board revision the account has read
+    board_read: Option<i64>,
+    rules_agreements: Vec<RulesAgreement>,
+    connections: Vec<Connection>,
+    discord: Option<DiscordLink>,
+    protected_names: Vec<String>,
+    name_changes: Vec<NameChange>,
+    sent_mail: Vec<Mail>,
+    poll_votes: Vec<PollVote>,
+    event_rsvps: Vec<i64>,
+    seashells: Option<i64>,
+    purchases: Vec<Purchase>,
+    seashell_transactions: Vec<SeashellTransaction>,
+    /// the messages still in the moderation log, older ones are not stored
+    chat: Vec<ChatLine>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+struct Stats {
+    name: String,
+    time_online_ms: i64,
+    tiles_walked: i64,
+    messages_sent: i64,
+    balls_kicked: i64,
+    updated_at: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+struct RulesAgreement {
+    ip: String,
+    name: String,
+    version: i64,
+    agreed_at: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+struct Connection {
+    ip: String,
+    name: String,
+    joined_at: i64,
+    left_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+struct DiscordLink {
+    discord_id: String,
+    discord_name: String,
+    linked_at: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+struct Mail {
+    recipient: String,
+    message: String,
+    sent_at: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+struct NameChange {
+    old_name: String,
+    new_name: String,
+    changed_at: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+struct PollVote {
+    poll_id: i64,
+    option: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+struct Purchase {
+    item: String,
+    purchased_at: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+struct SeashellTransaction {
+    amount: i64,
+    reason: String,
+    balance: i64,
+    created_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChatLine {
+    pub sent_at: i64,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+struct AccountForm {
+    account: String,
+}
+
+#[derive(Deserialize)]
+struct DeleteForm {
+    account: String,
+    /// has to be `delete`, so a stray request can't wipe an account
+    confirm: String,
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/account/export", post(post_export))
+        .route("/account/delete", post(post_delete))
+}
+
+async fn is_registered(account: &str) -> Result<bool, sqlx::Error> {
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM registered_accounts WHERE account = ?")
+            .bind(account)
+            .fetch_one(db().await)
+            .await?;
+    Ok(count > 0)
+}
+
+/// the names the account played under
+async fn names_of(account: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT name FROM rules_agreements WHERE account = ?
+         UNION SELECT name FROM player_stats WHERE account = ?
+         UNION SELECT new_name FROM name_history WHERE account = ?
+         ORDER BY name",
+    )
+    .bind(account)
+    .bind(account)
+    .bind(account)
+    .fetch_all(db().await)
+    .await
+}
+
+/// the account whose key starts with `prefix`, if exactly one does. the admin panel only ever shows
+/// the start of a key
+pub async fn find_account(prefix: &str) -> Result<Option<String>, sqlx::Error> {
+    let accounts: Vec<String> =
+        sqlx::query_scalar("SELECT account FROM registered_accounts WHERE account LIKE ? || '%'")
+            .bind(prefix.replace(['%', '_'], ""))
+            .fetch_all(db().await)
+            .await?;
+    Ok(match accounts.as_slice() {
+        [account] => Some(account.clone()),
+        _ => None,
+    })
+}
+
+/// everything stored about a registered account, `None` if it isn't registered
+pub async fn export(account: &str) -> Result<Option<AccountExport>, sqlx::Error> {
+    let db = db().await;
+    let Some(registered_at) =
+        sqlx::query_scalar("SELECT registered_at FROM registered_accounts WHERE account = ?")
+            .bind(account)
+            .fetch_optional(db)
+            .await?
+    else {
+        return Ok(None);
+    };
+
+    let names = names_of(account).await?;
+    let settings =
+        sqlx::query_scalar::<_, Vec<u8>>("SELECT settings FROM player_settings WHERE account = ?")
+            .bind(account)
+            .fetch_optional(db)
+            .await?
+            .and_then(|bytes| PlayerSettings::from_bytes(&bytes).ok());
+    let blocked_players: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM player_blocks WHERE account = ?")
+            .bind(account)
+            .fetch_one(db)
+            .await?;
+    let home = sqlx::query_as("SELECT x, y FROM player_homes WHERE account = ?")
+        .bind(account)
+        .fetch_optional(db)
+        .await?;
+    let stats = sqlx::query_as(
+        "SELECT name, time_online_ms, tiles_walked, messages_sent, balls_kicked, updated_at
+         FROM player_stats WHERE account = ?",
+    )
+    .bind(account)
+    .fetch_optional(db)
+    .await?;
+    let board_read = sqlx::query_scalar("SELECT revision FROM board_reads WHERE account = ?")
+        .bind(account)
+        .fetch_optional(db)
+        .await?;
+    let rules_agreements = sqlx::query_as(
+        "SELECT ip, name, version, agreed_at FROM rules_agreements WHERE account = ? ORDER BY agreed_at",
+    )
+    .bind(account)
+    .fetch_all(db)
+    .await?;
+    let connections = sqlx::query_as(
+        "SELECT ip, name, joined_at, left_at FROM connections WHERE account = ? ORDER BY joined_at",
+    )
+    .bind(account)
+    .fetch_all(db)
+    .await?;
+    let discord = sqlx::query_as(
+        "SELECT discord_id, discord_name, linked_at FROM discord_links WHERE account = ?",
+    )
+    .bind(account)
+    .fetch_optional(db)
+    .await?;
+    let protected_names =
+        sqlx::query_scalar("SELECT name FROM protected_names WHERE account = ? ORDER BY name")
+            .bind(account)
+            .fetch_all(db)
+            .await?;
+    let name_changes = sqlx::query_as(
+        "SELECT old_name, new_name, changed_at FROM name_history WHERE account = ? ORDER BY changed_at",
+    )
+    .bind(account)
+    .fetch_all(db)
+    .await?;
+    let sent_mail = sqlx::query_as(
+        "SELECT recipient, message, sent_at FROM mail WHERE sender_account = ? ORDER BY sent_at",
+    )
+    .bind(account)
+    .fetch_all(db)
+    .await?;
+    let poll_votes =
+        sqlx::query_as("SELECT poll_id, option FROM poll_votes WHERE voter = ? ORDER BY poll_id")
+            .bind(account)
+            .fetch_all(db)
+            .await?;
+    let event_rsvps =
+        sqlx::query_scalar("SELECT event_id FROM event_rsvps WHERE voter = ? ORDER BY event_id")
+            .bind(account)
+            .fetch_all(db)
+            .await?;
+    let seashells = sqlx::query_scalar("SELECT balance FROM seashells WHERE account = ?")
+        .bind(account)
+        .fetch_optional(db)
+        .await?;
+    let purchases = sqlx::query_as(
+        "SELECT item, purchased_at FROM shop_purchases WHERE account = ? ORDER BY purchased_at",
+    )
+    .bind(account)
+    .fetch_all(db)
+    .await?;
+    let seashell_transactions = sqlx::query_as(
+        "SELECT amount, reason, balance, created_at FROM seashell_transactions WHERE account = ? ORDER BY id",
+    )
+    .bind(account)
+    .fetch_all(db)
+    .await?;
+    let chat = admin_panel::logged_messages_by(account);
+
+    Ok(Some(AccountExport {
+        registered_at,
+        names,
+        settings,
+        blocked_players: blocked_players as usize,
+        home,
+        stats,
+        board_read,
+        rules_agreements,
+        connections,
+        discord,
+        protected_names,
+        name_changes,
+        sent_mail,
+        poll_votes,
+        event_rsvps,
+        seashells,
+        purchases,
+        seashell_transactions,
+        chat,
+    }))
+}
+
+/// remove a registered account and anonymize what it left behind. returns whether there was one
+pub async fn delete(account: &str) -> Result<bool, sqlx::Error> {
+    if !is_registered(account).await? {
+        return Ok(false);
+    }
+
+    let mut tx = db().await.begin().await?;
+    sqlx::query("UPDATE connections SET account = NULL, ip = ?, name = ? WHERE account = ?")
+        .bind(DELETED)
+        .bind(DELETED)
+        .bind(account)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE rules_agreements SET account = NULL, ip = ?, name = ? WHERE account = ?")
+        .bind(DELETED)
+        .bind(DELETED)
+        .bind(account)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE poll_votes SET ip = NULL WHERE voter = ?")
+        .bind(account)
+        .execute(&mut *tx)
+        .await?;
+    // votes still count, but every voter has to stay unique
+    for table in ["poll_votes", "event_rsvps"] {
+        sqlx::query(&format!(
+            "UPDATE {} SET voter = ? || '-' || hex(randomblob(8)) WHERE voter = ?",
+            table
+        ))
+        .bind(DELETED)
+        .bind(account)
+        .execute(&mut *tx)
+        .await?;
+    }
+    // the audit log keeps its amounts, but not who they belonged to
+    sqlx::query("UPDATE seashell_transactions SET account = ? WHERE account = ?")
+        .bind(DELETED)
+        .bind(account)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM player_blocks WHERE account = ? OR blocked = ?")
+        .bind(account)
+        .bind(account)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM mail WHERE sender_account = ?")
+        .bind(account)
+        .execute(&mut *tx)
+        .await?;
+    for table in [
+        "player_settings",
+        "player_homes",
+        "board_reads",
+        "player_stats",
+        "discord_links",
+        "protected_names",
+        "name_history",
+        "seashells",
+        "shop_purchases",
+        "registered_accounts",
+    ] {
+        sqlx::query(&format!("DELETE FROM {} WHERE account = ?", table))
+            .bind(account)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    game_server::forget_account(account);
+    admin_panel::forget_logged_messages(account);
+    Ok(true)
+}
+
+/// whether the ip sent too many keys that aren't registered lately
+fn too_many_attempts(ip: IpAddr) -> bool {
+    let mut attempts = FAILED_ATTEMPTS.lock().unwrap();
+    attempts.retain(|_, (_, first)| first.elapsed() < FAILED_ATTEMPT_WINDOW);
+    attempts
+        .get(&ip)
+        .is_some_and(|(count, _)| *count >= FAILED_ATTEMPT_LIMIT)
+}
+
+fn count_failed_attempt(ip: IpAddr) {
+    let mut attempts = FAILED_ATTEMPTS.lock().unwrap();
+    let (count, _) = attempts.entry(ip).or_insert((0, Instant::now()));
+    *count += 1;
+    if *count == FAILED_ATTEMPT_LIMIT {
+        warn!("{} sent too many unknown account keys", ip);
+    }
+}
+
+/// checks shared by the self service pages, `Err` is the response to send instead
+async fn check_request(
+    addr: SocketAddr,
+    headers: &HeaderMap,
+    account: &str,
+) -> Result<(), Response> {
+    let Some(ip) = request_ip(addr, headers) else {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    };
+    if too_many_attempts(ip) {
+        return Err(StatusCode::TOO_MANY_REQUESTS.into_response());
+    }
+    if !valid_account_key(account) {
+        count_failed_attempt(ip);
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    match is_registered(account).await {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            count_failed_attempt(ip);
+            Err(StatusCode::NOT_FOUND.into_response())
+        }
+        Err(err) => {
+            error!("failed to look up account: {}", err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+async fn post_export(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(form): Form<AccountForm>,
+) -> Response {
+    if let Err(response) = check_request(addr, &headers, &form.account).await {
+        return response;
+    }
+    export_response(&form.account).await
+}
+
+/// the export as a json download, shared with the admin panel
+pub async fn export_response(account: &str) -> Response {
+    match export(account).await {
+        Ok(Some(export)) => (
+            [(
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"cibo-online-data.json\"",
+            )],
+            Json(export),
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            error!("failed to export account data: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn post_delete(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(form): Form<DeleteForm>,
+) -> Response {
+    if form.confirm != "delete" {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    if let Err(response) = check_request(addr, &headers, &form.account).await {
+        return response;
+    }
+    // the session would save its settings and stats again when it ends
+    if game_server::account_online(&form.account) {
+        return (
+            StatusCode::CONFLICT,
+            "leave the game before deleting your account",
+        )
+            .into_response();
+    }
+
+    match delete(&form.account).await {
+        Ok(true) => {
+            info!("deleted an account on request of the player");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            error!("failed to delete account: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! messages with `Delivery::Critical` are numbered per connection, wrapped in
+//! `ServerMessage::Critical` and sent again until the client confirms them with
+//! `ClientMessage::Ack`. a client that never does is disconnected.
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+/// how long the client has to confirm a message before it is sent again
+pub const ACK_TIMEOUT: Duration = Duration::from_secs(2);
+/// a message that still isn't confirmed after this many sends gets the client disconnected
+const MAX_ATTEMPTS: u32 = 5;
+
+/// critical messages that weren't confirmed yet, over all connections
+static UNACKED: AtomicUsize = AtomicUsize::new(0);
+
+struct Unacked {
+    seq: u32,
+    bytes: Vec<u8>,
+    sent_at: Instant,
+    attempts: u32,
+}
+
+/// the critical messages of a single connection
+#[derive(Default)]
+pub struct CriticalMessages {
+    next_seq: u32,
+    unacked: Vec<Unacked>,
+}
+
+impl CriticalMessages {
+    pub fn next_seq(&mut self) -> u32 {
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        seq
+    }
+
+    /// keep a sent message around until the client confirms it
+    pub fn sent(&mut self, seq: u32, bytes: Vec<u8>) {
+        self.unacked.push(Unacked {
+            seq,
+            bytes,
+            sent_at: Instant::now(),
+            attempts: 1,
+        });
+        UNACKED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn ack(&mut self, seq: u32) {
+        let before = self.unacked.len();
+        self.unacked.retain(|unacked| unacked.seq != seq);
+        UNACKED.fetch_sub(before - self.unacked.len(), Ordering::Relaxed);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unacked.is_empty()
+    }
+
+    /// the messages that have to be sent again, or the sequence number of one that ran out of attempts
+    pub fn due(&mut self) -> Result<Vec<Vec<u8>>, u32> {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        for unacked in &mut self.unacked {
+            if now - unacked.sent_at < ACK_TIMEOUT {
+                continue;
+            }
+            if unacked.attempts >= MAX_ATTEMPTS {
+                return Err(unacked.seq);
+            }
+            unacked.attempts += 1;
+            unacked.sent_at = now;
+            due.push(unacked.bytes.clone());
+        }
+        Ok(due)
+    }
+}
+
+impl Drop for CriticalMessages {
+    fn drop(&mut self) {
+        UNACKED.fetch_sub(self.unacked.len(), Ordering::Relaxed);
+    }
+}
+
+/// wait until every client confirmed its critical messages or `timeout` ran out.
+/// returns whether everything was confirmed
+pub async fn wait_for_acks(timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while UNACKED.load(Ordering::Relaxed) > 0 {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    true
+}
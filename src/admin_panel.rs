@@ -1,6 +1,21 @@
+mod chat_export;
+mod components;
+mod cutscenes;
+#[cfg(feature = "debug_tools")]
+mod debug;
 mod login;
+mod recordings;
 
-use crate::{db::db, game_server};
+use crate::{
+    account_data,
+    db::{self, db},
+    digest::{self, DailyStats},
+    discord_bridge::{self, Direction},
+    flags::{self, FlaggedPlayer},
+    game_server, heatmap, logs, restart,
+    tips::{self, Tip},
+    tls,
+};
 use axum::{
     body::Body,
     extract::{Form, Path},
@@ -9,8 +24,20 @@ use axum::{
     routing::{delete, get, post, put},
     Extension, Router,
 };
-use chrono::{DateTime, Duration, FixedOffset, Utc};
-use cibo_online::server::SpecialEvent;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, Utc};
+use cibo_online::{
+    client::valid_account_key,
+    moderation::Language,
+    server::{ChatId, Cutscene, EffectKind, SpawnSpread, SpecialEvent},
+    MinigameKind, ObjectId, PhysicsParam, PlaceableObject, Position, TuningParam,
+    POLL_OPTION_LIMIT,
+};
+use components::{
+    auth_failed, ban_ip_button, delete_button, page_base, section, table_header, theme_form,
+    timestamp, toggle, ColorScheme, Theme,
+};
+#[cfg(feature = "debug_tools")]
+pub use debug::snapshot_submitted;
 use maud::{html, Markup};
 use serde::Deserialize;
 use sqlx::FromRow;
@@ -21,26 +48,40 @@ use std::{
 };
 use tokio::sync::mpsc::Sender;
 use tower_http::{compression::CompressionLayer, services::ServeDir};
-use tracing::{error, info, instrument};
+use tracing::{error, info, instrument, warn};
 
 static ADMIN_CHAT_LOG: LazyLock<Mutex<VecDeque<AdminChatMessage>>> =
     LazyLock::new(|| Mutex::new(VecDeque::new()));
 
 struct AdminChatMessage {
+    pub id: ChatId,
     pub msg: String,
     pub sender_name: String,
     pub sender_ip: IpAddr,
+    /// the account key the message was sent with, if the player had one
+    pub sender_account: Option<String>,
     pub contains_banned: bool,
+    pub deleted: bool,
     pub timestamp: DateTime<Utc>,
 }
 
-pub fn log_admin_message(msg: &str, sender_name: &str, sender_ip: IpAddr, contains_banned: bool) {
+pub fn log_admin_message(
+    id: ChatId,
+    msg: &str,
+    sender_name: &str,
+    sender_ip: IpAddr,
+    sender_account: Option<String>,
+    contains_banned: bool,
+) {
     let mut log = ADMIN_CHAT_LOG.lock().unwrap();
     log.push_back(AdminChatMessage {
+        id,
         msg: msg.to_string(),
         sender_name: sender_name.to_string(),
         sender_ip,
+        sender_account,
         contains_banned,
+        deleted: false,
         timestamp: Utc::now(),
     });
 
@@ -49,12 +90,122 @@ pub fn log_admin_message(msg: &str, sender_name: &str, sender_ip: IpAddr, contai
     }
 }
 
+/// the logged messages sent with the account, oldest first
+pub fn logged_messages_by(account: &str) -> Vec<account_data::ChatLine> {
+    ADMIN_CHAT_LOG
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|msg| msg.sender_account.as_deref() == Some(account))
+        .map(|msg| account_data::ChatLine {
+            sent_at: msg.timestamp.timestamp(),
+            message: msg.msg.clone(),
+        })
+        .collect()
+}
+
+/// anonymize the logged messages of a deleted account. the text stays, it might have been reported
+pub fn forget_logged_messages(account: &str) {
+    for msg in ADMIN_CHAT_LOG
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .filter(|msg| msg.sender_account.as_deref() == Some(account))
+    {
+        msg.sender_name = "deleted".to_string();
+        msg.sender_ip = IpAddr::from([0, 0, 0, 0]);
+        msg.sender_account = None;
+    }
+}
+
+/// keep the original text around, it might be why the message was reported
+pub fn edit_logged_message(id: ChatId, msg: &str, contains_banned: bool) {
+    let mut log = ADMIN_CHAT_LOG.lock().unwrap();
+    if let Some(logged) = log.iter_mut().find(|logged| logged.id == id) {
+        logged.msg = format!("{} (edited, was '{}')", msg, logged.msg);
+        logged.contains_banned |= contains_banned;
+    }
+}
+
 pub enum AdminAction {
     BanIp(IpAddr),
     UnbanIp(IpAddr),
+    /// disconnect whoever plays from the ip, they can come back right away
+    Kick(IpAddr),
+    /// keep the ip from chatting for as long as banned words do
+    Mute(IpAddr),
 
     BanWord(BannedWord),
     UnbanWord(String),
+    SetWordSeverity {
+        category: WordCategory,
+        severity: Severity,
+    },
+    SetWordLanguage {
+        language: Language,
+        policy: LanguagePolicy,
+    },
+
+    AddEditorToken(String),
+    RemoveEditorToken(String),
+
+    ProtectName {
+        name: String,
+        account: Option<String>,
+    },
+    UnprotectName(String),
+
+    SetSlowMode(Option<std::time::Duration>),
+
+    SpawnObjects {
+        kind: PlaceableObject,
+        count: usize,
+    },
+    ClearObjects(PlaceableObject),
+    /// wipe the chalk within `radius` pixels around `center`
+    ClearChalk {
+        center: Position,
+        radius: u32,
+    },
+    SetObjectCap {
+        kind: PlaceableObject,
+        cap: usize,
+    },
+    SetPhysicsParam {
+        param: PhysicsParam,
+        value: f32,
+    },
+    SetTuningParam {
+        param: TuningParam,
+        value: u64,
+    },
+
+    CameraHint {
+        target: Position,
+        duration_ms: u64,
+    },
+    Effect {
+        kind: EffectKind,
+        position: Position,
+        duration_ms: u64,
+    },
+    SetSpawns {
+        spread: SpawnSpread,
+        /// center and radius, replaces the spawn points while set
+        area: Option<(Position, u32)>,
+    },
+    /// replace the fields of an object with the json in `bytes`. ids are only unique within a shard
+    PatchObject {
+        shard: usize,
+        id: ObjectId,
+        bytes: Vec<u8>,
+    },
+    /// the stage is found by its position, ids differ between the shards
+    PlayCutscene {
+        stage: Position,
+        cutscene: Cutscene,
+    },
+    StopCutscene,
 }
 
 #[instrument(name = "admin", skip(action_tx))]
@@ -79,10 +230,11 @@ pub async fn run(action_tx: Sender<AdminAction>) {
         }
     }
 
-    let banned_words = sqlx::query_as("SELECT word, full_ban FROM banned_words")
-        .fetch_all(db)
-        .await
-        .unwrap();
+    let banned_words =
+        sqlx::query_as("SELECT word, full_ban, category, language FROM banned_words")
+            .fetch_all(db)
+            .await
+            .unwrap();
     {
         info!("loaded {} banned words", banned_words.len());
         for word in banned_words {
@@ -90,11 +242,119 @@ pub async fn run(action_tx: Sender<AdminAction>) {
         }
     }
 
+    let word_categories: Vec<(String, String)> =
+        sqlx::query_as("SELECT category, severity FROM word_categories")
+            .fetch_all(db)
+            .await
+            .unwrap();
+    for (category, severity) in word_categories {
+        match (
+            WordCategory::from_name(&category),
+            Severity::from_name(&severity),
+        ) {
+            (Some(category), Some(severity)) => action_tx
+                .send(AdminAction::SetWordSeverity { category, severity })
+                .await
+                .unwrap(),
+            _ => warn!("unknown word category policy {} -> {}", category, severity),
+        }
+    }
+
+    let word_languages: Vec<(String, bool, bool)> =
+        sqlx::query_as("SELECT language, enabled, strict FROM word_languages")
+            .fetch_all(db)
+            .await
+            .unwrap();
+    for (language, enabled, strict) in word_languages {
+        match Language::from_code(&language) {
+            Some(language) => action_tx
+                .send(AdminAction::SetWordLanguage {
+                    language,
+                    policy: LanguagePolicy { enabled, strict },
+                })
+                .await
+                .unwrap(),
+            None => warn!("unknown word language {}", language),
+        }
+    }
+
+    let tuning: Vec<(String, i64)> = sqlx::query_as("SELECT param, value FROM game_tuning")
+        .fetch_all(db)
+        .await
+        .unwrap();
+    for (param, value) in tuning {
+        match TuningParam::from_name(&param) {
+            Some(param) => action_tx
+                .send(AdminAction::SetTuningParam {
+                    param,
+                    value: value as u64,
+                })
+                .await
+                .unwrap(),
+            None => warn!("unknown tuning param {}", param),
+        }
+    }
+
+    let editor_tokens: Vec<String> = sqlx::query_scalar("SELECT token FROM editor_tokens")
+        .fetch_all(db)
+        .await
+        .unwrap();
+    {
+        info!("loaded {} editor tokens", editor_tokens.len());
+        for token in editor_tokens {
+            action_tx
+                .send(AdminAction::AddEditorToken(token))
+                .await
+                .unwrap();
+        }
+    }
+
+    let protected_names: Vec<(String, Option<String>)> =
+        sqlx::query_as("SELECT name, account FROM protected_names")
+            .fetch_all(db)
+            .await
+            .unwrap();
+    {
+        info!("loaded {} protected names", protected_names.len());
+        for (name, account) in protected_names {
+            action_tx
+                .send(AdminAction::ProtectName { name, account })
+                .await
+                .unwrap();
+        }
+    }
+
     let app = app
         .route("/", get(main_page))
         .route("/login", post(post_login))
+        .route("/theme", put(put_theme))
         .route("/chat_log", get(get_chat_log))
+        .route("/chat_log/:id", delete(delete_chat_message))
+        .route("/chat_log/export", get(chat_export::get_chat_export))
+        .route("/logs", get(logs_page))
+        .route("/logs/entries", get(get_log_entries))
+        .route("/logs/malformed", get(get_malformed_messages))
+        .route("/logs/filter", put(put_log_filter))
+        .route("/tips", get(tips_page).post(post_tip))
+        .route("/tips/:id", delete(delete_tip))
+        .route("/flags", get(flags_page))
+        .route("/flags/recordings", get(recordings::recordings_page))
+        .route("/flags/recordings/:id", get(recordings::replay_page))
+        .route("/digest", get(digest_page))
+        .route("/heatmap", get(heatmap_page))
+        .route("/flags/:ip/mute", post(post_mute))
+        .route("/flags/:ip/kick", post(post_kick))
         .route("/stream_mode", get(get_stream_mode).put(put_stream_mode))
+        .route("/slow_mode", get(get_slow_mode).put(put_slow_mode))
+        .route("/player_cap", get(get_player_cap).put(put_player_cap))
+        .route("/discord_bridge", get(get_discord_bridge))
+        .route("/discord_bridge/:direction", put(put_discord_bridge))
+        .route("/connections", get(get_connections))
+        .route("/player_stats", get(get_player_stats))
+        .route("/account_data/export", post(post_account_export))
+        .route("/account_data/delete", post(post_account_delete))
+        .route("/player_stats/:account/staff", put(put_staff))
+        .route("/backup", post(post_backup))
         .route("/banned_ips", get(get_banned_ips).post(post_banned_ip))
         .route("/banned_ips/:ip", delete(delete_banned_ip))
         .route(
@@ -105,8 +365,75 @@ pub async fn run(action_tx: Sender<AdminAction>) {
             "/banned_words/:word",
             delete(delete_banned_word).put(put_banned_word),
         )
+        .route(
+            "/banned_words/:word/category",
+            put(put_banned_word_category),
+        )
+        .route(
+            "/banned_words/:word/language",
+            put(put_banned_word_language),
+        )
+        .route("/word_import", get(word_import_page).post(post_word_import))
+        .route("/word_categories", get(get_word_categories))
+        .route("/word_categories/:category", put(put_word_category))
+        .route("/word_languages/:language/:flag", put(put_word_language))
         .route("/special_events", get(get_special_events))
         .route("/special_events/:event", put(put_special_event))
+        .route("/chalk", get(get_chalk))
+        .route("/chalk/clear", post(post_clear_chalk))
+        .route("/objects", get(get_objects))
+        .route("/objects/spawn", post(post_spawn_objects))
+        .route("/objects/clear", post(post_clear_objects))
+        .route("/objects/cap", put(put_object_cap))
+        .route("/objects/search", get(get_object_search))
+        .route(
+            "/objects/:shard/:id",
+            get(get_object_editor).put(put_object),
+        )
+        .route("/physics", put(put_physics))
+        .route("/tuning", put(put_tuning))
+        .route(
+            "/camera",
+            get(get_camera_bookmarks).post(post_camera_bookmark),
+        )
+        .route("/camera/:name", delete(delete_camera_bookmark))
+        .route("/camera/pan", post(post_camera_pan))
+        .route(
+            "/protected_regions",
+            get(get_protected_regions).post(post_protected_region),
+        )
+        .route("/protected_regions/:name", delete(delete_protected_region))
+        .route("/effects", post(post_effect))
+        .route(
+            "/cutscenes",
+            post(cutscenes::post_cutscene).delete(cutscenes::delete_cutscene),
+        )
+        .route("/spawns", get(get_spawns).put(put_spawns))
+        .route(
+            "/events",
+            get(get_scheduled_events).post(post_scheduled_event),
+        )
+        .route("/events/:id", delete(delete_scheduled_event))
+        .route("/polls", get(get_polls).post(post_poll))
+        .route("/polls/:id/close", post(post_close_poll))
+        .route("/message_board", post(post_message_board))
+        .route("/rules", post(post_rules))
+        .route("/minigames", get(get_minigames))
+        .route("/minigames/:game", put(put_minigame))
+        .route(
+            "/editor_tokens",
+            get(get_editor_tokens).post(post_editor_token),
+        )
+        .route("/editor_tokens/:token", delete(delete_editor_token))
+        .route(
+            "/protected_names",
+            get(get_protected_names).post(post_protected_name),
+        )
+        .route("/protected_names/:name", delete(delete_protected_name));
+    #[cfg(feature = "debug_tools")]
+    let app = app.merge(debug::routes());
+
+    let app = app
         .nest_service("/shared", serve_shared_dir)
         .layer(middleware::from_fn(move |req, next| login::auth(req, next)))
         .layer(Extension(action_tx))
@@ -122,37 +449,17 @@ pub async fn run(action_tx: Sender<AdminAction>) {
         listener.local_addr().unwrap().port()
     );
 
-    axum::serve(listener, app.into_make_service())
-        .await
-        .unwrap();
-}
-
-fn page_base(body: Markup) -> Markup {
-    html! {
-        (maud::DOCTYPE)
-        html {
-            head {
-                title { "Cibo Online! (Admin Panel)" }
-                meta content="text/html;charset=utf-8" http-equiv="Content-Type";
-                link rel="apple-touch-icon" sizes="120x120" href="/shared/apple-touch-icon.png";
-                link rel="icon" type="image/png" sizes="32x32" href="/shared/favicon-32x32.png";
-                link rel="icon" type="image/png" sizes="16x16" href="/shared/favicon-16x16.png";
-                link rel="manifest" href="/shared/site.webmanifest";
-                link rel="mask-icon" href="/shared/safari-pinned-tab.svg" color="#5bbad5";
-                link rel="shortcut icon" href="/shared/favicon.ico";
-                meta name="msapplication-TileColor" content="#da532c";
-                meta name="msapplication-config" content="/shared/browserconfig.xml";
-                meta name="theme-color" content="#ffffff";
-
-                link rel="stylesheet" href="/style.css";
-            }
-
-            body {
-                (body)
-                script src="https://unpkg.com/htmx.org@2.0.1" {}
-                script src="/script.js" {}
-            }
+    match tls::rustls_config().await {
+        Some(rustls_config) => {
+            info!("serving over tls");
+            axum_server::from_tcp_rustls(listener.into_std().unwrap(), rustls_config)
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
         }
+        None => axum::serve(listener, app.into_make_service())
+            .await
+            .unwrap(),
     }
 }
 
@@ -163,58 +470,293 @@ async fn main_page(Extension(auth): Extension<login::AuthState>) -> impl IntoRes
 
     let is_admin = auth.user().unwrap().username == "admin";
 
-    page_base(html! {
-        h1 { "Admin Dashboard" }
-        p { "that is incredibly scuffed because im lazy, sowwy ><" }
-        p {
-            "most things should be self explanatory (i hope)." br; br;
-            "a quick explanation for stream mode and banned words:" br;
-            "a 'fully banned' word will always be filtered. otherwise, it will only be filtered when stream mode is enabled. " br;
-            "this allows using a stricter banlist while the game is being shown on stream :)"
-        }
+    page_base(
+        auth.theme(),
+        html! {
+            h1 { "Admin Dashboard" }
+            (theme_form(auth.theme()))
+            p { a href="/logs" { "server logs" } }
+            p { a href="/tips" { "tips" } }
+            p { a href="/flags" { "anti-cheat flags" } }
+            p { a href="/digest" { "daily digest" } }
+            p { a href="/heatmap" { "player heatmap" } }
+            @if cfg!(feature = "debug_tools") {
+                p { a href="/debug/diff" { "world diff" } }
+            }
+            p { "that is incredibly scuffed because im lazy, sowwy ><" }
+            p {
+                "most things should be self explanatory (i hope)." br; br;
+                "a quick explanation for stream mode and banned words:" br;
+                "a 'fully banned' word will always be filtered. otherwise, it will only be filtered when stream mode is enabled. " br;
+                "this allows using a stricter banlist while the game is being shown on stream :)"
+            }
 
-        (get_stream_mode(Extension(auth.clone())).await)
-        @if is_admin {
-            (get_special_events(Extension(auth.clone())).await)
-        }
-        div class="panel" {
-            div {
-                h2 { "Banned IPs" }
-                form hx-post="/banned_ips" hx-target="next" hx-swap="beforeend" {
-                    input type="text" name="ip" placeholder="IP" required;
-                    button type="submit" { "ban" }
-                }
-                (get_banned_ips(Extension(auth.clone())).await)
+            (get_stream_mode(Extension(auth.clone())).await)
+            (get_slow_mode(Extension(auth.clone())).await)
+            (get_player_cap(Extension(auth.clone())).await)
+            @if discord_bridge::is_configured() {
+                (section("Discord Bridge", "public chat is passed between the game and the discord channel. messages from discord go through the same filters as chat from linked servers", html! {
+                    (get_discord_bridge(Extension(auth.clone())).await)
+                }))
             }
-            div {
-                h2 { "Banned Words" }
-                form hx-post="/banned_words" hx-target="next" hx-swap="beforeend" {
-                    input type="text" name="word" placeholder="Word" required;
-                    button type="submit" { "ban" }
+            (section("Chalk", "players draw on the ground with /chalk, drawings fade away after a day. clearing wipes every stroke touching the area, coordinates are the tile coordinates shown in game", html! {
+                span hx-get="/chalk" hx-trigger="load, every 5s" {}
+                form hx-post="/chalk/clear" hx-swap="none" hx-confirm="sure?" {
+                    input type="number" name="x" placeholder="X" required;
+                    input type="number" name="y" placeholder="Y" required;
+                    input type="number" name="radius" min="1" value="8" required;
+                    "tiles "
+                    button type="submit" { "clear" }
                 }
-                (get_banned_words(Extension(auth.clone())).await)
+            }))
+            @if is_admin {
+                (get_special_events(Extension(auth.clone())).await)
+                (section("Objects", "spawned objects are scattered across the world and are gone after a restart. clearing only removes spawned objects, not placed ones.", html! {
+                    table hx-get="/objects" hx-trigger="load, every 2s" {}
+                    form hx-post="/objects/spawn" hx-swap="none" {
+                        (object_select())
+                        input type="number" name="count" min="1" value="10" required;
+                        button type="submit" { "spawn" }
+                    }
+                    form hx-post="/objects/clear" hx-swap="none" hx-confirm="sure?" {
+                        (object_select())
+                        button type="submit" { "clear" }
+                    }
+                    form hx-put="/objects/cap" hx-swap="none" {
+                        (object_select())
+                        input type="number" name="cap" min="0" required;
+                        button type="submit" { "set cap" }
+                    }
+                    h3 { "Search" }
+                    p { "edits only change the object in its shard and aren't saved, fields that aren't sent to clients start over" }
+                    form hx-get="/objects/search" hx-target="#ObjectSearch" {
+                        select name="kind" {
+                            option value="" { "any" }
+                            @for kind in PlaceableObject::ALL {
+                                option value=(kind.name()) { (kind.name()) }
+                            }
+                        }
+                        input type="number" name="id" min="0" placeholder="ID";
+                        button type="submit" { "search" }
+                    }
+                    table id="ObjectSearch" {}
+                    div id="ObjectEditor" {}
+                }))
+                (section("Physics", "how beach balls roll and bounce, e.g. less friction for icy ground. changes are applied right away and reset after a restart", html! {
+                    @let physics = game_server::get_physics();
+                    @for param in PhysicsParam::ALL {
+                        @let (min, max) = param.range();
+                        form hx-put="/physics" hx-trigger="change" hx-swap="none" {
+                            label { (param.name()) " (default " (param.default_value()) ")" }
+                            input type="hidden" name="param" value=(param.name());
+                            input type="range" name="value" min=(min) max=(max) step="0.01" value=(physics.get(param))
+                                oninput="this.nextElementSibling.value = this.value";
+                            output { (physics.get(param)) }
+                        }
+                    }
+                }))
+                (section("Tuning", "client side constants like the camera edges and how long chat bubbles stay up. clients pick up changes right away, they are kept over restarts", html! {
+                    @let tuning = game_server::get_tuning();
+                    p { "server ticks at " (1000 / tuning.server_tick_ms) "hz, lowered on its own while it can't keep up" }
+                    @for param in TuningParam::ALL {
+                        @let (min, max) = param.range();
+                        form hx-put="/tuning" hx-trigger="change" hx-swap="none" {
+                            label { (param.name()) " (default " (param.default_value()) ")" }
+                            input type="hidden" name="param" value=(param.name());
+                            input type="number" name="value" min=(min) max=(max) value=(tuning.get(param));
+                        }
+                    }
+                }))
+                (get_minigames(Extension(auth.clone())).await)
+                (section("Event Camera", "pans the camera of everyone in event mode (f8) over to a spot. coordinates are the tile coordinates shown in game. moving cancels the pan.", html! {
+                    form hx-post="/camera/pan" hx-swap="none" {
+                        (camera_inputs())
+                        button type="submit" { "pan" }
+                    }
+                    form hx-post="/camera" hx-target="#CameraBookmarks" hx-swap="beforeend" {
+                        input type="text" name="name" placeholder="Name" required;
+                        input type="number" name="x" placeholder="X" required;
+                        input type="number" name="y" placeholder="Y" required;
+                        button type="submit" { "bookmark" }
+                    }
+                    (get_camera_bookmarks(Extension(auth.clone())).await)
+                }))
+                (section("Protected Regions", "nothing can be placed, drawn with chalk or spawned inside these, e.g. around the spawn or the stage. corners are the tile coordinates shown in game and are included", html! {
+                    form hx-post="/protected_regions" hx-target="#ProtectedRegions" hx-swap="beforeend" {
+                        input type="text" name="name" placeholder="Name" required;
+                        input type="number" name="min_x" placeholder="X1" required;
+                        input type="number" name="min_y" placeholder="Y1" required;
+                        input type="number" name="max_x" placeholder="X2" required;
+                        input type="number" name="max_y" placeholder="Y2" required;
+                        button type="submit" { "protect" }
+                    }
+                    (get_protected_regions(Extension(auth.clone())).await)
+                }))
+                (section("Spawns", "new players are spread over the spawn points placed in the editor. set an area to have them show up there instead, e.g. during events. leave the coordinates empty to go back to the spawn points", html! {
+                (get_spawns(Extension(auth.clone())).await)
+            }))
+            (section("Effects", "shows an effect to everyone around a spot, e.g. to celebrate during an event", html! {
+                    form hx-post="/effects" hx-swap="none" {
+                        select name="kind" {
+                            @for kind in EffectKind::ALL {
+                                option value=(kind.name()) { (kind.name()) }
+                            }
+                        }
+                        (camera_inputs())
+                        button type="submit" { "show" }
+                    }
+                }))
+                (section("Cutscenes", "plays a scripted show on a stage for everyone, with actors walking around and talking, camera moves and effects. starting one stops the one that is running", html! {
+                    (cutscenes::cutscene_form().await)
+                }))
+                (section("Scheduled Events", "countdown boards show the next event until 10 minutes after it started. times are in UTC", html! {
+                    form hx-post="/events" hx-target="#ScheduledEvents" hx-swap="outerHTML" {
+                        input type="text" name="name" placeholder="Name" required;
+                        input type="datetime-local" name="starts_at" required;
+                        button type="submit" { "schedule" }
+                    }
+                    (get_scheduled_events(Extension(auth.clone())).await)
+                }))
+                (section("Polls", "poll boards show the newest open poll. starting a new poll closes the running one, results of closed polls are kept below", html! {
+                    form hx-post="/polls" hx-target="#Polls" hx-swap="outerHTML" {
+                        input type="text" name="question" placeholder="Question" required;
+                        textarea name="options" placeholder={"Options, one per line (up to "(POLL_OPTION_LIMIT)")"} required {}
+                        button type="submit" { "start poll" }
+                    }
+                    (get_polls(Extension(auth.clone())).await)
+                }))
+                (section("Message Board", "players that haven't read the newest text see a ! above the board until they do", html! {
+                    form hx-post="/message_board" hx-target="next" {
+                        textarea name="text" rows="10" cols="60" required {
+                            (game_server::load_board().await.map(|(text, _)| text).unwrap_or_default())
+                        }
+                        button type="submit" { "publish" }
+                    }
+                    p {}
+                }))
+                (section("Rules", "players have to agree to these before joining. who agreed to which version is recorded. leave empty to not ask for an agreement", html! {
+                    form hx-post="/rules" hx-target="next" {
+                        textarea name="text" rows="10" cols="60" {
+                            (game_server::load_rules().await.ok().flatten().map(|(_, text)| text).unwrap_or_default())
+                        }
+                        button type="submit" { "publish" }
+                    }
+                    p {}
+                }))
+                (section("Backups", "the database is backed up automatically, but it doesn't hurt to make one before changing a lot of things", html! {
+                    button hx-post="/backup" hx-target="next" { "back up now" }
+                    p {}
+                }))
+                (section("Restarts", "scheduled restarts are set up in the config. players are warned 10, 5 and 1 minutes before", html! {
+                    p {
+                        "up for " (uptime(restart::uptime())) ". "
+                        @match restart::next_restart() {
+                            Some(next) => { "next restart at " (timestamp(next.timestamp())) " UTC" }
+                            None => { "no restart scheduled" }
+                        }
+                    }
+                }))
+                (section("Editor Tokens", "open the game with ?admin=<token> to get access to the world editor (f2)", html! {
+                    button hx-post="/editor_tokens" hx-target="next" hx-swap="beforeend" { "generate" }
+                    (get_editor_tokens(Extension(auth.clone())).await)
+                }))
+                (section("Protected Names", "only the given account can join under these names. leave the account empty to reserve a name for nobody", html! {
+                    form hx-post="/protected_names" hx-target="next" hx-swap="beforeend" {
+                        input type="text" name="name" placeholder="Name" required;
+                        input type="text" name="account" placeholder="Account";
+                        button type="submit" { "protect" }
+                    }
+                    (get_protected_names(Extension(auth.clone())).await)
+                }))
+            }
+            div class="panel" {
+                (section("Banned IPs", "", html! {
+                    form hx-post="/banned_ips" hx-target="next" hx-swap="beforeend" {
+                        input type="text" name="ip" placeholder="IP" required;
+                        button type="submit" { "ban" }
+                    }
+                    (get_banned_ips(Extension(auth.clone())).await)
+                }))
+                (section("Banned Words", "the severity of a category decides what happens to messages with its words. masked words are starred out on their own, censored messages are replaced completely. muted players can't chat for a while, banned ones have their ip banned. names with banned words are always censored. words of disabled languages aren't matched at all. messages that look like they are in a strict language, e.g. one no moderator can read, are handled one step harsher (up to blocking) and light bans apply to them outside of stream mode too", html! {
+                    (get_word_categories(Extension(auth.clone())).await)
+                    (get_word_languages(Extension(auth.clone())).await)
+                    form hx-post="/banned_words" hx-target="next" hx-swap="beforeend" {
+                        input type="text" name="word" placeholder="Word" required;
+                        select name="category" { (category_options(WordCategory::default())) }
+                        select name="language" { (language_options(Language::default())) }
+                        button type="submit" { "ban" }
+                    }
+                    p { a href="/word_import" { "import a list of words" } }
+                    (get_banned_words(Extension(auth.clone())).await)
+                }))
             }
-        }
 
-        div id="ChatLog" {
-            h2 { "Chat Log" }
-            table hx-get="/chat_log" hx-trigger="load, every 2s" {}
-        }
-    })
+            (section("Connection History", "search for an ip to see every name used from it, or for a name to see every ip it connected from and the name changes of its account.", html! {
+                form hx-get="/connections" hx-target="#Connections" {
+                    input type="text" name="query" placeholder="IP or name" required;
+                    button type="submit" { "search" }
+                }
+                div id="Connections" {}
+            }))
+
+            (section("Player Stats", "added up over all sessions of an account whenever the player leaves. only players with an account are tracked. leave empty to see who played the longest.", html! {
+                form hx-get="/player_stats" hx-target="#PlayerStats" {
+                    input type="text" name="query" placeholder="Name";
+                    button type="submit" { "search" }
+                }
+                div id="PlayerStats" {}
+            }))
+
+            (section("Account Data", "export or delete everything stored about an account, for players that ask for it. they have to send their whole account key, the player stats only show its start. deleting anonymizes what is kept for moderation", html! {
+                form method="post" action="/account_data/export" {
+                    input type="text" name="account" placeholder="Account key" required;
+                    button type="submit" { "export" }
+                }
+                form hx-post="/account_data/delete" hx-target="#AccountDataStatus" hx-confirm="sure?" {
+                    input type="text" name="account" placeholder="Account key" required;
+                    button type="submit" { "delete" }
+                }
+                div id="AccountDataStatus" {}
+            }))
+
+            div id="ChatLog" {
+                h2 { "Chat Log" }
+                details {
+                    summary { "export for a stream vod" }
+                    p { "subtitles of the chat with times relative to the stream start, to lay over the vod. times are in UTC, leave the range empty to export everything since the stream started. stream mode leaves out lines with banned words and links it wouldn't show" }
+                    form action="/chat_log/export" method="get" {
+                        label { "stream start " input type="datetime-local" name="stream_start" step="1" required; }
+                        label { " from " input type="datetime-local" name="from" step="1"; }
+                        label { " to " input type="datetime-local" name="to" step="1"; }
+                        select name="format" {
+                            option value="vtt" { "WebVTT" }
+                            option value="srt" { "SRT" }
+                        }
+                        label { input type="checkbox" name="stream_mode" checked; " stream mode" }
+                        button type="submit" { "export" }
+                    }
+                }
+                table hx-get="/chat_log" hx-trigger="load, every 2s" {}
+            }
+        },
+    )
 }
 
 fn login_page() -> Markup {
-    page_base(html! {
-        form action="/login" method="post" {
-            label for="username" { "enter username" }
-            input type="text" name="username" placeholder="Username" required
-            br;
-            label for="password" { "enter password" }
-            input type="password" name="password" placeholder="Password" required;
-            br;
-            input type="submit" value="Login";
-        }
-    })
+    page_base(
+        Theme::default(),
+        html! {
+            form action="/login" method="post" {
+                label for="username" { "enter username" }
+                input type="text" name="username" placeholder="Username" required
+                br;
+                label for="password" { "enter password" }
+                input type="password" name="password" placeholder="Password" required;
+                br;
+                input type="submit" value="Login";
+            }
+        },
+    )
 }
 
 async fn post_login(Form(data): Form<login::LoginData>) -> impl IntoResponse {
@@ -225,14 +767,59 @@ async fn post_login(Form(data): Form<login::LoginData>) -> impl IntoResponse {
             .header("Set-Cookie", format!("session_id={}", session_id.as_u128()))
             .body(Body::empty())
             .unwrap(),
-        Err(err) => page_base(html! {
-            "login failed:" (err) br;
-            a href="/" { "Try again" }
-        })
+        Err(err) => page_base(
+            Theme::default(),
+            html! {
+                "login failed:" (err) br;
+                a href="/" { "Try again" }
+            },
+        )
         .into_response(),
     }
 }
 
+#[derive(Deserialize)]
+struct ThemeForm {
+    scheme: String,
+    compact: Option<String>,
+}
+
+async fn put_theme(
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<ThemeForm>,
+) -> impl IntoResponse {
+    let Some(user) = auth.user() else {
+        return auth_failed().into_response();
+    };
+
+    let Some(scheme) = ColorScheme::from_name(&form.scheme) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+
+    let db = db().await;
+    if let Err(err) = sqlx::query("UPDATE users SET theme = ?, compact = ? WHERE id = ?")
+        .bind(scheme.name())
+        .bind(form.compact.is_some())
+        .bind(user.id)
+        .execute(db)
+        .await
+    {
+        error!("failed to save theme: {}", err);
+
+        return http::Response::builder()
+            .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    // the theme is set on the whole page, so just reload it
+    http::Response::builder()
+        .status(http::StatusCode::OK)
+        .header("HX-Refresh", "true")
+        .body(Body::empty())
+        .unwrap()
+}
+
 #[derive(Deserialize)]
 struct TimeOffset {
     offset: i32,
@@ -243,9 +830,7 @@ async fn get_chat_log(
     Form(TimeOffset { offset }): Form<TimeOffset>,
 ) -> Markup {
     if !auth.is_authenticated() {
-        return page_base(html! {
-            p { "authentication failed" }
-        });
+        return auth_failed();
     }
 
     let log = ADMIN_CHAT_LOG.lock().unwrap();
@@ -255,21 +840,20 @@ async fn get_chat_log(
 
     html! {
         table {
-            tr {
-                th { "Time" }
-                th { "Sender" }
-                th { "Message" }
-                th { }
-            }
+            (table_header(&["Time", "Sender", "Message", ""]))
             @for msg in log {
-                tr style=(if msg.contains_banned { "background-color: orange" } else { "" }) {
+                tr class=[msg.contains_banned.then_some("banned")] {
                     td { (msg.timestamp.with_timezone(&offset).format("%H:%M:%S")) }
                     td { (msg.sender_name) }
-                    td { (msg.msg) }
+                    @if msg.deleted {
+                        td { s { (msg.msg) } }
+                    } @else {
+                        td { (msg.msg) }
+                    }
                     td {
-                        form hx-post="/banned_ips" hx-target="#BannedIPs" hx-swap="beforeend" {
-                            input type="text" name="ip" value=(msg.sender_ip) style="display: none";
-                            button type="submit" { "ban" }
+                        (ban_ip_button(&msg.sender_ip.to_string()))
+                        @if !msg.deleted {
+                            button hx-delete={"/chat_log/"(msg.id.as_u64())} hx-swap="none" { "delete" }
                         }
                     }
                 }
@@ -278,172 +862,2529 @@ async fn get_chat_log(
     }
 }
 
-fn ip_table_row(ip: &str) -> Markup {
-    html! {
-        tr {
-            td { (ip) }
-            td { button hx-delete={"/banned_ips/"(ip)} { "x" } }
-        }
+async fn logs_page(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return login_page();
     }
-}
 
-fn ip_table(rows: Vec<String>) -> Markup {
-    html! {
-        table id="BannedIPs" hx-confirm="sure?" hx-target="closest tr" hx-swap="outerHTML" {
-            tr {
-                th { "IP" }
-                th {  }
+    page_base(
+        auth.theme(),
+        html! {
+            h1 { "Server Logs" }
+            p { a href="/" { "back to the dashboard" } }
+            p { "the last 1000 events the server logged since it started. filter by name or ip to see what happened to a single player" }
+            form id="LogFilter" {
+                select name="level" {
+                    option value="error" { "errors" }
+                    option value="warn" selected { "warnings" }
+                    option value="info" { "everything" }
+                    option value="trace" { "everything, with debug output" }
+                }
+                input type="text" name="query" placeholder="Name, IP or text";
             }
-            @for row in rows {
-                (ip_table_row(&row))
+            details {
+                summary { "log verbosity" }
+                p { "what gets logged at all, e.g. " code { "info,cibo_online_server::game_server=debug" } " to debug the game server during an incident. goes back to " code { (logs::DEFAULT_FILTER) } " on restart" }
+                form hx-put="/logs/filter" hx-target="next" {
+                    input type="text" name="filter" value=(logs::current_filter()) required;
+                    button type="submit" { "apply" }
+                }
+                p {}
             }
-        }
-    }
+            p hx-get="/logs/malformed" hx-trigger="load, every 5s" {}
+            table hx-get="/logs/entries" hx-include="#LogFilter" hx-trigger="load, every 2s, change from:#LogFilter, keyup changed delay:300ms from:#LogFilter" {}
+        },
+    )
 }
 
 #[derive(Deserialize)]
-struct StreamMode {
-    stream_mode: Option<String>,
+struct TipForm {
+    text: String,
+    weight: i64,
+    /// empty for tips that are always shown
+    event: String,
 }
 
-async fn get_stream_mode(Extension(auth): Extension<login::AuthState>) -> Markup {
-    if !auth.is_authenticated() {
-        return page_base(html! {
-            p { "authentication failed" }
-        });
-    }
-    let is_stream_mode = game_server::get_stream_mode();
-
+fn tip_row(tip: &Tip) -> Markup {
     html! {
-        label for="stream_mode" { "Enable/Disable Stream Mode" }
-        @if is_stream_mode {
-            input type="checkbox" name="stream_mode" hx-put="/stream_mode" checked;
-        } @else {
-            input type="checkbox" name="stream_mode" hx-put="/stream_mode";
+        tr {
+            td { (tip.text) }
+            td { (tip.weight) }
+            td { (tip.event.as_deref().unwrap_or("always")) }
+            td hx-confirm="sure?" hx-target="closest tr" hx-swap="outerHTML" {
+                (delete_button(&format!("/tips/{}", tip.id)))
+            }
         }
     }
 }
 
-async fn put_stream_mode(
-    Extension(auth): Extension<login::AuthState>,
-    Form(StreamMode { stream_mode }): Form<StreamMode>,
-) -> Markup {
-    if !auth.is_authenticated() {
-        return html! {"authentication failed"};
-    }
-    game_server::set_stream_mode(stream_mode.is_some());
-    get_stream_mode(Extension(auth)).await
-}
-
-#[derive(Deserialize)]
-struct SpecialEventData {
-    active: Option<String>,
-}
-
-async fn get_special_events(Extension(auth): Extension<login::AuthState>) -> Markup {
+async fn tips_page(Extension(auth): Extension<login::AuthState>) -> Markup {
     if !auth.is_authenticated() {
-        return page_base(html! {
-            p { "authentication failed" }
-        });
+        return login_page();
     }
 
-    let special_events = [(
-        "Beach Episode",
-        game_server::get_special_event(SpecialEvent::BeachEpisode),
-    )];
+    let tips = tips::load_tips().await.unwrap_or_else(|err| {
+        error!("failed to load tips: {}", err);
+        Vec::new()
+    });
 
-    html! {
-        table {
-            tr {
-                th { "Event" }
-                th { "Active" }
-            }
-            @for (event, active) in special_events {
-                tr {
-                    td { (event) }
-                    td {
-                        @if active {
-                            input type="checkbox" name="active" hx-put={"/special_events/"(event)} checked;
-                        } @else {
-                            input type="checkbox" name="active" hx-put={"/special_events/"(event)};
-                        }
+    page_base(
+        auth.theme(),
+        html! {
+            h1 { "Tips" }
+            p { a href="/" { "back to the dashboard" } }
+            p { "one of these is shown in the ticker every few minutes while people are online. tips with a higher weight come up more often, tips for an event only while it is active" }
+            form hx-post="/tips" hx-target="#Tips" hx-swap="beforeend" {
+                input type="text" name="text" placeholder="Tip" maxlength=(cibo_online::MESSAGE_LIMIT) required;
+                input type="number" name="weight" min="1" max="100" value="1" required;
+                select name="event" {
+                    option value="" { "always" }
+                    @for event in SpecialEvent::ALL {
+                        option value=(event.name()) { (event.name()) }
                     }
                 }
+                button type="submit" { "add" }
             }
-        }
-    }
+            table id="Tips" {
+                (table_header(&["Tip", "Weight", "Event", ""]))
+                @for tip in &tips {
+                    (tip_row(tip))
+                }
+            }
+        },
+    )
 }
 
-async fn put_special_event(
-    Path(event): Path<String>,
+async fn post_tip(
     Extension(auth): Extension<login::AuthState>,
-    Form(SpecialEventData { active }): Form<SpecialEventData>,
-) -> Markup {
+    Form(form): Form<TipForm>,
+) -> impl IntoResponse {
     if !auth.is_authenticated() {
-        return html! {"authentication failed"};
+        return auth_failed().into_response();
     }
 
-    let event = match event.as_str() {
-        "Beach Episode" => SpecialEvent::BeachEpisode,
-        _ => return html! {"unknown event"},
-    };
-
-    game_server::set_special_event(event, active.is_some());
-    get_special_events(Extension(auth)).await
-}
-
-#[derive(Deserialize)]
-struct BannedIp {
-    ip: IpAddr,
-}
-
-async fn get_banned_ips(Extension(auth): Extension<login::AuthState>) -> Markup {
-    if !auth.is_authenticated() {
-        return page_base(html! {
-            p { "authentication failed" }
-        });
+    let text = form.text.trim().to_string();
+    let event = SpecialEvent::from_name(&form.event).map(|event| event.name().to_string());
+    if text.is_empty() || form.weight < 1 || (event.is_none() && !form.event.is_empty()) {
+        return http::StatusCode::BAD_REQUEST.into_response();
     }
 
     let db = db().await;
-    let banned_ips: Vec<String> = sqlx::query_scalar("SELECT ip FROM banned_ips")
-        .fetch_all(db)
-        .await
-        .unwrap()
-        .into_iter()
-        .collect();
+    let id =
+        match sqlx::query("INSERT INTO tips (text, weight, event, created_at) VALUES (?, ?, ?, ?)")
+            .bind(&text)
+            .bind(form.weight)
+            .bind(&event)
+            .bind(Utc::now().timestamp())
+            .execute(db)
+            .await
+        {
+            Ok(result) => result.last_insert_rowid(),
+            Err(err) => {
+                error!("failed to save tip: {}", err);
+                return http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+    info!("added tip '{}'", text);
 
-    ip_table(banned_ips)
+    tip_row(&Tip {
+        id,
+        text,
+        weight: form.weight,
+        event,
+    })
+    .into_response()
 }
 
-async fn post_banned_ip(
-    Extension(action_tx): Extension<Sender<AdminAction>>,
+async fn delete_tip(
+    Path(id): Path<i64>,
     Extension(auth): Extension<login::AuthState>,
-    Form(BannedIp { ip }): Form<BannedIp>,
 ) -> impl IntoResponse {
     if !auth.is_authenticated() {
-        return html! {"authentication failed"}.into_response();
+        return auth_failed().into_response();
     }
 
-    action_tx.send(AdminAction::BanIp(ip)).await.unwrap();
-
     let db = db().await;
-    match sqlx::query("INSERT INTO banned_ips (ip) VALUES (?)")
-        .bind(ip.to_string())
+    if let Err(err) = sqlx::query("DELETE FROM tips WHERE id = ?")
+        .bind(id)
         .execute(db)
         .await
     {
-        Ok(_) => {}
-        Err(err) => {
-            error!("failed to save banned ip: {}", err);
+        error!("failed to delete tip: {}", err);
+    }
 
-            return http::Response::builder()
-                .status(http::StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Body::empty())
-                .unwrap();
+    http::StatusCode::OK.into_response()
+}
+
+fn flagged_row(player: &FlaggedPlayer) -> Markup {
+    html! {
+        tr {
+            td { (player.name) }
+            td { (player.ip.as_deref().unwrap_or("local")) }
+            td { (player.score) }
+            td { (player.flags) }
+            td { (player.reasons) }
+            td { (timestamp(player.last_flagged)) }
+            td {
+                @if let Some(ip) = &player.ip {
+                    button hx-post=(format!("/flags/{}/mute", ip)) hx-swap="none" { "mute" }
+                    button hx-post=(format!("/flags/{}/kick", ip)) hx-swap="none" { "kick" }
+                    form hx-post="/banned_ips" hx-swap="none" hx-confirm="sure?" {
+                        input type="hidden" name="ip" value=(ip);
+                        button type="submit" { "ban" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn flags_page(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return login_page();
+    }
+
+    let flagged = flags::load_flagged().await.unwrap_or_else(|err| {
+        error!("failed to load flags: {}", err);
+        Vec::new()
+    });
+
+    page_base(
+        auth.theme(),
+        html! {
+            h1 { "Anti-Cheat Flags" }
+            p { a href="/" { "back to the dashboard" } }
+            p { "players that moved faster than walking allows, tried to teleport or sent way too many actions. every flag adds to their suspicion score, which goes down again over time. a single flag is often just lag, a high score that keeps coming back is worth a look" }
+            p { "how flagged players moved afterwards is recorded, check the " a href="/flags/recordings" { "movement recordings" } " before banning anyone" }
+            table {
+                (table_header(&["Name", "IP", "Highest Score", "Flags", "Reasons", "Last Flagged", ""]))
+                @for player in &flagged {
+                    (flagged_row(player))
+                }
+            }
+        },
+    )
+}
+
+/// how many days the digest page goes back
+const DIGEST_DAYS: i64 = 14;
+
+fn digest_row(stats: &DailyStats, previous: Option<&DailyStats>) -> Markup {
+    html! {
+        tr {
+            td {
+                (stats.day)
+                @if !stats.finished { " (so far)" }
+            }
+            @for (index, (_, value)) in stats.values().into_iter().enumerate() {
+                td {
+                    (value)
+                    @if let Some(previous) = previous {
+                        " (" (digest::change(value, previous.values()[index].1)) ")"
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn digest_page(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return login_page();
+    }
+
+    let days = digest::recent(DIGEST_DAYS).await.unwrap_or_else(|err| {
+        error!("failed to load daily stats: {}", err);
+        Vec::new()
+    });
+    let header = ["Day"]
+        .into_iter()
+        .chain(DailyStats::default().values().map(|(name, _)| name))
+        .collect::<Vec<_>>();
+
+    page_base(
+        auth.theme(),
+        html! {
+            h1 { "Daily Digest" }
+            p { a href="/" { "back to the dashboard" } }
+            p { "what happened on each of the last two weeks, in UTC days. the number in brackets is the change to the day before. unique players are counted by ip, filter hits are messages with a banned word in them" }
+            table {
+                (table_header(&header))
+                @for (index, stats) in days.iter().enumerate() {
+                    (digest_row(stats, days.get(index + 1)))
+                }
+            }
+        },
+    )
+}
+
+/// how far the heatmap goes back if no range was picked
+const HEATMAP_DEFAULT_RANGE: Duration = Duration::days(1);
+
+#[derive(Deserialize)]
+struct HeatmapQuery {
+    /// from datetime-local inputs in UTC
+    from: Option<String>,
+    to: Option<String>,
+}
+
+/// the heat of each cell as a grid over the world, with the placed objects and protected regions
+/// on top so it is clear what is where. everything is in tiles
+fn heatmap_svg(
+    cells: &[(i64, i64, i64)],
+    objects: &[(PlaceableObject, Position)],
+    regions: &[ProtectedRegion],
+) -> Markup {
+    let size = heatmap::CELL_TILES;
+    let objects = objects
+        .iter()
+        .map(|(kind, position)| (kind, position.x.div_euclid(16), position.y.div_euclid(16)))
+        .collect::<Vec<_>>();
+
+    let xs = cells
+        .iter()
+        .flat_map(|(x, _, _)| [x * size, x * size + size])
+        .chain(objects.iter().map(|(_, x, _)| *x))
+        .chain(
+            regions
+                .iter()
+                .flat_map(|region| [region.min_x, region.max_x + 1]),
+        );
+    let ys = cells
+        .iter()
+        .flat_map(|(_, y, _)| [y * size, y * size + size])
+        .chain(objects.iter().map(|(_, _, y)| *y))
+        .chain(
+            regions
+                .iter()
+                .flat_map(|region| [region.min_y, region.max_y + 1]),
+        );
+    let (min_x, max_x) = xs.fold((0, 0), |(min, max), x| (min.min(x), max.max(x)));
+    let (min_y, max_y) = ys.fold((0, 0), |(min, max), y| (min.min(y), max.max(y)));
+    let view_box = format!(
+        "{} {} {} {}",
+        min_x - size,
+        min_y - size,
+        max_x - min_x + size * 2,
+        max_y - min_y + size * 2
+    );
+    let hottest = cells
+        .iter()
+        .map(|(_, _, samples)| *samples)
+        .max()
+        .unwrap_or(1);
+
+    html! {
+        svg id="Heatmap" viewBox=(view_box) {
+            @for (x, y, samples) in cells {
+                // a bit of color for every cell that was used at all
+                @let opacity = 0.1 + 0.9 * (*samples as f64 / hottest as f64);
+                rect x=(x * size) y=(y * size) width=(size) height=(size) fill="red" fill-opacity=(format!("{:.2}", opacity)) {
+                    title { (x * size) ", " (y * size) ": " (samples) " player minutes" }
+                }
+            }
+            @for region in regions {
+                rect class="region" x=(region.min_x) y=(region.min_y) width=(region.max_x - region.min_x + 1) height=(region.max_y - region.min_y + 1) {
+                    title { (region.name) }
+                }
+            }
+            @for (kind, x, y) in &objects {
+                circle class="object" cx=(x) cy=(y) r="1.5" {
+                    title { (kind.name()) " at " (x) ", " (y) }
+                }
+            }
+        }
+    }
+}
+
+async fn heatmap_page(
+    Extension(auth): Extension<login::AuthState>,
+    Form(query): Form<HeatmapQuery>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return login_page();
+    }
+
+    let parse = |input: &Option<String>| {
+        input
+            .as_deref()
+            .filter(|input| !input.is_empty())
+            .and_then(|input| NaiveDateTime::parse_from_str(input, "%Y-%m-%dT%H:%M").ok())
+            .map(|time| time.and_utc())
+    };
+    let to = parse(&query.to).unwrap_or_else(Utc::now);
+    let from = parse(&query.from).unwrap_or(to - HEATMAP_DEFAULT_RANGE);
+
+    let cells = heatmap::load(from.timestamp(), to.timestamp())
+        .await
+        .unwrap_or_else(|err| {
+            error!("failed to load the heatmap: {}", err);
+            Vec::new()
+        });
+    let regions: Vec<ProtectedRegion> =
+        sqlx::query_as("SELECT name, min_x, min_y, max_x, max_y FROM protected_regions")
+            .fetch_all(db().await)
+            .await
+            .unwrap_or_else(|err| {
+                error!("failed to load protected regions: {}", err);
+                Vec::new()
+            });
+    let objects = game_server::get_placed_objects();
+    let total = cells.iter().map(|(_, _, samples)| samples).sum::<i64>();
+
+    page_base(
+        auth.theme(),
+        html! {
+            h1 { "Player Heatmap" }
+            p { a href="/" { "back to the dashboard" } }
+            p { "where players were standing, counted once a minute in cells of " (heatmap::CELL_TILES) "x" (heatmap::CELL_TILES) " tiles and summed up per hour. the redder a cell, the more time was spent there. placed objects are the dots, protected regions the outlines. hover for tile coordinates. times are in UTC" }
+            form method="get" {
+                label { "from " input type="datetime-local" name="from" value=(from.format("%Y-%m-%dT%H:%M")); }
+                label { " to " input type="datetime-local" name="to" value=(to.format("%Y-%m-%dT%H:%M")); }
+                button type="submit" { "show" }
+            }
+            @if cells.is_empty() {
+                p { "nobody was online in this range" }
+            } @else {
+                p { (total) " player minutes in " (cells.len()) " cells" }
+            }
+            (heatmap_svg(&cells, &objects, &regions))
+        },
+    )
+}
+
+async fn post_mute(
+    Path(ip): Path<IpAddr>,
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    action_tx.send(AdminAction::Mute(ip)).await.unwrap();
+    http::StatusCode::OK.into_response()
+}
+
+async fn post_kick(
+    Path(ip): Path<IpAddr>,
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    action_tx.send(AdminAction::Kick(ip)).await.unwrap();
+    http::StatusCode::OK.into_response()
+}
+
+#[derive(Deserialize)]
+struct LogFilter {
+    level: String,
+    #[serde(default)]
+    query: String,
+    offset: i32,
+}
+
+async fn get_log_entries(
+    Extension(auth): Extension<login::AuthState>,
+    Form(LogFilter {
+        level,
+        query,
+        offset,
+    }): Form<LogFilter>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let level = level.parse().unwrap_or(tracing::Level::WARN);
+    let query = query.trim().to_lowercase();
+    let offset = FixedOffset::east_opt(offset).unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+    let entries = logs::recent(level).into_iter().filter(|entry| {
+        query.is_empty()
+            || entry.spans.to_lowercase().contains(&query)
+            || entry.message.to_lowercase().contains(&query)
+    });
+
+    html! {
+        table {
+            (table_header(&["Time", "Level", "Context", "Message"]))
+            @for entry in entries.take(200) {
+                tr class=[(entry.level == tracing::Level::ERROR).then_some("banned")] {
+                    td { (entry.timestamp.with_timezone(&offset).format("%d.%m. %H:%M:%S")) }
+                    td { (entry.level) }
+                    td { (entry.spans) }
+                    td { (entry.message) }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LogFilterForm {
+    filter: String,
+}
+
+async fn put_log_filter(
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<LogFilterForm>,
+) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    match logs::set_filter(form.filter.trim()) {
+        Ok(()) => {
+            info!("log filter changed to {}", form.filter.trim());
+            html! { "now logging " code { (logs::current_filter()) } }
+        }
+        Err(err) => html! { "invalid filter: " (err) },
+    }
+}
+
+async fn get_malformed_messages(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let (last_minute, total) = game_server::get_malformed_messages();
+    html! {
+        (last_minute) " malformed messages in the last minute, " (total) " since the start. clients are disconnected after sending too many"
+    }
+}
+
+async fn delete_chat_message(
+    Path(id): Path<u64>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    let id = ChatId::from_u64(id);
+    if let Some(msg) = ADMIN_CHAT_LOG
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .find(|msg| msg.id == id)
+    {
+        msg.deleted = true;
+    }
+    game_server::delete_chat(id);
+
+    http::Response::builder()
+        .status(http::StatusCode::OK)
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// delete already sent messages that contain a newly banned word
+fn delete_logged_messages(word: &BannedWord) {
+    if !word.full_ban && !game_server::get_stream_mode() {
+        return;
+    }
+    if !game_server::word_language(word.language).enabled {
+        return;
+    }
+
+    // collect first so the chat log isn't locked while the game state is
+    let ids = ADMIN_CHAT_LOG
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .filter(|msg| !msg.deleted && msg.msg.to_lowercase().contains(&word.word))
+        .map(|msg| {
+            msg.deleted = true;
+            msg.id
+        })
+        .collect::<Vec<_>>();
+
+    for id in ids {
+        game_server::delete_chat(id);
+    }
+}
+
+async fn post_backup(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    match db::backup().await {
+        Ok(path) => {
+            info!("backed up database to {}", path.display());
+            html! { "saved backup to " (path.display()) }
+        }
+        Err(err) => {
+            error!("failed to back up database: {}", err);
+            html! { "backup failed: " (err) }
+        }
+    }
+}
+
+/// e.g. `2d 3h 15m`
+fn uptime(uptime: Duration) -> String {
+    let (days, hours, minutes) = (
+        uptime.num_days(),
+        uptime.num_hours() % 24,
+        uptime.num_minutes() % 60,
+    );
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[derive(Deserialize)]
+struct ConnectionQuery {
+    query: String,
+    #[serde(default)]
+    offset: i32,
+}
+
+#[derive(FromRow)]
+struct ConnectionSummary {
+    value: String,
+    connections: i64,
+    first_seen: i64,
+    last_seen: i64,
+}
+
+fn connection_search_button(query: &str) -> Markup {
+    html! {
+        form hx-get="/connections" hx-target="#Connections" {
+            input type="text" name="query" value=(query) style="display: none";
+            button type="submit" { "search" }
+        }
+    }
+}
+
+fn connection_table(
+    column: &str,
+    rows: &[ConnectionSummary],
+    offset: &FixedOffset,
+    bannable: bool,
+) -> Markup {
+    let format_time = |timestamp: i64| {
+        DateTime::<Utc>::from_timestamp(timestamp, 0)
+            .map(|time| {
+                time.with_timezone(offset)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default()
+    };
+
+    html! {
+        table {
+            (table_header(&[column, "Connections", "First Seen", "Last Seen", ""]))
+            @for row in rows {
+                tr {
+                    td { (row.value) }
+                    td { (row.connections) }
+                    td { (format_time(row.first_seen)) }
+                    td { (format_time(row.last_seen)) }
+                    td {
+                        (connection_search_button(&row.value))
+                        @if bannable {
+                            (ban_ip_button(&row.value))
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn get_connections(
+    Extension(auth): Extension<login::AuthState>,
+    Form(ConnectionQuery { query, offset }): Form<ConnectionQuery>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let offset = FixedOffset::east_opt(offset).unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+    let query = query.trim();
+    let db = db().await;
+
+    if let Ok(ip) = query.parse::<IpAddr>() {
+        let names: Vec<ConnectionSummary> = match sqlx::query_as(
+            "SELECT name AS value, COUNT(*) AS connections, MIN(joined_at) AS first_seen, MAX(joined_at) AS last_seen
+             FROM connections WHERE ip = ? GROUP BY name ORDER BY last_seen DESC",
+        )
+        .bind(ip.to_string())
+        .fetch_all(db)
+        .await
+        {
+            Ok(names) => names,
+            Err(err) => {
+                error!("failed to load connection history: {}", err);
+                return html! { p { "failed to load connection history" } };
+            }
+        };
+
+        html! {
+            h3 { "names used from " (ip) }
+            @if names.is_empty() {
+                p { "no connections from this ip" }
+            } @else {
+                (connection_table("Name", &names, &offset, false))
+            }
+        }
+    } else {
+        let result: Result<_, sqlx::Error> = async {
+            let ips: Vec<ConnectionSummary> = sqlx::query_as(
+                "SELECT ip AS value, COUNT(*) AS connections, MIN(joined_at) AS first_seen, MAX(joined_at) AS last_seen
+                 FROM connections WHERE name = ? COLLATE NOCASE GROUP BY ip ORDER BY last_seen DESC",
+            )
+            .bind(query)
+            .fetch_all(db)
+            .await?;
+
+            // everyone else that shared an ip with this name
+            let other_names: Vec<ConnectionSummary> = sqlx::query_as(
+                "SELECT name AS value, COUNT(*) AS connections, MIN(joined_at) AS first_seen, MAX(joined_at) AS last_seen
+                 FROM connections
+                 WHERE ip IN (SELECT ip FROM connections WHERE name = ? COLLATE NOCASE)
+                   AND name != ? COLLATE NOCASE
+                 GROUP BY name ORDER BY last_seen DESC",
+            )
+            .bind(query)
+            .bind(query)
+            .fetch_all(db)
+            .await?;
+
+            // every name change of the accounts that were called this at some point
+            let renames: Vec<(String, String, i64)> = sqlx::query_as(
+                "SELECT old_name, new_name, changed_at FROM name_history
+                 WHERE account IN (
+                     SELECT account FROM name_history
+                     WHERE old_name = ? COLLATE NOCASE OR new_name = ? COLLATE NOCASE
+                 )
+                 ORDER BY changed_at DESC",
+            )
+            .bind(query)
+            .bind(query)
+            .fetch_all(db)
+            .await?;
+
+            Ok((ips, other_names, renames))
+        }
+        .await;
+
+        let (ips, other_names, renames) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                error!("failed to load connection history: {}", err);
+                return html! { p { "failed to load connection history" } };
+            }
+        };
+
+        html! {
+            h3 { "ips used by " (query) }
+            @if ips.is_empty() {
+                p { "nobody with this name has connected" }
+            } @else {
+                (connection_table("IP", &ips, &offset, true))
+                h3 { "other names used from these ips" }
+                @if other_names.is_empty() {
+                    p { "none" }
+                } @else {
+                    (connection_table("Name", &other_names, &offset, false))
+                }
+            }
+            @if !renames.is_empty() {
+                h3 { "name changes" }
+                table {
+                    (table_header(&["From", "To", "Changed", ""]))
+                    @for (old_name, new_name, changed_at) in &renames {
+                        tr {
+                            td { (old_name) }
+                            td { (new_name) }
+                            td { (timestamp(*changed_at)) }
+                            td { (connection_search_button(old_name)) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PlayerStatsQuery {
+    #[serde(default)]
+    query: String,
+}
+
+#[derive(FromRow)]
+struct PlayerStatsRow {
+    account: String,
+    name: String,
+    time_online_ms: i64,
+    tiles_walked: i64,
+    messages_sent: i64,
+    balls_kicked: i64,
+    updated_at: i64,
+    /// only ever set for registered accounts
+    staff: bool,
+}
+
+#[derive(Deserialize)]
+struct StaffData {
+    staff: Option<String>,
+}
+
+/// the players that last played under a name, or the ones that played the longest
+async fn get_player_stats(
+    Extension(auth): Extension<login::AuthState>,
+    Form(PlayerStatsQuery { query }): Form<PlayerStatsQuery>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let query = query.trim();
+    let db = db().await;
+    let rows: Result<Vec<PlayerStatsRow>, _> = if query.is_empty() {
+        sqlx::query_as(
+            "SELECT player_stats.*, COALESCE(registered_accounts.staff, FALSE) AS staff
+             FROM player_stats LEFT JOIN registered_accounts USING (account)
+             ORDER BY time_online_ms DESC LIMIT 20",
+        )
+        .fetch_all(db)
+        .await
+    } else {
+        sqlx::query_as(
+            "SELECT player_stats.*, COALESCE(registered_accounts.staff, FALSE) AS staff
+             FROM player_stats LEFT JOIN registered_accounts USING (account)
+             WHERE name = ? COLLATE NOCASE ORDER BY updated_at DESC",
+        )
+        .bind(query)
+        .fetch_all(db)
+        .await
+    };
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("failed to load player stats: {}", err);
+            return html! { p { "failed to load player stats" } };
+        }
+    };
+
+    html! {
+        @if rows.is_empty() {
+            p { "no stats for this name yet" }
+        } @else {
+            table {
+                (table_header(&["Name", "Account", "Time Online", "Tiles Walked", "Messages", "Balls Kicked", "Last Seen", "Staff"]))
+                @for row in &rows {
+                    @let prefix = row.account.get(..8).unwrap_or(&row.account);
+                    tr {
+                        td { (row.name) }
+                        // the whole key would let anyone take over the account
+                        td { (prefix) "…" }
+                        td { (uptime(Duration::milliseconds(row.time_online_ms))) }
+                        td { (row.tiles_walked) }
+                        td { (row.messages_sent) }
+                        td { (row.balls_kicked) }
+                        td { (timestamp(row.updated_at)) }
+                        td hx-target="this" { (toggle("staff", &format!("/player_stats/{}/staff", prefix), row.staff)) }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AccountDataForm {
+    account: String,
+}
+
+async fn post_account_export(
+    Extension(auth): Extension<login::AuthState>,
+    Form(AccountDataForm { account }): Form<AccountDataForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let account = account.trim();
+    if !valid_account_key(account) {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    }
+    info!("exported account {}…", account.get(..8).unwrap_or(account));
+    account_data::export_response(account).await
+}
+
+/// event staff can put out decorations, mute players and run minigames in game. only the admin can make someone staff
+async fn put_staff(
+    Path(prefix): Path<String>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(StaffData { staff }): Form<StaffData>,
+) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    let account = match account_data::find_account(&prefix).await {
+        Ok(Some(account)) => account,
+        Ok(None) => return html! { "not registered" },
+        Err(err) => {
+            error!("failed to look up account: {}", err);
+            return html! { "failed to look up the account" };
+        }
+    };
+    let url = format!("/player_stats/{}/staff", prefix);
+    match game_server::set_staff(&account, staff.is_some()).await {
+        Ok(false) => html! { "not registered" },
+        Ok(true) => {
+            info!(
+                "{} event staff for {}…",
+                if staff.is_some() {
+                    "granted"
+                } else {
+                    "revoked"
+                },
+                prefix
+            );
+            toggle("staff", &url, staff.is_some())
+        }
+        Err(err) => {
+            error!("failed to update staff flag: {}", err);
+            html! { "failed to update" }
+        }
+    }
+}
+
+async fn post_account_delete(
+    Extension(auth): Extension<login::AuthState>,
+    Form(AccountDataForm { account }): Form<AccountDataForm>,
+) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    let account = account.trim();
+    if !valid_account_key(account) {
+        return html! { "that isn't an account key" };
+    }
+    if game_server::account_online(account) {
+        return html! { "the player is online, kick them first" };
+    }
+
+    let prefix = account.get(..8).unwrap_or(account);
+    match account_data::delete(account).await {
+        Ok(true) => {
+            info!("deleted account {}…", prefix);
+            html! { "deleted account " (prefix) "…" }
+        }
+        Ok(false) => html! { "no account is registered with that key" },
+        Err(err) => {
+            error!("failed to delete account: {}", err);
+            html! { "failed to delete the account" }
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct BanAppeal {
+    ip: String,
+    message: String,
+    contact: String,
+    created_at: i64,
+}
+
+fn ip_table_row(ip: &str, appeals: &[BanAppeal]) -> Markup {
+    html! {
+        tr {
+            td { (ip) }
+            td {
+                @for appeal in appeals.iter().filter(|appeal| appeal.ip == ip) {
+                    p {
+                        b {
+                            (timestamp(appeal.created_at))
+                            @if !appeal.contact.is_empty() {
+                                " (" (appeal.contact) ")"
+                            }
+                        }
+                        br;
+                        (appeal.message)
+                    }
+                }
+            }
+            td { (delete_button(&format!("/banned_ips/{}", ip))) }
+        }
+    }
+}
+
+fn ip_table(rows: Vec<String>, appeals: Vec<BanAppeal>) -> Markup {
+    html! {
+        table id="BannedIPs" hx-confirm="sure?" hx-target="closest tr" hx-swap="outerHTML" {
+            (table_header(&["IP", "Appeals", ""]))
+            @for row in rows {
+                (ip_table_row(&row, &appeals))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct StreamMode {
+    stream_mode: Option<String>,
+}
+
+async fn get_stream_mode(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+    let is_stream_mode = game_server::get_stream_mode();
+
+    html! {
+        label for="stream_mode" { "Enable/Disable Stream Mode" }
+        (toggle("stream_mode", "/stream_mode", is_stream_mode))
+    }
+}
+
+async fn put_stream_mode(
+    Extension(auth): Extension<login::AuthState>,
+    Form(StreamMode { stream_mode }): Form<StreamMode>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+    game_server::set_stream_mode(stream_mode.is_some());
+    get_stream_mode(Extension(auth)).await
+}
+
+#[derive(Deserialize)]
+struct DiscordBridgeData {
+    enabled: Option<String>,
+}
+
+async fn get_discord_bridge(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    html! {
+        table {
+            (table_header(&["Direction", "Enabled"]))
+            @for direction in Direction::ALL {
+                tr {
+                    td { (direction.description()) }
+                    td {
+                        (toggle(
+                            "enabled",
+                            &format!("/discord_bridge/{}", direction.name()),
+                            discord_bridge::get_direction(direction),
+                        ))
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn put_discord_bridge(
+    Path(direction): Path<String>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(DiscordBridgeData { enabled }): Form<DiscordBridgeData>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let Some(direction) = Direction::from_name(&direction) else {
+        return html! {"unknown direction"};
+    };
+
+    discord_bridge::set_direction(direction, enabled.is_some());
+    get_discord_bridge(Extension(auth)).await
+}
+
+#[derive(Deserialize)]
+struct SlowMode {
+    seconds: Option<String>,
+}
+
+fn slow_mode_form(seconds: u64) -> Markup {
+    html! {
+        form id="SlowMode" hx-put="/slow_mode" hx-swap="outerHTML" {
+            label for="seconds" { "Slow Mode (seconds between messages, 0 to disable)" }
+            input type="number" name="seconds" min="0" value=(seconds);
+            button type="submit" { "set" }
+        }
+    }
+}
+
+async fn get_slow_mode(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+    let seconds = game_server::get_slow_mode().map_or(0, |slow_mode| slow_mode.as_secs());
+
+    slow_mode_form(seconds)
+}
+
+async fn put_slow_mode(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(SlowMode { seconds }): Form<SlowMode>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let slow_mode = seconds
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .map(std::time::Duration::from_secs);
+    action_tx
+        .send(AdminAction::SetSlowMode(slow_mode))
+        .await
+        .unwrap();
+
+    // the action is handled asynchronously, so answer with the requested value
+    let seconds = slow_mode.map_or(0, |slow_mode| slow_mode.as_secs());
+    slow_mode_form(seconds)
+}
+
+#[derive(Deserialize)]
+struct PlayerCap {
+    cap: Option<String>,
+}
+
+async fn get_player_cap(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+    let cap = game_server::get_player_cap();
+    let (players, waiting) = game_server::get_queue_stats();
+    let shard_players = game_server::get_shard_players();
+
+    html! {
+        form id="PlayerCap" hx-put="/player_cap" hx-swap="outerHTML" {
+            label for="cap" { "Player Cap (players beyond it have to wait in a queue, 0 to disable)" }
+            input type="number" name="cap" min="0" value=(cap);
+            button type="submit" { "set" }
+            span { " " (players) " playing, " (waiting) " waiting" }
+            @if shard_players.len() > 1 {
+                span {
+                    " (per shard: "
+                    @for (i, players) in shard_players.iter().enumerate() {
+                        @if i > 0 { ", " }
+                        (players)
+                    }
+                    ")"
+                }
+            }
+        }
+    }
+}
+
+async fn put_player_cap(
+    Extension(auth): Extension<login::AuthState>,
+    Form(PlayerCap { cap }): Form<PlayerCap>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let cap = cap.and_then(|cap| cap.parse::<usize>().ok()).unwrap_or(0);
+    game_server::set_player_cap(cap);
+    get_player_cap(Extension(auth)).await
+}
+
+#[derive(Deserialize)]
+struct SpecialEventData {
+    active: Option<String>,
+}
+
+async fn get_special_events(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    html! {
+        table {
+            (table_header(&["Event", "Active"]))
+            @for event in SpecialEvent::ALL {
+                tr {
+                    td { (event.name()) }
+                    td {
+                        (toggle(
+                            "active",
+                            &format!("/special_events/{}", event.name()),
+                            game_server::get_special_event(event),
+                        ))
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn put_special_event(
+    Path(event): Path<String>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(SpecialEventData { active }): Form<SpecialEventData>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let Some(event) = SpecialEvent::from_name(&event) else {
+        return html! {"unknown event"};
+    };
+
+    game_server::set_special_event(event, active.is_some());
+    get_special_events(Extension(auth)).await
+}
+
+#[derive(Deserialize)]
+struct MinigameData {
+    active: Option<String>,
+}
+
+fn object_select() -> Markup {
+    html! {
+        select name="kind" {
+            @for kind in PlaceableObject::ALL {
+                option value=(kind.name()) { (kind.name()) }
+            }
+        }
+    }
+}
+
+async fn get_chalk(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    html! { (game_server::get_chalk_count()) " strokes in the world" }
+}
+
+#[derive(Deserialize)]
+struct ClearChalkForm {
+    x: i64,
+    y: i64,
+    radius: u32,
+}
+
+async fn post_clear_chalk(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<ClearChalkForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    action_tx
+        .send(AdminAction::ClearChalk {
+            // the middle of the tile, like the camera
+            center: Position::new(form.x * 16 + 8, form.y * 16 + 8),
+            radius: form.radius.saturating_mul(16),
+        })
+        .await
+        .unwrap();
+
+    http::StatusCode::OK.into_response()
+}
+
+#[derive(Deserialize)]
+struct ObjectForm {
+    kind: String,
+    count: Option<usize>,
+    cap: Option<usize>,
+}
+
+async fn get_objects(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    html! {
+        (table_header(&["Object", "Count", "Cap", "Spawned", "Despawned"]))
+        @for (kind, stats) in game_server::get_object_stats() {
+            tr {
+                td { (kind.name()) }
+                td { (stats.count) }
+                td { (stats.cap) }
+                td { (stats.spawned) }
+                td { (stats.despawned) }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ObjectSearch {
+    kind: String,
+    id: String,
+}
+
+/// results past this aren't listed, search by id to find the others
+const OBJECT_SEARCH_LIMIT: usize = 100;
+
+async fn get_object_search(
+    Extension(auth): Extension<login::AuthState>,
+    Form(search): Form<ObjectSearch>,
+) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    let kind = PlaceableObject::from_name(&search.kind);
+    let id = search.id.trim().parse().ok().map(ObjectId::from_u32);
+    let found = game_server::find_objects(kind, id);
+
+    html! {
+        (table_header(&["ID", "Object", "Shard", "Tile", "", ""]))
+        @for (shard, info) in found.iter().take(OBJECT_SEARCH_LIMIT) {
+            @let (x, y) = (info.position.x.div_euclid(16), info.position.y.div_euclid(16));
+            tr {
+                td { (info.id.as_u32()) }
+                td { (info.kind.name()) }
+                td { (shard) }
+                td { (x) " / " (y) }
+                td {
+                    button hx-get=(format!("/objects/{}/{}", shard, info.id.as_u32())) hx-target="#ObjectEditor" { "edit" }
+                }
+                td {
+                    form hx-post="/camera/pan" hx-swap="none" {
+                        input type="hidden" name="x" value=(x);
+                        input type="hidden" name="y" value=(y);
+                        input type="hidden" name="duration" value="10";
+                        button type="submit" { "pan" }
+                    }
+                }
+            }
+        }
+        @if found.len() > OBJECT_SEARCH_LIMIT {
+            tr { td { "and " (found.len() - OBJECT_SEARCH_LIMIT) " more" } }
+        }
+    }
+}
+
+fn object_editor(shard: usize, id: ObjectId, json: &str, status: Option<&str>) -> Markup {
+    html! {
+        form hx-put=(format!("/objects/{}/{}", shard, id.as_u32())) hx-target="#ObjectEditor" {
+            p { "object " (id.as_u32()) " in shard " (shard) }
+            textarea name="json" rows="20" cols="80" spellcheck="false" { (json) }
+            p {
+                button type="submit" { "apply" }
+                @if let Some(status) = status {
+                    " " (status)
+                }
+            }
+        }
+    }
+}
+
+async fn get_object_editor(
+    Path((shard, id)): Path<(usize, u32)>,
+    Extension(auth): Extension<login::AuthState>,
+) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    let id = ObjectId::from_u32(id);
+    match game_server::get_object_json(shard, id) {
+        Some(json) => object_editor(shard, id, &json, None),
+        None => html! { p { "object " (id.as_u32()) " is gone" } },
+    }
+}
+
+#[derive(Deserialize)]
+struct ObjectPatch {
+    json: String,
+}
+
+async fn put_object(
+    Path((shard, id)): Path<(usize, u32)>,
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(ObjectPatch { json }): Form<ObjectPatch>,
+) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    let id = ObjectId::from_u32(id);
+    // checked here as well so mistakes show up in the editor instead of only in the log
+    if let Err(err) = game_server::check_object_patch(shard, id, json.as_bytes()) {
+        return object_editor(shard, id, &json, Some(&err));
+    }
+    action_tx
+        .send(AdminAction::PatchObject {
+            shard,
+            id,
+            bytes: json.clone().into_bytes(),
+        })
+        .await
+        .unwrap();
+
+    object_editor(shard, id, &json, Some("applied!"))
+}
+
+async fn post_spawn_objects(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<ObjectForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    let (Some(kind), Some(count)) = (PlaceableObject::from_name(&form.kind), form.count) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+    action_tx
+        .send(AdminAction::SpawnObjects { kind, count })
+        .await
+        .unwrap();
+
+    http::StatusCode::OK.into_response()
+}
+
+async fn post_clear_objects(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<ObjectForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    let Some(kind) = PlaceableObject::from_name(&form.kind) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+    action_tx
+        .send(AdminAction::ClearObjects(kind))
+        .await
+        .unwrap();
+
+    http::StatusCode::OK.into_response()
+}
+
+async fn put_object_cap(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<ObjectForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    let (Some(kind), Some(cap)) = (PlaceableObject::from_name(&form.kind), form.cap) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+    action_tx
+        .send(AdminAction::SetObjectCap { kind, cap })
+        .await
+        .unwrap();
+
+    http::StatusCode::OK.into_response()
+}
+
+#[derive(Deserialize)]
+struct PhysicsForm {
+    param: String,
+    value: f32,
+}
+
+async fn put_physics(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<PhysicsForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let Some(param) = PhysicsParam::from_name(&form.param) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+    action_tx
+        .send(AdminAction::SetPhysicsParam {
+            param,
+            value: form.value,
+        })
+        .await
+        .unwrap();
+
+    http::StatusCode::OK.into_response()
+}
+
+#[derive(Deserialize)]
+struct TuningForm {
+    param: String,
+    value: u64,
+}
+
+async fn put_tuning(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<TuningForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let Some(param) = TuningParam::from_name(&form.param) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+    let (min, max) = param.range();
+    let value = form.value.clamp(min, max);
+    action_tx
+        .send(AdminAction::SetTuningParam { param, value })
+        .await
+        .unwrap();
+
+    let db = db().await;
+    if let Err(err) = sqlx::query("INSERT OR REPLACE INTO game_tuning (param, value) VALUES (?, ?)")
+        .bind(param.name())
+        .bind(value as i64)
+        .execute(db)
+        .await
+    {
+        error!("failed to save tuning: {}", err);
+        return http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    http::StatusCode::OK.into_response()
+}
+
+#[derive(Deserialize, FromRow)]
+struct ProtectedRegion {
+    name: String,
+    min_x: i64,
+    min_y: i64,
+    max_x: i64,
+    max_y: i64,
+}
+
+fn protected_region_row(region: &ProtectedRegion) -> Markup {
+    html! {
+        tr {
+            td { (region.name) }
+            td { (region.min_x) " / " (region.min_y) }
+            td { (region.max_x) " / " (region.max_y) }
+            td hx-confirm="sure?" hx-target="closest tr" hx-swap="outerHTML" {
+                (delete_button(&format!("/protected_regions/{}", region.name)))
+            }
+        }
+    }
+}
+
+async fn get_protected_regions(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let db = db().await;
+    let regions: Vec<ProtectedRegion> = sqlx::query_as(
+        "SELECT name, min_x, min_y, max_x, max_y FROM protected_regions ORDER BY name",
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    html! {
+        table id="ProtectedRegions" {
+            (table_header(&["Region", "From", "To", ""]))
+            @for region in &regions {
+                (protected_region_row(region))
+            }
+        }
+    }
+}
+
+async fn post_protected_region(
+    Extension(auth): Extension<login::AuthState>,
+    Form(region): Form<ProtectedRegion>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    if region.name.is_empty() {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    }
+
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO protected_regions (name, min_x, min_y, max_x, max_y) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&region.name)
+    .bind(region.min_x)
+    .bind(region.min_y)
+    .bind(region.max_x)
+    .bind(region.max_y)
+    .execute(db)
+    .await
+    {
+        error!("failed to save protected region: {}", err);
+
+        return http::Response::builder()
+            .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    info!("protected region '{}'", region.name);
+    game_server::refresh_protected_regions().await;
+    protected_region_row(&region).into_response()
+}
+
+async fn delete_protected_region(
+    Path(name): Path<String>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let db = db().await;
+    if let Err(err) = sqlx::query("DELETE FROM protected_regions WHERE name = ?")
+        .bind(&name)
+        .execute(db)
+        .await
+    {
+        error!("failed to delete protected region: {}", err);
+    }
+
+    info!("unprotected region '{}'", name);
+    game_server::refresh_protected_regions().await;
+    http::StatusCode::OK.into_response()
+}
+
+#[derive(FromRow)]
+struct CameraBookmark {
+    name: String,
+    x: i64,
+    y: i64,
+}
+
+/// longest camera pan in seconds, so a typo doesn't take the camera away from everyone for hours
+const CAMERA_PAN_MAX_SECS: u64 = 60;
+
+#[derive(Deserialize)]
+struct CameraForm {
+    name: Option<String>,
+    x: i64,
+    y: i64,
+    duration: Option<u64>,
+}
+
+fn camera_inputs() -> Markup {
+    html! {
+        input type="number" name="x" placeholder="X" required;
+        input type="number" name="y" placeholder="Y" required;
+        input type="number" name="duration" min="1" max=(CAMERA_PAN_MAX_SECS) value="10" required;
+        "s"
+    }
+}
+
+fn camera_bookmark_row(bookmark: &CameraBookmark) -> Markup {
+    html! {
+        tr {
+            td { (bookmark.name) }
+            td { (bookmark.x) " / " (bookmark.y) }
+            td {
+                form hx-post="/camera/pan" hx-swap="none" {
+                    input type="hidden" name="x" value=(bookmark.x);
+                    input type="hidden" name="y" value=(bookmark.y);
+                    input type="number" name="duration" min="1" max=(CAMERA_PAN_MAX_SECS) value="10" required;
+                    "s "
+                    button type="submit" { "pan" }
+                }
+            }
+            td hx-confirm="sure?" hx-target="closest tr" hx-swap="outerHTML" {
+                (delete_button(&format!("/camera/{}", bookmark.name)))
+            }
+        }
+    }
+}
+
+async fn get_camera_bookmarks(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let db = db().await;
+    let bookmarks: Vec<CameraBookmark> =
+        sqlx::query_as("SELECT name, x, y FROM camera_bookmarks ORDER BY name")
+            .fetch_all(db)
+            .await
+            .unwrap();
+
+    html! {
+        table id="CameraBookmarks" {
+            (table_header(&["Bookmark", "Tile", "", ""]))
+            @for bookmark in &bookmarks {
+                (camera_bookmark_row(bookmark))
+            }
+        }
+    }
+}
+
+async fn post_camera_bookmark(
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<CameraForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let Some(name) = form.name.filter(|name| !name.is_empty()) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+
+    let db = db().await;
+    match sqlx::query("INSERT INTO camera_bookmarks (name, x, y) VALUES (?, ?, ?)")
+        .bind(&name)
+        .bind(form.x)
+        .bind(form.y)
+        .execute(db)
+        .await
+    {
+        Ok(_) => {}
+        Err(err) => {
+            error!("failed to save camera bookmark: {}", err);
+
+            return http::Response::builder()
+                .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap();
+        }
+    }
+
+    camera_bookmark_row(&CameraBookmark {
+        name,
+        x: form.x,
+        y: form.y,
+    })
+    .into_response()
+}
+
+async fn delete_camera_bookmark(
+    Path(name): Path<String>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let db = db().await;
+    if let Err(err) = sqlx::query("DELETE FROM camera_bookmarks WHERE name = ?")
+        .bind(name)
+        .execute(db)
+        .await
+    {
+        error!("failed to delete camera bookmark: {}", err);
+    }
+
+    http::StatusCode::OK.into_response()
+}
+
+#[derive(FromRow)]
+struct ScheduledEvent {
+    id: i64,
+    name: String,
+    starts_at: i64,
+    rsvps: i64,
+}
+
+#[derive(Deserialize)]
+struct ScheduledEventForm {
+    name: String,
+    /// as sent by a datetime-local input
+    starts_at: String,
+}
+
+async fn get_scheduled_events(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let db = db().await;
+    let events: Vec<ScheduledEvent> = sqlx::query_as(
+        "SELECT id, name, starts_at,
+         (SELECT COUNT(*) FROM event_rsvps WHERE event_id = scheduled_events.id) AS rsvps
+         FROM scheduled_events ORDER BY starts_at DESC",
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    html! {
+        table id="ScheduledEvents" hx-get="/events" hx-trigger="every 10s" hx-swap="outerHTML" {
+            (table_header(&["Event", "Starts", "RSVPs", ""]))
+            @for event in &events {
+                tr {
+                    td { (event.name) }
+                    td { (timestamp(event.starts_at)) }
+                    td { (event.rsvps) }
+                    td hx-confirm="sure?" hx-target="closest tr" hx-swap="outerHTML" {
+                        (delete_button(&format!("/events/{}", event.id)))
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn post_scheduled_event(
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<ScheduledEventForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let name = form.name.trim();
+    let Ok(starts_at) = NaiveDateTime::parse_from_str(&form.starts_at, "%Y-%m-%dT%H:%M") else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+    if name.is_empty() {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    }
+
+    let db = db().await;
+    if let Err(err) = sqlx::query("INSERT INTO scheduled_events (name, starts_at) VALUES (?, ?)")
+        .bind(name)
+        .bind(starts_at.and_utc().timestamp())
+        .execute(db)
+        .await
+    {
+        error!("failed to schedule event: {}", err);
+
+        return http::Response::builder()
+            .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::empty())
+            .unwrap();
+    }
+    info!("scheduled event '{}' at {}", name, starts_at);
+    game_server::refresh_next_event().await;
+
+    get_scheduled_events(Extension(auth)).await.into_response()
+}
+
+async fn delete_scheduled_event(
+    Path(id): Path<i64>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let db = db().await;
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        sqlx::query("DELETE FROM event_rsvps WHERE event_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM scheduled_events WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+    if let Err(err) = result {
+        error!("failed to delete scheduled event: {}", err);
+    }
+    game_server::refresh_next_event().await;
+
+    http::StatusCode::OK.into_response()
+}
+
+#[derive(FromRow)]
+struct PollRow {
+    id: i64,
+    question: String,
+    options: String,
+    closed_at: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct PollForm {
+    question: String,
+    /// one option per line
+    options: String,
+}
+
+async fn get_polls(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let db = db().await;
+    let polls: Vec<PollRow> =
+        sqlx::query_as("SELECT id, question, options, closed_at FROM polls ORDER BY id DESC")
+            .fetch_all(db)
+            .await
+            .unwrap();
+    let votes: Vec<(i64, i64, i64)> =
+        sqlx::query_as("SELECT poll_id, option, COUNT(*) FROM poll_votes GROUP BY poll_id, option")
+            .fetch_all(db)
+            .await
+            .unwrap();
+    let vote_count = |poll: i64, option: usize| {
+        votes
+            .iter()
+            .find(|(p, o, _)| *p == poll && *o == option as i64)
+            .map_or(0, |(_, _, count)| *count)
+    };
+
+    html! {
+        table id="Polls" hx-get="/polls" hx-trigger="every 10s" hx-swap="outerHTML" {
+            (table_header(&["Question", "Results", "Closed", ""]))
+            @for poll in &polls {
+                tr {
+                    td { (poll.question) }
+                    td {
+                        @for (i, option) in poll.options.lines().enumerate() {
+                            (option) ": " (vote_count(poll.id, i)) br;
+                        }
+                    }
+                    td { (poll.closed_at.map(timestamp).unwrap_or_default()) }
+                    td {
+                        @if poll.closed_at.is_none() {
+                            button hx-post={"/polls/"(poll.id)"/close"} hx-confirm="sure?" hx-target="#Polls" hx-swap="outerHTML" { "close" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn post_poll(
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<PollForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let question = form.question.trim();
+    let options = form
+        .options
+        .lines()
+        .map(str::trim)
+        .filter(|option| !option.is_empty())
+        .collect::<Vec<_>>();
+    if question.is_empty() || options.len() < 2 || options.len() > POLL_OPTION_LIMIT {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    }
+
+    let db = db().await;
+    let now = Utc::now().timestamp();
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        sqlx::query("UPDATE polls SET closed_at = ? WHERE closed_at IS NULL")
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO polls (question, options, created_at) VALUES (?, ?, ?)")
+            .bind(question)
+            .bind(options.join("\n"))
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+    if let Err(err) = result {
+        error!("failed to start poll: {}", err);
+
+        return http::Response::builder()
+            .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::empty())
+            .unwrap();
+    }
+    info!("started poll '{}'", question);
+    game_server::refresh_poll().await;
+
+    get_polls(Extension(auth)).await.into_response()
+}
+
+async fn post_close_poll(
+    Path(id): Path<i64>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let db = db().await;
+    if let Err(err) =
+        sqlx::query("UPDATE polls SET closed_at = ? WHERE id = ? AND closed_at IS NULL")
+            .bind(Utc::now().timestamp())
+            .bind(id)
+            .execute(db)
+            .await
+    {
+        error!("failed to close poll: {}", err);
+    }
+    game_server::refresh_poll().await;
+
+    get_polls(Extension(auth)).await.into_response()
+}
+
+#[derive(Deserialize)]
+struct MessageBoardForm {
+    text: String,
+}
+
+async fn post_message_board(
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<MessageBoardForm>,
+) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    let text = form.text.trim();
+    if text.is_empty() {
+        return html! { "the message board can't be empty" };
+    }
+
+    let db = db().await;
+    let result = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO message_board (text, created_at) VALUES (?, ?) RETURNING revision",
+    )
+    .bind(text)
+    .bind(Utc::now().timestamp())
+    .fetch_one(db)
+    .await;
+    match result {
+        Ok(revision) => {
+            info!("published message board revision {}", revision);
+            game_server::refresh_board().await;
+            html! { "published revision " (revision) }
+        }
+        Err(err) => {
+            error!("failed to publish message board: {}", err);
+            html! { "publishing failed: " (err) }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RulesForm {
+    text: String,
+}
+
+async fn post_rules(
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<RulesForm>,
+) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    // an empty text is a version too, so the rules can be taken down again
+    let db = db().await;
+    let result = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO rules (text, created_at) VALUES (?, ?) RETURNING version",
+    )
+    .bind(form.text.trim())
+    .bind(Utc::now().timestamp())
+    .fetch_one(db)
+    .await;
+    match result {
+        Ok(version) => {
+            info!("published rules version {}", version);
+            game_server::refresh_rules().await;
+            html! { "published version " (version) }
+        }
+        Err(err) => {
+            error!("failed to publish rules: {}", err);
+            html! { "publishing failed: " (err) }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct EffectForm {
+    kind: String,
+    x: i64,
+    y: i64,
+    duration: u64,
+}
+
+async fn post_effect(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<EffectForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let Some(kind) = EffectKind::from_name(&form.kind) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+    action_tx
+        .send(AdminAction::Effect {
+            kind,
+            // the middle of the tile, like the camera
+            position: Position::new(form.x * 16 + 8, form.y * 16 + 8),
+            duration_ms: form.duration.saturating_mul(1000),
+        })
+        .await
+        .unwrap();
+
+    http::StatusCode::OK.into_response()
+}
+
+#[derive(Deserialize)]
+struct SpawnForm {
+    spread: String,
+    x: Option<String>,
+    y: Option<String>,
+    radius: Option<String>,
+}
+
+/// `area` is in tiles, like the coordinates shown in game
+fn spawn_form(spread: SpawnSpread, area: Option<(i64, i64, u32)>) -> Markup {
+    html! {
+        form id="Spawns" hx-put="/spawns" hx-swap="outerHTML" {
+            select name="spread" {
+                @for choice in SpawnSpread::ALL {
+                    option value=(choice.name()) selected[choice == spread] { (choice.name()) }
+                }
+            }
+            input type="number" name="x" placeholder="X" value=[area.map(|(x, _, _)| x)];
+            input type="number" name="y" placeholder="Y" value=[area.map(|(_, y, _)| y)];
+            input type="number" name="radius" min="0" value=(area.map_or(3, |(_, _, radius)| radius));
+            "tiles "
+            button type="submit" { "set" }
+        }
+    }
+}
+
+async fn get_spawns(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let (spread, area) = game_server::get_spawns();
+    let area = area.map(|(center, radius)| {
+        (
+            center.x.div_euclid(16),
+            center.y.div_euclid(16),
+            radius / 16,
+        )
+    });
+    spawn_form(spread, area)
+}
+
+async fn put_spawns(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<SpawnForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let Some(spread) = SpawnSpread::from_name(&form.spread) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+    let parse = |value: Option<String>| value.and_then(|value| value.trim().parse::<i64>().ok());
+    let radius = parse(form.radius).unwrap_or(0).clamp(0, 100) as u32;
+    let area = parse(form.x)
+        .zip(parse(form.y))
+        .map(|(x, y)| (x, y, radius));
+
+    action_tx
+        .send(AdminAction::SetSpawns {
+            spread,
+            // the middle of the tile, like the camera
+            area: area.map(|(x, y, radius)| (Position::new(x * 16 + 8, y * 16 + 8), radius * 16)),
+        })
+        .await
+        .unwrap();
+
+    // the action is handled asynchronously, so answer with the requested values
+    spawn_form(spread, area).into_response()
+}
+
+async fn post_camera_pan(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<CameraForm>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let Some(duration) = form.duration else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+    // aim for the middle of the tile
+    let target = Position::new(form.x * 16 + 8, form.y * 16 + 8);
+    action_tx
+        .send(AdminAction::CameraHint {
+            target,
+            duration_ms: duration.clamp(1, CAMERA_PAN_MAX_SECS) * 1000,
+        })
+        .await
+        .unwrap();
+
+    http::StatusCode::OK.into_response()
+}
+
+async fn get_minigames(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let running = game_server::get_minigame();
+    let minigames = [
+        ("Tag", MinigameKind::Tag),
+        ("Hide and Seek", MinigameKind::HideAndSeek),
+    ];
+    let scores = game_server::get_minigame_scores();
+
+    html! {
+        div id="Minigames" {
+            table hx-target="#Minigames" hx-swap="outerHTML" {
+                (table_header(&["Minigame", "Running"]))
+                @for (name, kind) in minigames {
+                    tr {
+                        td { (name) }
+                        td { (toggle("active", &format!("/minigames/{}", name), running == Some(kind))) }
+                    }
+                }
+            }
+            @if running.is_some() {
+                table hx-get="/minigames" hx-trigger="every 5s" hx-target="#Minigames" hx-swap="outerHTML" {
+                    (table_header(&["Player", "Score"]))
+                    @for (name, score) in scores {
+                        tr {
+                            td { (name) }
+                            td { (score) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn put_minigame(
+    Path(game): Path<String>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(MinigameData { active }): Form<MinigameData>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let kind = match game.as_str() {
+        "Tag" => MinigameKind::Tag,
+        "Hide and Seek" => MinigameKind::HideAndSeek,
+        _ => return html! {"unknown minigame"},
+    };
+
+    if active.is_some() {
+        game_server::set_minigame(Some(kind));
+    } else if game_server::get_minigame() == Some(kind) {
+        game_server::set_minigame(None);
+    }
+
+    get_minigames(Extension(auth)).await
+}
+
+fn editor_token_row(token: &str) -> Markup {
+    html! {
+        tr {
+            td { (token) }
+            td { (delete_button(&format!("/editor_tokens/{}", token))) }
+        }
+    }
+}
+
+async fn get_editor_tokens(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let db = db().await;
+    let tokens: Vec<String> = sqlx::query_scalar("SELECT token FROM editor_tokens")
+        .fetch_all(db)
+        .await
+        .unwrap();
+
+    html! {
+        table hx-confirm="sure?" hx-target="closest tr" hx-swap="outerHTML" {
+            (table_header(&["Token", ""]))
+            @for token in tokens {
+                (editor_token_row(&token))
+            }
+        }
+    }
+}
+
+async fn post_editor_token(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let token = format!("{:032x}", rand::random::<u128>());
+
+    let db = db().await;
+    match sqlx::query("INSERT INTO editor_tokens (token) VALUES (?)")
+        .bind(&token)
+        .execute(db)
+        .await
+    {
+        Ok(_) => {}
+        Err(err) => {
+            error!("failed to save editor token: {}", err);
+
+            return http::Response::builder()
+                .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap();
+        }
+    }
+
+    action_tx
+        .send(AdminAction::AddEditorToken(token.clone()))
+        .await
+        .unwrap();
+
+    editor_token_row(&token).into_response()
+}
+
+async fn delete_editor_token(
+    Path(token): Path<String>,
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    action_tx
+        .send(AdminAction::RemoveEditorToken(token.clone()))
+        .await
+        .unwrap();
+
+    let db = db().await;
+    match sqlx::query("DELETE FROM editor_tokens WHERE token = ?")
+        .bind(token)
+        .execute(db)
+        .await
+    {
+        Ok(_) => {}
+        Err(err) => {
+            error!("failed to delete editor token: {}", err);
+        }
+    }
+
+    http::Response::builder()
+        .status(http::StatusCode::OK)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[derive(Deserialize)]
+struct ProtectedName {
+    name: String,
+    account: Option<String>,
+}
+
+fn protected_name_row(name: &str, account: Option<&str>) -> Markup {
+    html! {
+        tr {
+            td { (name) }
+            // the whole key would let anyone take over the account
+            td {
+                @match account {
+                    Some(account) => { (account.get(..8).unwrap_or(account)) "…" }
+                    None => { "nobody" }
+                }
+            }
+            td { (delete_button(&format!("/protected_names/{}", name))) }
+        }
+    }
+}
+
+async fn get_protected_names(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    let db = db().await;
+    let names: Vec<(String, Option<String>)> =
+        sqlx::query_as("SELECT name, account FROM protected_names")
+            .fetch_all(db)
+            .await
+            .unwrap();
+
+    html! {
+        table hx-confirm="sure?" hx-target="closest tr" hx-swap="outerHTML" {
+            (table_header(&["Name", "Account", ""]))
+            @for (name, account) in names {
+                (protected_name_row(&name, account.as_deref()))
+            }
+        }
+    }
+}
+
+async fn post_protected_name(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(ProtectedName { name, account }): Form<ProtectedName>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    let name = name.trim().to_string();
+    let account = account
+        .map(|account| account.trim().to_string())
+        .filter(|account| !account.is_empty());
+
+    let db = db().await;
+    match sqlx::query("INSERT OR REPLACE INTO protected_names (name, account) VALUES (?, ?)")
+        .bind(&name)
+        .bind(&account)
+        .execute(db)
+        .await
+    {
+        Ok(_) => {}
+        Err(err) => {
+            error!("failed to save protected name: {}", err);
+
+            return http::Response::builder()
+                .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap();
+        }
+    }
+
+    action_tx
+        .send(AdminAction::ProtectName {
+            name: name.clone(),
+            account: account.clone(),
+        })
+        .await
+        .unwrap();
+
+    protected_name_row(&name, account.as_deref()).into_response()
+}
+
+async fn delete_protected_name(
+    Path(name): Path<String>,
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+
+    action_tx
+        .send(AdminAction::UnprotectName(name.clone()))
+        .await
+        .unwrap();
+
+    let db = db().await;
+    match sqlx::query("DELETE FROM protected_names WHERE name = ?")
+        .bind(name)
+        .execute(db)
+        .await
+    {
+        Ok(_) => {}
+        Err(err) => {
+            error!("failed to delete protected name: {}", err);
+        }
+    }
+
+    http::Response::builder()
+        .status(http::StatusCode::OK)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[derive(Deserialize)]
+struct BannedIp {
+    ip: IpAddr,
+}
+
+async fn get_banned_ips(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let db = db().await;
+    let banned_ips: Vec<String> = sqlx::query_scalar("SELECT ip FROM banned_ips")
+        .fetch_all(db)
+        .await
+        .unwrap()
+        .into_iter()
+        .collect();
+    let appeals: Vec<BanAppeal> = sqlx::query_as(
+        "SELECT ip, message, contact, created_at FROM ban_appeals ORDER BY created_at DESC",
+    )
+    .fetch_all(db)
+    .await
+    .unwrap();
+
+    ip_table(banned_ips, appeals)
+}
+
+async fn post_banned_ip(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(BannedIp { ip }): Form<BannedIp>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    action_tx.send(AdminAction::BanIp(ip)).await.unwrap();
+
+    let db = db().await;
+    match sqlx::query("INSERT INTO banned_ips (ip) VALUES (?)")
+        .bind(ip.to_string())
+        .execute(db)
+        .await
+    {
+        Ok(_) => {}
+        Err(err) => {
+            error!("failed to save banned ip: {}", err);
+
+            return http::Response::builder()
+                .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap();
         }
     }
+    digest::count_ban();
 
-    ip_table_row(&ip.to_string()).into_response()
+    ip_table_row(&ip.to_string(), &[]).into_response()
 }
 
 async fn delete_banned_ip(
@@ -452,7 +3393,7 @@ async fn delete_banned_ip(
     Extension(auth): Extension<login::AuthState>,
 ) -> impl IntoResponse {
     if !auth.is_authenticated() {
-        return html! {"authentication failed"}.into_response();
+        return auth_failed().into_response();
     }
 
     action_tx.send(AdminAction::UnbanIp(ip)).await.unwrap();
@@ -475,37 +3416,179 @@ async fn delete_banned_ip(
         .unwrap()
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum WordCategory {
+    Slur,
+    Sexual,
+    SpamLink,
+    #[default]
+    Other,
+}
+
+impl WordCategory {
+    pub const ALL: [WordCategory; 4] = [
+        WordCategory::Slur,
+        WordCategory::Sexual,
+        WordCategory::SpamLink,
+        WordCategory::Other,
+    ];
+
+    /// as stored in the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            WordCategory::Slur => "slur",
+            WordCategory::Sexual => "sexual",
+            WordCategory::SpamLink => "spam-link",
+            WordCategory::Other => "other",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|category| category.name() == name)
+    }
+}
+
+impl TryFrom<String> for WordCategory {
+    type Error = String;
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Self::from_name(&name).ok_or_else(|| format!("unknown word category '{}'", name))
+    }
+}
+
+/// what happens to a message with a banned word, from mildest to harshest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// only the banned words are replaced with `*`, the rest of the message is sent
+    Mask,
+    /// the message is sent as `*****`
+    #[default]
+    Censor,
+    /// the message isn't sent at all
+    Block,
+    /// blocked, and the sender can't chat for a while
+    Mute,
+    /// blocked, and the ip of the sender is banned
+    Ban,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 5] = [
+        Severity::Mask,
+        Severity::Censor,
+        Severity::Block,
+        Severity::Mute,
+        Severity::Ban,
+    ];
+
+    /// as stored in the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Mask => "mask",
+            Severity::Censor => "censor",
+            Severity::Block => "block",
+            Severity::Mute => "mute",
+            Severity::Ban => "ban",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|severity| severity.name() == name)
+    }
+
+    /// one step harsher, for messages nobody can check. never more than blocking, a wrong guess at
+    /// the language shouldn't get anyone muted or banned
+    pub fn stricter(self) -> Self {
+        match self {
+            Severity::Mask => Severity::Censor,
+            Severity::Censor | Severity::Block => Severity::Block,
+            severity => severity,
+        }
+    }
+}
+
+/// how the banned words of a language are used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LanguagePolicy {
+    /// whether its words are matched at all
+    pub enabled: bool,
+    /// for languages the moderators can't read, messages that look like they are in it are
+    /// filtered harder
+    pub strict: bool,
+}
+
+impl Default for LanguagePolicy {
+    fn default() -> Self {
+        LanguagePolicy {
+            enabled: true,
+            strict: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, FromRow)]
 pub struct BannedWord {
     pub word: String,
     pub full_ban: bool,
+    #[sqlx(try_from = "String")]
+    pub category: WordCategory,
+    #[sqlx(try_from = "String")]
+    pub language: Language,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 struct BannedWordForm {
     word: String,
+    category: String,
+    language: String,
 }
 impl From<BannedWordForm> for BannedWord {
     fn from(value: BannedWordForm) -> Self {
         BannedWord {
             word: value.word,
             full_ban: true,
+            category: WordCategory::from_name(&value.category).unwrap_or_default(),
+            language: Language::from_code(&value.language).unwrap_or_default(),
+        }
+    }
+}
+
+fn category_options(selected: WordCategory) -> Markup {
+    html! {
+        @for category in WordCategory::ALL {
+            option value=(category.name()) selected[category == selected] { (category.name()) }
+        }
+    }
+}
+
+fn language_options(selected: Language) -> Markup {
+    html! {
+        @for language in Language::ALL {
+            option value=(language.code()) selected[language == selected] { (language.name()) }
         }
     }
 }
 
 fn word_table_row(word: BannedWord) -> Markup {
+    let url = format!("/banned_words/{}", word.word);
     html! {
         tr {
             td { (word.word) }
             td {
-                @if word.full_ban {
-                    input type="checkbox" name="full_ban" hx-put={"/banned_words/"(word.word)} checked;
-                } @else {
-                    input type="checkbox" name="full_ban" hx-put={"/banned_words/"(word.word)};
+                select name="category" hx-put=(format!("{}/category", url)) {
+                    (category_options(word.category))
+                }
+            }
+            td {
+                select name="language" hx-put=(format!("{}/language", url)) {
+                    (language_options(word.language))
                 }
             }
-            td { button hx-delete={"/banned_words/"(word.word)} { "x" } }
+            td { (toggle("full_ban", &url, word.full_ban)) }
+            td { (delete_button(&url)) }
         }
     }
 }
@@ -513,11 +3596,7 @@ fn word_table_row(word: BannedWord) -> Markup {
 fn word_table(rows: Vec<BannedWord>) -> Markup {
     html! {
         table hx-target="closest tr" hx-swap="outerHTML" {
-            tr {
-                th { "Word" }
-                th { "Full Ban?" }
-                th {  }
-            }
+            (table_header(&["Word", "Category", "Language", "Full Ban?", ""]))
             @for row in rows {
                 (word_table_row(row))
             }
@@ -527,15 +3606,14 @@ fn word_table(rows: Vec<BannedWord>) -> Markup {
 
 async fn get_banned_words(Extension(auth): Extension<login::AuthState>) -> Markup {
     if !auth.is_authenticated() {
-        return page_base(html! {
-            p { "authentication failed" }
-        });
+        return auth_failed();
     }
     let db = db().await;
-    let banned_words = sqlx::query_as("SELECT word, full_ban FROM banned_words")
-        .fetch_all(db)
-        .await
-        .unwrap();
+    let banned_words =
+        sqlx::query_as("SELECT word, full_ban, category, language FROM banned_words")
+            .fetch_all(db)
+            .await
+            .unwrap();
 
     word_table(banned_words)
 }
@@ -546,7 +3624,7 @@ async fn post_banned_word(
     Form(word): Form<BannedWordForm>,
 ) -> impl IntoResponse {
     if !auth.is_authenticated() {
-        return html! {"authentication failed"}.into_response();
+        return auth_failed().into_response();
     }
 
     let word = BannedWord::from(word);
@@ -557,11 +3635,15 @@ async fn post_banned_word(
         .unwrap();
 
     let db = db().await;
-    match sqlx::query("INSERT OR REPLACE INTO banned_words (word, full_ban) VALUES (?, ?)")
-        .bind(&word.word)
-        .bind(word.full_ban)
-        .execute(db)
-        .await
+    match sqlx::query(
+        "INSERT OR REPLACE INTO banned_words (word, full_ban, category, language) VALUES (?, ?, ?, ?)",
+    )
+    .bind(&word.word)
+    .bind(word.full_ban)
+    .bind(word.category.name())
+    .bind(word.language.code())
+    .execute(db)
+    .await
     {
         Ok(_) => {}
         Err(err) => {
@@ -574,6 +3656,7 @@ async fn post_banned_word(
         }
     }
 
+    delete_logged_messages(&word);
     word_table_row(word).into_response()
 }
 
@@ -589,11 +3672,23 @@ async fn put_banned_word(
     Form(params): Form<BannedWordParams>,
 ) -> Markup {
     if !auth.is_authenticated() {
-        return html! {"authentication failed"};
+        return auth_failed();
     }
-    let word = BannedWord {
-        word,
-        full_ban: params.full_ban.is_some(),
+
+    let db = db().await;
+    let word: BannedWord = match sqlx::query_as(
+        "UPDATE banned_words SET full_ban = ? WHERE word = ? RETURNING word, full_ban, category, language",
+    )
+    .bind(params.full_ban.is_some())
+    .bind(&word)
+    .fetch_one(db)
+    .await
+    {
+        Ok(word) => word,
+        Err(err) => {
+            error!("failed to save banned word: {}", err);
+            return html! {"failed to save banned word"};
+        }
     };
 
     action_tx
@@ -601,29 +3696,410 @@ async fn put_banned_word(
         .await
         .unwrap();
 
+    delete_logged_messages(&word);
+    word_table_row(word)
+}
+
+#[derive(Deserialize)]
+struct CategoryParams {
+    category: String,
+}
+
+async fn put_banned_word_category(
+    Path(word): Path<String>,
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(params): Form<CategoryParams>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+    let Some(category) = WordCategory::from_name(&params.category) else {
+        return html! {"unknown category"};
+    };
+
     let db = db().await;
-    match sqlx::query("INSERT OR REPLACE INTO banned_words (word, full_ban) VALUES (?, ?)")
-        .bind(&word.word)
-        .bind(word.full_ban)
-        .execute(db)
-        .await
+    let word: BannedWord = match sqlx::query_as(
+        "UPDATE banned_words SET category = ? WHERE word = ? RETURNING word, full_ban, category, language",
+    )
+    .bind(category.name())
+    .bind(&word)
+    .fetch_one(db)
+    .await
     {
-        Ok(_) => {}
+        Ok(word) => word,
         Err(err) => {
             error!("failed to save banned word: {}", err);
             return html! {"failed to save banned word"};
         }
+    };
+
+    action_tx
+        .send(AdminAction::BanWord(word.clone()))
+        .await
+        .unwrap();
+
+    word_table_row(word)
+}
+
+#[derive(Deserialize)]
+struct LanguageParams {
+    language: String,
+}
+
+async fn put_banned_word_language(
+    Path(word): Path<String>,
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(params): Form<LanguageParams>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
     }
+    let Some(language) = Language::from_code(&params.language) else {
+        return html! {"unknown language"};
+    };
+
+    let db = db().await;
+    let word: BannedWord = match sqlx::query_as(
+        "UPDATE banned_words SET language = ? WHERE word = ? RETURNING word, full_ban, category, language",
+    )
+    .bind(language.code())
+    .bind(&word)
+    .fetch_one(db)
+    .await
+    {
+        Ok(word) => word,
+        Err(err) => {
+            error!("failed to save banned word: {}", err);
+            return html! {"failed to save banned word"};
+        }
+    };
+
+    action_tx
+        .send(AdminAction::BanWord(word.clone()))
+        .await
+        .unwrap();
+
+    delete_logged_messages(&word);
     word_table_row(word)
 }
 
+#[derive(Deserialize)]
+struct WordImportForm {
+    /// one word per line or separated by commas
+    words: String,
+    category: String,
+    language: String,
+    /// set by the preview button, nothing is saved
+    dry_run: Option<String>,
+}
+
+/// the distinct words of a pasted list, lowercased like the chat they are matched against
+fn parse_word_list(list: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for word in list.split(['\n', ',']) {
+        let word = word.trim().to_lowercase();
+        if !word.is_empty() && !words.contains(&word) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+async fn word_import_page(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return login_page();
+    }
+
+    page_base(
+        auth.theme(),
+        html! {
+            h1 { "Import Banned Words" }
+            p { a href="/" { "back to the dashboard" } }
+            p { "paste a list with one word per line or separated by commas, or load it from a file. the preview shows how many messages in the chat log of the last day each word would have caught, so words that are too broad stand out before they are saved" }
+            form hx-post="/word_import" hx-target="#WordImport" {
+                textarea id="WordList" name="words" rows="12" cols="40" placeholder="Words" required {}
+                input type="file" id="WordFile" accept=".txt,.csv,text/plain";
+                select name="category" { (category_options(WordCategory::default())) }
+                select name="language" { (language_options(Language::default())) }
+                button type="submit" name="dry_run" value="true" { "preview" }
+                button type="submit" { "import" }
+            }
+            div id="WordImport" {}
+        },
+    )
+}
+
+async fn post_word_import(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<WordImportForm>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+    let Some(category) = WordCategory::from_name(&form.category) else {
+        return html! {"unknown category"};
+    };
+    let Some(language) = Language::from_code(&form.language) else {
+        return html! {"unknown language"};
+    };
+    let words = parse_word_list(&form.words);
+    if words.is_empty() {
+        return html! {"no words to import"};
+    }
+
+    let db = db().await;
+    let banned: Vec<String> = match sqlx::query_scalar("SELECT word FROM banned_words")
+        .fetch_all(db)
+        .await
+    {
+        Ok(banned) => banned,
+        Err(err) => {
+            error!("failed to load banned words: {}", err);
+            return html! {"failed to load banned words"};
+        }
+    };
+
+    if form.dry_run.is_some() {
+        // lowercase the log once instead of for every word
+        let messages = ADMIN_CHAT_LOG
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|msg| !msg.deleted)
+            .map(|msg| msg.msg.to_lowercase())
+            .collect::<Vec<_>>();
+
+        return html! {
+            p { (words.len()) " words, checked against " (messages.len()) " recent messages" }
+            table {
+                (table_header(&["Word", "Matches", "Example", ""]))
+                @for word in &words {
+                    @let matches = messages.iter().filter(|msg| msg.contains(word.as_str())).collect::<Vec<_>>();
+                    tr class=[(!matches.is_empty()).then_some("banned")] {
+                        td { (word) }
+                        td { (matches.len()) }
+                        td { (matches.first().map(|msg| msg.as_str()).unwrap_or_default()) }
+                        td { @if banned.contains(word) { "already banned" } }
+                    }
+                }
+            }
+        };
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        for word in &words {
+            sqlx::query(
+                "INSERT OR REPLACE INTO banned_words (word, full_ban, category, language) VALUES (?, ?, ?, ?)",
+            )
+            .bind(word)
+            .bind(true)
+            .bind(category.name())
+            .bind(language.code())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+    .await;
+    if let Err(err) = result {
+        error!("failed to import banned words: {}", err);
+        return html! {"failed to import banned words"};
+    }
+    info!(
+        "imported {} banned {} words as {}",
+        words.len(),
+        language.name(),
+        category.name()
+    );
+
+    let total = words.len();
+    let new = words.iter().filter(|word| !banned.contains(word)).count();
+    for word in words {
+        let word = BannedWord {
+            word,
+            full_ban: true,
+            category,
+            language,
+        };
+        action_tx
+            .send(AdminAction::BanWord(word.clone()))
+            .await
+            .unwrap();
+        delete_logged_messages(&word);
+    }
+
+    html! {
+        p { "imported " (total) " " (language.name()) " words as " (category.name()) ", " (new) " of them new" }
+    }
+}
+
+async fn get_word_categories(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let severities = game_server::get_word_severities();
+
+    html! {
+        table {
+            (table_header(&["Category", "Severity"]))
+            @for (category, severity) in severities {
+                tr {
+                    td { (category.name()) }
+                    td {
+                        select name="severity" hx-put=(format!("/word_categories/{}", category.name())) hx-swap="none" {
+                            @for option in Severity::ALL {
+                                option value=(option.name()) selected[option == severity] { (option.name()) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SeverityParams {
+    severity: String,
+}
+
+async fn put_word_category(
+    Path(category): Path<String>,
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(params): Form<SeverityParams>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+    let (Some(category), Some(severity)) = (
+        WordCategory::from_name(&category),
+        Severity::from_name(&params.severity),
+    ) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+
+    info!(
+        "{} words are now handled with {}",
+        category.name(),
+        severity.name()
+    );
+    action_tx
+        .send(AdminAction::SetWordSeverity { category, severity })
+        .await
+        .unwrap();
+
+    let db = db().await;
+    if let Err(err) =
+        sqlx::query("INSERT OR REPLACE INTO word_categories (category, severity) VALUES (?, ?)")
+            .bind(category.name())
+            .bind(severity.name())
+            .execute(db)
+            .await
+    {
+        error!("failed to save word category: {}", err);
+        return http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    http::StatusCode::OK.into_response()
+}
+
+async fn get_word_languages(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return auth_failed();
+    }
+
+    let languages = game_server::get_word_languages();
+
+    html! {
+        table {
+            (table_header(&["Language", "Enabled", "Strict"]))
+            @for (language, policy) in languages {
+                @let url = format!("/word_languages/{}", language.code());
+                tr hx-swap="none" {
+                    td { (language.name()) }
+                    td { (toggle("enabled", &format!("{}/enabled", url), policy.enabled)) }
+                    td { (toggle("strict", &format!("{}/strict", url), policy.strict)) }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LanguageFlagParams {
+    enabled: Option<String>,
+    strict: Option<String>,
+}
+
+async fn put_word_language(
+    Path((language, flag)): Path<(String, String)>,
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(params): Form<LanguageFlagParams>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed().into_response();
+    }
+    let Some(language) = Language::from_code(&language) else {
+        return http::StatusCode::BAD_REQUEST.into_response();
+    };
+
+    let mut policy = game_server::word_language(language);
+    match flag.as_str() {
+        "enabled" => policy.enabled = params.enabled.is_some(),
+        "strict" => policy.strict = params.strict.is_some(),
+        _ => return http::StatusCode::BAD_REQUEST.into_response(),
+    }
+
+    info!(
+        "{} words are now {}, {}",
+        language.name(),
+        if policy.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        },
+        if policy.strict {
+            "strict"
+        } else {
+            "not strict"
+        }
+    );
+    action_tx
+        .send(AdminAction::SetWordLanguage { language, policy })
+        .await
+        .unwrap();
+
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT OR REPLACE INTO word_languages (language, enabled, strict) VALUES (?, ?, ?)",
+    )
+    .bind(language.code())
+    .bind(policy.enabled)
+    .bind(policy.strict)
+    .execute(db)
+    .await
+    {
+        error!("failed to save word language: {}", err);
+        return http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    http::StatusCode::OK.into_response()
+}
+
 async fn delete_banned_word(
     Path(word): Path<String>,
     Extension(action_tx): Extension<Sender<AdminAction>>,
     Extension(auth): Extension<login::AuthState>,
 ) -> impl IntoResponse {
     if !auth.is_authenticated() {
-        return html! {"authentication failed"}.into_response();
+        return auth_failed().into_response();
     }
 
     action_tx
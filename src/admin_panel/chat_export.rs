@@ -0,0 +1,207 @@
+//! the chat log as subtitles, so streamers can lay the in-game chat over their vods. cue times are
+//! relative to when the stream started, deleted messages are never part of it.
+
+use super::{components::auth_failed, login, ADMIN_CHAT_LOG};
+use crate::links;
+use axum::{
+    extract::Form,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::Deserialize;
+use std::fmt::Write;
+
+/// how long a line stays up if nobody writes after it
+const CUE_LENGTH: Duration = Duration::seconds(5);
+/// lines sent right after each other still get this long, even if they overlap then
+const MIN_CUE_LENGTH: Duration = Duration::seconds(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Vtt,
+    Srt,
+}
+
+impl SubtitleFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Vtt => "vtt",
+            SubtitleFormat::Srt => "srt",
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Vtt => "text/vtt; charset=utf-8",
+            SubtitleFormat::Srt => "application/x-subrip; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    /// when the stream started, from a datetime-local input in UTC
+    stream_start: String,
+    /// the part of the log to export. from the stream start until now if left empty
+    #[serde(default)]
+    from: String,
+    #[serde(default)]
+    to: String,
+    format: SubtitleFormat,
+    /// leave out lines with banned words and links stream mode wouldn't show
+    stream_mode: Option<String>,
+}
+
+struct Cue {
+    start: Duration,
+    end: Duration,
+    text: String,
+}
+
+fn parse_time(input: &str) -> Option<DateTime<Utc>> {
+    // browsers leave out the seconds when they are zero
+    NaiveDateTime::parse_from_str(input, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(input, "%Y-%m-%dT%H:%M"))
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+/// the text a message ended up with, `edit_logged_message` adds the original to it
+fn final_text(msg: &str) -> &str {
+    msg.split(" (edited, was '").next().unwrap_or(msg)
+}
+
+/// `hh:mm:ss.mmm`, srt uses a comma before the milliseconds
+fn cue_time(time: Duration, separator: char) -> String {
+    let ms = time.num_milliseconds().max(0);
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+fn render(cues: &[Cue], format: SubtitleFormat) -> String {
+    let mut out = String::new();
+    let separator = match format {
+        SubtitleFormat::Vtt => {
+            out.push_str("WEBVTT\n\n");
+            '.'
+        }
+        SubtitleFormat::Srt => ',',
+    };
+
+    for (i, cue) in cues.iter().enumerate() {
+        if format == SubtitleFormat::Srt {
+            let _ = writeln!(out, "{}", i + 1);
+        }
+        let text = match format {
+            // `-->` and tags would break the cue otherwise
+            SubtitleFormat::Vtt => cue
+                .text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            SubtitleFormat::Srt => cue.text.clone(),
+        };
+        let _ = writeln!(
+            out,
+            "{} --> {}\n{}\n",
+            cue_time(cue.start, separator),
+            cue_time(cue.end, separator),
+            text
+        );
+    }
+    out
+}
+
+pub async fn get_chat_export(
+    Extension(auth): Extension<login::AuthState>,
+    Form(params): Form<ExportParams>,
+) -> Response {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    let Some(stream_start) = parse_time(&params.stream_start) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let from = match params.from.as_str() {
+        "" => Some(stream_start),
+        from => parse_time(from),
+    };
+    let to = match params.to.as_str() {
+        "" => Some(Utc::now()),
+        to => parse_time(to),
+    };
+    let (Some(from), Some(to)) = (from, to) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    // nothing before the stream started can be shown on it
+    let from = from.max(stream_start);
+    let stream_mode = params.stream_mode.is_some();
+
+    let lines = ADMIN_CHAT_LOG
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|msg| msg.timestamp >= from && msg.timestamp <= to && !msg.deleted)
+        .filter(|msg| !stream_mode || !msg.contains_banned)
+        .filter_map(|msg| {
+            let text = final_text(&msg.msg).replace('\n', " ");
+            let text = if stream_mode {
+                links::filter_links(&text, true)
+            } else {
+                text
+            };
+            (!text.is_empty()).then(|| {
+                (
+                    msg.timestamp - stream_start,
+                    format!("{}: {}", msg.sender_name, text),
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let cues = lines
+        .iter()
+        .enumerate()
+        .map(|(i, (start, text))| {
+            let end = match lines.get(i + 1) {
+                Some((next, _)) => (*next).clamp(*start + MIN_CUE_LENGTH, *start + CUE_LENGTH),
+                None => *start + CUE_LENGTH,
+            };
+            Cue {
+                start: *start,
+                end,
+                text: text.clone(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let filename = format!(
+        "chat-{}.{}",
+        stream_start.format("%Y%m%d-%H%M"),
+        params.format.extension()
+    );
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                params.format.content_type().to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        render(&cues, params.format),
+    )
+        .into_response()
+}
@@ -0,0 +1,151 @@
+//! markup shared by the admin panel pages, so sections don't have to repeat it
+
+use chrono::{DateTime, Utc};
+use maud::{html, Markup};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    pub const ALL: [ColorScheme; 2] = [ColorScheme::Light, ColorScheme::Dark];
+
+    /// as stored in the users table
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scheme| scheme.name() == name)
+    }
+}
+
+/// how the panel looks for a user. picked per account
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Theme {
+    pub scheme: ColorScheme,
+    /// smaller spacing, mostly for phones
+    pub compact: bool,
+}
+
+impl Theme {
+    /// classes on the html element, the stylesheet does the rest
+    fn classes(&self) -> String {
+        let mut classes = self.scheme.name().to_string();
+        if self.compact {
+            classes.push_str(" compact");
+        }
+        classes
+    }
+}
+
+pub fn page_base(theme: Theme, body: Markup) -> Markup {
+    html! {
+        (maud::DOCTYPE)
+        html class=(theme.classes()) {
+            head {
+                title { "Cibo Online! (Admin Panel)" }
+                meta content="text/html;charset=utf-8" http-equiv="Content-Type";
+                meta name="viewport" content="width=device-width, initial-scale=1";
+                link rel="apple-touch-icon" sizes="120x120" href="/shared/apple-touch-icon.png";
+                link rel="icon" type="image/png" sizes="32x32" href="/shared/favicon-32x32.png";
+                link rel="icon" type="image/png" sizes="16x16" href="/shared/favicon-16x16.png";
+                link rel="manifest" href="/shared/site.webmanifest";
+                link rel="mask-icon" href="/shared/safari-pinned-tab.svg" color="#5bbad5";
+                link rel="shortcut icon" href="/shared/favicon.ico";
+                meta name="msapplication-TileColor" content="#da532c";
+                meta name="msapplication-config" content="/shared/browserconfig.xml";
+                meta name="theme-color" content=(match theme.scheme {
+                    ColorScheme::Light => "#ffffff",
+                    ColorScheme::Dark => "#1e1f22",
+                });
+
+                link rel="stylesheet" href="/style.css";
+            }
+
+            body {
+                (body)
+                script src="https://unpkg.com/htmx.org@2.0.1" {}
+                script src="/script.js" {}
+            }
+        }
+    }
+}
+
+/// lets a user switch their theme. the page reloads once it is saved
+pub fn theme_form(theme: Theme) -> Markup {
+    html! {
+        form id="Theme" hx-put="/theme" hx-trigger="change" hx-swap="none" {
+            select name="scheme" {
+                @for scheme in ColorScheme::ALL {
+                    option value=(scheme.name()) selected[scheme == theme.scheme] { (scheme.name()) }
+                }
+            }
+            label { input type="checkbox" name="compact" checked[theme.compact]; "compact" }
+        }
+    }
+}
+
+pub fn auth_failed() -> Markup {
+    html! { p { "authentication failed" } }
+}
+
+/// a heading with an optional explanation below it
+pub fn section(title: &str, description: &str, content: Markup) -> Markup {
+    html! {
+        div {
+            h2 { (title) }
+            @if !description.is_empty() {
+                p { (description) }
+            }
+            (content)
+        }
+    }
+}
+
+/// the first row of a table. use an empty column for buttons
+pub fn table_header(columns: &[&str]) -> Markup {
+    html! {
+        tr {
+            @for column in columns {
+                th { (column) }
+            }
+        }
+    }
+}
+
+/// a checkbox that puts its state to `url` whenever it is clicked
+pub fn toggle(name: &str, url: &str, checked: bool) -> Markup {
+    html! {
+        input type="checkbox" name=(name) hx-put=(url) checked[checked];
+    }
+}
+
+pub fn delete_button(url: &str) -> Markup {
+    html! {
+        button hx-delete=(url) { "x" }
+    }
+}
+
+/// adds the ip to the banned ips table on the main page
+pub fn ban_ip_button(ip: &str) -> Markup {
+    html! {
+        form hx-post="/banned_ips" hx-target="#BannedIPs" hx-swap="beforeend" {
+            input type="hidden" name="ip" value=(ip);
+            button type="submit" { "ban" }
+        }
+    }
+}
+
+/// formats a unix timestamp, times are in UTC
+pub fn timestamp(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,116 @@
+//! playing cutscenes on stages, see `cibo_online::server::Cutscene` for the script format. the
+//! scripts aren't kept, admins paste them in when the show starts.
+
+use super::{components::auth_failed, login, AdminAction};
+use crate::game_server;
+use axum::{extract::Form, Extension};
+use cibo_online::{
+    server::{Cutscene, CUTSCENE_STEP_LIMIT},
+    PlaceableObject, Position,
+};
+use maud::{html, Markup};
+use serde::Deserialize;
+use tokio::sync::mpsc::Sender;
+
+const EXAMPLE_SCRIPT: &str = "place Host 5 4
+say Host welcome everyone!
+wait 2
+move Host 8 4 1.5
+camera 8 3 4
+effect confetti 8 3 3
+wait 3
+leave Host";
+
+/// the form for the dashboard
+pub async fn cutscene_form() -> Markup {
+    let stages = game_server::get_placed_objects()
+        .into_iter()
+        .filter(|(kind, _)| *kind == PlaceableObject::Stage)
+        .map(|(_, position)| position)
+        .collect::<Vec<_>>();
+
+    html! {
+        @if stages.is_empty() {
+            p { "place a stage in the editor first" }
+        } @else {
+            form hx-post="/cutscenes" hx-target="#CutsceneStatus" {
+                select name="stage" {
+                    @for stage in &stages {
+                        option value=(format!("{},{}", stage.x, stage.y)) {
+                            "stage at " (stage.x.div_euclid(16)) " / " (stage.y.div_euclid(16))
+                        }
+                    }
+                }
+                br;
+                textarea name="script" rows="10" cols="60" placeholder=(EXAMPLE_SCRIPT) required {}
+                br;
+                button type="submit" { "play" }
+                button type="button" hx-delete="/cutscenes" hx-target="#CutsceneStatus" { "stop" }
+            }
+            p id="CutsceneStatus" {}
+            details {
+                summary { "script format" }
+                p { "one step per line, up to " (CUTSCENE_STEP_LIMIT) ". tiles are counted from the top left corner of the stage, actor names are one word. everything but wait happens right away" }
+                pre {
+                    "place <actor> <x> <y>\n"
+                    "move <actor> <x> <y> <seconds>\n"
+                    "say <actor> <line>\n"
+                    "leave <actor>\n"
+                    "camera <x> <y> <seconds>\n"
+                    "effect <confetti|hearts|spotlight> <x> <y> <seconds>\n"
+                    "wait <seconds>\n"
+                    "# comment"
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CutsceneForm {
+    /// position of the stage in pixels, as `x,y`
+    stage: String,
+    script: String,
+}
+
+pub async fn post_cutscene(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+    Form(form): Form<CutsceneForm>,
+) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    let Some(stage) = form
+        .stage
+        .split_once(',')
+        .and_then(|(x, y)| Some(Position::new(x.parse().ok()?, y.parse().ok()?)))
+    else {
+        return html! { "no stage selected" };
+    };
+    let cutscene = match Cutscene::parse(&form.script) {
+        Ok(cutscene) => cutscene,
+        Err(err) => return html! { "the script has a mistake, " (err) },
+    };
+
+    let (steps, seconds) = (cutscene.steps(), cutscene.duration_ms().div_ceil(1000));
+    action_tx
+        .send(AdminAction::PlayCutscene { stage, cutscene })
+        .await
+        .unwrap();
+
+    html! { "playing " (steps) " steps, about " (seconds) "s" }
+}
+
+pub async fn delete_cutscene(
+    Extension(action_tx): Extension<Sender<AdminAction>>,
+    Extension(auth): Extension<login::AuthState>,
+) -> Markup {
+    if !auth.is_authenticated() || auth.user().unwrap().username != "admin" {
+        return auth_failed();
+    }
+
+    action_tx.send(AdminAction::StopCutscene).await.unwrap();
+    html! { "stopped" }
+}
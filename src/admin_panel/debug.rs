@@ -0,0 +1,156 @@
+//! developer tools, only built with the `debug_tools` feature. `/debug/diff` asks the client of a
+//! player for the world it sees and shows where it disagrees with the server.
+
+use super::{
+    components::{auth_failed, page_base, table_header},
+    login, login_page,
+};
+use crate::game_server;
+use axum::{extract::Path, http, response::IntoResponse, routing::get, Extension, Router};
+use chrono::{DateTime, Utc};
+use cibo_online::{
+    debug::{self, WorldSnapshot},
+    ClientId,
+};
+use maud::{html, Markup};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{LazyLock, Mutex},
+};
+use tracing::info;
+
+/// positions this close count as the same, the client lags behind for everything that moves
+const POSITION_TOLERANCE: i64 = 16;
+
+struct Capture {
+    server: WorldSnapshot,
+    client: WorldSnapshot,
+    captured_at: DateTime<Utc>,
+}
+
+/// clients that were asked for a snapshot, others are ignored so nobody can flood the server with them
+static REQUESTED: LazyLock<Mutex<HashSet<ClientId>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+static CAPTURES: LazyLock<Mutex<HashMap<ClientId, Capture>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// keep the snapshot a client sent, if it was asked for one. `server` should be taken right away,
+/// so both are as close in time as possible
+pub fn snapshot_submitted(client_id: ClientId, server: WorldSnapshot, client: WorldSnapshot) {
+    if !REQUESTED.lock().unwrap().remove(&client_id) {
+        return;
+    }
+    CAPTURES.lock().unwrap().insert(
+        client_id,
+        Capture {
+            server,
+            client,
+            captured_at: Utc::now(),
+        },
+    );
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/debug/diff", get(diff_page))
+        .route("/debug/diff/:client", get(get_diff).post(post_capture))
+}
+
+async fn diff_page(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return login_page();
+    }
+
+    let players = game_server::get_players();
+    page_base(
+        auth.theme(),
+        html! {
+            h1 { "World Diff" }
+            p { a href="/" { "back to the dashboard" } }
+            p { "asks the client of a player for the players and objects it sees and compares them with the server. positions up to " (POSITION_TOLERANCE) " pixels apart are treated as the same" }
+            table {
+                (table_header(&["Player", "Id", ""]))
+                @for (id, name) in &players {
+                    tr {
+                        td { (name) }
+                        td { (id.as_u32()) }
+                        td {
+                            button hx-post={"/debug/diff/"(id.as_u32())} hx-target="#Diff" { "capture" }
+                        }
+                    }
+                }
+            }
+            div id="Diff" {}
+        },
+    )
+}
+
+async fn post_capture(
+    Path(client): Path<u32>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    let client_id = ClientId::from_u32(client);
+    CAPTURES.lock().unwrap().remove(&client_id);
+    REQUESTED.lock().unwrap().insert(client_id);
+    if !game_server::request_snapshot(client_id) {
+        REQUESTED.lock().unwrap().remove(&client_id);
+        return http::StatusCode::NOT_FOUND.into_response();
+    }
+    info!("asked client {} for a world snapshot", client);
+
+    // polls until the client answered
+    html! {
+        div hx-get={"/debug/diff/"(client)} hx-trigger="every 1s" hx-swap="outerHTML" {
+            "waiting for the client..."
+        }
+    }
+    .into_response()
+}
+
+async fn get_diff(
+    Path(client): Path<u32>,
+    Extension(auth): Extension<login::AuthState>,
+) -> impl IntoResponse {
+    if !auth.is_authenticated() {
+        return auth_failed().into_response();
+    }
+
+    let client_id = ClientId::from_u32(client);
+    let captures = CAPTURES.lock().unwrap();
+    let Some(capture) = captures.get(&client_id) else {
+        let online = game_server::get_players()
+            .iter()
+            .any(|(id, _)| *id == client_id);
+        if !online {
+            REQUESTED.lock().unwrap().remove(&client_id);
+            return html! { "the player left before their client answered" }.into_response();
+        }
+        // htmx keeps polling
+        return http::StatusCode::NO_CONTENT.into_response();
+    };
+
+    let differences = debug::diff(&capture.server, &capture.client, POSITION_TOLERANCE);
+    html! {
+        div {
+            h2 { "Client " (client) }
+            p {
+                "captured at " (capture.captured_at.format("%H:%M:%S UTC")) ". the server has "
+                (capture.server.clients.len()) " players and " (capture.server.objects.len()) " objects, the client "
+                (capture.client.clients.len()) " and " (capture.client.objects.len())
+            }
+            @if differences.is_empty() {
+                p { "no differences" }
+            } @else {
+                ul {
+                    @for difference in &differences {
+                        li { (difference.to_string()) }
+                    }
+                }
+            }
+        }
+    }
+    .into_response()
+}
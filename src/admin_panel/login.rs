@@ -1,3 +1,4 @@
+use super::components::{ColorScheme, Theme};
 use crate::db::db;
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
@@ -50,6 +51,17 @@ pub struct User {
     pub id: i64,
     pub username: String,
     password: String,
+    theme: String,
+    compact: bool,
+}
+
+impl User {
+    pub fn theme(&self) -> Theme {
+        Theme {
+            scheme: ColorScheme::from_name(&self.theme).unwrap_or_default(),
+            compact: self.compact,
+        }
+    }
 }
 
 impl core::fmt::Debug for User {
@@ -71,6 +83,11 @@ impl AuthState {
     pub fn user(&self) -> Option<&User> {
         self.0.as_ref()
     }
+
+    /// the theme the user picked, or the default one on the login page
+    pub fn theme(&self) -> Theme {
+        self.0.as_ref().map(User::theme).unwrap_or_default()
+    }
 }
 
 pub async fn auth(mut req: Request, next: middleware::Next) -> Response {
@@ -113,7 +130,7 @@ pub async fn auth(mut req: Request, next: middleware::Next) -> Response {
 
             sqlx::query_as!(
                 User,
-                "SELECT id AS 'id!', username, password FROM users WHERE id = ?",
+                "SELECT id AS 'id!', username, password, theme, compact FROM users WHERE id = ?",
                 user_id
             )
             .fetch_optional(db)
@@ -168,7 +185,7 @@ pub async fn login(data: LoginData) -> Result<SessionId, LoginError> {
     let db = db().await;
     let user = sqlx::query_as!(
         User,
-        "SELECT id AS 'id!', username, password FROM users WHERE username = ?",
+        "SELECT id AS 'id!', username, password, theme, compact FROM users WHERE username = ?",
         data.username
     )
     .fetch_optional(db)
@@ -0,0 +1,240 @@
+//! replays of the movement recordings, see `movement_recordings`. the path the server had the player
+//! on is drawn over the placed objects and protected regions, with every move the client claimed
+//! on top. claimed moves further from the server position than anti-cheat allows are marked.
+
+use super::{
+    components::{page_base, table_header, timestamp},
+    login, login_page, ProtectedRegion,
+};
+use crate::{
+    db::db,
+    game_server,
+    movement_recordings::{self, RecordingInfo, Replay},
+};
+use axum::{extract::Path, Extension};
+use cibo_online::{server::SPEED_SLACK, Position};
+use maud::{html, Markup};
+use tracing::error;
+
+/// room around the path, in pixels
+const PADDING: i64 = 64;
+const TILE_SIZE: i64 = 16;
+
+fn recording_row(recording: &RecordingInfo) -> Markup {
+    html! {
+        tr {
+            td { (recording.name) }
+            td { (recording.ip.as_deref().unwrap_or("local")) }
+            td { (recording.reason) }
+            td { (timestamp(recording.started_at)) }
+            td { (recording.length / 1000) "s" }
+            td { (recording.actions) }
+            td { a href=(format!("/flags/recordings/{}", recording.id)) { "replay" } }
+        }
+    }
+}
+
+pub async fn recordings_page(Extension(auth): Extension<login::AuthState>) -> Markup {
+    if !auth.is_authenticated() {
+        return login_page();
+    }
+
+    let recordings = movement_recordings::load_recordings()
+        .await
+        .unwrap_or_else(|err| {
+            error!("failed to load movement recordings: {}", err);
+            Vec::new()
+        });
+
+    page_base(
+        auth.theme(),
+        html! {
+            h1 { "Movement Recordings" }
+            p { a href="/flags" { "back to the flags" } }
+            p { "a flag starts recording everything the player sends for the next 10 minutes, or until they leave. replay it to see whether the moves were actually impossible or just lag before banning someone. recordings go away together with the flags" }
+            table {
+                (table_header(&["Name", "IP", "Flagged For", "Started", "Length", "Actions", ""]))
+                @for recording in &recordings {
+                    (recording_row(recording))
+                }
+            }
+        },
+    )
+}
+
+/// where the server had the player at `at`, the last position before it
+fn server_position_at(server: &[(u64, Position)], at: u64) -> Option<Position> {
+    let index = server.partition_point(|(time, _)| *time <= at);
+    server
+        .get(index.checked_sub(1)?)
+        .map(|(_, position)| *position)
+}
+
+fn distance(a: Position, b: Position) -> i64 {
+    (a.x - b.x).abs() + (a.y - b.y).abs()
+}
+
+/// the replay marker, moving along the server path in real time
+fn marker(server: &[(u64, Position)]) -> Markup {
+    let (Some((_, first)), Some((end, _))) = (server.first(), server.last()) else {
+        return html! {};
+    };
+    if *end == 0 {
+        return html! { circle class="marker" cx=(first.x) cy=(first.y) r="6" {} };
+    }
+
+    let key_times = server
+        .iter()
+        .map(|(time, _)| format!("{:.4}", *time as f64 / *end as f64))
+        .collect::<Vec<_>>()
+        .join(";");
+    let xs = server
+        .iter()
+        .map(|(_, position)| position.x.to_string())
+        .collect::<Vec<_>>()
+        .join(";");
+    let ys = server
+        .iter()
+        .map(|(_, position)| position.y.to_string())
+        .collect::<Vec<_>>()
+        .join(";");
+    let duration = format!("{}ms", end);
+
+    html! {
+        circle class="marker" cx=(first.x) cy=(first.y) r="6" {
+            animate attributeName="cx" values=(xs) keyTimes=(key_times) dur=(duration) repeatCount="indefinite" {}
+            animate attributeName="cy" values=(ys) keyTimes=(key_times) dur=(duration) repeatCount="indefinite" {}
+        }
+    }
+}
+
+fn replay_svg(replay: &Replay, regions: &[ProtectedRegion]) -> Markup {
+    let points = replay
+        .server
+        .iter()
+        .chain(replay.claimed.iter())
+        .map(|(_, position)| *position);
+    let (min, max) = points.fold(
+        (
+            Position::new(i64::MAX, i64::MAX),
+            Position::new(i64::MIN, i64::MIN),
+        ),
+        |(min, max), position| {
+            (
+                Position::new(min.x.min(position.x), min.y.min(position.y)),
+                Position::new(max.x.max(position.x), max.y.max(position.y)),
+            )
+        },
+    );
+    let (min, max) = (
+        Position::new(min.x - PADDING, min.y - PADDING),
+        Position::new(max.x + PADDING, max.y + PADDING),
+    );
+    let view_box = format!("{} {} {} {}", min.x, min.y, max.x - min.x, max.y - min.y);
+    let inside = |position: Position| {
+        position.x >= min.x && position.x <= max.x && position.y >= min.y && position.y <= max.y
+    };
+
+    let objects = game_server::get_placed_objects()
+        .into_iter()
+        .filter(|(_, position)| inside(*position))
+        .collect::<Vec<_>>();
+    let path = replay
+        .server
+        .iter()
+        .map(|(_, position)| format!("{},{}", position.x, position.y))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    html! {
+        svg id="Replay" viewBox=(view_box) {
+            @for region in regions {
+                rect class="region" x=(region.min_x * TILE_SIZE) y=(region.min_y * TILE_SIZE) width=((region.max_x - region.min_x + 1) * TILE_SIZE) height=((region.max_y - region.min_y + 1) * TILE_SIZE) {
+                    title { (region.name) }
+                }
+            }
+            @for (kind, position) in &objects {
+                circle class="object" cx=(position.x) cy=(position.y) r="4" {
+                    title { (kind.name()) }
+                }
+            }
+            polyline class="path" points=(path) {}
+            @for (at, position) in &replay.claimed {
+                @let off = server_position_at(&replay.server, *at).map(|server| distance(server, *position));
+                @let suspicious = off.is_some_and(|off| off > SPEED_SLACK);
+                circle class=[suspicious.then_some("suspicious")] cx=(position.x) cy=(position.y) r=(if suspicious { "3" } else { "1" }) {
+                    title {
+                        (format!("{:.1}s: claimed {}, {}", *at as f64 / 1000.0, position.x, position.y))
+                        @if let Some(off) = off { ", " (off) "px from the server position" }
+                    }
+                }
+            }
+            (marker(&replay.server))
+        }
+    }
+}
+
+pub async fn replay_page(
+    Path(id): Path<i64>,
+    Extension(auth): Extension<login::AuthState>,
+) -> Markup {
+    if !auth.is_authenticated() {
+        return login_page();
+    }
+
+    let replay = match movement_recordings::load_replay(id).await {
+        Ok(Some(replay)) => replay,
+        Ok(None) => return page_base(auth.theme(), html! { p { "no such recording" } }),
+        Err(err) => {
+            error!("failed to load movement recording {}: {}", id, err);
+            return page_base(auth.theme(), html! { p { "loading failed: " (err) } });
+        }
+    };
+    let regions: Vec<ProtectedRegion> =
+        sqlx::query_as("SELECT name, min_x, min_y, max_x, max_y FROM protected_regions")
+            .fetch_all(db().await)
+            .await
+            .unwrap_or_else(|err| {
+                error!("failed to load protected regions: {}", err);
+                Vec::new()
+            });
+
+    let suspicious = replay
+        .claimed
+        .iter()
+        .filter(|(at, position)| {
+            server_position_at(&replay.server, *at)
+                .is_some_and(|server| distance(server, *position) > SPEED_SLACK)
+        })
+        .count();
+    let furthest = replay
+        .claimed
+        .iter()
+        .filter_map(|(at, position)| {
+            Some(distance(
+                server_position_at(&replay.server, *at)?,
+                *position,
+            ))
+        })
+        .max()
+        .unwrap_or(0);
+    let info = &replay.info;
+
+    page_base(
+        auth.theme(),
+        html! {
+            h1 { "Movement of " (info.name) }
+            p { a href="/flags/recordings" { "back to the recordings" } }
+            p {
+                "flagged for " (info.reason) " at " (timestamp(info.started_at)) ", recorded for " (info.length / 1000) "s. "
+                (replay.claimed.len()) " moves, " (suspicious) " of them more than " (SPEED_SLACK) "px from where the server had the player. the furthest was " (furthest) "px"
+            }
+            p { "the line is where the server had the player and the dot follows it in real time. small dots are the moves the client sent, big red ones were too far to walk. hover them for details. a few right after each other usually means speeding, single far ones that the server didn't follow were refused teleports" }
+            @if replay.server.is_empty() && replay.claimed.is_empty() {
+                p { "nothing was recorded, the player probably left right away" }
+            } @else {
+                (replay_svg(&replay, &regions))
+            }
+        },
+    )
+}
@@ -0,0 +1,47 @@
+//! public read-only json api, versioned so outside tools don't break when it changes.
+
+use crate::game_server;
+use axum::{http::StatusCode, routing::get, Json, Router};
+use cibo_online::MinigameKind;
+use serde::Serialize;
+use tracing::error;
+
+/// how many players of every leaderboard are listed
+const LEADERBOARD_LIMIT: u32 = 10;
+
+#[derive(Serialize)]
+struct Leaderboard {
+    game: &'static str,
+    entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Serialize)]
+struct LeaderboardEntry {
+    name: String,
+    score: u32,
+}
+
+pub fn routes() -> Router {
+    Router::new().route("/api/v1/leaderboards", get(get_leaderboards))
+}
+
+async fn get_leaderboards() -> Result<Json<Vec<Leaderboard>>, StatusCode> {
+    let mut leaderboards = Vec::new();
+    for game in MinigameKind::ALL {
+        let entries = game_server::load_leaderboard(game, LEADERBOARD_LIMIT)
+            .await
+            .map_err(|err| {
+                error!("failed to load leaderboard: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+        leaderboards.push(Leaderboard {
+            game: game.name(),
+            entries: entries
+                .into_iter()
+                .map(|(name, score)| LeaderboardEntry { name, score })
+                .collect(),
+        });
+    }
+    Ok(Json(leaderboards))
+}
@@ -0,0 +1,139 @@
+//! public page where banned players can ask to be unbanned. it's served by the game server,
+//! so it stays reachable when the websocket refuses the connection.
+
+use crate::{db::db, game_server};
+use axum::{
+    extract::{connect_info::ConnectInfo, Form},
+    http::HeaderMap,
+    routing::get,
+    Router,
+};
+use maud::{html, Markup};
+use serde::Deserialize;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use tracing::{error, info};
+
+/// time an ip has to wait before it can send another appeal, in seconds
+const APPEAL_COOLDOWN: i64 = 24 * 60 * 60;
+const MESSAGE_LIMIT: usize = 2000;
+const CONTACT_LIMIT: usize = 200;
+
+#[derive(Deserialize)]
+struct AppealForm {
+    message: String,
+    contact: String,
+}
+
+pub fn routes() -> Router {
+    Router::new().route("/appeal", get(get_appeal).post(post_appeal))
+}
+
+fn page(body: Markup) -> Markup {
+    html! {
+        (maud::DOCTYPE)
+        html {
+            head {
+                title { "Cibo Online! (Ban Appeal)" }
+                meta content="text/html;charset=utf-8" http-equiv="Content-Type";
+                link rel="shortcut icon" href="/shared/favicon.ico";
+            }
+            body {
+                h1 { "Ban Appeal" }
+                (body)
+            }
+        }
+    }
+}
+
+/// the ip the request came from, like the websocket handler resolves it
+pub(crate) fn request_ip(addr: SocketAddr, headers: &HeaderMap) -> Option<IpAddr> {
+    match headers.get("x-real-ip") {
+        Some(ip) => ip.to_str().ok()?.parse().ok(),
+        None if addr.ip() == IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)) => Some(addr.ip()),
+        None => None,
+    }
+}
+
+/// when the ip last sent an appeal, as a unix timestamp
+async fn last_appeal(ip: IpAddr) -> Result<Option<i64>, sqlx::Error> {
+    let db = db().await;
+    sqlx::query_scalar("SELECT MAX(created_at) FROM ban_appeals WHERE ip = ?")
+        .bind(ip.to_string())
+        .fetch_one(db)
+        .await
+}
+
+async fn get_appeal(ConnectInfo(addr): ConnectInfo<SocketAddr>, headers: HeaderMap) -> Markup {
+    let Some(ip) = request_ip(addr, &headers) else {
+        return page(html! { p { "couldn't tell where you are connecting from" } });
+    };
+    if !game_server::is_banned(ip) {
+        return page(html! { p { "you are not banned, have fun playing!" } });
+    }
+
+    page(html! {
+        p { "you were banned from cibo online. if you think this was a mistake, tell us what happened and we will take a look." }
+        form method="post" action="/appeal" {
+            p {
+                textarea name="message" rows="10" cols="60" maxlength=(MESSAGE_LIMIT) placeholder="what happened?" required {}
+            }
+            p {
+                input type="text" name="contact" maxlength=(CONTACT_LIMIT) placeholder="how can we reach you? (optional)";
+            }
+            button type="submit" { "send" }
+        }
+    })
+}
+
+async fn post_appeal(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(form): Form<AppealForm>,
+) -> Markup {
+    let Some(ip) = request_ip(addr, &headers) else {
+        return page(html! { p { "couldn't tell where you are connecting from" } });
+    };
+    if !game_server::is_banned(ip) {
+        return page(html! { p { "you are not banned, have fun playing!" } });
+    }
+
+    let message = form.message.trim();
+    let contact = form.contact.trim();
+    if message.is_empty() || message.len() > MESSAGE_LIMIT || contact.len() > CONTACT_LIMIT {
+        return page(
+            html! { p { "please write a message of up to " (MESSAGE_LIMIT) " characters" } },
+        );
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    match last_appeal(ip).await {
+        Ok(Some(last)) if now - last < APPEAL_COOLDOWN => {
+            return page(
+                html! { p { "you already sent an appeal recently, please wait for an answer" } },
+            );
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("failed to look up appeals: {}", err);
+            return page(html! { p { "something went wrong, please try again later" } });
+        }
+    }
+
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO ban_appeals (ip, message, contact, created_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(ip.to_string())
+    .bind(message)
+    .bind(contact)
+    .bind(now)
+    .execute(db)
+    .await
+    {
+        error!("failed to save appeal: {}", err);
+        return page(html! { p { "something went wrong, please try again later" } });
+    }
+    info!("received a ban appeal from {}", ip);
+
+    page(html! { p { "thanks, your appeal was sent!" } })
+}
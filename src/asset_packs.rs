@@ -0,0 +1,118 @@
+//! art that clients download at runtime, e.g. seasonal skins or event tiles. every folder in
+//! `ASSET_PACK_DIR` (`asset_packs` by default) is a pack, its `.ppm` and `.pbm` files replace the
+//! built-in assets with the same name. packs are read on startup, broken ones are skipped.
+
+use crate::db::env_or;
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use cibo_online::asset_pack::{AssetPack, AssetPackInfo};
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::LazyLock,
+};
+use tracing::{error, info};
+
+struct LoadedPack {
+    info: AssetPackInfo,
+    bytes: Vec<u8>,
+}
+
+static PACKS: LazyLock<Vec<LoadedPack>> = LazyLock::new(load_packs);
+
+fn load_packs() -> Vec<LoadedPack> {
+    let dir = env_or("ASSET_PACK_DIR", "asset_packs".to_string());
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(err) => {
+            error!("failed to read asset packs from {}: {}", dir, err);
+            return Vec::new();
+        }
+    };
+
+    let mut packs = Vec::new();
+    for entry in entries.flatten().filter(|entry| entry.path().is_dir()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        match load_pack(name.clone(), &entry.path()) {
+            Ok(pack) => {
+                info!("loaded asset pack {} from {}", name, pack.info.path);
+                packs.push(pack);
+            }
+            Err(err) => error!("skipping asset pack {}: {}", name, err),
+        }
+    }
+    packs.sort_by(|a, b| a.info.name.cmp(&b.info.name));
+    packs
+}
+
+fn load_pack(name: String, dir: &std::path::Path) -> Result<LoadedPack, String> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(|err| err.to_string())? {
+        let path = entry.map_err(|err| err.to_string())?.path();
+        let is_image = path
+            .extension()
+            .is_some_and(|extension| extension == "ppm" || extension == "pbm");
+        if !is_image {
+            continue;
+        }
+        let file = path.file_name().unwrap().to_string_lossy().into_owned();
+        let bytes = std::fs::read(&path).map_err(|err| err.to_string())?;
+        files.push((file, bytes));
+    }
+    if files.is_empty() {
+        return Err("no images in it".to_string());
+    }
+    files.sort();
+
+    let pack = AssetPack { name, files };
+    // better to find out now than on every client
+    pack.check().map_err(|err| err.to_string())?;
+    let bytes = pack.to_bytes().map_err(|err| err.to_string())?;
+
+    // fingerprinted like the client files, so the pack can be cached until it changes
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    let path = format!("/packs/{}.{:016x}.pack", pack.name, hasher.finish());
+
+    Ok(LoadedPack {
+        info: AssetPackInfo {
+            name: pack.name,
+            path,
+        },
+        bytes,
+    })
+}
+
+/// read the packs now, so broken ones show up in the log before anyone joins
+pub fn load() {
+    LazyLock::force(&PACKS);
+}
+
+/// the packs clients should load, told to them when they connect
+pub fn advertised() -> Vec<AssetPackInfo> {
+    PACKS.iter().map(|pack| pack.info.clone()).collect()
+}
+
+pub fn routes() -> Router {
+    Router::new().route("/packs/:file", get(get_pack))
+}
+
+async fn get_pack(Path(file): Path<String>) -> impl IntoResponse {
+    let path = format!("/packs/{}", file);
+    match PACKS.iter().find(|pack| pack.info.path == path) {
+        Some(pack) => (
+            [
+                (header::CONTENT_TYPE, "application/octet-stream"),
+                (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+            ],
+            pack.bytes.clone(),
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
@@ -0,0 +1,134 @@
+use crate::db::db;
+use cibo_online::PlaceableObject;
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: cibo_online-server [command]
+
+commands:
+    serve                 run the game server and admin panel (default)
+    export-world <file>   write the placed world objects to a file
+    import-world <file>   replace the placed world objects with the ones from a file
+    wipe-objects          remove all placed world objects
+
+the world commands operate on the database, stop the server before running them.";
+
+pub enum Command {
+    Serve,
+    ExportWorld(PathBuf),
+    ImportWorld(PathBuf),
+    WipeObjects,
+}
+
+impl Command {
+    /// parse the command from the arguments, without the binary name
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let command = match args.next().as_deref() {
+            None | Some("serve") => Command::Serve,
+            Some("export-world") => Command::ExportWorld(file_arg(args.next())?),
+            Some("import-world") => Command::ImportWorld(file_arg(args.next())?),
+            Some("wipe-objects") => Command::WipeObjects,
+            Some("help" | "-h" | "--help") => return Err(USAGE.to_string()),
+            Some(other) => return Err(format!("unknown command '{}'\n\n{}", other, USAGE)),
+        };
+
+        match args.next() {
+            Some(extra) => Err(format!("unexpected argument '{}'\n\n{}", extra, USAGE)),
+            None => Ok(command),
+        }
+    }
+
+    /// run a non-serve command to completion
+    pub async fn run(self) -> Result<(), String> {
+        match self {
+            Command::Serve => unreachable!("serve is handled by main"),
+            Command::ExportWorld(path) => export_world(path).await,
+            Command::ImportWorld(path) => import_world(path).await,
+            Command::WipeObjects => wipe_objects().await,
+        }
+    }
+}
+
+fn file_arg(arg: Option<String>) -> Result<PathBuf, String> {
+    arg.map(PathBuf::from)
+        .ok_or_else(|| format!("missing file argument\n\n{}", USAGE))
+}
+
+/// the world file has one object per line: `<x> <y> <name>`. names can contain spaces, so they go last
+async fn export_world(path: PathBuf) -> Result<(), String> {
+    let objects: Vec<(String, i64, i64)> =
+        sqlx::query_as("SELECT object, x, y FROM world_objects ORDER BY id")
+            .fetch_all(db().await)
+            .await
+            .map_err(|err| format!("failed to load world objects: {}", err))?;
+
+    let mut file = String::new();
+    for (object, x, y) in &objects {
+        file.push_str(&format!("{} {} {}\n", x, y, object));
+    }
+    tokio::fs::write(&path, file)
+        .await
+        .map_err(|err| format!("failed to write {}: {}", path.display(), err))?;
+
+    println!("exported {} objects to {}", objects.len(), path.display());
+    Ok(())
+}
+
+async fn import_world(path: PathBuf) -> Result<(), String> {
+    let file = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+
+    // validate everything before touching the db
+    let mut objects = Vec::new();
+    for (i, line) in file.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let parse_line = || {
+            let mut parts = line.splitn(3, ' ');
+            let x: i64 = parts.next()?.parse().ok()?;
+            let y: i64 = parts.next()?.parse().ok()?;
+            let object = PlaceableObject::from_name(parts.next()?.trim())?;
+            Some((object, x, y))
+        };
+        match parse_line() {
+            Some(object) => objects.push(object),
+            None => return Err(format!("invalid object on line {}: '{}'", i + 1, line)),
+        }
+    }
+
+    let db = db().await;
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        sqlx::query("DELETE FROM world_objects")
+            .execute(&mut *tx)
+            .await?;
+        for (object, x, y) in &objects {
+            sqlx::query("INSERT INTO world_objects (object, x, y) VALUES (?, ?, ?)")
+                .bind(object.name())
+                .bind(x)
+                .bind(y)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+    .await;
+    result.map_err(|err| format!("failed to save world objects: {}", err))?;
+
+    println!("imported {} objects from {}", objects.len(), path.display());
+    Ok(())
+}
+
+async fn wipe_objects() -> Result<(), String> {
+    let removed = sqlx::query("DELETE FROM world_objects")
+        .execute(db().await)
+        .await
+        .map_err(|err| format!("failed to remove world objects: {}", err))?
+        .rows_affected();
+
+    println!("removed {} objects", removed);
+    Ok(())
+}
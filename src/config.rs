@@ -0,0 +1,42 @@
+//! settings from `config.toml` in the working directory (or wherever `CONFIG_FILE` points to).
+//! the file is optional, most settings are still read from environment variables.
+
+use crate::{
+    db::env_or, digest::DigestConfig, discord::DiscordConfig, discord_bridge::DiscordBridgeConfig,
+    links::LinkConfig, restart::RestartConfig, tls::TlsConfig, webhooks::WebhookConfig,
+};
+use serde::Deserialize;
+use std::sync::LazyLock;
+use tracing::info;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// serve both listeners over https/wss instead of plain http
+    pub tls: Option<TlsConfig>,
+    /// restart the server on a schedule, warning players beforehand
+    pub restart: Option<RestartConfig>,
+    /// what happens to links in chat
+    pub links: Option<LinkConfig>,
+    /// pass chat and events on to overlays and bridges
+    pub webhooks: Option<WebhookConfig>,
+    /// let players link their discord account on the landing page
+    pub discord: Option<DiscordConfig>,
+    /// pass public chat between the game and a discord channel
+    pub discord_bridge: Option<DiscordBridgeConfig>,
+    /// post a summary of every day to discord
+    pub digest: Option<DigestConfig>,
+}
+
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| {
+    let path = env_or("CONFIG_FILE", "config.toml".to_string());
+    match std::fs::read_to_string(&path) {
+        Ok(config) => {
+            info!("loaded config from {}", path);
+            // better to not start at all than to silently ignore a broken setting
+            toml::from_str(&config).unwrap_or_else(|err| panic!("invalid config {}: {}", path, err))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Config::default(),
+        Err(err) => panic!("failed to read config {}: {}", path, err),
+    }
+});
@@ -1,10 +1,17 @@
 use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool};
 use sqlx::Error;
 
-use std::str::FromStr;
+use std::{
+    net::IpAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 use tokio::sync::OnceCell;
+use tracing::{error, info, warn};
 
 const DB_PATH: &str = "sqlite://./data/db.sqlite?mode=rwc";
+const BACKUP_DIR: &str = "./data/backups";
 
 pub async fn db() -> &'static SqlitePool {
     static DB: OnceCell<SqlitePool> = OnceCell::const_new();
@@ -17,6 +24,146 @@ async fn init_db() -> Result<SqlitePool, Error> {
     let pool = SqlitePool::connect_with(options).await?;
 
     sqlx::migrate!("./migrations").run(&pool).await?;
+    import_legacy(&pool).await?;
 
     Ok(pool)
 }
+
+pub fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// snapshot the database into the backup directory and remove old snapshots
+pub async fn backup() -> Result<PathBuf, Error> {
+    tokio::fs::create_dir_all(BACKUP_DIR).await?;
+
+    let path = Path::new(BACKUP_DIR).join(format!(
+        "db-{}.sqlite",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy())
+        .execute(db().await)
+        .await?;
+
+    rotate_backups(env_or("BACKUP_KEEP", 7)).await?;
+    Ok(path)
+}
+
+async fn rotate_backups(keep: usize) -> Result<(), Error> {
+    let mut backups = Vec::new();
+    let mut entries = tokio::fs::read_dir(BACKUP_DIR).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with("db-") && name.ends_with(".sqlite") {
+            backups.push(entry.path());
+        }
+    }
+
+    // the timestamp in the name sorts chronologically
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    for old in backups.into_iter().take(excess) {
+        info!("removing old backup {}", old.display());
+        tokio::fs::remove_file(old).await?;
+    }
+    Ok(())
+}
+
+/// periodically back up the database. the interval is set with BACKUP_INTERVAL_HOURS, 0 disables it
+pub async fn run_backups() {
+    let hours: u64 = env_or("BACKUP_INTERVAL_HOURS", 24);
+    if hours == 0 {
+        info!("automatic backups are disabled");
+        return;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(hours * 60 * 60));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // the first tick completes immediately, don't back up on every restart
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        match backup().await {
+            Ok(path) => info!("backed up database to {}", path.display()),
+            Err(err) => error!("failed to back up database: {}", err),
+        }
+    }
+}
+
+/// directories the old file based server kept its ban lists in
+const LEGACY_DIRS: [&str; 2] = ["./data", "."];
+
+/// read a legacy list file, skipping empty lines and comments
+async fn read_legacy_file(path: &Path) -> Option<Vec<String>> {
+    let content = tokio::fs::read_to_string(path).await.ok()?;
+    Some(
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// move an imported file out of the way so it only gets imported once
+async fn mark_imported(path: &Path) {
+    let imported = path.with_extension("txt.imported");
+    if let Err(err) = tokio::fs::rename(path, &imported).await {
+        warn!("failed to rename {}: {}", path.display(), err);
+    }
+}
+
+/// import banned_ips.txt and banned_words.txt from the old server into the database
+async fn import_legacy(pool: &SqlitePool) -> Result<(), Error> {
+    for dir in LEGACY_DIRS {
+        let ips_path = Path::new(dir).join("banned_ips.txt");
+        if let Some(lines) = read_legacy_file(&ips_path).await {
+            let mut imported = 0;
+            for line in lines {
+                let Ok(ip) = line.parse::<IpAddr>() else {
+                    warn!("skipping invalid legacy ip '{}'", line);
+                    continue;
+                };
+                imported += sqlx::query("INSERT OR IGNORE INTO banned_ips (ip) VALUES (?)")
+                    .bind(ip.to_string())
+                    .execute(pool)
+                    .await?
+                    .rows_affected();
+            }
+            info!(
+                "imported {} banned ips from {}",
+                imported,
+                ips_path.display()
+            );
+            mark_imported(&ips_path).await;
+        }
+
+        let words_path = Path::new(dir).join("banned_words.txt");
+        if let Some(lines) = read_legacy_file(&words_path).await {
+            let mut imported = 0;
+            for word in lines {
+                // the old server had no light bans
+                imported += sqlx::query(
+                    "INSERT OR IGNORE INTO banned_words (word, full_ban) VALUES (?, TRUE)",
+                )
+                .bind(word.to_lowercase())
+                .execute(pool)
+                .await?
+                .rows_affected();
+            }
+            info!(
+                "imported {} banned words from {}",
+                imported,
+                words_path.display()
+            );
+            mark_imported(&words_path).await;
+        }
+    }
+
+    Ok(())
+}
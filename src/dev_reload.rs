@@ -0,0 +1,135 @@
+//! reloads open game tabs after the web client was rebuilt, only built with the `dev_reload`
+//! feature. `static/game` is checked for changes and every page served from it gets a small script
+//! that listens on `/dev/reload` and reloads once the files differ from the ones it was loaded with.
+//! the server restarting after `cargo run` counts too, the script notices when it reconnects.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, Response, StatusCode},
+    middleware,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    routing::get,
+    Router,
+};
+use std::{
+    convert::Infallible,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::{Duration, SystemTime},
+};
+use tokio::sync::watch;
+use tracing::info;
+
+const WATCHED_DIR: &str = "./static/game";
+/// how often the client files are checked for changes
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+const RELOAD_SCRIPT: &str = r#"<script>
+    let dev_version;
+    new EventSource('/dev/reload').onmessage = (event) => {
+        if (dev_version && dev_version !== event.data) location.reload();
+        dev_version = event.data;
+    };
+</script>"#;
+
+/// when the client files were last changed, as seen by the watcher
+static VERSION: LazyLock<watch::Sender<String>> = LazyLock::new(|| watch::channel(String::new()).0);
+
+pub fn routes() -> Router {
+    tokio::spawn(watch_client());
+    Router::new().route("/dev/reload", get(reload_events))
+}
+
+async fn reload_events() -> impl IntoResponse {
+    // the current version is sent right away, so a restarted server is noticed on reconnecting
+    let stream =
+        futures::stream::unfold((VERSION.subscribe(), true), |(mut rx, first)| async move {
+            if !first {
+                rx.changed().await.ok()?;
+            }
+            let version = rx.borrow_and_update().clone();
+            Some((
+                Ok::<_, Infallible>(Event::default().data(version)),
+                (rx, false),
+            ))
+        });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// add the reload script to html pages
+pub async fn inject_script(req: Request, next: middleware::Next) -> Response<Body> {
+    let response = next.run(req).await;
+    let html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|content_type| content_type.as_bytes().starts_with(b"text/html"));
+    if !html {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let body =
+        String::from_utf8_lossy(&body).replace("</body>", &format!("{}</body>", RELOAD_SCRIPT));
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// the newest modification time of all files below `dir`
+async fn newest_change(dir: &Path) -> Option<SystemTime> {
+    let mut newest = None;
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else if let Ok(modified) = metadata.modified() {
+                newest = newest.max(Some(modified));
+            }
+        }
+    }
+    newest
+}
+
+fn version_of(time: Option<SystemTime>) -> String {
+    time.and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|time| time.as_nanos().to_string())
+        .unwrap_or_default()
+}
+
+async fn watch_client() {
+    let dir = PathBuf::from(WATCHED_DIR);
+    let mut last_change = newest_change(&dir).await;
+    VERSION.send_replace(version_of(last_change));
+
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    // a rebuild deletes and copies the files one by one, so wait until they stopped changing
+    let mut settling = false;
+    loop {
+        interval.tick().await;
+
+        let current = newest_change(&dir).await;
+        if current != last_change {
+            last_change = current;
+            settling = true;
+            continue;
+        }
+        if settling {
+            settling = false;
+            info!("web client changed, reloading dev clients");
+            VERSION.send_replace(version_of(current));
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! a daily summary of what happened on the server. the counters are kept in memory and added to
+//! the row of the current UTC day every few minutes, so restarts only lose the last bit. once a day
+//! is over, its row is finished and optionally posted to a discord webhook. configured by a
+//! `[digest]` section in the config:
+//!
+//! ```toml
+//! [digest]
+//! discord_webhook = "https://discord.com/api/webhooks/..."
+//! ```
+//!
+//! the digests are shown in the admin panel either way.
+
+use crate::{config::CONFIG, db::db, game_server};
+use chrono::{Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+use tracing::{error, info, instrument, warn};
+
+/// how often the player count is sampled for the peak
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+/// the counters are saved every this many samples
+const SAMPLES_PER_FLUSH: u32 = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    /// finished digests are posted here
+    discord_webhook: Option<String>,
+}
+
+static MESSAGES: AtomicU64 = AtomicU64::new(0);
+static FILTER_HITS: AtomicU64 = AtomicU64::new(0);
+static NEW_BANS: AtomicU64 = AtomicU64::new(0);
+static PEAK_PLAYERS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn count_message() {
+    MESSAGES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// a message that had a banned word in it, whatever happened to it
+pub fn count_filter_hit() {
+    FILTER_HITS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn count_ban() {
+    NEW_BANS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct DailyStats {
+    /// `YYYY-MM-DD` in UTC
+    pub day: String,
+    pub unique_players: i64,
+    pub peak_players: i64,
+    pub messages: i64,
+    pub filter_hits: i64,
+    pub new_bans: i64,
+    pub finished: bool,
+}
+
+impl DailyStats {
+    /// the values with their names, in the order they are shown
+    pub fn values(&self) -> [(&'static str, i64); 5] {
+        [
+            ("unique players", self.unique_players),
+            ("peak players", self.peak_players),
+            ("messages", self.messages),
+            ("filter hits", self.filter_hits),
+            ("new bans", self.new_bans),
+        ]
+    }
+
+    /// a short summary with the change to the day before, if there is one
+    fn summary(&self, previous: Option<&DailyStats>) -> String {
+        let mut summary = format!("**cibo online, {}**", self.day);
+        for (index, (name, value)) in self.values().into_iter().enumerate() {
+            summary.push_str(&format!("\n{}: {}", name, value));
+            if let Some(previous) = previous {
+                summary.push_str(&format!(" ({})", change(value, previous.values()[index].1)));
+            }
+        }
+        summary
+    }
+}
+
+/// the difference between two values of neighbouring days, like `+3` or `-1`
+pub fn change(value: i64, previous: i64) -> String {
+    format!("{:+}", value - previous)
+}
+
+/// the utc timestamps the day starts and ends at
+fn day_bounds(day: NaiveDate) -> (i64, i64) {
+    let start = day.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
+    (start, start + 24 * 60 * 60)
+}
+
+fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+/// add the counters to the row of the day and reset them
+async fn flush(day: NaiveDate) -> Result<(), sqlx::Error> {
+    let messages = MESSAGES.swap(0, Ordering::Relaxed);
+    let filter_hits = FILTER_HITS.swap(0, Ordering::Relaxed);
+    let new_bans = NEW_BANS.swap(0, Ordering::Relaxed);
+    let peak_players = PEAK_PLAYERS.swap(0, Ordering::Relaxed);
+
+    sqlx::query(
+        "INSERT INTO daily_stats (day, peak_players, messages, filter_hits, new_bans) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT (day) DO UPDATE SET
+            peak_players = MAX(peak_players, excluded.peak_players),
+            messages = messages + excluded.messages,
+            filter_hits = filter_hits + excluded.filter_hits,
+            new_bans = new_bans + excluded.new_bans",
+    )
+    .bind(day.to_string())
+    .bind(peak_players as i64)
+    .bind(messages as i64)
+    .bind(filter_hits as i64)
+    .bind(new_bans as i64)
+    .execute(db().await)
+    .await?;
+    Ok(())
+}
+
+/// save the counters before the server exits
+pub async fn save() {
+    if let Err(err) = flush(today()).await {
+        error!("failed to save the daily stats: {}", err);
+    }
+}
+
+async fn unique_players(day: NaiveDate) -> Result<i64, sqlx::Error> {
+    let (start, end) = day_bounds(day);
+    sqlx::query_scalar(
+        "SELECT COUNT(DISTINCT ip) FROM connections WHERE joined_at >= ? AND joined_at < ?",
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_one(db().await)
+    .await
+}
+
+/// the digests of the most recent days, newest first. today is included with the numbers so far
+pub async fn recent(days: i64) -> Result<Vec<DailyStats>, sqlx::Error> {
+    let mut recent: Vec<DailyStats> = sqlx::query_as(
+        "SELECT day, unique_players, peak_players, messages, filter_hits, new_bans, finished
+         FROM daily_stats ORDER BY day DESC LIMIT ?",
+    )
+    .bind(days)
+    .fetch_all(db().await)
+    .await?;
+
+    let today = today();
+    let mut current = match recent.first() {
+        Some(stats) if stats.day == today.to_string() => recent.remove(0),
+        _ => DailyStats {
+            day: today.to_string(),
+            ..Default::default()
+        },
+    };
+    // the counters that weren't saved yet
+    current.messages += MESSAGES.load(Ordering::Relaxed) as i64;
+    current.filter_hits += FILTER_HITS.load(Ordering::Relaxed) as i64;
+    current.new_bans += NEW_BANS.load(Ordering::Relaxed) as i64;
+    current.peak_players = current
+        .peak_players
+        .max(PEAK_PLAYERS.load(Ordering::Relaxed) as i64);
+    current.unique_players = unique_players(today).await?;
+    recent.insert(0, current);
+    recent.truncate(days as usize);
+    Ok(recent)
+}
+
+/// fill in what is only known once the day is over and post the digest
+async fn finish(day: NaiveDate, client: Option<&reqwest::Client>) -> Result<(), sqlx::Error> {
+    let db = db().await;
+    let stats: DailyStats = sqlx::query_as(
+        "UPDATE daily_stats SET unique_players = ?, finished = TRUE WHERE day = ?
+         RETURNING day, unique_players, peak_players, messages, filter_hits, new_bans, finished",
+    )
+    .bind(unique_players(day).await?)
+    .bind(day.to_string())
+    .fetch_one(db)
+    .await?;
+    let previous: Option<DailyStats> = sqlx::query_as(
+        "SELECT day, unique_players, peak_players, messages, filter_hits, new_bans, finished
+         FROM daily_stats WHERE day = ?",
+    )
+    .bind(
+        day.checked_sub_days(Days::new(1))
+            .map(|day| day.to_string()),
+    )
+    .fetch_optional(db)
+    .await?;
+
+    let summary = stats.summary(previous.as_ref());
+    info!("finished the digest for {}", stats.day);
+    if let Some(client) = client {
+        post(client, summary).await;
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct WebhookMessage {
+    content: String,
+    allowed_mentions: AllowedMentions,
+}
+
+#[derive(Serialize)]
+struct AllowedMentions {
+    parse: [&'static str; 0],
+}
+
+async fn post(client: &reqwest::Client, content: String) {
+    let Some(url) = CONFIG
+        .digest
+        .as_ref()
+        .and_then(|config| config.discord_webhook.as_ref())
+    else {
+        return;
+    };
+    let result = client
+        .post(url)
+        .json(&WebhookMessage {
+            content,
+            allowed_mentions: AllowedMentions { parse: [] },
+        })
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(err) = result {
+        warn!("failed to post digest: {}", err);
+    }
+}
+
+#[instrument(name = "digest")]
+pub async fn run() {
+    let client = CONFIG
+        .digest
+        .as_ref()
+        .and_then(|config| config.discord_webhook.as_ref())
+        .map(|_| {
+            reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap()
+        });
+
+    // days the server was down over aren't finished yet
+    let unfinished: Vec<String> = sqlx::query_scalar(
+        "SELECT day FROM daily_stats WHERE finished = FALSE AND day < ? ORDER BY day",
+    )
+    .bind(today().to_string())
+    .fetch_all(db().await)
+    .await
+    .unwrap_or_else(|err| {
+        error!("failed to load unfinished digests: {}", err);
+        Vec::new()
+    });
+    for day in unfinished {
+        let Ok(day) = day.parse::<NaiveDate>() else {
+            continue;
+        };
+        if let Err(err) = finish(day, client.as_ref()).await {
+            error!("failed to finish the digest for {}: {}", day, err);
+        }
+    }
+
+    let mut day = today();
+    let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut samples = 0;
+    loop {
+        interval.tick().await;
+        let players = game_server::get_shard_players().into_iter().sum();
+        PEAK_PLAYERS.fetch_max(players, Ordering::Relaxed);
+
+        let now = today();
+        samples += 1;
+        if samples < SAMPLES_PER_FLUSH && now == day {
+            continue;
+        }
+        samples = 0;
+        // right after midnight, what was counted since the last flush mostly belongs to the day before
+        if let Err(err) = flush(day).await {
+            error!("failed to save the daily stats: {}", err);
+        }
+        if now != day {
+            if let Err(err) = finish(day, client.as_ref()).await {
+                error!("failed to finish the digest for {}: {}", day, err);
+            }
+            day = now;
+        }
+    }
+}
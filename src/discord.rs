@@ -0,0 +1,339 @@
+//! optional discord login on the landing page that links a discord account to the account key of a
+//! player. linked players get a verified badge in game and can register their account to get past
+//! the guest time limit, and chat webhooks carry their discord id so bridges can attribute messages.
+//! enabled by a `[discord]` section in the config:
+//!
+//! ```toml
+//! [discord]
+//! client_id = "..."
+//! client_secret = "..."
+//! redirect_url = "https://cibo.example.com/discord/callback"
+//! ```
+//!
+//! the redirect url has to be added to the oauth2 settings of the discord application as well.
+
+use crate::{config::CONFIG, db::db, game_server};
+use axum::{
+    extract::Query,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form, Json, Router,
+};
+use cibo_online::{client::valid_account_key, webhook};
+use maud::{html, Markup};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
+
+const AUTHORIZE_URL: &str = "https://discord.com/oauth2/authorize";
+const TOKEN_URL: &str = "https://discord.com/api/oauth2/token";
+const USER_URL: &str = "https://discord.com/api/users/@me";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// logins that take longer than this have to start over
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// started logins that are kept at once, the oldest one is dropped past that
+const MAX_PENDING: usize = 4096;
+/// holds the signed `state` of the login started in this browser, so a callback link sent by
+/// someone else can't link their discord account to the player's account key
+const STATE_COOKIE: &str = "discord_state";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordConfig {
+    client_id: String,
+    client_secret: String,
+    /// where discord sends players back to, has to point to `/discord/callback`
+    redirect_url: String,
+}
+
+/// the account key each started login is for, by the `state` sent to discord. an account key has
+/// at most one login going
+static PENDING: LazyLock<Mutex<HashMap<String, (String, Instant)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordUser {
+    pub id: String,
+    pub username: String,
+}
+
+#[derive(Deserialize)]
+struct AccountForm {
+    account: String,
+}
+
+#[derive(Deserialize)]
+struct CallbackQuery {
+    code: Option<String>,
+    state: String,
+}
+
+#[derive(Serialize)]
+struct LinkStatus {
+    /// the discord name the account is linked to
+    linked: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/discord/status", post(post_status))
+        .route("/discord/login", post(post_login))
+        .route("/discord/callback", get(get_callback))
+}
+
+fn page(body: Markup) -> Markup {
+    html! {
+        (maud::DOCTYPE)
+        html {
+            head {
+                title { "Cibo Online! (Discord)" }
+                meta content="text/html;charset=utf-8" http-equiv="Content-Type";
+                link rel="shortcut icon" href="/shared/favicon.ico";
+            }
+            body {
+                h1 { "Discord" }
+                (body)
+                p { a href="/" { "back to the game" } }
+            }
+        }
+    }
+}
+
+/// the discord account linked to the account key, if any
+pub async fn linked_user(account: &str) -> Option<DiscordUser> {
+    let db = db().await;
+    match sqlx::query_as::<_, (String, String)>(
+        "SELECT discord_id, discord_name FROM discord_links WHERE account = ?",
+    )
+    .bind(account)
+    .fetch_optional(db)
+    .await
+    {
+        Ok(user) => user.map(|(id, username)| DiscordUser { id, username }),
+        Err(err) => {
+            error!("failed to look up discord link: {}", err);
+            None
+        }
+    }
+}
+
+/// link the account key to the discord user, returns the account keys the user was linked to before
+async fn save_link(account: &str, user: &DiscordUser) -> Result<Vec<String>, sqlx::Error> {
+    let db = db().await;
+    // linking again moves the discord account over from the old account key
+    let previous = sqlx::query_scalar::<_, String>(
+        "SELECT account FROM discord_links WHERE discord_id = ? AND account != ?",
+    )
+    .bind(&user.id)
+    .bind(account)
+    .fetch_all(db)
+    .await?;
+    sqlx::query("DELETE FROM discord_links WHERE discord_id = ? AND account != ?")
+        .bind(&user.id)
+        .bind(account)
+        .execute(db)
+        .await?;
+    sqlx::query(
+        "INSERT INTO discord_links (account, discord_id, discord_name, linked_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT (account) DO UPDATE SET discord_id = excluded.discord_id, discord_name = excluded.discord_name, linked_at = excluded.linked_at",
+    )
+    .bind(account)
+    .bind(&user.id)
+    .bind(&user.username)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(db)
+    .await?;
+    Ok(previous)
+}
+
+/// 404 if discord login isn't configured, so the landing page knows to hide the link. the account
+/// key is sent in the body so it doesn't end up in access logs
+async fn post_status(Form(query): Form<AccountForm>) -> Response {
+    if CONFIG.discord.is_none() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    if !valid_account_key(&query.account) {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    let linked = linked_user(&query.account).await.map(|user| user.username);
+    Json(LinkStatus { linked }).into_response()
+}
+
+/// `{timestamp}.{signature}` of the state, signed like webhooks with the client secret
+fn state_cookie(config: &DiscordConfig, state: &str) -> String {
+    let timestamp = chrono::Utc::now().timestamp() as u64;
+    let signature = webhook::sign(config.client_secret.as_bytes(), timestamp, state.as_bytes());
+    format!("{}.{}", timestamp, signature)
+}
+
+/// whether the state cookie of the browser was set for the login with this `state`
+fn state_cookie_matches(config: &DiscordConfig, headers: &HeaderMap, state: &str) -> bool {
+    let Some(cookie) = headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|cookie| cookie.to_str().ok())
+        .flat_map(|cookie| cookie.split(';'))
+        .filter_map(|cookie| cookie::Cookie::parse(cookie.trim()).ok())
+        .find(|cookie| cookie.name() == STATE_COOKIE)
+    else {
+        return false;
+    };
+    let Some((timestamp, signature)) = cookie.value().split_once('.') else {
+        return false;
+    };
+    let Ok(timestamp) = timestamp.parse::<u64>() else {
+        return false;
+    };
+    let now = chrono::Utc::now().timestamp() as u64;
+    now.saturating_sub(timestamp) < LOGIN_TIMEOUT.as_secs()
+        && webhook::verify(
+            config.client_secret.as_bytes(),
+            timestamp,
+            state.as_bytes(),
+            signature,
+        )
+}
+
+async fn post_login(Form(query): Form<AccountForm>) -> Response {
+    let Some(config) = CONFIG.discord.as_ref() else {
+        return page(html! { p { "discord login isn't available on this server" } })
+            .into_response();
+    };
+    if !valid_account_key(&query.account) {
+        return page(
+            html! { p { "please open the game once before linking your discord account" } },
+        )
+        .into_response();
+    }
+
+    let state = format!("{:032x}", rand::random::<u128>());
+    {
+        let mut pending = PENDING.lock().unwrap();
+        pending.retain(|_, (account, started_at)| {
+            started_at.elapsed() < LOGIN_TIMEOUT && *account != query.account
+        });
+        if pending.len() >= MAX_PENDING {
+            let oldest = pending
+                .iter()
+                .min_by_key(|(_, (_, started_at))| *started_at)
+                .map(|(state, _)| state.clone());
+            if let Some(oldest) = oldest {
+                pending.remove(&oldest);
+            }
+        }
+        pending.insert(state.clone(), (query.account, Instant::now()));
+    }
+
+    let url = reqwest::Url::parse_with_params(
+        AUTHORIZE_URL,
+        &[
+            ("response_type", "code"),
+            ("client_id", config.client_id.as_str()),
+            ("scope", "identify"),
+            ("state", state.as_str()),
+            ("redirect_uri", config.redirect_url.as_str()),
+        ],
+    )
+    .unwrap();
+    let cookie = format!(
+        "{}={}; Path=/discord; Max-Age={}; HttpOnly; SameSite=Lax",
+        STATE_COOKIE,
+        state_cookie(config, &state),
+        LOGIN_TIMEOUT.as_secs()
+    );
+    ([(header::SET_COOKIE, cookie)], Redirect::to(url.as_str())).into_response()
+}
+
+async fn get_callback(headers: HeaderMap, Query(query): Query<CallbackQuery>) -> Response {
+    let clear_cookie = [(
+        header::SET_COOKIE,
+        format!("{}=; Path=/discord; Max-Age=0", STATE_COOKIE),
+    )];
+    (clear_cookie, callback(&headers, query).await).into_response()
+}
+
+async fn callback(headers: &HeaderMap, query: CallbackQuery) -> Markup {
+    let Some(config) = CONFIG.discord.as_ref() else {
+        return page(html! { p { "discord login isn't available on this server" } });
+    };
+
+    if !state_cookie_matches(config, headers, &query.state) {
+        warn!("discord callback without a matching state cookie");
+        return page(
+            html! { p { "this login wasn't started in this browser, please try again from the game" } },
+        );
+    }
+    let account = match PENDING.lock().unwrap().remove(&query.state) {
+        Some((account, started_at)) if started_at.elapsed() < LOGIN_TIMEOUT => account,
+        _ => return page(html! { p { "the login expired, please try again" } }),
+    };
+    // the player cancelled on the discord page
+    let Some(code) = query.code else {
+        return page(html! { p { "your discord account was not linked" } });
+    };
+
+    let user = match fetch_user(config, &code).await {
+        Ok(user) => user,
+        Err(err) => {
+            warn!("failed to log in with discord: {}", err);
+            return page(html! { p { "couldn't reach discord, please try again later" } });
+        }
+    };
+
+    let previous = match save_link(&account, &user).await {
+        Ok(previous) => previous,
+        Err(err) => {
+            error!("failed to save discord link: {}", err);
+            return page(html! { p { "something went wrong, please try again later" } });
+        }
+    };
+    info!("linked discord account {} ({})", user.username, user.id);
+    for previous in previous {
+        game_server::unlink_discord(&previous);
+    }
+    game_server::link_discord(&account, user.id.clone());
+
+    page(html! { p { "linked your discord account " b { (user.username) } "!" } })
+}
+
+/// trade the code from the callback for the discord user that logged in
+async fn fetch_user(config: &DiscordConfig, code: &str) -> Result<DiscordUser, reqwest::Error> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap();
+
+    let token = client
+        .post(TOKEN_URL)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.redirect_url.as_str()),
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", config.client_secret.as_str()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<TokenResponse>()
+        .await?;
+
+    client
+        .get(USER_URL)
+        .bearer_auth(token.access_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<DiscordUser>()
+        .await
+}
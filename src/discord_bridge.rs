@@ -0,0 +1,292 @@
+//! optional bridge between public chat and a discord channel. messages from the channel show up in
+//! game like chat from a linked server, under `[discord]`, and in-game chat is posted to the channel.
+//! enabled by a `[discord_bridge]` section in the config:
+//!
+//! ```toml
+//! [discord_bridge]
+//! bot_token = "..."
+//! channel_id = "123456789012345678"
+//! ```
+//!
+//! the bot needs to be able to read and send messages in the channel, and reading other people's
+//! messages needs the message content intent. both directions can be turned off in the admin panel.
+
+use crate::{config::CONFIG, game_server};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use tracing::{info, instrument, warn};
+
+const API_URL: &str = "https://discord.com/api/v10";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// how often the channel is checked for new messages
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+/// shown in front of messages from discord
+const PERSONA: &str = "discord";
+/// chat messages waiting to be posted, anything past that is dropped while discord is slow
+const CHAT_QUEUE: usize = 256;
+/// longest wait when discord asks us to slow down
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordBridgeConfig {
+    bot_token: String,
+    channel_id: String,
+}
+
+static TO_GAME: AtomicBool = AtomicBool::new(true);
+static TO_DISCORD: AtomicBool = AtomicBool::new(true);
+static OUTGOING: OnceLock<mpsc::Sender<(String, String)>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// messages from the channel are shown in game
+    ToGame,
+    /// in-game chat is posted to the channel
+    ToDiscord,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 2] = [Direction::ToGame, Direction::ToDiscord];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Direction::ToGame => "to_game",
+            Direction::ToDiscord => "to_discord",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|direction| direction.name() == name)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Direction::ToGame => "discord to game",
+            Direction::ToDiscord => "game to discord",
+        }
+    }
+
+    fn flag(&self) -> &'static AtomicBool {
+        match self {
+            Direction::ToGame => &TO_GAME,
+            Direction::ToDiscord => &TO_DISCORD,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DiscordMessage {
+    id: String,
+    content: String,
+    author: DiscordAuthor,
+    /// messages sent through channel webhooks, e.g. other bridges
+    webhook_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DiscordAuthor {
+    username: String,
+    global_name: Option<String>,
+    #[serde(default)]
+    bot: bool,
+}
+
+/// body of a 429 response
+#[derive(Deserialize)]
+struct RateLimited {
+    /// in seconds
+    retry_after: f64,
+}
+
+#[derive(Serialize)]
+struct NewMessage {
+    content: String,
+    allowed_mentions: AllowedMentions,
+}
+
+/// nothing in relayed messages is allowed to ping anyone
+#[derive(Serialize)]
+struct AllowedMentions {
+    parse: [&'static str; 0],
+}
+
+pub fn is_configured() -> bool {
+    CONFIG.discord_bridge.is_some()
+}
+
+pub fn get_direction(direction: Direction) -> bool {
+    direction.flag().load(Ordering::Relaxed)
+}
+pub fn set_direction(direction: Direction, enabled: bool) {
+    info!(
+        "discord bridge {} {}!",
+        direction.description(),
+        if enabled { "enabled" } else { "disabled" }
+    );
+    direction.flag().store(enabled, Ordering::Relaxed);
+}
+
+/// post a chat message to the channel. does nothing if the bridge isn't configured or turned off
+pub fn relay_chat(name: String, message: String) {
+    if !TO_DISCORD.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(tx) = OUTGOING.get() {
+        if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send((name, message)) {
+            warn!("dropped a chat message, discord can't keep up");
+        }
+    }
+}
+
+#[instrument(name = "discord_bridge")]
+pub async fn run() {
+    let Some(config) = CONFIG.discord_bridge.as_ref() else {
+        return;
+    };
+    info!("bridging chat with discord channel {}", config.channel_id);
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap();
+    let messages_url = format!("{}/channels/{}/messages", API_URL, config.channel_id);
+    let authorization = format!("Bot {}", config.bot_token);
+
+    let (tx, mut rx) = mpsc::channel::<(String, String)>(CHAT_QUEUE);
+    OUTGOING.set(tx).ok();
+
+    let send_client = client.clone();
+    let send_url = messages_url.clone();
+    let send_authorization = authorization.clone();
+    tokio::spawn(async move {
+        while let Some((name, message)) = rx.recv().await {
+            let message = NewMessage {
+                content: format!("**{}**: {}", escape_markdown(&name), message),
+                allowed_mentions: AllowedMentions { parse: [] },
+            };
+            // posted again once discord lets us, the queue fills up in the meantime
+            loop {
+                let result = send_client
+                    .post(&send_url)
+                    .header("authorization", &send_authorization)
+                    .json(&message)
+                    .send()
+                    .await;
+                let result = match result {
+                    Ok(response) => retry_after(response).await,
+                    Err(err) => Err(err),
+                };
+                match result {
+                    Ok(Some(wait)) => {
+                        warn!("discord is rate limiting posts, waiting {:?}", wait);
+                        tokio::time::sleep(wait).await;
+                        continue;
+                    }
+                    Ok(None) => {}
+                    Err(err) => warn!("failed to post to discord: {}", err),
+                }
+                break;
+            }
+        }
+    });
+
+    // start after the newest message, so the channel history isn't replayed into the game
+    let mut last_id = None;
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let mut request = client
+            .get(&messages_url)
+            .header("authorization", &authorization);
+        request = match &last_id {
+            Some(last_id) => request.query(&[("after", last_id.as_str()), ("limit", "50")]),
+            None => request.query(&[("limit", "1")]),
+        };
+        let response = match request.send().await {
+            Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                if let Ok(Some(wait)) = retry_after(response).await {
+                    warn!("discord is rate limiting polls, waiting {:?}", wait);
+                    tokio::time::sleep(wait).await;
+                }
+                continue;
+            }
+            result => result,
+        };
+        let messages = match response.and_then(|response| response.error_for_status()) {
+            Ok(response) => match response.json::<Vec<DiscordMessage>>().await {
+                Ok(messages) => messages,
+                Err(err) => {
+                    warn!("failed to read discord messages: {}", err);
+                    continue;
+                }
+            },
+            Err(err) => {
+                warn!("failed to fetch discord messages: {}", err);
+                continue;
+            }
+        };
+
+        let first_poll = last_id.is_none();
+        // discord sends the newest message first
+        for message in messages.into_iter().rev() {
+            last_id = Some(message.id.clone());
+            // bots include this bridge, so its own messages don't come back around
+            if first_poll
+                || message.author.bot
+                || message.webhook_id.is_some()
+                || message.content.trim().is_empty()
+                || !TO_GAME.load(Ordering::Relaxed)
+            {
+                continue;
+            }
+
+            let name = message
+                .author
+                .global_name
+                .unwrap_or(message.author.username);
+            game_server::remote_chat(PERSONA.to_string(), name, message.content);
+        }
+        // an empty channel has nothing to start after, everything from now on is new
+        if first_poll && last_id.is_none() {
+            last_id = Some("0".to_string());
+        }
+    }
+}
+
+/// how long discord wants us to wait before trying again, if it rate limited the request.
+/// other errors are passed on
+async fn retry_after(response: reqwest::Response) -> Result<Option<Duration>, reqwest::Error> {
+    if response.status() != StatusCode::TOO_MANY_REQUESTS {
+        return response.error_for_status().map(|_| None);
+    }
+    let wait = match response.json::<RateLimited>().await {
+        Ok(limited) if limited.retry_after.is_finite() && limited.retry_after >= 0.0 => {
+            Duration::from_secs_f64(limited.retry_after.min(MAX_RETRY_AFTER.as_secs_f64()))
+        }
+        _ => POLL_INTERVAL,
+    };
+    Ok(Some(wait))
+}
+
+/// keep player names from turning into discord formatting
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '_' | '~' | '`' | '|' | '>' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,221 @@
+//! optional link to other cibo-online servers that passes public chat and online counts between them.
+//! configured with `FEDERATION_NAME`, `FEDERATION_PEERS` (comma separated base urls) and `FEDERATION_SECRET`,
+//! every peer has to use the same secret.
+
+use crate::game_server;
+use axum::{
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+use tracing::{info, instrument, warn};
+
+const SECRET_HEADER: &str = "x-federation-secret";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const ONLINE_INTERVAL: Duration = Duration::from_secs(30);
+/// peers that haven't reported their online count for this long are considered offline
+const ONLINE_TIMEOUT: Duration = Duration::from_secs(90);
+/// chat messages waiting to be passed on, anything past that is dropped while the peers are slow
+const CHAT_QUEUE: usize = 256;
+
+struct Config {
+    /// shown in front of messages from this server
+    name: String,
+    peers: Vec<String>,
+    secret: String,
+}
+
+static CONFIG: LazyLock<Option<Config>> = LazyLock::new(|| {
+    let secret = std::env::var("FEDERATION_SECRET")
+        .ok()
+        .filter(|secret| !secret.is_empty())?;
+    let peers = std::env::var("FEDERATION_PEERS")
+        .ok()?
+        .split(',')
+        .map(|peer| peer.trim().trim_end_matches('/').to_string())
+        .filter(|peer| !peer.is_empty())
+        .collect::<Vec<_>>();
+    if peers.is_empty() {
+        return None;
+    }
+    let name = std::env::var("FEDERATION_NAME").unwrap_or_else(|_| "cibo".to_string());
+
+    Some(Config {
+        name,
+        peers,
+        secret,
+    })
+});
+
+static OUTGOING: OnceLock<mpsc::Sender<RemoteChat>> = OnceLock::new();
+/// online counts reported by the peers and when they arrived
+static REMOTE_ONLINE: LazyLock<Mutex<HashMap<String, (u32, Instant)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Serialize, Deserialize)]
+struct RemoteChat {
+    server: String,
+    name: String,
+    message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RemoteOnline {
+    server: String,
+    players: u32,
+}
+
+/// endpoints the peers send to. they answer with 403 if federation isn't configured
+pub fn routes() -> Router {
+    Router::new()
+        .route("/federation/chat", post(post_chat))
+        .route("/federation/online", post(post_online))
+}
+
+/// pass a chat message on to all peers. does nothing if federation isn't configured
+pub fn relay_chat(name: String, message: String) {
+    if let (Some(config), Some(tx)) = (CONFIG.as_ref(), OUTGOING.get()) {
+        let chat = RemoteChat {
+            server: config.name.clone(),
+            name,
+            message,
+        };
+        if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send(chat) {
+            warn!("dropped a chat message, the peers can't keep up");
+        }
+    }
+}
+
+#[instrument(name = "federation")]
+pub async fn run() {
+    let Some(config) = CONFIG.as_ref() else {
+        return;
+    };
+    info!(
+        "linked with {} peers as '{}'",
+        config.peers.len(),
+        config.name
+    );
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap();
+
+    let (tx, mut rx) = mpsc::channel(CHAT_QUEUE);
+    OUTGOING.set(tx).ok();
+
+    let chat_client = client.clone();
+    tokio::spawn(async move {
+        while let Some(chat) = rx.recv().await {
+            send_all(&chat_client, config, "chat", &chat).await;
+        }
+    });
+
+    let mut interval = tokio::time::interval(ONLINE_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let online = RemoteOnline {
+            server: config.name.clone(),
+            players: game_server::get_queue_stats().0 as u32,
+        };
+        send_all(&client, config, "online", &online).await;
+
+        // forget peers that went quiet, and resend the others for players that joined since
+        let remote_online = {
+            let mut remote_online = REMOTE_ONLINE.lock().unwrap();
+            let counts = remote_online
+                .iter()
+                .map(|(server, (players, received_at))| {
+                    let players = if received_at.elapsed() > ONLINE_TIMEOUT {
+                        0
+                    } else {
+                        *players
+                    };
+                    (server.clone(), players)
+                })
+                .collect::<Vec<_>>();
+            remote_online.retain(|_, (_, received_at)| received_at.elapsed() <= ONLINE_TIMEOUT);
+            counts
+        };
+        for (server, players) in remote_online {
+            game_server::remote_online(server, players);
+        }
+    }
+}
+
+/// send to every peer at once, so one that is down doesn't hold up the others
+async fn send_all(client: &reqwest::Client, config: &Config, path: &str, body: &impl Serialize) {
+    futures::future::join_all(
+        config
+            .peers
+            .iter()
+            .map(|peer| send(client, config, peer, path, body)),
+    )
+    .await;
+}
+
+async fn send(
+    client: &reqwest::Client,
+    config: &Config,
+    peer: &str,
+    path: &str,
+    body: &impl Serialize,
+) {
+    let result = client
+        .post(format!("{}/federation/{}", peer, path))
+        .header(SECRET_HEADER, &config.secret)
+        .json(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    if let Err(err) = result {
+        warn!("failed to reach peer {}: {}", peer, err);
+    }
+}
+
+fn authorized(headers: &HeaderMap) -> bool {
+    let Some(config) = CONFIG.as_ref() else {
+        return false;
+    };
+    headers.get(SECRET_HEADER).map_or(false, |secret| {
+        constant_time_eq(secret.as_bytes(), config.secret.as_bytes())
+    })
+}
+
+/// compares without returning early, so the secret can't be guessed byte by byte from how long a
+/// request takes
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+async fn post_chat(headers: HeaderMap, Json(chat): Json<RemoteChat>) -> StatusCode {
+    if !authorized(&headers) {
+        return StatusCode::FORBIDDEN;
+    }
+
+    game_server::remote_chat(chat.server, chat.name, chat.message);
+    StatusCode::OK
+}
+
+async fn post_online(headers: HeaderMap, Json(online): Json<RemoteOnline>) -> StatusCode {
+    if !authorized(&headers) {
+        return StatusCode::FORBIDDEN;
+    }
+
+    REMOTE_ONLINE
+        .lock()
+        .unwrap()
+        .insert(online.server.clone(), (online.players, Instant::now()));
+    game_server::remote_online(online.server, online.players);
+    StatusCode::OK
+}
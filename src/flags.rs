@@ -0,0 +1,73 @@
+//! flags raised by the anti-cheat heuristics of the game states. they are only logged here, the
+//! admin panel lists them with buttons to mute, kick or ban the players. flags older than
+//! `FLAG_EXPIRY_DAYS` days (14 by default) are thrown away.
+
+use crate::db::{db, env_or};
+use cibo_online::server::Flag;
+use sqlx::FromRow;
+use std::{net::IpAddr, sync::LazyLock};
+use tracing::{error, warn};
+
+pub static FLAG_EXPIRY_SECS: LazyLock<i64> =
+    LazyLock::new(|| env_or("FLAG_EXPIRY_DAYS", 14i64) * 24 * 60 * 60);
+
+/// everything flagged for one ip (or name, for local connections)
+#[derive(Debug, Clone, FromRow)]
+pub struct FlaggedPlayer {
+    pub ip: Option<String>,
+    pub name: String,
+    /// the highest score the player reached
+    pub score: i64,
+    pub flags: i64,
+    /// the reasons, comma separated
+    pub reasons: String,
+    pub last_flagged: i64,
+}
+
+pub async fn save_flags(flags: Vec<(Option<IpAddr>, String, Flag)>) {
+    let db = db().await;
+    let now = chrono::Utc::now().timestamp();
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        for (ip, name, flag) in flags {
+            warn!(
+                "flagged {} for {}, suspicion score is {}",
+                name,
+                flag.suspicion.name(),
+                flag.score
+            );
+            sqlx::query(
+                "INSERT INTO flags (ip, name, reason, score, flagged_at) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(ip.map(|ip| ip.to_string()))
+            .bind(name)
+            .bind(flag.suspicion.name())
+            .bind(flag.score as i64)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("DELETE FROM flags WHERE flagged_at <= ?")
+            .bind(now - *FLAG_EXPIRY_SECS)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+
+    if let Err(err) = result {
+        error!("failed to save flags: {}", err);
+    }
+}
+
+/// flagged players, the most suspicious first
+pub async fn load_flagged() -> Result<Vec<FlaggedPlayer>, sqlx::Error> {
+    let db = db().await;
+    sqlx::query_as(
+        "SELECT ip, MAX(name) AS name, MAX(score) AS score, COUNT(*) AS flags,
+                GROUP_CONCAT(DISTINCT reason) AS reasons, MAX(flagged_at) AS last_flagged
+         FROM flags GROUP BY COALESCE(ip, name) ORDER BY score DESC, last_flagged DESC",
+    )
+    .fetch_all(db)
+    .await
+}
@@ -1,54 +1,407 @@
-use crate::admin_panel::{log_admin_message, AdminAction, BannedWord};
+use crate::{
+    account_data,
+    acks::{self, CriticalMessages},
+    admin_panel::{
+        edit_logged_message, log_admin_message, AdminAction, BannedWord, LanguagePolicy, Severity,
+        WordCategory,
+    },
+    api, appeal, asset_packs,
+    db::{db, env_or},
+    digest, discord, discord_bridge, federation, flags, links,
+    load_governor::LoadGovernor,
+    mail, movement_recordings, name_history, seashells, tls, transfer, webhooks,
+};
 use axum::{
+    body::Body,
     extract::{
         connect_info::ConnectInfo,
         ws::{Message, WebSocket, WebSocketUpgrade},
+        Request,
     },
-    http::{HeaderMap, Response, StatusCode},
+    http::{header, HeaderMap, HeaderValue, Response, StatusCode},
+    middleware,
     response::IntoResponse,
     routing::get,
     Router,
 };
 use cibo_online::{
-    client::ClientMessage,
-    server::{ServerGameState, ServerMessage, SpecialEvent},
-    ClientId,
+    client::{valid_account_key, ClientMessage, PlayStats, PlayerSettings},
+    moderation::{self, Language},
+    names,
+    server::{
+        ChatId, Delivery, NextEvent, ObjectInfo, ObjectStats, PlacementId, ProtectedRegion,
+        Refusal, ServerGameState, ServerMessage, SpawnSpread, SpecialEvent, TickSnapshot,
+    },
+    ClientId, Elevation, EnvelopeError, GameTuning, MinigameKind, ObjectId, Painting, Physics,
+    PlaceableObject, Poll, Position, ShopItem, Standings, DEFAULT_BOARD_TEXT, TROPHY_PLACES,
 };
 use futures::{sink::SinkExt, stream::StreamExt};
+use rand::Rng;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     net::{IpAddr, Ipv4Addr, SocketAddr},
     sync::{
-        atomic::{AtomicBool, Ordering},
-        LazyLock, Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, LazyLock, Mutex, MutexGuard, OnceLock,
     },
 };
 use tokio::sync::mpsc;
 use tower_http::{compression::CompressionLayer, services::ServeDir};
 use tracing::{error, info, instrument, span, warn, Instrument, Span};
 
+/// client messages that were skipped because the server didn't understand them
+static SKIPPED_MESSAGES: AtomicU64 = AtomicU64::new(0);
+/// clients are disconnected after sending this many messages with broken framing
+const MAX_MALFORMED_MESSAGES: u32 = 32;
+/// when the malformed messages of the last minute arrived, for the rate shown in the admin panel
+static MALFORMED_MESSAGES: LazyLock<Mutex<VecDeque<std::time::Instant>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::new()));
+static MALFORMED_TOTAL: AtomicU64 = AtomicU64::new(0);
+
 static CONNECTED_IPS: LazyLock<Mutex<HashMap<IpAddr, ClientId>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
-static GAME_STATE: LazyLock<Mutex<ServerGameState<PerClientState>>> = LazyLock::new(|| {
-    Mutex::new(ServerGameState::new(
-        |client_state: &PerClientState, msg| {
-            client_state.tx.send(msg).unwrap_or_else(|e| {
-                error!("sending message to client: {:?}", e);
-            });
-        },
-    ))
+/// how long players without a registered account can play per day, set with `GUEST_MINUTES`. 0 if there is no limit.
+/// registering needs a linked discord account, so there is no limit without discord login
+static GUEST_LIMIT_MS: LazyLock<u64> = LazyLock::new(|| {
+    let limit_ms = env_or("GUEST_MINUTES", 0u64) * 60 * 1000;
+    if limit_ms > 0 && crate::config::CONFIG.discord.is_none() {
+        warn!("GUEST_MINUTES needs discord login to register accounts, guests can play as long as they like");
+        return 0;
+    }
+    limit_ms
+});
+/// guests that are currently playing and when they joined, to add up their time on leaving
+static GUESTS: LazyLock<Mutex<HashMap<ClientId, (IpAddr, std::time::Instant)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// account keys of the connected clients that sent one
+static ACCOUNTS: LazyLock<Mutex<HashMap<ClientId, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// discord ids of the connected clients that linked their account, passed on with their chat
+static DISCORD_IDS: LazyLock<Mutex<HashMap<ClientId, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// a game state with its own tick loop. players are spread across the shards, every shard has its
+/// own copy of the world and edits to it are carried over to the others
+struct Shard {
+    state: Mutex<ServerGameState<PerClientState>>,
+    players: AtomicUsize,
+}
+
+impl Shard {
+    fn new(index: usize) -> Self {
+        let mut state = ServerGameState::new(|client_state: &PerClientState, msg| {
+            client_state.send(Outgoing::Message(msg));
+        });
+        // chat ids have to stay unique across shards so the admin panel can delete messages
+        state.set_first_chat_id((index as u64) << 48);
+        state.set_ambient_seed(*AMBIENT_SEED);
+
+        Shard {
+            state: Mutex::new(state),
+            players: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// picked on startup and the same on every shard, so the critters stay put when players move between them
+static AMBIENT_SEED: LazyLock<u64> = LazyLock::new(rand::random);
+
+/// the number of shards is read from `GAME_SHARDS` on startup
+static SHARDS: LazyLock<Vec<Shard>> = LazyLock::new(|| {
+    (0..env_or("GAME_SHARDS", 1usize).max(1))
+        .map(Shard::new)
+        .collect()
 });
 
+/// chat messages are passed on to the other shards through this
+static CHAT_BRIDGE: OnceLock<mpsc::Sender<BridgedChat>> = OnceLock::new();
+/// chat messages waiting to be passed on to the other shards, anything past that is dropped
+const CHAT_BRIDGE_QUEUE: usize = 256;
+
+struct BridgedChat {
+    shard: usize,
+    id: ChatId,
+    from: ClientId,
+    name: String,
+    message: String,
+}
+
+fn for_each_shard(mut f: impl FnMut(&mut ServerGameState<PerClientState>)) {
+    for shard in SHARDS.iter() {
+        f(&mut shard.state.lock().unwrap());
+    }
+}
+
+/// like `for_each_shard`, skipping the one the change was made in
+fn for_other_shards(index: usize, mut f: impl FnMut(&mut ServerGameState<PerClientState>)) {
+    for (_, shard) in SHARDS.iter().enumerate().filter(|(i, _)| *i != index) {
+        f(&mut shard.state.lock().unwrap());
+    }
+}
+
+/// the shard with the fewest players, which a new player is put into
+fn join_shard() -> usize {
+    let (index, shard) = SHARDS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, shard)| shard.players.load(Ordering::Relaxed))
+        .expect("no shards");
+    shard.players.fetch_add(1, Ordering::Relaxed);
+    index
+}
+
+/// players per shard
+pub fn get_shard_players() -> Vec<usize> {
+    SHARDS
+        .iter()
+        .map(|shard| shard.players.load(Ordering::Relaxed))
+        .collect()
+}
+
+#[derive(Clone)]
 struct PerClientState {
-    tx: mpsc::UnboundedSender<ServerMessage>,
+    tx: mpsc::UnboundedSender<Outgoing>,
+}
+
+impl PerClientState {
+    fn send(&self, outgoing: Outgoing) {
+        self.tx.send(outgoing).unwrap_or_else(|e| {
+            error!("sending message to client: {:?}", e);
+        });
+    }
+}
+
+/// a message on its way to the send task of a client
+enum Outgoing {
+    /// serialized by the send task
+    Message(ServerMessage),
+    /// a message of a tick, serialized once for everyone it goes to
+    Shared(Arc<(ServerMessage, Vec<u8>)>),
+}
+
+impl Outgoing {
+    fn message(&self) -> &ServerMessage {
+        match self {
+            Outgoing::Message(message) => message,
+            Outgoing::Shared(shared) => &shared.0,
+        }
+    }
+
+    fn into_message(self) -> ServerMessage {
+        match self {
+            Outgoing::Message(message) => message,
+            Outgoing::Shared(shared) => shared.0.clone(),
+        }
+    }
+}
+
+/// serialize the messages of a tick and hand them to the clients. runs on the blocking pool, the
+/// game state is already unlocked again
+fn send_snapshot(snapshot: TickSnapshot<PerClientState>) {
+    for (message, recipients) in snapshot.deliveries() {
+        if recipients.is_empty() {
+            continue;
+        }
+        let bytes = match message.to_bytes() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                error!("serializing message: {:?}", e);
+                continue;
+            }
+        };
+        let shared = Arc::new((message.clone(), bytes));
+        for client in recipients {
+            client.send(Outgoing::Shared(shared.clone()));
+        }
+    }
 }
 
 static BANNED_IPS: LazyLock<Mutex<HashSet<IpAddr>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
 static BANNED_WORDS: LazyLock<Mutex<HashMap<String, BannedWord>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
+/// what happens to messages with words of a category, unlisted ones are censored
+static WORD_SEVERITIES: LazyLock<Mutex<HashMap<WordCategory, Severity>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static WORD_LANGUAGES: LazyLock<Mutex<HashMap<Language, LanguagePolicy>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// muted ips and until when, so reconnecting doesn't help
+static MUTED: LazyLock<Mutex<HashMap<IpAddr, std::time::Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static AUTO_MUTE: LazyLock<std::time::Duration> =
+    LazyLock::new(|| std::time::Duration::from_secs(env_or("AUTO_MUTE_MINUTES", 10u64) * 60));
+static EDITOR_TOKENS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+/// names that only the matching account can join with, `None` if nobody is allowed to
+static PROTECTED_NAMES: LazyLock<Mutex<HashMap<String, Option<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// key in `PROTECTED_NAMES`, so changing the case or padding with spaces doesn't get around it
+fn protected_name_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && !names::banned_char(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// whether the name is protected and the account isn't the one allowed to use it
+fn name_reserved(name: &str, account: &Option<String>) -> bool {
+    PROTECTED_NAMES
+        .lock()
+        .unwrap()
+        .get(&protected_name_key(name))
+        .map_or(false, |owner| owner.is_none() || owner != account)
+}
+
+fn name_banned(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let stream_mode = STREAM_MODE.load(Ordering::Relaxed);
+    let languages = WORD_LANGUAGES.lock().unwrap();
+    BANNED_WORDS
+        .lock()
+        .unwrap()
+        .values()
+        .any(|word| name.contains(&word.word) && word_applies(word, &languages, stream_mode, false))
+}
+
+/// a name for a player that didn't pick one. nobody online has it and the word filter is fine with
+/// it, so it is only "Anon" if every generated name is taken
+fn anonymous_name() -> String {
+    let start = rand::thread_rng().gen_range(0..names::GENERATED_NAMES);
+    (0..names::GENERATED_NAMES)
+        .map(|i| names::generated((start + i) % names::GENERATED_NAMES))
+        .find(|name| {
+            !name_banned(name)
+                && !name_reserved(name, &None)
+                && !SHARDS
+                    .iter()
+                    .any(|shard| shard.state.lock().unwrap().name_online(name))
+        })
+        .unwrap_or_else(|| "Anon".to_string())
+}
+
+/// why a name shouldn't be used, for the check in the join menu. joining with it might still work,
+/// e.g. a banned name is replaced instead of refused
+fn name_problem(name: &str, account: &Option<String>) -> Option<String> {
+    if let Some(problem) = names::problem(name) {
+        return Some(problem.to_string());
+    }
+    let name = names::clean(name);
+    if name.is_empty() {
+        None
+    } else if name_banned(&name) {
+        Some("that name isn't allowed here".to_string())
+    } else if name_reserved(&name, account) {
+        Some(format!(
+            "the name '{}' is reserved. please pick a different one!",
+            name
+        ))
+    } else if SHARDS
+        .iter()
+        .any(|shard| shard.state.lock().unwrap().name_online(&name))
+    {
+        Some("someone with that name is already online".to_string())
+    } else {
+        None
+    }
+}
+
+/// check a name change of a registered player and store it, returns the new name or why it can't be used
+async fn rename(
+    account: &Option<String>,
+    ip: IpAddr,
+    old_name: &str,
+    new_name: &str,
+) -> Result<String, String> {
+    let registered = match account {
+        Some(account) => is_registered(account).await,
+        None => false,
+    };
+    let (Some(account), true) = (account, registered) else {
+        return Err(
+            "only registered players can change their name, use /register first".to_string(),
+        );
+    };
+
+    let new_name = names::clean(new_name);
+    if new_name.is_empty() {
+        return Err("that name doesn't work, please pick a different one".to_string());
+    }
+    if new_name == old_name {
+        return Err(format!("you are already called {}", new_name));
+    }
+    // only changing the case would find ourselves online
+    if new_name.to_lowercase() != old_name.to_lowercase() {
+        if let Some(problem) = name_problem(&new_name, &Some(account.clone())) {
+            return Err(problem);
+        }
+    }
+
+    match name_history::cooldown_remaining(account).await {
+        Ok(None) => {}
+        Ok(Some(remaining)) => {
+            let hours = remaining / 3600 + 1;
+            return Err(format!(
+                "you can change your name again in {} hour{}",
+                hours,
+                if hours == 1 { "" } else { "s" }
+            ));
+        }
+        Err(err) => {
+            error!("failed to load name history: {}", err);
+            return Err("your name couldn't be changed, please try again later".to_string());
+        }
+    }
+    if let Err(err) = name_history::record_rename(account, ip, old_name, &new_name).await {
+        error!("failed to record name change: {}", err);
+        return Err("your name couldn't be changed, please try again later".to_string());
+    }
+    Ok(new_name)
+}
+
+/// maximum number of players in the game at once, 0 if there is no limit
+static PLAYER_CAP: AtomicUsize = AtomicUsize::new(0);
+/// how often queued clients are told their position
+const QUEUE_UPDATE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(Default)]
+struct JoinQueue {
+    players: usize,
+    waiting: VecDeque<ClientId>,
+}
+
+static JOIN_QUEUE: LazyLock<Mutex<JoinQueue>> = LazyLock::new(|| Mutex::new(JoinQueue::default()));
+
+pub fn get_player_cap() -> usize {
+    PLAYER_CAP.load(Ordering::Relaxed)
+}
+pub fn set_player_cap(cap: usize) {
+    match cap {
+        0 => info!("player cap disabled!"),
+        cap => info!("player cap set to {}!", cap),
+    }
+
+    PLAYER_CAP.store(cap, Ordering::Relaxed);
+}
+
+/// players in the game and clients waiting to join
+pub fn get_queue_stats() -> (usize, usize) {
+    let queue = JOIN_QUEUE.lock().unwrap();
+    (queue.players, queue.waiting.len())
+}
+
+/// how often countdown boards are checked against the schedule
+const EVENT_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+/// how long an event stays on the countdown boards after it started, in seconds
+const EVENT_SHOWN_AFTER_START: i64 = 10 * 60;
 
 static STREAM_MODE: AtomicBool = AtomicBool::new(false);
+
+pub fn is_banned(ip: IpAddr) -> bool {
+    BANNED_IPS.lock().unwrap().contains(&ip)
+}
+
 pub fn get_stream_mode() -> bool {
     STREAM_MODE.load(Ordering::Relaxed)
 }
@@ -61,11 +414,18 @@ pub fn set_stream_mode(stream_mode: bool) {
     STREAM_MODE.store(stream_mode, Ordering::Relaxed);
 }
 
+// all shards are kept in the same state, so reading from the first one is enough
+
 pub fn get_special_event(event: SpecialEvent) -> bool {
-    GAME_STATE.lock().unwrap().get_special_event(event)
+    SHARDS[0].state.lock().unwrap().get_special_event(event)
 }
 pub fn set_special_event(event: SpecialEvent, active: bool) {
-    GAME_STATE.lock().unwrap().set_special_event(event, active);
+    for_each_shard(|state| {
+        if let Err(e) = state.set_special_event(event, active) {
+            error!("failed to set up special event {:?}: {}", event, e);
+        }
+    });
+    webhooks::special_event(event, active);
     info!(
         "special event {:?} {}!",
         event,
@@ -73,38 +433,1610 @@ pub fn set_special_event(event: SpecialEvent, active: bool) {
     );
 }
 
-#[instrument(name = "game", skip(admin_rx))]
-pub async fn run(mut admin_rx: mpsc::Receiver<AdminAction>) {
-    let app = Router::new();
+/// a hint for everyone in every shard, shown in the ticker
+pub fn show_tip(tip: String) {
+    for_each_shard(|state| state.show_tip(tip.clone()));
+}
 
-    let serve_game_dir = ServeDir::new("./static/game").append_index_html_on_directories(true);
-    let serve_shared_dir = ServeDir::new("./static/shared");
+/// a system message for everyone in every shard
+pub fn announce(message: String) {
+    for_each_shard(|state| state.system_chat(message.clone()));
+    webhooks::announcement(message);
+}
 
-    let app = app
-        .route("/ws", get(ws_handler))
-        .nest_service("/shared", serve_shared_dir)
-        .fallback_service(serve_game_dir);
+fn account_clients(account: &str) -> Vec<ClientId> {
+    ACCOUNTS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, client_account)| *client_account == account)
+        .map(|(id, _)| *id)
+        .collect()
+}
 
-    let compression = CompressionLayer::new()
-        .gzip(true)
-        .zstd(true)
-        .br(true)
-        .deflate(true);
-    let app = app.layer(compression);
+/// show the verified badge for everyone currently playing with the account
+pub fn link_discord(account: &str, discord_id: String) {
+    let clients = account_clients(account);
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
+    let mut discord_ids = DISCORD_IDS.lock().unwrap();
+    for client_id in &clients {
+        discord_ids.insert(*client_id, discord_id.clone());
+    }
+    drop(discord_ids);
+    for_each_shard(|state| {
+        for client_id in &clients {
+            state.set_verified(*client_id, true);
+        }
+    });
+}
 
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_millis(
-            cibo_online::SERVER_TICK_RATE,
-        ));
-        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
-        loop {
-            interval.tick().await;
-            GAME_STATE
+/// take the verified badge away from everyone playing with the account, e.g. because its discord
+/// account was linked to another one
+pub fn unlink_discord(account: &str) {
+    let clients = account_clients(account);
+
+    let mut discord_ids = DISCORD_IDS.lock().unwrap();
+    for client_id in &clients {
+        discord_ids.remove(client_id);
+    }
+    drop(discord_ids);
+    for_each_shard(|state| {
+        for client_id in &clients {
+            state.set_verified(*client_id, false);
+        }
+    });
+}
+
+/// whether a player is playing with the account right now
+pub fn account_online(account: &str) -> bool {
+    ACCOUNTS
+        .lock()
+        .unwrap()
+        .values()
+        .any(|client_account| client_account == account)
+}
+
+/// drop what is kept in memory about a deleted account
+pub fn forget_account(account: &str) {
+    PROTECTED_NAMES
+        .lock()
+        .unwrap()
+        .retain(|_, owner| owner.as_deref() != Some(account));
+}
+
+/// how long players get to confirm the shutdown notice before the server goes down anyway
+const SHUTDOWN_ACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// tell everyone the server is going down and wait for them to confirm it
+pub async fn shutdown_notice() {
+    for_each_shard(|state| state.shutting_down());
+    if !acks::wait_for_acks(SHUTDOWN_ACK_TIMEOUT).await {
+        warn!("not every player confirmed the shutdown notice");
+    }
+}
+
+pub fn delete_chat(message_id: ChatId) {
+    for_each_shard(|state| state.delete_chat(message_id));
+    info!("deleted chat message {}", message_id.as_u64());
+}
+
+pub fn get_slow_mode() -> Option<std::time::Duration> {
+    SHARDS[0]
+        .state
+        .lock()
+        .unwrap()
+        .slow_mode()
+        .map(std::time::Duration::from_millis)
+}
+
+/// how players are spread over the spawn points and the area that replaces them, if any
+pub fn get_spawns() -> (SpawnSpread, Option<(Position, u32)>) {
+    let game_state = SHARDS[0].state.lock().unwrap();
+    (game_state.spawn_spread(), game_state.spawn_area())
+}
+
+pub fn get_physics() -> Physics {
+    SHARDS[0].state.lock().unwrap().physics()
+}
+
+/// malformed client messages in the last minute and since the start
+pub fn get_malformed_messages() -> (usize, u64) {
+    (
+        recent_malformed_messages().len(),
+        MALFORMED_TOTAL.load(Ordering::Relaxed),
+    )
+}
+
+fn record_malformed_message() {
+    MALFORMED_TOTAL.fetch_add(1, Ordering::Relaxed);
+    recent_malformed_messages().push_back(std::time::Instant::now());
+}
+
+/// the malformed messages of the last minute, older ones are dropped
+fn recent_malformed_messages() -> MutexGuard<'static, VecDeque<std::time::Instant>> {
+    let mut recent = MALFORMED_MESSAGES.lock().unwrap();
+    while recent
+        .front()
+        .is_some_and(|at| at.elapsed() > std::time::Duration::from_secs(60))
+    {
+        recent.pop_front();
+    }
+    recent
+}
+
+pub fn get_tuning() -> GameTuning {
+    SHARDS[0].state.lock().unwrap().tuning()
+}
+
+/// objects of every shard that match the search, with the shard they are in
+pub fn find_objects(
+    kind: Option<PlaceableObject>,
+    id: Option<ObjectId>,
+) -> Vec<(usize, ObjectInfo)> {
+    SHARDS
+        .iter()
+        .enumerate()
+        .flat_map(|(index, shard)| {
+            let found = shard.state.lock().unwrap().find_objects(kind, id);
+            found.into_iter().map(move |info| (index, info))
+        })
+        .collect()
+}
+
+/// where the players of all shards are standing
+pub fn get_player_positions() -> Vec<Position> {
+    let mut positions = Vec::new();
+    for_each_shard(|state| positions.extend(state.client_positions()));
+    positions
+}
+
+/// the placed objects, they are the same in every shard
+pub fn get_placed_objects() -> Vec<(PlaceableObject, Position)> {
+    SHARDS[0].state.lock().unwrap().placed_objects()
+}
+
+/// everyone playing on any shard, by id
+#[cfg(feature = "debug_tools")]
+pub fn get_players() -> Vec<(ClientId, String)> {
+    let mut players = Vec::new();
+    for_each_shard(|state| {
+        let snapshot = state.world_snapshot();
+        players.extend(
+            snapshot
+                .clients
+                .into_iter()
+                .map(|client| (client.id, client.name)),
+        );
+    });
+    players
+}
+
+/// ask the client for its world, the answer ends up in the world diff of the admin panel.
+/// `false` if no shard has the client
+#[cfg(feature = "debug_tools")]
+pub fn request_snapshot(client_id: ClientId) -> bool {
+    let mut found = false;
+    for_each_shard(|state| {
+        if state
+            .world_snapshot()
+            .clients
+            .iter()
+            .any(|c| c.id == client_id)
+        {
+            state.request_snapshot(client_id);
+            found = true;
+        }
+    });
+    found
+}
+
+/// the fields of an object in a shard as json, `None` if it isn't there
+pub fn get_object_json(shard: usize, id: ObjectId) -> Option<String> {
+    let mut json = Vec::new();
+    let mut serializer = serde_json::Serializer::pretty(&mut json);
+    match SHARDS
+        .get(shard)?
+        .state
+        .lock()
+        .unwrap()
+        .serialize_object(id, &mut serializer)?
+    {
+        Ok(()) => String::from_utf8(json).ok(),
+        Err(err) => {
+            error!("failed to serialize object {}: {}", id.as_u32(), err);
+            None
+        }
+    }
+}
+
+/// whether `json` holds valid fields for the object, so the admin panel can show what's wrong
+pub fn check_object_patch(shard: usize, id: ObjectId, json: &[u8]) -> Result<(), String> {
+    SHARDS
+        .get(shard)
+        .and_then(|shard| {
+            let mut deserializer = serde_json::Deserializer::from_slice(json);
+            shard
+                .state
                 .lock()
                 .unwrap()
-                .tick(cibo_online::SERVER_TICK_RATE as u64);
+                .check_object_patch(id, &mut deserializer)
+        })
+        .unwrap_or_else(|| Err(serde::de::Error::custom("the object is gone")))
+        .map_err(|err: serde_json::Error| err.to_string())
+}
+
+pub fn get_object_stats() -> Vec<(PlaceableObject, ObjectStats)> {
+    let game_state = SHARDS[0].state.lock().unwrap();
+    PlaceableObject::ALL
+        .into_iter()
+        .map(|kind| (kind, game_state.object_stats(kind)))
+        .collect()
+}
+
+/// show a chat message from a linked server
+pub fn remote_chat(server: String, name: String, mut message: String) {
+    let server = server
+        .chars()
+        .take(cibo_online::NAME_LIMIT)
+        .collect::<String>();
+    let name = name
+        .chars()
+        .take(cibo_online::NAME_LIMIT)
+        .collect::<String>();
+    message = message.chars().take(cibo_online::MESSAGE_LIMIT).collect();
+    info!("[{}] {} says '{}'", server, name, message);
+    if contains_banned_word(&message) || contains_banned_word(&name) {
+        warn!("linked server sent banned word");
+        return;
+    }
+    let message = links::filter_links(&message, STREAM_MODE.load(Ordering::Relaxed));
+    if message.is_empty() {
+        return;
+    }
+
+    for_each_shard(|state| state.remote_chat(&server, &name, &message));
+}
+
+/// tell everyone how many players are on a linked server, `0` once it is gone
+pub fn remote_online(server: String, players: u32) {
+    let server = server
+        .chars()
+        .take(cibo_online::NAME_LIMIT)
+        .collect::<String>();
+    for_each_shard(|state| state.remote_online(&server, players));
+}
+
+fn contains_banned_word(text: &str) -> bool {
+    moderate(text).is_some()
+}
+
+/// whether a banned word is matched right now. `strict` is set for messages that look like they
+/// are in a strict language
+fn word_applies(
+    word: &BannedWord,
+    languages: &HashMap<Language, LanguagePolicy>,
+    stream_mode: bool,
+    strict: bool,
+) -> bool {
+    let policy = languages.get(&word.language).copied().unwrap_or_default();
+    // allow light bans outside of stream mode
+    policy.enabled && (word.full_ban || stream_mode || strict)
+}
+
+fn word_severity(
+    word: &BannedWord,
+    severities: &HashMap<WordCategory, Severity>,
+    strict: bool,
+) -> Severity {
+    let severity = severities.get(&word.category).copied().unwrap_or_default();
+    if strict {
+        severity.stricter()
+    } else {
+        severity
+    }
+}
+
+/// whether the text looks like it is in a language the moderators marked as strict
+fn in_strict_language(text: &str, languages: &HashMap<Language, LanguagePolicy>) -> bool {
+    moderation::detect_language(text)
+        .and_then(|language| languages.get(&language))
+        .is_some_and(|policy| policy.strict)
+}
+
+/// the severity of the harshest banned word in the text, if there is one
+fn moderate(text: &str) -> Option<Severity> {
+    let text = text.to_lowercase();
+    let stream_mode = STREAM_MODE.load(Ordering::Relaxed);
+    let severities = WORD_SEVERITIES.lock().unwrap();
+    let languages = WORD_LANGUAGES.lock().unwrap();
+    let strict = in_strict_language(&text, &languages);
+    BANNED_WORDS
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|word| {
+            text.contains(&word.word) && word_applies(word, &languages, stream_mode, strict)
+        })
+        .map(|word| word_severity(word, &severities, strict))
+        .max()
+}
+
+/// the text with the words of masked categories starred out. only used when masking is the
+/// harshest thing a message earned, otherwise it is replaced or dropped anyway
+fn mask_banned_words(text: &str) -> String {
+    let stream_mode = STREAM_MODE.load(Ordering::Relaxed);
+    let severities = WORD_SEVERITIES.lock().unwrap();
+    let languages = WORD_LANGUAGES.lock().unwrap();
+    let strict = in_strict_language(text, &languages);
+    let words = BANNED_WORDS.lock().unwrap();
+    let masked = words
+        .values()
+        .filter(|word| word_applies(word, &languages, stream_mode, strict))
+        .filter(|word| word_severity(word, &severities, strict) == Severity::Mask)
+        .map(|word| word.word.as_str());
+    moderation::mask_words(text, masked)
+}
+
+pub fn word_language(language: Language) -> LanguagePolicy {
+    WORD_LANGUAGES
+        .lock()
+        .unwrap()
+        .get(&language)
+        .copied()
+        .unwrap_or_default()
+}
+
+pub fn get_word_languages() -> Vec<(Language, LanguagePolicy)> {
+    Language::ALL
+        .into_iter()
+        .map(|language| (language, word_language(language)))
+        .collect()
+}
+
+pub fn get_word_severities() -> Vec<(WordCategory, Severity)> {
+    let severities = WORD_SEVERITIES.lock().unwrap();
+    WordCategory::ALL
+        .into_iter()
+        .map(|category| {
+            (
+                category,
+                severities.get(&category).copied().unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+/// how much longer the ip can't chat
+fn muted_for(ip: IpAddr) -> Option<std::time::Duration> {
+    let mut muted = MUTED.lock().unwrap();
+    let remaining = muted
+        .get(&ip)
+        .map(|until| until.saturating_duration_since(std::time::Instant::now()));
+    match remaining {
+        Some(remaining) if remaining.is_zero() => {
+            muted.remove(&ip);
+            None
+        }
+        remaining => remaining,
+    }
+}
+
+/// the ip a client connected from, `None` for local connections
+fn ip_of(client_id: ClientId) -> Option<IpAddr> {
+    CONNECTED_IPS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(_, id)| **id == client_id)
+        .map(|(ip, _)| *ip)
+}
+
+/// ban an ip for good, like banning it from the admin panel
+async fn auto_ban(ip: IpAddr) {
+    BANNED_IPS.lock().unwrap().insert(ip);
+    digest::count_ban();
+    if let Err(err) = sqlx::query("INSERT OR IGNORE INTO banned_ips (ip) VALUES (?)")
+        .bind(ip.to_string())
+        .execute(db().await)
+        .await
+    {
+        error!("failed to save banned ip: {}", err);
+    }
+}
+
+pub fn get_minigame() -> Option<MinigameKind> {
+    SHARDS[0].state.lock().unwrap().get_minigame()
+}
+pub fn set_minigame(minigame: Option<MinigameKind>) {
+    for_each_shard(|state| state.set_minigame(minigame));
+    match minigame {
+        Some(minigame) => info!("minigame {:?} started!", minigame),
+        None => info!("minigame stopped!"),
+    }
+}
+pub fn get_minigame_scores() -> Vec<(String, u32)> {
+    SHARDS
+        .iter()
+        .flat_map(|shard| shard.state.lock().unwrap().minigame_scores())
+        .collect()
+}
+
+async fn load_world_objects() {
+    let db = db().await;
+    let objects: Vec<(String, i64, i64)> =
+        match sqlx::query_as("SELECT object, x, y FROM world_objects")
+            .fetch_all(db)
+            .await
+        {
+            Ok(objects) => objects,
+            Err(err) => {
+                error!("failed to load world objects: {}", err);
+                return;
+            }
+        };
+
+    let objects = objects
+        .iter()
+        .filter_map(|(object, x, y)| match PlaceableObject::from_name(object) {
+            Some(object) => Some((object, Position::new(*x, *y))),
+            None => {
+                warn!("unknown world object '{}'", object);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    let objects = objects
+        .into_iter()
+        .map(|(object, position)| (PlacementId::new(), object, position))
+        .collect::<Vec<_>>();
+    for_each_shard(|state| {
+        for (placement, object, position) in &objects {
+            if let Err(e) = state.place_object(*placement, *object, *position) {
+                error!("failed to place {}: {}", object.name(), e);
+            }
+        }
+    });
+    info!("loaded {} world objects", objects.len());
+}
+
+async fn save_world_objects(objects: Vec<(PlaceableObject, Position)>) {
+    let db = db().await;
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        sqlx::query("DELETE FROM world_objects")
+            .execute(&mut *tx)
+            .await?;
+        for (object, position) in objects {
+            sqlx::query("INSERT INTO world_objects (object, x, y) VALUES (?, ?, ?)")
+                .bind(object.name())
+                .bind(position.x)
+                .bind(position.y)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+    .await;
+
+    if let Err(err) = result {
+        error!("failed to save world objects: {}", err);
+    }
+}
+
+/// write the world as it is right now, e.g. before shutting down. placed objects and the gallery
+/// are the same in every shard
+pub async fn save_world() {
+    let (objects, gallery) = {
+        let state = SHARDS[0].state.lock().unwrap();
+        (state.placed_objects(), state.gallery().to_vec())
+    };
+    WORLD_OBJECT_SAVES.run(save_world_objects(objects)).await;
+    GALLERY_SAVES.run(save_gallery(gallery)).await;
+    info!("saved the world");
+}
+
+/// saves that replace a whole table, run one after the other. a save is skipped if a newer one was
+/// already written, so an older snapshot can't end up in the database last
+struct Saves {
+    queued: AtomicU64,
+    written: tokio::sync::Mutex<u64>,
+}
+
+impl Saves {
+    const fn new() -> Self {
+        Saves {
+            queued: AtomicU64::new(0),
+            written: tokio::sync::Mutex::const_new(0),
+        }
+    }
+
+    fn spawn(&'static self, save: impl std::future::Future<Output = ()> + Send + 'static) {
+        let number = self.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        tokio::spawn(self.write(number, save));
+    }
+
+    /// like `spawn`, but waits until the save is written
+    async fn run(&self, save: impl std::future::Future<Output = ()>) {
+        let number = self.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        self.write(number, save).await;
+    }
+
+    async fn write(&self, number: u64, save: impl std::future::Future<Output = ()>) {
+        let mut written = self.written.lock().await;
+        if *written > number {
+            return;
+        }
+        save.await;
+        *written = number;
+    }
+}
+
+static GALLERY_SAVES: Saves = Saves::new();
+static WORLD_OBJECT_SAVES: Saves = Saves::new();
+
+async fn load_gallery() {
+    let db = db().await;
+    let paintings: Vec<(String, u32, u32, Vec<u8>)> =
+        match sqlx::query_as("SELECT artist, width, height, data FROM paintings ORDER BY id")
+            .fetch_all(db)
+            .await
+        {
+            Ok(paintings) => paintings,
+            Err(err) => {
+                error!("failed to load paintings: {}", err);
+                return;
+            }
+        };
+
+    let paintings = paintings
+        .into_iter()
+        .map(|(artist, width, height, data)| Painting {
+            artist,
+            width,
+            height,
+            data,
+        })
+        .collect::<Vec<_>>();
+    for_each_shard(|state| {
+        for painting in &paintings {
+            state.hang_painting(painting.clone());
+        }
+        // nothing new to save yet
+        state.take_gallery_changed();
+    });
+    info!("loaded {} paintings", paintings.len());
+}
+
+async fn save_gallery(paintings: Vec<Painting>) {
+    let db = db().await;
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        sqlx::query("DELETE FROM paintings")
+            .execute(&mut *tx)
+            .await?;
+        for painting in paintings {
+            sqlx::query("INSERT INTO paintings (artist, width, height, data) VALUES (?, ?, ?, ?)")
+                .bind(painting.artist)
+                .bind(painting.width)
+                .bind(painting.height)
+                .bind(painting.data)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+    .await;
+
+    if let Err(err) = result {
+        error!("failed to save paintings: {}", err);
+    }
+}
+
+async fn load_chalk() {
+    let db = db().await;
+    let chunks: Vec<(i64, i64, Vec<u8>, i64)> =
+        match sqlx::query_as("SELECT chunk_x, chunk_y, data, saved_at FROM chalk_chunks")
+            .fetch_all(db)
+            .await
+        {
+            Ok(chunks) => chunks,
+            Err(err) => {
+                error!("failed to load chalk: {}", err);
+                return;
+            }
+        };
+
+    let now = chrono::Utc::now().timestamp();
+    let mut strokes = 0;
+    for (x, y, data, saved_at) in chunks {
+        let elapsed_ms = (now - saved_at).max(0) as u64 * 1000;
+        let mut loaded = None;
+        for_each_shard(|state| loaded = state.load_chalk_chunk((x, y), &data, elapsed_ms));
+        match loaded {
+            Some(count) => strokes += count,
+            None => error!("failed to load chalk chunk {}/{}", x, y),
+        }
+    }
+    info!("loaded {} chalk strokes", strokes);
+}
+
+async fn save_chalk_chunk(chunk: (i64, i64), data: Vec<u8>) {
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO chalk_chunks (chunk_x, chunk_y, data, saved_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT (chunk_x, chunk_y) DO UPDATE SET data = excluded.data, saved_at = excluded.saved_at",
+    )
+    .bind(chunk.0)
+    .bind(chunk.1)
+    .bind(data)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(db)
+    .await
+    {
+        error!("failed to save chalk: {}", err);
+    }
+}
+
+pub fn get_chalk_count() -> usize {
+    SHARDS[0].state.lock().unwrap().chalk_stroke_count()
+}
+
+/// put the next scheduled event and its rsvp count on the countdown boards
+pub async fn refresh_next_event() {
+    let db = db().await;
+    let event: Option<(i64, String, i64, i64)> = match sqlx::query_as(
+        "SELECT id, name, starts_at, (SELECT COUNT(*) FROM event_rsvps WHERE event_id = scheduled_events.id)
+         FROM scheduled_events WHERE starts_at > ? ORDER BY starts_at LIMIT 1",
+    )
+    .bind(chrono::Utc::now().timestamp() - EVENT_SHOWN_AFTER_START)
+    .fetch_optional(db)
+    .await
+    {
+        Ok(event) => event,
+        Err(err) => {
+            error!("failed to load scheduled events: {}", err);
+            return;
+        }
+    };
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let event = event.map(|(id, name, starts_at, rsvps)| NextEvent {
+        id: id as u64,
+        name,
+        starts_in_ms: starts_at * 1000 - now_ms,
+        rsvps: rsvps as u32,
+    });
+    for_each_shard(|state| state.set_next_event(event.clone()));
+}
+
+/// `voter` is the account of the player, or their ip if they don't have one
+async fn save_rsvp(event_id: u64, voter: String) {
+    let db = db().await;
+    if let Err(err) =
+        sqlx::query("INSERT OR IGNORE INTO event_rsvps (event_id, voter) VALUES (?, ?)")
+            .bind(event_id as i64)
+            .bind(voter)
+            .execute(db)
+            .await
+    {
+        error!("failed to save rsvp: {}", err);
+        return;
+    }
+    refresh_next_event().await;
+}
+
+/// put the open poll and its results on the poll boards
+pub async fn refresh_poll() {
+    let db = db().await;
+    let result: Result<Option<(Poll, Vec<u32>)>, sqlx::Error> = async {
+        let poll: Option<(i64, String, String)> = sqlx::query_as(
+            "SELECT id, question, options FROM polls WHERE closed_at IS NULL ORDER BY id DESC LIMIT 1",
+        )
+        .fetch_optional(db)
+        .await?;
+        let Some((id, question, options)) = poll else {
+            return Ok(None);
+        };
+
+        let options = options.lines().map(str::to_string).collect::<Vec<_>>();
+        let mut votes = vec![0; options.len()];
+        let counts: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT option, COUNT(*) FROM poll_votes WHERE poll_id = ? GROUP BY option",
+        )
+        .bind(id)
+        .fetch_all(db)
+        .await?;
+        for (option, count) in counts {
+            if let Some(votes) = votes.get_mut(option as usize) {
+                *votes = count as u32;
+            }
+        }
+
+        let poll = Poll {
+            id: id as u64,
+            question,
+            options,
+        };
+        Ok(Some((poll, votes)))
+    }
+    .await;
+
+    match result {
+        Ok(Some((poll, votes))) => {
+            for_each_shard(|state| state.set_poll(Some(poll.clone()), votes.clone()))
+        }
+        Ok(None) => for_each_shard(|state| state.set_poll(None, Vec::new())),
+        Err(err) => error!("failed to load poll: {}", err),
+    }
+}
+
+/// the newest message board text and its revision, or the default text if it was never changed
+pub async fn load_board() -> Result<(String, u32), sqlx::Error> {
+    let db = db().await;
+    let board: Option<(i64, String)> =
+        sqlx::query_as("SELECT revision, text FROM message_board ORDER BY revision DESC LIMIT 1")
+            .fetch_optional(db)
+            .await?;
+    Ok(board.map_or_else(
+        || (DEFAULT_BOARD_TEXT.to_string(), 0),
+        |(revision, text)| (text, revision as u32),
+    ))
+}
+
+/// the newest rules players have to agree to before joining, `None` if there aren't any
+static RULES: Mutex<Option<(u32, String)>> = Mutex::new(None);
+
+/// the newest version of the rules, `None` if there never were any or they were taken down
+pub async fn load_rules() -> Result<Option<(u32, String)>, sqlx::Error> {
+    let db = db().await;
+    let rules: Option<(i64, String)> =
+        sqlx::query_as("SELECT version, text FROM rules ORDER BY version DESC LIMIT 1")
+            .fetch_optional(db)
+            .await?;
+    Ok(rules
+        .filter(|(_, text)| !text.is_empty())
+        .map(|(version, text)| (version as u32, text)))
+}
+
+/// pick up the newest rules, players already in the menu get them once they reload
+pub async fn refresh_rules() {
+    match load_rules().await {
+        Ok(rules) => *RULES.lock().unwrap() = rules,
+        Err(err) => error!("failed to load rules: {}", err),
+    }
+}
+
+/// the version on the first line, the text after it. empty if there are no rules
+async fn get_rules() -> String {
+    match &*RULES.lock().unwrap() {
+        Some((version, text)) => format!("{}\n{}", version, text),
+        None => String::new(),
+    }
+}
+
+async fn record_rules_agreement(ip: IpAddr, account: Option<String>, name: &str, version: u32) {
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO rules_agreements (ip, account, name, version, agreed_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(ip.to_string())
+    .bind(account)
+    .bind(name)
+    .bind(version)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(db)
+    .await
+    {
+        error!("failed to record rules agreement: {}", err);
+    }
+}
+
+/// put the protected regions from the admin panel into every shard
+pub async fn refresh_protected_regions() {
+    let db = db().await;
+    let regions: Vec<(String, i64, i64, i64, i64)> =
+        match sqlx::query_as("SELECT name, min_x, min_y, max_x, max_y FROM protected_regions")
+            .fetch_all(db)
+            .await
+        {
+            Ok(regions) => regions,
+            Err(err) => {
+                error!("failed to load protected regions: {}", err);
+                return;
+            }
+        };
+
+    // stored in tiles, the game works in pixels
+    let regions = regions
+        .into_iter()
+        .map(|(name, min_x, min_y, max_x, max_y)| ProtectedRegion {
+            name,
+            min: Position::new(min_x.min(max_x) * 16, min_y.min(max_y) * 16),
+            max: Position::new(min_x.max(max_x) * 16 + 15, min_y.max(max_y) * 16 + 15),
+        })
+        .collect::<Vec<_>>();
+    for_each_shard(|state| state.set_protected_regions(regions.clone()));
+}
+
+/// put the newest text on the message boards
+pub async fn refresh_board() {
+    match load_board().await {
+        Ok((text, revision)) => {
+            for_each_shard(|state| state.set_board_text(text.clone(), revision))
+        }
+        Err(err) => error!("failed to load message board: {}", err),
+    }
+}
+
+async fn load_board_read(account: &str) -> u32 {
+    let db = db().await;
+    match sqlx::query_scalar::<_, i64>("SELECT revision FROM board_reads WHERE account = ?")
+        .bind(account)
+        .fetch_optional(db)
+        .await
+    {
+        Ok(revision) => revision.unwrap_or(0) as u32,
+        Err(err) => {
+            error!("failed to load message board read: {}", err);
+            0
+        }
+    }
+}
+
+async fn save_board_read(account: String, revision: u32) {
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO board_reads (account, revision) VALUES (?, ?)
+         ON CONFLICT (account) DO UPDATE SET revision = MAX(revision, excluded.revision)",
+    )
+    .bind(account)
+    .bind(revision)
+    .execute(db)
+    .await
+    {
+        error!("failed to save message board read: {}", err);
+    }
+}
+
+/// count a vote if the poll is still open and has the option. every voter and every ip gets one
+/// vote per poll
+async fn save_vote(poll_id: u64, option: u32, voter: String, ip: IpAddr) {
+    let db = db().await;
+    let result: Result<bool, sqlx::Error> = async {
+        // the poll board of a shard might still show a poll that was closed or replaced
+        let options: Option<String> =
+            sqlx::query_scalar("SELECT options FROM polls WHERE id = ? AND closed_at IS NULL")
+                .bind(poll_id as i64)
+                .fetch_optional(db)
+                .await?;
+        if options.map_or(true, |options| option as usize >= options.lines().count()) {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO poll_votes (poll_id, voter, option, ip) VALUES (?, ?, ?, ?)",
+        )
+        .bind(poll_id as i64)
+        .bind(voter)
+        .bind(option)
+        .bind(ip.to_string())
+        .execute(db)
+        .await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!(
+                "vote for option {} of poll {} that isn't open",
+                option, poll_id
+            );
+            return;
+        }
+        Err(err) => {
+            error!("failed to save vote: {}", err);
+            return;
+        }
+    }
+    refresh_poll().await;
+    refresh_standings().await;
+}
+
+/// the best players of a minigame, highest score first
+pub async fn load_leaderboard(
+    game: MinigameKind,
+    limit: u32,
+) -> Result<Vec<(String, u32)>, sqlx::Error> {
+    let db = db().await;
+    sqlx::query_as(
+        "SELECT name, score FROM leaderboard WHERE game = ? ORDER BY score DESC, name LIMIT ?",
+    )
+    .bind(game.name())
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// put the top players of every leaderboard into the trophy cases
+async fn refresh_standings() {
+    let mut standings = Vec::new();
+    for game in MinigameKind::ALL {
+        match load_leaderboard(game, TROPHY_PLACES as u32).await {
+            Ok(top) => standings.push(Standings { game, top }),
+            Err(err) => {
+                error!("failed to load leaderboard: {}", err);
+                return;
+            }
+        }
+    }
+    for_each_shard(|state| state.set_standings(standings.clone()));
+}
+
+async fn save_minigame_points(points: Vec<(MinigameKind, String)>) {
+    let db = db().await;
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = db.begin().await?;
+        for (game, name) in points {
+            sqlx::query(
+                "INSERT INTO leaderboard (game, name, score) VALUES (?, ?, 1)
+                 ON CONFLICT (game, name) DO UPDATE SET score = score + 1",
+            )
+            .bind(game.name())
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+    .await;
+
+    if let Err(err) = result {
+        error!("failed to save minigame points: {}", err);
+        return;
+    }
+    refresh_standings().await;
+}
+
+/// the settings stored for an account. accounts without stored settings take over the ones from the device
+async fn load_settings(account: &str, mut device_settings: PlayerSettings) -> PlayerSettings {
+    device_settings.clamp();
+
+    let db = db().await;
+    let stored: Option<(Vec<u8>,)> =
+        match sqlx::query_as("SELECT settings FROM player_settings WHERE account = ?")
+            .bind(account)
+            .fetch_optional(db)
+            .await
+        {
+            Ok(stored) => stored,
+            Err(err) => {
+                error!("failed to load settings: {}", err);
+                return device_settings;
+            }
+        };
+
+    match stored.map(|(bytes,)| PlayerSettings::from_bytes(&bytes)) {
+        Some(Ok(settings)) => settings,
+        Some(Err(err)) => {
+            warn!("stored settings are invalid, replacing them: {}", err);
+            save_settings(account.to_string(), device_settings.clone()).await;
+            device_settings
+        }
+        None => {
+            save_settings(account.to_string(), device_settings.clone()).await;
+            device_settings
+        }
+    }
+}
+
+async fn save_settings(account: String, settings: PlayerSettings) {
+    let Ok(bytes) = settings.to_bytes() else {
+        return;
+    };
+
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO player_settings (account, settings, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT (account) DO UPDATE SET settings = excluded.settings, updated_at = excluded.updated_at",
+    )
+    .bind(account)
+    .bind(bytes)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(db)
+    .await
+    {
+        error!("failed to save settings: {}", err);
+    }
+}
+
+/// accounts the given account blocked, and accounts that blocked it
+async fn load_blocks(account: &str) -> (Vec<String>, Vec<String>) {
+    let db = db().await;
+    let blocks: Vec<(String, String)> = match sqlx::query_as(
+        "SELECT account, blocked FROM player_blocks WHERE account = ? OR blocked = ?",
+    )
+    .bind(account)
+    .bind(account)
+    .fetch_all(db)
+    .await
+    {
+        Ok(blocks) => blocks,
+        Err(err) => {
+            error!("failed to load blocks: {}", err);
+            return (Vec::new(), Vec::new());
+        }
+    };
+
+    let (blocking, blocked_by) = blocks
+        .into_iter()
+        .partition::<Vec<_>, _>(|(blocker, _)| blocker == account);
+    (
+        blocking.into_iter().map(|(_, blocked)| blocked).collect(),
+        blocked_by.into_iter().map(|(blocker, _)| blocker).collect(),
+    )
+}
+
+async fn save_block(account: String, blocked: String, block: bool) {
+    let db = db().await;
+    let result = if block {
+        sqlx::query(
+            "INSERT INTO player_blocks (account, blocked, created_at) VALUES (?, ?, ?)
+             ON CONFLICT (account, blocked) DO NOTHING",
+        )
+        .bind(account)
+        .bind(blocked)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(db)
+        .await
+    } else {
+        sqlx::query("DELETE FROM player_blocks WHERE account = ? AND blocked = ?")
+            .bind(account)
+            .bind(blocked)
+            .execute(db)
+            .await
+    };
+
+    if let Err(err) = result {
+        error!("failed to save block: {}", err);
+    }
+}
+
+async fn post_letter(
+    shard: usize,
+    client_id: ClientId,
+    account: String,
+    name: String,
+    to: String,
+    message: String,
+) {
+    let reply = match mail::post_letter(&account, &name, &to, &message).await {
+        Ok(mail::PostResult::Sent) => format!("your letter to {} is on its way", to),
+        Ok(mail::PostResult::UnknownRecipient) => format!("nobody called {} played here yet", to),
+        Ok(mail::PostResult::MailboxFull) => format!("the mailbox of {} is full", to),
+        Err(err) => {
+            error!("failed to post letter: {}", err);
+            "your letter couldn't be sent, please try again later".to_string()
+        }
+    };
+    SHARDS[shard]
+        .state
+        .lock()
+        .unwrap()
+        .system_chat_to(client_id, reply);
+}
+
+/// add what players earned in minigames to their accounts and show them the new balance
+async fn award_seashells(shard: usize, earnings: Vec<(ClientId, String, u64)>) {
+    for (client_id, account, amount) in earnings {
+        if let Err(err) = seashells::earn(&account, amount, "minigame").await {
+            error!("failed to award seashells: {}", err);
+            continue;
+        }
+        let (balance, owned) = seashells::load_wallet(&account).await;
+        SHARDS[shard]
+            .state
+            .lock()
+            .unwrap()
+            .set_wallet(client_id, balance, owned);
+    }
+}
+
+async fn buy_item(shard: usize, client_id: ClientId, account: String, item: ShopItem) {
+    let reply = match seashells::buy(&account, item).await {
+        Ok(seashells::BuyResult::Bought) => {
+            info!("bought the {}", item.name());
+            format!("you bought the {}!", item.name())
+        }
+        Ok(seashells::BuyResult::NotEnough) => {
+            format!("you don't have enough seashells for the {}", item.name())
+        }
+        Ok(seashells::BuyResult::AlreadyOwned) => format!("you already have the {}", item.name()),
+        Err(err) => {
+            error!("failed to buy item: {}", err);
+            "the shop is closed right now, please try again later".to_string()
+        }
+    };
+    // also sent when nothing was bought, the shop waits for it
+    let (balance, owned) = seashells::load_wallet(&account).await;
+    let mut state = SHARDS[shard].state.lock().unwrap();
+    state.set_wallet(client_id, balance, owned);
+    state.system_chat_to(client_id, reply);
+}
+
+async fn load_home(account: &str) -> Option<Position> {
+    let db = db().await;
+    match sqlx::query_as::<_, (i64, i64)>("SELECT x, y FROM player_homes WHERE account = ?")
+        .bind(account)
+        .fetch_optional(db)
+        .await
+    {
+        Ok(home) => home.map(|(x, y)| Position::new(x, y)),
+        Err(err) => {
+            error!("failed to load home: {}", err);
+            None
+        }
+    }
+}
+
+/// whether an admin made the account event staff
+async fn is_staff(account: &str) -> bool {
+    let db = db().await;
+    match sqlx::query_scalar::<_, bool>("SELECT staff FROM registered_accounts WHERE account = ?")
+        .bind(account)
+        .fetch_optional(db)
+        .await
+    {
+        Ok(staff) => staff.unwrap_or(false),
+        Err(err) => {
+            error!("failed to check staff flag: {}", err);
+            false
+        }
+    }
+}
+
+/// make a registered account event staff or take it away again, applies right away if they are
+/// online. `false` if the account isn't registered
+pub async fn set_staff(account: &str, staff: bool) -> Result<bool, sqlx::Error> {
+    let db = db().await;
+    let updated = sqlx::query("UPDATE registered_accounts SET staff = ? WHERE account = ?")
+        .bind(staff)
+        .bind(account)
+        .execute(db)
+        .await?
+        .rows_affected();
+    if updated == 0 {
+        return Ok(false);
+    }
+
+    let clients = account_clients(account);
+    for_each_shard(|state| {
+        for client in &clients {
+            if staff {
+                state.grant_staff(*client);
+            } else {
+                state.revoke_staff(*client);
+            }
+        }
+    });
+    Ok(true)
+}
+
+async fn save_home(account: String, home: Position) {
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO player_homes (account, x, y, updated_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT (account) DO UPDATE SET x = excluded.x, y = excluded.y, updated_at = excluded.updated_at",
+    )
+    .bind(account)
+    .bind(home.x)
+    .bind(home.y)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(db)
+    .await
+    {
+        error!("failed to save home: {}", err);
+    }
+}
+
+/// the stats of all earlier sessions of an account
+async fn load_play_stats(account: &str) -> PlayStats {
+    let db = db().await;
+    match sqlx::query_as::<_, (i64, i64, i64, i64)>(
+        "SELECT time_online_ms, tiles_walked, messages_sent, balls_kicked FROM player_stats WHERE account = ?",
+    )
+    .bind(account)
+    .fetch_optional(db)
+    .await
+    {
+        Ok(stats) => stats.map_or_else(PlayStats::default, |(time, tiles, messages, kicks)| {
+            PlayStats {
+                time_online_ms: time as u64,
+                tiles_walked: tiles as u64,
+                messages_sent: messages as u64,
+                balls_kicked: kicks as u64,
+            }
+        }),
+        Err(err) => {
+            error!("failed to load stats: {}", err);
+            PlayStats::default()
+        }
+    }
+}
+
+/// add the stats of a session to the account
+async fn save_play_stats(account: String, name: String, stats: PlayStats) {
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO player_stats (account, name, time_online_ms, tiles_walked, messages_sent, balls_kicked, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (account) DO UPDATE SET
+            name = excluded.name,
+            time_online_ms = time_online_ms + excluded.time_online_ms,
+            tiles_walked = tiles_walked + excluded.tiles_walked,
+            messages_sent = messages_sent + excluded.messages_sent,
+            balls_kicked = balls_kicked + excluded.balls_kicked,
+            updated_at = excluded.updated_at",
+    )
+    .bind(account)
+    .bind(name)
+    .bind(stats.time_online_ms as i64)
+    .bind(stats.tiles_walked as i64)
+    .bind(stats.messages_sent as i64)
+    .bind(stats.balls_kicked as i64)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(db)
+    .await
+    {
+        error!("failed to save stats: {}", err);
+    }
+}
+
+async fn is_registered(account: &str) -> bool {
+    let db = db().await;
+    match sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM registered_accounts WHERE account = ?")
+        .bind(account)
+        .fetch_one(db)
+        .await
+    {
+        Ok(count) => count > 0,
+        Err(err) => {
+            error!("failed to look up account: {}", err);
+            // rather let someone play for too long than lock them out
+            true
+        }
+    }
+}
+
+async fn register_account(account: &str) {
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO registered_accounts (account, registered_at) VALUES (?, ?)
+         ON CONFLICT (account) DO NOTHING",
+    )
+    .bind(account)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(db)
+    .await
+    {
+        error!("failed to register account: {}", err);
+    }
+}
+
+/// guest time already used up today from this ip
+async fn guest_time_used(ip: IpAddr) -> u64 {
+    let db = db().await;
+    match sqlx::query_scalar::<_, i64>("SELECT played_ms FROM guest_time WHERE ip = ? AND day = ?")
+        .bind(ip.to_string())
+        .bind(chrono::Utc::now().date_naive().to_string())
+        .fetch_optional(db)
+        .await
+    {
+        Ok(played_ms) => played_ms.unwrap_or(0) as u64,
+        Err(err) => {
+            error!("failed to load guest time: {}", err);
+            0
+        }
+    }
+}
+
+/// stop counting the time of a guest, because it left or registered
+async fn end_guest_session(client_id: ClientId) {
+    let Some((ip, joined_at)) = GUESTS.lock().unwrap().remove(&client_id) else {
+        return;
+    };
+    let played_ms = joined_at.elapsed().as_millis() as i64;
+
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO guest_time (ip, day, played_ms) VALUES (?, ?, ?)
+         ON CONFLICT (ip, day) DO UPDATE SET played_ms = played_ms + excluded.played_ms",
+    )
+    .bind(ip.to_string())
+    .bind(chrono::Utc::now().date_naive().to_string())
+    .bind(played_ms)
+    .execute(db)
+    .await
+    {
+        error!("failed to save guest time: {}", err);
+    }
+}
+
+async fn record_join(client_id: ClientId, ip: IpAddr, name: &str, account: Option<String>) {
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "INSERT INTO connections (ip, name, client_id, joined_at, account) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(ip.to_string())
+    .bind(name)
+    .bind(client_id.as_u32())
+    .bind(chrono::Utc::now().timestamp())
+    .bind(account)
+    .execute(db)
+    .await
+    {
+        error!("failed to record connection: {}", err);
+    }
+}
+
+/// take a free slot if the client is first in line, otherwise get its 1-based position in the queue
+fn try_admit(client_id: ClientId) -> Result<(), usize> {
+    let cap = get_player_cap();
+    let mut queue = JOIN_QUEUE.lock().unwrap();
+    let position = match queue.waiting.iter().position(|id| *id == client_id) {
+        Some(position) => position,
+        None => {
+            queue.waiting.push_back(client_id);
+            queue.waiting.len() - 1
+        }
+    };
+
+    if position == 0 && (cap == 0 || queue.players < cap) {
+        queue.waiting.pop_front();
+        queue.players += 1;
+        Ok(())
+    } else {
+        Err(position + 1)
+    }
+}
+
+/// keep the socket waiting until there is room in the game. `None` if the client gave up
+async fn wait_for_slot(client_id: ClientId, mut socket: WebSocket) -> Option<WebSocket> {
+    let mut interval = tokio::time::interval(QUEUE_UPDATE_INTERVAL);
+    let mut queued = false;
+
+    let admitted = loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let position = match try_admit(client_id) {
+                    Ok(()) => break true,
+                    Err(position) => position,
+                };
+                if !queued {
+                    info!("server full, queued at position {}", position);
+                    queued = true;
+                }
+
+                let msg = ServerMessage::QueuePosition(position as u32);
+                let Ok(bytes) = msg.to_bytes() else {
+                    break false;
+                };
+                if socket.send(Message::Binary(bytes)).await.is_err() {
+                    break false;
+                }
+            }
+            msg = socket.recv() => match msg {
+                // nothing to do with messages while waiting
+                Some(Ok(_)) => {}
+                _ => break false,
+            },
+        }
+    };
+
+    if !admitted {
+        JOIN_QUEUE
+            .lock()
+            .unwrap()
+            .waiting
+            .retain(|id| *id != client_id);
+        return None;
+    }
+
+    if queued {
+        info!("admitted from the queue");
+        let sent = match ServerMessage::QueuePosition(0).to_bytes() {
+            Ok(bytes) => socket.send(Message::Binary(bytes)).await.is_ok(),
+            Err(_) => false,
+        };
+        if !sent {
+            JOIN_QUEUE.lock().unwrap().players -= 1;
+            return None;
+        }
+    }
+    Some(socket)
+}
+
+async fn record_leave(client_id: ClientId, ip: IpAddr) {
+    let db = db().await;
+    if let Err(err) = sqlx::query(
+        "UPDATE connections SET left_at = ? WHERE client_id = ? AND ip = ? AND left_at IS NULL",
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .bind(client_id.as_u32())
+    .bind(ip.to_string())
+    .execute(db)
+    .await
+    {
+        error!("failed to record disconnect: {}", err);
+    }
+}
+
+/// connections that were still open when the server stopped never got a leave time
+async fn close_stale_connections() {
+    let db = db().await;
+    if let Err(err) =
+        sqlx::query("UPDATE connections SET left_at = joined_at WHERE left_at IS NULL")
+            .execute(db)
+            .await
+    {
+        error!("failed to close stale connections: {}", err);
+    }
+}
+
+#[instrument(name = "game", skip(admin_rx))]
+pub async fn run(mut admin_rx: mpsc::Receiver<AdminAction>) {
+    let app = Router::new();
+
+    let serve_game_dir = Router::new()
+        .fallback_service(ServeDir::new("./static/game").append_index_html_on_directories(true))
+        .layer(middleware::from_fn(cache_control));
+    #[cfg(feature = "dev_reload")]
+    let serve_game_dir =
+        serve_game_dir.layer(middleware::from_fn(crate::dev_reload::inject_script));
+    let serve_shared_dir = ServeDir::new("./static/shared");
+
+    let app = app
+        .route("/ws", get(ws_handler))
+        .route("/rules", get(get_rules))
+        .merge(asset_packs::routes())
+        .merge(federation::routes())
+        .merge(api::routes())
+        .merge(appeal::routes())
+        .merge(discord::routes())
+        .merge(account_data::routes())
+        .nest_service("/shared", serve_shared_dir)
+        .fallback_service(serve_game_dir);
+    #[cfg(feature = "dev_reload")]
+    let app = app.merge(crate::dev_reload::routes());
+
+    let compression = CompressionLayer::new()
+        .gzip(true)
+        .zstd(true)
+        .br(true)
+        .deflate(true);
+    let app = app.layer(compression);
+
+    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
+
+    // gallery walls pick up the paintings when they are placed
+    load_gallery().await;
+    load_world_objects().await;
+    load_chalk().await;
+    refresh_poll().await;
+    refresh_board().await;
+    refresh_rules().await;
+    refresh_protected_regions().await;
+    close_stale_connections().await;
+    asset_packs::load();
+
+    info!("running {} shards", SHARDS.len());
+    for (shard_index, shard) in SHARDS.iter().enumerate() {
+        tokio::spawn(async move {
+            let mut governor = LoadGovernor::default();
+            let mut interval = governor.interval();
+            loop {
+                interval.tick().await;
+                let started = std::time::Instant::now();
+                let (snapshot, points, earned, flags) = {
+                    let mut state = shard.state.lock().unwrap();
+                    let snapshot = state.tick_snapshot(governor.tick_ms());
+                    let recorded = movement_recordings::recorded_clients();
+                    if !recorded.is_empty() {
+                        movement_recordings::record_positions(
+                            recorded
+                                .into_iter()
+                                .filter_map(|client| Some((client, state.client_spot(client)?.0)))
+                                .collect(),
+                        );
+                    }
+                    let flags = state
+                        .take_flags()
+                        .into_iter()
+                        .map(|flag| (state.client_name(flag.client), flag))
+                        .collect::<Vec<_>>();
+                    (
+                        snapshot,
+                        state.take_minigame_points(),
+                        state.take_seashells(),
+                        flags,
+                    )
+                };
+                // waited for, so the next tick can't overtake this one
+                if !snapshot.is_empty() {
+                    if let Err(err) =
+                        tokio::task::spawn_blocking(move || send_snapshot(snapshot)).await
+                    {
+                        error!("sending tick updates: {}", err);
+                    }
+                }
+                if let Some(tick_ms) = governor.record(started.elapsed()) {
+                    if governor.degraded() {
+                        warn!(
+                            "shard {} can't keep up, ticking at {}hz",
+                            shard_index,
+                            1000 / tick_ms
+                        );
+                    } else {
+                        info!("shard {} is back to {}hz", shard_index, 1000 / tick_ms);
+                    }
+                    shard.state.lock().unwrap().set_tick_ms(tick_ms);
+                    interval = governor.interval();
+                }
+                // looked up after the state is unlocked, the ban action locks them the other way around
+                let flags = flags
+                    .into_iter()
+                    .map(|(name, flag)| (ip_of(flag.client), name, flag))
+                    .collect::<Vec<_>>();
+                if !points.is_empty() {
+                    tokio::spawn(save_minigame_points(points));
+                }
+                // guests don't have an account to keep them
+                let earned = {
+                    let accounts = ACCOUNTS.lock().unwrap();
+                    earned
+                        .into_iter()
+                        .filter_map(|(client_id, amount)| {
+                            Some((client_id, accounts.get(&client_id)?.clone(), amount))
+                        })
+                        .collect::<Vec<_>>()
+                };
+                if !earned.is_empty() {
+                    tokio::spawn(award_seashells(shard_index, earned));
+                }
+                for (ip, name, flag) in &flags {
+                    movement_recordings::start(
+                        flag.client,
+                        *ip,
+                        name.clone(),
+                        flag.suspicion.name(),
+                    );
+                }
+                if !flags.is_empty() {
+                    tokio::spawn(flags::save_flags(flags));
+                }
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EVENT_REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            // keeps the clocks of the game states in step with the wall clock
+            let now_ms = chrono::Utc::now().timestamp_millis() as u64;
+            for_each_shard(|state| state.set_unix_time(now_ms));
+            refresh_next_event().await;
+        }
+    });
+
+    let (bridge_tx, mut bridge_rx) = mpsc::channel::<BridgedChat>(CHAT_BRIDGE_QUEUE);
+    CHAT_BRIDGE.set(bridge_tx).ok();
+    tokio::spawn(async move {
+        while let Some(chat) = bridge_rx.recv().await {
+            for_other_shards(chat.shard, |state| {
+                state.relay_chat(chat.id, chat.from, &chat.name, &chat.message)
+            });
         }
     });
 
@@ -115,10 +2047,31 @@ pub async fn run(mut admin_rx: mpsc::Receiver<AdminAction>) {
                     let mut banned_ips = BANNED_IPS.lock().unwrap();
                     let mut connected_ips = CONNECTED_IPS.lock().unwrap();
                     if let Some(client_id) = connected_ips.remove(&ip) {
-                        GAME_STATE.lock().unwrap().remove_client(client_id);
+                        for_each_shard(|state| {
+                            state.refuse(client_id, Refusal::Banned);
+                            state.remove_client(client_id);
+                        });
                     }
                     banned_ips.insert(ip);
                 }
+                AdminAction::Kick(ip) => {
+                    // the connection closes once the client confirmed that it was kicked
+                    let client_id = CONNECTED_IPS.lock().unwrap().remove(&ip);
+                    if let Some(client_id) = client_id {
+                        info!("kicked {}", ip);
+                        for_each_shard(|state| {
+                            state.refuse(client_id, Refusal::Kicked);
+                            state.remove_client(client_id);
+                        });
+                    }
+                }
+                AdminAction::Mute(ip) => {
+                    info!("muted {} for {:?}", ip, *AUTO_MUTE);
+                    MUTED
+                        .lock()
+                        .unwrap()
+                        .insert(ip, std::time::Instant::now() + *AUTO_MUTE);
+                }
                 AdminAction::UnbanIp(ip) => {
                     let mut banned_ips = BANNED_IPS.lock().unwrap();
                     banned_ips.remove(&ip);
@@ -132,6 +2085,160 @@ pub async fn run(mut admin_rx: mpsc::Receiver<AdminAction>) {
                     let mut banned_words = BANNED_WORDS.lock().unwrap();
                     banned_words.remove(&word);
                 }
+                AdminAction::SetWordSeverity { category, severity } => {
+                    WORD_SEVERITIES.lock().unwrap().insert(category, severity);
+                }
+                AdminAction::SetWordLanguage { language, policy } => {
+                    WORD_LANGUAGES.lock().unwrap().insert(language, policy);
+                }
+
+                AdminAction::AddEditorToken(token) => {
+                    EDITOR_TOKENS.lock().unwrap().insert(token);
+                }
+                AdminAction::RemoveEditorToken(token) => {
+                    EDITOR_TOKENS.lock().unwrap().remove(&token);
+                }
+
+                AdminAction::ProtectName { name, account } => {
+                    PROTECTED_NAMES
+                        .lock()
+                        .unwrap()
+                        .insert(protected_name_key(&name), account);
+                }
+                AdminAction::UnprotectName(name) => {
+                    PROTECTED_NAMES
+                        .lock()
+                        .unwrap()
+                        .remove(&protected_name_key(&name));
+                }
+
+                AdminAction::SetSlowMode(slow_mode) => {
+                    match slow_mode {
+                        Some(slow_mode) => info!("slow mode set to {:?}!", slow_mode),
+                        None => info!("slow mode disabled!"),
+                    }
+                    let slow_mode_ms = slow_mode.map(|slow_mode| slow_mode.as_millis() as u64);
+                    for_each_shard(|state| state.set_slow_mode(slow_mode_ms));
+                }
+                AdminAction::SpawnObjects { kind, count } => {
+                    // the same amount is spawned in every shard
+                    let mut spawned = 0;
+                    for_each_shard(|state| match state.spawn_objects(kind, count) {
+                        Ok(count) => spawned = count,
+                        Err(e) => error!("failed to spawn {}s: {}", kind.name(), e),
+                    });
+                    info!("spawned {} {}s!", spawned, kind.name());
+                }
+                AdminAction::ClearChalk { center, radius } => {
+                    let mut changed = Vec::new();
+                    for_each_shard(|state| changed = state.clear_chalk(center, radius as i64));
+                    info!(
+                        "cleared chalk around {}/{} in {} chunks",
+                        center.x,
+                        center.y,
+                        changed.len()
+                    );
+                    let state = SHARDS[0].state.lock().unwrap();
+                    let chunks = changed
+                        .into_iter()
+                        .map(|chunk| (chunk, state.save_chalk_chunk(chunk)))
+                        .collect::<Vec<_>>();
+                    drop(state);
+                    for (chunk, data) in chunks {
+                        tokio::spawn(save_chalk_chunk(chunk, data));
+                    }
+                }
+                AdminAction::ClearObjects(kind) => {
+                    let mut removed = 0;
+                    for_each_shard(|state| removed = state.clear_objects(kind));
+                    info!("cleared {} {}s!", removed, kind.name());
+                }
+                AdminAction::SetObjectCap { kind, cap } => {
+                    info!("{} cap set to {}!", kind.name(), cap);
+                    for_each_shard(|state| state.set_object_cap(kind, cap));
+                }
+                AdminAction::SetPhysicsParam { param, value } => {
+                    info!("{} set to {}!", param.name(), value);
+                    for_each_shard(|state| state.set_physics_param(param, value));
+                }
+                AdminAction::SetTuningParam { param, value } => {
+                    info!("{} set to {}!", param.name(), value);
+                    for_each_shard(|state| state.set_tuning_param(param, value));
+                }
+                AdminAction::CameraHint {
+                    target,
+                    duration_ms,
+                } => {
+                    info!(
+                        "panning event cameras to {}/{} for {}ms",
+                        target.x, target.y, duration_ms
+                    );
+                    for_each_shard(|state| state.camera_hint(target, duration_ms));
+                }
+                AdminAction::Effect {
+                    kind,
+                    position,
+                    duration_ms,
+                } => {
+                    info!(
+                        "showing {} at {}/{} for {}ms",
+                        kind.name(),
+                        position.x,
+                        position.y,
+                        duration_ms
+                    );
+                    for_each_shard(|state| state.effect(kind, position, duration_ms));
+                }
+                AdminAction::PatchObject { shard, id, bytes } => {
+                    let Some(shard) = SHARDS.get(shard) else {
+                        continue;
+                    };
+                    let mut deserializer = serde_json::Deserializer::from_slice(&bytes);
+                    match shard
+                        .state
+                        .lock()
+                        .unwrap()
+                        .patch_object(id, &mut deserializer)
+                    {
+                        Some(Ok(())) => info!("patched object {}", id.as_u32()),
+                        Some(Err(err)) => {
+                            warn!("failed to patch object {}: {}", id.as_u32(), err)
+                        }
+                        None => warn!("object {} to patch is gone", id.as_u32()),
+                    }
+                }
+                AdminAction::PlayCutscene { stage, cutscene } => {
+                    info!(
+                        "playing a cutscene with {} steps on the stage at {}/{}",
+                        cutscene.steps(),
+                        stage.x,
+                        stage.y
+                    );
+                    let mut missing = false;
+                    for_each_shard(|state| {
+                        missing |= !state.play_cutscene(stage, cutscene.clone())
+                    });
+                    if missing {
+                        warn!("the stage at {}/{} is gone", stage.x, stage.y);
+                    }
+                }
+                AdminAction::StopCutscene => {
+                    info!("stopped the cutscene");
+                    for_each_shard(|state| state.stop_cutscene());
+                }
+                AdminAction::SetSpawns { spread, area } => {
+                    match area {
+                        Some((center, radius)) => info!(
+                            "new players spawn around {}/{} ({}px)",
+                            center.x, center.y, radius
+                        ),
+                        None => info!("new players spawn at spawn points ({})", spread.name()),
+                    }
+                    for_each_shard(|state| {
+                        state.set_spawn_spread(spread);
+                        state.set_spawn_area(area);
+                    });
+                }
             }
         }
     });
@@ -142,12 +2249,47 @@ pub async fn run(mut admin_rx: mpsc::Receiver<AdminAction>) {
         "ready! listening on port {}",
         listener.local_addr().unwrap().port()
     );
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await
-    .unwrap();
+    match tls::rustls_config().await {
+        Some(rustls_config) => {
+            info!("serving over tls");
+            axum_server::from_tcp_rustls(listener.into_std().unwrap(), rustls_config)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .unwrap();
+        }
+        None => axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap(),
+    }
+}
+
+/// the build puts a content hash into the names of the client files, so they can be cached forever.
+/// everything else has to be revalidated, otherwise browsers keep loading an outdated client
+async fn cache_control(req: Request, next: middleware::Next) -> Response<Body> {
+    let fingerprinted = req
+        .uri()
+        .path()
+        .rsplit('/')
+        .next()
+        .and_then(|file| file.split('.').rev().nth(1))
+        .is_some_and(|hash| hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+    let mut response = next.run(req).await;
+    if response.status().is_success() {
+        let cache_control = if fingerprinted {
+            "public, max-age=31536000, immutable"
+        } else {
+            "no-cache"
+        };
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(cache_control),
+        );
+    }
+    response
 }
 
 async fn ws_handler(
@@ -158,10 +2300,7 @@ async fn ws_handler(
     let client_id = ClientId::new();
 
     if BANNED_IPS.lock().unwrap().contains(&addr.ip()) {
-        return Response::builder()
-            .status(StatusCode::FORBIDDEN)
-            .body("you are banned".into())
-            .unwrap();
+        return refuse(ws, Refusal::Banned);
     }
 
     let actual_ip = if let Some(ip) = headers.get("x-real-ip") {
@@ -186,19 +2325,15 @@ async fn ws_handler(
         };
 
         if BANNED_IPS.lock().unwrap().contains(&ip) {
-            return Response::builder()
-                .status(StatusCode::FORBIDDEN)
-                .body("you are banned".into())
-                .unwrap();
+            return refuse(ws, Refusal::Banned);
         }
 
         let mut connected_ips = CONNECTED_IPS.lock().unwrap();
-        if connected_ips.insert(ip, client_id).is_some() {
-            return Response::builder()
-                .status(StatusCode::FORBIDDEN)
-                .body("only one connection per IP allowed".into())
-                .unwrap();
+        // the other device of someone moving their session over has to redeem its code on joining
+        if connected_ips.contains_key(&ip) && !transfer::expected_from(ip) {
+            return refuse(ws, Refusal::AlreadyConnected);
         }
+        connected_ips.entry(ip).or_insert(client_id);
 
         Some(ip)
     } else if addr.ip() == IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)) {
@@ -211,7 +2346,61 @@ async fn ws_handler(
             .unwrap();
     };
 
-    ws.on_upgrade(move |socket| handle_client(socket, client_id, addr, actual_ip))
+    let site = site_of(&headers);
+    ws.on_upgrade(move |socket| handle_client(socket, client_id, addr, actual_ip, site))
+}
+
+/// where the game can be opened, like `https://cibo.example.com`, for links handed to players
+fn site_of(headers: &HeaderMap) -> Option<String> {
+    let host = headers.get(header::HOST)?.to_str().ok()?;
+    let scheme = match headers
+        .get("x-forwarded-proto")
+        .and_then(|proto| proto.to_str().ok())
+    {
+        Some(proto) => proto,
+        None if crate::config::CONFIG.tls.is_some() => "https",
+        None => "http",
+    };
+    Some(format!("{}://{}", scheme, host))
+}
+
+/// disconnect the client a transfer code was made for and give its place on the ip to the one that
+/// redeemed the code. returns where the old client was standing and the stats of its session, which
+/// are gone from the shard once the old connection closes
+fn take_over(
+    old: ClientId,
+    new: ClientId,
+    ip: Option<IpAddr>,
+) -> (Option<(Position, Elevation)>, Option<PlayStats>) {
+    {
+        let mut connected_ips = CONNECTED_IPS.lock().unwrap();
+        connected_ips.retain(|_, id| *id != old);
+        if let Some(ip) = ip {
+            connected_ips.insert(ip, new);
+        }
+    }
+
+    let (mut spot, mut stats) = (None, None);
+    for_each_shard(|state| {
+        if let Some(found) = state.client_spot(old) {
+            spot = Some(found);
+            stats = state.take_play_stats(old);
+            state.refuse(old, Refusal::Transferred);
+            state.remove_client(old);
+        }
+    });
+    (spot, stats)
+}
+
+/// tell the client why it can't play and close the connection again. browsers don't let the page
+/// read why a websocket couldn't be opened, so a plain http error would only show up as a failed connection
+fn refuse(ws: WebSocketUpgrade, refusal: Refusal) -> Response<Body> {
+    ws.on_upgrade(move |mut socket| async move {
+        if let Ok(bytes) = ServerMessage::Refused(refusal).to_bytes() {
+            let _ = socket.send(Message::Binary(bytes)).await;
+        }
+        let _ = socket.close().await;
+    })
 }
 
 async fn handle_client(
@@ -219,46 +2408,94 @@ async fn handle_client(
     client_id: ClientId,
     client_addr: SocketAddr,
     remote_client_ip: Option<IpAddr>,
+    site: Option<String>,
 ) {
     let (client_tx, client_rx) = mpsc::unbounded_channel();
     let client_ip = remote_client_ip.unwrap_or(client_addr.ip());
 
-    let span = span!(tracing::Level::INFO, "client", id=client_id.as_u32(), ip = %client_ip, name = tracing::field::Empty);
+    let span = span!(tracing::Level::INFO, "client", id=client_id.as_u32(), ip = %client_ip, name = tracing::field::Empty, shard = tracing::field::Empty);
 
     async move {
         info!("connected");
 
-        GAME_STATE
+        let Some(socket) = wait_for_slot(client_id, socket).await else {
+            info!("left the queue");
+            return;
+        };
+
+        let shard = join_shard();
+        Span::current().record("shard", shard as u64);
+        SHARDS[shard]
+            .state
             .lock()
             .unwrap()
             .new_client(client_id, PerClientState { tx: client_tx });
-        handle_client_inner(client_id, socket, client_rx, remote_client_ip, client_ip).await;
+        handle_client_inner(
+            client_id,
+            shard,
+            socket,
+            client_rx,
+            remote_client_ip,
+            client_ip,
+            site,
+        )
+        .await;
 
         info!("disconnected");
+        let shard = &SHARDS[shard];
+        let (name, stats) = {
+            let mut state = shard.state.lock().unwrap();
+            let name = state.client_name(client_id);
+            let stats = state.take_play_stats(client_id);
+            state.remove_client(client_id);
+            (name, stats)
+        };
+        shard.players.fetch_sub(1, Ordering::Relaxed);
+        JOIN_QUEUE.lock().unwrap().players -= 1;
+        let account = ACCOUNTS.lock().unwrap().remove(&client_id);
+        DISCORD_IDS.lock().unwrap().remove(&client_id);
+        transfer::forget(client_id);
+        movement_recordings::finish(client_id).await;
+        if let (Some(account), Some(stats)) = (account, stats) {
+            save_play_stats(account, name, stats).await;
+        }
+        end_guest_session(client_id).await;
+        record_leave(client_id, client_ip).await;
     }
     .instrument(span)
     .await;
 
-    GAME_STATE.lock().unwrap().remove_client(client_id);
     if let Some(remote_client_ip) = remote_client_ip {
-        CONNECTED_IPS.lock().unwrap().remove(&remote_client_ip);
+        // a kicked client might already be back on a new connection
+        let mut connected_ips = CONNECTED_IPS.lock().unwrap();
+        if connected_ips.get(&remote_client_ip) == Some(&client_id) {
+            connected_ips.remove(&remote_client_ip);
+        }
     }
 }
 
 async fn handle_client_inner(
     client_id: ClientId,
+    shard: usize,
     socket: WebSocket,
-    mut client_rx: mpsc::UnboundedReceiver<ServerMessage>,
+    mut client_rx: mpsc::UnboundedReceiver<Outgoing>,
     remote_client_ip: Option<IpAddr>,
     client_ip: IpAddr,
+    site: Option<String>,
 ) {
     let (mut socket_tx, mut socket_rx) = socket.split();
+    // acks arrive on the receiving end, but the send task keeps track of them
+    let (ack_tx, mut ack_rx) = mpsc::unbounded_channel();
     let mut client_name = None;
 
     let mut connected = false;
+    let mut account: Option<String> = None;
 
     let recv_task = tokio::spawn(
         async move {
+            // only every power of two is logged, so a client sending garbage can't flood the log
+            let mut unknown = 0u32;
+            let mut malformed = 0u32;
             while let Some(Ok(Message::Binary(msg))) = socket_rx.next().await {
                 if let Some(remote_client_ip) = remote_client_ip {
                     if CONNECTED_IPS
@@ -267,94 +2504,696 @@ async fn handle_client_inner(
                         .get(&remote_client_ip)
                         .is_none()
                     {
+                        // a kicked client still confirms why it was kicked
+                        if let Ok(ClientMessage::Ack(seq)) = ClientMessage::from_bytes(&msg) {
+                            let _ = ack_tx.send(seq);
+                            continue;
+                        }
                         warn!("received message from disconnected client");
                         break;
                     }
                 }
 
+                let mut admin_log = None;
+                let mut joined = None;
+                let mut agreed_rules = None;
+                let mut resume_at = None;
+                let mut generated_name = None;
                 let mut client_msg = match ClientMessage::from_bytes(&msg) {
                     Ok(client_msg) => client_msg,
+                    Err(EnvelopeError::Unknown { tag, error }) => {
+                        let skipped = SKIPPED_MESSAGES.fetch_add(1, Ordering::Relaxed) + 1;
+                        unknown += 1;
+                        if unknown.is_power_of_two() {
+                            warn!(
+                                "skipped unknown message with tag {} ({:?}), {} from this client, {} skipped in total",
+                                tag, error, unknown, skipped
+                            );
+                        }
+                        continue;
+                    }
                     Err(e) => {
-                        error!("deserializing message: {:?}", e);
+                        record_malformed_message();
+                        malformed += 1;
+                        if malformed >= MAX_MALFORMED_MESSAGES {
+                            warn!("disconnecting after {} malformed messages", malformed);
+                            break;
+                        }
+                        if malformed.is_power_of_two() {
+                            error!("deserializing message: {:?} ({} from this client)", e, malformed);
+                        }
                         continue;
                     }
                 };
 
-                if !matches!(client_msg, ClientMessage::Connect { .. }) && !connected {
+                if let ClientMessage::Ack(seq) = client_msg {
+                    let _ = ack_tx.send(seq);
+                    continue;
+                }
+                if let ClientMessage::Action(_) = client_msg {
+                    movement_recordings::record_action(client_id, &msg);
+                }
+                if !matches!(
+                    client_msg,
+                    ClientMessage::Connect { .. } | ClientMessage::CheckName { .. }
+                ) && !connected
+                {
                     warn!("sent message before connecting");
                     continue;
                 }
 
                 match client_msg {
-                    ClientMessage::Connect { ref mut name } => {
+                    ClientMessage::CheckName {
+                        name,
+                        account: client_account,
+                    } => {
+                        let client_account = client_account.filter(|key| valid_account_key(key));
+                        let problem = name_problem(&name, &client_account);
+                        SHARDS[shard]
+                            .state
+                            .lock()
+                            .unwrap()
+                            .name_checked(client_id, name, problem);
+                        continue;
+                    }
+                    ClientMessage::Connect {
+                        ref mut name,
+                        account: ref client_account,
+                        rules_version,
+                        transfer: ref transfer_code,
+                        ..
+                    } => {
                         if connected {
                             warn!("tried to connect twice");
                             continue;
                         }
 
-                        let banned_words = BANNED_WORDS.lock().unwrap();
-                        let name_lower = name.to_lowercase();
-                        let display_name = if name.is_empty() {
-                            "Anon".to_string()
-                        } else {
-                            name.clone()
+                        let has_rules = RULES.lock().unwrap().is_some();
+                        if has_rules && rules_version.is_none() {
+                            warn!("tried to connect without agreeing to the rules");
+                            SHARDS[shard].state.lock().unwrap().reject_connect(
+                                client_id,
+                                "please read and agree to the rules first".to_string(),
+                            );
+                            continue;
+                        }
+                        agreed_rules = rules_version;
+
+                        let session = match transfer_code {
+                            Some(code) => match transfer::redeem(code) {
+                                Some(session) => Some(session),
+                                None => {
+                                    warn!("tried to continue with an unknown transfer code");
+                                    SHARDS[shard].state.lock().unwrap().reject_connect(
+                                        client_id,
+                                        "that transfer code is wrong or ran out, ask for a new one with /transfer".to_string(),
+                                    );
+                                    continue;
+                                }
+                            },
+                            None => None,
                         };
+                        // let in because someone on the same ip was about to move over, but didn't bring the code
+                        let ip_taken = remote_client_ip.is_some_and(|ip| {
+                            CONNECTED_IPS.lock().unwrap().get(&ip) != Some(&client_id)
+                        });
+                        if session.is_none() && ip_taken {
+                            SHARDS[shard].state.lock().unwrap().reject_connect(
+                                client_id,
+                                "someone is already playing from your ip".to_string(),
+                            );
+                            continue;
+                        }
+
+                        let mut client_account =
+                            client_account.clone().filter(|key| valid_account_key(key));
+                        if let Some(session) = session {
+                            info!("continued the session of '{}'", session.name);
+                            let (spot, stats) =
+                                take_over(session.client, client_id, remote_client_ip);
+                            // saved before the new session loads the account's stats
+                            if let (Some(account), Some(stats)) = (&session.account, stats) {
+                                save_play_stats(account.clone(), session.name.clone(), stats)
+                                    .await;
+                            }
+                            resume_at = spot;
+                            *name = session.name;
+                            client_account = session.account;
+                        }
+
+                        *name = names::clean(name);
+                        if name_reserved(name, &client_account) {
+                            warn!("tried to connect with protected name '{}'", name);
+                            SHARDS[shard].state.lock().unwrap().reject_connect(
+                                client_id,
+                                format!(
+                                    "the name '{}' is reserved. please pick a different one!",
+                                    name
+                                ),
+                            );
+                            continue;
+                        }
+
+                        account = client_account;
+                        if let Some(account) = &account {
+                            ACCOUNTS.lock().unwrap().insert(client_id, account.clone());
+                        }
+
+                        if name.is_empty() {
+                            *name = anonymous_name();
+                            generated_name = Some(name.clone());
+                        }
+                        let display_name = name.clone();
                         Span::current().record("name", &display_name);
+                        joined = Some(display_name.clone());
                         client_name = Some(display_name);
 
                         info!("fully connected");
-                        let stream_mode = STREAM_MODE.load(Ordering::Relaxed);
-                        if banned_words.values().any(|word| {
-                            if name_lower.contains(&word.word) {
-                                // allow light bans outside of stream mode
-                                if !stream_mode && !word.full_ban {
-                                    return false;
-                                }
-                                return true;
-                            }
-                            false
-                        }) {
+                        if name_banned(name) {
                             warn!("tried to connect with banned name");
                             *name = "*****".to_string();
                         }
-                        name.truncate(cibo_online::NAME_LIMIT);
-                        *name = name.trim().to_string();
                         connected = true;
                     }
-                    ClientMessage::Chat(ref mut msg) => {
+                    ClientMessage::Chat(ref mut msg)
+                    | ClientMessage::Whisper {
+                        message: ref mut msg,
+                        ..
+                    }
+                    | ClientMessage::EditChat {
+                        new_text: ref mut msg,
+                        ..
+                    }
+                    | ClientMessage::SendMail {
+                        message: ref mut msg,
+                        ..
+                    } => {
                         info!("says '{}'", msg);
 
-                        let banned_words = BANNED_WORDS.lock().unwrap();
+                        if let Some(remaining) = muted_for(client_ip) {
+                            let minutes = remaining.as_secs() / 60 + 1;
+                            SHARDS[shard].state.lock().unwrap().system_chat_to(
+                                client_id,
+                                format!(
+                                    "you are muted for {} more minute{}",
+                                    minutes,
+                                    if minutes == 1 { "" } else { "s" }
+                                ),
+                            );
+                            continue;
+                        }
+
+                        let severity = moderate(msg);
+                        if severity.is_some() {
+                            digest::count_filter_hit();
+                        }
+                        match severity {
+                            None => {}
+                            Some(Severity::Mask) => {
+                                warn!("tried to send banned word, masked it");
+                            }
+                            Some(Severity::Censor) => {
+                                warn!("tried to send banned word");
+                            }
+                            Some(Severity::Block) => {
+                                warn!("tried to send banned word, blocked the message");
+                                SHARDS[shard].state.lock().unwrap().system_chat_to(
+                                    client_id,
+                                    "your message wasn't sent".to_string(),
+                                );
+                                continue;
+                            }
+                            Some(Severity::Mute) => {
+                                warn!("tried to send banned word, muted for {:?}", *AUTO_MUTE);
+                                MUTED
+                                    .lock()
+                                    .unwrap()
+                                    .insert(client_ip, std::time::Instant::now() + *AUTO_MUTE);
+                                SHARDS[shard].state.lock().unwrap().system_chat_to(
+                                    client_id,
+                                    format!(
+                                        "your message wasn't sent and you are muted for {} minutes",
+                                        AUTO_MUTE.as_secs() / 60
+                                    ),
+                                );
+                                continue;
+                            }
+                            Some(Severity::Ban) => {
+                                warn!("tried to send banned word, banned");
+                                auto_ban(client_ip).await;
+                                break;
+                            }
+                        }
 
-                        let msg_lower = msg.to_lowercase();
-                        let stream_mode = STREAM_MODE.load(Ordering::Relaxed);
+                        // logged once the message has its id
+                        admin_log = Some((msg.clone(), severity.is_some()));
+                        match severity {
+                            None => {}
+                            Some(Severity::Mask) => *msg = mask_banned_words(msg),
+                            Some(_) => *msg = "*****".to_string(),
+                        }
 
-                        let contains_banned = banned_words.values().any(|word| {
-                            if msg_lower.contains(&word.word) {
-                                // allow light bans outside of stream mode
-                                if !word.full_ban && !stream_mode {
-                                    return false;
-                                }
-                                return true;
+                        let filtered =
+                            links::filter_links(msg, STREAM_MODE.load(Ordering::Relaxed));
+                        if filtered.is_empty() && !msg.is_empty() {
+                            SHARDS[shard]
+                                .state
+                                .lock()
+                                .unwrap()
+                                .system_chat_to(client_id, "links aren't allowed here".to_string());
+                            continue;
+                        }
+                        *msg = filtered;
+                    }
+                    // the phrases don't need moderation, but muted players stay quiet
+                    ClientMessage::QuickChat(_) if muted_for(client_ip).is_some() => {
+                        SHARDS[shard]
+                            .state
+                            .lock()
+                            .unwrap()
+                            .system_chat_to(client_id, "you are muted".to_string());
+                        continue;
+                    }
+                    ClientMessage::UpdateSettings(ref mut settings) => {
+                        settings.clamp();
+                        if let Some(account) = &account {
+                            tokio::spawn(save_settings(account.clone(), settings.clone()));
+                        }
+                    }
+                    ClientMessage::Block(blocked) | ClientMessage::Unblock(blocked) => {
+                        let block = matches!(client_msg, ClientMessage::Block(_));
+                        let blocked_account = ACCOUNTS.lock().unwrap().get(&blocked).cloned();
+                        if let (Some(account), Some(blocked_account)) = (&account, blocked_account)
+                        {
+                            tokio::spawn(save_block(account.clone(), blocked_account, block));
+                        }
+                    }
+                    // streamers shouldn't have people show up right next to them
+                    ClientMessage::TeleportRequest(_) | ClientMessage::TeleportAccept
+                        if STREAM_MODE.load(Ordering::Relaxed) =>
+                    {
+                        SHARDS[shard]
+                            .state
+                            .lock()
+                            .unwrap()
+                            .system_chat_to(client_id, "teleports are off right now".to_string());
+                        continue;
+                    }
+                    ClientMessage::RequestStats => {
+                        let mut stats = match &account {
+                            Some(account) => load_play_stats(account).await,
+                            None => PlayStats::default(),
+                        };
+                        let state = SHARDS[shard].state.lock().unwrap();
+                        stats.add(&state.play_stats(client_id));
+                        state.send_play_stats(client_id, stats);
+                        continue;
+                    }
+                    ClientMessage::SetHome if account.is_none() => {
+                        SHARDS[shard].state.lock().unwrap().system_chat_to(
+                            client_id,
+                            "homes are saved to your account, try reloading the page".to_string(),
+                        );
+                        continue;
+                    }
+                    #[cfg(feature = "debug_tools")]
+                    ClientMessage::WorldSnapshot(client_snapshot) => {
+                        let server_snapshot =
+                            SHARDS[shard].state.lock().unwrap().world_snapshot();
+                        crate::admin_panel::snapshot_submitted(
+                            client_id,
+                            server_snapshot,
+                            client_snapshot,
+                        );
+                        continue;
+                    }
+                    ClientMessage::Rename(ref new_name) => {
+                        let old_name = client_name.clone().unwrap_or_default();
+                        match rename(&account, client_ip, &old_name, new_name).await {
+                            Ok(name) => {
+                                info!("changed their name to '{}'", name);
+                                Span::current().record("name", &name);
+                                SHARDS[shard]
+                                    .state
+                                    .lock()
+                                    .unwrap()
+                                    .rename_client(client_id, name.clone());
+                                client_name = Some(name);
                             }
-                            false
+                            Err(problem) => SHARDS[shard]
+                                .state
+                                .lock()
+                                .unwrap()
+                                .system_chat_to(client_id, problem),
+                        }
+                        continue;
+                    }
+                    ClientMessage::RequestTransfer => {
+                        let code = transfer::create(transfer::Session {
+                            client: client_id,
+                            ip: remote_client_ip,
+                            account: account.clone(),
+                            name: client_name.clone().unwrap_or_default(),
                         });
+                        let link = site
+                            .as_ref()
+                            .map(|site| format!("{}/?transfer={}", site, code));
+                        info!("asked to continue on another device");
+                        SHARDS[shard]
+                            .state
+                            .lock()
+                            .unwrap()
+                            .transfer_code(client_id, code, link);
+                        continue;
+                    }
+                    ClientMessage::SendMail { .. } if account.is_none() => {
+                        SHARDS[shard].state.lock().unwrap().system_chat_to(
+                            client_id,
+                            "letters are sent from your account, try reloading the page"
+                                .to_string(),
+                        );
+                        continue;
+                    }
+                    _ => (),
+                }
+
+                if let Some(name) = joined {
+                    record_join(client_id, client_ip, &name, account.clone()).await;
+                    if let Some(version) = agreed_rules {
+                        record_rules_agreement(client_ip, account.clone(), &name, version).await;
+                    }
+                }
+
+                let joined_settings = match (&client_msg, &account) {
+                    (ClientMessage::Connect { settings, .. }, Some(account)) => {
+                        Some(load_settings(account, settings.clone()).await)
+                    }
+                    _ => None,
+                };
+
+                let joined_home = match (&client_msg, &account) {
+                    (ClientMessage::Connect { .. }, Some(account)) => load_home(account).await,
+                    _ => None,
+                };
+                let joined_staff = match (&client_msg, &account) {
+                    (ClientMessage::Connect { .. }, Some(account)) => is_staff(account).await,
+                    _ => false,
+                };
+                let joined_stats = match (&client_msg, &account) {
+                    (ClientMessage::Connect { .. }, Some(account)) => {
+                        Some(load_play_stats(account).await)
+                    }
+                    _ => None,
+                };
+                let joined_discord = match (&client_msg, &account) {
+                    (ClientMessage::Connect { .. }, Some(account)) => {
+                        discord::linked_user(account).await
+                    }
+                    _ => None,
+                };
+                let joined_wallet = match (&client_msg, &account) {
+                    (ClientMessage::Connect { .. }, Some(account)) => {
+                        Some(seashells::load_wallet(account).await)
+                    }
+                    _ => None,
+                };
+                let joined_mail = match (&client_msg, &account) {
+                    (ClientMessage::Connect { .. }, Some(account)) => {
+                        mail::take_letters(account).await
+                    }
+                    _ => Vec::new(),
+                };
+                let board_read = match (&client_msg, &account) {
+                    (ClientMessage::Connect { .. }, Some(account)) => {
+                        Some(load_board_read(account).await)
+                    }
+                    _ => None,
+                };
+
+                // blocks are stored per account, so they have to be matched up with whoever is online
+                let joined_blocks = match (&client_msg, &account) {
+                    (ClientMessage::Connect { .. }, Some(account)) => {
+                        let (blocking, blocked_by) = load_blocks(account).await;
+                        let accounts = ACCOUNTS.lock().unwrap();
+                        let online = |accounts_of: &[String]| {
+                            accounts
+                                .iter()
+                                .filter(|(id, account)| {
+                                    **id != client_id && accounts_of.contains(account)
+                                })
+                                .map(|(id, _)| *id)
+                                .collect::<Vec<_>>()
+                        };
+                        Some((online(&blocking), online(&blocked_by)))
+                    }
+                    _ => None,
+                };
+
+                // players without a registered account only get a limited time per day
+                let guest_time = match &client_msg {
+                    ClientMessage::Connect { .. } if *GUEST_LIMIT_MS > 0 => {
+                        let registered = match &account {
+                            Some(account) => is_registered(account).await,
+                            None => false,
+                        };
+                        if registered {
+                            None
+                        } else {
+                            let used = guest_time_used(client_ip).await;
+                            GUESTS
+                                .lock()
+                                .unwrap()
+                                .insert(client_id, (client_ip, std::time::Instant::now()));
+                            Some(GUEST_LIMIT_MS.saturating_sub(used))
+                        }
+                    }
+                    _ => None,
+                };
+                let registered = match (&client_msg, &account) {
+                    // a discord account can only be linked to one account key at a time, so
+                    // registering can't be repeated with fresh keys to get around the guest limit
+                    (ClientMessage::Register, Some(account))
+                        if discord::linked_user(account).await.is_none() =>
+                    {
+                        SHARDS[shard].state.lock().unwrap().system_chat_to(
+                            client_id,
+                            "link your discord account on the game's page first, then /register again"
+                                .to_string(),
+                        );
+                        continue;
+                    }
+                    (ClientMessage::Register, Some(account)) => {
+                        info!("registered their account");
+                        register_account(account).await;
+                        end_guest_session(client_id).await;
+                        true
+                    }
+                    (ClientMessage::Register, None) => {
+                        warn!("tried to register without an account");
+                        false
+                    }
+                    _ => false,
+                };
+
+                let grant_editor = matches!(
+                    &client_msg,
+                    ClientMessage::Connect { admin_token: Some(token), .. }
+                        if EDITOR_TOKENS.lock().unwrap().contains(token)
+                );
+                let connecting = matches!(client_msg, ClientMessage::Connect { .. });
 
+                let bridged_chat = match &client_msg {
+                    ClientMessage::Chat(msg) => Some(msg.clone()),
+                    ClientMessage::QuickChat(phrase) => phrase.text().map(String::from),
+                    _ => None,
+                };
+
+                let mut game_state = SHARDS[shard].state.lock().unwrap();
+                let chat_id = game_state
+                    .update(client_id, client_msg)
+                    .unwrap_or_else(|err| {
+                        warn!("failed to handle client message: {}", err);
+                        None
+                    });
+                if let Some(settings) = joined_settings {
+                    game_state.set_safe_chat(client_id, settings.safe_chat);
+                    game_state.send_settings(client_id, settings);
+                }
+                if let Some((position, elevation)) = resume_at.take() {
+                    game_state.resume_at(client_id, position, elevation);
+                }
+                if let Some(name) = generated_name.take() {
+                    game_state.system_chat_to(
+                        client_id,
+                        format!(
+                            "you're playing as {}. enter a name before joining to pick your own",
+                            name
+                        ),
+                    );
+                }
+                if connecting {
+                    game_state.advertise_asset_packs(client_id, asset_packs::advertised());
+                }
+                if joined_home.is_some() {
+                    game_state.set_home(client_id, joined_home);
+                }
+                if let Some(revision) = board_read {
+                    game_state.set_board_seen(client_id, revision);
+                }
+                if !joined_mail.is_empty() {
+                    info!("got {} letters", joined_mail.len());
+                    game_state.deliver_letters(client_id, joined_mail);
+                }
+                if let Some(user) = joined_discord {
+                    game_state.set_verified(client_id, true);
+                    DISCORD_IDS.lock().unwrap().insert(client_id, user.id);
+                }
+                if let Some(stats) = joined_stats {
+                    game_state.set_earlier_play_stats(client_id, stats);
+                }
+                if let Some((balance, owned)) = joined_wallet {
+                    game_state.set_wallet(client_id, balance, owned);
+                }
+                if let Some(remaining_ms) = guest_time {
+                    game_state.set_guest_time(client_id, Some(remaining_ms));
+                }
+                if registered {
+                    game_state.set_guest_time(client_id, None);
+                }
+                if let Some((blocking, blocked_by)) = joined_blocks {
+                    for blocked in blocking {
+                        game_state.block(client_id, blocked);
+                    }
+                    for blocker in blocked_by {
+                        game_state.block(blocker, client_id);
+                    }
+                }
+
+                let chat_edits = game_state.take_chat_edits();
+                if let Some((msg, contains_banned)) = admin_log {
+                    if let Some(chat_id) = chat_id {
+                        digest::count_message();
                         log_admin_message(
+                            chat_id,
                             &msg,
                             client_name.as_ref().map_or("UNKNOWN", |name| name.as_str()),
                             client_ip,
+                            account.clone(),
                             contains_banned,
                         );
-                        if contains_banned {
-                            client_msg = ClientMessage::Chat("*****".to_string());
-                            warn!("tried to send banned word");
-                        }
                     }
-                    _ => (),
+                    for message_id in chat_edits {
+                        edit_logged_message(message_id, &msg, contains_banned);
+                    }
+                }
+
+                if grant_editor {
+                    info!("granted editor access");
+                    game_state.grant_editor(client_id);
+                }
+                if joined_staff {
+                    info!("joined as event staff");
+                    game_state.grant_staff(client_id);
                 }
 
-                GAME_STATE.lock().unwrap().update(client_id, client_msg);
+                let placed_changes = game_state.take_placed_changes();
+                let placed_objects =
+                    (!placed_changes.is_empty()).then(|| game_state.placed_objects());
+                let gallery = game_state
+                    .take_gallery_changed()
+                    .then(|| game_state.gallery().to_vec());
+                let rsvps = game_state.take_rsvps();
+                let votes = game_state.take_votes();
+                let homes = game_state.take_home_changes();
+                let board_reads = game_state.take_board_reads();
+                let letters = game_state.take_letters();
+                let purchases = game_state.take_purchases();
+                let chalk = game_state
+                    .take_new_chalk()
+                    .into_iter()
+                    .map(|stroke| {
+                        let data = game_state.save_chalk_chunk(stroke.chunk());
+                        (stroke, data)
+                    })
+                    .collect::<Vec<_>>();
+                let name = game_state.client_name(client_id);
+                // only one shard is locked at a time, otherwise two shards syncing at once could deadlock
+                drop(game_state);
+
+                if let Some(objects) = placed_objects {
+                    for_other_shards(shard, |state| {
+                        for change in &placed_changes {
+                            if let Err(e) = state.apply_placed_change(change.clone()) {
+                                error!("failed to copy a world object change: {}", e);
+                            }
+                        }
+                    });
+                    WORLD_OBJECT_SAVES.spawn(save_world_objects(objects));
+                }
+                if let Some(gallery) = gallery {
+                    info!("hung a painting");
+                    if let Some(painting) = gallery.last() {
+                        for_other_shards(shard, |state| {
+                            state.hang_painting(painting.clone());
+                            state.take_gallery_changed();
+                        });
+                    }
+                    GALLERY_SAVES.spawn(save_gallery(gallery));
+                }
+                for (_, event_id) in rsvps {
+                    info!("rsvped to event {}", event_id);
+                    let voter = account.clone().unwrap_or_else(|| client_ip.to_string());
+                    tokio::spawn(save_rsvp(event_id, voter));
+                }
+                if let (Some((_, home)), Some(account)) = (homes.last(), &account) {
+                    info!("set their home to {:?}", home);
+                    tokio::spawn(save_home(account.clone(), *home));
+                }
+                if let (Some((_, revision)), Some(account)) = (board_reads.last(), &account) {
+                    tokio::spawn(save_board_read(account.clone(), *revision));
+                }
+                if let Some(account) = &account {
+                    for (_, to, message) in letters {
+                        info!("left a letter for {}", to);
+                        tokio::spawn(
+                            post_letter(shard, client_id, account.clone(), name.clone(), to, message)
+                                .in_current_span(),
+                        );
+                    }
+                    for (_, item) in purchases {
+                        tokio::spawn(
+                            buy_item(shard, client_id, account.clone(), item).in_current_span(),
+                        );
+                    }
+                }
+                for (stroke, data) in chalk {
+                    let chunk = stroke.chunk();
+                    for_other_shards(shard, |state| state.add_chalk(stroke.clone()));
+                    tokio::spawn(save_chalk_chunk(chunk, data));
+                }
+                for (_, poll_id, option) in votes {
+                    info!("voted for option {} in poll {}", option, poll_id);
+                    let voter = account.clone().unwrap_or_else(|| client_ip.to_string());
+                    tokio::spawn(save_vote(poll_id, option, voter, client_ip));
+                }
+                if let (Some(message), Some(chat_id)) = (bridged_chat, chat_id) {
+                    federation::relay_chat(name.clone(), message.clone());
+                    discord_bridge::relay_chat(name.clone(), message.clone());
+                    let discord_id = DISCORD_IDS.lock().unwrap().get(&client_id).cloned();
+                    webhooks::chat(name.clone(), message.clone(), discord_id);
+                    if let (Some(bridge), true) = (CHAT_BRIDGE.get(), SHARDS.len() > 1) {
+                        let chat = BridgedChat {
+                            shard,
+                            id: chat_id,
+                            from: client_id,
+                            name,
+                            message,
+                        };
+                        if let Err(mpsc::error::TrySendError::Full(_)) = bridge.try_send(chat) {
+                            warn!("dropped a chat message, the other shards can't keep up");
+                        }
+                    }
+                }
             }
         }
         .in_current_span(),
@@ -362,14 +3201,92 @@ async fn handle_client_inner(
 
     let send_task = tokio::spawn(
         async move {
-            while let Some(server_msg) = client_rx.recv().await {
-                let server_msg_bytes = match server_msg.to_bytes() {
+            let mut pending = None;
+            let mut critical = CriticalMessages::default();
+            let mut retry = tokio::time::interval(acks::ACK_TIMEOUT / 2);
+            // removed from the game, only waiting for the last critical messages to be confirmed
+            let mut closing = false;
+            'send: loop {
+                let outgoing = match pending.take() {
+                    Some(outgoing) => outgoing,
+                    None => tokio::select! {
+                        outgoing = client_rx.recv(), if !closing => match outgoing {
+                            Some(outgoing) => outgoing,
+                            None if !critical.is_empty() => {
+                                closing = true;
+                                continue;
+                            }
+                            None => break,
+                        },
+                        Some(seq) = ack_rx.recv() => {
+                            critical.ack(seq);
+                            if closing && critical.is_empty() {
+                                break;
+                            }
+                            continue;
+                        }
+                        _ = retry.tick() => {
+                            match critical.due() {
+                                Ok(due) => {
+                                    for bytes in due {
+                                        let sent = socket_tx.send(Message::Binary(bytes)).await;
+                                        if sent.is_err() {
+                                            break 'send;
+                                        }
+                                    }
+                                }
+                                Err(seq) => {
+                                    warn!(
+                                        "critical message {} was never confirmed, disconnecting",
+                                        seq
+                                    );
+                                    break;
+                                }
+                            }
+                            continue;
+                        }
+                    },
+                };
+
+                // if the client can't keep up, merge queued positional updates instead of sending stale ones
+                let mut merged: Option<ServerMessage> = None;
+                if outgoing.message().delivery() == Delivery::Unreliable {
+                    while let Ok(next) = client_rx.try_recv() {
+                        if !outgoing.message().coalesces_with(next.message()) {
+                            pending = Some(next);
+                            break;
+                        }
+                        merged
+                            .get_or_insert_with(|| outgoing.message().clone())
+                            .coalesce(next.into_message());
+                    }
+                }
+
+                let mut critical_seq = None;
+                let server_msg = match (merged, outgoing) {
+                    (Some(merged), _) => merged.to_bytes(),
+                    (None, outgoing) if outgoing.message().delivery() == Delivery::Critical => {
+                        let seq = critical.next_seq();
+                        critical_seq = Some(seq);
+                        ServerMessage::Critical {
+                            seq,
+                            message: Box::new(outgoing.into_message()),
+                        }
+                        .to_bytes()
+                    }
+                    (None, Outgoing::Shared(shared)) => Ok(shared.1.clone()),
+                    (None, Outgoing::Message(message)) => message.to_bytes(),
+                };
+                let server_msg_bytes = match server_msg {
                     Ok(bytes) => bytes,
                     Err(e) => {
                         error!("serializing message: {:?}", e);
                         break;
                     }
                 };
+                if let Some(seq) = critical_seq {
+                    critical.sent(seq, server_msg_bytes.clone());
+                }
 
                 match socket_tx.send(Message::Binary(server_msg_bytes)).await {
                     Ok(_) => (),
@@ -0,0 +1,80 @@
+//! where players spend their time, to plan new parts of the map. every minute the positions of
+//! everyone online are counted into a coarse grid that is added up per hour, so the admin panel can
+//! show any range of time. nothing is kept about who stood where.
+
+use crate::{db::db, game_server};
+use chrono::Utc;
+use cibo_online::Position;
+use std::{collections::HashMap, time::Duration};
+use tracing::error;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+/// width and height of a cell, in tiles
+pub const CELL_TILES: i64 = 8;
+const TILE_SIZE: i64 = 16;
+/// hours of samples that are kept
+const RETENTION_HOURS: i64 = 90 * 24;
+
+/// the cell a position in the world falls into
+fn cell_of(position: Position) -> (i64, i64) {
+    let size = CELL_TILES * TILE_SIZE;
+    (position.x.div_euclid(size), position.y.div_euclid(size))
+}
+
+fn current_hour() -> i64 {
+    Utc::now().timestamp() / (60 * 60)
+}
+
+async fn record(positions: &[Position]) -> Result<(), sqlx::Error> {
+    let mut cells = HashMap::<(i64, i64), i64>::new();
+    for position in positions {
+        *cells.entry(cell_of(*position)).or_default() += 1;
+    }
+
+    let hour = current_hour();
+    let mut tx = db().await.begin().await?;
+    for ((x, y), samples) in cells {
+        sqlx::query(
+            "INSERT INTO heatmap (hour, x, y, samples) VALUES (?, ?, ?, ?)
+             ON CONFLICT (hour, x, y) DO UPDATE SET samples = samples + excluded.samples",
+        )
+        .bind(hour)
+        .bind(x)
+        .bind(y)
+        .bind(samples)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("DELETE FROM heatmap WHERE hour < ?")
+        .bind(hour - RETENTION_HOURS)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// the samples of each cell between two unix timestamps, as (x, y, samples) with the cell
+/// coordinates counted in cells
+pub async fn load(from: i64, to: i64) -> Result<Vec<(i64, i64, i64)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT x, y, SUM(samples) FROM heatmap WHERE hour >= ? AND hour <= ? GROUP BY x, y",
+    )
+    .bind(from.div_euclid(60 * 60))
+    .bind(to.div_euclid(60 * 60))
+    .fetch_all(db().await)
+    .await
+}
+
+pub async fn run() {
+    let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let positions = game_server::get_player_positions();
+        if positions.is_empty() {
+            continue;
+        }
+        if let Err(err) = record(&positions).await {
+            error!("failed to save the heatmap: {}", err);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! what happens to links in chat, so nobody can post scam links while the game is on stream.
+//! configured by a `[links]` section in the config:
+//!
+//! ```toml
+//! [links]
+//! policy = "censor"
+//! allowed_domains = ["youtube.com", "github.com"]
+//! ```
+//!
+//! links to allowed domains (and their subdomains) always go through, the policy decides what
+//! happens to all other ones. in stream mode, `allow` acts like `censor`.
+
+use crate::config::CONFIG;
+use cibo_online::links;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkPolicy {
+    /// links are sent as they are
+    #[default]
+    Allow,
+    /// links are replaced with `*****`
+    Censor,
+    /// links are removed from the message
+    Strip,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LinkConfig {
+    policy: LinkPolicy,
+    allowed_domains: Vec<String>,
+}
+
+/// apply the link policy to a chat message. an empty result means nothing was left to send
+pub fn filter_links(message: &str, stream_mode: bool) -> String {
+    let default = LinkConfig::default();
+    let config = CONFIG.links.as_ref().unwrap_or(&default);
+    let policy = match config.policy {
+        LinkPolicy::Allow if stream_mode => LinkPolicy::Censor,
+        policy => policy,
+    };
+    if policy == LinkPolicy::Allow {
+        return message.to_string();
+    }
+
+    let mut filtered = String::new();
+    let mut last = 0;
+    for link in links::find_links(message) {
+        let allowed = links::domain(&message[link.clone()]).map_or(false, |domain| {
+            links::domain_allowed(domain, &config.allowed_domains)
+        });
+        if allowed {
+            continue;
+        }
+
+        filtered.push_str(&message[last..link.start]);
+        if policy == LinkPolicy::Censor {
+            filtered.push_str("*****");
+        }
+        last = link.end;
+    }
+    filtered.push_str(&message[last..]);
+
+    match policy {
+        // removing a link can leave double spaces behind
+        LinkPolicy::Strip => filtered.split_whitespace().collect::<Vec<_>>().join(" "),
+        _ => filtered,
+    }
+}
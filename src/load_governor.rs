@@ -0,0 +1,88 @@
+//! keeps a shard playable when its ticks regularly take longer than they may. the tick rate drops
+//! to 30hz and every tick simulates twice the time, so the game keeps its speed and only gets a bit
+//! choppier. clients are told through the tuning and send their actions at the same pace. the full
+//! rate comes back once ticks have been fast for a while.
+
+use cibo_online::SERVER_TICK_RATE;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+use tokio::time::{Interval, MissedTickBehavior};
+
+/// how many ticks of the full rate are simulated at once under load
+const DEGRADED_TICKS: u64 = 2;
+/// how many of the last ticks are looked at to decide whether the shard can't keep up
+const WINDOW: usize = 120;
+/// more ticks over budget than this within the window lowers the rate
+const OVERRUN_LIMIT: usize = WINDOW / 4;
+/// ticks have to take less than this share of the full rate budget before it is restored...
+const RESTORE_PERCENT: u32 = 50;
+/// ...for this long without a break
+const RESTORE_AFTER: Duration = Duration::from_secs(10);
+
+pub struct LoadGovernor {
+    tick_ms: u64,
+    /// whether each of the last ticks took longer than a tick at the full rate may
+    overruns: VecDeque<bool>,
+    /// since when ticks were fast enough to go back to the full rate
+    calm_since: Option<Instant>,
+}
+
+impl Default for LoadGovernor {
+    fn default() -> Self {
+        LoadGovernor {
+            tick_ms: SERVER_TICK_RATE,
+            overruns: VecDeque::with_capacity(WINDOW),
+            calm_since: None,
+        }
+    }
+}
+
+impl LoadGovernor {
+    /// how much time a tick simulates right now
+    pub fn tick_ms(&self) -> u64 {
+        self.tick_ms
+    }
+
+    pub fn degraded(&self) -> bool {
+        self.tick_ms != SERVER_TICK_RATE
+    }
+
+    /// a timer for the current tick rate, has to be replaced whenever it changes
+    pub fn interval(&self) -> Interval {
+        let mut interval = tokio::time::interval(Duration::from_millis(self.tick_ms));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    }
+
+    /// how long the last tick took, returns the new tick length if the rate changed
+    pub fn record(&mut self, took: Duration) -> Option<u64> {
+        let budget = Duration::from_millis(SERVER_TICK_RATE);
+
+        if !self.degraded() {
+            if self.overruns.len() == WINDOW {
+                self.overruns.pop_front();
+            }
+            self.overruns.push_back(took > budget);
+            if self.overruns.iter().filter(|overrun| **overrun).count() <= OVERRUN_LIMIT {
+                return None;
+            }
+            self.overruns.clear();
+            self.tick_ms = SERVER_TICK_RATE * DEGRADED_TICKS;
+            return Some(self.tick_ms);
+        }
+
+        if took >= budget * RESTORE_PERCENT / 100 {
+            self.calm_since = None;
+            return None;
+        }
+        let calm_since = *self.calm_since.get_or_insert_with(Instant::now);
+        if calm_since.elapsed() < RESTORE_AFTER {
+            return None;
+        }
+        self.calm_since = None;
+        self.tick_ms = SERVER_TICK_RATE;
+        Some(self.tick_ms)
+    }
+}
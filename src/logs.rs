@@ -0,0 +1,159 @@
+//! the most recent log events, kept in memory so the admin panel can show them. the filter that
+//! decides what gets logged can be changed from there too, without restarting
+
+use chrono::{DateTime, Utc};
+use std::{
+    collections::VecDeque,
+    fmt::{Debug, Write},
+    sync::{LazyLock, Mutex, OnceLock},
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Level, Subscriber,
+};
+use tracing_subscriber::{
+    layer::Context, registry::LookupSpan, reload, EnvFilter, Layer, Registry,
+};
+
+/// events past this are dropped, oldest first
+const LOG_CAPACITY: usize = 1000;
+/// what is logged after a start, in `EnvFilter` syntax
+pub const DEFAULT_FILTER: &str = "info";
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+static LOG: LazyLock<Mutex<VecDeque<LogEntry>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(LOG_CAPACITY)));
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub level: Level,
+    /// the spans the event happened in, e.g. `handle_connection{ip=..}:game`
+    pub spans: String,
+    /// the message followed by the fields of the event
+    pub message: String,
+}
+
+/// the logged events that are at least as severe as `level`, newest first
+pub fn recent(level: Level) -> Vec<LogEntry> {
+    LOG.lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .filter(|entry| entry.level <= level)
+        .cloned()
+        .collect()
+}
+
+/// the layer that filters all events, it has to come first so the handle for changing it matches
+pub fn filter_layer() -> reload::Layer<EnvFilter, Registry> {
+    let (layer, handle) = reload::Layer::new(EnvFilter::new(DEFAULT_FILTER));
+    let _ = FILTER.set(handle);
+    layer
+}
+
+/// the filter that is used right now
+pub fn current_filter() -> String {
+    FILTER
+        .get()
+        .and_then(|handle| handle.with_current(|filter| filter.to_string()).ok())
+        .unwrap_or_else(|| DEFAULT_FILTER.to_string())
+}
+
+/// replace the filter, e.g. with `info,cibo_online_server::game_server=debug`. it stays until the
+/// next restart
+pub fn set_filter(directives: &str) -> Result<(), String> {
+    let filter = EnvFilter::try_new(directives).map_err(|err| err.to_string())?;
+    let handle = FILTER.get().ok_or("logging isn't set up")?;
+    handle.reload(filter).map_err(|err| err.to_string())
+}
+
+/// tracing layer that copies every event into the in-memory log
+pub struct LogBuffer;
+
+/// the fields of a span, recorded when it is created since they're gone afterwards
+struct SpanFields(String);
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+            return;
+        }
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        let _ = write!(self.fields, "{}={:?}", field.name(), value);
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for LogBuffer {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        span.extensions_mut().insert(SpanFields(visitor.fields));
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() else {
+            return;
+        };
+        let mut visitor = FieldVisitor {
+            fields: std::mem::take(fields),
+            ..Default::default()
+        };
+        values.record(&mut visitor);
+        *fields = visitor.fields;
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| match span.extensions().get::<SpanFields>() {
+                        Some(SpanFields(fields)) if !fields.is_empty() => {
+                            format!("{}{{{}}}", span.name(), fields)
+                        }
+                        _ => span.name().to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(":")
+            })
+            .unwrap_or_default();
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let message = match (visitor.message.is_empty(), visitor.fields.is_empty()) {
+            (_, true) => visitor.message,
+            (true, false) => visitor.fields,
+            (false, false) => format!("{} {}", visitor.message, visitor.fields),
+        };
+
+        let mut log = LOG.lock().unwrap();
+        if log.len() >= LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(LogEntry {
+            timestamp: Utc::now(),
+            level: *event.metadata().level(),
+            spans,
+            message,
+        });
+    }
+}
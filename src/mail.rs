@@ -0,0 +1,107 @@
+//! letters players leave for each other at mailboxes. they are stored for the account that last
+//! used the recipient name and delivered the next time it joins. letters that weren't picked up
+//! within `MAIL_EXPIRY_DAYS` days (30 by default) are thrown away.
+
+use crate::db::{db, env_or};
+use cibo_online::server::Letter;
+use std::sync::LazyLock;
+use tracing::error;
+
+static MAIL_EXPIRY_SECS: LazyLock<i64> =
+    LazyLock::new(|| env_or("MAIL_EXPIRY_DAYS", 30i64) * 24 * 60 * 60);
+
+/// letters that can wait for a single player at once
+const MAILBOX_SIZE: i64 = 20;
+
+pub enum PostResult {
+    Sent,
+    UnknownRecipient,
+    MailboxFull,
+}
+
+/// the account of the player that is known under a name, registered names come first
+async fn account_of(name: &str) -> Result<Option<String>, sqlx::Error> {
+    let db = db().await;
+    sqlx::query_scalar(
+        "SELECT account FROM protected_names WHERE name = ? COLLATE NOCASE AND account IS NOT NULL
+         UNION ALL
+         SELECT account FROM (SELECT account FROM player_stats WHERE name = ? COLLATE NOCASE ORDER BY updated_at DESC)
+         LIMIT 1",
+    )
+    .bind(name)
+    .bind(name)
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn post_letter(
+    sender_account: &str,
+    sender_name: &str,
+    to: &str,
+    message: &str,
+) -> Result<PostResult, sqlx::Error> {
+    let Some(recipient) = account_of(to).await? else {
+        return Ok(PostResult::UnknownRecipient);
+    };
+
+    let db = db().await;
+    let now = chrono::Utc::now().timestamp();
+    let waiting: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM mail WHERE recipient = ? AND sent_at > ?")
+            .bind(&recipient)
+            .bind(now - *MAIL_EXPIRY_SECS)
+            .fetch_one(db)
+            .await?;
+    if waiting >= MAILBOX_SIZE {
+        return Ok(PostResult::MailboxFull);
+    }
+
+    sqlx::query(
+        "INSERT INTO mail (recipient, sender_account, sender_name, message, sent_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(recipient)
+    .bind(sender_account)
+    .bind(sender_name)
+    .bind(message)
+    .bind(now)
+    .execute(db)
+    .await?;
+    Ok(PostResult::Sent)
+}
+
+/// the letters waiting for an account, oldest first. they are removed, so they are only delivered once
+pub async fn take_letters(account: &str) -> Vec<Letter> {
+    let db = db().await;
+    let result: Result<Vec<(i64, String, String)>, sqlx::Error> = async {
+        sqlx::query("DELETE FROM mail WHERE sent_at <= ?")
+            .bind(chrono::Utc::now().timestamp() - *MAIL_EXPIRY_SECS)
+            .execute(db)
+            .await?;
+        let letters = sqlx::query_as::<_, (i64, String, String)>(
+            "SELECT id, sender_name, message FROM mail WHERE recipient = ? ORDER BY id",
+        )
+        .bind(account)
+        .fetch_all(db)
+        .await?;
+        if let Some((last_id, _, _)) = letters.last() {
+            sqlx::query("DELETE FROM mail WHERE recipient = ? AND id <= ?")
+                .bind(account)
+                .bind(last_id)
+                .execute(db)
+                .await?;
+        }
+        Ok(letters)
+    }
+    .await;
+
+    match result {
+        Ok(letters) => letters
+            .into_iter()
+            .map(|(_, from, message)| Letter { from, message })
+            .collect(),
+        Err(err) => {
+            error!("failed to load mail: {}", err);
+            Vec::new()
+        }
+    }
+}
@@ -1,17 +1,77 @@
+mod account_data;
+mod acks;
 mod admin_panel;
+mod api;
+mod appeal;
+mod asset_packs;
+mod cli;
+mod config;
 mod db;
+#[cfg(feature = "dev_reload")]
+mod dev_reload;
+mod digest;
+mod discord;
+mod discord_bridge;
+mod federation;
+mod flags;
 mod game_server;
+mod heatmap;
+mod links;
+mod load_governor;
+mod logs;
+mod mail;
+mod movement_recordings;
+mod name_history;
+mod restart;
+mod seashells;
+mod tips;
+mod tls;
+mod transfer;
+mod webhooks;
 
+use cli::Command;
 use tokio::sync::mpsc::channel;
+use tracing_subscriber::layer::SubscriberExt;
 
 #[tokio::main]
 async fn main() {
-    let subscriber = tracing_subscriber::fmt().with_target(false).finish();
+    let subscriber = tracing_subscriber::registry()
+        .with(logs::filter_layer())
+        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(logs::LogBuffer);
     tracing::subscriber::set_global_default(subscriber).unwrap();
 
+    let command = match Command::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    if !matches!(command, Command::Serve) {
+        if let Err(err) = command.run().await {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // fail right away if the configured certificate can't be loaded
+    tls::rustls_config().await;
+    restart::schedule();
+
     let (tx, rx) = channel(16);
     let admin_panel_task = tokio::spawn(admin_panel::run(tx));
     let game_server_task = tokio::spawn(game_server::run(rx));
+    tokio::spawn(db::run_backups());
+    tokio::spawn(federation::run());
+    tokio::spawn(restart::run());
+    tokio::spawn(webhooks::run());
+    tokio::spawn(discord_bridge::run());
+    tokio::spawn(tips::run());
+    tokio::spawn(digest::run());
+    tokio::spawn(heatmap::run());
+    tokio::spawn(movement_recordings::run());
 
     tokio::select! {
         _ = admin_panel_task => {},
@@ -0,0 +1,285 @@
+//! short recordings of how flagged players move, so a speeding or teleport flag can be checked in the
+//! admin panel before banning anyone. a flag starts one, from then on every action the player sends
+//! is kept exactly as it arrived, along with where the server had them, for `RECORDING_WINDOW`.
+//! recordings are thrown away together with the flags.
+
+use crate::{db::db, flags::FLAG_EXPIRY_SECS};
+use cibo_online::{client::ClientMessage, ClientId, Position};
+use sqlx::FromRow;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        LazyLock, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
+
+/// how long a player is recorded after being flagged
+const RECORDING_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// how often finished recordings are saved
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// how many players can be recorded at the same time, flags past that aren't recorded
+const MAX_RECORDINGS: usize = 32;
+/// how many actions and positions are kept per recording, anything after that is dropped
+const MAX_SAMPLES: usize = 20_000;
+
+struct Recording {
+    ip: Option<IpAddr>,
+    name: String,
+    reason: &'static str,
+    started: Instant,
+    started_at: i64,
+    /// raw action messages and when they arrived
+    actions: Vec<(u64, Vec<u8>)>,
+    /// where the server had the player, whenever it changed
+    positions: Vec<(u64, Position)>,
+}
+
+impl Recording {
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+}
+
+static RECORDINGS: LazyLock<Mutex<HashMap<ClientId, Recording>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// the number of running recordings, so actions of everyone else don't have to wait for the lock
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+/// start recording a client, unless it already is
+pub fn start(client: ClientId, ip: Option<IpAddr>, name: String, reason: &'static str) {
+    let mut recordings = RECORDINGS.lock().unwrap();
+    if recordings.contains_key(&client) {
+        return;
+    }
+    if recordings.len() >= MAX_RECORDINGS {
+        warn!(
+            "not recording the movement of {} after a {} flag, too many recordings are running",
+            name, reason
+        );
+        return;
+    }
+    info!("recording the movement of {} after a {} flag", name, reason);
+    recordings.insert(
+        client,
+        Recording {
+            ip,
+            name,
+            reason,
+            started: Instant::now(),
+            started_at: chrono::Utc::now().timestamp(),
+            actions: Vec::new(),
+            positions: Vec::new(),
+        },
+    );
+    RUNNING.store(recordings.len(), Ordering::Relaxed);
+}
+
+/// the clients being recorded right now
+pub fn recorded_clients() -> Vec<ClientId> {
+    if RUNNING.load(Ordering::Relaxed) == 0 {
+        return Vec::new();
+    }
+    RECORDINGS.lock().unwrap().keys().copied().collect()
+}
+
+/// keep an action message of a client, if it is being recorded
+pub fn record_action(client: ClientId, message: &[u8]) {
+    if RUNNING.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    if let Some(recording) = RECORDINGS.lock().unwrap().get_mut(&client) {
+        if recording.actions.len() < MAX_SAMPLES {
+            let at = recording.elapsed_ms();
+            recording.actions.push((at, message.to_vec()));
+        }
+    }
+}
+
+/// where the server has the recorded clients after a tick
+pub fn record_positions(positions: Vec<(ClientId, Position)>) {
+    let mut recordings = RECORDINGS.lock().unwrap();
+    for (client, position) in positions {
+        let Some(recording) = recordings.get_mut(&client) else {
+            continue;
+        };
+        if recording.positions.len() < MAX_SAMPLES
+            && recording.positions.last().map(|(_, last)| *last) != Some(position)
+        {
+            let at = recording.elapsed_ms();
+            recording.positions.push((at, position));
+        }
+    }
+}
+
+/// stop recording a client that left
+pub async fn finish(client: ClientId) {
+    let recording = {
+        let mut recordings = RECORDINGS.lock().unwrap();
+        let recording = recordings.remove(&client);
+        RUNNING.store(recordings.len(), Ordering::Relaxed);
+        recording
+    };
+    if let Some(recording) = recording {
+        save(recording).await;
+    }
+}
+
+async fn save(recording: Recording) {
+    let now = chrono::Utc::now().timestamp();
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = db().await.begin().await?;
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO movement_recordings (ip, name, reason, started_at) VALUES (?, ?, ?, ?)
+             RETURNING id",
+        )
+        .bind(recording.ip.map(|ip| ip.to_string()))
+        .bind(&recording.name)
+        .bind(recording.reason)
+        .bind(recording.started_at)
+        .fetch_one(&mut *tx)
+        .await?;
+        for (at, action) in &recording.actions {
+            sqlx::query(
+                "INSERT INTO movement_samples (recording_id, at_ms, action) VALUES (?, ?, ?)",
+            )
+            .bind(id)
+            .bind(*at as i64)
+            .bind(action)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for (at, position) in &recording.positions {
+            sqlx::query(
+                "INSERT INTO movement_samples (recording_id, at_ms, x, y) VALUES (?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(*at as i64)
+            .bind(position.x)
+            .bind(position.y)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(
+            "DELETE FROM movement_samples WHERE recording_id IN
+             (SELECT id FROM movement_recordings WHERE started_at <= ?)",
+        )
+        .bind(now - *FLAG_EXPIRY_SECS)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM movement_recordings WHERE started_at <= ?")
+            .bind(now - *FLAG_EXPIRY_SECS)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+
+    if let Err(err) = result {
+        error!(
+            "failed to save the movement recording of {}: {}",
+            recording.name, err
+        );
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct RecordingInfo {
+    pub id: i64,
+    pub ip: Option<String>,
+    pub name: String,
+    pub reason: String,
+    pub started_at: i64,
+    /// until the last sample, in ms
+    pub length: i64,
+    pub actions: i64,
+}
+
+/// the saved recordings, newest first
+pub async fn load_recordings() -> Result<Vec<RecordingInfo>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT r.id, r.ip, r.name, r.reason, r.started_at,
+                COALESCE(MAX(s.at_ms), 0) AS length, COUNT(s.action) AS actions
+         FROM movement_recordings r LEFT JOIN movement_samples s ON s.recording_id = r.id
+         GROUP BY r.id ORDER BY r.started_at DESC",
+    )
+    .fetch_all(db().await)
+    .await
+}
+
+/// a recording as the moves the client claimed and the positions the server had, both with the
+/// time in ms since the start
+pub struct Replay {
+    pub info: RecordingInfo,
+    pub claimed: Vec<(u64, Position)>,
+    pub server: Vec<(u64, Position)>,
+}
+
+pub async fn load_replay(id: i64) -> Result<Option<Replay>, sqlx::Error> {
+    let Some(info) = load_recordings()
+        .await?
+        .into_iter()
+        .find(|recording| recording.id == id)
+    else {
+        return Ok(None);
+    };
+
+    let samples: Vec<(i64, Option<Vec<u8>>, Option<i64>, Option<i64>)> = sqlx::query_as(
+        "SELECT at_ms, action, x, y FROM movement_samples WHERE recording_id = ? ORDER BY at_ms",
+    )
+    .bind(id)
+    .fetch_all(db().await)
+    .await?;
+
+    let mut claimed = Vec::new();
+    let mut server = Vec::new();
+    for (at, action, x, y) in samples {
+        let at = at as u64;
+        match (action, x, y) {
+            (Some(action), _, _) => {
+                // actions that don't move the player are kept too, they just don't show up here
+                if let Ok(ClientMessage::Action(action)) = ClientMessage::from_bytes(&action) {
+                    if let Some(position) = action.moved_to() {
+                        claimed.push((at, position));
+                    }
+                }
+            }
+            (None, Some(x), Some(y)) => server.push((at, Position::new(x, y))),
+            _ => {}
+        }
+    }
+
+    Ok(Some(Replay {
+        info,
+        claimed,
+        server,
+    }))
+}
+
+pub async fn run() {
+    let mut interval = tokio::time::interval(SAVE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let finished = {
+            let mut recordings = RECORDINGS.lock().unwrap();
+            let done = recordings
+                .iter()
+                .filter(|(_, recording)| recording.started.elapsed() >= RECORDING_WINDOW)
+                .map(|(client, _)| *client)
+                .collect::<Vec<_>>();
+            let finished = done
+                .into_iter()
+                .filter_map(|client| recordings.remove(&client))
+                .collect::<Vec<_>>();
+            RUNNING.store(recordings.len(), Ordering::Relaxed);
+            finished
+        };
+        for recording in finished {
+            save(recording).await;
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! registered players can change their name with `/rename` while playing. every change is kept
+//! with the old name, so moderators can still tell who someone was. players have to wait
+//! `RENAME_COOLDOWN_DAYS` days (7 by default) between changes.
+
+use crate::db::{db, env_or};
+use std::{net::IpAddr, sync::LazyLock};
+
+static RENAME_COOLDOWN_SECS: LazyLock<i64> =
+    LazyLock::new(|| env_or("RENAME_COOLDOWN_DAYS", 7i64) * 24 * 60 * 60);
+
+/// how many seconds the account still has to wait before it can change its name again
+pub async fn cooldown_remaining(account: &str) -> Result<Option<i64>, sqlx::Error> {
+    let last: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(changed_at) FROM name_history WHERE account = ?")
+            .bind(account)
+            .fetch_one(db().await)
+            .await?;
+    let now = chrono::Utc::now().timestamp();
+    Ok(last
+        .map(|last| last + *RENAME_COOLDOWN_SECS - now)
+        .filter(|remaining| *remaining > 0))
+}
+
+pub async fn record_rename(
+    account: &str,
+    ip: IpAddr,
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO name_history (account, old_name, new_name, ip, changed_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(account)
+    .bind(old_name)
+    .bind(new_name)
+    .bind(ip.to_string())
+    .bind(chrono::Utc::now().timestamp())
+    .execute(db().await)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! scheduled restarts, so players get a warning instead of being disconnected out of nowhere.
+//! enabled by a `[restart]` section in the config. the schedule is a cron expression in UTC,
+//! starting with the seconds:
+//!
+//! ```toml
+//! [restart]
+//! schedule = "0 0 5 * * *"
+//! ```
+//!
+//! the server only saves the world and exits, something like systemd or docker has to start it again.
+
+use crate::{config::CONFIG, db, digest, game_server};
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serde::Deserialize;
+use std::{
+    str::FromStr,
+    sync::{LazyLock, OnceLock},
+};
+use tracing::{error, info, warn};
+
+/// minutes before the restart at which everyone is warned
+const WARNINGS: [i64; 3] = [10, 5, 1];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestartConfig {
+    /// cron expression, e.g. `0 0 5 * * *` for every day at 5:00
+    schedule: String,
+}
+
+static STARTED_AT: OnceLock<DateTime<Utc>> = OnceLock::new();
+
+/// the parsed schedule, or `None` if restarts aren't configured
+pub fn schedule() -> Option<&'static Schedule> {
+    static SCHEDULE: LazyLock<Option<Schedule>> = LazyLock::new(|| {
+        let restart = CONFIG.restart.as_ref()?;
+        let schedule = Schedule::from_str(&restart.schedule).unwrap_or_else(|err| {
+            panic!("invalid restart schedule '{}': {}", restart.schedule, err)
+        });
+        Some(schedule)
+    });
+    SCHEDULE.as_ref()
+}
+
+pub fn uptime() -> chrono::Duration {
+    STARTED_AT
+        .get()
+        .map(|started_at| Utc::now() - *started_at)
+        .unwrap_or_default()
+}
+
+pub fn next_restart() -> Option<DateTime<Utc>> {
+    schedule()?.upcoming(Utc).next()
+}
+
+async fn sleep_until(time: DateTime<Utc>) {
+    tokio::time::sleep((time - Utc::now()).to_std().unwrap_or_default()).await;
+}
+
+pub async fn run() {
+    STARTED_AT.get_or_init(Utc::now);
+
+    if schedule().is_none() {
+        info!("scheduled restarts are disabled");
+        return;
+    }
+    let Some(restart_at) = next_restart() else {
+        warn!("the restart schedule never matches, not restarting");
+        return;
+    };
+    info!(
+        "restarting at {}",
+        restart_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+
+    for minutes in WARNINGS {
+        let warn_at = restart_at - chrono::Duration::minutes(minutes);
+        // started right before the restart, the earlier warnings are already over
+        if warn_at < Utc::now() {
+            continue;
+        }
+        sleep_until(warn_at).await;
+        game_server::announce(format!(
+            "the server restarts in {} minute{}!",
+            minutes,
+            if minutes == 1 { "" } else { "s" }
+        ));
+    }
+    sleep_until(restart_at).await;
+
+    info!("restarting as scheduled");
+    game_server::announce("restarting now, see you in a bit!".to_string());
+    game_server::shutdown_notice().await;
+    game_server::save_world().await;
+    digest::save().await;
+    match db::backup().await {
+        Ok(path) => info!("backed up database to {}", path.display()),
+        Err(err) => error!("failed to back up database: {}", err),
+    }
+    std::process::exit(0);
+}
@@ -0,0 +1,123 @@
+//! seashells, the currency players earn in minigames and spend at shops. balances and purchases
+//! are stored per account and every change to a balance is written to `seashell_transactions`
+//! along with the balance after it, so they can be checked later.
+
+use crate::db::db;
+use cibo_online::ShopItem;
+use tracing::error;
+
+pub enum BuyResult {
+    Bought,
+    NotEnough,
+    AlreadyOwned,
+}
+
+/// the seashells of an account and the items it bought
+pub async fn load_wallet(account: &str) -> (u64, Vec<ShopItem>) {
+    let db = db().await;
+    let result: Result<_, sqlx::Error> = async {
+        let balance: Option<i64> =
+            sqlx::query_scalar("SELECT balance FROM seashells WHERE account = ?")
+                .bind(account)
+                .fetch_optional(db)
+                .await?;
+        let items: Vec<String> =
+            sqlx::query_scalar("SELECT item FROM shop_purchases WHERE account = ?")
+                .bind(account)
+                .fetch_all(db)
+                .await?;
+        Ok((balance, items))
+    }
+    .await;
+
+    match result {
+        Ok((balance, items)) => {
+            // items that were taken out of the shop are still in the table, but can't be used
+            let owned = items
+                .iter()
+                .filter_map(|item| ShopItem::from_key(item))
+                .collect();
+            (balance.unwrap_or(0).max(0) as u64, owned)
+        }
+        Err(err) => {
+            error!("failed to load seashells: {}", err);
+            (0, Vec::new())
+        }
+    }
+}
+
+/// add seashells to an account
+pub async fn earn(account: &str, amount: u64, reason: &str) -> Result<(), sqlx::Error> {
+    let mut tx = db().await.begin().await?;
+    let balance: i64 = sqlx::query_scalar(
+        "INSERT INTO seashells (account, balance) VALUES (?, ?)
+         ON CONFLICT (account) DO UPDATE SET balance = balance + excluded.balance
+         RETURNING balance",
+    )
+    .bind(account)
+    .bind(amount as i64)
+    .fetch_one(&mut *tx)
+    .await?;
+    log_transaction(&mut tx, account, amount as i64, reason, balance).await?;
+    tx.commit().await
+}
+
+/// take the price of an item from an account and hand it the item, if it can afford it
+pub async fn buy(account: &str, item: ShopItem) -> Result<BuyResult, sqlx::Error> {
+    let mut tx = db().await.begin().await?;
+    let owned: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM shop_purchases WHERE account = ? AND item = ?")
+            .bind(account)
+            .bind(item.key())
+            .fetch_one(&mut *tx)
+            .await?;
+    if owned > 0 {
+        return Ok(BuyResult::AlreadyOwned);
+    }
+
+    let price = item.price() as i64;
+    let Some(balance) = sqlx::query_scalar::<_, i64>(
+        "UPDATE seashells SET balance = balance - ? WHERE account = ? AND balance >= ?
+         RETURNING balance",
+    )
+    .bind(price)
+    .bind(account)
+    .bind(price)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(BuyResult::NotEnough);
+    };
+
+    sqlx::query("INSERT INTO shop_purchases (account, item, purchased_at) VALUES (?, ?, ?)")
+        .bind(account)
+        .bind(item.key())
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&mut *tx)
+        .await?;
+    let reason = format!("bought {}", item.key());
+    log_transaction(&mut tx, account, -price, &reason, balance).await?;
+    tx.commit().await?;
+    Ok(BuyResult::Bought)
+}
+
+async fn log_transaction(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    account: &str,
+    amount: i64,
+    reason: &str,
+    balance: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO seashell_transactions (account, amount, reason, balance, created_at)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(account)
+    .bind(amount)
+    .bind(reason)
+    .bind(balance)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! tips written in the admin panel, one of them is shown in the ticker every `TIP_MINUTES` minutes
+//! (10 by default, 0 turns them off). tips with a higher weight come up more often, and tips for a
+//! special event are only picked while it is active.
+
+use crate::{
+    db::{db, env_or},
+    game_server,
+};
+use cibo_online::server::SpecialEvent;
+use rand::{distributions::WeightedIndex, prelude::Distribution};
+use sqlx::FromRow;
+use std::{sync::LazyLock, time::Duration};
+use tracing::{error, info, instrument};
+
+static TIP_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_or("TIP_MINUTES", 10u64) * 60));
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Tip {
+    pub id: i64,
+    pub text: String,
+    pub weight: i64,
+    /// name of the special event the tip belongs to
+    pub event: Option<String>,
+}
+
+impl Tip {
+    /// whether the tip can come up right now
+    fn is_current(&self) -> bool {
+        match &self.event {
+            None => true,
+            Some(event) => {
+                SpecialEvent::from_name(event).map_or(false, game_server::get_special_event)
+            }
+        }
+    }
+}
+
+pub async fn load_tips() -> Result<Vec<Tip>, sqlx::Error> {
+    let db = db().await;
+    sqlx::query_as("SELECT id, text, weight, event FROM tips ORDER BY id")
+        .fetch_all(db)
+        .await
+}
+
+#[instrument(name = "tips")]
+pub async fn run() {
+    if TIP_INTERVAL.is_zero() {
+        return;
+    }
+    info!(
+        "showing a tip every {} minutes",
+        TIP_INTERVAL.as_secs() / 60
+    );
+
+    let mut last_tip = None;
+    let mut interval = tokio::time::interval(*TIP_INTERVAL);
+    // the first tick fires right away, nobody is online yet to see it
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        if game_server::get_queue_stats().0 == 0 {
+            continue;
+        }
+
+        let tips = match load_tips().await {
+            Ok(tips) => tips,
+            Err(err) => {
+                error!("failed to load tips: {}", err);
+                continue;
+            }
+        };
+        let mut candidates = tips
+            .into_iter()
+            .filter(|tip| tip.weight > 0 && tip.is_current())
+            .collect::<Vec<_>>();
+        // don't show the same tip twice in a row unless it's the only one
+        if candidates.len() > 1 {
+            candidates.retain(|tip| Some(tip.id) != last_tip);
+        }
+
+        let Ok(weights) = WeightedIndex::new(candidates.iter().map(|tip| tip.weight)) else {
+            continue;
+        };
+        let tip = &candidates[weights.sample(&mut rand::thread_rng())];
+        last_tip = Some(tip.id);
+        game_server::show_tip(tip.text.clone());
+    }
+}
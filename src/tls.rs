@@ -0,0 +1,89 @@
+//! optional tls termination for the game server and the admin panel, so small deployments
+//! don't need a reverse proxy just for wss://. enabled by a `[tls]` section in the config:
+//!
+//! ```toml
+//! [tls]
+//! cert = "data/cert.pem"
+//! key = "data/key.pem"
+//! ```
+//!
+//! the certificate is reloaded when one of the files changes, e.g. after a renewal.
+
+use crate::config::CONFIG;
+use axum_server::tls_rustls::RustlsConfig;
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+/// how often the certificate files are checked for changes
+const RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TlsConfig {
+    /// pem encoded certificate chain
+    cert: PathBuf,
+    /// pem encoded private key
+    key: PathBuf,
+}
+
+/// the rustls config shared by both listeners, or `None` if tls isn't configured
+pub async fn rustls_config() -> Option<RustlsConfig> {
+    static RUSTLS_CONFIG: OnceCell<Option<RustlsConfig>> = OnceCell::const_new();
+    RUSTLS_CONFIG
+        .get_or_init(|| async {
+            let tls = CONFIG.tls.as_ref()?;
+
+            // reqwest already pulls in ring, so use it for the server side too
+            let _ = rustls::crypto::ring::default_provider().install_default();
+
+            let rustls_config = RustlsConfig::from_pem_file(&tls.cert, &tls.key)
+                .await
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "failed to load certificate {} / {}: {}",
+                        tls.cert.display(),
+                        tls.key.display(),
+                        err
+                    )
+                });
+            tokio::spawn(watch_certificate(tls.clone(), rustls_config.clone()));
+
+            Some(rustls_config)
+        })
+        .await
+        .clone()
+}
+
+async fn modified(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+async fn watch_certificate(tls: TlsConfig, rustls_config: RustlsConfig) {
+    let mut last_modified = (modified(&tls.cert).await, modified(&tls.key).await);
+
+    let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let current = (modified(&tls.cert).await, modified(&tls.key).await);
+        if current == last_modified {
+            continue;
+        }
+
+        // keep serving the old certificate if the new one is broken or only half written
+        match rustls_config
+            .reload_from_pem_file(&tls.cert, &tls.key)
+            .await
+        {
+            Ok(()) => {
+                info!("reloaded certificate {}", tls.cert.display());
+                last_modified = current;
+            }
+            Err(err) => warn!("failed to reload certificate: {}", err),
+        }
+    }
+}
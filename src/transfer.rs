@@ -0,0 +1,79 @@
+//! "continue on another device". a player asks for a code with `/transfer` and opens the game
+//! with it somewhere else, the new connection then takes over their name, account and spot in the
+//! world while the old one is disconnected. codes work once and run out after `CODE_LIFETIME`.
+
+use cibo_online::ClientId;
+use rand::Rng;
+use std::{
+    net::IpAddr,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+const CODE_LIFETIME: Duration = Duration::from_secs(5 * 60);
+const CODE_LENGTH: usize = 8;
+/// no 0/O or 1/I, so the code can be typed off another screen
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// the session a code was made for
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub client: ClientId,
+    pub ip: Option<IpAddr>,
+    pub account: Option<String>,
+    pub name: String,
+}
+
+struct PendingTransfer {
+    code: String,
+    session: Session,
+    created_at: Instant,
+}
+
+static TRANSFERS: LazyLock<Mutex<Vec<PendingTransfer>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// a new code for the session, replacing the one it got earlier
+pub fn create(session: Session) -> String {
+    let mut rng = rand::thread_rng();
+    let code = (0..CODE_LENGTH)
+        .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+        .collect::<String>();
+
+    let mut transfers = TRANSFERS.lock().unwrap();
+    transfers.retain(|transfer| {
+        transfer.session.client != session.client && transfer.created_at.elapsed() < CODE_LIFETIME
+    });
+    transfers.push(PendingTransfer {
+        code: code.clone(),
+        session,
+        created_at: Instant::now(),
+    });
+    code
+}
+
+/// whether someone on this ip is waiting for their other device, which gets let in even though
+/// there is only one connection per ip
+pub fn expected_from(ip: IpAddr) -> bool {
+    TRANSFERS.lock().unwrap().iter().any(|transfer| {
+        transfer.session.ip == Some(ip) && transfer.created_at.elapsed() < CODE_LIFETIME
+    })
+}
+
+/// the session a code was made for, the code can't be used again afterwards
+pub fn redeem(code: &str) -> Option<Session> {
+    let code = code.trim().to_uppercase();
+    let mut transfers = TRANSFERS.lock().unwrap();
+    let index = transfers
+        .iter()
+        .position(|transfer| transfer.code == code)?;
+    let transfer = transfers.swap_remove(index);
+    (transfer.created_at.elapsed() < CODE_LIFETIME).then_some(transfer.session)
+}
+
+/// the client left, so there is nothing to continue anymore
+pub fn forget(client: ClientId) {
+    TRANSFERS
+        .lock()
+        .unwrap()
+        .retain(|transfer| transfer.session.client != client);
+}
@@ -0,0 +1,164 @@
+//! optional webhooks that pass public chat and events on to stream overlays and chat bridges.
+//! configured by a `[webhooks]` section in the config:
+//!
+//! ```toml
+//! [webhooks]
+//! urls = ["https://overlay.example.com/cibo"]
+//! secret = "..."
+//! ```
+//!
+//! every event is posted as json to all urls. with a secret, requests are signed so receivers can
+//! check that they really came from this server, see `cibo_online::webhook` for the format.
+
+use crate::config::CONFIG;
+use cibo_online::{server::SpecialEvent, webhook};
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc;
+use tracing::{info, instrument, warn};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// events waiting to be posted, anything past that is dropped while the webhooks are slow
+const EVENT_QUEUE: usize = 256;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    urls: Vec<String>,
+    /// requests aren't signed without one
+    secret: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Chat {
+        name: String,
+        message: String,
+        /// set if the player linked their discord account
+        #[serde(skip_serializing_if = "Option::is_none")]
+        discord_id: Option<String>,
+    },
+    SpecialEvent {
+        event: &'static str,
+        active: bool,
+    },
+    Announcement {
+        message: String,
+    },
+}
+
+static OUTGOING: OnceLock<mpsc::Sender<WebhookEvent>> = OnceLock::new();
+/// events dropped because the queue was full, logged once it drains again
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// post an event to all webhooks. does nothing if there aren't any
+pub fn send(event: WebhookEvent) {
+    if let Some(tx) = OUTGOING.get() {
+        if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send(event) {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn chat(name: String, message: String, discord_id: Option<String>) {
+    send(WebhookEvent::Chat {
+        name,
+        message,
+        discord_id,
+    });
+}
+
+pub fn special_event(event: SpecialEvent, active: bool) {
+    send(WebhookEvent::SpecialEvent {
+        event: event.name(),
+        active,
+    });
+}
+
+pub fn announcement(message: String) {
+    send(WebhookEvent::Announcement { message });
+}
+
+#[instrument(name = "webhooks")]
+pub async fn run() {
+    let Some(config) = CONFIG
+        .webhooks
+        .as_ref()
+        .filter(|config| !config.urls.is_empty())
+    else {
+        return;
+    };
+    info!(
+        "sending {} webhooks to {} urls",
+        if config.secret.is_some() {
+            "signed"
+        } else {
+            "unsigned"
+        },
+        config.urls.len()
+    );
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap();
+
+    let (tx, mut rx) = mpsc::channel(EVENT_QUEUE);
+    OUTGOING.set(tx).ok();
+
+    while let Some(event) = rx.recv().await {
+        let dropped = DROPPED.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!(
+                "dropped {} webhook events, the webhooks can't keep up",
+                dropped
+            );
+        }
+
+        let body = match serde_json::to_vec(&event) {
+            Ok(body) => body,
+            Err(err) => {
+                warn!("failed to serialize webhook event {:?}: {}", event, err);
+                continue;
+            }
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let signature = config
+            .secret
+            .as_ref()
+            .map(|secret| webhook::sign(secret.as_bytes(), timestamp, &body));
+
+        // posted to every url at once, so one that is down doesn't hold up the others
+        futures::future::join_all(config.urls.iter().map(|url| {
+            let mut request = client
+                .post(url)
+                .header("content-type", "application/json")
+                .header(webhook::TIMESTAMP_HEADER, timestamp)
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header(webhook::SIGNATURE_HEADER, signature);
+            }
+
+            async move {
+                let result = request
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(err) = result {
+                    warn!("failed to reach webhook {}: {}", url, err);
+                }
+            }
+        }))
+        .await;
+    }
+}
@@ -1,9 +1,14 @@
 document.body.addEventListener('htmx:configRequest', function (evt) {
     switch (evt.detail.path) {
         case '/chat_log':
+        case '/connections':
             evt.detail.parameters['offset'] = new Date().getTimezoneOffset();
             break;
         default:
             break;
     }
-});
\ No newline at end of file
+});
+document.body.addEventListener('change', async function (evt) {
+    if (evt.target.id !== 'WordFile' || evt.target.files.length === 0) return;
+    document.getElementById('WordList').value = await evt.target.files[0].text();
+});
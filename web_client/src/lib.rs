@@ -1,19 +1,27 @@
+mod stats;
+use stats::{latency_line, NetStats};
+
+mod alloc_counter;
+
+#[cfg(feature = "net_sim")]
+use client_core::net_sim::{NetSim, NetSimConfig};
+
 use std::{cell::RefCell, rc::Rc};
 
 use cibo_online::{
-    client::{ClientGameState, ClientMessage},
-    server::ServerMessage,
+    client::{controls::Action, LatencyStage},
+    Critter,
 };
+use client_core::{action_pressed, Frontend, Platform};
 use monos_gfx::{
-    image::SliceReader,
-    input::{Input, Key, KeyEvent, KeyState, RawKey},
-    text::font,
-    ui::{self, widgets::Textbox, UIFrame},
-    Color, Dimension, Framebuffer, FramebufferFormat, Image, Position, Rect,
+    input::{Key, KeyEvent, KeyState, RawKey},
+    Dimension, Framebuffer, FramebufferFormat, Position,
 };
 use wasm_bindgen::prelude::*;
 use web_sys::{ErrorEvent, MessageEvent, WebSocket};
 
+type EventHandler<E> = Closure<dyn FnMut(E)>;
+
 /// create a new static framebuffer
 fn raw_fb() -> &'static mut Vec<u8> {
     let fb = Box::new(Vec::new());
@@ -46,17 +54,166 @@ struct Game {
 
 // everything we don't want to pass to JS
 struct LocalState {
-    ws: WebSocket,
-    game_state: Rc<RefCell<Option<ClientGameState>>>,
+    frontend: Rc<RefCell<Frontend>>,
+    platform: Rc<RefCell<WebPlatform>>,
+    show_stats: bool,
+    /// frames per second to draw at most, 0 draws every frame the page asks for
+    target_fps: u32,
+    /// time passed since the last frame that was run
+    pending_ms: f32,
+}
+
+/// how often the game keeps running while it is hidden
+const LOW_POWER_FPS: u32 = 10;
+/// frame callbacks jitter a bit, a frame this close to being due isn't held back
+const FRAME_SLACK_MS: f32 = 2.0;
+
+/// websocket to the server. with the `net_sim` feature, messages in both directions go through the network simulation first
+struct Connection {
+    url: String,
+    ws: Option<WebSocket>,
+    /// kept alive for as long as the websocket is, dropping them would leave the socket calling freed closures
+    handlers: Option<SocketHandlers>,
+    /// the frontend asked for a new connection, opened once it is done with the platform
+    reconnect: bool,
+    #[cfg(feature = "net_sim")]
+    net_sim: Rc<RefCell<NetSim>>,
+}
+
+#[allow(dead_code)]
+struct SocketHandlers {
+    on_open: EventHandler<JsValue>,
+    on_message: EventHandler<MessageEvent>,
+    on_close: EventHandler<JsValue>,
+    on_error: EventHandler<ErrorEvent>,
+}
+
+impl Connection {
+    fn send(&self, bytes: &[u8]) {
+        #[cfg(feature = "net_sim")]
+        self.net_sim
+            .borrow_mut()
+            .send(js_sys::Date::now(), bytes.to_vec());
+        #[cfg(not(feature = "net_sim"))]
+        self.send_now(bytes);
+    }
+
+    fn send_now(&self, bytes: &[u8]) {
+        let Some(ws) = &self.ws else {
+            return;
+        };
+        if let Err(e) = ws.send_with_u8_array(bytes) {
+            console_log!("failed to send message: {:?}", e);
+        }
+    }
+
+    /// detach the handlers before closing, so the old socket can't report back anymore
+    fn close(&mut self) {
+        if let Some(ws) = self.ws.take() {
+            ws.set_onopen(None);
+            ws.set_onmessage(None);
+            ws.set_onclose(None);
+            ws.set_onerror(None);
+            let _ = ws.close();
+        }
+        self.handlers = None;
+    }
+}
+
+/// the browser side of the game: local storage, the page url and the websocket
+struct WebPlatform {
+    connection: Connection,
+    stats: NetStats,
+    audio: Option<web_sys::AudioContext>,
+    /// called with the url of links clicked in game, the page decides how to open them
+    link_handler: Option<js_sys::Function>,
+    /// responses to `Platform::fetch` that arrived, handed to the frontend on the next frame
+    fetched: Rc<RefCell<Vec<(String, Vec<u8>)>>>,
+}
+
+impl Platform for WebPlatform {
+    fn connect(&mut self) {
+        self.connection.reconnect = true;
+    }
+
+    fn send(&mut self, bytes: &[u8]) {
+        self.connection.send(bytes);
+    }
+
+    fn load(&self, key: &str) -> Option<String> {
+        local_storage()?.get_item(key).ok()?
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        if let Some(storage) = local_storage() {
+            if let Err(e) = storage.set_item(key, value) {
+                console_log!("failed to save {}: {:?}", key, e);
+            }
+        }
+    }
+
+    fn param(&self, name: &str) -> Option<String> {
+        url_param(name)
+    }
+
+    fn random(&mut self) -> f64 {
+        js_sys::Math::random()
+    }
+
+    fn secure_random(&mut self, bytes: &mut [u8]) {
+        getrandom::getrandom(bytes).expect("the browser has no secure random numbers");
+    }
+
+    fn log(&mut self, message: &str) {
+        log(message);
+    }
+
+    fn alert(&mut self, message: &str) {
+        alert(message);
+    }
+
+    fn play_ping(&mut self) {
+        play_ping(&mut self.audio);
+    }
+
+    fn play_ambient(&mut self, critter: Critter, volume: f32) {
+        play_ambient(&mut self.audio, critter, volume);
+    }
+
+    fn open_link(&mut self, url: &str) {
+        if let Some(handler) = &self.link_handler {
+            if let Err(err) = handler.call1(&JsValue::NULL, &JsValue::from_str(url)) {
+                console_log!("failed to open link: {:?}", err);
+            }
+        }
+    }
 
-    input: Rc<RefCell<Input>>,
-    ui_frame: UIFrame,
-    name_input: String,
-    game_logo: Image,
+    fn fetch(&mut self, path: &str) {
+        let path = path.to_string();
+        let fetched = self.fetched.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            match fetch_bytes(&path).await {
+                Ok(body) => fetched.borrow_mut().push((path, body)),
+                Err(e) => console_log!("failed to fetch {}: {:?}", path, e),
+            }
+        });
+    }
+
+    fn message_sent(&mut self, kind: &'static str, bytes: usize) {
+        self.stats.record_sent(kind, bytes);
+    }
+
+    fn message_received(&mut self, kind: &'static str, bytes: usize) {
+        self.stats.record_received(kind, bytes);
+    }
 }
 
-fn js_key_to_key(key: &str) -> Option<(Key, bool)> {
+fn js_key_to_key(key: &str, ctrl: bool) -> Option<(Key, bool)> {
     let key = match key {
+        // ctrl+z, ctrl+y and ctrl+b are passed on as their ascii control characters
+        "z" | "Z" if ctrl => Some(Key::Unicode('\u{1a}')),
+        "y" | "Y" if ctrl => Some(Key::Unicode('\u{19}')),
+        "b" | "B" if ctrl => Some(Key::Unicode('\u{2}')),
         "ArrowUp" => Some(Key::RawKey(RawKey::ArrowUp)),
         "ArrowDown" => Some(Key::RawKey(RawKey::ArrowDown)),
         "ArrowLeft" => Some(Key::RawKey(RawKey::ArrowLeft)),
@@ -66,6 +223,14 @@ fn js_key_to_key(key: &str) -> Option<(Key, bool)> {
         "Enter" => Some(Key::RawKey(RawKey::Return)),
         "Tab" => Some(Key::RawKey(RawKey::Tab)),
         "F1" => Some(Key::RawKey(RawKey::F1)),
+        "F2" => Some(Key::RawKey(RawKey::F2)),
+        "F3" => Some(Key::RawKey(RawKey::F3)),
+        "F4" => Some(Key::RawKey(RawKey::F4)),
+        "F6" => Some(Key::RawKey(RawKey::F6)),
+        "F7" => Some(Key::RawKey(RawKey::F7)),
+        "F8" => Some(Key::RawKey(RawKey::F8)),
+        "F9" => Some(Key::RawKey(RawKey::F9)),
+        "F10" => Some(Key::RawKey(RawKey::F10)),
         other if other.len() == 1 => {
             let char = other.chars().next().unwrap();
             Some(Key::Unicode(char))
@@ -75,12 +240,199 @@ fn js_key_to_key(key: &str) -> Option<(Key, bool)> {
 
     let prevent_default = match key {
         Some(Key::RawKey(_)) => true,
+        Some(Key::Unicode(c)) if c.is_ascii_control() => true,
         _ => false,
     };
 
     key.map(|key| (key, prevent_default))
 }
 
+/// short beep for when the player gets mentioned
+fn play_ping(audio: &mut Option<web_sys::AudioContext>) {
+    let result: Result<(), JsValue> = (|| {
+        if audio.is_none() {
+            *audio = Some(web_sys::AudioContext::new()?);
+        }
+        let ctx = audio.as_ref().unwrap();
+        let oscillator = ctx.create_oscillator()?;
+        let gain = ctx.create_gain()?;
+        oscillator.frequency().set_value(880.0);
+        gain.gain().set_value(0.1);
+        gain.gain()
+            .exponential_ramp_to_value_at_time(0.001, ctx.current_time() + 0.3)?;
+
+        oscillator.connect_with_audio_node(&gain)?;
+        gain.connect_with_audio_node(&ctx.destination())?;
+        oscillator.start()?;
+        oscillator.stop_with_when(ctx.current_time() + 0.3)?;
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        console_log!("failed to play ping: {:?}", e);
+    }
+}
+
+/// a short synthesized call, a falling squawk for seagulls and a few quick chirps for crickets
+fn play_ambient(audio: &mut Option<web_sys::AudioContext>, critter: Critter, volume: f32) {
+    // (start, length, start frequency, end frequency) of each tone
+    let tones: &[(f64, f64, f32, f32)] = match critter {
+        Critter::Seagull => &[(0.0, 0.25, 1400.0, 700.0), (0.3, 0.2, 1300.0, 800.0)],
+        Critter::Cricket => &[
+            (0.0, 0.04, 4200.0, 4200.0),
+            (0.07, 0.04, 4200.0, 4200.0),
+            (0.14, 0.04, 4200.0, 4200.0),
+        ],
+        Critter::Butterfly => return,
+    };
+
+    let result: Result<(), JsValue> = (|| {
+        if audio.is_none() {
+            *audio = Some(web_sys::AudioContext::new()?);
+        }
+        let ctx = audio.as_ref().unwrap();
+        for (start, length, from, to) in tones {
+            let start = ctx.current_time() + start;
+            let oscillator = ctx.create_oscillator()?;
+            let gain = ctx.create_gain()?;
+            oscillator.frequency().set_value_at_time(*from, start)?;
+            oscillator
+                .frequency()
+                .linear_ramp_to_value_at_time(*to, start + length)?;
+            // quieter than the ping, it's just background
+            gain.gain().set_value_at_time(0.03 * volume, start)?;
+            gain.gain()
+                .exponential_ramp_to_value_at_time(0.001, start + length)?;
+
+            oscillator.connect_with_audio_node(&gain)?;
+            gain.connect_with_audio_node(&ctx.destination())?;
+            oscillator.start_with_when(start)?;
+            oscillator.stop_with_when(start + length)?;
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        console_log!("failed to play ambience: {:?}", e);
+    }
+}
+
+/// body of a file on the server the page was loaded from
+async fn fetch_bytes(path: &str) -> Result<Vec<u8>, JsValue> {
+    let window = web_sys::window().ok_or("no window")?;
+    let response: web_sys::Response =
+        wasm_bindgen_futures::JsFuture::from(window.fetch_with_str(path))
+            .await?
+            .dyn_into()?;
+    if !response.ok() {
+        return Err(JsValue::from(response.status()));
+    }
+    let buffer = wasm_bindgen_futures::JsFuture::from(response.array_buffer()?).await?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// value of a `?name=value` parameter in the page url
+fn url_param(name: &str) -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|param| param.strip_prefix(name)?.strip_prefix('='))
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+}
+
+/// pass on the messages that have made it through the simulated network
+#[cfg(feature = "net_sim")]
+fn poll_net_sim(local_state: &LocalState) {
+    let mut platform = local_state.platform.borrow_mut();
+    let now = js_sys::Date::now();
+    let outgoing = platform.connection.net_sim.borrow_mut().take_outgoing(now);
+    for bytes in outgoing {
+        platform.connection.send_now(&bytes);
+    }
+
+    let incoming = platform.connection.net_sim.borrow_mut().take_incoming(now);
+    for bytes in incoming {
+        local_state
+            .frontend
+            .borrow_mut()
+            .handle_server_bytes(&bytes, &mut *platform);
+    }
+}
+
+/// replace the websocket with a new one, the frontend is told once it opened or closed
+fn open_connection(local_state: &LocalState) {
+    let mut platform = local_state.platform.borrow_mut();
+    platform.connection.reconnect = false;
+    platform.connection.close();
+    #[cfg(feature = "net_sim")]
+    {
+        // whatever was still on its way belonged to the old connection
+        let config = NetSimConfig::from_params(url_param);
+        let seed = (js_sys::Math::random() * u64::MAX as f64) as u64;
+        *platform.connection.net_sim.borrow_mut() = NetSim::new(config, seed);
+    }
+
+    let ws = match WebSocket::new(&platform.connection.url) {
+        Ok(ws) => ws,
+        Err(e) => {
+            console_log!("failed to open connection: {:?}", e);
+            drop(platform);
+            local_state.frontend.borrow_mut().disconnected();
+            return;
+        }
+    };
+    ws.set_binary_type(web_sys::BinaryType::Arraybuffer);
+
+    let frontend = local_state.frontend.clone();
+    let on_open = Closure::<dyn FnMut(_)>::new(move |_: JsValue| {
+        frontend.borrow_mut().connected();
+    });
+    ws.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+
+    #[cfg(not(feature = "net_sim"))]
+    let (frontend, platform_handle) = (local_state.frontend.clone(), local_state.platform.clone());
+    #[cfg(feature = "net_sim")]
+    let net_sim = platform.connection.net_sim.clone();
+    let on_message = Closure::<dyn FnMut(_)>::new(move |e: MessageEvent| {
+        if let Ok(array_buf) = e.data().dyn_into::<js_sys::ArrayBuffer>() {
+            let bytes = js_sys::Uint8Array::new(&array_buf).to_vec();
+            #[cfg(feature = "net_sim")]
+            net_sim.borrow_mut().receive(js_sys::Date::now(), bytes);
+            #[cfg(not(feature = "net_sim"))]
+            frontend
+                .borrow_mut()
+                .handle_server_bytes(&bytes, &mut *platform_handle.borrow_mut());
+        }
+    });
+    ws.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+    // also fired after an error, the frontend takes care of reconnecting
+    let frontend = local_state.frontend.clone();
+    let on_close = Closure::<dyn FnMut(_)>::new(move |_: JsValue| {
+        frontend.borrow_mut().disconnected();
+    });
+    ws.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+    let on_error = Closure::<dyn FnMut(_)>::new(move |e: ErrorEvent| {
+        console_log!("connection error: {:?}", e.error());
+    });
+    ws.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+    platform.connection.ws = Some(ws);
+    platform.connection.handlers = Some(SocketHandlers {
+        on_open,
+        on_message,
+        on_close,
+        on_error,
+    });
+}
+
 #[wasm_bindgen]
 #[allow(dead_code)]
 impl Game {
@@ -115,21 +467,38 @@ impl Game {
             "ws"
         };
 
+        let mut platform = WebPlatform {
+            connection: Connection {
+                url: format!("{}://{}/ws", ws_protocol, server_host),
+                ws: None,
+                handlers: None,
+                reconnect: false,
+                #[cfg(feature = "net_sim")]
+                net_sim: {
+                    let config = NetSimConfig::from_params(url_param);
+                    console_log!("simulating network conditions: {:?}", config);
+                    let seed = (js_sys::Math::random() * u64::MAX as f64) as u64;
+                    Rc::new(RefCell::new(NetSim::new(config, seed)))
+                },
+            },
+            stats: NetStats::default(),
+            audio: None,
+            link_handler: None,
+            fetched: Rc::new(RefCell::new(Vec::new())),
+        };
         let local_state = Box::new(LocalState {
-            ws: WebSocket::new(&format!("{}://{}/ws", ws_protocol, server_host)).unwrap(),
-            game_state: Rc::new(RefCell::new(None)),
-            ui_frame: UIFrame::new(ui::Direction::TopToBottom),
-            input: Rc::new(RefCell::new(Input::default())),
-            name_input: String::new(),
-            game_logo: Image::from_ppm(&SliceReader::new(include_bytes!("../../assets/logo.ppm",)))
-                .expect("Failed to load logo"),
+            frontend: Rc::new(RefCell::new(Frontend::new(&mut platform))),
+            platform: Rc::new(RefCell::new(platform)),
+            show_stats: false,
+            target_fps: 0,
+            pending_ms: 0.0,
         });
 
         // register input handlers
-        let input = local_state.input.clone();
+        let frontend = local_state.frontend.clone();
         let on_keydown = Closure::<dyn FnMut(_)>::new(move |e: web_sys::KeyboardEvent| {
-            if let Some((key, prevent_default)) = js_key_to_key(&e.key()) {
-                input.borrow_mut().keyboard.push_back(KeyEvent {
+            if let Some((key, prevent_default)) = js_key_to_key(&e.key(), e.ctrl_key()) {
+                frontend.borrow_mut().input.keyboard.push_back(KeyEvent {
                     key,
                     state: KeyState::Down,
                 });
@@ -144,10 +513,10 @@ impl Game {
             .unwrap();
         on_keydown.forget();
 
-        let input = local_state.input.clone();
+        let frontend = local_state.frontend.clone();
         let on_keyup = Closure::<dyn FnMut(_)>::new(move |e: web_sys::KeyboardEvent| {
-            if let Some((key, prevent_default)) = js_key_to_key(&e.key()) {
-                input.borrow_mut().keyboard.push_back(KeyEvent {
+            if let Some((key, prevent_default)) = js_key_to_key(&e.key(), e.ctrl_key()) {
+                frontend.borrow_mut().input.keyboard.push_back(KeyEvent {
                     key,
                     state: KeyState::Up,
                 });
@@ -162,44 +531,8 @@ impl Game {
             .unwrap();
         on_keyup.forget();
 
-        // register websocket handlers
-        local_state
-            .ws
-            .set_binary_type(web_sys::BinaryType::Arraybuffer);
-        let game_state = local_state.game_state.clone();
-        let on_message = Closure::<dyn FnMut(_)>::new(move |e: MessageEvent| {
-            if let Ok(array_buf) = e.data().dyn_into::<js_sys::ArrayBuffer>() {
-                let array = js_sys::Uint8Array::new(&array_buf);
-                let server_message = ServerMessage::from_bytes(&array.to_vec());
-                match server_message {
-                    Ok(ServerMessage::FullState(new_state)) => {
-                        cibo_online::setup_network_objects();
-                        let new_state = new_state.serialize();
-                        game_state.replace(Some(new_state));
-                    }
-                    Ok(message) => {
-                        if let Some(ref mut game_state) = *game_state.borrow_mut() {
-                            game_state.handle_message(message);
-                        }
-                    }
-                    Err(e) => console_log!("Error deserializing server message: {:#?}", e),
-                }
-            }
-        });
-        local_state
-            .ws
-            .set_onmessage(Some(on_message.as_ref().unchecked_ref()));
-        on_message.forget();
-
-        let on_error = Closure::<dyn FnMut(_)>::new(move |e: ErrorEvent| {
-            alert("connection to server failed. please reload the page to try again.\n if this error keeps persisting, you might have already joined under this ip or you might be banned.");
-            console_log!("connection error: {:?}", e.error());
-            web_sys::window().unwrap().location().reload().unwrap();
-        });
-        local_state
-            .ws
-            .set_onerror(Some(on_error.as_ref().unchecked_ref()));
-        on_error.forget();
+        // `Frontend::new` asked for the first connection
+        open_connection(&local_state);
 
         Self {
             framebuffer,
@@ -225,118 +558,137 @@ impl Game {
     }
 
     pub fn mouse_pos(&mut self, x: i32, y: i32) {
-        let mouse = &mut self.local_state.input.borrow_mut().mouse;
+        let mouse = &mut self.local_state.frontend.borrow_mut().input.mouse;
         mouse.position = Position::new(x as i64, y as i64);
     }
 
     pub fn mouse_scroll(&mut self, scroll: i32) {
-        let mouse = &mut self.local_state.input.borrow_mut().mouse;
+        let mouse = &mut self.local_state.frontend.borrow_mut().input.mouse;
         mouse.scroll += scroll as i64;
     }
 
     pub fn mouse_left(&mut self, down: bool) {
-        let mouse = &mut self.local_state.input.borrow_mut().mouse;
+        let mouse = &mut self.local_state.frontend.borrow_mut().input.mouse;
         mouse.left_button.update(down);
     }
 
     pub fn mouse_right(&mut self, down: bool) {
-        let mouse = &mut self.local_state.input.borrow_mut().mouse;
+        let mouse = &mut self.local_state.frontend.borrow_mut().input.mouse;
         mouse.right_button.update(down);
     }
 
-    pub fn update(&mut self, delta_ms: f32) {
-        self.framebuffer.clear();
-        self.framebuffer.clear_alpha();
+    pub fn on_link(&mut self, handler: js_sys::Function) {
+        self.local_state.platform.borrow_mut().link_handler = Some(handler);
+    }
 
-        let delta_ms = delta_ms.round() as u64;
-        if let Some(ref mut game_state) = *self.local_state.game_state.borrow_mut() {
-            // we are connected to the server and have received a game state.
-            // let the game state handle the rest
-            game_state.update(
-                delta_ms,
-                &mut self.framebuffer,
-                &mut self.local_state.input.borrow_mut(),
-                &mut |client_msg| {
-                    self.local_state
-                        .ws
-                        .send_with_u8_array(&client_msg.to_bytes().unwrap())
-                        .unwrap();
-                },
-            );
-            // console_log!("{:#?}", game_state.client());
+    /// limit how often the game is drawn, 0 removes the limit
+    pub fn set_target_fps(&mut self, fps: u32) {
+        self.local_state.target_fps = fps;
+    }
+
+    /// tell the game whether the page can see the canvas. while it can't, the game only runs
+    /// `LOW_POWER_FPS` times a second and isn't drawn
+    pub fn set_visible(&mut self, visible: bool) {
+        console_log!("game {}", if visible { "visible" } else { "hidden" });
+        self.local_state
+            .frontend
+            .borrow_mut()
+            .set_low_power(!visible);
+    }
+
+    /// run the game for the time since the last call. returns whether a new frame was drawn, which
+    /// isn't the case if the frame limit says to wait or the game is hidden
+    pub fn update(&mut self, delta_ms: f32) -> bool {
+        let low_power = self.local_state.frontend.borrow().low_power();
+        let fps = if low_power {
+            LOW_POWER_FPS
         } else {
-            // no game state was received yet, draw a menu to let the player enter their name
-            let fb_rect = Rect::from_dimensions(self.framebuffer.dimensions());
+            self.local_state.target_fps
+        };
+        self.local_state.pending_ms += delta_ms;
+        if fps != 0 && self.local_state.pending_ms + FRAME_SLACK_MS < 1000.0 / fps as f32 {
+            return false;
+        }
+        let delta_ms = std::mem::take(&mut self.local_state.pending_ms);
 
-            self.framebuffer
-                .draw_rect(fb_rect, Color::new(100, 100, 100));
+        let allocations = alloc_counter::allocations();
+        if !low_power {
+            self.framebuffer.clear();
+            self.framebuffer.clear_alpha();
+        }
 
-            let logo_pos = Position::new(
-                (self.framebuffer.dimensions().width as i64
-                    - self.local_state.game_logo.dimensions().width as i64)
-                    / 2,
-                20,
-            );
-            self.framebuffer
-                .draw_img(&self.local_state.game_logo, logo_pos);
+        let mut frontend = self.local_state.frontend.borrow_mut();
+        let mut platform = self.local_state.platform.borrow_mut();
+        platform.stats.record_frame(delta_ms);
+        #[cfg(feature = "net_sim")]
+        {
+            drop((frontend, platform));
+            poll_net_sim(&self.local_state);
+            frontend = self.local_state.frontend.borrow_mut();
+            platform = self.local_state.platform.borrow_mut();
+        }
+        if platform.connection.reconnect {
+            drop((frontend, platform));
+            open_connection(&self.local_state);
+            frontend = self.local_state.frontend.borrow_mut();
+            platform = self.local_state.platform.borrow_mut();
+        }
+        let fetched = std::mem::take(&mut *platform.fetched.borrow_mut());
+        for (path, body) in fetched {
+            frontend.fetched(&path, &body, &mut *platform);
+        }
+        if action_pressed(&mut frontend.input, Action::Stats) {
+            self.local_state.show_stats = !self.local_state.show_stats;
+        }
 
-            let mut rect = fb_rect.clone();
-            rect.min.y = self.local_state.game_logo.dimensions().height as i64 + 50;
+        frontend.update(
+            delta_ms.round() as u64,
+            &mut self.framebuffer,
+            &mut *platform,
+        );
 
-            self.local_state.ui_frame.draw_frame(
+        // drawing the overlay allocates as well, so count before it
+        platform.stats.record_allocations(
+            alloc_counter::allocations() - allocations,
+            alloc_counter::heap_bytes(),
+        );
+        if self.local_state.show_stats && !low_power {
+            let palette = frontend.settings().color_mode.palette();
+            let latency = frontend.latency().cloned();
+            platform.stats.draw(
                 &mut self.framebuffer,
-                rect,
-                &mut self.local_state.input.borrow_mut(),
-                |ui| {
-                    ui.margin(ui::MarginMode::Grow);
-
-                    ui.label::<font::Cozette>("please enter a nickname!");
-                    ui.label::<font::Glean>("(or leave empty to join anonymously)");
-
-                    let name_input =
-                        Textbox::<font::Cozette>::new(&mut self.local_state.name_input)
-                            .char_limit(cibo_online::NAME_LIMIT);
-                    if ui.add(name_input).submitted {
-                        let client_msg = ClientMessage::Connect {
-                            name: self.local_state.name_input.clone(),
-                        };
-                        self.local_state
-                            .ws
-                            .send_with_u8_array(&client_msg.to_bytes().unwrap())
-                            .unwrap();
-                        self.local_state.name_input.clear();
-                    }
-                },
+                &mut frontend.input,
+                palette,
+                latency.as_ref(),
             );
+        }
+        !low_power
+    }
 
-            let mut credits_frame = UIFrame::new_stateless(ui::Direction::BottomToTop);
-            credits_frame.draw_frame(
-                &mut self.framebuffer,
-                fb_rect,
-                &mut self.local_state.input.borrow_mut(),
-                |ui| {
-                    ui.margin(ui::MarginMode::Grow);
-
-                    ui.label::<font::Glean>("made with ♡ by sakanaa");
-                },
-            );
+    /// textual summary of the game for screen readers, empty until the game state was received
+    pub fn accessibility_text(&self) -> String {
+        self.local_state.frontend.borrow().accessibility_text()
+    }
 
-            let mut controls_frame = UIFrame::new_stateless(ui::Direction::BottomToTop);
-            controls_frame.draw_frame(
-                &mut self.framebuffer,
-                fb_rect,
-                &mut self.local_state.input.borrow_mut(),
-                |ui| {
-                    ui.label::<font::Glean>("- f1 to enter 'stream mode'");
-                    ui.label::<font::Glean>("- tab to view player list");
-                    ui.label::<font::Glean>("- enter or t to chat");
-                    ui.label::<font::Glean>("- arrow keys or w/a/s/d to move");
-                    ui.label::<font::Glean>("controls:");
-                },
-            );
+    /// per-stage latency of our own actions, one stage per line, for collecting from the console
+    /// or a test harness. empty until the game state was received
+    pub fn latency_report(&self) -> String {
+        let frontend = self.local_state.frontend.borrow();
+        let Some(latency) = frontend.latency() else {
+            return String::new();
+        };
+        LatencyStage::ALL
+            .iter()
+            .map(|stage| latency_line(latency, *stage))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
-            self.local_state.input.borrow_mut().clear();
-        }
+    /// the session so far when the game was started with `?record=1`, to attach to a bug report.
+    /// see `client_core::recording`
+    pub fn export_recording(&self) -> Option<Vec<u8>> {
+        let frontend = self.local_state.frontend.borrow();
+        frontend.recording()?.to_bytes().ok()
     }
 
     pub fn width(&self) -> u32 {
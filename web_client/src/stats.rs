@@ -0,0 +1,209 @@
+use std::collections::{HashMap, VecDeque};
+
+use monos_gfx::{
+    text::font,
+    ui::{self, UIFrame},
+    Framebuffer, Input, Position, Rect,
+};
+
+use cibo_online::{
+    client::{LatencyStage, LatencyStats},
+    Palette,
+};
+
+const RATE_WINDOW_MS: f64 = 1000.0;
+const FRAME_HISTORY: usize = 120;
+const GRAPH_HEIGHT: i64 = 40;
+const GRAPH_MAX_MS: f32 = 50.0;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct TypeStats {
+    count: u64,
+    bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Sent,
+    Received,
+}
+
+/// network and frame time statistics collected by the websocket handlers and the render loop.
+#[derive(Debug, Default)]
+pub struct NetStats {
+    received: HashMap<&'static str, TypeStats>,
+    sent: HashMap<&'static str, TypeStats>,
+    recent: VecDeque<(f64, Direction, usize)>,
+    last_full_state: Option<usize>,
+    /// the last message was part of a full state, the next part adds to its size
+    receiving_full_state: bool,
+    frame_times: VecDeque<f32>,
+    /// allocations of the last frames, for an average that doesn't jump around
+    frame_allocations: VecDeque<usize>,
+    heap_bytes: usize,
+}
+
+impl NetStats {
+    pub fn record_received(&mut self, kind: &'static str, bytes: usize) {
+        match kind {
+            "FullState" => self.last_full_state = Some(bytes),
+            // the parts of one state are always sent right after each other
+            "FullStatePart" if self.receiving_full_state => {
+                *self.last_full_state.get_or_insert(0) += bytes
+            }
+            "FullStatePart" => self.last_full_state = Some(bytes),
+            _ => {}
+        }
+        self.receiving_full_state = kind == "FullStatePart";
+        Self::record(&mut self.received, kind, bytes);
+        self.record_recent(Direction::Received, bytes);
+    }
+
+    pub fn record_sent(&mut self, kind: &'static str, bytes: usize) {
+        Self::record(&mut self.sent, kind, bytes);
+        self.record_recent(Direction::Sent, bytes);
+    }
+
+    /// messages are recorded even while the overlay is closed, so old ones are dropped right away
+    fn record_recent(&mut self, direction: Direction, bytes: usize) {
+        let now = js_sys::Date::now();
+        self.recent.push_back((now, direction, bytes));
+        self.prune_recent(now);
+    }
+
+    fn prune_recent(&mut self, now: f64) {
+        while let Some((time, _, _)) = self.recent.front() {
+            if now - time > RATE_WINDOW_MS {
+                self.recent.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn record_frame(&mut self, delta_ms: f32) {
+        self.frame_times.push_back(delta_ms);
+        if self.frame_times.len() > FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+    }
+
+    pub fn record_allocations(&mut self, allocations: usize, heap_bytes: usize) {
+        self.frame_allocations.push_back(allocations);
+        if self.frame_allocations.len() > FRAME_HISTORY {
+            self.frame_allocations.pop_front();
+        }
+        self.heap_bytes = heap_bytes;
+    }
+
+    fn record(map: &mut HashMap<&'static str, TypeStats>, kind: &'static str, bytes: usize) {
+        let stats = map.entry(kind).or_default();
+        stats.count += 1;
+        stats.bytes += bytes as u64;
+    }
+
+    /// messages and bytes per second in the given direction, measured over the last second.
+    fn rate(&self, direction: Direction) -> (usize, f64) {
+        self.recent
+            .iter()
+            .filter(|(_, d, _)| *d == direction)
+            .fold((0, 0.0), |(msgs, bytes), (_, _, b)| {
+                (msgs + 1, bytes + *b as f64)
+            })
+    }
+
+    pub fn draw(
+        &mut self,
+        fb: &mut Framebuffer,
+        input: &mut Input,
+        palette: &Palette,
+        latency: Option<&LatencyStats>,
+    ) {
+        self.prune_recent(js_sys::Date::now());
+
+        let (recv_msgs, recv_bytes) = self.rate(Direction::Received);
+        let (sent_msgs, sent_bytes) = self.rate(Direction::Sent);
+
+        let mut by_type = self.received.iter().collect::<Vec<_>>();
+        by_type.sort_unstable_by(|a, b| b.1.bytes.cmp(&a.1.bytes));
+
+        let rect = Rect::new(
+            Position::new(5, 5),
+            Position::new(160, fb.dimensions().height as i64),
+        );
+        let mut frame = UIFrame::new_stateless(ui::Direction::TopToBottom);
+        frame.draw_frame(fb, rect, input, |ui| {
+            ui.label::<font::Glean>(&format!(
+                "recv {} msg/s {:.1} KB/s",
+                recv_msgs,
+                recv_bytes / 1024.0
+            ));
+            ui.label::<font::Glean>(&format!(
+                "sent {} msg/s {:.1} KB/s",
+                sent_msgs,
+                sent_bytes / 1024.0
+            ));
+            let allocations = self.frame_allocations.iter().sum::<usize>() as f64
+                / self.frame_allocations.len().max(1) as f64;
+            ui.label::<font::Glean>(&format!(
+                "{:.1} allocs/frame heap {:.1} KB",
+                allocations,
+                self.heap_bytes as f64 / 1024.0
+            ));
+            match self.last_full_state {
+                Some(bytes) => ui.label::<font::Glean>(&format!("last FullState {} B", bytes)),
+                None => ui.label::<font::Glean>("last FullState -"),
+            };
+            for (kind, stats) in by_type.iter().take(5) {
+                ui.label::<font::Glean>(&format!(
+                    "{} {}x {:.1} KB",
+                    kind,
+                    stats.count,
+                    stats.bytes as f64 / 1024.0
+                ));
+            }
+            if let Some(latency) = latency {
+                for stage in LatencyStage::ALL {
+                    ui.label::<font::Glean>(&latency_line(latency, stage));
+                }
+            }
+        });
+
+        // frame time graph, one bar per frame
+        let graph_bottom = fb.dimensions().height as i64 - 5;
+        for (i, delta) in self.frame_times.iter().enumerate() {
+            let height = ((delta / GRAPH_MAX_MS).min(1.0) * GRAPH_HEIGHT as f32) as i64;
+            let color = if *delta > 1000.0 / 30.0 {
+                palette.bad
+            } else if *delta > 1000.0 / 55.0 {
+                palette.warning
+            } else {
+                palette.good
+            };
+            fb.draw_rect(
+                Rect::new(
+                    Position::new(5 + i as i64, graph_bottom - height.max(1)),
+                    Position::new(6 + i as i64, graph_bottom),
+                ),
+                color,
+            );
+        }
+    }
+}
+
+/// percentiles of one latency stage, e.g. `uplink p50 25ms p95 50ms (12)`
+pub fn latency_line(latency: &LatencyStats, stage: LatencyStage) -> String {
+    let histogram = latency.stage(stage);
+    let percentile = |percentile| match histogram.percentile(percentile) {
+        Some(ms) => format!("{}ms", ms),
+        None if histogram.count() == 0 => "-".to_string(),
+        None => ">500ms".to_string(),
+    };
+    format!(
+        "{} p50 {} p95 {} ({})",
+        stage.name(),
+        percentile(50),
+        percentile(95),
+        histogram.count()
+    )
+}